@@ -301,6 +301,12 @@ impl Server {
             .add_request_handler(forward_read_only_project_request::<proto::GetReferences>)
             .add_request_handler(forward_find_search_candidates_request)
             .add_request_handler(forward_read_only_project_request::<proto::GetDocumentHighlights>)
+            .add_request_handler(forward_read_only_project_request::<proto::GetDocumentLinks>)
+            .add_request_handler(forward_read_only_project_request::<proto::GetDocumentColors>)
+            .add_request_handler(forward_read_only_project_request::<proto::GetColorPresentations>)
+            .add_request_handler(forward_read_only_project_request::<proto::ResolveDocumentLink>)
+            .add_request_handler(forward_read_only_project_request::<proto::GetInlineValues>)
+            .add_request_handler(forward_read_only_project_request::<proto::GetRenameEdits>)
             .add_request_handler(forward_read_only_project_request::<proto::GetProjectSymbols>)
             .add_request_handler(forward_read_only_project_request::<proto::OpenBufferForSymbol>)
             .add_request_handler(forward_read_only_project_request::<proto::OpenBufferById>)
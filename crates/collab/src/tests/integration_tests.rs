@@ -23,7 +23,8 @@ use gpui::{
 };
 use language::{
     language_settings::{
-        AllLanguageSettings, Formatter, FormatterList, PrettierSettings, SelectedFormatter,
+        AllLanguageSettings, Formatter, FormatterFailureBehavior, FormatterList, PrettierSettings,
+        SelectedFormatter,
     },
     tree_sitter_rust, tree_sitter_typescript, Diagnostic, DiagnosticEntry, FakeLspAdapter,
     Language, LanguageConfig, LanguageMatcher, LineEnding, OffsetRangeExt, Point, Rope,
@@ -4489,6 +4490,8 @@ async fn test_formatting_buffer(
                     vec![Formatter::External {
                         command: "awk".into(),
                         arguments: Some(vec!["{sub(/two/,\"{buffer_path}\")}1".to_string()].into()),
+                        range_arguments: None,
+                        on_failure: FormatterFailureBehavior::Abort,
                     }]
                     .into(),
                 )));
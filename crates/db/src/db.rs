@@ -12,14 +12,17 @@ pub use sqlez;
 pub use sqlez_macros;
 
 pub use release_channel::RELEASE_CHANNEL;
+use sqlez::connection::{Connection, SqliteError};
 use sqlez::domain::Migrator;
+use sqlez::migrations::IrreversibleMigration;
 use sqlez::thread_safe_connection::ThreadSafeConnection;
 use sqlez_macros::sql;
 use std::future::Future;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::{atomic::Ordering, LazyLock};
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::{env, sync::atomic::AtomicBool};
-use util::{maybe, ResultExt};
+use util::ResultExt;
 
 const CONNECTION_INITIALIZE_QUERY: &str = sql!(
     PRAGMA foreign_keys=TRUE;
@@ -36,14 +39,29 @@ const FALLBACK_DB_NAME: &str = "FALLBACK_MEMORY_DB";
 
 const DB_FILE_NAME: &str = "db.sqlite";
 
+const BACKUP_DB_FILE_NAME: &str = "db.sqlite.bak";
+
+/// The folder in the database directory that broken databases are moved to.
+pub const QUARANTINE_DIR_NAME: &str = "quarantine";
+
 pub static ZED_STATELESS: LazyLock<bool> =
     LazyLock::new(|| env::var("ZED_STATELESS").map_or(false, |v| !v.is_empty()));
 
 pub static ALL_FILE_DB_FAILED: LazyLock<AtomicBool> = LazyLock::new(|| AtomicBool::new(false));
 
+/// Set when a damaged database was moved aside and replaced.
+pub static DB_QUARANTINED: LazyLock<AtomicBool> = LazyLock::new(|| AtomicBool::new(false));
+
+/// Set when a damaged database was replaced by its backup from before its last migration.
+pub static DB_RESTORED_FROM_BACKUP: LazyLock<AtomicBool> = LazyLock::new(|| AtomicBool::new(false));
+
+/// Serializes the recovery of broken databases, which may be opened by several connections at once.
+static DB_RECOVERY: LazyLock<smol::lock::Mutex<()>> = LazyLock::new(Default::default);
+
 /// Open or create a database at the given directory path.
-/// This will retry a couple times if there are failures. If opening fails once, the db directory
-/// is moved to a backup folder and a new one is created. If that fails, a shared in memory db is created.
+/// If opening fails because the database is damaged, the db directory is moved to a quarantine
+/// folder, and the database is restored from its backup or created anew. If that fails, or the
+/// database couldn't be opened for another reason, a shared in memory db is created.
 /// In either case, static variables are set so that the user can be notified.
 pub async fn open_db<M: Migrator + 'static>(db_dir: &Path, scope: &str) -> ThreadSafeConnection<M> {
     if *ZED_STATELESS {
@@ -52,15 +70,13 @@ pub async fn open_db<M: Migrator + 'static>(db_dir: &Path, scope: &str) -> Threa
 
     let main_db_dir = db_dir.join(format!("0-{}", scope));
 
-    let connection = maybe!(async {
-        smol::fs::create_dir_all(&main_db_dir)
-            .await
-            .context("Could not create db directory")
-            .log_err()?;
-        let db_path = main_db_dir.join(Path::new(DB_FILE_NAME));
-        open_main_db(&db_path).await
-    })
-    .await;
+    let connection = match open_db_in_dir(&main_db_dir).await {
+        Ok(connection) => Some(connection),
+        Err(error) => {
+            log::error!("Failed to open database: {error:?}");
+            recover_db(db_dir, &main_db_dir).await
+        }
+    };
 
     if let Some(connection) = connection {
         return connection;
@@ -73,14 +89,142 @@ pub async fn open_db<M: Migrator + 'static>(db_dir: &Path, scope: &str) -> Threa
     open_fallback_db().await
 }
 
-async fn open_main_db<M: Migrator>(db_path: &Path) -> Option<ThreadSafeConnection<M>> {
+async fn open_db_in_dir<M: Migrator>(
+    main_db_dir: &Path,
+) -> anyhow::Result<ThreadSafeConnection<M>> {
+    smol::fs::create_dir_all(main_db_dir)
+        .await
+        .context("Could not create db directory")?;
+    open_main_db(
+        &main_db_dir.join(DB_FILE_NAME),
+        &main_db_dir.join(BACKUP_DB_FILE_NAME),
+    )
+    .await
+}
+
+async fn open_main_db<M: Migrator>(
+    db_path: &Path,
+    backup_path: &Path,
+) -> anyhow::Result<ThreadSafeConnection<M>> {
     log::info!("Opening main db");
     ThreadSafeConnection::<M>::builder(db_path.to_string_lossy().as_ref(), true)
         .with_db_initialization_query(DB_INITIALIZE_QUERY)
         .with_connection_initialize_query(CONNECTION_INITIALIZE_QUERY)
+        .with_backup_before_migration(backup_path)
+        .with_schema_verification()
         .build()
         .await
-        .log_err()
+}
+
+/// Moves a database that failed to open into the quarantine folder if it's damaged, and replaces
+/// it with its backup from before its last migration, or with a new database if that fails too.
+async fn recover_db<M: Migrator>(
+    db_dir: &Path,
+    main_db_dir: &Path,
+) -> Option<ThreadSafeConnection<M>> {
+    let _recovery = DB_RECOVERY.lock().await;
+
+    // Another connection to the same database may have recovered it in the meantime.
+    let error = match open_db_in_dir(main_db_dir).await {
+        Ok(connection) => return Some(connection),
+        Err(error) => error,
+    };
+
+    let db_path = main_db_dir.join(DB_FILE_NAME);
+    if !is_damaged(&db_path, &error) {
+        return None;
+    }
+    // Connections that have the database open would keep using the quarantined file.
+    if ThreadSafeConnection::is_open(&db_path.to_string_lossy()) {
+        log::error!("Not moving the damaged database at {db_path:?}, as it's in use");
+        return None;
+    }
+
+    let quarantine_dir = quarantine_db_dir(db_dir, main_db_dir).await.log_err()?;
+    log::error!("Moved the damaged database to {:?}", quarantine_dir);
+    DB_QUARANTINED.store(true, Ordering::Release);
+
+    if let Some(connection) = restore_backup(&quarantine_dir, main_db_dir).await {
+        DB_RESTORED_FROM_BACKUP.store(true, Ordering::Release);
+        return Some(connection);
+    }
+    open_db_in_dir(main_db_dir).await.log_err()
+}
+
+/// Whether a database that failed to open is damaged, as opposed to locked by another process
+/// or migrated by a newer build, which moving it aside wouldn't fix.
+fn is_damaged(db_path: &Path, error: &anyhow::Error) -> bool {
+    if SqliteError::is_busy_error(error) {
+        return false;
+    }
+    if error
+        .chain()
+        .any(|error| error.is::<IrreversibleMigration>())
+    {
+        return true;
+    }
+
+    match Connection::open_file(&db_path.to_string_lossy()).integrity_check() {
+        Ok(problems) if problems.is_empty() => false,
+        Ok(problems) => {
+            log::error!("Database integrity check failed:\n{}", problems.join("\n"));
+            true
+        }
+        Err(error) => !SqliteError::is_busy_error(&error),
+    }
+}
+
+/// Moves a damaged database directory into the quarantine folder, so that a new database can be
+/// created in its place while keeping the damaged one around for inspection.
+async fn quarantine_db_dir(db_dir: &Path, main_db_dir: &Path) -> anyhow::Result<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let dir_name = main_db_dir
+        .file_name()
+        .context("Database directory has no name")?
+        .to_string_lossy();
+    let quarantine_dir = db_dir
+        .join(QUARANTINE_DIR_NAME)
+        .join(format!("{dir_name}-{timestamp}"));
+
+    smol::fs::create_dir_all(db_dir.join(QUARANTINE_DIR_NAME))
+        .await
+        .context("Could not create quarantine directory")?;
+    smol::fs::rename(main_db_dir, &quarantine_dir)
+        .await
+        .with_context(|| format!("Could not move {main_db_dir:?} to {quarantine_dir:?}"))?;
+    Ok(quarantine_dir)
+}
+
+/// Opens the backup kept alongside a quarantined database in place of it, if there is one.
+async fn restore_backup<M: Migrator>(
+    quarantine_dir: &Path,
+    main_db_dir: &Path,
+) -> Option<ThreadSafeConnection<M>> {
+    let backup_path = quarantine_dir.join(BACKUP_DB_FILE_NAME);
+    smol::fs::metadata(&backup_path).await.ok()?;
+
+    smol::fs::create_dir_all(main_db_dir)
+        .await
+        .context("Could not create db directory")
+        .log_err()?;
+    smol::fs::copy(&backup_path, main_db_dir.join(DB_FILE_NAME))
+        .await
+        .context("Could not restore database backup")
+        .log_err()?;
+
+    match open_db_in_dir(main_db_dir).await {
+        Ok(connection) => {
+            log::info!("Restored the database from {:?}", backup_path);
+            Some(connection)
+        }
+        Err(error) => {
+            log::error!("Failed to open the database backup: {error:?}");
+            smol::fs::remove_dir_all(main_db_dir).await.log_err();
+            None
+        }
+    }
 }
 
 async fn open_fallback_db<M: Migrator>() -> ThreadSafeConnection<M> {
@@ -109,10 +253,11 @@ pub async fn open_test_db<M: Migrator>(db_name: &str) -> ThreadSafeConnection<M>
         .unwrap()
 }
 
-/// Implements a basic DB wrapper for a given domain
+/// Implements a basic DB wrapper for a given domain. The migrations may be followed by the
+/// down migrations undoing each of them, which let diverged databases be rolled back.
 #[macro_export]
 macro_rules! define_connection {
-    (pub static ref $id:ident: $t:ident<()> = $migrations:expr; $($global:ident)?) => {
+    (pub static ref $id:ident: $t:ident<()> = $migrations:expr $(, $down_migrations:expr)?; $($global:ident)?) => {
         pub struct $t($crate::sqlez::thread_safe_connection::ThreadSafeConnection<$t>);
 
         impl ::std::ops::Deref for $t {
@@ -131,6 +276,12 @@ macro_rules! define_connection {
             fn migrations() -> &'static [&'static str] {
                 $migrations
             }
+
+            $(
+                fn down_migrations() -> &'static [&'static str] {
+                    $down_migrations
+                }
+            )?
         }
 
         #[cfg(any(test, feature = "test-support"))]
@@ -149,7 +300,7 @@ macro_rules! define_connection {
             $t($crate::smol::block_on($crate::open_db(db_dir, scope)))
         });
     };
-    (pub static ref $id:ident: $t:ident<$($d:ty),+> = $migrations:expr; $($global:ident)?) => {
+    (pub static ref $id:ident: $t:ident<$($d:ty),+> = $migrations:expr $(, $down_migrations:expr)?; $($global:ident)?) => {
         pub struct $t($crate::sqlez::thread_safe_connection::ThreadSafeConnection<( $($d),+, $t )>);
 
         impl ::std::ops::Deref for $t {
@@ -168,6 +319,12 @@ macro_rules! define_connection {
             fn migrations() -> &'static [&'static str] {
                 $migrations
             }
+
+            $(
+                fn down_migrations() -> &'static [&'static str] {
+                    $down_migrations
+                }
+            )?
         }
 
         #[cfg(any(test, feature = "test-support"))]
@@ -200,10 +357,10 @@ where
 mod tests {
     use std::thread;
 
-    use sqlez::domain::Domain;
+    use sqlez::{connection::Connection, domain::Domain};
     use sqlez_macros::sql;
 
-    use crate::open_db;
+    use crate::{open_db, BACKUP_DB_FILE_NAME, DB_FILE_NAME, QUARANTINE_DIR_NAME};
 
     // Test bad migration panics
     #[gpui::test]
@@ -288,6 +445,13 @@ mod tests {
                 .unwrap()
                 .is_none()
         );
+
+        // The diverged database was quarantined, and a new one created in its place
+        assert!(good_db.persistent());
+        let quarantined = std::fs::read_dir(tempdir.path().join(QUARANTINE_DIR_NAME))
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(quarantined.len(), 1);
     }
 
     /// Test that DB exists but corrupted (causing recreate)
@@ -356,4 +520,271 @@ mod tests {
             assert!(guard.join().is_ok());
         }
     }
+
+    /// Test that a DB migrated by a newer build, which altered a table, is kept
+    #[gpui::test]
+    async fn test_newer_db_schema(cx: &mut gpui::TestAppContext) {
+        cx.executor().allow_parking();
+
+        enum OldDB {}
+
+        impl Domain for OldDB {
+            fn name() -> &'static str {
+                "newer_db_tests"
+            }
+
+            fn migrations() -> &'static [&'static str] {
+                &[sql!(CREATE TABLE test(value);)]
+            }
+        }
+
+        enum NewDB {}
+
+        impl Domain for NewDB {
+            fn name() -> &'static str {
+                "newer_db_tests"
+            }
+
+            fn migrations() -> &'static [&'static str] {
+                &[
+                    sql!(CREATE TABLE test(value);),
+                    sql!(ALTER TABLE test ADD COLUMN other;),
+                ]
+            }
+        }
+
+        let tempdir = tempfile::Builder::new()
+            .prefix("DbTests")
+            .tempdir()
+            .unwrap();
+        {
+            let new_db = open_db::<NewDB>(
+                tempdir.path(),
+                &release_channel::ReleaseChannel::Dev.dev_name(),
+            )
+            .await;
+            new_db
+                .write(|connection| {
+                    connection
+                        .exec("INSERT INTO test(value, other) VALUES (1, 2)")
+                        .unwrap()()
+                    .unwrap()
+                })
+                .await;
+        }
+
+        let old_db = open_db::<OldDB>(
+            tempdir.path(),
+            &release_channel::ReleaseChannel::Dev.dev_name(),
+        )
+        .await;
+        assert!(old_db.persistent());
+        assert_eq!(
+            old_db
+                .select_row::<(usize, usize)>("SELECT value, other FROM test")
+                .unwrap()()
+            .unwrap(),
+            Some((1, 2))
+        );
+        assert!(!tempdir.path().join(QUARANTINE_DIR_NAME).exists());
+    }
+
+    /// Test that a DB locked by another process is left in place
+    #[gpui::test]
+    async fn test_busy_db(cx: &mut gpui::TestAppContext) {
+        cx.executor().allow_parking();
+
+        enum BusyDB {}
+
+        impl Domain for BusyDB {
+            fn name() -> &'static str {
+                "busy_db_tests"
+            }
+
+            fn migrations() -> &'static [&'static str] {
+                &[sql!(CREATE TABLE test(value);)]
+            }
+        }
+
+        let tempdir = tempfile::Builder::new()
+            .prefix("DbTests")
+            .tempdir()
+            .unwrap();
+        let db_dir = tempdir.path().join(format!(
+            "0-{}",
+            release_channel::ReleaseChannel::Dev.dev_name()
+        ));
+        std::fs::create_dir_all(&db_dir).unwrap();
+        let lock = Connection::open_file(&db_dir.join(DB_FILE_NAME).to_string_lossy());
+        lock.exec("BEGIN EXCLUSIVE").unwrap()().unwrap();
+
+        let busy_db = open_db::<BusyDB>(
+            tempdir.path(),
+            &release_channel::ReleaseChannel::Dev.dev_name(),
+        )
+        .await;
+        assert!(!busy_db.persistent());
+        assert!(db_dir.join(DB_FILE_NAME).exists());
+        assert!(!tempdir.path().join(QUARANTINE_DIR_NAME).exists());
+
+        // Once the lock is released, the database opens
+        lock.exec("COMMIT").unwrap()().unwrap();
+        drop(lock);
+        let db = open_db::<BusyDB>(
+            tempdir.path(),
+            &release_channel::ReleaseChannel::Dev.dev_name(),
+        )
+        .await;
+        assert!(db.persistent());
+    }
+
+    /// Test that a damaged DB is restored from the backup taken before its last migration
+    #[gpui::test]
+    async fn test_db_restored_from_backup(cx: &mut gpui::TestAppContext) {
+        cx.executor().allow_parking();
+
+        enum FirstDB {}
+
+        impl Domain for FirstDB {
+            fn name() -> &'static str {
+                "backup_db_tests"
+            }
+
+            fn migrations() -> &'static [&'static str] {
+                &[sql!(CREATE TABLE test(value);)]
+            }
+        }
+
+        enum SecondDB {}
+
+        impl Domain for SecondDB {
+            fn name() -> &'static str {
+                "backup_db_tests"
+            }
+
+            fn migrations() -> &'static [&'static str] {
+                &[
+                    sql!(CREATE TABLE test(value);),
+                    sql!(CREATE TABLE test2(value);),
+                ]
+            }
+        }
+
+        let tempdir = tempfile::Builder::new()
+            .prefix("DbTests")
+            .tempdir()
+            .unwrap();
+        let db_dir = tempdir.path().join(format!(
+            "0-{}",
+            release_channel::ReleaseChannel::Dev.dev_name()
+        ));
+        {
+            let first_db = open_db::<FirstDB>(
+                tempdir.path(),
+                &release_channel::ReleaseChannel::Dev.dev_name(),
+            )
+            .await;
+            first_db
+                .write(|connection| {
+                    connection
+                        .exec("INSERT INTO test(value) VALUES (1)")
+                        .unwrap()()
+                    .unwrap()
+                })
+                .await;
+        }
+        {
+            // Migrating the database backs it up first
+            let second_db = open_db::<SecondDB>(
+                tempdir.path(),
+                &release_channel::ReleaseChannel::Dev.dev_name(),
+            )
+            .await;
+            assert!(second_db.persistent());
+        }
+        assert!(db_dir.join(BACKUP_DB_FILE_NAME).exists());
+
+        for file_name in ["db.sqlite-wal", "db.sqlite-shm"] {
+            std::fs::remove_file(db_dir.join(file_name)).ok();
+        }
+        std::fs::write(db_dir.join(DB_FILE_NAME), vec![0xab; 4096]).unwrap();
+
+        let restored_db = open_db::<SecondDB>(
+            tempdir.path(),
+            &release_channel::ReleaseChannel::Dev.dev_name(),
+        )
+        .await;
+        assert!(restored_db.persistent());
+        assert_eq!(
+            restored_db
+                .select_row::<usize>("SELECT value FROM test")
+                .unwrap()()
+            .unwrap(),
+            Some(1)
+        );
+        assert!(restored_db
+            .select_row::<usize>("SELECT value FROM test2")
+            .unwrap()()
+        .unwrap()
+        .is_none());
+        assert_eq!(
+            std::fs::read_dir(tempdir.path().join(QUARANTINE_DIR_NAME))
+                .unwrap()
+                .count(),
+            1
+        );
+    }
+
+    /// Test that a diverged DB isn't moved while other connections are using it
+    #[gpui::test]
+    async fn test_diverged_db_in_use(cx: &mut gpui::TestAppContext) {
+        cx.executor().allow_parking();
+
+        enum UsedDB {}
+
+        impl Domain for UsedDB {
+            fn name() -> &'static str {
+                "in_use_db_tests"
+            }
+
+            fn migrations() -> &'static [&'static str] {
+                &[sql!(CREATE TABLE test(value);)]
+            }
+        }
+
+        enum DivergedDB {}
+
+        impl Domain for DivergedDB {
+            fn name() -> &'static str {
+                "in_use_db_tests"
+            }
+
+            fn migrations() -> &'static [&'static str] {
+                &[sql!(CREATE TABLE test2(value);)]
+            }
+        }
+
+        let tempdir = tempfile::Builder::new()
+            .prefix("DbTests")
+            .tempdir()
+            .unwrap();
+        let used_db = open_db::<UsedDB>(
+            tempdir.path(),
+            &release_channel::ReleaseChannel::Dev.dev_name(),
+        )
+        .await;
+        let diverged_db = open_db::<DivergedDB>(
+            tempdir.path(),
+            &release_channel::ReleaseChannel::Dev.dev_name(),
+        )
+        .await;
+
+        assert!(!diverged_db.persistent());
+        assert!(!tempdir.path().join(QUARANTINE_DIR_NAME).exists());
+        assert!(used_db
+            .select_row::<usize>("SELECT value FROM test")
+            .unwrap()()
+        .unwrap()
+        .is_none());
+    }
 }
@@ -8,6 +8,9 @@ define_connection!(pub static ref KEY_VALUE_STORE: KeyValueStore<()> =
             key TEXT PRIMARY KEY,
             value TEXT NOT NULL
         ) STRICT;
+    )],
+    &[sql!(
+        DROP TABLE kv_store;
     )];
 );
 
@@ -67,6 +70,9 @@ define_connection!(pub static ref GLOBAL_KEY_VALUE_STORE: GlobalKeyValueStore<()
             key TEXT PRIMARY KEY,
             value TEXT NOT NULL
         ) STRICT;
+    )],
+    &[sql!(
+        DROP TABLE kv_store;
     )];
     global
 );
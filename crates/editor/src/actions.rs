@@ -254,6 +254,7 @@ gpui::actions!(
         Cancel,
         CancelLanguageServerWork,
         ConfirmRename,
+        ConfirmRenameWithPreview,
         ContextMenuFirst,
         ContextMenuLast,
         ContextMenuNext,
@@ -295,6 +296,7 @@ gpui::actions!(
         ToggleFoldRecursive,
         Format,
         FormatSelections,
+        FormatSelectionsWithExternalFormatter,
         GoToDeclaration,
         GoToDeclarationSplit,
         GoToDefinition,
@@ -390,6 +392,7 @@ gpui::actions!(
         SelectToStartOfParagraph,
         SelectUp,
         ShowCharacterPalette,
+        ShowColorPicker,
         ShowEditPrediction,
         ShowSignatureHelp,
         ShuffleLines,
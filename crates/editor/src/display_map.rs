@@ -1966,6 +1966,7 @@ pub mod tests {
                     id: InlayId::InlineCompletion(0),
                     position: buffer_snapshot.anchor_after(0),
                     text: "\n".into(),
+                    color: None,
                 }],
                 cx,
             );
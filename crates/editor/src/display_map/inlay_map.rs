@@ -1,5 +1,6 @@
 use crate::{HighlightStyles, InlayId};
 use collections::BTreeSet;
+use gpui::{HighlightStyle, Hsla};
 use language::{Chunk, Edit, Point, TextSummary};
 use multi_buffer::{
     Anchor, MultiBufferRow, MultiBufferRows, MultiBufferSnapshot, RowInfo, ToOffset,
@@ -39,6 +40,8 @@ pub struct Inlay {
     pub(crate) id: InlayId,
     pub position: Anchor,
     pub text: text::Rope,
+    /// The color used to render the inlay's text, overriding the highlight style for its kind.
+    pub color: Option<Hsla>,
 }
 
 impl Inlay {
@@ -54,6 +57,7 @@ impl Inlay {
             id: InlayId::Hint(id),
             position,
             text: text.into(),
+            color: None,
         }
     }
 
@@ -62,6 +66,29 @@ impl Inlay {
             id: InlayId::InlineCompletion(id),
             position,
             text: text.into(),
+            color: None,
+        }
+    }
+
+    pub fn inline_value(id: usize, position: Anchor, text: &str) -> Self {
+        let mut text = text.to_string();
+        if !text.starts_with(' ') {
+            text.insert(0, ' ');
+        }
+        Self {
+            id: InlayId::InlineValue(id),
+            position,
+            text: text.into(),
+            color: None,
+        }
+    }
+
+    pub fn color(id: usize, position: Anchor, color: Hsla) -> Self {
+        Self {
+            id: InlayId::Color(id),
+            position,
+            text: "■ ".into(),
+            color: Some(color),
         }
     }
 }
@@ -286,7 +313,11 @@ impl<'a> Iterator for InlayChunks<'a> {
                             }
                         })
                     }
-                    InlayId::Hint(_) => self.highlight_styles.inlay_hint,
+                    InlayId::Hint(_) | InlayId::InlineValue(_) => self.highlight_styles.inlay_hint,
+                    InlayId::Color(_) => inlay.color.map(|color| HighlightStyle {
+                        color: Some(color),
+                        ..Default::default()
+                    }),
                 };
                 let next_inlay_highlight_endpoint;
                 let offset_in_inlay = self.output_offset - self.transforms.start().0;
@@ -640,6 +671,7 @@ impl InlayMap {
                     id: inlay_id,
                     position: snapshot.buffer.anchor_at(position, bias),
                     text: text.into(),
+                    color: None,
                 });
             } else {
                 to_remove.push(
@@ -1176,6 +1208,7 @@ mod tests {
                 id: InlayId::Hint(post_inc(&mut next_inlay_id)),
                 position: buffer.read(cx).snapshot(cx).anchor_after(3),
                 text: "|123|".into(),
+                color: None,
             }],
         );
         assert_eq!(inlay_snapshot.text(), "abc|123|defghi");
@@ -1253,11 +1286,13 @@ mod tests {
                     id: InlayId::Hint(post_inc(&mut next_inlay_id)),
                     position: buffer.read(cx).snapshot(cx).anchor_before(3),
                     text: "|123|".into(),
+                    color: None,
                 },
                 Inlay {
                     id: InlayId::InlineCompletion(post_inc(&mut next_inlay_id)),
                     position: buffer.read(cx).snapshot(cx).anchor_after(3),
                     text: "|456|".into(),
+                    color: None,
                 },
             ],
         );
@@ -1468,16 +1503,19 @@ mod tests {
                     id: InlayId::Hint(post_inc(&mut next_inlay_id)),
                     position: buffer.read(cx).snapshot(cx).anchor_before(0),
                     text: "|123|\n".into(),
+                    color: None,
                 },
                 Inlay {
                     id: InlayId::Hint(post_inc(&mut next_inlay_id)),
                     position: buffer.read(cx).snapshot(cx).anchor_before(4),
                     text: "|456|".into(),
+                    color: None,
                 },
                 Inlay {
                     id: InlayId::InlineCompletion(post_inc(&mut next_inlay_id)),
                     position: buffer.read(cx).snapshot(cx).anchor_before(7),
                     text: "\n|567|\n".into(),
+                    color: None,
                 },
             ],
         );
@@ -0,0 +1,578 @@
+//! Colors reported by language servers via `textDocument/documentColor`.
+//!
+//! Each reported color gets a swatch inlay in front of it. Clicking the swatch (or invoking
+//! [`ShowColorPicker`] with the cursor on a color) opens a picker that asks the language server
+//! how to write the adjusted color via `textDocument/colorPresentation` and applies its edits.
+
+use std::{mem, ops::Range, time::Duration};
+
+use collections::{HashMap, HashSet};
+use gpui::{point, Context, Hsla, Rgba, Task, Window};
+use language::Bias;
+use multi_buffer::{Anchor, ExcerptId, ToOffset as _};
+use project::{ColorPresentation, DocumentColor};
+use settings::Settings as _;
+use text::BufferId;
+use ui::{prelude::*, ContextMenu};
+use util::{post_inc, ResultExt as _};
+
+use crate::{
+    display_map::Inlay,
+    mouse_context_menu::{MenuPosition, MouseContextMenu},
+    Editor, EditorMode, EditorSettings, EditorSnapshot, InlayId, PointForPosition, ShowColorPicker,
+};
+
+const REFRESH_DEBOUNCE: Duration = Duration::from_millis(200);
+const HUE_STEP: f32 = 15. / 360.;
+const LIGHTNESS_STEP: f32 = 0.05;
+const SATURATION_STEP: f32 = 0.1;
+const ALPHA_STEP: f32 = 0.1;
+
+#[derive(Debug, Clone)]
+pub(crate) struct ColorSwatch {
+    inlay_id: InlayId,
+    excerpt_id: ExcerptId,
+    buffer_id: BufferId,
+    range: Range<Anchor>,
+    document_color: DocumentColor,
+}
+
+impl ColorSwatch {
+    pub(crate) fn color(&self) -> Hsla {
+        lsp_color_to_hsla(self.document_color.color)
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct DocumentColors {
+    swatches: Vec<ColorSwatch>,
+    /// Buffers whose colors are requested once the pending refresh fires.
+    buffers_to_refresh: HashSet<BufferId>,
+    refresh_task: Option<Task<()>>,
+}
+
+impl Editor {
+    /// Re-requests the colors of `buffer_id` after a short debounce, or of every buffer in the
+    /// multibuffer when `buffer_id` is `None`.
+    pub(crate) fn refresh_document_colors(
+        &mut self,
+        buffer_id: Option<BufferId>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.mode != EditorMode::Full {
+            return;
+        }
+        if !EditorSettings::get_global(cx).lsp_document_colors {
+            self.clear_document_colors(cx);
+            return;
+        }
+        let Some(provider) = self.semantics_provider.clone() else {
+            return;
+        };
+
+        match buffer_id {
+            Some(buffer_id) => {
+                self.document_colors.buffers_to_refresh.insert(buffer_id);
+            }
+            None => self.document_colors.buffers_to_refresh.extend(
+                self.buffer
+                    .read(cx)
+                    .all_buffers()
+                    .into_iter()
+                    .map(|buffer| buffer.read(cx).remote_id()),
+            ),
+        }
+        self.document_colors.refresh_task =
+            Some(cx.spawn_in(window, |editor, mut cx| async move {
+                cx.background_executor().timer(REFRESH_DEBOUNCE).await;
+
+                let Ok(color_tasks) = editor.update(&mut cx, |editor, cx| {
+                    let buffer_ids = mem::take(&mut editor.document_colors.buffers_to_refresh);
+                    let multi_buffer = editor.buffer.read(cx);
+                    buffer_ids
+                        .into_iter()
+                        .filter_map(|buffer_id| multi_buffer.buffer(buffer_id))
+                        .filter_map(|buffer| {
+                            let task = provider.document_colors(&buffer, cx)?;
+                            Some(async move { (buffer, task.await) })
+                        })
+                        .collect::<Vec<_>>()
+                }) else {
+                    return;
+                };
+                let colors = futures::future::join_all(color_tasks).await;
+
+                editor
+                    .update(&mut cx, |editor, cx| {
+                        let mut colors_by_buffer = HashMap::default();
+                        for (buffer, colors) in colors {
+                            if let Some(colors) = colors.log_err() {
+                                colors_by_buffer.insert(buffer.read(cx).remote_id(), colors);
+                            }
+                        }
+                        editor.set_document_colors(colors_by_buffer, cx);
+                    })
+                    .ok();
+            }));
+    }
+
+    fn clear_document_colors(&mut self, cx: &mut Context<Self>) {
+        self.document_colors.refresh_task = None;
+        self.document_colors.buffers_to_refresh.clear();
+        let swatches = mem::take(&mut self.document_colors.swatches);
+        if !swatches.is_empty() {
+            let to_remove = swatches
+                .into_iter()
+                .map(|swatch| swatch.inlay_id)
+                .collect::<Vec<_>>();
+            self.splice_inlays(&to_remove, Vec::new(), cx);
+        }
+    }
+
+    /// Drops the swatches shown in excerpts that were removed from the multibuffer.
+    pub(crate) fn remove_document_colors_in_excerpts(
+        &mut self,
+        excerpt_ids: &[ExcerptId],
+        cx: &mut Context<Self>,
+    ) {
+        let mut to_remove = Vec::new();
+        self.document_colors.swatches.retain(|swatch| {
+            let removed = excerpt_ids.contains(&swatch.excerpt_id);
+            if removed {
+                to_remove.push(swatch.inlay_id);
+            }
+            !removed
+        });
+        if !to_remove.is_empty() {
+            self.splice_inlays(&to_remove, Vec::new(), cx);
+        }
+    }
+
+    fn set_document_colors(
+        &mut self,
+        colors_by_buffer: HashMap<BufferId, Vec<DocumentColor>>,
+        cx: &mut Context<Self>,
+    ) {
+        let multi_buffer = self.buffer.read(cx);
+        let snapshot = multi_buffer.snapshot(cx);
+        let mut swatches = Vec::new();
+        let mut to_insert = Vec::new();
+        for (&buffer_id, colors) in &colors_by_buffer {
+            let Some(buffer) = multi_buffer.buffer(buffer_id) else {
+                continue;
+            };
+            let buffer_snapshot = buffer.read(cx).snapshot();
+            for (excerpt_id, excerpt_range) in multi_buffer.excerpts_for_buffer(buffer_id, cx) {
+                for document_color in colors {
+                    let range = &document_color.range;
+                    if range
+                        .start
+                        .cmp(&excerpt_range.context.start, &buffer_snapshot)
+                        .is_lt()
+                        || range
+                            .end
+                            .cmp(&excerpt_range.context.end, &buffer_snapshot)
+                            .is_gt()
+                    {
+                        continue;
+                    }
+                    let (Some(start), Some(end)) = (
+                        snapshot.anchor_in_excerpt(excerpt_id, range.start),
+                        snapshot.anchor_in_excerpt(excerpt_id, range.end),
+                    ) else {
+                        continue;
+                    };
+
+                    let inlay = Inlay::color(
+                        post_inc(&mut self.next_inlay_id),
+                        start,
+                        lsp_color_to_hsla(document_color.color),
+                    );
+                    swatches.push(ColorSwatch {
+                        inlay_id: inlay.id,
+                        excerpt_id,
+                        buffer_id,
+                        range: start..end,
+                        document_color: document_color.clone(),
+                    });
+                    to_insert.push(inlay);
+                }
+            }
+        }
+
+        let mut to_remove = Vec::new();
+        self.document_colors.swatches.retain(|swatch| {
+            let replaced = colors_by_buffer.contains_key(&swatch.buffer_id)
+                || multi_buffer.buffer(swatch.buffer_id).is_none();
+            if replaced {
+                to_remove.push(swatch.inlay_id);
+            }
+            !replaced
+        });
+        self.document_colors.swatches.extend(swatches);
+        if !to_remove.is_empty() || !to_insert.is_empty() {
+            self.splice_inlays(&to_remove, to_insert, cx);
+        }
+    }
+
+    /// Returns the swatch rendered under the given mouse position, if any.
+    pub(crate) fn color_swatch_at(
+        &self,
+        point_for_position: &PointForPosition,
+        snapshot: &EditorSnapshot,
+    ) -> Option<ColorSwatch> {
+        if point_for_position.as_valid().is_some()
+            || point_for_position.column_overshoot_after_line_end > 0
+        {
+            return None;
+        }
+        let buffer_snapshot = &snapshot.buffer_snapshot;
+        let start = snapshot
+            .display_point_to_point(point_for_position.previous_valid, Bias::Left)
+            .to_offset(buffer_snapshot);
+        let end = snapshot
+            .display_point_to_point(point_for_position.next_valid, Bias::Right)
+            .to_offset(buffer_snapshot);
+        self.document_colors
+            .swatches
+            .iter()
+            .find(|swatch| (start..=end).contains(&swatch.range.start.to_offset(buffer_snapshot)))
+            .cloned()
+    }
+
+    pub fn show_color_picker(
+        &mut self,
+        _: &ShowColorPicker,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let cursor = self.selections.newest_anchor().head().to_offset(&snapshot);
+        let swatch = self.document_colors.swatches.iter().find(|swatch| {
+            swatch.range.start.to_offset(&snapshot) <= cursor
+                && cursor <= swatch.range.end.to_offset(&snapshot)
+        });
+        if let Some(swatch) = swatch.cloned() {
+            let color = swatch.color();
+            self.deploy_color_picker(swatch, color, window, cx);
+        }
+    }
+
+    pub(crate) fn deploy_color_picker(
+        &mut self,
+        swatch: ColorSwatch,
+        color: Hsla,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(provider) = self.semantics_provider.clone() else {
+            return;
+        };
+        let Some(buffer) = self.buffer.read(cx).buffer(swatch.buffer_id) else {
+            return;
+        };
+        let Some(presentations) = provider.color_presentations(
+            &buffer,
+            &swatch.document_color,
+            hsla_to_lsp_color(color),
+            cx,
+        ) else {
+            return;
+        };
+
+        cx.spawn_in(window, |editor, mut cx| async move {
+            let presentations = presentations.await.log_err().unwrap_or_default();
+            editor.update_in(&mut cx, |editor, window, cx| {
+                let menu = build_color_picker_menu(
+                    cx.entity().downgrade(),
+                    swatch.clone(),
+                    color,
+                    presentations,
+                    window,
+                    cx,
+                );
+                let line_height = editor.character_size(window).height;
+                editor.mouse_context_menu = Some(MouseContextMenu::new(
+                    MenuPosition::PinnedToEditor {
+                        source: swatch.range.start,
+                        offset: point(px(0.), line_height),
+                    },
+                    menu,
+                    window,
+                    cx,
+                ));
+                cx.notify();
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn apply_color_presentation(
+        &mut self,
+        swatch: &ColorSwatch,
+        presentation: ColorPresentation,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let mut edits = Vec::new();
+        // Per the spec, the label is inserted in place of the color when no edit is provided.
+        match presentation.text_edit {
+            Some((range, new_text)) => edits.extend(
+                snapshot
+                    .anchor_in_excerpt(swatch.excerpt_id, range.start)
+                    .zip(snapshot.anchor_in_excerpt(swatch.excerpt_id, range.end))
+                    .map(|(start, end)| (start..end, new_text)),
+            ),
+            None => edits.push((swatch.range.clone(), presentation.label)),
+        }
+        for (range, new_text) in presentation.additional_text_edits {
+            if let Some((start, end)) = snapshot
+                .anchor_in_excerpt(swatch.excerpt_id, range.start)
+                .zip(snapshot.anchor_in_excerpt(swatch.excerpt_id, range.end))
+            {
+                edits.push((start..end, new_text));
+            }
+        }
+
+        self.transact(window, cx, |editor, _, cx| {
+            editor
+                .buffer
+                .update(cx, |buffer, cx| buffer.edit(edits, None, cx));
+        });
+    }
+}
+
+fn build_color_picker_menu(
+    editor: gpui::WeakEntity<Editor>,
+    swatch: ColorSwatch,
+    color: Hsla,
+    presentations: Vec<ColorPresentation>,
+    window: &mut Window,
+    cx: &mut App,
+) -> gpui::Entity<ContextMenu> {
+    let adjustments: [(&str, fn(Hsla) -> Hsla); 8] = [
+        ("Rotate Hue Forward", |c| Hsla {
+            h: (c.h + HUE_STEP).rem_euclid(1.),
+            ..c
+        }),
+        ("Rotate Hue Backward", |c| Hsla {
+            h: (c.h - HUE_STEP).rem_euclid(1.),
+            ..c
+        }),
+        ("Lighten", |c| Hsla {
+            l: (c.l + LIGHTNESS_STEP).min(1.),
+            ..c
+        }),
+        ("Darken", |c| Hsla {
+            l: (c.l - LIGHTNESS_STEP).max(0.),
+            ..c
+        }),
+        ("Saturate", |c| Hsla {
+            s: (c.s + SATURATION_STEP).min(1.),
+            ..c
+        }),
+        ("Desaturate", |c| Hsla {
+            s: (c.s - SATURATION_STEP).max(0.),
+            ..c
+        }),
+        ("Increase Opacity", |c| Hsla {
+            a: (c.a + ALPHA_STEP).min(1.),
+            ..c
+        }),
+        ("Decrease Opacity", |c| Hsla {
+            a: (c.a - ALPHA_STEP).max(0.),
+            ..c
+        }),
+    ];
+
+    ContextMenu::build(window, cx, move |mut menu, _, _| {
+        let label = color_label(color);
+        menu = menu.custom_row(move |_, cx| {
+            h_flex()
+                .gap_2()
+                .child(
+                    div()
+                        .size_4()
+                        .rounded_sm()
+                        .border_1()
+                        .border_color(cx.theme().colors().border)
+                        .bg(color),
+                )
+                .child(Label::new(label.clone()))
+                .into_any_element()
+        });
+        menu = menu.separator();
+        for (label, adjust) in adjustments {
+            let editor = editor.clone();
+            let swatch = swatch.clone();
+            menu = menu.entry(label, None, move |window, cx| {
+                editor
+                    .update(cx, |editor, cx| {
+                        editor.deploy_color_picker(swatch.clone(), adjust(color), window, cx)
+                    })
+                    .ok();
+            });
+        }
+
+        if !presentations.is_empty() {
+            menu = menu.separator().header("Apply");
+        }
+        for presentation in presentations {
+            let editor = editor.clone();
+            let swatch = swatch.clone();
+            menu = menu.entry(presentation.label.clone(), None, move |window, cx| {
+                editor
+                    .update(cx, |editor, cx| {
+                        editor.apply_color_presentation(&swatch, presentation.clone(), window, cx)
+                    })
+                    .ok();
+            });
+        }
+        menu
+    })
+}
+
+fn lsp_color_to_hsla(color: lsp::Color) -> Hsla {
+    Rgba {
+        r: color.red,
+        g: color.green,
+        b: color.blue,
+        a: color.alpha,
+    }
+    .into()
+}
+
+fn hsla_to_lsp_color(color: Hsla) -> lsp::Color {
+    let rgba = color.to_rgb();
+    lsp::Color {
+        red: rgba.r,
+        green: rgba.g,
+        blue: rgba.b,
+        alpha: rgba.a,
+    }
+}
+
+fn color_label(color: Hsla) -> SharedString {
+    let rgba = color.to_rgb();
+    let channel = |value: f32| (value.clamp(0., 1.) * 255.).round() as u8;
+    if rgba.a < 1. {
+        format!(
+            "#{:02x}{:02x}{:02x}{:02x}",
+            channel(rgba.r),
+            channel(rgba.g),
+            channel(rgba.b),
+            channel(rgba.a)
+        )
+    } else {
+        format!(
+            "#{:02x}{:02x}{:02x}",
+            channel(rgba.r),
+            channel(rgba.g),
+            channel(rgba.b)
+        )
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        actions::Undo, editor_tests::init_test, test::editor_lsp_test_context::EditorLspTestContext,
+    };
+    use futures::StreamExt as _;
+    use indoc::indoc;
+
+    #[gpui::test]
+    async fn test_document_colors(cx: &mut gpui::TestAppContext) {
+        init_test(cx, |_| {});
+        let mut cx = EditorLspTestContext::new_rust(
+            lsp::ServerCapabilities {
+                color_provider: Some(lsp::ColorProviderCapability::Simple(true)),
+                ..Default::default()
+            },
+            cx,
+        )
+        .await;
+
+        let red = lsp::Color {
+            red: 1.,
+            green: 0.,
+            blue: 0.,
+            alpha: 1.,
+        };
+        let color_range = cx.lsp_range(indoc! {r#"
+            let red = "«#ff0000»";
+        "#});
+        let mut color_requests =
+            cx.handle_request::<lsp::request::DocumentColor, _, _>(move |_, _, _| async move {
+                Ok(vec![lsp::ColorInformation {
+                    range: color_range,
+                    color: red,
+                }])
+            });
+        let mut presentation_requests = cx
+            .handle_request::<lsp::request::ColorPresentationRequest, _, _>(
+                move |_, params, _| async move {
+                    assert_eq!(params.range, color_range);
+                    Ok(vec![lsp::ColorPresentation {
+                        label: "#0000ff".to_string(),
+                        text_edit: Some(lsp::TextEdit::new(color_range, "#0000ff".to_string())),
+                        additional_text_edits: None,
+                    }])
+                },
+            );
+
+        cx.set_state(indoc! {r#"
+            let red = "#ffˇ0000";
+        "#});
+        cx.executor().advance_clock(REFRESH_DEBOUNCE);
+        color_requests.next().await;
+        cx.run_until_parked();
+        cx.update_editor(|editor, _, cx| {
+            let swatches = &editor.document_colors.swatches;
+            assert_eq!(swatches.len(), 1);
+            assert_eq!(swatches[0].document_color.color, red);
+            assert_eq!(
+                editor.display_text(cx),
+                "let red = \"■ #ff0000\";\n",
+                "a swatch should be shown in front of the color"
+            );
+        });
+
+        cx.update_editor(|editor, window, cx| {
+            editor.show_color_picker(&ShowColorPicker, window, cx)
+        });
+        presentation_requests.next().await;
+        cx.run_until_parked();
+        let swatch = cx.update_editor(|editor, _, _| {
+            assert!(
+                editor.mouse_context_menu.is_some(),
+                "the color picker should be shown"
+            );
+            editor.document_colors.swatches[0].clone()
+        });
+
+        cx.update_editor(|editor, window, cx| {
+            let presentation = ColorPresentation {
+                label: "#0000ff".to_string(),
+                text_edit: Some((swatch.document_color.range.clone(), "#0000ff".to_string())),
+                additional_text_edits: Vec::new(),
+            };
+            editor.apply_color_presentation(&swatch, presentation, window, cx);
+        });
+        assert_eq!(cx.buffer_text(), "let red = \"#0000ff\";\n");
+        cx.executor().advance_clock(REFRESH_DEBOUNCE);
+        color_requests.next().await;
+        cx.run_until_parked();
+
+        cx.update_editor(|editor, window, cx| editor.undo(&Undo, window, cx));
+        assert_eq!(
+            cx.buffer_text(),
+            "let red = \"#ff0000\";\n",
+            "applying a presentation should be undone in one step"
+        );
+    }
+}
@@ -18,6 +18,7 @@ mod clangd_ext;
 mod code_context_menus;
 pub mod commit_tooltip;
 pub mod display_map;
+mod document_colors;
 mod editor_settings;
 mod editor_settings_controls;
 mod element;
@@ -34,6 +35,7 @@ mod mouse_context_menu;
 pub mod movement;
 mod persistence;
 mod proposed_changes_editor;
+mod rename_preview;
 mod rust_analyzer_ext;
 pub mod scroll;
 mod selections_collection;
@@ -59,6 +61,7 @@ use collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use convert_case::{Case, Casing};
 use display_map::*;
 pub use display_map::{DisplayPoint, FoldPlaceholder};
+use document_colors::DocumentColors;
 pub use editor_settings::{
     CurrentLineHighlight, EditorSettings, ScrollBeyondLastLine, SearchSettings, ShowScrollbar,
 };
@@ -113,6 +116,7 @@ use persistence::DB;
 pub use proposed_changes_editor::{
     ProposedChangeLocation, ProposedChangesEditor, ProposedChangesEditorToolbar,
 };
+pub use rename_preview::RenamePreview;
 use smallvec::smallvec;
 use std::iter::Peekable;
 use task::{ResolvedTask, TaskTemplate, TaskVariables};
@@ -137,7 +141,8 @@ use multi_buffer::{
 use project::{
     lsp_store::{CompletionDocumentation, FormatTrigger, LspFormatTarget, OpenLspBufferHandle},
     project_settings::{GitGutterSetting, ProjectSettings},
-    CodeAction, Completion, CompletionIntent, DocumentHighlight, InlayHint, Location, LocationLink,
+    BufferRenameEdits, CodeAction, ColorPresentation, Completion, CompletionIntent, DocumentColor,
+    DocumentHighlight, DocumentLink, InlayHint, InlineValue, Location, LocationLink,
     PrepareRenameResponse, Project, ProjectItem, ProjectTransaction, TaskSourceKind,
 };
 use rand::prelude::*;
@@ -221,6 +226,8 @@ const COLUMNAR_SELECTION_MODIFIERS: Modifiers = Modifiers {
 pub enum InlayId {
     InlineCompletion(usize),
     Hint(usize),
+    Color(usize),
+    InlineValue(usize),
 }
 
 impl InlayId {
@@ -228,6 +235,8 @@ impl InlayId {
         match self {
             Self::InlineCompletion(id) => *id,
             Self::Hint(id) => *id,
+            Self::Color(id) => *id,
+            Self::InlineValue(id) => *id,
         }
     }
 }
@@ -711,6 +720,7 @@ pub struct Editor {
     edit_prediction_indent_conflict: bool,
     edit_prediction_requires_modifier_in_indent_conflict: bool,
     inlay_hint_cache: InlayHintCache,
+    document_colors: DocumentColors,
     next_inlay_id: usize,
     _subscriptions: Vec<Subscription>,
     pixel_position_of_newest_cursor: Option<gpui::Point<Pixels>>,
@@ -1258,6 +1268,7 @@ impl Editor {
                         if let project::Event::RefreshInlayHints = event {
                             editor
                                 .refresh_inlay_hints(InlayHintRefreshReason::RefreshRequested, cx);
+                            editor.refresh_document_colors(None, window, cx);
                         } else if let project::Event::SnippetEdit(id, snippet_edits) = event {
                             if let Some(buffer) = editor.buffer.read(cx).buffer(*id) {
                                 let focus_handle = editor.focus_handle(cx);
@@ -1422,6 +1433,7 @@ impl Editor {
             },
             inline_diagnostics_enabled: mode == EditorMode::Full,
             inlay_hint_cache: InlayHintCache::new(inlay_hint_settings),
+            document_colors: DocumentColors::default(),
 
             gutter_hovered: false,
             pixel_position_of_newest_cursor: None,
@@ -1511,6 +1523,8 @@ impl Editor {
                         .insert(buffer.read(cx).remote_id(), handle);
                 }
             }
+
+            this.refresh_document_colors(None, window, cx);
         }
 
         this.report_editor_event("Editor Opened", None, cx);
@@ -3870,6 +3884,31 @@ impl Editor {
         cx.notify();
     }
 
+    /// Renders each value's text after its anchor, e.g. to annotate variables with values
+    /// evaluated by a language server or a REPL. Returns the ids of the inserted inlays, to be
+    /// passed to [`Self::remove_inline_values`].
+    pub fn insert_inline_values(
+        &mut self,
+        values: impl IntoIterator<Item = (Anchor, String)>,
+        cx: &mut Context<Self>,
+    ) -> Vec<InlayId> {
+        let inlays = values
+            .into_iter()
+            .map(|(position, text)| {
+                Inlay::inline_value(post_inc(&mut self.next_inlay_id), position, &text)
+            })
+            .collect::<Vec<_>>();
+        let ids = inlays.iter().map(|inlay| inlay.id).collect();
+        self.splice_inlays(&[], inlays, cx);
+        ids
+    }
+
+    pub fn remove_inline_values(&mut self, ids: &[InlayId], cx: &mut Context<Self>) {
+        if !ids.is_empty() {
+            self.splice_inlays(ids, Vec::new(), cx);
+        }
+    }
+
     fn trigger_on_type_formatting(
         &self,
         input: String,
@@ -12338,6 +12377,75 @@ impl Editor {
         }))
     }
 
+    pub fn confirm_rename_with_preview(
+        &mut self,
+        _: &ConfirmRenameWithPreview,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Option<Task<Result<()>>> {
+        let rename = self.pending_rename.as_ref()?;
+        let preview = maybe!({
+            let workspace = self.workspace()?.downgrade();
+            let (buffer, start) = self
+                .buffer
+                .read(cx)
+                .text_anchor_for_position(rename.range.start, cx)?;
+            let (end_buffer, _) = self
+                .buffer
+                .read(cx)
+                .text_anchor_for_position(rename.range.end, cx)?;
+            if buffer != end_buffer {
+                return None;
+            }
+            let new_name = rename.editor.read(cx).text(cx);
+            let rename_edits = self.semantics_provider.as_ref()?.rename_edits(
+                &buffer,
+                start,
+                new_name.clone(),
+                cx,
+            )?;
+            Some((workspace, new_name, rename_edits))
+        });
+        // Renaming without a preview beats dropping the user's rename.
+        let Some((workspace, new_name, rename_edits)) = preview else {
+            return self.confirm_rename(&ConfirmRename, window, cx);
+        };
+        let old_name = self.take_rename(false, window, cx)?.old_name;
+        let project = self.project.clone();
+
+        Some(cx.spawn_in(window, |_, mut cx| async move {
+            let rename_edits = rename_edits.await?;
+            let title = format!("Rename: {} → {}", old_name, new_name);
+            workspace.update_in(&mut cx, |workspace, window, cx| {
+                if rename_edits.is_empty() {
+                    struct EmptyRename;
+
+                    workspace.show_toast(
+                        Toast::new(
+                            NotificationId::unique::<EmptyRename>(),
+                            format!("Renaming {old_name} to {new_name} doesn't change anything"),
+                        ),
+                        cx,
+                    );
+                    return;
+                }
+
+                let preview = cx.new(|cx| {
+                    RenamePreview::new(
+                        title,
+                        rename_edits,
+                        project,
+                        workspace.weak_handle(),
+                        window,
+                        cx,
+                    )
+                });
+                workspace.add_item_to_active_pane(Box::new(preview), None, true, window, cx);
+            })?;
+            Ok(())
+        }))
+    }
+
     fn take_rename(
         &mut self,
         moving_cursor: bool,
@@ -12432,6 +12540,27 @@ impl Editor {
         ))
     }
 
+    fn format_selections_with_external_formatter(
+        &mut self,
+        _: &FormatSelectionsWithExternalFormatter,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Option<Task<Result<()>>> {
+        let project = self.project.clone()?;
+        let ranges = self
+            .selections
+            .all_adjusted(cx)
+            .into_iter()
+            .map(|selection| selection.range())
+            .collect_vec();
+        let (buffers, target) =
+            self.lsp_format_target(FormatTrigger::Manual, FormatTarget::Ranges(ranges), cx);
+        let format = project.update(cx, |project, cx| {
+            project.format_with_external_formatters(buffers, target, true, cx)
+        });
+        Some(self.apply_format(format, window, cx))
+    }
+
     fn perform_format(
         &mut self,
         project: Entity<Project>,
@@ -12440,8 +12569,21 @@ impl Editor {
         window: &mut Window,
         cx: &mut Context<Self>,
     ) -> Task<Result<()>> {
-        let buffer = self.buffer.clone();
-        let (buffers, target) = match target {
+        let (buffers, target) = self.lsp_format_target(trigger, target, cx);
+        let format = project.update(cx, |project, cx| {
+            project.format(buffers, target, true, trigger, cx)
+        });
+        self.apply_format(format, window, cx)
+    }
+
+    fn lsp_format_target(
+        &self,
+        trigger: FormatTrigger,
+        target: FormatTarget,
+        cx: &App,
+    ) -> (HashSet<Entity<Buffer>>, LspFormatTarget) {
+        let buffer = &self.buffer;
+        match target {
             FormatTarget::Buffers => {
                 let mut buffers = buffer.read(cx).all_buffers();
                 if trigger == FormatTrigger::Save {
@@ -12471,13 +12613,18 @@ impl Editor {
                 }
                 (buffers, LspFormatTarget::Ranges(buffer_id_to_ranges))
             }
-        };
+        }
+    }
 
+    /// Waits for a format to finish, pushing its transaction onto the multi-buffer's undo stack.
+    fn apply_format(
+        &mut self,
+        format: Task<Result<ProjectTransaction>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        let buffer = self.buffer.clone();
         let mut timeout = cx.background_executor().timer(FORMAT_TIMEOUT).fuse();
-        let format = project.update(cx, |project, cx| {
-            project.format(buffers, target, true, trigger, cx)
-        });
-
         cx.spawn_in(window, |_, mut cx| async move {
             let transaction = futures::select_biased! {
                 () = timeout => {
@@ -15195,6 +15342,13 @@ impl Editor {
                         }
                     }
                 }
+                self.refresh_document_colors(
+                    buffer_edited
+                        .as_ref()
+                        .map(|buffer| buffer.read(cx).remote_id()),
+                    window,
+                    cx,
+                );
 
                 let Some(project) = &self.project else { return };
                 let (telemetry, is_via_ssh) = {
@@ -15230,9 +15384,11 @@ impl Editor {
                     excerpts: excerpts.clone(),
                 });
                 self.refresh_inlay_hints(InlayHintRefreshReason::NewLinesShown, cx);
+                self.refresh_document_colors(Some(buffer_id), window, cx);
             }
             multi_buffer::Event::ExcerptsRemoved { ids } => {
                 self.refresh_inlay_hints(InlayHintRefreshReason::ExcerptsRemoved(ids.clone()), cx);
+                self.remove_document_colors_in_excerpts(ids, cx);
                 let buffer = self.buffer.read(cx);
                 self.registered_buffers
                     .retain(|buffer_id, _| buffer.buffer(*buffer_id).is_some());
@@ -15263,6 +15419,7 @@ impl Editor {
             }
             multi_buffer::Event::LanguageChanged(buffer_id) => {
                 linked_editing_ranges::refresh_linked_ranges(self, window, cx);
+                self.refresh_document_colors(Some(*buffer_id), window, cx);
                 cx.emit(EditorEvent::Reparsed(*buffer_id));
                 cx.notify();
             }
@@ -15336,6 +15493,8 @@ impl Editor {
             if self.git_blame_inline_enabled != inline_blame_enabled {
                 self.toggle_git_blame_inline_internal(false, window, cx);
             }
+
+            self.refresh_document_colors(None, window, cx);
         }
 
         cx.notify();
@@ -16521,6 +16680,40 @@ pub trait SemanticsProvider {
         cx: &mut App,
     ) -> Option<Task<Result<Vec<LocationLink>>>>;
 
+    fn inline_values(
+        &self,
+        buffer: &Entity<Buffer>,
+        range: Range<text::Anchor>,
+        cx: &mut App,
+    ) -> Option<Task<Result<Vec<InlineValue>>>>;
+
+    fn document_links(
+        &self,
+        buffer: &Entity<Buffer>,
+        cx: &mut App,
+    ) -> Option<Task<Result<Vec<DocumentLink>>>>;
+
+    fn resolve_document_link(
+        &self,
+        link: DocumentLink,
+        buffer: Entity<Buffer>,
+        cx: &mut App,
+    ) -> Option<Task<Result<DocumentLink>>>;
+
+    fn document_colors(
+        &self,
+        buffer: &Entity<Buffer>,
+        cx: &mut App,
+    ) -> Option<Task<Result<Vec<DocumentColor>>>>;
+
+    fn color_presentations(
+        &self,
+        buffer: &Entity<Buffer>,
+        document_color: &DocumentColor,
+        new_color: lsp::Color,
+        cx: &mut App,
+    ) -> Option<Task<Result<Vec<ColorPresentation>>>>;
+
     fn range_for_rename(
         &self,
         buffer: &Entity<Buffer>,
@@ -16535,6 +16728,14 @@ pub trait SemanticsProvider {
         new_name: String,
         cx: &mut App,
     ) -> Option<Task<Result<ProjectTransaction>>>;
+
+    fn rename_edits(
+        &self,
+        buffer: &Entity<Buffer>,
+        position: text::Anchor,
+        new_name: String,
+        cx: &mut App,
+    ) -> Option<Task<Result<Vec<BufferRenameEdits>>>>;
 }
 
 pub trait CompletionProvider {
@@ -16930,6 +17131,54 @@ impl SemanticsProvider for Entity<Project> {
         }))
     }
 
+    fn inline_values(
+        &self,
+        buffer: &Entity<Buffer>,
+        range: Range<text::Anchor>,
+        cx: &mut App,
+    ) -> Option<Task<Result<Vec<InlineValue>>>> {
+        Some(self.update(cx, |project, cx| project.inline_values(buffer, range, cx)))
+    }
+
+    fn document_links(
+        &self,
+        buffer: &Entity<Buffer>,
+        cx: &mut App,
+    ) -> Option<Task<Result<Vec<DocumentLink>>>> {
+        Some(self.update(cx, |project, cx| project.document_links(buffer, cx)))
+    }
+
+    fn resolve_document_link(
+        &self,
+        link: DocumentLink,
+        buffer: Entity<Buffer>,
+        cx: &mut App,
+    ) -> Option<Task<Result<DocumentLink>>> {
+        Some(self.update(cx, |project, cx| {
+            project.resolve_document_link(link, buffer, cx)
+        }))
+    }
+
+    fn document_colors(
+        &self,
+        buffer: &Entity<Buffer>,
+        cx: &mut App,
+    ) -> Option<Task<Result<Vec<DocumentColor>>>> {
+        Some(self.update(cx, |project, cx| project.document_colors(buffer, cx)))
+    }
+
+    fn color_presentations(
+        &self,
+        buffer: &Entity<Buffer>,
+        document_color: &DocumentColor,
+        new_color: lsp::Color,
+        cx: &mut App,
+    ) -> Option<Task<Result<Vec<ColorPresentation>>>> {
+        Some(self.update(cx, |project, cx| {
+            project.color_presentations(buffer, document_color, new_color, cx)
+        }))
+    }
+
     fn range_for_rename(
         &self,
         buffer: &Entity<Buffer>,
@@ -16973,6 +17222,18 @@ impl SemanticsProvider for Entity<Project> {
             project.perform_rename(buffer.clone(), position, new_name, cx)
         }))
     }
+
+    fn rename_edits(
+        &self,
+        buffer: &Entity<Buffer>,
+        position: text::Anchor,
+        new_name: String,
+        cx: &mut App,
+    ) -> Option<Task<Result<Vec<BufferRenameEdits>>>> {
+        Some(self.update(cx, |project, cx| {
+            project.rename_edits(buffer.clone(), position, new_name, cx)
+        }))
+    }
 }
 
 fn inlay_hint_settings(
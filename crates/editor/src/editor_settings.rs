@@ -12,6 +12,7 @@ pub struct EditorSettings {
     pub selection_highlight: bool,
     pub selection_highlight_debounce: u64,
     pub lsp_highlight_debounce: u64,
+    pub lsp_document_colors: bool,
     pub hover_popover_enabled: bool,
     pub hover_popover_delay: u64,
     pub toolbar: Toolbar,
@@ -287,6 +288,11 @@ pub struct EditorSettingsContent {
     ///
     /// Default: 75
     pub lsp_highlight_debounce: Option<u64>,
+    /// Whether to show color swatches next to colors reported by the
+    /// language server, and allow editing them with a color picker.
+    ///
+    /// Default: true
+    pub lsp_document_colors: Option<bool>,
     /// Whether to show the informational hover box when moving the mouse
     /// over symbols in the editor.
     ///
@@ -16478,6 +16478,49 @@ async fn test_rename_without_prepare(cx: &mut TestAppContext) {
     "});
 }
 
+#[gpui::test]
+async fn test_rename_with_preview_without_edits(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+    let capabilities = lsp::ServerCapabilities {
+        rename_provider: Some(lsp::OneOf::Left(true)),
+        ..Default::default()
+    };
+    let mut cx = EditorLspTestContext::new_rust(capabilities, cx).await;
+
+    cx.set_state(indoc! {"
+        struct Fˇoo {}
+    "});
+    cx.update_editor(|e, window, cx| e.rename(&Rename, window, cx))
+        .expect("Prepare rename was not started")
+        .await
+        .expect("Prepare rename failed");
+
+    let mut rename_handler =
+        cx.handle_request::<lsp::request::Rename, _, _>(move |_, _, _| async move { Ok(None) });
+    let rename_task = cx
+        .update_editor(|e, window, cx| {
+            e.confirm_rename_with_preview(&ConfirmRenameWithPreview, window, cx)
+        })
+        .expect("Confirm rename was not started");
+    rename_handler.next().await.unwrap();
+    rename_task.await.expect("Confirm rename failed");
+    cx.run_until_parked();
+
+    assert_eq!(cx.buffer_text(), "struct Foo {}\n");
+    cx.update_editor(|editor, _, _| assert!(editor.pending_rename.is_none()));
+    cx.update_workspace(|workspace, _, cx| {
+        assert!(
+            workspace.active_item_as::<RenamePreview>(cx).is_none(),
+            "no preview should be opened for a rename without edits"
+        );
+        assert_eq!(
+            workspace.notification_ids().len(),
+            1,
+            "the user should be told the rename had no effect"
+        );
+    });
+}
+
 #[gpui::test]
 async fn test_tree_sitter_brackets_newline_insertion(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
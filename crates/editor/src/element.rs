@@ -429,8 +429,17 @@ impl EditorElement {
                 cx.propagate();
             }
         });
+        register_action(editor, window, |editor, action, window, cx| {
+            if let Some(task) = editor.format_selections_with_external_formatter(action, window, cx)
+            {
+                task.detach_and_notify_err(window, cx);
+            } else {
+                cx.propagate();
+            }
+        });
         register_action(editor, window, Editor::restart_language_server);
         register_action(editor, window, Editor::show_character_palette);
+        register_action(editor, window, Editor::show_color_picker);
         register_action(editor, window, |editor, action, window, cx| {
             if let Some(task) = editor.confirm_completion(action, window, cx) {
                 task.detach_and_notify_err(window, cx);
@@ -466,6 +475,13 @@ impl EditorElement {
                 cx.propagate();
             }
         });
+        register_action(editor, window, |editor, action, window, cx| {
+            if let Some(task) = editor.confirm_rename_with_preview(action, window, cx) {
+                task.detach_and_notify_err(window, cx);
+            } else {
+                cx.propagate();
+            }
+        });
         register_action(editor, window, |editor, action, window, cx| {
             if let Some(task) = editor.find_all_references(action, window, cx) {
                 task.detach_and_log_err(cx);
@@ -608,6 +624,17 @@ impl EditorElement {
         }
 
         let point_for_position = position_map.point_for_position(event.position);
+        if click_count == 1 && !modifiers.modified() && text_hitbox.is_hovered(window) {
+            if let Some(swatch) =
+                editor.color_swatch_at(&point_for_position, &position_map.snapshot)
+            {
+                let color = swatch.color();
+                editor.deploy_color_picker(swatch, color, window, cx);
+                cx.stop_propagation();
+                return;
+            }
+        }
+
         let position = point_for_position.previous_valid;
         if modifiers == COLUMNAR_SELECTION_MODIFIERS {
             editor.select(
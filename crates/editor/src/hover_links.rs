@@ -4,6 +4,7 @@ use crate::{
     scroll::ScrollAmount,
     Anchor, Editor, EditorSettings, EditorSnapshot, FindAllReferences, GoToDefinition,
     GoToTypeDefinition, GotoDefinitionKind, InlayId, Navigated, PointForPosition, SelectPhase,
+    SemanticsProvider,
 };
 use gpui::{px, App, AsyncWindowContext, Context, Entity, Modifiers, Task, Window};
 use language::{Bias, ToOffset};
@@ -14,7 +15,7 @@ use project::{
     ResolveState, ResolvedPath,
 };
 use settings::Settings;
-use std::ops::Range;
+use std::{ops::Range, rc::Rc};
 use theme::ActiveTheme as _;
 use util::{maybe, ResultExt, TryFutureExt as _};
 
@@ -529,7 +530,19 @@ pub fn show_link_definition(
         async move {
             let result = match &trigger_point {
                 TriggerPoint::Text(_) => {
-                    if let Some((url_range, url)) = find_url(&buffer, buffer_position, cx.clone()) {
+                    if let Some((link_range, link)) =
+                        find_document_link(&buffer, provider.as_ref(), buffer_position, &mut cx)
+                            .await
+                    {
+                        let range = maybe!({
+                            let start = snapshot.anchor_in_excerpt(excerpt_id, link_range.start)?;
+                            let end = snapshot.anchor_in_excerpt(excerpt_id, link_range.end)?;
+                            Some(RangeInEditor::Text(start..end))
+                        });
+                        Some((range, vec![link]))
+                    } else if let Some((url_range, url)) =
+                        find_url(&buffer, buffer_position, cx.clone())
+                    {
                         this.update(&mut cx, |_, _| {
                             let range = maybe!({
                                 let start =
@@ -648,6 +661,39 @@ pub fn show_link_definition(
     editor.hovered_link_state = Some(hovered_link_state);
 }
 
+/// Finds the language server reported document link containing the given position.
+pub(crate) async fn find_document_link(
+    buffer: &Entity<language::Buffer>,
+    provider: Option<&Rc<dyn SemanticsProvider>>,
+    position: text::Anchor,
+    cx: &mut AsyncWindowContext,
+) -> Option<(Range<text::Anchor>, HoverLink)> {
+    let task = cx
+        .update(|_, cx| provider?.document_links(buffer, cx))
+        .ok()??;
+    let links = task.await.log_err()?;
+    let snapshot = buffer.update(cx, |buffer, _| buffer.snapshot()).ok()?;
+    let mut link = links.into_iter().find(|link| {
+        link.range.start.cmp(&position, &snapshot).is_le()
+            && link.range.end.cmp(&position, &snapshot).is_ge()
+    })?;
+    if link.target.is_none() {
+        let resolve_task = cx
+            .update(|_, cx| provider?.resolve_document_link(link, buffer.clone(), cx))
+            .ok()??;
+        link = resolve_task.await.log_err()?;
+    }
+    let target = link.target?;
+    let hover_link = match url::Url::parse(&target) {
+        Ok(url) if url.scheme() == "file" => HoverLink::File(ResolvedPath::AbsPath {
+            path: url.to_file_path().ok()?,
+            is_dir: false,
+        }),
+        _ => HoverLink::Url(target),
+    };
+    Some((link.range, hover_link))
+}
+
 pub(crate) fn find_url(
     buffer: &Entity<language::Buffer>,
     position: text::Anchor,
@@ -921,6 +967,7 @@ mod tests {
     use indoc::indoc;
     use language::language_settings::InlayHintSettings;
     use lsp::request::{GotoDefinition, GotoTypeDefinition};
+    use serde_json::json;
     use util::{assert_set_eq, path};
     use workspace::item::Item;
 
@@ -1441,6 +1488,109 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_document_links(cx: &mut gpui::TestAppContext) {
+        init_test(cx, |_| {});
+        let mut cx = EditorLspTestContext::new_rust(
+            lsp::ServerCapabilities {
+                document_link_provider: Some(lsp::DocumentLinkOptions {
+                    resolve_provider: Some(false),
+                    work_done_progress_options: Default::default(),
+                }),
+                ..Default::default()
+            },
+            cx,
+        )
+        .await;
+
+        cx.set_state(indoc! {"
+            // See the ˇdocs.
+        "});
+        let link_range = cx.lsp_range(indoc! {"
+            // See the «docs».
+        "});
+        let mut requests = cx.handle_request::<lsp::request::DocumentLinkRequest, _, _>(
+            move |_, _, _| async move {
+                Ok(Some(vec![lsp::DocumentLink {
+                    range: link_range,
+                    target: Some(lsp::Url::parse("https://zed.dev/docs").unwrap()),
+                    tooltip: None,
+                    data: None,
+                }]))
+            },
+        );
+
+        let screen_coord = cx.pixel_position(indoc! {"
+            // See the doˇcs.
+        "});
+        cx.simulate_mouse_move(screen_coord, None, Modifiers::secondary_key());
+        requests.next().await;
+        cx.run_until_parked();
+        cx.assert_editor_text_highlights::<HoveredLinkState>(indoc! {"
+            // See the «docsˇ».
+        "});
+
+        cx.simulate_click(screen_coord, Modifiers::secondary_key());
+        assert_eq!(cx.opened_url(), Some("https://zed.dev/docs".into()));
+    }
+
+    #[gpui::test]
+    async fn test_document_links_resolved_lazily(cx: &mut gpui::TestAppContext) {
+        init_test(cx, |_| {});
+        let mut cx = EditorLspTestContext::new_rust(
+            lsp::ServerCapabilities {
+                document_link_provider: Some(lsp::DocumentLinkOptions {
+                    resolve_provider: Some(true),
+                    work_done_progress_options: Default::default(),
+                }),
+                ..Default::default()
+            },
+            cx,
+        )
+        .await;
+
+        cx.set_state(indoc! {"
+            // See the ˇdocs.
+        "});
+        let link_range = cx.lsp_range(indoc! {"
+            // See the «docs».
+        "});
+        let mut link_requests = cx.handle_request::<lsp::request::DocumentLinkRequest, _, _>(
+            move |_, _, _| async move {
+                Ok(Some(vec![lsp::DocumentLink {
+                    range: link_range,
+                    target: None,
+                    tooltip: None,
+                    data: Some(json!({ "id": 7 })),
+                }]))
+            },
+        );
+        let mut resolve_requests = cx.handle_request::<lsp::request::DocumentLinkResolve, _, _>(
+            move |_, link, _| async move {
+                assert_eq!(link.range, link_range);
+                assert_eq!(link.data, Some(json!({ "id": 7 })));
+                Ok(lsp::DocumentLink {
+                    target: Some(lsp::Url::parse("https://zed.dev/docs/7").unwrap()),
+                    ..link
+                })
+            },
+        );
+
+        let screen_coord = cx.pixel_position(indoc! {"
+            // See the doˇcs.
+        "});
+        cx.simulate_mouse_move(screen_coord, None, Modifiers::secondary_key());
+        link_requests.next().await;
+        resolve_requests.next().await;
+        cx.run_until_parked();
+        cx.assert_editor_text_highlights::<HoveredLinkState>(indoc! {"
+            // See the «docsˇ».
+        "});
+
+        cx.simulate_click(screen_coord, Modifiers::secondary_key());
+        assert_eq!(cx.opened_url(), Some("https://zed.dev/docs/7".into()));
+    }
+
     #[gpui::test]
     async fn test_urls_at_beginning_of_buffer(cx: &mut gpui::TestAppContext) {
         init_test(cx, |_| {});
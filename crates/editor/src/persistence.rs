@@ -147,6 +147,47 @@ define_connection!(
                 ON DELETE CASCADE
             ) STRICT;
         ),
+    ],
+    &[
+        sql! (
+            DROP TABLE editors;
+        ),
+        sql! (
+            ALTER TABLE editors DROP COLUMN scroll_vertical_offset;
+            ALTER TABLE editors DROP COLUMN scroll_horizontal_offset;
+            ALTER TABLE editors DROP COLUMN scroll_top_row;
+        ),
+        sql! (
+            // Editors without a path can't be kept once it's required again.
+            CREATE TABLE old_editors_tmp (
+                item_id INTEGER NOT NULL,
+                workspace_id INTEGER NOT NULL,
+                path BLOB NOT NULL,
+                scroll_top_row INTEGER NOT NULL DEFAULT 0,
+                scroll_horizontal_offset REAL NOT NULL DEFAULT 0,
+                scroll_vertical_offset REAL NOT NULL DEFAULT 0,
+                PRIMARY KEY(item_id, workspace_id),
+                FOREIGN KEY(workspace_id) REFERENCES workspaces(workspace_id)
+                ON DELETE CASCADE
+                ON UPDATE CASCADE
+            ) STRICT;
+
+            INSERT INTO old_editors_tmp(item_id, workspace_id, path, scroll_top_row, scroll_horizontal_offset, scroll_vertical_offset)
+            SELECT item_id, workspace_id, path, scroll_top_row, scroll_horizontal_offset, scroll_vertical_offset
+            FROM editors
+            WHERE path IS NOT NULL;
+
+            DROP TABLE editors;
+
+            ALTER TABLE old_editors_tmp RENAME TO editors;
+        ),
+        sql! (
+            ALTER TABLE editors DROP COLUMN mtime_nanos;
+            ALTER TABLE editors DROP COLUMN mtime_seconds;
+        ),
+        sql! (
+            DROP TABLE editor_selections;
+        ),
     ];
 );
 
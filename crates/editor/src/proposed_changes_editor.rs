@@ -496,6 +496,51 @@ impl SemanticsProvider for BranchBufferSemanticsProvider {
         self.0.definitions(&buffer, position, kind, cx)
     }
 
+    fn inline_values(
+        &self,
+        buffer: &Entity<Buffer>,
+        range: Range<text::Anchor>,
+        cx: &mut App,
+    ) -> Option<Task<gpui::Result<Vec<project::InlineValue>>>> {
+        let buffer = self.to_base(&buffer, &[range.start, range.end], cx)?;
+        self.0.inline_values(&buffer, range, cx)
+    }
+
+    fn document_links(
+        &self,
+        _: &Entity<Buffer>,
+        _: &mut App,
+    ) -> Option<Task<gpui::Result<Vec<project::DocumentLink>>>> {
+        None
+    }
+
+    fn resolve_document_link(
+        &self,
+        _: project::DocumentLink,
+        _: Entity<Buffer>,
+        _: &mut App,
+    ) -> Option<Task<gpui::Result<project::DocumentLink>>> {
+        None
+    }
+
+    fn document_colors(
+        &self,
+        _: &Entity<Buffer>,
+        _: &mut App,
+    ) -> Option<Task<gpui::Result<Vec<project::DocumentColor>>>> {
+        None
+    }
+
+    fn color_presentations(
+        &self,
+        _: &Entity<Buffer>,
+        _: &project::DocumentColor,
+        _: lsp::Color,
+        _: &mut App,
+    ) -> Option<Task<gpui::Result<Vec<project::ColorPresentation>>>> {
+        None
+    }
+
     fn range_for_rename(
         &self,
         _: &Entity<Buffer>,
@@ -514,4 +559,14 @@ impl SemanticsProvider for BranchBufferSemanticsProvider {
     ) -> Option<Task<gpui::Result<project::ProjectTransaction>>> {
         None
     }
+
+    fn rename_edits(
+        &self,
+        _: &Entity<Buffer>,
+        _: text::Anchor,
+        _: String,
+        _: &mut App,
+    ) -> Option<Task<gpui::Result<Vec<project::BufferRenameEdits>>>> {
+        None
+    }
 }
@@ -0,0 +1,626 @@
+use crate::{scroll::Autoscroll, Editor, EditorEvent, DEFAULT_MULTIBUFFER_CONTEXT};
+use buffer_diff::BufferDiff;
+use gpui::{App, Entity, EventEmitter, FocusHandle, Focusable, Render, WeakEntity};
+use language::{Buffer, Capability, OffsetRangeExt as _, Point, ToPoint as _};
+use multi_buffer::MultiBuffer;
+use project::{BufferRenameEdits, Project, ProjectTransaction, RenameEdit};
+use std::any::TypeId;
+use ui::{prelude::*, Checkbox, Tooltip};
+use workspace::{item::ItemEvent, searchable::SearchableItemHandle, Item, Workspace};
+
+/// Shows the edits of a rename before they get applied, letting the user
+/// exclude individual files and edits from it.
+///
+/// The edits are previewed on branches of the affected buffers, and only get
+/// applied to the real buffers, as a single project transaction, once
+/// confirmed.
+pub struct RenamePreview {
+    editor: Entity<Editor>,
+    title: SharedString,
+    files: Vec<RenamePreviewFile>,
+    workspace: WeakEntity<Workspace>,
+}
+
+struct RenamePreviewFile {
+    base: Entity<Buffer>,
+    branch: Entity<Buffer>,
+    diff: Entity<BufferDiff>,
+    path: SharedString,
+    edits: Vec<RenamePreviewEdit>,
+}
+
+struct RenamePreviewEdit {
+    edit: RenameEdit,
+    label: SharedString,
+    included: bool,
+}
+
+impl RenamePreview {
+    pub fn new(
+        title: impl Into<SharedString>,
+        rename_edits: Vec<BufferRenameEdits>,
+        project: Option<Entity<Project>>,
+        workspace: WeakEntity<Workspace>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let multibuffer = cx.new(|_| MultiBuffer::new(Capability::ReadOnly));
+        let mut files = Vec::new();
+        for buffer_edits in rename_edits {
+            let base = buffer_edits.buffer;
+            let branch = base.update(cx, |buffer, cx| buffer.branch(cx));
+            let diff = cx.new(|_| BufferDiff::new(branch.read(cx)));
+
+            let snapshot = base.read(cx).snapshot();
+            let path = snapshot
+                .file()
+                .map(|file| file.full_path(cx).to_string_lossy().to_string())
+                .unwrap_or_else(|| "untitled".to_string())
+                .into();
+            let edits = buffer_edits
+                .edits
+                .into_iter()
+                .map(|edit| {
+                    let row = edit.range.start.to_point(&snapshot).row;
+                    let line_range = Point::new(row, 0)..Point::new(row, snapshot.line_len(row));
+                    let line = snapshot.text_for_range(line_range).collect::<String>();
+                    let label = format!("{}: {}", row + 1, line.trim()).into();
+                    RenamePreviewEdit {
+                        // Edits the user has to confirm are opted out of by default.
+                        included: !edit
+                            .annotation
+                            .as_ref()
+                            .map_or(false, |annotation| annotation.needs_confirmation),
+                        label,
+                        edit,
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            let ranges = edits
+                .iter()
+                .map(|edit| edit.edit.range.to_point(&snapshot))
+                .collect();
+            multibuffer.update(cx, |multibuffer, cx| {
+                multibuffer.push_excerpts_with_context_lines(
+                    branch.clone(),
+                    ranges,
+                    DEFAULT_MULTIBUFFER_CONTEXT,
+                    cx,
+                );
+            });
+
+            let file = RenamePreviewFile {
+                base,
+                branch,
+                diff,
+                path,
+                edits,
+            };
+            file.update_branch(cx);
+            files.push(file);
+        }
+
+        let editor = cx.new(|cx| {
+            let mut editor =
+                Editor::for_multibuffer(multibuffer.clone(), project, true, window, cx);
+            editor.set_expand_all_diff_hunks(cx);
+            editor.set_completion_provider(None);
+            editor.clear_code_action_providers();
+            editor.set_semantics_provider(None);
+            editor.buffer.update(cx, |buffer, cx| {
+                for file in &files {
+                    buffer.add_diff(file.diff.clone(), cx);
+                }
+            });
+            editor
+        });
+
+        Self {
+            editor,
+            title: title.into(),
+            files,
+            workspace,
+        }
+    }
+
+    fn included_edit_count(&self) -> usize {
+        self.files
+            .iter()
+            .flat_map(|file| &file.edits)
+            .filter(|edit| edit.included)
+            .count()
+    }
+
+    fn set_included(
+        &mut self,
+        file_ix: usize,
+        edit_ix: Option<usize>,
+        included: bool,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(file) = self.files.get_mut(file_ix) else {
+            return;
+        };
+        match edit_ix {
+            Some(edit_ix) => {
+                if let Some(edit) = file.edits.get_mut(edit_ix) {
+                    edit.included = included;
+                }
+            }
+            None => {
+                for edit in &mut file.edits {
+                    edit.included = included;
+                }
+            }
+        }
+        file.update_branch(cx);
+        cx.notify();
+    }
+
+    fn reveal_edit(
+        &mut self,
+        file_ix: usize,
+        edit_ix: usize,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(file) = self.files.get(file_ix) else {
+            return;
+        };
+        let Some(edit) = file.edits.get(edit_ix) else {
+            return;
+        };
+        let point = edit.edit.range.start.to_point(file.branch.read(cx));
+        let Some(anchor) = self
+            .editor
+            .read(cx)
+            .buffer()
+            .read(cx)
+            .buffer_point_to_anchor(&file.branch, point, cx)
+        else {
+            return;
+        };
+        self.editor.update(cx, |editor, cx| {
+            editor.change_selections(Some(Autoscroll::center()), window, cx, |selections| {
+                selections.select_anchor_ranges([anchor..anchor])
+            });
+        });
+        window.focus(&self.editor.focus_handle(cx));
+    }
+
+    /// Applies every included edit to its buffer, grouping them into a single
+    /// project transaction so that the whole rename can be undone at once.
+    fn apply(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let mut project_transaction = ProjectTransaction::default();
+        for file in &self.files {
+            let edits = file
+                .edits
+                .iter()
+                .filter(|edit| edit.included)
+                .map(|edit| (edit.edit.range.clone(), edit.edit.new_text.clone()))
+                .collect::<Vec<_>>();
+            if edits.is_empty() {
+                continue;
+            }
+
+            let transaction = file.base.update(cx, |buffer, cx| {
+                buffer.finalize_last_transaction();
+                buffer.start_transaction();
+                buffer.edit(edits, None, cx);
+                buffer.end_transaction(cx)?;
+                buffer.finalize_last_transaction().cloned()
+            });
+            if let Some(transaction) = transaction {
+                project_transaction.0.insert(file.base.clone(), transaction);
+            }
+        }
+
+        let editor = self.editor.clone();
+        let workspace = self.workspace.clone();
+        let title = self.title.to_string();
+        cx.spawn_in(window, |_, cx| async move {
+            Editor::open_project_transaction(
+                &editor.downgrade(),
+                workspace,
+                project_transaction,
+                title,
+                cx,
+            )
+            .await
+        })
+        .detach_and_log_err(cx);
+        cx.emit(EditorEvent::Closed);
+    }
+
+    fn render_file(&self, file_ix: usize, cx: &mut Context<Self>) -> impl IntoElement {
+        let file = &self.files[file_ix];
+        let any_included = file.edits.iter().any(|edit| edit.included);
+        let all_included = file.edits.iter().all(|edit| edit.included);
+
+        v_flex()
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(
+                        Checkbox::new(
+                            ("rename-preview-file", file_ix),
+                            ToggleState::from_any_and_all(any_included, all_included),
+                        )
+                        .on_click(cx.listener(
+                            move |this, state, _, cx| {
+                                this.set_included(
+                                    file_ix,
+                                    None,
+                                    *state == ToggleState::Selected,
+                                    cx,
+                                )
+                            },
+                        )),
+                    )
+                    .child(Label::new(file.path.clone()).text_ellipsis()),
+            )
+            .children(file.edits.iter().enumerate().map(|(edit_ix, edit)| {
+                let annotation = edit.edit.annotation.clone();
+                h_flex()
+                    .id(SharedString::from(format!(
+                        "rename-preview-edit-{file_ix}-{edit_ix}"
+                    )))
+                    .pl_4()
+                    .gap_1()
+                    .child(
+                        Checkbox::new(
+                            SharedString::from(format!(
+                                "rename-preview-edit-checkbox-{file_ix}-{edit_ix}"
+                            )),
+                            edit.included.into(),
+                        )
+                        .on_click(cx.listener(
+                            move |this, state, _, cx| {
+                                this.set_included(
+                                    file_ix,
+                                    Some(edit_ix),
+                                    *state == ToggleState::Selected,
+                                    cx,
+                                )
+                            },
+                        )),
+                    )
+                    .child(
+                        Label::new(edit.label.clone())
+                            .size(LabelSize::Small)
+                            .color(Color::Muted)
+                            .text_ellipsis(),
+                    )
+                    .when_some(annotation, |this, annotation| {
+                        let tooltip = annotation
+                            .description
+                            .clone()
+                            .unwrap_or_else(|| annotation.label.clone());
+                        this.child(
+                            Label::new(annotation.label)
+                                .size(LabelSize::Small)
+                                .color(if annotation.needs_confirmation {
+                                    Color::Warning
+                                } else {
+                                    Color::Muted
+                                })
+                                .italic(),
+                        )
+                        .tooltip(Tooltip::text(tooltip))
+                    })
+                    .cursor_pointer()
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        this.reveal_edit(file_ix, edit_ix, window, cx)
+                    }))
+            }))
+    }
+}
+
+impl RenamePreviewFile {
+    /// Resets the branch buffer to its base, then applies the included edits to it.
+    fn update_branch(&self, cx: &mut App) {
+        let base_version = self.base.read(cx).version();
+        let edits = self
+            .edits
+            .iter()
+            .filter(|edit| edit.included)
+            .map(|edit| (edit.edit.range.clone(), edit.edit.new_text.clone()))
+            .collect::<Vec<_>>();
+        self.branch.update(cx, |buffer, cx| {
+            let undo_counts = buffer
+                .operations()
+                .iter()
+                .filter_map(|(timestamp, _)| {
+                    if !base_version.observed(*timestamp) {
+                        Some((*timestamp, u32::MAX))
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+            buffer.undo_operations(undo_counts, cx);
+            buffer.edit(edits, None, cx);
+        });
+
+        let snapshot = self.branch.read(cx).text_snapshot();
+        let base = self.base.clone();
+        let _ = self
+            .diff
+            .update(cx, |diff, cx| diff.set_base_text(base, snapshot, cx));
+    }
+}
+
+impl Render for RenamePreview {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let included_edit_count = self.included_edit_count();
+        v_flex()
+            .size_full()
+            .key_context("RenamePreview")
+            .child(
+                h_flex()
+                    .p_2()
+                    .gap_2()
+                    .justify_between()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border)
+                    .child(Label::new(self.title.clone()))
+                    .child(
+                        h_flex()
+                            .gap_1()
+                            .child(
+                                Button::new("cancel-rename", "Cancel").on_click(
+                                    cx.listener(|_, _, _, cx| cx.emit(EditorEvent::Closed)),
+                                ),
+                            )
+                            .child(
+                                Button::new(
+                                    "apply-rename",
+                                    format!("Apply {} Edits", included_edit_count),
+                                )
+                                .style(ButtonStyle::Filled)
+                                .disabled(included_edit_count == 0)
+                                .on_click(
+                                    cx.listener(|this, _, window, cx| this.apply(window, cx)),
+                                ),
+                            ),
+                    ),
+            )
+            .child(
+                h_flex()
+                    .flex_1()
+                    .min_h_0()
+                    .items_start()
+                    .child(
+                        v_flex()
+                            .id("rename-preview-files")
+                            .w_1_3()
+                            .h_full()
+                            .p_2()
+                            .gap_2()
+                            .overflow_y_scroll()
+                            .border_r_1()
+                            .border_color(cx.theme().colors().border)
+                            .children(
+                                (0..self.files.len()).map(|file_ix| self.render_file(file_ix, cx)),
+                            ),
+                    )
+                    .child(div().flex_1().h_full().child(self.editor.clone())),
+            )
+    }
+}
+
+impl Focusable for RenamePreview {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.editor.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<EditorEvent> for RenamePreview {}
+
+impl Item for RenamePreview {
+    type Event = EditorEvent;
+
+    fn tab_icon(&self, _window: &Window, _cx: &App) -> Option<Icon> {
+        Some(Icon::new(IconName::Diff))
+    }
+
+    fn tab_content_text(&self, _window: &Window, _cx: &App) -> Option<SharedString> {
+        Some(self.title.clone())
+    }
+
+    fn to_item_events(event: &EditorEvent, mut f: impl FnMut(ItemEvent)) {
+        if let EditorEvent::Closed = event {
+            f(ItemEvent::CloseItem);
+        }
+    }
+
+    fn as_searchable(&self, _: &Entity<Self>) -> Option<Box<dyn SearchableItemHandle>> {
+        Some(Box::new(self.editor.clone()))
+    }
+
+    fn act_as_type<'a>(
+        &'a self,
+        type_id: TypeId,
+        self_handle: &'a Entity<Self>,
+        _: &'a App,
+    ) -> Option<gpui::AnyView> {
+        if type_id == TypeId::of::<Self>() {
+            Some(self_handle.to_any())
+        } else if type_id == TypeId::of::<Editor>() {
+            Some(self.editor.to_any())
+        } else {
+            None
+        }
+    }
+
+    fn deactivated(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.editor
+            .update(cx, |editor, cx| editor.deactivated(window, cx));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{actions::Undo, editor_tests::init_test};
+    use gpui::{TestAppContext, VisualTestContext};
+    use project::{ChangeAnnotation, FakeFs};
+    use serde_json::json;
+    use std::ops::Range;
+    use util::path;
+
+    #[gpui::test]
+    async fn test_rename_preview(cx: &mut TestAppContext) {
+        init_test(cx, |_| {});
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            path!("/dir"),
+            json!({
+                "one.rs": "const ONE: usize = 1;",
+                "two.rs": "const TWO: usize = one::ONE + one::ONE;",
+            }),
+        )
+        .await;
+        let project = Project::test(fs, [path!("/dir").as_ref()], cx).await;
+        let (workspace, cx) =
+            cx.add_window_view(|window, cx| Workspace::test_new(project.clone(), window, cx));
+
+        let one = project
+            .update(cx, |project, cx| {
+                project.open_local_buffer(path!("/dir/one.rs"), cx)
+            })
+            .await
+            .unwrap();
+        let two = project
+            .update(cx, |project, cx| {
+                project.open_local_buffer(path!("/dir/two.rs"), cx)
+            })
+            .await
+            .unwrap();
+
+        let rename_edit = |buffer: &Entity<Buffer>, range: Range<usize>, cx: &mut App| {
+            let buffer = buffer.read(cx);
+            RenameEdit {
+                range: buffer.anchor_before(range.start)..buffer.anchor_after(range.end),
+                new_text: "THREE".to_string(),
+                annotation: None,
+            }
+        };
+        let rename_edits = cx.update(|_, cx| {
+            vec![
+                BufferRenameEdits {
+                    buffer: one.clone(),
+                    edits: vec![rename_edit(&one, 6..9, cx)],
+                },
+                BufferRenameEdits {
+                    buffer: two.clone(),
+                    edits: vec![
+                        rename_edit(&two, 24..27, cx),
+                        RenameEdit {
+                            annotation: Some(ChangeAnnotation {
+                                label: "In an expression".to_string(),
+                                description: None,
+                                needs_confirmation: true,
+                            }),
+                            ..rename_edit(&two, 35..38, cx)
+                        },
+                    ],
+                },
+            ]
+        });
+
+        let preview = workspace.update_in(cx, |workspace, window, cx| {
+            let preview = cx.new(|cx| {
+                RenamePreview::new(
+                    "Rename: ONE → THREE",
+                    rename_edits,
+                    Some(project.clone()),
+                    workspace.weak_handle(),
+                    window,
+                    cx,
+                )
+            });
+            workspace.add_item_to_active_pane(Box::new(preview.clone()), None, true, window, cx);
+            preview
+        });
+        cx.run_until_parked();
+
+        let branch_texts = |preview: &Entity<RenamePreview>, cx: &mut VisualTestContext| {
+            preview.read_with(cx, |preview, cx| {
+                preview
+                    .files
+                    .iter()
+                    .map(|file| file.branch.read(cx).text())
+                    .collect::<Vec<_>>()
+            })
+        };
+        assert_eq!(
+            branch_texts(&preview, cx),
+            [
+                "const THREE: usize = 1;",
+                "const TWO: usize = one::THREE + one::ONE;",
+            ],
+            "edits that need confirmation should be excluded by default"
+        );
+        preview.read_with(cx, |preview, _| {
+            assert_eq!(preview.included_edit_count(), 2)
+        });
+
+        preview.update(cx, |preview, cx| preview.set_included(1, Some(1), true, cx));
+        preview.update(cx, |preview, cx| preview.set_included(0, None, false, cx));
+        assert_eq!(
+            branch_texts(&preview, cx),
+            [
+                "const ONE: usize = 1;",
+                "const TWO: usize = one::THREE + one::THREE;",
+            ],
+        );
+        preview.update(cx, |preview, cx| {
+            preview.set_included(1, Some(0), false, cx)
+        });
+        preview.update(cx, |preview, cx| preview.set_included(0, None, true, cx));
+        assert_eq!(
+            branch_texts(&preview, cx),
+            [
+                "const THREE: usize = 1;",
+                "const TWO: usize = one::ONE + one::THREE;",
+            ],
+        );
+        assert_eq!(
+            one.read_with(cx, |buffer, _| buffer.text()),
+            "const ONE: usize = 1;",
+            "previewing shouldn't touch the buffers"
+        );
+
+        preview.update_in(cx, |preview, window, cx| preview.apply(window, cx));
+        cx.run_until_parked();
+        assert_eq!(
+            one.read_with(cx, |buffer, _| buffer.text()),
+            "const THREE: usize = 1;"
+        );
+        assert_eq!(
+            two.read_with(cx, |buffer, _| buffer.text()),
+            "const TWO: usize = one::ONE + one::THREE;"
+        );
+
+        let editor = workspace.update(cx, |workspace, cx| {
+            assert!(
+                workspace.active_item_as::<RenamePreview>(cx).is_none(),
+                "the preview should be closed once applied"
+            );
+            workspace.active_item_as::<Editor>(cx).unwrap()
+        });
+        editor.update_in(cx, |editor, window, cx| editor.undo(&Undo, window, cx));
+        assert_eq!(
+            one.read_with(cx, |buffer, _| buffer.text()),
+            "const ONE: usize = 1;"
+        );
+        assert_eq!(
+            two.read_with(cx, |buffer, _| buffer.text()),
+            "const TWO: usize = one::ONE + one::ONE;",
+            "the whole rename should be undone at once"
+        );
+    }
+}
@@ -1,8 +1,10 @@
 pub mod extension_builder;
+mod extension_capabilities;
 mod extension_host_proxy;
 mod extension_manifest;
 mod types;
 
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
@@ -10,10 +12,12 @@ use ::lsp::LanguageServerName;
 use anyhow::{anyhow, bail, Context as _, Result};
 use async_trait::async_trait;
 use fs::normalize_path;
+use futures::stream::BoxStream;
 use gpui::{App, Task};
 use language::LanguageName;
 use semantic_version::SemanticVersion;
 
+pub use crate::extension_capabilities::*;
 pub use crate::extension_host_proxy::*;
 pub use crate::extension_manifest::*;
 pub use crate::types::*;
@@ -40,6 +44,18 @@ pub trait KeyValueStoreDelegate: Send + Sync + 'static {
     fn insert(&self, key: String, docs: String) -> Task<Result<()>>;
 }
 
+/// A snapshot of a buffer open in an editor, taken when an editor command is
+/// run on it.
+pub trait BufferDelegate: Send + Sync + 'static {
+    fn path(&self) -> Option<String>;
+    fn language_name(&self) -> Option<String>;
+    fn text(&self) -> String;
+    fn text_for_range(&self, range: Range<usize>) -> Result<String>;
+    fn selections(&self) -> Vec<BufferSelection>;
+    fn syntax_ancestors(&self, range: Range<usize>) -> Vec<SyntaxNode>;
+    fn syntax_children(&self, range: Range<usize>) -> Vec<SyntaxNode>;
+}
+
 #[async_trait]
 pub trait Extension: Send + Sync + 'static {
     /// Returns the [`ExtensionManifest`] for this extension.
@@ -112,6 +128,60 @@ pub trait Extension: Send + Sync + 'static {
         package_name: Arc<str>,
         kv_store: Arc<dyn KeyValueStoreDelegate>,
     ) -> Result<()>;
+
+    async fn run_editor_command(
+        &self,
+        command: EditorCommand,
+        buffer: Arc<dyn BufferDelegate>,
+    ) -> Result<Vec<TextEdit>>;
+
+    async fn task_templates(
+        &self,
+        task_provider_id: Arc<str>,
+        worktree: Arc<dyn WorktreeDelegate>,
+    ) -> Result<Vec<TaskTemplate>>;
+
+    async fn task_variables(
+        &self,
+        task_provider_id: Arc<str>,
+        worktree: Arc<dyn WorktreeDelegate>,
+        variables: EnvVars,
+    ) -> Result<EnvVars>;
+
+    async fn format(
+        &self,
+        formatter_id: Arc<str>,
+        worktree: Arc<dyn WorktreeDelegate>,
+        file: SourceFile,
+    ) -> Result<FormatOutput>;
+
+    async fn lint(
+        &self,
+        linter_id: Arc<str>,
+        worktree: Arc<dyn WorktreeDelegate>,
+        file: SourceFile,
+    ) -> Result<Vec<LintDiagnostic>>;
+
+    async fn language_models(
+        &self,
+        provider_id: Arc<str>,
+        api_key: Option<String>,
+    ) -> Result<Vec<LanguageModelInfo>>;
+
+    async fn count_language_model_tokens(
+        &self,
+        provider_id: Arc<str>,
+        model_id: Arc<str>,
+        request: CompletionRequest,
+    ) -> Result<usize>;
+
+    async fn stream_completion(
+        &self,
+        provider_id: Arc<str>,
+        model_id: Arc<str>,
+        request: CompletionRequest,
+        api_key: Option<String>,
+    ) -> Result<BoxStream<'static, Result<CompletionEvent>>>;
 }
 
 pub fn parse_wasm_extension_version(
@@ -0,0 +1,128 @@
+use std::path::PathBuf;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// The capabilities an extension is granted by the extension host.
+///
+/// Extensions declare the capabilities they need in the `[capabilities]` section
+/// of their `extension.toml`, and users may override them in the
+/// `extension_capabilities` setting.
+#[derive(Clone, Default, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ExtensionCapabilities {
+    /// The commands the extension may run, as passed to `run-command`.
+    ///
+    /// `"*"` allows any command.
+    #[serde(default)]
+    pub commands: Vec<String>,
+    /// The hosts the extension may send HTTP requests to, including through `download-file`.
+    ///
+    /// `"*.example.com"` allows any subdomain of `example.com`, and `"*"` allows any host.
+    #[serde(default)]
+    pub hosts: Vec<String>,
+    /// The directories outside of its working directory that the extension may read.
+    ///
+    /// A leading `~` is expanded to the home directory.
+    #[serde(default)]
+    pub paths: Vec<String>,
+}
+
+impl ExtensionCapabilities {
+    /// Returns the capabilities granted to extensions that do not declare any,
+    /// which allow running any command and accessing any host.
+    pub fn unrestricted() -> Self {
+        Self {
+            commands: vec!["*".into()],
+            hosts: vec!["*".into()],
+            paths: Vec::new(),
+        }
+    }
+
+    /// Returns whether no capabilities are granted.
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty() && self.hosts.is_empty() && self.paths.is_empty()
+    }
+
+    /// Returns whether the extension may run the given command.
+    pub fn allows_command(&self, command: &str) -> bool {
+        self.commands
+            .iter()
+            .any(|allowed| allowed == "*" || allowed == command)
+    }
+
+    /// Returns whether the extension may send requests to any host.
+    pub fn allows_any_host(&self) -> bool {
+        self.hosts.iter().any(|allowed| allowed == "*")
+    }
+
+    /// Returns whether the extension may send requests to the given URL.
+    pub fn allows_url(&self, url: &str) -> bool {
+        let Some(host) = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| host.to_ascii_lowercase()))
+        else {
+            return false;
+        };
+
+        self.hosts.iter().any(|allowed| {
+            let allowed = allowed.to_ascii_lowercase();
+            if allowed == "*" {
+                true
+            } else if let Some(domain) = allowed.strip_prefix("*.") {
+                host.strip_suffix(domain)
+                    .map_or(false, |subdomain| subdomain.ends_with('.'))
+            } else {
+                host == allowed
+            }
+        })
+    }
+
+    /// Returns the directories outside of its working directory that the
+    /// extension may read.
+    pub fn readable_paths(&self) -> Vec<PathBuf> {
+        self.paths
+            .iter()
+            .map(|path| match path.strip_prefix('~') {
+                Some(rest) => util::paths::home_dir().join(rest.trim_start_matches('/')),
+                None => PathBuf::from(path),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allows_command() {
+        let capabilities = ExtensionCapabilities {
+            commands: vec!["cargo".into()],
+            ..Default::default()
+        };
+        assert!(capabilities.allows_command("cargo"));
+        assert!(!capabilities.allows_command("/tmp/cargo"));
+        assert!(!capabilities.allows_command("rustc"));
+        assert!(ExtensionCapabilities::unrestricted().allows_command("rustc"));
+        assert!(!ExtensionCapabilities::default().allows_command("cargo"));
+    }
+
+    #[test]
+    fn test_allows_url() {
+        let capabilities = ExtensionCapabilities {
+            hosts: vec!["api.github.com".into(), "*.example.com".into()],
+            ..Default::default()
+        };
+        assert!(capabilities.allows_url("https://api.github.com/repos"));
+        assert!(capabilities.allows_url("https://API.GitHub.com/repos"));
+        assert!(!capabilities.allows_url("https://github.com/"));
+        assert!(capabilities.allows_url("https://cdn.example.com/file.tar.gz"));
+        assert!(!capabilities.allows_url("https://example.com/"));
+        assert!(!capabilities.allows_url("https://notexample.com/"));
+        assert!(!capabilities.allows_url("not a url"));
+        assert!(ExtensionCapabilities::unrestricted().allows_url("https://github.com/"));
+        assert!(!capabilities.allows_any_host());
+        assert!(ExtensionCapabilities::unrestricted().allows_any_host());
+    }
+}
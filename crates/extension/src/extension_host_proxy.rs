@@ -8,7 +8,7 @@ use language::{LanguageMatcher, LanguageName, LanguageServerBinaryStatus, Loaded
 use lsp::LanguageServerName;
 use parking_lot::RwLock;
 
-use crate::{Extension, SlashCommand};
+use crate::{EditorCommand, Extension, SlashCommand};
 
 #[derive(Default)]
 struct GlobalExtensionHostProxy(Arc<ExtensionHostProxy>);
@@ -29,6 +29,11 @@ pub struct ExtensionHostProxy {
     slash_command_proxy: RwLock<Option<Arc<dyn ExtensionSlashCommandProxy>>>,
     context_server_proxy: RwLock<Option<Arc<dyn ExtensionContextServerProxy>>>,
     indexed_docs_provider_proxy: RwLock<Option<Arc<dyn ExtensionIndexedDocsProviderProxy>>>,
+    editor_command_proxy: RwLock<Option<Arc<dyn ExtensionEditorCommandProxy>>>,
+    task_provider_proxy: RwLock<Option<Arc<dyn ExtensionTaskProviderProxy>>>,
+    formatter_proxy: RwLock<Option<Arc<dyn ExtensionFormatterProxy>>>,
+    linter_proxy: RwLock<Option<Arc<dyn ExtensionLinterProxy>>>,
+    language_model_provider_proxy: RwLock<Option<Arc<dyn ExtensionLanguageModelProviderProxy>>>,
 }
 
 impl ExtensionHostProxy {
@@ -54,6 +59,11 @@ impl ExtensionHostProxy {
             slash_command_proxy: RwLock::default(),
             context_server_proxy: RwLock::default(),
             indexed_docs_provider_proxy: RwLock::default(),
+            editor_command_proxy: RwLock::default(),
+            task_provider_proxy: RwLock::default(),
+            formatter_proxy: RwLock::default(),
+            linter_proxy: RwLock::default(),
+            language_model_provider_proxy: RwLock::default(),
         }
     }
 
@@ -93,6 +103,31 @@ impl ExtensionHostProxy {
             .write()
             .replace(Arc::new(proxy));
     }
+
+    pub fn register_editor_command_proxy(&self, proxy: impl ExtensionEditorCommandProxy) {
+        self.editor_command_proxy.write().replace(Arc::new(proxy));
+    }
+
+    pub fn register_task_provider_proxy(&self, proxy: impl ExtensionTaskProviderProxy) {
+        self.task_provider_proxy.write().replace(Arc::new(proxy));
+    }
+
+    pub fn register_formatter_proxy(&self, proxy: impl ExtensionFormatterProxy) {
+        self.formatter_proxy.write().replace(Arc::new(proxy));
+    }
+
+    pub fn register_linter_proxy(&self, proxy: impl ExtensionLinterProxy) {
+        self.linter_proxy.write().replace(Arc::new(proxy));
+    }
+
+    pub fn register_language_model_provider_proxy(
+        &self,
+        proxy: impl ExtensionLanguageModelProviderProxy,
+    ) {
+        self.language_model_provider_proxy
+            .write()
+            .replace(Arc::new(proxy));
+    }
 }
 
 pub trait ExtensionThemeProxy: Send + Sync + 'static {
@@ -392,3 +427,173 @@ impl ExtensionIndexedDocsProviderProxy for ExtensionHostProxy {
         proxy.register_indexed_docs_provider(extension, provider_id)
     }
 }
+
+pub trait ExtensionEditorCommandProxy: Send + Sync + 'static {
+    fn register_editor_command(&self, extension: Arc<dyn Extension>, command: EditorCommand);
+
+    fn remove_editor_command(&self, extension_id: &Arc<str>, command_name: &str);
+}
+
+impl ExtensionEditorCommandProxy for ExtensionHostProxy {
+    fn register_editor_command(&self, extension: Arc<dyn Extension>, command: EditorCommand) {
+        let Some(proxy) = self.editor_command_proxy.read().clone() else {
+            return;
+        };
+
+        proxy.register_editor_command(extension, command)
+    }
+
+    fn remove_editor_command(&self, extension_id: &Arc<str>, command_name: &str) {
+        let Some(proxy) = self.editor_command_proxy.read().clone() else {
+            return;
+        };
+
+        proxy.remove_editor_command(extension_id, command_name)
+    }
+}
+
+pub trait ExtensionTaskProviderProxy: Send + Sync + 'static {
+    fn register_task_provider(
+        &self,
+        extension: Arc<dyn Extension>,
+        task_provider_id: Arc<str>,
+        cx: &mut App,
+    );
+
+    fn remove_task_provider(&self, task_provider_id: &Arc<str>, cx: &mut App);
+}
+
+impl ExtensionTaskProviderProxy for ExtensionHostProxy {
+    fn register_task_provider(
+        &self,
+        extension: Arc<dyn Extension>,
+        task_provider_id: Arc<str>,
+        cx: &mut App,
+    ) {
+        let Some(proxy) = self.task_provider_proxy.read().clone() else {
+            return;
+        };
+
+        proxy.register_task_provider(extension, task_provider_id, cx)
+    }
+
+    fn remove_task_provider(&self, task_provider_id: &Arc<str>, cx: &mut App) {
+        let Some(proxy) = self.task_provider_proxy.read().clone() else {
+            return;
+        };
+
+        proxy.remove_task_provider(task_provider_id, cx)
+    }
+}
+
+pub trait ExtensionFormatterProxy: Send + Sync + 'static {
+    fn register_formatter(
+        &self,
+        extension: Arc<dyn Extension>,
+        formatter_id: Arc<str>,
+        language: LanguageName,
+    );
+
+    fn remove_formatter(&self, language: &LanguageName, formatter_id: &Arc<str>);
+}
+
+impl ExtensionFormatterProxy for ExtensionHostProxy {
+    fn register_formatter(
+        &self,
+        extension: Arc<dyn Extension>,
+        formatter_id: Arc<str>,
+        language: LanguageName,
+    ) {
+        let Some(proxy) = self.formatter_proxy.read().clone() else {
+            return;
+        };
+
+        proxy.register_formatter(extension, formatter_id, language)
+    }
+
+    fn remove_formatter(&self, language: &LanguageName, formatter_id: &Arc<str>) {
+        let Some(proxy) = self.formatter_proxy.read().clone() else {
+            return;
+        };
+
+        proxy.remove_formatter(language, formatter_id)
+    }
+}
+
+pub trait ExtensionLinterProxy: Send + Sync + 'static {
+    fn register_linter(
+        &self,
+        extension: Arc<dyn Extension>,
+        linter_id: Arc<str>,
+        language: LanguageName,
+    );
+
+    fn remove_linter(&self, language: &LanguageName, linter_id: &Arc<str>);
+}
+
+impl ExtensionLinterProxy for ExtensionHostProxy {
+    fn register_linter(
+        &self,
+        extension: Arc<dyn Extension>,
+        linter_id: Arc<str>,
+        language: LanguageName,
+    ) {
+        let Some(proxy) = self.linter_proxy.read().clone() else {
+            return;
+        };
+
+        proxy.register_linter(extension, linter_id, language)
+    }
+
+    fn remove_linter(&self, language: &LanguageName, linter_id: &Arc<str>) {
+        let Some(proxy) = self.linter_proxy.read().clone() else {
+            return;
+        };
+
+        proxy.remove_linter(language, linter_id)
+    }
+}
+
+pub trait ExtensionLanguageModelProviderProxy: Send + Sync + 'static {
+    fn register_language_model_provider(
+        &self,
+        extension: Arc<dyn Extension>,
+        provider_id: Arc<str>,
+        cx: &mut App,
+    );
+
+    fn remove_language_model_provider(
+        &self,
+        extension_id: &Arc<str>,
+        provider_id: &Arc<str>,
+        cx: &mut App,
+    );
+}
+
+impl ExtensionLanguageModelProviderProxy for ExtensionHostProxy {
+    fn register_language_model_provider(
+        &self,
+        extension: Arc<dyn Extension>,
+        provider_id: Arc<str>,
+        cx: &mut App,
+    ) {
+        let Some(proxy) = self.language_model_provider_proxy.read().clone() else {
+            return;
+        };
+
+        proxy.register_language_model_provider(extension, provider_id, cx)
+    }
+
+    fn remove_language_model_provider(
+        &self,
+        extension_id: &Arc<str>,
+        provider_id: &Arc<str>,
+        cx: &mut App,
+    ) {
+        let Some(proxy) = self.language_model_provider_proxy.read().clone() else {
+            return;
+        };
+
+        proxy.remove_language_model_provider(extension_id, provider_id, cx)
+    }
+}
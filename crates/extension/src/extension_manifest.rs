@@ -12,6 +12,8 @@ use std::{
     sync::Arc,
 };
 
+use crate::ExtensionCapabilities;
+
 /// This is the old version of the extension manifest, from when it was `extension.json`.
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct OldExtensionManifest {
@@ -84,7 +86,22 @@ pub struct ExtensionManifest {
     #[serde(default)]
     pub indexed_docs_providers: BTreeMap<Arc<str>, IndexedDocsProviderEntry>,
     #[serde(default)]
+    pub editor_commands: BTreeMap<Arc<str>, EditorCommandManifestEntry>,
+    #[serde(default)]
+    pub task_providers: BTreeMap<Arc<str>, TaskProviderManifestEntry>,
+    #[serde(default)]
+    pub formatters: BTreeMap<Arc<str>, FormatterManifestEntry>,
+    #[serde(default)]
+    pub linters: BTreeMap<Arc<str>, LinterManifestEntry>,
+    #[serde(default)]
+    pub language_model_providers: BTreeMap<Arc<str>, LanguageModelProviderManifestEntry>,
+    #[serde(default)]
     pub snippets: Option<PathBuf>,
+    /// The capabilities the extension needs from the extension host.
+    ///
+    /// Extensions that do not declare their capabilities are granted unrestricted ones.
+    #[serde(default)]
+    pub capabilities: Option<ExtensionCapabilities>,
 }
 
 #[derive(Clone, Default, PartialEq, Eq, Debug, Deserialize, Serialize)]
@@ -151,7 +168,47 @@ pub struct SlashCommandManifestEntry {
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 pub struct IndexedDocsProviderEntry {}
 
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct EditorCommandManifestEntry {
+    pub description: String,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct TaskProviderManifestEntry {}
+
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct FormatterManifestEntry {
+    /// The list of languages this formatter can format.
+    pub languages: Vec<LanguageName>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct LinterManifestEntry {
+    /// The list of languages this linter should run on.
+    pub languages: Vec<LanguageName>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct LanguageModelProviderManifestEntry {
+    /// The human-readable name of the provider.
+    pub name: String,
+    /// The environment variable to read the provider's API key from, if any.
+    #[serde(default)]
+    pub api_key_env_var: Option<String>,
+    /// Whether the provider needs an API key before its models can be used.
+    #[serde(default)]
+    pub requires_api_key: bool,
+}
+
 impl ExtensionManifest {
+    /// Returns the capabilities the extension declares, or unrestricted
+    /// capabilities if it does not declare any.
+    pub fn capabilities(&self) -> ExtensionCapabilities {
+        self.capabilities
+            .clone()
+            .unwrap_or_else(ExtensionCapabilities::unrestricted)
+    }
+
     pub async fn load(fs: Arc<dyn Fs>, extension_dir: &Path) -> Result<Self> {
         let extension_name = extension_dir
             .file_name()
@@ -217,6 +274,12 @@ fn manifest_from_old_manifest(
         context_servers: BTreeMap::default(),
         slash_commands: BTreeMap::default(),
         indexed_docs_providers: BTreeMap::default(),
+        editor_commands: BTreeMap::default(),
+        task_providers: BTreeMap::default(),
+        formatters: BTreeMap::default(),
+        linters: BTreeMap::default(),
+        language_model_providers: BTreeMap::default(),
         snippets: None,
+        capabilities: None,
     }
 }
@@ -1,10 +1,18 @@
+mod editor;
+mod formatting;
+mod language_model;
 mod lsp;
 mod slash_command;
+mod task;
 
 use std::ops::Range;
 
+pub use editor::*;
+pub use formatting::*;
+pub use language_model::*;
 pub use lsp::*;
 pub use slash_command::*;
+pub use task::*;
 
 /// A list of environment variables.
 pub type EnvVars = Vec<(String, String)>;
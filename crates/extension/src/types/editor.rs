@@ -0,0 +1,40 @@
+use std::ops::Range;
+
+/// An editor command provided by an extension.
+#[derive(Debug, Clone)]
+pub struct EditorCommand {
+    /// The name of the command.
+    pub name: String,
+    /// The description of the command.
+    pub description: String,
+}
+
+/// A selection in a buffer.
+#[derive(Debug, Clone)]
+pub struct BufferSelection {
+    /// The byte range of the selection.
+    pub range: Range<usize>,
+    /// Whether the cursor is at the start of the selection, rather than at its end.
+    pub reversed: bool,
+}
+
+/// A node in the syntax tree of a buffer.
+#[derive(Debug, Clone)]
+pub struct SyntaxNode {
+    /// The kind of the node, as named by the language's Tree-sitter grammar.
+    pub kind: String,
+    /// The byte range of the node.
+    pub range: Range<usize>,
+    /// Whether the node is named in the grammar, as opposed to being an
+    /// anonymous token, such as punctuation.
+    pub is_named: bool,
+}
+
+/// An edit to apply to a buffer.
+#[derive(Debug, Clone)]
+pub struct TextEdit {
+    /// The byte range to replace.
+    pub range: Range<usize>,
+    /// The text to replace the range with.
+    pub new_text: String,
+}
@@ -0,0 +1,45 @@
+use std::ops::Range;
+
+use crate::Command;
+
+/// A file to format or lint.
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    /// The path of the file, relative to the worktree root.
+    pub path: Option<String>,
+    /// The name of the language of the file.
+    pub language_name: String,
+    /// The text of the file.
+    pub text: String,
+}
+
+/// The output of a formatter.
+#[derive(Debug)]
+pub enum FormatOutput {
+    /// A command that reads the text from stdin and writes the formatted text to stdout.
+    Command(Command),
+    /// The formatted text.
+    Text(String),
+}
+
+/// The severity of a lint diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintSeverity {
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+/// A diagnostic reported by a linter.
+#[derive(Debug, Clone)]
+pub struct LintDiagnostic {
+    /// The byte range of the diagnostic in the linted text.
+    pub range: Range<usize>,
+    /// The severity of the diagnostic.
+    pub severity: LintSeverity,
+    /// The message of the diagnostic.
+    pub message: String,
+    /// The code of the diagnostic, such as the name of the violated rule.
+    pub code: Option<String>,
+}
@@ -0,0 +1,57 @@
+/// A language model offered by a language model provider.
+#[derive(Debug, Clone)]
+pub struct LanguageModelInfo {
+    /// The ID of the model, as passed to the provider's API.
+    pub id: String,
+    /// The human-readable name of the model.
+    pub name: String,
+    /// The maximum number of tokens in the context of the model.
+    pub max_token_count: usize,
+    /// The maximum number of tokens the model may output.
+    pub max_output_tokens: Option<u32>,
+}
+
+/// The role of the author of a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageRole {
+    User,
+    Assistant,
+    System,
+}
+
+/// A message in a completion request.
+#[derive(Debug, Clone)]
+pub struct RequestMessage {
+    /// The role of the author of the message.
+    pub role: MessageRole,
+    /// The text of the message.
+    pub content: String,
+}
+
+/// A request for a completion.
+#[derive(Debug, Clone)]
+pub struct CompletionRequest {
+    /// The messages of the conversation, in order.
+    pub messages: Vec<RequestMessage>,
+    /// The sequences at which the model should stop generating.
+    pub stop: Vec<String>,
+    /// The sampling temperature.
+    pub temperature: Option<f32>,
+}
+
+/// The reason a completion stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    EndTurn,
+    MaxTokens,
+    ToolUse,
+}
+
+/// An event in a completion stream.
+#[derive(Debug, Clone)]
+pub enum CompletionEvent {
+    /// Text generated by the model.
+    Text(String),
+    /// The completion stopped.
+    Stop(StopReason),
+}
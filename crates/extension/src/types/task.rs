@@ -0,0 +1,18 @@
+use crate::EnvVars;
+
+/// A template for a task provided by an extension.
+#[derive(Debug, Clone)]
+pub struct TaskTemplate {
+    /// The human-readable label of the task.
+    pub label: String,
+    /// The command to run.
+    pub command: String,
+    /// The arguments to pass to the command.
+    pub args: Vec<String>,
+    /// The environment variables to set for the command.
+    pub env: EnvVars,
+    /// The working directory of the command, if it should not run in the worktree root.
+    pub cwd: Option<String>,
+    /// The tags used to match the task against runnables in the editor.
+    pub tags: Vec<String>,
+}
@@ -1,6 +1,7 @@
 //! The Zed Rust Extension API allows you write extensions for [Zed](https://zed.dev/) in Rust.
 
 pub mod http_client;
+pub mod language_model;
 pub mod process;
 pub mod settings;
 
@@ -39,6 +40,25 @@ pub use wit::{
 #[doc(hidden)]
 pub use wit::Guest;
 
+/// Constructs for reading buffers open in editors and editing them.
+pub mod editor {
+    pub use crate::wit::zed::extension::editor::{
+        Buffer, EditorCommand, Selection, SyntaxNode, TextEdit,
+    };
+}
+
+/// Constructs for providing formatters and linters.
+pub mod formatting {
+    pub use crate::wit::zed::extension::formatting::{
+        FormatOutput, LintDiagnostic, LintSeverity, SourceFile,
+    };
+}
+
+/// Constructs for providing tasks.
+pub mod task {
+    pub use crate::wit::zed::extension::task::TaskTemplate;
+}
+
 /// Constructs for interacting with language servers over the
 /// Language Server Protocol (LSP).
 pub mod lsp {
@@ -157,6 +177,99 @@ pub trait Extension: Send + Sync {
     ) -> Result<(), String> {
         Err("`index_docs` not implemented".to_string())
     }
+
+    /// Returns the edits to apply to the buffer when running the provided
+    /// editor command on it.
+    ///
+    /// The edits are applied together, as a single transaction.
+    fn run_editor_command(
+        &self,
+        _command: EditorCommand,
+        _buffer: &Buffer,
+    ) -> Result<Vec<TextEdit>, String> {
+        Err("`run_editor_command` not implemented".to_string())
+    }
+
+    /// Returns the task templates that the specified task provider offers for
+    /// the given worktree, such as the targets of a `Makefile` in it.
+    fn task_templates(
+        &self,
+        _task_provider_id: &TaskProviderId,
+        _worktree: &Worktree,
+    ) -> Result<Vec<TaskTemplate>, String> {
+        Ok(Vec::new())
+    }
+
+    /// Returns the values of the custom task variables that the specified task
+    /// provider defines, given the task variables resolved so far.
+    ///
+    /// A variable returned with the name `NAME` is available to tasks as
+    /// `$ZED_CUSTOM_NAME`.
+    fn task_variables(
+        &self,
+        _task_provider_id: &TaskProviderId,
+        _worktree: &Worktree,
+        _variables: EnvVars,
+    ) -> Result<EnvVars, String> {
+        Ok(Vec::new())
+    }
+
+    /// Formats the given file with the specified formatter.
+    ///
+    /// Returns either the formatted text, or a command that reads the text
+    /// from stdin and writes the formatted text to stdout.
+    fn format(
+        &self,
+        _formatter_id: &FormatterId,
+        _worktree: &Worktree,
+        _file: SourceFile,
+    ) -> Result<FormatOutput, String> {
+        Err("`format` not implemented".to_string())
+    }
+
+    /// Returns the diagnostics that the specified linter reports for the given file.
+    fn lint(
+        &self,
+        _linter_id: &LinterId,
+        _worktree: &Worktree,
+        _file: SourceFile,
+    ) -> Result<Vec<LintDiagnostic>, String> {
+        Ok(Vec::new())
+    }
+
+    /// Returns the language models offered by the specified language model provider.
+    ///
+    /// `api_key` is the API key the user configured for the provider, if any.
+    fn language_models(
+        &self,
+        _provider_id: &LanguageModelProviderId,
+        _api_key: Option<String>,
+    ) -> Result<Vec<LanguageModelInfo>, String> {
+        Ok(Vec::new())
+    }
+
+    /// Returns the number of tokens in the given request for the specified model.
+    ///
+    /// Defaults to an estimate based on the length of the messages.
+    fn count_language_model_tokens(
+        &self,
+        _provider_id: &LanguageModelProviderId,
+        _model_id: &str,
+        request: CompletionRequest,
+    ) -> Result<u64, String> {
+        Ok(request.estimated_token_count())
+    }
+
+    /// Streams a completion of the given request from the specified model.
+    fn stream_completion(
+        &self,
+        _provider_id: &LanguageModelProviderId,
+        _model_id: &str,
+        _request: CompletionRequest,
+        _api_key: Option<String>,
+    ) -> Result<language_model::CompletionStream, String> {
+        Err("`stream_completion` not implemented".to_string())
+    }
 }
 
 /// Registers the provided type as a Zed extension.
@@ -196,7 +309,7 @@ mod wit {
 
     wit_bindgen::generate!({
         skip: ["init-extension"],
-        path: "./wit/since_v0.3.0",
+        path: "./wit/since_v0.4.0",
     });
 }
 
@@ -299,6 +412,84 @@ impl wit::Guest for Component {
     ) -> Result<(), String> {
         extension().index_docs(provider, package, database)
     }
+
+    fn run_editor_command(
+        command: EditorCommand,
+        buffer: &Buffer,
+    ) -> Result<Vec<TextEdit>, String> {
+        extension().run_editor_command(command, buffer)
+    }
+
+    fn task_templates(
+        task_provider_id: String,
+        worktree: &Worktree,
+    ) -> Result<Vec<TaskTemplate>, String> {
+        let task_provider_id = TaskProviderId(task_provider_id);
+        extension().task_templates(&task_provider_id, worktree)
+    }
+
+    fn task_variables(
+        task_provider_id: String,
+        worktree: &Worktree,
+        variables: EnvVars,
+    ) -> Result<EnvVars, String> {
+        let task_provider_id = TaskProviderId(task_provider_id);
+        extension().task_variables(&task_provider_id, worktree, variables)
+    }
+
+    fn format(
+        formatter_id: String,
+        worktree: &Worktree,
+        file: SourceFile,
+    ) -> Result<FormatOutput, String> {
+        let formatter_id = FormatterId(formatter_id);
+        extension().format(&formatter_id, worktree, file)
+    }
+
+    fn lint(
+        linter_id: String,
+        worktree: &Worktree,
+        file: SourceFile,
+    ) -> Result<Vec<LintDiagnostic>, String> {
+        let linter_id = LinterId(linter_id);
+        extension().lint(&linter_id, worktree, file)
+    }
+
+    fn language_models(
+        provider_id: String,
+        api_key: Option<String>,
+    ) -> Result<Vec<LanguageModelInfo>, String> {
+        let provider_id = LanguageModelProviderId(provider_id);
+        extension().language_models(&provider_id, api_key)
+    }
+
+    fn count_language_model_tokens(
+        provider_id: String,
+        model_id: String,
+        request: CompletionRequest,
+    ) -> Result<u64, String> {
+        let provider_id = LanguageModelProviderId(provider_id);
+        extension().count_language_model_tokens(&provider_id, &model_id, request)
+    }
+
+    fn start_completion(
+        provider_id: String,
+        model_id: String,
+        request: CompletionRequest,
+        api_key: Option<String>,
+    ) -> Result<u64, String> {
+        let provider_id = LanguageModelProviderId(provider_id);
+        let stream = extension().stream_completion(&provider_id, &model_id, request, api_key)?;
+        Ok(language_model::insert_completion(stream))
+    }
+
+    fn next_completion_event(completion_id: u64) -> Result<Option<CompletionEvent>, String> {
+        language_model::next_completion_event(completion_id)
+    }
+
+    fn drop_completion(completion_id: u64) {
+        language_model::drop_completion(completion_id)
+    }
 }
 
 /// The ID of a language server.
@@ -333,6 +524,70 @@ impl fmt::Display for ContextServerId {
     }
 }
 
+/// The ID of a task provider.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct TaskProviderId(String);
+
+impl AsRef<str> for TaskProviderId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for TaskProviderId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The ID of a formatter.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct FormatterId(String);
+
+impl AsRef<str> for FormatterId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for FormatterId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The ID of a linter.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct LinterId(String);
+
+impl AsRef<str> for LinterId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for LinterId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The ID of a language model provider.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct LanguageModelProviderId(String);
+
+impl AsRef<str> for LanguageModelProviderId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for LanguageModelProviderId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 impl CodeLabelSpan {
     /// Returns a [`CodeLabelSpan::CodeRange`].
     pub fn code_range(range: impl Into<wit::Range>) -> Self {
@@ -0,0 +1,87 @@
+//! Constructs for providing language models.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+pub use crate::wit::zed::extension::language_model::{
+    CompletionEvent, CompletionRequest, LanguageModelInfo, MessageRole, RequestMessage, StopReason,
+};
+
+/// A stream of [`CompletionEvent`]s produced by a language model.
+///
+/// Zed pulls events from the stream one at a time until it is exhausted, so
+/// the stream can read the response of the provider incrementally, such as
+/// with [`crate::http_client::fetch_stream`].
+pub struct CompletionStream(Box<dyn Iterator<Item = Result<CompletionEvent, String>>>);
+
+impl CompletionStream {
+    /// Returns a [`CompletionStream`] that yields the events of the given iterator.
+    pub fn new(events: impl Iterator<Item = Result<CompletionEvent, String>> + 'static) -> Self {
+        Self(Box::new(events))
+    }
+
+    /// Returns a [`CompletionStream`] that yields the given events.
+    pub fn from_events(events: Vec<CompletionEvent>) -> Self {
+        Self::new(events.into_iter().map(Ok))
+    }
+}
+
+impl Iterator for CompletionStream {
+    type Item = Result<CompletionEvent, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+impl CompletionRequest {
+    /// Returns a rough estimate of the number of tokens in the request,
+    /// assuming four characters per token.
+    pub fn estimated_token_count(&self) -> u64 {
+        let characters: usize = self
+            .messages
+            .iter()
+            .map(|message| message.content.chars().count())
+            .sum();
+        characters.div_ceil(4) as u64
+    }
+}
+
+#[derive(Default)]
+struct Completions {
+    next_id: u64,
+    streams: HashMap<u64, CompletionStream>,
+}
+
+thread_local! {
+    static COMPLETIONS: RefCell<Completions> = RefCell::default();
+}
+
+pub(crate) fn insert_completion(stream: CompletionStream) -> u64 {
+    COMPLETIONS.with_borrow_mut(|completions| {
+        let id = completions.next_id;
+        completions.next_id += 1;
+        completions.streams.insert(id, stream);
+        id
+    })
+}
+
+pub(crate) fn next_completion_event(id: u64) -> Result<Option<CompletionEvent>, String> {
+    COMPLETIONS.with_borrow_mut(|completions| {
+        let stream = completions
+            .streams
+            .get_mut(&id)
+            .ok_or_else(|| format!("no completion with ID {id}"))?;
+        let event = stream.next().transpose()?;
+        if event.is_none() {
+            completions.streams.remove(&id);
+        }
+        Ok(event)
+    })
+}
+
+pub(crate) fn drop_completion(id: u64) {
+    COMPLETIONS.with_borrow_mut(|completions| {
+        completions.streams.remove(&id);
+    });
+}
@@ -7,6 +7,7 @@ use std::sync::Arc;
 
 use ::fs::{copy_recursive, CopyOptions, Fs, RealFs};
 use anyhow::{anyhow, bail, Context, Result};
+use chrono::Utc;
 use clap::Parser;
 use extension::extension_builder::{CompileExtensionOptions, ExtensionBuilder};
 use extension::ExtensionManifest;
@@ -27,6 +28,9 @@ struct Args {
     /// The path to a directory where build dependencies are downloaded
     #[arg(long)]
     scratch_dir: PathBuf,
+    /// The path to a file-system extension registry to publish the packaged extension to.
+    #[arg(long)]
+    registry_dir: Option<PathBuf>,
 }
 
 #[tokio::main]
@@ -97,9 +101,16 @@ async fn main() -> Result<()> {
         );
     }
 
+    let archive = fs::read(output_dir.join("archive.tar.gz"))?;
+    let archive_checksum = rpc::extension_archive_checksum(&archive);
+    fs::write(
+        output_dir.join("archive.tar.gz.sha256"),
+        format!("{archive_checksum}  archive.tar.gz\n"),
+    )?;
+
     let extension_provides = extension_provides(&manifest);
 
-    let manifest_json = serde_json::to_string(&rpc::ExtensionApiManifest {
+    let api_manifest = rpc::ExtensionApiManifest {
         name: manifest.name,
         version: manifest.version,
         description: manifest.description,
@@ -110,10 +121,74 @@ async fn main() -> Result<()> {
             .ok_or_else(|| anyhow!("missing repository in extension manifest"))?,
         wasm_api_version: manifest.lib.version.map(|version| version.to_string()),
         provides: extension_provides,
-    })?;
+    };
+    let manifest_json = serde_json::to_string(&api_manifest)?;
     fs::remove_dir_all(&archive_dir)?;
     fs::write(output_dir.join("manifest.json"), manifest_json.as_bytes())?;
 
+    if let Some(registry_dir) = args.registry_dir {
+        publish_to_registry(
+            &registry_dir,
+            manifest.id,
+            api_manifest,
+            &archive,
+            archive_checksum,
+        )
+        .context("failed to publish extension to registry")?;
+    }
+
+    Ok(())
+}
+
+/// Adds the packaged extension to the file-system registry in `registry_dir`,
+/// replacing any previously published archive for the same version.
+fn publish_to_registry(
+    registry_dir: &Path,
+    extension_id: Arc<str>,
+    manifest: rpc::ExtensionApiManifest,
+    archive: &[u8],
+    archive_checksum: String,
+) -> Result<()> {
+    let archive_path = format!(
+        "archives/{extension_id}/{extension_id}-{}.tar.gz",
+        manifest.version
+    );
+    let archive_dir = registry_dir.join("archives").join(extension_id.as_ref());
+    fs::create_dir_all(&archive_dir).context("failed to create registry archive dir")?;
+    fs::write(registry_dir.join(&archive_path), archive)
+        .context("failed to copy archive to registry")?;
+
+    let index_path = registry_dir.join(rpc::ExtensionRegistryIndex::FILE_NAME);
+    let mut index = if index_path.exists() {
+        let index_json = fs::read(&index_path).context("failed to read registry index")?;
+        serde_json::from_slice::<rpc::ExtensionRegistryIndex>(&index_json)
+            .context("invalid registry index")?
+    } else {
+        rpc::ExtensionRegistryIndex::default()
+    };
+
+    index.extensions.retain(|entry| {
+        entry.metadata.id != extension_id || entry.metadata.manifest.version != manifest.version
+    });
+    log::info!(
+        "publishing {extension_id} {} to {}",
+        manifest.version,
+        registry_dir.display()
+    );
+    index.extensions.push(rpc::ExtensionRegistryEntry {
+        metadata: rpc::ExtensionMetadata {
+            id: extension_id,
+            manifest,
+            published_at: Utc::now(),
+            download_count: 0,
+        },
+        archive: archive_path,
+        sha256: archive_checksum,
+    });
+
+    let index_json = serde_json::to_string_pretty(&index)?;
+    fs::write(&index_path, index_json.as_bytes()).context("failed to write registry index")?;
+
     Ok(())
 }
 
@@ -167,6 +242,12 @@ async fn copy_extension_resources(
     fs::write(output_dir.join("extension.toml"), &manifest_toml)
         .context("failed to write extension.toml")?;
 
+    let changelog_path = extension_path.join("CHANGELOG.md");
+    if changelog_path.exists() {
+        fs::copy(&changelog_path, output_dir.join("CHANGELOG.md"))
+            .context("failed to copy CHANGELOG.md")?;
+    }
+
     if manifest.lib.kind.is_some() {
         fs::copy(
             extension_path.join("extension.wasm"),
@@ -1,3 +1,4 @@
+pub mod extension_registry;
 pub mod extension_settings;
 pub mod headless_host;
 pub mod wasm_host;
@@ -9,16 +10,21 @@ use anyhow::{anyhow, bail, Context as _, Result};
 use async_compression::futures::bufread::GzipDecoder;
 use async_tar::Archive;
 use client::ExtensionProvides;
-use client::{proto, telemetry::Telemetry, Client, ExtensionMetadata, GetExtensionsResponse};
+use client::{
+    proto, telemetry::Telemetry, Client, ExtensionMetadata, ExtensionRegistryEntry,
+    GetExtensionsResponse,
+};
 use collections::{btree_map, BTreeMap, BTreeSet, HashMap, HashSet};
 use extension::extension_builder::{CompileExtensionOptions, ExtensionBuilder};
 pub use extension::ExtensionManifest;
 use extension::{
-    ExtensionContextServerProxy, ExtensionGrammarProxy, ExtensionHostProxy,
-    ExtensionIndexedDocsProviderProxy, ExtensionLanguageProxy, ExtensionLanguageServerProxy,
-    ExtensionSlashCommandProxy, ExtensionSnippetProxy, ExtensionThemeProxy,
+    ExtensionCapabilities, ExtensionContextServerProxy, ExtensionEditorCommandProxy,
+    ExtensionFormatterProxy, ExtensionGrammarProxy, ExtensionHostProxy,
+    ExtensionIndexedDocsProviderProxy, ExtensionLanguageModelProviderProxy, ExtensionLanguageProxy,
+    ExtensionLanguageServerProxy, ExtensionLinterProxy, ExtensionSlashCommandProxy,
+    ExtensionSnippetProxy, ExtensionTaskProviderProxy, ExtensionThemeProxy,
 };
-use fs::{Fs, RemoveOptions};
+use fs::{Fs, RemoveOptions, RenameOptions};
 use futures::{
     channel::{
         mpsc::{unbounded, UnboundedSender},
@@ -42,7 +48,7 @@ use release_channel::ReleaseChannel;
 use remote::SshRemoteClient;
 use semantic_version::SemanticVersion;
 use serde::{Deserialize, Serialize};
-use settings::Settings;
+use settings::{Settings, SettingsStore};
 use std::ops::RangeInclusive;
 use std::str::FromStr;
 use std::{
@@ -61,10 +67,15 @@ use wasm_host::{
 pub use extension::{
     ExtensionLibraryKind, GrammarManifestEntry, OldExtensionManifest, SchemaVersion,
 };
+pub use extension_registry::ExtensionRegistry;
 pub use extension_settings::ExtensionSettings;
 
 pub const RELOAD_DEBOUNCE_DURATION: Duration = Duration::from_millis(200);
 const FS_WATCH_LATENCY: Duration = Duration::from_millis(100);
+/// How long to wait before retrying to install the pinned version of an
+/// extension after it failed to install. This doubles with every failure.
+const PINNED_INSTALL_RETRY_DELAY: Duration = Duration::from_secs(5 * 60);
+const MAX_PINNED_INSTALL_RETRY_DELAY: Duration = Duration::from_secs(24 * 60 * 60);
 
 /// The current extension [`SchemaVersion`] supported by Zed.
 const CURRENT_SCHEMA_VERSION: SchemaVersion = SchemaVersion(1);
@@ -98,6 +109,22 @@ pub fn is_version_compatible(
     true
 }
 
+/// Returns an error unless the given extension ID, which may come from an untrusted
+/// archive or registry, is safe to use as the name of the extension's directory.
+///
+/// Valid IDs consist of ASCII letters, digits, `-` and `_`, so they are always a single
+/// normal path component.
+fn validate_extension_id(extension_id: &str) -> Result<()> {
+    let is_valid = !extension_id.is_empty()
+        && extension_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !is_valid {
+        bail!("invalid extension id {extension_id:?}");
+    }
+    Ok(())
+}
+
 pub struct ExtensionStore {
     pub proxy: Arc<ExtensionHostProxy>,
     pub builder: Arc<ExtensionBuilder>,
@@ -108,6 +135,9 @@ pub struct ExtensionStore {
     pub reload_tx: UnboundedSender<Option<Arc<str>>>,
     pub reload_complete_senders: Vec<oneshot::Sender<()>>,
     pub installed_dir: PathBuf,
+    /// The directory containing the previously installed version of each upgraded extension.
+    pub previous_dir: PathBuf,
+    pub previous_versions: BTreeMap<Arc<str>, Arc<str>>,
     pub outstanding_operations: BTreeMap<Arc<str>, ExtensionOperation>,
     pub index_path: PathBuf,
     pub modified_extensions: HashSet<Arc<str>>,
@@ -116,8 +146,22 @@ pub struct ExtensionStore {
     pub tasks: Vec<Task<()>>,
     pub ssh_clients: HashMap<String, WeakEntity<SshRemoteClient>>,
     pub ssh_registered_tx: UnboundedSender<()>,
+    capabilities_prompt: Option<CapabilitiesPrompt>,
+    failed_pinned_installs: HashMap<Arc<str>, FailedPinnedInstall>,
+}
+
+/// A pinned extension version that failed to install.
+struct FailedPinnedInstall {
+    version: Arc<str>,
+    failure_count: u32,
+    retry_at: Instant,
 }
 
+/// Asks the user whether to install an extension that will be granted the
+/// given capabilities, resolving to whether they agreed.
+pub type CapabilitiesPrompt =
+    Arc<dyn Fn(Arc<ExtensionManifest>, ExtensionCapabilities, &mut App) -> Task<Result<bool>>>;
+
 #[derive(Clone, Copy)]
 pub enum ExtensionOperation {
     Upgrade,
@@ -233,6 +277,7 @@ impl ExtensionStore {
         let work_dir = extensions_dir.join("work");
         let build_dir = build_dir.unwrap_or_else(|| extensions_dir.join("build"));
         let installed_dir = extensions_dir.join("installed");
+        let previous_dir = extensions_dir.join("previous");
         let index_path = extensions_dir.join("index.json");
 
         let (reload_tx, mut reload_rx) = unbounded();
@@ -241,6 +286,8 @@ impl ExtensionStore {
             proxy: extension_host_proxy.clone(),
             extension_index: Default::default(),
             installed_dir,
+            previous_dir,
+            previous_versions: Default::default(),
             index_path,
             builder: Arc::new(ExtensionBuilder::new(builder_client, build_dir)),
             outstanding_operations: Default::default(),
@@ -263,6 +310,8 @@ impl ExtensionStore {
 
             ssh_clients: HashMap::default(),
             ssh_registered_tx: connection_registered_tx,
+            capabilities_prompt: None,
+            failed_pinned_installs: HashMap::default(),
         };
 
         // The extensions store maintains an index file, which contains a complete
@@ -310,6 +359,9 @@ impl ExtensionStore {
             if let Some(future) = reload_future {
                 future.await;
             }
+            if let Ok(task) = this.update(&mut cx, |this, cx| this.load_previous_versions(cx)) {
+                task.await;
+            }
             this.update(&mut cx, |this, cx| this.auto_install_extensions(cx))
                 .ok();
             this.update(&mut cx, |this, cx| this.check_for_updates(cx))
@@ -392,6 +444,35 @@ impl ExtensionStore {
             }
         }));
 
+        // Reload the extensions whose granted capabilities are changed in the
+        // settings, as capabilities are applied when an extension is loaded.
+        let mut extension_capabilities = ExtensionSettings::get_global(cx)
+            .extension_capabilities
+            .clone();
+        cx.observe_global::<SettingsStore>(move |this, cx| {
+            let new_capabilities = &ExtensionSettings::get_global(cx).extension_capabilities;
+            if *new_capabilities == extension_capabilities {
+                return;
+            }
+
+            let changed_extensions = new_capabilities
+                .iter()
+                .filter(|(id, capabilities)| extension_capabilities.get(*id) != Some(capabilities))
+                .map(|(id, _)| id.clone())
+                .chain(
+                    extension_capabilities
+                        .keys()
+                        .filter(|id| !new_capabilities.contains_key(*id))
+                        .cloned(),
+                )
+                .collect::<Vec<_>>();
+            extension_capabilities = new_capabilities.clone();
+            for extension_id in changed_extensions {
+                this.reload_tx.unbounded_send(Some(extension_id)).ok();
+            }
+        })
+        .detach();
+
         this
     }
 
@@ -420,6 +501,42 @@ impl ExtensionStore {
         &self.outstanding_operations
     }
 
+    /// Returns the version of the given extension that was installed before its
+    /// last upgrade, if it is still kept on disk.
+    pub fn previous_version(&self, extension_id: &str) -> Option<&Arc<str>> {
+        self.previous_versions.get(extension_id)
+    }
+
+    fn load_previous_versions(&mut self, cx: &mut Context<Self>) -> Task<()> {
+        let fs = self.fs.clone();
+        let previous_dir = self.previous_dir.clone();
+        cx.spawn(|this, mut cx| async move {
+            let mut previous_versions = BTreeMap::default();
+            if let Ok(mut extension_dirs) = fs.read_dir(&previous_dir).await {
+                while let Some(Ok(extension_dir)) = extension_dirs.next().await {
+                    if extension_dir
+                        .extension()
+                        .map_or(false, |ext| ext == "rollback")
+                    {
+                        continue;
+                    }
+                    if let Some(manifest) = ExtensionManifest::load(fs.clone(), &extension_dir)
+                        .await
+                        .log_err()
+                    {
+                        previous_versions.insert(manifest.id, manifest.version);
+                    }
+                }
+            }
+
+            this.update(&mut cx, |this, cx| {
+                this.previous_versions = previous_versions;
+                cx.notify();
+            })
+            .ok();
+        })
+    }
+
     pub fn installed_extensions(&self) -> &BTreeMap<Arc<str>, ExtensionIndexEntry> {
         &self.extension_index.extensions
     }
@@ -500,18 +617,48 @@ impl ExtensionStore {
             query.push(("filter", search));
         }
 
-        let provides_filter = provides_filter.map(|provides_filter| {
+        let provides_query = provides_filter.map(|provides_filter| {
             provides_filter
                 .iter()
                 .map(|provides| provides.to_string())
                 .collect::<Vec<_>>()
                 .join(",")
         });
-        if let Some(provides_filter) = provides_filter.as_deref() {
-            query.push(("provides", provides_filter));
+        if let Some(provides_query) = provides_query.as_deref() {
+            query.push(("provides", provides_query));
         }
 
-        self.fetch_extensions_from_api("/extensions", &query, cx)
+        match self.extension_registry(cx) {
+            Ok(None) => self.fetch_extensions_from_api("/extensions", &query, cx),
+            Ok(Some(registry)) => {
+                let search = search.map(|search| search.to_lowercase());
+                let provides_filter = provides_filter.cloned();
+                self.fetch_extensions_from_registry(
+                    registry,
+                    true,
+                    move |extension| {
+                        let manifest = &extension.manifest;
+                        let matches_search = search.as_ref().map_or(true, |search| {
+                            extension.id.to_lowercase().contains(search)
+                                || manifest.name.to_lowercase().contains(search)
+                                || manifest.description.as_ref().map_or(false, |description| {
+                                    description.to_lowercase().contains(search)
+                                })
+                        });
+                        let matches_provides = provides_filter.as_ref().map_or(true, |filter| {
+                            filter
+                                .iter()
+                                .any(|provides| manifest.provides.contains(provides))
+                        });
+                        manifest.schema_version.unwrap_or(0) <= CURRENT_SCHEMA_VERSION.0
+                            && matches_search
+                            && matches_provides
+                    },
+                    cx,
+                )
+            }
+            Err(error) => Task::ready(Err(error)),
+        }
     }
 
     pub fn fetch_extensions_with_update_available(
@@ -526,23 +673,44 @@ impl ExtensionStore {
             .extensions
             .iter()
             .filter(|(id, entry)| !entry.dev && extension_settings.should_auto_update(id))
-            .map(|(id, _)| id.as_ref())
-            .collect::<Vec<_>>()
-            .join(",");
-        let task = self.fetch_extensions_from_api(
-            "/extensions/updates",
-            &[
-                ("min_schema_version", &schema_versions.start().to_string()),
-                ("max_schema_version", &schema_versions.end().to_string()),
-                (
-                    "min_wasm_api_version",
-                    &wasm_api_versions.start().to_string(),
-                ),
-                ("max_wasm_api_version", &wasm_api_versions.end().to_string()),
-                ("ids", &extension_ids),
-            ],
-            cx,
-        );
+            .map(|(id, _)| id.clone())
+            .collect::<HashSet<_>>();
+        let task = match self.extension_registry(cx) {
+            Ok(None) => self.fetch_extensions_from_api(
+                "/extensions/updates",
+                &[
+                    ("min_schema_version", &schema_versions.start().to_string()),
+                    ("max_schema_version", &schema_versions.end().to_string()),
+                    (
+                        "min_wasm_api_version",
+                        &wasm_api_versions.start().to_string(),
+                    ),
+                    ("max_wasm_api_version", &wasm_api_versions.end().to_string()),
+                    (
+                        "ids",
+                        &extension_ids
+                            .iter()
+                            .map(|id| id.as_ref())
+                            .collect::<Vec<_>>()
+                            .join(","),
+                    ),
+                ],
+                cx,
+            ),
+            Ok(Some(registry)) => {
+                let release_channel = ReleaseChannel::global(cx);
+                self.fetch_extensions_from_registry(
+                    registry,
+                    true,
+                    move |extension| {
+                        extension_ids.contains(&extension.id)
+                            && is_version_compatible(release_channel, extension)
+                    },
+                    cx,
+                )
+            }
+            Err(error) => Task::ready(Err(error)),
+        };
         cx.spawn(move |this, mut cx| async move {
             let extensions = task.await?;
             this.update(&mut cx, |this, _cx| {
@@ -566,7 +734,33 @@ impl ExtensionStore {
         extension_id: &str,
         cx: &mut Context<Self>,
     ) -> Task<Result<Vec<ExtensionMetadata>>> {
-        self.fetch_extensions_from_api(&format!("/extensions/{extension_id}"), &[], cx)
+        match self.extension_registry(cx) {
+            Ok(None) => {
+                self.fetch_extensions_from_api(&format!("/extensions/{extension_id}"), &[], cx)
+            }
+            Ok(Some(registry)) => {
+                let extension_id = extension_id.to_string();
+                self.fetch_extensions_from_registry(
+                    registry,
+                    false,
+                    move |extension| extension.id.as_ref() == extension_id,
+                    cx,
+                )
+            }
+            Err(error) => Task::ready(Err(error)),
+        }
+    }
+
+    /// Returns the extension registry configured in the settings, if any.
+    ///
+    /// When no registry is configured, extensions are fetched from the zed.dev extensions API.
+    pub fn extension_registry(&self, cx: &App) -> Result<Option<ExtensionRegistry>> {
+        ExtensionSettings::get_global(cx)
+            .extension_registry
+            .as_deref()
+            .filter(|registry| !registry.is_empty())
+            .map(ExtensionRegistry::from_setting)
+            .transpose()
     }
 
     /// Installs any extensions that should be included with Zed by default.
@@ -602,6 +796,7 @@ impl ExtensionStore {
     }
 
     pub fn check_for_updates(&mut self, cx: &mut Context<Self>) {
+        self.install_pinned_versions(cx).detach();
         let task = self.fetch_extensions_with_update_available(cx);
         cx.spawn(move |this, mut cx| async move {
             Self::upgrade_extensions(this, task.await?, &mut cx).await
@@ -609,6 +804,78 @@ impl ExtensionStore {
         .detach();
     }
 
+    /// Installs the pinned version of each installed extension whose version
+    /// does not match its pin.
+    ///
+    /// Pinned versions that failed to install are retried with an exponential backoff.
+    fn install_pinned_versions(&mut self, cx: &mut Context<Self>) -> Task<()> {
+        let now = cx.background_executor().now();
+        let extension_settings = ExtensionSettings::get_global(cx);
+        let mismatched_extensions = self
+            .extension_index
+            .extensions
+            .iter()
+            .filter(|(_, entry)| !entry.dev)
+            .filter_map(|(id, entry)| {
+                let pinned_version = extension_settings.pinned_version(id)?;
+                (*pinned_version != entry.manifest.version)
+                    .then(|| (id.clone(), pinned_version.clone()))
+            })
+            .filter(|(id, pinned_version)| {
+                self.failed_pinned_installs.get(id).map_or(true, |failed| {
+                    failed.version != *pinned_version || failed.retry_at <= now
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let tasks = mismatched_extensions
+            .into_iter()
+            .map(|(extension_id, pinned_version)| {
+                log::info!(
+                    "installing pinned version {pinned_version} of extension {extension_id}"
+                );
+                let install = self.install_or_upgrade_extension(
+                    extension_id.clone(),
+                    pinned_version.clone(),
+                    ExtensionOperation::Upgrade,
+                    cx,
+                );
+                cx.spawn(|this, mut cx| async move {
+                    let result = install.await;
+                    this.update(&mut cx, |this, cx| {
+                        if result.is_ok() {
+                            this.failed_pinned_installs.remove(&extension_id);
+                            return;
+                        }
+
+                        let failure_count = this
+                            .failed_pinned_installs
+                            .get(&extension_id)
+                            .filter(|failed| failed.version == pinned_version)
+                            .map_or(0, |failed| failed.failure_count)
+                            + 1;
+                        let retry_delay = PINNED_INSTALL_RETRY_DELAY
+                            .saturating_mul(2u32.saturating_pow(failure_count - 1))
+                            .min(MAX_PINNED_INSTALL_RETRY_DELAY);
+                        this.failed_pinned_installs.insert(
+                            extension_id,
+                            FailedPinnedInstall {
+                                version: pinned_version,
+                                failure_count,
+                                retry_at: cx.background_executor().now() + retry_delay,
+                            },
+                        );
+                    })
+                    .ok();
+                    result.log_err();
+                })
+            })
+            .collect::<Vec<_>>();
+        cx.background_executor().spawn(async move {
+            futures::future::join_all(tasks).await;
+        })
+    }
+
     async fn upgrade_extensions(
         this: WeakEntity<Self>,
         extensions: Vec<ExtensionMetadata>,
@@ -672,6 +939,79 @@ impl ExtensionStore {
         })
     }
 
+    fn fetch_extensions_from_registry(
+        &self,
+        registry: ExtensionRegistry,
+        latest_only: bool,
+        filter: impl Fn(&ExtensionMetadata) -> bool + 'static,
+        cx: &mut Context<'_, ExtensionStore>,
+    ) -> Task<Result<Vec<ExtensionMetadata>>> {
+        let fs = self.fs.clone();
+        let http_client = self.http_client.clone();
+        cx.spawn(move |_, _| async move {
+            let index = registry
+                .load_index(fs.as_ref(), http_client.as_ref())
+                .await?;
+            let entries = index
+                .extensions
+                .iter()
+                .filter(|entry| filter(&entry.metadata));
+            let mut extensions = if latest_only {
+                extension_registry::latest_versions(entries)
+                    .into_iter()
+                    .map(|entry| entry.metadata.clone())
+                    .collect::<Vec<_>>()
+            } else {
+                entries.map(|entry| entry.metadata.clone()).collect()
+            };
+            extensions.sort_by(|a, b| {
+                a.manifest
+                    .name
+                    .cmp(&b.manifest.name)
+                    .then_with(|| b.published_at.cmp(&a.published_at))
+            });
+            Ok(extensions)
+        })
+    }
+
+    /// Sets the prompt used to confirm the capabilities of extensions before
+    /// they're installed, or upgraded to a version with other capabilities.
+    ///
+    /// Without one, extensions are installed without confirmation.
+    pub fn set_capabilities_prompt(&mut self, prompt: CapabilitiesPrompt) {
+        self.capabilities_prompt = Some(prompt);
+    }
+
+    /// Returns whether the extension with the given manifest may be installed,
+    /// prompting the user if it runs code with capabilities that they haven't
+    /// seen yet.
+    fn confirm_capabilities(
+        &self,
+        manifest: Arc<ExtensionManifest>,
+        cx: &mut App,
+    ) -> Task<Result<bool>> {
+        let Some(prompt) = self.capabilities_prompt.clone() else {
+            return Task::ready(Ok(true));
+        };
+        if manifest.lib.kind.is_none() {
+            return Task::ready(Ok(true));
+        }
+
+        let extension_settings = ExtensionSettings::get_global(cx);
+        let capabilities = extension_settings.granted_capabilities(&manifest);
+        let installed_capabilities = self
+            .extension_index
+            .extensions
+            .get(&manifest.id)
+            .filter(|entry| entry.manifest.lib.kind.is_some())
+            .map(|entry| extension_settings.granted_capabilities(&entry.manifest));
+        if installed_capabilities.as_ref() == Some(&capabilities) {
+            return Task::ready(Ok(true));
+        }
+
+        prompt(manifest, capabilities, cx)
+    }
+
     pub fn install_extension(
         &mut self,
         extension_id: Arc<str>,
@@ -689,8 +1029,99 @@ impl ExtensionStore {
         operation: ExtensionOperation,
         cx: &mut Context<Self>,
     ) -> Task<Result<()>> {
-        let extension_dir = self.installed_dir.join(extension_id.as_ref());
         let http_client = self.http_client.clone();
+        let archive = async move {
+            let mut response = http_client
+                .get(url.as_ref(), Default::default(), true)
+                .await
+                .map_err(|err| anyhow!("error downloading extension: {}", err))?;
+
+            let content_length = response
+                .headers()
+                .get(http_client::http::header::CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok()?.parse::<usize>().ok());
+
+            let mut body = BufReader::new(response.body_mut());
+            let mut tar_gz_bytes = Vec::new();
+            body.read_to_end(&mut tar_gz_bytes).await?;
+
+            if let Some(content_length) = content_length {
+                let actual_len = tar_gz_bytes.len();
+                if content_length != actual_len {
+                    bail!("downloaded extension size {actual_len} does not match content length {content_length}");
+                }
+            }
+            Ok(tar_gz_bytes)
+        };
+
+        self.install_or_upgrade_extension_from_archive(extension_id, archive, operation, cx)
+    }
+
+    fn install_or_upgrade_extension_from_registry(
+        &mut self,
+        extension_id: Arc<str>,
+        registry: ExtensionRegistry,
+        select_entry: impl Fn(&[&ExtensionRegistryEntry]) -> Option<ExtensionRegistryEntry> + 'static,
+        operation: ExtensionOperation,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        let fs = self.fs.clone();
+        let http_client = self.http_client.clone();
+        let archive = {
+            let extension_id = extension_id.clone();
+            async move {
+                let index = registry
+                    .load_index(fs.as_ref(), http_client.as_ref())
+                    .await?;
+                let versions = index
+                    .extensions
+                    .iter()
+                    .filter(|entry| entry.metadata.id == extension_id)
+                    .collect::<Vec<_>>();
+                let entry = select_entry(&versions).ok_or_else(|| {
+                    anyhow!("no compatible version of extension {extension_id} in registry")
+                })?;
+                let archive = registry
+                    .load_archive(&entry, fs.as_ref(), http_client.as_ref())
+                    .await?;
+                let manifest = extension_registry::manifest_from_archive(&archive).await?;
+                if manifest.id != entry.metadata.id
+                    || manifest.version != entry.metadata.manifest.version
+                {
+                    bail!(
+                        "archive for extension {} {} contains extension {:?} {}",
+                        entry.metadata.id,
+                        entry.metadata.manifest.version,
+                        manifest.id,
+                        manifest.version
+                    );
+                }
+                Ok(archive)
+            }
+        };
+
+        self.install_or_upgrade_extension_from_archive(extension_id, archive, operation, cx)
+    }
+
+    fn install_or_upgrade_extension_from_archive(
+        &mut self,
+        extension_id: Arc<str>,
+        archive: impl Future<Output = Result<Vec<u8>>> + 'static,
+        operation: ExtensionOperation,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        if let Err(error) = validate_extension_id(&extension_id) {
+            return Task::ready(Err(error));
+        }
+        let extension_dir = self.installed_dir.join(extension_id.as_ref());
+        let previous_dir = self.previous_dir.clone();
+        let previous_extension_dir = previous_dir.join(extension_id.as_ref());
+        let installed_version = self
+            .extension_index
+            .extensions
+            .get(&extension_id)
+            .filter(|entry| !entry.dev)
+            .map(|entry| entry.manifest.version.clone());
         let fs = self.fs.clone();
 
         match self.outstanding_operations.entry(extension_id.clone()) {
@@ -713,10 +1144,54 @@ impl ExtensionStore {
                 }
             });
 
-            let mut response = http_client
-                .get(url.as_ref(), Default::default(), true)
-                .await
-                .map_err(|err| anyhow!("error downloading extension: {}", err))?;
+            let tar_gz_bytes = archive.await?;
+
+            // Confirm the capabilities of the extension before anything of it is loaded.
+            let manifest =
+                Arc::new(extension_registry::manifest_from_archive(&tar_gz_bytes).await?);
+            if manifest.id != extension_id {
+                bail!(
+                    "extension archive for {extension_id} contains extension {:?}",
+                    manifest.id
+                );
+            }
+            let confirmed = this
+                .update(&mut cx, |this, cx| this.confirm_capabilities(manifest, cx))?
+                .await?;
+            if !confirmed {
+                log::info!("installation of extension {extension_id} was cancelled");
+                return Ok(());
+            }
+
+            // Keep the currently installed version on disk, so that the
+            // extension can be rolled back to it. The index may list an
+            // extension whose directory was removed outside of Zed.
+            let installed_version = if fs.is_dir(&extension_dir).await {
+                installed_version
+            } else {
+                None
+            };
+            if let Some(installed_version) = installed_version {
+                fs.remove_dir(
+                    &previous_extension_dir,
+                    RemoveOptions {
+                        recursive: true,
+                        ignore_if_not_exists: true,
+                    },
+                )
+                .await?;
+                fs.create_dir(&previous_dir).await?;
+                fs.rename(
+                    &extension_dir,
+                    &previous_extension_dir,
+                    RenameOptions::default(),
+                )
+                .await?;
+                this.update(&mut cx, |this, _| {
+                    this.previous_versions
+                        .insert(extension_id.clone(), installed_version);
+                })?;
+            }
 
             fs.remove_dir(
                 &extension_dir,
@@ -727,21 +1202,6 @@ impl ExtensionStore {
             )
             .await?;
 
-            let content_length = response
-                .headers()
-                .get(http_client::http::header::CONTENT_LENGTH)
-                .and_then(|value| value.to_str().ok()?.parse::<usize>().ok());
-
-            let mut body = BufReader::new(response.body_mut());
-            let mut tar_gz_bytes = Vec::new();
-            body.read_to_end(&mut tar_gz_bytes).await?;
-
-            if let Some(content_length) = content_length {
-                let actual_len = tar_gz_bytes.len();
-                if content_length != actual_len {
-                    bail!("downloaded extension size {actual_len} does not match content length {content_length}");
-                }
-            }
             let decompressed_bytes = GzipDecoder::new(BufReader::new(tar_gz_bytes.as_slice()));
             let archive = Archive::new(decompressed_bytes);
             archive.unpack(extension_dir).await?;
@@ -761,9 +1221,111 @@ impl ExtensionStore {
         })
     }
 
+    /// Installs an extension from a local `.tar.gz` archive, such as one produced by `zed-extension`.
+    ///
+    /// If a `<archive>.sha256` file exists next to the archive, the archive's checksum is verified
+    /// against it. The extension's declared schema and Wasm API versions must be supported by this
+    /// version of Zed.
+    pub fn install_extension_from_archive(
+        &mut self,
+        archive_path: PathBuf,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        let fs = self.fs.clone();
+        let release_channel = ReleaseChannel::global(cx);
+        cx.spawn(move |this, mut cx| async move {
+            let archive = fs
+                .load_bytes(&archive_path)
+                .await
+                .with_context(|| format!("failed to read {}", archive_path.display()))?;
+
+            let mut checksum_path = archive_path.clone().into_os_string();
+            checksum_path.push(".sha256");
+            let checksum_path = PathBuf::from(checksum_path);
+            if fs.is_file(&checksum_path).await {
+                let checksum = fs.load(&checksum_path).await?;
+                let expected_sha256 = checksum.split_whitespace().next().unwrap_or_default();
+                extension_registry::verify_archive_checksum(&archive, expected_sha256)?;
+            }
+
+            let manifest = extension_registry::manifest_from_archive(&archive).await?;
+            validate_extension_id(&manifest.id)?;
+            if !schema_version_range().contains(&manifest.schema_version) {
+                bail!(
+                    "extension {} uses unsupported schema version {}",
+                    manifest.id,
+                    manifest.schema_version
+                );
+            }
+            if manifest.lib.kind.is_some() {
+                let wasm_api_version = manifest.lib.version.ok_or_else(|| {
+                    anyhow!(
+                        "extension {} does not declare a Wasm API version",
+                        manifest.id
+                    )
+                })?;
+                if !is_supported_wasm_api_version(release_channel, wasm_api_version) {
+                    bail!(
+                        "extension {} uses unsupported Wasm API version {wasm_api_version}",
+                        manifest.id
+                    );
+                }
+            }
+
+            log::info!(
+                "installing extension {} {} from {}",
+                manifest.id,
+                manifest.version,
+                archive_path.display()
+            );
+            this.update(&mut cx, |this, cx| {
+                let operation = if this.extension_index.extensions.contains_key(&manifest.id) {
+                    ExtensionOperation::Upgrade
+                } else {
+                    ExtensionOperation::Install
+                };
+                this.install_or_upgrade_extension_from_archive(
+                    manifest.id.clone(),
+                    async move { Ok(archive) },
+                    operation,
+                    cx,
+                )
+            })?
+            .await
+        })
+    }
+
     pub fn install_latest_extension(&mut self, extension_id: Arc<str>, cx: &mut Context<Self>) {
         log::info!("installing extension {extension_id} latest version");
 
+        let registry = match self.extension_registry(cx) {
+            Ok(registry) => registry,
+            Err(error) => {
+                log::error!("{error:?}");
+                return;
+            }
+        };
+        if let Some(registry) = registry {
+            let release_channel = ReleaseChannel::global(cx);
+            self.install_or_upgrade_extension_from_registry(
+                extension_id,
+                registry,
+                move |versions| {
+                    let compatible_versions = versions
+                        .iter()
+                        .copied()
+                        .filter(|entry| is_version_compatible(release_channel, &entry.metadata));
+                    extension_registry::latest_versions(compatible_versions)
+                        .first()
+                        .map(|entry| (*entry).clone())
+                },
+                ExtensionOperation::Install,
+                cx,
+            )
+            .detach_and_log_err(cx);
+            return;
+        }
+
         let schema_versions = schema_version_range();
         let wasm_api_versions = wasm_api_version_range(ReleaseChannel::global(cx));
 
@@ -812,6 +1374,25 @@ impl ExtensionStore {
         cx: &mut Context<Self>,
     ) -> Task<Result<()>> {
         log::info!("installing extension {extension_id} {version}");
+        match self.extension_registry(cx) {
+            Ok(None) => {}
+            Ok(Some(registry)) => {
+                return self.install_or_upgrade_extension_from_registry(
+                    extension_id,
+                    registry,
+                    move |versions| {
+                        versions
+                            .iter()
+                            .find(|entry| entry.metadata.manifest.version == version)
+                            .map(|entry| (*entry).clone())
+                    },
+                    operation,
+                    cx,
+                );
+            }
+            Err(error) => return Task::ready(Err(error)),
+        }
+
         let Some(url) = self
             .http_client
             .build_zed_api_url(
@@ -826,8 +1407,106 @@ impl ExtensionStore {
         self.install_or_upgrade_extension_at_endpoint(extension_id, url, operation, cx)
     }
 
+    /// Rolls the given extension back to the version that was installed before
+    /// its last upgrade. The version being replaced is kept on disk in its place.
+    pub fn rollback_extension(
+        &mut self,
+        extension_id: Arc<str>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        let Some(previous_version) = self.previous_versions.get(&extension_id).cloned() else {
+            return Task::ready(Err(anyhow!(
+                "no previous version of extension {extension_id} is installed"
+            )));
+        };
+        let installed_version = self
+            .extension_index
+            .extensions
+            .get(&extension_id)
+            .map(|entry| entry.manifest.version.clone());
+        let extension_dir = self.installed_dir.join(extension_id.as_ref());
+        let previous_extension_dir = self.previous_dir.join(extension_id.as_ref());
+        let swap_dir = self.previous_dir.join(format!("{extension_id}.rollback"));
+        let fs = self.fs.clone();
+
+        match self.outstanding_operations.entry(extension_id.clone()) {
+            btree_map::Entry::Occupied(_) => return Task::ready(Ok(())),
+            btree_map::Entry::Vacant(e) => e.insert(ExtensionOperation::Upgrade),
+        };
+        cx.notify();
+
+        log::info!("rolling back extension {extension_id} to {previous_version}");
+        cx.spawn(move |this, mut cx| async move {
+            let _finish = util::defer({
+                let this = this.clone();
+                let mut cx = cx.clone();
+                let extension_id = extension_id.clone();
+                move || {
+                    this.update(&mut cx, |this, cx| {
+                        this.outstanding_operations.remove(extension_id.as_ref());
+                        cx.notify();
+                    })
+                    .ok();
+                }
+            });
+
+            if !fs.is_dir(&previous_extension_dir).await {
+                this.update(&mut cx, |this, cx| {
+                    this.previous_versions.remove(&extension_id);
+                    cx.notify();
+                })?;
+                bail!("no previous version of extension {extension_id} is installed");
+            }
+            let installed_version = if fs.is_dir(&extension_dir).await {
+                installed_version
+            } else {
+                None
+            };
+
+            fs.remove_dir(
+                &swap_dir,
+                RemoveOptions {
+                    recursive: true,
+                    ignore_if_not_exists: true,
+                },
+            )
+            .await?;
+            if installed_version.is_some() {
+                fs.rename(&extension_dir, &swap_dir, RenameOptions::default())
+                    .await?;
+            }
+            fs.rename(
+                &previous_extension_dir,
+                &extension_dir,
+                RenameOptions::default(),
+            )
+            .await?;
+            if installed_version.is_some() {
+                fs.rename(&swap_dir, &previous_extension_dir, RenameOptions::default())
+                    .await?;
+            }
+
+            this.update(&mut cx, |this, cx| {
+                match installed_version {
+                    Some(installed_version) => {
+                        this.previous_versions
+                            .insert(extension_id.clone(), installed_version);
+                    }
+                    None => {
+                        this.previous_versions.remove(&extension_id);
+                    }
+                }
+                this.reload(Some(extension_id.clone()), cx)
+            })?
+            .await;
+
+            anyhow::Ok(())
+        })
+    }
+
     pub fn uninstall_extension(&mut self, extension_id: Arc<str>, cx: &mut Context<Self>) {
         let extension_dir = self.installed_dir.join(extension_id.as_ref());
+        let previous_extension_dir = self.previous_dir.join(extension_id.as_ref());
         let work_dir = self.wasm_host.work_dir.join(extension_id.as_ref());
         let fs = self.fs.clone();
 
@@ -868,8 +1547,20 @@ impl ExtensionStore {
             )
             .await?;
 
-            this.update(&mut cx, |this, cx| this.reload(None, cx))?
-                .await;
+            fs.remove_dir(
+                &previous_extension_dir,
+                RemoveOptions {
+                    recursive: true,
+                    ignore_if_not_exists: true,
+                },
+            )
+            .await?;
+
+            this.update(&mut cx, |this, cx| {
+                this.previous_versions.remove(&extension_id);
+                this.reload(None, cx)
+            })?
+            .await;
             anyhow::Ok(())
         })
         .detach_and_log_err(cx)
@@ -1115,6 +1806,26 @@ impl ExtensionStore {
                         .remove_language_server(&language, language_server_name);
                 }
             }
+            for command_name in extension.manifest.editor_commands.keys() {
+                self.proxy.remove_editor_command(extension_id, command_name);
+            }
+            for task_provider_id in extension.manifest.task_providers.keys() {
+                self.proxy.remove_task_provider(task_provider_id, cx);
+            }
+            for (formatter_id, formatter) in &extension.manifest.formatters {
+                for language in &formatter.languages {
+                    self.proxy.remove_formatter(language, formatter_id);
+                }
+            }
+            for (linter_id, linter) in &extension.manifest.linters {
+                for language in &linter.languages {
+                    self.proxy.remove_linter(language, linter_id);
+                }
+            }
+            for provider_id in extension.manifest.language_model_providers.keys() {
+                self.proxy
+                    .remove_language_model_provider(extension_id, provider_id, cx);
+            }
         }
 
         self.wasm_extensions
@@ -1253,9 +1964,15 @@ impl ExtensionStore {
                 };
 
                 let extension_path = root_dir.join(extension.manifest.id.as_ref());
+                let capabilities = cx
+                    .update(|cx| {
+                        ExtensionSettings::get_global(cx).granted_capabilities(&extension.manifest)
+                    })
+                    .unwrap_or_else(|_| extension.manifest.capabilities());
                 let wasm_extension = WasmExtension::load(
                     extension_path,
                     &extension.manifest,
+                    capabilities,
                     wasm_host.clone(),
                     &cx,
                 )
@@ -1311,6 +2028,52 @@ impl ExtensionStore {
                         this.proxy
                             .register_indexed_docs_provider(extension.clone(), provider_id.clone());
                     }
+
+                    for (command_name, command) in &manifest.editor_commands {
+                        this.proxy.register_editor_command(
+                            extension.clone(),
+                            extension::EditorCommand {
+                                name: command_name.to_string(),
+                                description: command.description.clone(),
+                            },
+                        );
+                    }
+
+                    for (task_provider_id, _task_provider) in &manifest.task_providers {
+                        this.proxy.register_task_provider(
+                            extension.clone(),
+                            task_provider_id.clone(),
+                            cx,
+                        );
+                    }
+
+                    for (formatter_id, formatter) in &manifest.formatters {
+                        for language in &formatter.languages {
+                            this.proxy.register_formatter(
+                                extension.clone(),
+                                formatter_id.clone(),
+                                language.clone(),
+                            );
+                        }
+                    }
+
+                    for (linter_id, linter) in &manifest.linters {
+                        for language in &linter.languages {
+                            this.proxy.register_linter(
+                                extension.clone(),
+                                linter_id.clone(),
+                                language.clone(),
+                            );
+                        }
+                    }
+
+                    for provider_id in manifest.language_model_providers.keys() {
+                        this.proxy.register_language_model_provider(
+                            extension.clone(),
+                            provider_id.clone(),
+                            cx,
+                        );
+                    }
                 }
 
                 this.wasm_extensions.extend(wasm_extensions);
@@ -1573,7 +2336,8 @@ impl ExtensionStore {
         client: WeakEntity<SshRemoteClient>,
         cx: &mut AsyncApp,
     ) -> Result<()> {
-        let extensions = this.update(cx, |this, _cx| {
+        let extensions = this.update(cx, |this, cx| {
+            let extension_settings = ExtensionSettings::get_global(cx);
             this.extension_index
                 .extensions
                 .iter()
@@ -1581,10 +2345,12 @@ impl ExtensionStore {
                     if entry.manifest.language_servers.is_empty() {
                         return None;
                     }
+                    let capabilities = extension_settings.granted_capabilities(&entry.manifest);
                     Some(proto::Extension {
                         id: id.to_string(),
                         version: entry.manifest.version.to_string(),
                         dev: entry.dev,
+                        capabilities: Some(headless_host::capabilities_to_proto(capabilities)),
                     })
                 })
                 .collect()
@@ -0,0 +1,200 @@
+use anyhow::{anyhow, bail, Context as _, Result};
+use async_compression::futures::bufread::GzipDecoder;
+use async_tar::Archive;
+use client::{
+    extension_archive_checksum, ExtensionMetadata, ExtensionRegistryEntry, ExtensionRegistryIndex,
+};
+use collections::BTreeMap;
+use extension::ExtensionManifest;
+use fs::Fs;
+use futures::{io::BufReader, AsyncReadExt as _, StreamExt as _};
+use http_client::{AsyncBody, HttpClient};
+use semantic_version::SemanticVersion;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use url::Url;
+
+/// An extension registry that is used in place of the zed.dev extensions API.
+///
+/// Registries use the file-system format produced by `zed-extension --registry-dir`:
+/// an `index.json` file listing every published extension version, alongside
+/// the `.tar.gz` archives it refers to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtensionRegistry {
+    /// A registry directory on the local file system.
+    Directory(PathBuf),
+    /// A registry directory served over HTTP, such as an internal mirror.
+    Mirror(Url),
+}
+
+impl ExtensionRegistry {
+    /// Parses the value of the `extension_registry` setting.
+    ///
+    /// HTTP(S) URLs are treated as mirrors, while `file://` URLs and plain
+    /// paths refer to a registry directory on the local file system.
+    pub fn from_setting(value: &str) -> Result<Self> {
+        if value.starts_with("http://") || value.starts_with("https://") {
+            let mut url = Url::parse(value)
+                .with_context(|| format!("invalid extension registry URL {value:?}"))?;
+            if !url.path().ends_with('/') {
+                url.set_path(&format!("{}/", url.path()));
+            }
+            Ok(Self::Mirror(url))
+        } else if value.starts_with("file://") {
+            let url = Url::parse(value)
+                .with_context(|| format!("invalid extension registry URL {value:?}"))?;
+            let path = url
+                .to_file_path()
+                .map_err(|_| anyhow!("invalid extension registry path {value:?}"))?;
+            Ok(Self::Directory(path))
+        } else if let Some(rest) = value.strip_prefix('~') {
+            Ok(Self::Directory(
+                util::paths::home_dir().join(rest.trim_start_matches('/')),
+            ))
+        } else {
+            Ok(Self::Directory(PathBuf::from(value)))
+        }
+    }
+
+    pub async fn load_index(
+        &self,
+        fs: &dyn Fs,
+        http_client: &dyn HttpClient,
+    ) -> Result<ExtensionRegistryIndex> {
+        let bytes = self
+            .load_file(ExtensionRegistryIndex::FILE_NAME, fs, http_client)
+            .await
+            .context("error loading extension registry index")?;
+        serde_json::from_slice(&bytes).context("invalid extension registry index")
+    }
+
+    /// Loads the archive for the given registry entry, verifying its checksum.
+    pub async fn load_archive(
+        &self,
+        entry: &ExtensionRegistryEntry,
+        fs: &dyn Fs,
+        http_client: &dyn HttpClient,
+    ) -> Result<Vec<u8>> {
+        let archive = self
+            .load_file(&entry.archive, fs, http_client)
+            .await
+            .with_context(|| {
+                format!(
+                    "error loading archive for extension {} {}",
+                    entry.metadata.id, entry.metadata.manifest.version
+                )
+            })?;
+        verify_archive_checksum(&archive, &entry.sha256)?;
+        Ok(archive)
+    }
+
+    async fn load_file(
+        &self,
+        relative_path: &str,
+        fs: &dyn Fs,
+        http_client: &dyn HttpClient,
+    ) -> Result<Vec<u8>> {
+        let is_relative = Path::new(relative_path)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        if !is_relative {
+            bail!("path {relative_path:?} is outside of the extension registry");
+        }
+
+        match self {
+            Self::Directory(root) => fs.load_bytes(&root.join(relative_path)).await,
+            Self::Mirror(root) => {
+                let url = root.join(relative_path)?;
+                let mut response = http_client
+                    .get(url.as_str(), AsyncBody::empty(), true)
+                    .await?;
+
+                let mut body = Vec::new();
+                response.body_mut().read_to_end(&mut body).await?;
+                if !response.status().is_success() {
+                    bail!("status error {} fetching {url}", response.status().as_u16());
+                }
+                Ok(body)
+            }
+        }
+    }
+}
+
+/// Returns an error if the checksum of the archive does not match the expected one.
+pub fn verify_archive_checksum(archive: &[u8], expected_sha256: &str) -> Result<()> {
+    let actual_sha256 = extension_archive_checksum(archive);
+    if !actual_sha256.eq_ignore_ascii_case(expected_sha256.trim()) {
+        bail!("extension archive checksum {actual_sha256} does not match expected checksum {expected_sha256}");
+    }
+    Ok(())
+}
+
+/// Returns the newest version of each extension in the given registry entries.
+pub fn latest_versions<'a>(
+    entries: impl IntoIterator<Item = &'a ExtensionRegistryEntry>,
+) -> Vec<&'a ExtensionRegistryEntry> {
+    let mut latest = BTreeMap::<Arc<str>, &ExtensionRegistryEntry>::default();
+    for entry in entries {
+        let is_newer = latest.get(&entry.metadata.id).map_or(true, |existing| {
+            parse_version(&entry.metadata) > parse_version(&existing.metadata)
+        });
+        if is_newer {
+            latest.insert(entry.metadata.id.clone(), entry);
+        }
+    }
+    latest.into_values().collect()
+}
+
+fn parse_version(metadata: &ExtensionMetadata) -> Option<SemanticVersion> {
+    SemanticVersion::from_str(&metadata.manifest.version).ok()
+}
+
+/// Reads the `extension.toml` manifest from a packaged extension archive.
+pub async fn manifest_from_archive(archive: &[u8]) -> Result<ExtensionManifest> {
+    let decompressed_bytes = GzipDecoder::new(BufReader::new(archive));
+    let archive = Archive::new(decompressed_bytes);
+    let mut entries = archive.entries()?;
+    while let Some(entry) = entries.next().await {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        if path.strip_prefix(".").unwrap_or(&path) == Path::new("extension.toml") {
+            let mut manifest_content = String::new();
+            entry.read_to_string(&mut manifest_content).await?;
+            return toml::from_str(&manifest_content)
+                .context("invalid extension.toml in extension archive");
+        }
+    }
+
+    bail!("extension archive does not contain an extension.toml")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_from_setting() {
+        assert_eq!(
+            ExtensionRegistry::from_setting("https://example.com/zed").unwrap(),
+            ExtensionRegistry::Mirror(Url::parse("https://example.com/zed/").unwrap())
+        );
+        assert_eq!(
+            ExtensionRegistry::from_setting("/mnt/extensions").unwrap(),
+            ExtensionRegistry::Directory(PathBuf::from("/mnt/extensions"))
+        );
+        assert_eq!(
+            ExtensionRegistry::from_setting("~/extensions").unwrap(),
+            ExtensionRegistry::Directory(util::paths::home_dir().join("extensions"))
+        );
+    }
+
+    #[test]
+    fn test_verify_archive_checksum() {
+        let archive = b"archive contents";
+        let checksum = extension_archive_checksum(archive);
+        assert!(verify_archive_checksum(archive, &checksum).is_ok());
+        assert!(verify_archive_checksum(archive, &checksum.to_uppercase()).is_ok());
+        assert!(verify_archive_checksum(b"tampered contents", &checksum).is_err());
+    }
+}
@@ -1,5 +1,6 @@
 use anyhow::Result;
 use collections::HashMap;
+use extension::{ExtensionCapabilities, ExtensionManifest};
 use gpui::App;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -14,8 +15,27 @@ pub struct ExtensionSettings {
     /// available out-of-the-box.
     #[serde(default)]
     pub auto_install_extensions: HashMap<Arc<str>, bool>,
+    /// Whether the given extensions should be automatically updated.
+    ///
+    /// Extensions that are not listed are updated automatically.
     #[serde(default)]
     pub auto_update_extensions: HashMap<Arc<str>, bool>,
+    /// The versions that extensions are pinned to, keyed by extension ID.
+    ///
+    /// Pinned extensions are kept at their pinned version and are never automatically updated.
+    #[serde(default)]
+    pub pinned_extensions: HashMap<Arc<str>, Arc<str>>,
+    /// The capabilities granted to extensions, keyed by extension ID.
+    ///
+    /// These replace the capabilities that the extensions declare in their manifests.
+    #[serde(default)]
+    pub extension_capabilities: HashMap<Arc<str>, ExtensionCapabilities>,
+    /// The extension registry to use in place of the zed.dev extensions API.
+    ///
+    /// This can be an HTTP(S) URL of a registry mirror, or a path to a registry
+    /// directory on the local file system.
+    #[serde(default)]
+    pub extension_registry: Option<String>,
 }
 
 impl ExtensionSettings {
@@ -27,12 +47,30 @@ impl ExtensionSettings {
             .unwrap_or(true)
     }
 
+    /// Returns whether the given extension should be auto-updated.
     pub fn should_auto_update(&self, extension_id: &str) -> bool {
+        if self.pinned_extensions.contains_key(extension_id) {
+            return false;
+        }
+
         self.auto_update_extensions
             .get(extension_id)
             .copied()
             .unwrap_or(true)
     }
+
+    /// Returns the version the given extension is pinned to, if any.
+    pub fn pinned_version(&self, extension_id: &str) -> Option<&Arc<str>> {
+        self.pinned_extensions.get(extension_id)
+    }
+
+    /// Returns the capabilities granted to the given extension.
+    pub fn granted_capabilities(&self, manifest: &ExtensionManifest) -> ExtensionCapabilities {
+        self.extension_capabilities
+            .get(&manifest.id)
+            .cloned()
+            .unwrap_or_else(|| manifest.capabilities())
+    }
 }
 
 impl Settings for ExtensionSettings {
@@ -1,15 +1,17 @@
 use crate::{
     Event, ExtensionIndex, ExtensionIndexEntry, ExtensionIndexLanguageEntry,
-    ExtensionIndexThemeEntry, ExtensionManifest, ExtensionSettings, ExtensionStore,
-    GrammarManifestEntry, SchemaVersion, RELOAD_DEBOUNCE_DURATION,
+    ExtensionIndexThemeEntry, ExtensionManifest, ExtensionOperation, ExtensionSettings,
+    ExtensionStore, GrammarManifestEntry, SchemaVersion, PINNED_INSTALL_RETRY_DELAY,
+    RELOAD_DEBOUNCE_DURATION,
 };
 use async_compression::futures::bufread::GzipEncoder;
+use client::extension_archive_checksum;
 use collections::BTreeMap;
-use extension::ExtensionHostProxy;
-use fs::{FakeFs, Fs, RealFs};
+use extension::{ExtensionCapabilities, ExtensionHostProxy};
+use fs::{FakeFs, Fs, RealFs, RemoveOptions};
 use futures::{io::BufReader, AsyncReadExt, StreamExt};
-use gpui::{AppContext as _, SemanticVersion, TestAppContext};
-use http_client::{FakeHttpClient, Response};
+use gpui::{App, AppContext as _, Entity, SemanticVersion, Task, TestAppContext};
+use http_client::{FakeHttpClient, HttpClientWithUrl, Response};
 use language::{LanguageMatcher, LanguageRegistry, LanguageServerBinaryStatus};
 use lsp::LanguageServerName;
 use node_runtime::NodeRuntime;
@@ -162,7 +164,13 @@ async fn test_extension_store(cx: &mut TestAppContext) {
                         context_servers: BTreeMap::default(),
                         slash_commands: BTreeMap::default(),
                         indexed_docs_providers: BTreeMap::default(),
+                        editor_commands: BTreeMap::default(),
+                        task_providers: BTreeMap::default(),
+                        formatters: BTreeMap::default(),
+                        linters: BTreeMap::default(),
+                        language_model_providers: BTreeMap::default(),
                         snippets: None,
+                        capabilities: None,
                     }),
                     dev: false,
                 },
@@ -190,7 +198,13 @@ async fn test_extension_store(cx: &mut TestAppContext) {
                         context_servers: BTreeMap::default(),
                         slash_commands: BTreeMap::default(),
                         indexed_docs_providers: BTreeMap::default(),
+                        editor_commands: BTreeMap::default(),
+                        task_providers: BTreeMap::default(),
+                        formatters: BTreeMap::default(),
+                        linters: BTreeMap::default(),
+                        language_model_providers: BTreeMap::default(),
                         snippets: None,
+                        capabilities: None,
                     }),
                     dev: false,
                 },
@@ -355,7 +369,13 @@ async fn test_extension_store(cx: &mut TestAppContext) {
                 context_servers: BTreeMap::default(),
                 slash_commands: BTreeMap::default(),
                 indexed_docs_providers: BTreeMap::default(),
+                editor_commands: BTreeMap::default(),
+                task_providers: BTreeMap::default(),
+                formatters: BTreeMap::default(),
+                linters: BTreeMap::default(),
+                language_model_providers: BTreeMap::default(),
                 snippets: None,
+                capabilities: None,
             }),
             dev: false,
         },
@@ -770,6 +790,654 @@ async fn test_extension_store_with_test_extension(cx: &mut TestAppContext) {
 
     // The old language server directory has been cleaned up.
     assert!(fs.metadata(&expected_server_path).await.unwrap().is_none());
+
+    // Revoke the extension's capability to run commands, which reloads it.
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |store, cx| {
+            store.update_user_settings::<ExtensionSettings>(cx, |settings| {
+                settings.extension_capabilities.insert(
+                    test_extension_id.into(),
+                    ExtensionCapabilities {
+                        hosts: vec!["*".into()],
+                        ..Default::default()
+                    },
+                );
+            });
+        });
+    });
+    cx.executor().run_until_parked();
+    project.update(cx, |project, cx| {
+        project.restart_language_servers_for_buffers(vec![buffer.clone()], cx)
+    });
+
+    // The language server's command is refused, so it isn't spawned.
+    loop {
+        let (_, status) = status_updates.next().await.unwrap();
+        if let LanguageServerBinaryStatus::Failed { error } = status {
+            assert!(
+                error.contains("is not allowed to run command"),
+                "unexpected error: {error}"
+            );
+            break;
+        }
+    }
+    cx.executor().run_until_parked();
+    assert!(fake_servers.try_next().is_err());
+}
+
+#[gpui::test]
+async fn test_install_extension_capabilities_prompt(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.executor().allow_parking();
+
+    let dir = TempTree::new(json!({
+        "extensions": {
+            "installed": {},
+            "work": {}
+        },
+        "archives": {}
+    }));
+    let dir = dir.path().canonicalize().unwrap();
+    let extensions_dir = dir.join("extensions");
+    let archive_path = dir.join("archives/my-extension.tar.gz");
+    std::fs::write(
+        &archive_path,
+        extension_archive(&[(
+            "extension.toml",
+            r#"
+            id = "my-extension"
+            name = "My Extension"
+            version = "0.1.0"
+            schema_version = 1
+
+            [lib]
+            kind = "Rust"
+            version = "0.1.0"
+
+            [capabilities]
+            commands = ["cargo"]
+            "#,
+        )])
+        .await,
+    )
+    .unwrap();
+
+    let extension_store = build_extension_store(
+        extensions_dir.clone(),
+        Arc::new(RealFs::default()),
+        FakeHttpClient::with_404_response(),
+        cx,
+    );
+    let _task = advance_clock_on_reload(&extension_store, cx);
+
+    let prompts = Arc::new(Mutex::new(Vec::new()));
+    let confirm = Arc::new(Mutex::new(false));
+    extension_store.update(cx, |store, _| {
+        let prompts = prompts.clone();
+        let confirm = confirm.clone();
+        store.set_capabilities_prompt(Arc::new(
+            move |manifest: Arc<ExtensionManifest>, capabilities, _: &mut App| {
+                prompts.lock().push((manifest.id.clone(), capabilities));
+                Task::ready(Ok(*confirm.lock()))
+            },
+        ));
+    });
+
+    // Declining the prompt leaves the extension uninstalled.
+    extension_store
+        .update(cx, |store, cx| {
+            store.install_extension_from_archive(archive_path.clone(), cx)
+        })
+        .await
+        .unwrap();
+    let expected_capabilities = ExtensionCapabilities {
+        commands: vec!["cargo".into()],
+        ..Default::default()
+    };
+    assert_eq!(
+        prompts.lock().drain(..).collect::<Vec<_>>(),
+        [(Arc::from("my-extension"), expected_capabilities.clone())]
+    );
+    assert!(!extensions_dir.join("installed/my-extension").exists());
+
+    // Accepting it installs the extension.
+    *confirm.lock() = true;
+    extension_store
+        .update(cx, |store, cx| {
+            store.install_extension_from_archive(archive_path.clone(), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        prompts.lock().drain(..).collect::<Vec<_>>(),
+        [(Arc::from("my-extension"), expected_capabilities)]
+    );
+    assert!(extensions_dir
+        .join("installed/my-extension/extension.toml")
+        .exists());
+}
+
+#[gpui::test]
+async fn test_install_extension_from_archive(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.executor().allow_parking();
+
+    let dir = TempTree::new(json!({
+        "extensions": {
+            "installed": {},
+            "work": {}
+        },
+        "archives": {}
+    }));
+    let dir = dir.path().canonicalize().unwrap();
+    let extensions_dir = dir.join("extensions");
+    let extension_store = build_extension_store(
+        extensions_dir.clone(),
+        Arc::new(RealFs::default()),
+        FakeHttpClient::with_404_response(),
+        cx,
+    );
+    let _task = advance_clock_on_reload(&extension_store, cx);
+
+    let archive_path = dir.join("archives/my-theme.tar.gz");
+    std::fs::write(
+        &archive_path,
+        extension_archive(&[("extension.toml", &extension_manifest("my-theme", "0.1.0"))]).await,
+    )
+    .unwrap();
+    extension_store
+        .update(cx, |store, cx| {
+            store.install_extension_from_archive(archive_path, cx)
+        })
+        .await
+        .unwrap();
+    assert!(extensions_dir
+        .join("installed/my-theme/extension.toml")
+        .exists());
+    extension_store.read_with(cx, |store, _| {
+        assert_eq!(
+            &*store.extension_index.extensions["my-theme"]
+                .manifest
+                .version,
+            "0.1.0"
+        );
+    });
+
+    // Archives whose IDs aren't a single path component are rejected before
+    // anything is written.
+    for (name, extension_id) in [
+        ("parent", "../escape"),
+        ("nested", "my-theme/../../escape"),
+        ("absolute", "/tmp/escape"),
+        ("empty", ""),
+    ] {
+        let archive_path = dir.join(format!("archives/{name}.tar.gz"));
+        std::fs::write(
+            &archive_path,
+            extension_archive(&[("extension.toml", &extension_manifest(extension_id, "0.1.0"))])
+                .await,
+        )
+        .unwrap();
+        let error = extension_store
+            .update(cx, |store, cx| {
+                store.install_extension_from_archive(archive_path, cx)
+            })
+            .await
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("invalid extension id"),
+            "unexpected error: {error}"
+        );
+    }
+    assert!(!extensions_dir.join("escape").exists());
+    assert!(!extensions_dir.join("previous").exists());
+}
+
+#[gpui::test]
+async fn test_install_extension_from_registry(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.executor().allow_parking();
+
+    let dir = TempTree::new(json!({
+        "extensions": {
+            "installed": {},
+            "work": {}
+        },
+        "registry": {}
+    }));
+    let dir = dir.path().canonicalize().unwrap();
+    let extensions_dir = dir.join("extensions");
+    let registry_dir = dir.join("registry");
+    write_registry(
+        &registry_dir,
+        [
+            (
+                "my-theme",
+                "0.1.0",
+                extension_archive(&[("extension.toml", &extension_manifest("my-theme", "0.1.0"))])
+                    .await,
+            ),
+            // An archive that doesn't contain the extension of its index entry.
+            (
+                "other-theme",
+                "0.1.0",
+                extension_archive(&[("extension.toml", &extension_manifest("my-theme", "0.1.0"))])
+                    .await,
+            ),
+            (
+                "../escape",
+                "0.1.0",
+                extension_archive(&[("extension.toml", &extension_manifest("../escape", "0.1.0"))])
+                    .await,
+            ),
+        ],
+    );
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |store, cx| {
+            store.update_user_settings::<ExtensionSettings>(cx, |settings| {
+                settings.extension_registry = Some(registry_dir.to_string_lossy().into_owned());
+            });
+        });
+    });
+
+    let extension_store = build_extension_store(
+        extensions_dir.clone(),
+        Arc::new(RealFs::default()),
+        FakeHttpClient::with_404_response(),
+        cx,
+    );
+    let _task = advance_clock_on_reload(&extension_store, cx);
+
+    let error = extension_store
+        .update(cx, |store, cx| {
+            store.install_or_upgrade_extension(
+                "other-theme".into(),
+                "0.1.0".into(),
+                ExtensionOperation::Install,
+                cx,
+            )
+        })
+        .await
+        .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("contains extension \"my-theme\""),
+        "unexpected error: {error}"
+    );
+    assert!(!extensions_dir.join("installed/other-theme").exists());
+    assert!(!extensions_dir.join("installed/my-theme").exists());
+
+    let error = extension_store
+        .update(cx, |store, cx| {
+            store.install_or_upgrade_extension(
+                "../escape".into(),
+                "0.1.0".into(),
+                ExtensionOperation::Install,
+                cx,
+            )
+        })
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("invalid extension id"),
+        "unexpected error: {error}"
+    );
+    assert!(!extensions_dir.join("escape").exists());
+
+    extension_store
+        .update(cx, |store, cx| {
+            store.install_or_upgrade_extension(
+                "my-theme".into(),
+                "0.1.0".into(),
+                ExtensionOperation::Install,
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+    assert!(extensions_dir
+        .join("installed/my-theme/extension.toml")
+        .exists());
+    extension_store.read_with(cx, |store, _| {
+        assert!(store.extension_index.extensions.contains_key("my-theme"));
+    });
+}
+
+#[gpui::test]
+async fn test_extension_rollback(cx: &mut TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/extensions",
+        json!({
+            "installed": {
+                "my-theme": {
+                    "extension.toml": extension_manifest("my-theme", "0.2.0"),
+                },
+            },
+            "previous": {
+                "my-theme": {
+                    "extension.toml": extension_manifest("my-theme", "0.1.0"),
+                },
+            },
+        }),
+    )
+    .await;
+
+    let extension_store = build_extension_store(
+        PathBuf::from("/extensions"),
+        fs.clone(),
+        FakeHttpClient::with_404_response(),
+        cx,
+    );
+    let _task = advance_clock_on_reload(&extension_store, cx);
+    cx.executor().run_until_parked();
+
+    let versions = |cx: &mut TestAppContext| {
+        extension_store.read_with(cx, |store, _| {
+            (
+                store
+                    .installed_extensions()
+                    .get("my-theme")
+                    .map(|entry| entry.manifest.version.to_string()),
+                store.previous_version("my-theme").map(|v| v.to_string()),
+            )
+        })
+    };
+    assert_eq!(versions(cx), (Some("0.2.0".into()), Some("0.1.0".into())));
+
+    // Rolling back swaps the installed and previous versions.
+    extension_store
+        .update(cx, |store, cx| {
+            store.rollback_extension("my-theme".into(), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(versions(cx), (Some("0.1.0".into()), Some("0.2.0".into())));
+    assert!(fs
+        .load("/extensions/previous/my-theme/extension.toml".as_ref())
+        .await
+        .unwrap()
+        .contains("0.2.0"));
+
+    // Rolling back fails without changing the installed version when the
+    // previous version was removed from disk.
+    fs.remove_dir(
+        "/extensions/previous/my-theme".as_ref(),
+        RemoveOptions {
+            recursive: true,
+            ignore_if_not_exists: false,
+        },
+    )
+    .await
+    .unwrap();
+    extension_store
+        .update(cx, |store, cx| {
+            store.rollback_extension("my-theme".into(), cx)
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(versions(cx), (Some("0.1.0".into()), None));
+    assert!(fs.is_dir("/extensions/installed/my-theme".as_ref()).await);
+
+    // Uninstalling the extension removes its previous version too.
+    fs.insert_tree(
+        "/extensions/previous/my-theme",
+        json!({ "extension.toml": extension_manifest("my-theme", "0.2.0") }),
+    )
+    .await;
+    extension_store
+        .update(cx, |store, cx| store.load_previous_versions(cx))
+        .await;
+    assert_eq!(versions(cx), (Some("0.1.0".into()), Some("0.2.0".into())));
+    extension_store.update(cx, |store, cx| {
+        store.uninstall_extension("my-theme".into(), cx)
+    });
+    cx.executor().run_until_parked();
+    assert_eq!(versions(cx), (None, None));
+    assert!(!fs.is_dir("/extensions/installed/my-theme".as_ref()).await);
+    assert!(!fs.is_dir("/extensions/previous/my-theme".as_ref()).await);
+}
+
+#[gpui::test]
+async fn test_install_pinned_versions(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.executor().allow_parking();
+
+    let dir = TempTree::new(json!({
+        "extensions": {
+            "installed": {},
+            "work": {}
+        },
+        "registry": {}
+    }));
+    let dir = dir.path().canonicalize().unwrap();
+    let extensions_dir = dir.join("extensions");
+    let registry_dir = dir.join("registry");
+    let mut entries = Vec::new();
+    for version in ["0.1.0", "0.2.0"] {
+        entries.push((
+            "my-theme",
+            version,
+            extension_archive(&[("extension.toml", &extension_manifest("my-theme", version))])
+                .await,
+        ));
+    }
+    write_registry(&registry_dir, entries);
+
+    let set_pinned_version = |version: &str, cx: &mut TestAppContext| {
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings::<ExtensionSettings>(cx, |settings| {
+                    settings.extension_registry = Some(registry_dir.to_string_lossy().into_owned());
+                    settings
+                        .pinned_extensions
+                        .insert("my-theme".into(), version.into());
+                });
+            });
+        });
+    };
+    set_pinned_version("0.2.0", cx);
+
+    let extension_store = build_extension_store(
+        extensions_dir.clone(),
+        Arc::new(RealFs::default()),
+        FakeHttpClient::with_404_response(),
+        cx,
+    );
+    let _task = advance_clock_on_reload(&extension_store, cx);
+    extension_store
+        .update(cx, |store, cx| {
+            store.install_or_upgrade_extension(
+                "my-theme".into(),
+                "0.2.0".into(),
+                ExtensionOperation::Install,
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+
+    let failure_count = |cx: &mut TestAppContext| {
+        extension_store.read_with(cx, |store, _| {
+            store
+                .failed_pinned_installs
+                .get("my-theme")
+                .map(|failed| failed.failure_count)
+        })
+    };
+
+    // A pinned version that fails to install is only retried after a delay,
+    // which grows with every failure.
+    set_pinned_version("0.3.0", cx);
+    extension_store
+        .update(cx, |store, cx| store.install_pinned_versions(cx))
+        .await;
+    assert_eq!(failure_count(cx), Some(1));
+    extension_store
+        .update(cx, |store, cx| store.install_pinned_versions(cx))
+        .await;
+    assert_eq!(failure_count(cx), Some(1));
+
+    cx.executor().advance_clock(PINNED_INSTALL_RETRY_DELAY);
+    extension_store
+        .update(cx, |store, cx| store.install_pinned_versions(cx))
+        .await;
+    assert_eq!(failure_count(cx), Some(2));
+    cx.executor().advance_clock(PINNED_INSTALL_RETRY_DELAY);
+    extension_store
+        .update(cx, |store, cx| store.install_pinned_versions(cx))
+        .await;
+    assert_eq!(failure_count(cx), Some(2));
+
+    // Pinning another version installs it right away, keeping the version it
+    // replaces around for rolling back.
+    set_pinned_version("0.1.0", cx);
+    extension_store
+        .update(cx, |store, cx| store.install_pinned_versions(cx))
+        .await;
+    assert_eq!(failure_count(cx), None);
+    extension_store.read_with(cx, |store, _| {
+        assert_eq!(
+            &*store.installed_extensions()["my-theme"].manifest.version,
+            "0.1.0"
+        );
+        assert_eq!(
+            store.previous_version("my-theme").map(|v| v.as_ref()),
+            Some("0.2.0")
+        );
+    });
+    let previous_manifest =
+        std::fs::read_to_string(extensions_dir.join("previous/my-theme/extension.toml")).unwrap();
+    assert!(previous_manifest.contains("0.2.0"));
+
+    // Upgrading an extension whose directory was removed outside of Zed
+    // succeeds, and doesn't replace the kept version.
+    std::fs::remove_dir_all(extensions_dir.join("installed/my-theme")).unwrap();
+    extension_store
+        .update(cx, |store, cx| {
+            store.install_or_upgrade_extension(
+                "my-theme".into(),
+                "0.2.0".into(),
+                ExtensionOperation::Upgrade,
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+    assert!(extensions_dir
+        .join("installed/my-theme/extension.toml")
+        .exists());
+    let previous_manifest =
+        std::fs::read_to_string(extensions_dir.join("previous/my-theme/extension.toml")).unwrap();
+    assert!(previous_manifest.contains("0.2.0"));
+}
+
+fn extension_manifest(id: &str, version: &str) -> String {
+    format!(
+        r#"
+        id = "{id}"
+        name = "Test Extension"
+        version = "{version}"
+        schema_version = 1
+        "#
+    )
+}
+
+/// Writes a registry directory containing the given archives, listed under the
+/// given extension IDs and versions.
+fn write_registry<'a>(
+    registry_dir: &Path,
+    entries: impl IntoIterator<Item = (&'a str, &'a str, Vec<u8>)>,
+) {
+    let entries = entries
+        .into_iter()
+        .enumerate()
+        .map(|(ix, (id, version, archive))| {
+            let archive_name = format!("archive-{ix}.tar.gz");
+            std::fs::write(registry_dir.join(&archive_name), &archive).unwrap();
+            json!({
+                "id": id,
+                "name": id,
+                "version": version,
+                "description": null,
+                "authors": [],
+                "repository": "",
+                "schema_version": 1,
+                "wasm_api_version": null,
+                "provides": [],
+                "published_at": "2026-01-01T00:00:00Z",
+                "download_count": 0,
+                "archive": archive_name,
+                "sha256": extension_archive_checksum(&archive),
+            })
+        })
+        .collect::<Vec<_>>();
+    std::fs::write(
+        registry_dir.join("index.json"),
+        json!({ "extensions": entries }).to_string(),
+    )
+    .unwrap();
+}
+
+/// Returns a `.tar.gz` extension archive containing the given files.
+async fn extension_archive(files: &[(&str, &str)]) -> Vec<u8> {
+    let mut bytes = Vec::<u8>::new();
+    let mut archive = async_tar::Builder::new(&mut bytes);
+    for (path, contents) in files {
+        let mut header = async_tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        archive
+            .append_data(&mut header, path, contents.as_bytes())
+            .await
+            .unwrap();
+    }
+    archive.into_inner().await.unwrap();
+
+    let mut gzipped_bytes = Vec::new();
+    let mut encoder = GzipEncoder::new(BufReader::new(bytes.as_slice()));
+    encoder.read_to_end(&mut gzipped_bytes).await.unwrap();
+    gzipped_bytes
+}
+
+fn build_extension_store(
+    extensions_dir: PathBuf,
+    fs: Arc<dyn Fs>,
+    http_client: Arc<HttpClientWithUrl>,
+    cx: &mut TestAppContext,
+) -> Entity<ExtensionStore> {
+    cx.new(|cx| {
+        ExtensionStore::new(
+            extensions_dir,
+            None,
+            Arc::new(ExtensionHostProxy::new()),
+            fs,
+            http_client.clone(),
+            http_client,
+            None,
+            NodeRuntime::unavailable(),
+            cx,
+        )
+    })
+}
+
+/// Ensures that the extension store's reload debounces fire.
+fn advance_clock_on_reload(
+    extension_store: &Entity<ExtensionStore>,
+    cx: &mut TestAppContext,
+) -> Task<()> {
+    let mut events = cx.events(extension_store);
+    let executor = cx.executor();
+    cx.executor().spawn(async move {
+        while let Some(event) = events.next().await {
+            if let Event::StartedReloading = event {
+                executor.advance_clock(RELOAD_DEBOUNCE_DURATION);
+            }
+        }
+    })
 }
 
 fn init_test(cx: &mut TestAppContext) {
@@ -4,8 +4,8 @@ use anyhow::{anyhow, Context as _, Result};
 use client::{proto, TypedEnvelope};
 use collections::{HashMap, HashSet};
 use extension::{
-    Extension, ExtensionHostProxy, ExtensionLanguageProxy, ExtensionLanguageServerProxy,
-    ExtensionManifest,
+    Extension, ExtensionCapabilities, ExtensionHostProxy, ExtensionLanguageProxy,
+    ExtensionLanguageServerProxy, ExtensionManifest,
 };
 use fs::{Fs, RemoveOptions, RenameOptions};
 use gpui::{App, AppContext as _, AsyncApp, Context, Entity, Task, WeakEntity};
@@ -21,6 +21,47 @@ pub struct ExtensionVersion {
     pub id: String,
     pub version: String,
     pub dev: bool,
+    /// The capabilities granted to the extension on the client, which take
+    /// precedence over the ones declared in its manifest.
+    pub capabilities: Option<ExtensionCapabilities>,
+}
+
+impl ExtensionVersion {
+    fn from_proto(extension: proto::Extension) -> Self {
+        Self {
+            id: extension.id,
+            version: extension.version,
+            dev: extension.dev,
+            capabilities: extension.capabilities.map(capabilities_from_proto),
+        }
+    }
+
+    fn to_proto(self) -> proto::Extension {
+        proto::Extension {
+            id: self.id,
+            version: self.version,
+            dev: self.dev,
+            capabilities: self.capabilities.map(capabilities_to_proto),
+        }
+    }
+}
+
+pub(crate) fn capabilities_to_proto(
+    capabilities: ExtensionCapabilities,
+) -> proto::ExtensionCapabilities {
+    proto::ExtensionCapabilities {
+        commands: capabilities.commands,
+        hosts: capabilities.hosts,
+        paths: capabilities.paths,
+    }
+}
+
+fn capabilities_from_proto(capabilities: proto::ExtensionCapabilities) -> ExtensionCapabilities {
+    ExtensionCapabilities {
+        commands: capabilities.commands,
+        hosts: capabilities.hosts,
+        paths: capabilities.paths,
+    }
 }
 
 pub struct HeadlessExtensionStore {
@@ -28,7 +69,7 @@ pub struct HeadlessExtensionStore {
     pub extension_dir: PathBuf,
     pub proxy: Arc<ExtensionHostProxy>,
     pub wasm_host: Arc<WasmHost>,
-    pub loaded_extensions: HashMap<Arc<str>, Arc<str>>,
+    pub loaded_extensions: HashMap<Arc<str>, ExtensionVersion>,
     pub loaded_languages: HashMap<Arc<str>, Vec<LanguageName>>,
     pub loaded_language_servers: HashMap<Arc<str>, Vec<(LanguageServerName, LanguageName)>>,
 }
@@ -81,7 +122,9 @@ impl HeadlessExtensionStore {
                 !self
                     .loaded_extensions
                     .get(e.id.as_str())
-                    .is_some_and(|loaded| loaded.as_ref() == e.version.as_str())
+                    .is_some_and(|loaded| {
+                        loaded.version == e.version && loaded.capabilities == e.capabilities
+                    })
             })
             .collect();
 
@@ -116,10 +159,11 @@ impl HeadlessExtensionStore {
         cx: &mut AsyncApp,
     ) -> Result<()> {
         let (fs, wasm_host, extension_dir) = this.update(cx, |this, _cx| {
-            this.loaded_extensions.insert(
-                extension.id.clone().into(),
-                extension.version.clone().into(),
-            );
+            // Unload the extension first if it's being reloaded, such as when
+            // its capabilities changed.
+            this.unload_extension(&extension.id.clone().into());
+            this.loaded_extensions
+                .insert(extension.id.clone().into(), extension.clone());
             (
                 this.fs.clone(),
                 this.wasm_host.clone(),
@@ -173,8 +217,19 @@ impl HeadlessExtensionStore {
             return Ok(());
         }
 
-        let wasm_extension: Arc<dyn Extension> =
-            Arc::new(WasmExtension::load(extension_dir, &manifest, wasm_host.clone(), &cx).await?);
+        let capabilities = extension
+            .capabilities
+            .unwrap_or_else(|| manifest.capabilities());
+        let wasm_extension: Arc<dyn Extension> = Arc::new(
+            WasmExtension::load(
+                extension_dir,
+                &manifest,
+                capabilities,
+                wasm_host.clone(),
+                &cx,
+            )
+            .await?,
+        );
 
         for (language_server_id, language_server_config) in &manifest.language_servers {
             for language in language_server_config.languages() {
@@ -195,11 +250,7 @@ impl HeadlessExtensionStore {
         Ok(())
     }
 
-    fn uninstall_extension(
-        &mut self,
-        extension_id: &Arc<str>,
-        cx: &mut Context<Self>,
-    ) -> Task<Result<()>> {
+    fn unload_extension(&mut self, extension_id: &Arc<str>) {
         self.loaded_extensions.remove(extension_id);
 
         let languages_to_remove = self
@@ -216,6 +267,14 @@ impl HeadlessExtensionStore {
             self.proxy
                 .remove_language_server(&language, &language_server_name);
         }
+    }
+
+    fn uninstall_extension(
+        &mut self,
+        extension_id: &Arc<str>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        self.unload_extension(extension_id);
 
         let path = self.extension_dir.join(&extension_id.to_string());
         let fs = self.fs.clone();
@@ -260,16 +319,11 @@ impl HeadlessExtensionStore {
         envelope: TypedEnvelope<proto::SyncExtensions>,
         mut cx: AsyncApp,
     ) -> Result<proto::SyncExtensionsResponse> {
-        let requested_extensions =
-            envelope
-                .payload
-                .extensions
-                .into_iter()
-                .map(|p| ExtensionVersion {
-                    id: p.id,
-                    version: p.version,
-                    dev: p.dev,
-                });
+        let requested_extensions = envelope
+            .payload
+            .extensions
+            .into_iter()
+            .map(ExtensionVersion::from_proto);
         let missing_extensions = extension_store
             .update(&mut cx, |extension_store, cx| {
                 extension_store.sync_extensions(requested_extensions.collect(), cx)
@@ -279,11 +333,7 @@ impl HeadlessExtensionStore {
         Ok(proto::SyncExtensionsResponse {
             missing_extensions: missing_extensions
                 .into_iter()
-                .map(|e| proto::Extension {
-                    id: e.id,
-                    version: e.version,
-                    dev: e.dev,
-                })
+                .map(ExtensionVersion::to_proto)
                 .collect(),
             tmp_dir: paths::remote_extensions_uploads_dir()
                 .to_string_lossy()
@@ -304,11 +354,7 @@ impl HeadlessExtensionStore {
         extensions
             .update(&mut cx, |extensions, cx| {
                 extensions.install_extension(
-                    ExtensionVersion {
-                        id: extension.id,
-                        version: extension.version,
-                        dev: extension.dev,
-                    },
+                    ExtensionVersion::from_proto(extension),
                     PathBuf::from(envelope.payload.tmp_dir),
                     cx,
                 )
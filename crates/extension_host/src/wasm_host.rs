@@ -4,8 +4,11 @@ use crate::ExtensionManifest;
 use anyhow::{anyhow, bail, Context as _, Result};
 use async_trait::async_trait;
 use extension::{
-    CodeLabel, Command, Completion, ExtensionHostProxy, KeyValueStoreDelegate, ProjectDelegate,
-    SlashCommand, SlashCommandArgumentCompletion, SlashCommandOutput, Symbol, WorktreeDelegate,
+    BufferDelegate, CodeLabel, Command, Completion, CompletionEvent, CompletionRequest,
+    EditorCommand, EnvVars, ExtensionCapabilities, ExtensionHostProxy, FormatOutput,
+    KeyValueStoreDelegate, LanguageModelInfo, LintDiagnostic, ProjectDelegate, SlashCommand,
+    SlashCommandArgumentCompletion, SlashCommandOutput, SourceFile, Symbol, TaskTemplate, TextEdit,
+    WorktreeDelegate,
 };
 use fs::{normalize_path, Fs};
 use futures::future::LocalBoxFuture;
@@ -15,10 +18,14 @@ use futures::{
         oneshot,
     },
     future::BoxFuture,
+    stream::{self, BoxStream},
     Future, FutureExt, StreamExt as _,
 };
 use gpui::{App, AsyncApp, BackgroundExecutor, Task};
-use http_client::HttpClient;
+use http_client::{
+    http, AsyncBody, HttpClient, HttpRequestExt as _, Method, RedirectPolicy, Request, Response,
+    StatusCode, Url,
+};
 use language::LanguageName;
 use lsp::LanguageServerName;
 use node_runtime::NodeRuntime;
@@ -28,6 +35,7 @@ use std::{
     path::{Path, PathBuf},
     sync::{Arc, OnceLock},
 };
+use util::ResultExt as _;
 use wasmtime::{
     component::{Component, ResourceTable},
     Engine, Store,
@@ -35,6 +43,9 @@ use wasmtime::{
 use wasmtime_wasi::{self as wasi, WasiView};
 use wit::Extension;
 
+/// The number of redirects followed for extensions' requests that follow all redirects.
+const MAX_REDIRECTS: u32 = 20;
+
 pub struct WasmHost {
     engine: Engine,
     release_channel: ReleaseChannel,
@@ -84,8 +95,10 @@ impl extension::Extension for WasmExtension {
                     )
                     .await?
                     .map_err(|err| anyhow!("{err}"))?;
+                let command: Command = command.into();
+                store.data().ensure_command_allowed(&command.command)?;
 
-                Ok(command.into())
+                Ok(command)
             }
             .boxed()
         })
@@ -249,7 +262,9 @@ impl extension::Extension for WasmExtension {
                     .call_context_server_command(store, context_server_id.clone(), project_resource)
                     .await?
                     .map_err(|err| anyhow!("{err}"))?;
-                anyhow::Ok(command.into())
+                let command: Command = command.into();
+                store.data().ensure_command_allowed(&command.command)?;
+                anyhow::Ok(command)
             }
             .boxed()
         })
@@ -296,10 +311,273 @@ impl extension::Extension for WasmExtension {
         })
         .await
     }
+
+    async fn run_editor_command(
+        &self,
+        command: EditorCommand,
+        buffer: Arc<dyn BufferDelegate>,
+    ) -> Result<Vec<TextEdit>> {
+        self.call(|extension, store| {
+            async move {
+                let resource = store.data_mut().table().push(buffer)?;
+                let edits = extension
+                    .call_run_editor_command(store, &command.into(), resource)
+                    .await?
+                    .map_err(|err| anyhow!("{err}"))?;
+
+                Ok(edits.into_iter().map(Into::into).collect())
+            }
+            .boxed()
+        })
+        .await
+    }
+
+    async fn task_templates(
+        &self,
+        task_provider_id: Arc<str>,
+        worktree: Arc<dyn WorktreeDelegate>,
+    ) -> Result<Vec<TaskTemplate>> {
+        self.call(|extension, store| {
+            async move {
+                let resource = store.data_mut().table().push(worktree)?;
+                let templates = extension
+                    .call_task_templates(store, &task_provider_id, resource)
+                    .await?
+                    .map_err(|err| anyhow!("{err}"))?;
+
+                Ok(templates
+                    .into_iter()
+                    .map(TaskTemplate::from)
+                    .filter(|template| {
+                        store
+                            .data()
+                            .ensure_command_allowed(&template.command)
+                            .log_err()
+                            .is_some()
+                    })
+                    .collect())
+            }
+            .boxed()
+        })
+        .await
+    }
+
+    async fn task_variables(
+        &self,
+        task_provider_id: Arc<str>,
+        worktree: Arc<dyn WorktreeDelegate>,
+        variables: EnvVars,
+    ) -> Result<EnvVars> {
+        self.call(|extension, store| {
+            async move {
+                let resource = store.data_mut().table().push(worktree)?;
+                let variables = extension
+                    .call_task_variables(store, &task_provider_id, resource, &variables)
+                    .await?
+                    .map_err(|err| anyhow!("{err}"))?;
+
+                Ok(variables)
+            }
+            .boxed()
+        })
+        .await
+    }
+
+    async fn format(
+        &self,
+        formatter_id: Arc<str>,
+        worktree: Arc<dyn WorktreeDelegate>,
+        file: SourceFile,
+    ) -> Result<FormatOutput> {
+        self.call(|extension, store| {
+            async move {
+                let resource = store.data_mut().table().push(worktree)?;
+                let output = extension
+                    .call_format(store, &formatter_id, resource, &file.into())
+                    .await?
+                    .map_err(|err| anyhow!("{err}"))?;
+                let output: FormatOutput = output.into();
+                if let FormatOutput::Command(command) = &output {
+                    store.data().ensure_command_allowed(&command.command)?;
+                }
+
+                Ok(output)
+            }
+            .boxed()
+        })
+        .await
+    }
+
+    async fn lint(
+        &self,
+        linter_id: Arc<str>,
+        worktree: Arc<dyn WorktreeDelegate>,
+        file: SourceFile,
+    ) -> Result<Vec<LintDiagnostic>> {
+        self.call(|extension, store| {
+            async move {
+                let resource = store.data_mut().table().push(worktree)?;
+                let diagnostics = extension
+                    .call_lint(store, &linter_id, resource, &file.into())
+                    .await?
+                    .map_err(|err| anyhow!("{err}"))?;
+
+                Ok(diagnostics.into_iter().map(Into::into).collect())
+            }
+            .boxed()
+        })
+        .await
+    }
+
+    async fn language_models(
+        &self,
+        provider_id: Arc<str>,
+        api_key: Option<String>,
+    ) -> Result<Vec<LanguageModelInfo>> {
+        self.call(|extension, store| {
+            async move {
+                let models = extension
+                    .call_language_models(store, &provider_id, api_key.as_deref())
+                    .await?
+                    .map_err(|err| anyhow!("{err}"))?;
+
+                Ok(models.into_iter().map(Into::into).collect())
+            }
+            .boxed()
+        })
+        .await
+    }
+
+    async fn count_language_model_tokens(
+        &self,
+        provider_id: Arc<str>,
+        model_id: Arc<str>,
+        request: CompletionRequest,
+    ) -> Result<usize> {
+        self.call(|extension, store| {
+            async move {
+                let token_count = extension
+                    .call_count_language_model_tokens(
+                        store,
+                        &provider_id,
+                        &model_id,
+                        &request.into(),
+                    )
+                    .await?
+                    .map_err(|err| anyhow!("{err}"))?;
+
+                Ok(token_count as usize)
+            }
+            .boxed()
+        })
+        .await
+    }
+
+    async fn stream_completion(
+        &self,
+        provider_id: Arc<str>,
+        model_id: Arc<str>,
+        request: CompletionRequest,
+        api_key: Option<String>,
+    ) -> Result<BoxStream<'static, Result<CompletionEvent>>> {
+        let completion_id = self
+            .call(|extension, store| {
+                async move {
+                    extension
+                        .call_start_completion(
+                            store,
+                            &provider_id,
+                            &model_id,
+                            &request.into(),
+                            api_key.as_deref(),
+                        )
+                        .await?
+                        .map_err(|err| anyhow!("{err}"))
+                }
+                .boxed()
+            })
+            .await?;
+
+        let completion = WasmCompletion {
+            extension: self.clone(),
+            completion_id,
+            finished: false,
+            failed: false,
+        };
+        Ok(stream::unfold(completion, |mut completion| async move {
+            if completion.finished || completion.failed {
+                return None;
+            }
+            match completion.next_event().await {
+                Ok(Some(event)) => Some((Ok(event), completion)),
+                Ok(None) => {
+                    completion.finished = true;
+                    None
+                }
+                Err(error) => {
+                    completion.failed = true;
+                    Some((Err(error), completion))
+                }
+            }
+        })
+        .boxed())
+    }
+}
+
+/// A completion streamed by a wasm extension.
+///
+/// Dropping it before the extension has finished the completion tells the
+/// extension to drop the completion as well.
+struct WasmCompletion {
+    extension: WasmExtension,
+    completion_id: u64,
+    finished: bool,
+    failed: bool,
+}
+
+impl WasmCompletion {
+    async fn next_event(&self) -> Result<Option<CompletionEvent>> {
+        let completion_id = self.completion_id;
+        self.extension
+            .call(move |extension, store| {
+                async move {
+                    let event = extension
+                        .call_next_completion_event(store, completion_id)
+                        .await?
+                        .map_err(|err| anyhow!("{err}"))?;
+
+                    Ok(event.map(Into::into))
+                }
+                .boxed()
+            })
+            .await
+    }
+}
+
+impl Drop for WasmCompletion {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+
+        let completion_id = self.completion_id;
+        self.extension
+            .tx
+            .unbounded_send(Box::new(move |extension, store| {
+                async move {
+                    if let Err(error) = extension.call_drop_completion(store, completion_id).await {
+                        log::error!("failed to drop extension completion: {error}");
+                    }
+                }
+                .boxed()
+            }))
+            .ok();
+    }
 }
 
 pub struct WasmState {
     manifest: Arc<ExtensionManifest>,
+    capabilities: ExtensionCapabilities,
     pub table: ResourceTable,
     ctx: wasi::WasiCtx,
     pub host: Arc<WasmHost>,
@@ -356,6 +634,7 @@ impl WasmHost {
         self: &Arc<Self>,
         wasm_bytes: Vec<u8>,
         manifest: &Arc<ExtensionManifest>,
+        capabilities: ExtensionCapabilities,
         executor: BackgroundExecutor,
     ) -> Task<Result<WasmExtension>> {
         let this = self.clone();
@@ -369,8 +648,9 @@ impl WasmHost {
             let mut store = wasmtime::Store::new(
                 &this.engine,
                 WasmState {
-                    ctx: this.build_wasi_ctx(&manifest).await?,
+                    ctx: this.build_wasi_ctx(&manifest, &capabilities).await?,
                     manifest: manifest.clone(),
+                    capabilities,
                     table: ResourceTable::new(),
                     host: this.clone(),
                 },
@@ -407,7 +687,11 @@ impl WasmHost {
         })
     }
 
-    async fn build_wasi_ctx(&self, manifest: &Arc<ExtensionManifest>) -> Result<wasi::WasiCtx> {
+    async fn build_wasi_ctx(
+        &self,
+        manifest: &Arc<ExtensionManifest>,
+        capabilities: &ExtensionCapabilities,
+    ) -> Result<wasi::WasiCtx> {
         let extension_work_dir = self.work_dir.join(manifest.id.as_ref());
         self.fs
             .create_dir(&extension_work_dir)
@@ -417,7 +701,8 @@ impl WasmHost {
         let file_perms = wasi::FilePerms::all();
         let dir_perms = wasi::DirPerms::all();
 
-        Ok(wasi::WasiCtxBuilder::new()
+        let mut builder = wasi::WasiCtxBuilder::new();
+        builder
             .inherit_stdio()
             .preopened_dir(&extension_work_dir, ".", dir_perms, file_perms)?
             .preopened_dir(
@@ -427,8 +712,25 @@ impl WasmHost {
                 file_perms,
             )?
             .env("PWD", extension_work_dir.to_string_lossy())
-            .env("RUST_BACKTRACE", "full")
-            .build())
+            .env("RUST_BACKTRACE", "full");
+
+        for path in capabilities.readable_paths() {
+            if !self.fs.is_dir(&path).await {
+                log::warn!(
+                    "extension {} was granted access to {path:?}, which is not a directory",
+                    manifest.id
+                );
+                continue;
+            }
+            builder.preopened_dir(
+                &path,
+                path.to_string_lossy(),
+                wasi::DirPerms::READ,
+                wasi::FilePerms::READ,
+            )?;
+        }
+
+        Ok(builder.build())
     }
 
     pub fn writeable_path_from_extension(&self, id: &Arc<str>, path: &Path) -> Result<PathBuf> {
@@ -489,6 +791,7 @@ impl WasmExtension {
     pub async fn load(
         extension_dir: PathBuf,
         manifest: &Arc<ExtensionManifest>,
+        capabilities: ExtensionCapabilities,
         wasm_host: Arc<WasmHost>,
         cx: &AsyncApp,
     ) -> Result<Self> {
@@ -506,7 +809,12 @@ impl WasmExtension {
             .context("failed to read wasm")?;
 
         wasm_host
-            .load_extension(wasm_bytes, manifest, cx.background_executor().clone())
+            .load_extension(
+                wasm_bytes,
+                manifest,
+                capabilities,
+                cx.background_executor().clone(),
+            )
             .await
             .with_context(|| format!("failed to load wasm extension {}", manifest.id))
     }
@@ -533,7 +841,124 @@ impl WasmExtension {
     }
 }
 
+fn ensure_url_allowed(
+    manifest: &ExtensionManifest,
+    capabilities: &ExtensionCapabilities,
+    url: &str,
+) -> Result<()> {
+    if capabilities.allows_url(url) {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "extension {} is not allowed to access {url:?}",
+            manifest.id
+        ))
+    }
+}
+
 impl WasmState {
+    /// Returns an error unless the extension was granted the capability to
+    /// run the given command.
+    fn ensure_command_allowed(&self, command: &str) -> Result<()> {
+        if self.capabilities.allows_command(command) {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "extension {} is not allowed to run command {command:?}",
+                self.manifest.id
+            ))
+        }
+    }
+
+    /// Returns an error unless the extension was granted the capability to
+    /// send requests to the host of the given URL.
+    fn ensure_url_allowed(&self, url: &str) -> Result<()> {
+        ensure_url_allowed(&self.manifest, &self.capabilities, url)
+    }
+
+    /// Sends an HTTP request on behalf of the extension.
+    ///
+    /// Unless the extension may access any host, redirects are followed here rather
+    /// than by the HTTP client, so that every URL redirected to is checked against
+    /// the extension's capabilities.
+    fn send_http_request(
+        &self,
+        method: Method,
+        url: String,
+        headers: Vec<(String, String)>,
+        body: Option<Vec<u8>>,
+        redirect_policy: RedirectPolicy,
+    ) -> impl 'static + Future<Output = Result<Response<AsyncBody>>> {
+        let manifest = self.manifest.clone();
+        let capabilities = self.capabilities.clone();
+        let http_client = self.host.http_client.clone();
+        let allowed = self.ensure_url_allowed(&url);
+        async move {
+            allowed?;
+            let build_request = |method: &Method,
+                                 url: &str,
+                                 body: Option<Vec<u8>>,
+                                 redirect_policy: RedirectPolicy| {
+                let mut request = Request::builder()
+                    .method(method.clone())
+                    .uri(url)
+                    .follow_redirects(redirect_policy);
+                for (key, value) in &headers {
+                    request = request.header(key, value);
+                }
+                request
+                    .body(body.map(AsyncBody::from).unwrap_or_default())
+                    .map_err(anyhow::Error::from)
+            };
+            if capabilities.allows_any_host() {
+                let request = build_request(&method, &url, body, redirect_policy)?;
+                return http_client.send(request).await;
+            }
+
+            let max_redirects = match redirect_policy {
+                RedirectPolicy::NoFollow => 0,
+                RedirectPolicy::FollowLimit(limit) => limit,
+                RedirectPolicy::FollowAll => MAX_REDIRECTS,
+            };
+            let mut method = method;
+            let mut url = Url::parse(&url)?;
+            let mut body = body;
+            let mut redirects = 0;
+            loop {
+                let request = build_request(
+                    &method,
+                    url.as_str(),
+                    body.clone(),
+                    RedirectPolicy::NoFollow,
+                )?;
+                let response = http_client.send(request).await?;
+                let location = response.headers().get(http::header::LOCATION);
+                let Some(location) = location.filter(|_| response.status().is_redirection()) else {
+                    return Ok(response);
+                };
+                if redirect_policy == RedirectPolicy::NoFollow {
+                    return Ok(response);
+                }
+                if redirects == max_redirects {
+                    bail!("too many redirects when requesting {url}");
+                }
+
+                url = url.join(location.to_str()?)?;
+                ensure_url_allowed(&manifest, &capabilities, url.as_str())?;
+                // Like browsers, only 307 and 308 redirects repeat the request as it was.
+                if !matches!(
+                    response.status(),
+                    StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT
+                ) && method != Method::HEAD
+                {
+                    method = Method::GET;
+                    body = None;
+                }
+                redirects += 1;
+            }
+        }
+    }
+
     fn on_main_thread<T, Fn>(&self, f: Fn) -> impl 'static + Future<Output = T>
     where
         T: 'static + Send,
@@ -4,11 +4,12 @@ mod since_v0_0_6;
 mod since_v0_1_0;
 mod since_v0_2_0;
 mod since_v0_3_0;
-use extension::{KeyValueStoreDelegate, WorktreeDelegate};
+mod since_v0_4_0;
+use extension::{BufferDelegate, KeyValueStoreDelegate, WorktreeDelegate};
 use language::LanguageName;
 use lsp::LanguageServerName;
 use release_channel::ReleaseChannel;
-use since_v0_3_0 as latest;
+use since_v0_4_0 as latest;
 
 use super::{wasm_engine, WasmState};
 use anyhow::{anyhow, Context as _, Result};
@@ -22,10 +23,14 @@ use wasmtime::{
 #[cfg(test)]
 pub use latest::CodeLabelSpanLiteral;
 pub use latest::{
+    zed::extension::editor::{EditorCommand, TextEdit},
+    zed::extension::formatting::{FormatOutput, LintDiagnostic, LintSeverity, SourceFile},
+    zed::extension::language_model::{CompletionEvent, CompletionRequest, LanguageModelInfo},
     zed::extension::lsp::{
         Completion, CompletionKind, CompletionLabelDetails, InsertTextFormat, Symbol, SymbolKind,
     },
     zed::extension::slash_command::{SlashCommandArgumentCompletion, SlashCommandOutput},
+    zed::extension::task::TaskTemplate,
     CodeLabel, CodeLabelSpan, Command, ExtensionProject, Range, SlashCommand,
 };
 pub use since_v0_0_4::LanguageServerConfig;
@@ -89,6 +94,7 @@ pub fn authorize_access_to_unreleased_wasm_api_version(
 }
 
 pub enum Extension {
+    V040(since_v0_4_0::Extension),
     V030(since_v0_3_0::Extension),
     V020(since_v0_2_0::Extension),
     V010(since_v0_1_0::Extension),
@@ -114,6 +120,17 @@ impl Extension {
                 latest::Extension::instantiate_async(store, component, latest::linker())
                     .await
                     .context("failed to instantiate wasm extension")?;
+            Ok(Self::V040(extension))
+        } else if version >= since_v0_3_0::MIN_VERSION {
+            authorize_access_to_unreleased_wasm_api_version(release_channel)?;
+
+            let extension = since_v0_3_0::Extension::instantiate_async(
+                store,
+                component,
+                since_v0_3_0::linker(),
+            )
+            .await
+            .context("failed to instantiate wasm extension")?;
             Ok(Self::V030(extension))
         } else if version >= since_v0_2_0::MIN_VERSION {
             let extension = since_v0_2_0::Extension::instantiate_async(
@@ -165,6 +182,7 @@ impl Extension {
 
     pub async fn call_init_extension(&self, store: &mut Store<WasmState>) -> Result<()> {
         match self {
+            Extension::V040(ext) => ext.call_init_extension(store).await,
             Extension::V030(ext) => ext.call_init_extension(store).await,
             Extension::V020(ext) => ext.call_init_extension(store).await,
             Extension::V010(ext) => ext.call_init_extension(store).await,
@@ -182,6 +200,10 @@ impl Extension {
         resource: Resource<Arc<dyn WorktreeDelegate>>,
     ) -> Result<Result<Command, String>> {
         match self {
+            Extension::V040(ext) => {
+                ext.call_language_server_command(store, &language_server_id.0, resource)
+                    .await
+            }
             Extension::V030(ext) => {
                 ext.call_language_server_command(store, &language_server_id.0, resource)
                     .await
@@ -232,6 +254,14 @@ impl Extension {
         resource: Resource<Arc<dyn WorktreeDelegate>>,
     ) -> Result<Result<Option<String>, String>> {
         match self {
+            Extension::V040(ext) => {
+                ext.call_language_server_initialization_options(
+                    store,
+                    &language_server_id.0,
+                    resource,
+                )
+                .await
+            }
             Extension::V030(ext) => {
                 ext.call_language_server_initialization_options(
                     store,
@@ -297,6 +327,14 @@ impl Extension {
         resource: Resource<Arc<dyn WorktreeDelegate>>,
     ) -> Result<Result<Option<String>, String>> {
         match self {
+            Extension::V040(ext) => {
+                ext.call_language_server_workspace_configuration(
+                    store,
+                    &language_server_id.0,
+                    resource,
+                )
+                .await
+            }
             Extension::V030(ext) => {
                 ext.call_language_server_workspace_configuration(
                     store,
@@ -340,10 +378,19 @@ impl Extension {
         completions: Vec<latest::Completion>,
     ) -> Result<Result<Vec<Option<CodeLabel>>, String>> {
         match self {
-            Extension::V030(ext) => {
+            Extension::V040(ext) => {
                 ext.call_labels_for_completions(store, &language_server_id.0, &completions)
                     .await
             }
+            Extension::V030(ext) => Ok(ext
+                .call_labels_for_completions(store, &language_server_id.0, &completions)
+                .await?
+                .map(|labels| {
+                    labels
+                        .into_iter()
+                        .map(|label| label.map(Into::into))
+                        .collect()
+                })),
             Extension::V020(ext) => Ok(ext
                 .call_labels_for_completions(
                     store,
@@ -394,10 +441,19 @@ impl Extension {
         symbols: Vec<latest::Symbol>,
     ) -> Result<Result<Vec<Option<CodeLabel>>, String>> {
         match self {
-            Extension::V030(ext) => {
+            Extension::V040(ext) => {
                 ext.call_labels_for_symbols(store, &language_server_id.0, &symbols)
                     .await
             }
+            Extension::V030(ext) => Ok(ext
+                .call_labels_for_symbols(store, &language_server_id.0, &symbols)
+                .await?
+                .map(|labels| {
+                    labels
+                        .into_iter()
+                        .map(|label| label.map(Into::into))
+                        .collect()
+                })),
             Extension::V020(ext) => Ok(ext
                 .call_labels_for_symbols(
                     store,
@@ -448,6 +504,10 @@ impl Extension {
         arguments: &[String],
     ) -> Result<Result<Vec<SlashCommandArgumentCompletion>, String>> {
         match self {
+            Extension::V040(ext) => {
+                ext.call_complete_slash_command_argument(store, command, arguments)
+                    .await
+            }
             Extension::V030(ext) => {
                 ext.call_complete_slash_command_argument(store, command, arguments)
                     .await
@@ -472,6 +532,10 @@ impl Extension {
         resource: Option<Resource<Arc<dyn WorktreeDelegate>>>,
     ) -> Result<Result<SlashCommandOutput, String>> {
         match self {
+            Extension::V040(ext) => {
+                ext.call_run_slash_command(store, command, arguments, resource)
+                    .await
+            }
             Extension::V030(ext) => {
                 ext.call_run_slash_command(store, command, arguments, resource)
                     .await
@@ -497,6 +561,10 @@ impl Extension {
         project: Resource<ExtensionProject>,
     ) -> Result<Result<Command, String>> {
         match self {
+            Extension::V040(ext) => {
+                ext.call_context_server_command(store, &context_server_id, project)
+                    .await
+            }
             Extension::V030(ext) => {
                 ext.call_context_server_command(store, &context_server_id, project)
                     .await
@@ -519,6 +587,7 @@ impl Extension {
         provider: &str,
     ) -> Result<Result<Vec<String>, String>> {
         match self {
+            Extension::V040(ext) => ext.call_suggest_docs_packages(store, provider).await,
             Extension::V030(ext) => ext.call_suggest_docs_packages(store, provider).await,
             Extension::V020(ext) => ext.call_suggest_docs_packages(store, provider).await,
             Extension::V010(ext) => ext.call_suggest_docs_packages(store, provider).await,
@@ -536,6 +605,10 @@ impl Extension {
         kv_store: Resource<Arc<dyn KeyValueStoreDelegate>>,
     ) -> Result<Result<(), String>> {
         match self {
+            Extension::V040(ext) => {
+                ext.call_index_docs(store, provider, package_name, kv_store)
+                    .await
+            }
             Extension::V030(ext) => {
                 ext.call_index_docs(store, provider, package_name, kv_store)
                     .await
@@ -553,6 +626,200 @@ impl Extension {
             }
         }
     }
+
+    pub async fn call_run_editor_command(
+        &self,
+        store: &mut Store<WasmState>,
+        command: &EditorCommand,
+        buffer: Resource<Arc<dyn BufferDelegate>>,
+    ) -> Result<Result<Vec<TextEdit>, String>> {
+        match self {
+            Extension::V040(ext) => ext.call_run_editor_command(store, command, buffer).await,
+            Extension::V030(_)
+            | Extension::V020(_)
+            | Extension::V010(_)
+            | Extension::V006(_)
+            | Extension::V004(_)
+            | Extension::V001(_) => Err(anyhow!(
+                "`run_editor_command` not available prior to v0.4.0"
+            )),
+        }
+    }
+
+    pub async fn call_task_templates(
+        &self,
+        store: &mut Store<WasmState>,
+        task_provider_id: &str,
+        worktree: Resource<Arc<dyn WorktreeDelegate>>,
+    ) -> Result<Result<Vec<TaskTemplate>, String>> {
+        match self {
+            Extension::V040(ext) => {
+                ext.call_task_templates(store, task_provider_id, worktree)
+                    .await
+            }
+            Extension::V030(_)
+            | Extension::V020(_)
+            | Extension::V010(_)
+            | Extension::V006(_)
+            | Extension::V004(_)
+            | Extension::V001(_) => Err(anyhow!("`task_templates` not available prior to v0.4.0")),
+        }
+    }
+
+    pub async fn call_task_variables(
+        &self,
+        store: &mut Store<WasmState>,
+        task_provider_id: &str,
+        worktree: Resource<Arc<dyn WorktreeDelegate>>,
+        variables: &[(String, String)],
+    ) -> Result<Result<Vec<(String, String)>, String>> {
+        match self {
+            Extension::V040(ext) => {
+                ext.call_task_variables(store, task_provider_id, worktree, variables)
+                    .await
+            }
+            Extension::V030(_)
+            | Extension::V020(_)
+            | Extension::V010(_)
+            | Extension::V006(_)
+            | Extension::V004(_)
+            | Extension::V001(_) => Err(anyhow!("`task_variables` not available prior to v0.4.0")),
+        }
+    }
+
+    pub async fn call_format(
+        &self,
+        store: &mut Store<WasmState>,
+        formatter_id: &str,
+        worktree: Resource<Arc<dyn WorktreeDelegate>>,
+        file: &SourceFile,
+    ) -> Result<Result<FormatOutput, String>> {
+        match self {
+            Extension::V040(ext) => ext.call_format(store, formatter_id, worktree, file).await,
+            Extension::V030(_)
+            | Extension::V020(_)
+            | Extension::V010(_)
+            | Extension::V006(_)
+            | Extension::V004(_)
+            | Extension::V001(_) => Err(anyhow!("`format` not available prior to v0.4.0")),
+        }
+    }
+
+    pub async fn call_lint(
+        &self,
+        store: &mut Store<WasmState>,
+        linter_id: &str,
+        worktree: Resource<Arc<dyn WorktreeDelegate>>,
+        file: &SourceFile,
+    ) -> Result<Result<Vec<LintDiagnostic>, String>> {
+        match self {
+            Extension::V040(ext) => ext.call_lint(store, linter_id, worktree, file).await,
+            Extension::V030(_)
+            | Extension::V020(_)
+            | Extension::V010(_)
+            | Extension::V006(_)
+            | Extension::V004(_)
+            | Extension::V001(_) => Err(anyhow!("`lint` not available prior to v0.4.0")),
+        }
+    }
+
+    pub async fn call_language_models(
+        &self,
+        store: &mut Store<WasmState>,
+        provider_id: &str,
+        api_key: Option<&str>,
+    ) -> Result<Result<Vec<LanguageModelInfo>, String>> {
+        match self {
+            Extension::V040(ext) => ext.call_language_models(store, provider_id, api_key).await,
+            Extension::V030(_)
+            | Extension::V020(_)
+            | Extension::V010(_)
+            | Extension::V006(_)
+            | Extension::V004(_)
+            | Extension::V001(_) => Err(anyhow!("`language_models` not available prior to v0.4.0")),
+        }
+    }
+
+    pub async fn call_count_language_model_tokens(
+        &self,
+        store: &mut Store<WasmState>,
+        provider_id: &str,
+        model_id: &str,
+        request: &CompletionRequest,
+    ) -> Result<Result<u64, String>> {
+        match self {
+            Extension::V040(ext) => {
+                ext.call_count_language_model_tokens(store, provider_id, model_id, request)
+                    .await
+            }
+            Extension::V030(_)
+            | Extension::V020(_)
+            | Extension::V010(_)
+            | Extension::V006(_)
+            | Extension::V004(_)
+            | Extension::V001(_) => Err(anyhow!(
+                "`count_language_model_tokens` not available prior to v0.4.0"
+            )),
+        }
+    }
+
+    pub async fn call_start_completion(
+        &self,
+        store: &mut Store<WasmState>,
+        provider_id: &str,
+        model_id: &str,
+        request: &CompletionRequest,
+        api_key: Option<&str>,
+    ) -> Result<Result<u64, String>> {
+        match self {
+            Extension::V040(ext) => {
+                ext.call_start_completion(store, provider_id, model_id, request, api_key)
+                    .await
+            }
+            Extension::V030(_)
+            | Extension::V020(_)
+            | Extension::V010(_)
+            | Extension::V006(_)
+            | Extension::V004(_)
+            | Extension::V001(_) => {
+                Err(anyhow!("`start_completion` not available prior to v0.4.0"))
+            }
+        }
+    }
+
+    pub async fn call_next_completion_event(
+        &self,
+        store: &mut Store<WasmState>,
+        completion_id: u64,
+    ) -> Result<Result<Option<CompletionEvent>, String>> {
+        match self {
+            Extension::V040(ext) => ext.call_next_completion_event(store, completion_id).await,
+            Extension::V030(_)
+            | Extension::V020(_)
+            | Extension::V010(_)
+            | Extension::V006(_)
+            | Extension::V004(_)
+            | Extension::V001(_) => Err(anyhow!(
+                "`next_completion_event` not available prior to v0.4.0"
+            )),
+        }
+    }
+
+    pub async fn call_drop_completion(
+        &self,
+        store: &mut Store<WasmState>,
+        completion_id: u64,
+    ) -> Result<()> {
+        match self {
+            Extension::V040(ext) => ext.call_drop_completion(store, completion_id).await,
+            Extension::V030(_)
+            | Extension::V020(_)
+            | Extension::V010(_)
+            | Extension::V006(_)
+            | Extension::V004(_)
+            | Extension::V001(_) => Err(anyhow!("`drop_completion` not available prior to v0.4.0")),
+        }
+    }
 }
 
 trait ToWasmtimeResult<T> {
@@ -564,3 +831,179 @@ impl<T> ToWasmtimeResult<T> for Result<T> {
         Ok(self.map_err(|error| error.to_string()))
     }
 }
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use super::*;
+    use crate::wasm_host::WasmHost;
+    use extension::{ExtensionCapabilities, ExtensionHostProxy, ExtensionManifest};
+    use fs::RealFs;
+    use gpui::TestAppContext;
+    use http_client::FakeHttpClient;
+    use http_client::{AsyncBody, HttpClient, Response};
+    use latest::http_client::Host as _;
+    use latest::process::{self, Host as _};
+    use latest::ExtensionImports as _;
+    use node_runtime::NodeRuntime;
+    use serde_json::json;
+    use std::path::Path;
+    use util::test::TempTree;
+    use wasmtime::component::ResourceTable;
+
+    async fn build_wasm_state(
+        dir: &Path,
+        http_client: Arc<dyn HttpClient>,
+        capabilities: ExtensionCapabilities,
+        cx: &mut TestAppContext,
+    ) -> WasmState {
+        cx.update(|cx| release_channel::init(SemanticVersion::default(), cx));
+        let wasm_host = cx.update(|cx| {
+            WasmHost::new(
+                Arc::new(RealFs::default()),
+                http_client,
+                NodeRuntime::unavailable(),
+                Arc::new(ExtensionHostProxy::new()),
+                dir.join("work"),
+                cx,
+            )
+        });
+        let manifest: Arc<ExtensionManifest> = Arc::new(
+            toml::from_str(
+                r#"
+                id = "test-extension"
+                name = "Test Extension"
+                version = "0.1.0"
+                schema_version = 1
+                "#,
+            )
+            .unwrap(),
+        );
+        WasmState {
+            ctx: wasm_host
+                .build_wasi_ctx(&manifest, &capabilities)
+                .await
+                .unwrap(),
+            manifest,
+            capabilities,
+            table: ResourceTable::new(),
+            host: wasm_host,
+        }
+    }
+
+    #[gpui::test]
+    async fn test_denied_commands_are_not_run(cx: &mut TestAppContext) {
+        cx.executor().allow_parking();
+
+        let dir = TempTree::new(json!({ "work": {} }));
+        let dir = dir.path().canonicalize().unwrap();
+        let capabilities = ExtensionCapabilities {
+            commands: vec!["echo".into()],
+            ..Default::default()
+        };
+        let mut state =
+            build_wasm_state(&dir, FakeHttpClient::with_404_response(), capabilities, cx).await;
+
+        let marker_path = dir.join("marker");
+        let touch = process::Command {
+            command: "touch".into(),
+            args: vec![marker_path.to_string_lossy().into_owned()],
+            env: Vec::new(),
+        };
+        let error = state.run_command(touch).await.unwrap().unwrap_err();
+        assert_eq!(
+            error,
+            "extension test-extension is not allowed to run command \"touch\""
+        );
+        assert!(!marker_path.exists());
+
+        let echo = process::Command {
+            command: "echo".into(),
+            args: vec!["hello".into()],
+            env: Vec::new(),
+        };
+        let output = state.run_command(echo).await.unwrap().unwrap();
+        assert_eq!(output.stdout, b"hello\n");
+    }
+
+    #[gpui::test]
+    async fn test_redirects_to_denied_hosts_are_not_followed(cx: &mut TestAppContext) {
+        cx.executor().allow_parking();
+
+        let dir = TempTree::new(json!({ "work": {} }));
+        let dir = dir.path().canonicalize().unwrap();
+        let requested_urls = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let http_client = FakeHttpClient::create({
+            let requested_urls = requested_urls.clone();
+            move |request| {
+                let url = request.uri().to_string();
+                requested_urls.lock().push(url.clone());
+                async move {
+                    let response = match url.as_str() {
+                        "https://allowed.example/to-denied" => Response::builder()
+                            .status(302)
+                            .header("location", "https://denied.example/file"),
+                        "https://allowed.example/to-allowed" => {
+                            Response::builder().status(301).header("location", "/file")
+                        }
+                        _ => Response::builder().status(200),
+                    };
+                    Ok(response.body(AsyncBody::from("contents")).unwrap())
+                }
+            }
+        });
+        let capabilities = ExtensionCapabilities {
+            hosts: vec!["allowed.example".into()],
+            ..Default::default()
+        };
+        let mut state = build_wasm_state(&dir, http_client, capabilities, cx).await;
+
+        let request = |url: &str| latest::http_client::HttpRequest {
+            method: latest::http_client::HttpMethod::Get,
+            url: url.into(),
+            headers: Vec::new(),
+            body: None,
+            redirect_policy: latest::http_client::RedirectPolicy::FollowAll,
+        };
+        let response = state
+            .fetch(request("https://allowed.example/to-allowed"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.body, b"contents");
+
+        let error = state
+            .fetch(request("https://allowed.example/to-denied"))
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(
+            error,
+            "extension test-extension is not allowed to access \"https://denied.example/file\""
+        );
+
+        let error = state
+            .download_file(
+                "https://allowed.example/to-denied".into(),
+                "file".into(),
+                latest::DownloadedFileType::Uncompressed,
+            )
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert!(
+            error.contains("not allowed to access \"https://denied.example/file\""),
+            "unexpected error: {error}"
+        );
+        assert!(!dir.join("work/test-extension/file").exists());
+
+        assert_eq!(
+            *requested_urls.lock(),
+            [
+                "https://allowed.example/to-allowed",
+                "https://allowed.example/file",
+                "https://allowed.example/to-denied",
+                "https://allowed.example/to-denied",
+            ]
+        );
+    }
+}
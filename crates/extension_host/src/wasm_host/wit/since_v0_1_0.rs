@@ -1,11 +1,11 @@
 use crate::wasm_host::{wit::ToWasmtimeResult, WasmState};
-use ::http_client::{AsyncBody, HttpRequestExt};
+use ::http_client::AsyncBody;
 use ::settings::{Settings, WorktreeId};
 use anyhow::{anyhow, bail, Context, Result};
 use async_compression::futures::bufread::GzipDecoder;
 use async_tar::Archive;
 use extension::{ExtensionLanguageServerProxy, KeyValueStoreDelegate, WorktreeDelegate};
-use futures::{io::BufReader, FutureExt as _};
+use futures::{io::BufReader, Future, FutureExt as _};
 use futures::{lock::Mutex, AsyncReadExt};
 use language::LanguageName;
 use language::{language_settings::AllLanguageSettings, LanguageServerBinaryStatus};
@@ -294,8 +294,7 @@ impl http_client::Host for WasmState {
     ) -> wasmtime::Result<Result<http_client::HttpResponse, String>> {
         maybe!(async {
             let url = &request.url;
-            let request = convert_request(&request)?;
-            let mut response = self.host.http_client.send(request).await?;
+            let mut response = send_request(self, &request).await?;
 
             if response.status().is_client_error() || response.status().is_server_error() {
                 bail!("failed to fetch '{url}': status code {}", response.status())
@@ -310,10 +309,8 @@ impl http_client::Host for WasmState {
         &mut self,
         request: http_client::HttpRequest,
     ) -> wasmtime::Result<Result<Resource<ExtensionHttpResponseStream>, String>> {
-        let request = convert_request(&request)?;
-        let response = self.host.http_client.send(request);
         maybe!(async {
-            let response = response.await?;
+            let response = send_request(self, &request).await?;
             let stream = Arc::new(Mutex::new(response));
             let resource = self.table.push(stream)?;
             Ok(resource)
@@ -363,28 +360,28 @@ impl From<http_client::HttpMethod> for ::http_client::Method {
     }
 }
 
-fn convert_request(
-    extension_request: &http_client::HttpRequest,
-) -> Result<::http_client::Request<AsyncBody>, anyhow::Error> {
-    let mut request = ::http_client::Request::builder()
-        .method(::http_client::Method::from(extension_request.method))
-        .uri(&extension_request.url)
-        .follow_redirects(match extension_request.redirect_policy {
-            http_client::RedirectPolicy::NoFollow => ::http_client::RedirectPolicy::NoFollow,
-            http_client::RedirectPolicy::FollowLimit(limit) => {
-                ::http_client::RedirectPolicy::FollowLimit(limit)
-            }
-            http_client::RedirectPolicy::FollowAll => ::http_client::RedirectPolicy::FollowAll,
-        });
-    for (key, value) in &extension_request.headers {
-        request = request.header(key, value);
-    }
-    let body = extension_request
-        .body
-        .clone()
-        .map(AsyncBody::from)
-        .unwrap_or_default();
-    request.body(body).map_err(anyhow::Error::from)
+impl From<http_client::RedirectPolicy> for ::http_client::RedirectPolicy {
+    fn from(value: http_client::RedirectPolicy) -> Self {
+        match value {
+            http_client::RedirectPolicy::NoFollow => Self::NoFollow,
+            http_client::RedirectPolicy::FollowLimit(limit) => Self::FollowLimit(limit),
+            http_client::RedirectPolicy::FollowAll => Self::FollowAll,
+        }
+    }
+}
+
+/// Sends an extension's request, checking every URL it's redirected to.
+fn send_request(
+    state: &WasmState,
+    request: &http_client::HttpRequest,
+) -> impl 'static + Future<Output = Result<::http_client::Response<AsyncBody>>> {
+    state.send_http_request(
+        request.method.into(),
+        request.url.clone(),
+        request.headers.clone(),
+        request.body.clone(),
+        request.redirect_policy.into(),
+    )
 }
 
 async fn convert_response(
@@ -510,9 +507,13 @@ impl ExtensionImports for WasmState {
                 .writeable_path_from_extension(&self.manifest.id, &path)?;
 
             let mut response = self
-                .host
-                .http_client
-                .get(&url, Default::default(), true)
+                .send_http_request(
+                    ::http_client::Method::GET,
+                    url.clone(),
+                    Vec::new(),
+                    None,
+                    ::http_client::RedirectPolicy::FollowAll,
+                )
                 .await
                 .map_err(|err| anyhow!("error downloading release: {}", err))?;
 
@@ -0,0 +1,622 @@
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use editor::Editor;
+use extension::{
+    BufferDelegate, BufferSelection, EditorCommand, Extension, ExtensionEditorCommandProxy,
+    ExtensionHostProxy, SyntaxNode, TextEdit,
+};
+use fuzzy::{match_strings, StringMatch, StringMatchCandidate};
+use gpui::{
+    actions, prelude::*, App, DismissEvent, Entity, EventEmitter, Focusable, Global, ReadGlobal,
+    Task, WeakEntity,
+};
+use language::{Bias, BufferSnapshot, Node};
+use parking_lot::RwLock;
+use picker::{Picker, PickerDelegate};
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::ResultExt;
+use workspace::{ModalView, Workspace};
+
+actions!(zed, [RunExtensionEditorCommand]);
+
+pub fn init(cx: &mut App) {
+    let commands = ExtensionEditorCommands::default_global(cx);
+    ExtensionHostProxy::default_global(cx).register_editor_command_proxy(
+        ExtensionEditorCommandsProxy {
+            editor_commands: commands,
+        },
+    );
+
+    cx.observe_new(|workspace: &mut Workspace, _, _| {
+        workspace.register_action(|workspace, _: &RunExtensionEditorCommand, window, cx| {
+            let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+                return;
+            };
+            let workspace_handle = cx.entity().downgrade();
+            workspace.toggle_modal(window, cx, |window, cx| {
+                let delegate = EditorCommandSelectorDelegate::new(
+                    cx.entity().downgrade(),
+                    workspace_handle,
+                    editor.downgrade(),
+                    ExtensionEditorCommands::global(cx).commands(),
+                );
+                EditorCommandSelector::new(delegate, window, cx)
+            });
+        });
+    })
+    .detach();
+}
+
+#[derive(Default)]
+struct GlobalExtensionEditorCommands(Arc<ExtensionEditorCommands>);
+
+impl Global for GlobalExtensionEditorCommands {}
+
+/// The editor commands provided by the loaded extensions.
+#[derive(Default)]
+pub struct ExtensionEditorCommands {
+    commands: RwLock<Vec<RegisteredEditorCommand>>,
+}
+
+#[derive(Clone)]
+struct RegisteredEditorCommand {
+    extension: Arc<dyn Extension>,
+    command: EditorCommand,
+}
+
+impl ExtensionEditorCommands {
+    /// Returns the global [`ExtensionEditorCommands`].
+    pub fn global(cx: &App) -> Arc<Self> {
+        GlobalExtensionEditorCommands::global(cx).0.clone()
+    }
+
+    /// Returns the global [`ExtensionEditorCommands`].
+    ///
+    /// Inserts a default [`ExtensionEditorCommands`] if one does not yet exist.
+    pub fn default_global(cx: &mut App) -> Arc<Self> {
+        cx.default_global::<GlobalExtensionEditorCommands>()
+            .0
+            .clone()
+    }
+
+    fn commands(&self) -> Vec<RegisteredEditorCommand> {
+        let mut commands = self.commands.read().clone();
+        commands.sort_by(|a, b| a.command.name.cmp(&b.command.name));
+        commands
+    }
+}
+
+struct ExtensionEditorCommandsProxy {
+    editor_commands: Arc<ExtensionEditorCommands>,
+}
+
+impl ExtensionEditorCommandProxy for ExtensionEditorCommandsProxy {
+    fn register_editor_command(&self, extension: Arc<dyn Extension>, command: EditorCommand) {
+        let mut commands = self.editor_commands.commands.write();
+        let extension_id = extension.manifest().id.clone();
+        commands.retain(|registered| {
+            registered.extension.manifest().id != extension_id
+                || registered.command.name != command.name
+        });
+        commands.push(RegisteredEditorCommand { extension, command });
+    }
+
+    fn remove_editor_command(&self, extension_id: &Arc<str>, command_name: &str) {
+        self.editor_commands.commands.write().retain(|registered| {
+            &registered.extension.manifest().id != extension_id
+                || registered.command.name != command_name
+        });
+    }
+}
+
+/// Exposes a snapshot of a buffer, taken when an editor command was invoked,
+/// to the extension running the command.
+struct EditorBufferDelegate {
+    snapshot: BufferSnapshot,
+    path: Option<PathBuf>,
+    selections: Vec<BufferSelection>,
+}
+
+impl EditorBufferDelegate {
+    fn validate_range(&self, range: &Range<usize>) -> Result<()> {
+        if range.start > range.end || range.end > self.snapshot.len() {
+            return Err(anyhow!(
+                "range {range:?} is out of bounds for a buffer of length {}",
+                self.snapshot.len()
+            ));
+        }
+        if self.snapshot.clip_offset(range.start, Bias::Left) != range.start
+            || self.snapshot.clip_offset(range.end, Bias::Left) != range.end
+        {
+            return Err(anyhow!(
+                "range {range:?} does not lie on character boundaries"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the smallest syntax node that contains the given range.
+    fn node_for_range(&self, range: &Range<usize>) -> Option<Node> {
+        if self.validate_range(range).is_err() {
+            return None;
+        }
+        let layer = self
+            .snapshot
+            .syntax_layer_at(range.start)
+            .or_else(|| self.snapshot.syntax_layers().next())?;
+        layer
+            .node()
+            .descendant_for_byte_range(range.start, range.end)
+    }
+}
+
+fn syntax_node(node: Node) -> SyntaxNode {
+    SyntaxNode {
+        kind: node.kind().to_string(),
+        range: node.byte_range(),
+        is_named: node.is_named(),
+    }
+}
+
+impl BufferDelegate for EditorBufferDelegate {
+    fn path(&self) -> Option<String> {
+        self.path
+            .as_ref()
+            .map(|path| path.to_string_lossy().to_string())
+    }
+
+    fn language_name(&self) -> Option<String> {
+        self.snapshot
+            .language()
+            .map(|language| language.name().to_string())
+    }
+
+    fn text(&self) -> String {
+        self.snapshot.text()
+    }
+
+    fn text_for_range(&self, range: Range<usize>) -> Result<String> {
+        self.validate_range(&range)?;
+        Ok(self.snapshot.text_for_range(range).collect())
+    }
+
+    fn selections(&self) -> Vec<BufferSelection> {
+        self.selections.clone()
+    }
+
+    fn syntax_ancestors(&self, range: Range<usize>) -> Vec<SyntaxNode> {
+        let mut ancestors = Vec::new();
+        let mut node = self.node_for_range(&range);
+        while let Some(current) = node {
+            ancestors.push(syntax_node(current));
+            node = current.parent();
+        }
+        ancestors
+    }
+
+    fn syntax_children(&self, range: Range<usize>) -> Vec<SyntaxNode> {
+        let Some(node) = self.node_for_range(&range) else {
+            return Vec::new();
+        };
+        let mut cursor = node.walk();
+        node.children(&mut cursor).map(syntax_node).collect()
+    }
+}
+
+/// Runs the given command against the buffer of the given editor, applying
+/// the returned edits as a single transaction.
+fn run_editor_command(
+    registered: RegisteredEditorCommand,
+    editor: Entity<Editor>,
+    workspace: WeakEntity<Workspace>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let Some(buffer) = editor.read(cx).buffer().read(cx).as_singleton() else {
+        workspace
+            .update(cx, |workspace, cx| {
+                workspace.show_error(
+                    &anyhow!("editor commands can only be run in single-file editors"),
+                    cx,
+                )
+            })
+            .ok();
+        return;
+    };
+
+    let snapshot = buffer.read(cx).snapshot();
+    let path = snapshot.file().map(|file| file.path().to_path_buf());
+    let selections = editor.update(cx, |editor, cx| {
+        editor
+            .selections
+            .all::<usize>(cx)
+            .into_iter()
+            .map(|selection| BufferSelection {
+                range: selection.start..selection.end,
+                reversed: selection.reversed,
+            })
+            .collect()
+    });
+    let delegate = Arc::new(EditorBufferDelegate {
+        snapshot: snapshot.clone(),
+        path,
+        selections,
+    });
+
+    let command_name = registered.command.name.clone();
+    window
+        .spawn(cx, |mut cx| async move {
+            let result = async {
+                let edits = registered
+                    .extension
+                    .run_editor_command(registered.command, delegate.clone())
+                    .await?;
+                let edits = resolve_edits(&delegate, edits)?;
+                buffer.update(&mut cx, |buffer, cx| {
+                    let edits = edits.into_iter().map(|edit| {
+                        (
+                            snapshot.anchor_before(edit.range.start)
+                                ..snapshot.anchor_after(edit.range.end),
+                            edit.new_text,
+                        )
+                    });
+                    buffer.edit(edits, None, cx);
+                })
+            }
+            .await;
+
+            if let Err(error) = result {
+                workspace
+                    .update(&mut cx, |workspace, cx| {
+                        workspace.show_error(
+                            &error.context(format!("failed to run editor command {command_name}")),
+                            cx,
+                        )
+                    })
+                    .ok();
+            }
+        })
+        .detach();
+}
+
+/// Validates the edits returned by an editor command, ordering them by
+/// position.
+fn resolve_edits(
+    delegate: &EditorBufferDelegate,
+    mut edits: Vec<TextEdit>,
+) -> Result<Vec<TextEdit>> {
+    for edit in &edits {
+        delegate.validate_range(&edit.range)?;
+    }
+    edits.sort_by_key(|edit| (edit.range.start, edit.range.end));
+    for pair in edits.windows(2) {
+        if pair[0].range.end > pair[1].range.start {
+            return Err(anyhow!(
+                "edits at {:?} and {:?} overlap",
+                pair[0].range,
+                pair[1].range
+            ));
+        }
+    }
+    Ok(edits)
+}
+
+pub struct EditorCommandSelector {
+    picker: Entity<Picker<EditorCommandSelectorDelegate>>,
+}
+
+impl ModalView for EditorCommandSelector {}
+
+impl EventEmitter<DismissEvent> for EditorCommandSelector {}
+
+impl Focusable for EditorCommandSelector {
+    fn focus_handle(&self, cx: &App) -> gpui::FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl Render for EditorCommandSelector {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+impl EditorCommandSelector {
+    fn new(
+        delegate: EditorCommandSelectorDelegate,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let picker = cx.new(|cx| Picker::uniform_list(delegate, window, cx));
+        Self { picker }
+    }
+}
+
+pub struct EditorCommandSelectorDelegate {
+    selector: WeakEntity<EditorCommandSelector>,
+    workspace: WeakEntity<Workspace>,
+    editor: WeakEntity<Editor>,
+    commands: Vec<RegisteredEditorCommand>,
+    selected_index: usize,
+    matches: Vec<StringMatch>,
+}
+
+impl EditorCommandSelectorDelegate {
+    fn new(
+        selector: WeakEntity<EditorCommandSelector>,
+        workspace: WeakEntity<Workspace>,
+        editor: WeakEntity<Editor>,
+        commands: Vec<RegisteredEditorCommand>,
+    ) -> Self {
+        let matches = commands
+            .iter()
+            .enumerate()
+            .map(|(index, registered)| StringMatch {
+                candidate_id: index,
+                score: 0.0,
+                positions: Default::default(),
+                string: registered.command.name.clone(),
+            })
+            .collect();
+
+        Self {
+            selector,
+            workspace,
+            editor,
+            commands,
+            selected_index: 0,
+            matches,
+        }
+    }
+}
+
+impl PickerDelegate for EditorCommandSelectorDelegate {
+    type ListItem = ui::ListItem;
+
+    fn placeholder_text(&self, _window: &mut Window, _cx: &mut App) -> Arc<str> {
+        "Run extension editor command...".into()
+    }
+
+    fn no_matches_text(&self, _window: &mut Window, _cx: &mut App) -> SharedString {
+        if self.commands.is_empty() {
+            "No installed extension provides editor commands".into()
+        } else {
+            "No matches".into()
+        }
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(
+        &mut self,
+        ix: usize,
+        _window: &mut Window,
+        _cx: &mut Context<Picker<Self>>,
+    ) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(
+        &mut self,
+        query: String,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> Task<()> {
+        let background_executor = cx.background_executor().clone();
+        let candidates = self
+            .commands
+            .iter()
+            .enumerate()
+            .map(|(id, registered)| StringMatchCandidate::new(id, &registered.command.name))
+            .collect::<Vec<_>>();
+
+        cx.spawn_in(window, move |this, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, candidate)| StringMatch {
+                        candidate_id: index,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    100,
+                    &Default::default(),
+                    background_executor,
+                )
+                .await
+            };
+
+            this.update(&mut cx, |this, _cx| {
+                this.delegate.matches = matches;
+                this.delegate.selected_index = this
+                    .delegate
+                    .selected_index
+                    .min(this.delegate.matches.len().saturating_sub(1));
+            })
+            .log_err();
+        })
+    }
+
+    fn confirm(&mut self, _secondary: bool, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        let Some(command_match) = self.matches.get(self.selected_index) else {
+            self.dismissed(window, cx);
+            return;
+        };
+        let registered = self.commands[command_match.candidate_id].clone();
+
+        if let Some(editor) = self.editor.upgrade() {
+            run_editor_command(registered, editor, self.workspace.clone(), window, cx);
+        }
+        self.dismissed(window, cx);
+    }
+
+    fn dismissed(&mut self, _: &mut Window, cx: &mut Context<Picker<Self>>) {
+        self.selector
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _: &mut Window,
+        _cx: &mut Context<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let command_match = &self.matches[ix];
+        let registered = &self.commands[command_match.candidate_id];
+
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .toggle_state(selected)
+                .child(
+                    v_flex()
+                        .child(HighlightedLabel::new(
+                            command_match.string.clone(),
+                            command_match.positions.clone(),
+                        ))
+                        .child(
+                            Label::new(registered.command.description.clone())
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        ),
+                )
+                .end_slot(
+                    Label::new(registered.extension.manifest().name.clone())
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use language::{Buffer, Language, LanguageConfig};
+
+    fn buffer_delegate(
+        text: &str,
+        language: Option<Language>,
+        cx: &mut TestAppContext,
+    ) -> EditorBufferDelegate {
+        let buffer = cx.new(|cx| {
+            let buffer = Buffer::local(text, cx);
+            match language {
+                Some(language) => buffer.with_language(Arc::new(language), cx),
+                None => buffer,
+            }
+        });
+        cx.run_until_parked();
+        EditorBufferDelegate {
+            snapshot: buffer.read_with(cx, |buffer, _| buffer.snapshot()),
+            path: None,
+            selections: Vec::new(),
+        }
+    }
+
+    fn edit(range: Range<usize>, new_text: &str) -> TextEdit {
+        TextEdit {
+            range,
+            new_text: new_text.into(),
+        }
+    }
+
+    fn rust_lang() -> Language {
+        Language::new(
+            LanguageConfig {
+                name: "Rust".into(),
+                ..Default::default()
+            },
+            Some(tree_sitter_rust::LANGUAGE.into()),
+        )
+    }
+
+    #[gpui::test]
+    fn test_validate_range(cx: &mut TestAppContext) {
+        let delegate = buffer_delegate("héllo", None, cx);
+        assert!(delegate.validate_range(&(0..6)).is_ok());
+        assert!(delegate.validate_range(&(6..6)).is_ok());
+        // Out of bounds.
+        assert!(delegate.validate_range(&(0..7)).is_err());
+        assert!(delegate.validate_range(&(7..7)).is_err());
+        // Reversed.
+        assert!(delegate.validate_range(&(4..1)).is_err());
+        // Inside the two bytes of `é`.
+        assert!(delegate.validate_range(&(2..4)).is_err());
+    }
+
+    #[gpui::test]
+    fn test_resolve_edits(cx: &mut TestAppContext) {
+        let delegate = buffer_delegate("one two three", None, cx);
+
+        let edits = resolve_edits(
+            &delegate,
+            vec![edit(8..13, "3"), edit(0..3, "1"), edit(4..4, "and ")],
+        )
+        .unwrap();
+        assert_eq!(
+            edits
+                .iter()
+                .map(|edit| (edit.range.clone(), edit.new_text.as_str()))
+                .collect::<Vec<_>>(),
+            [(0..3, "1"), (4..4, "and "), (8..13, "3")]
+        );
+
+        // Adjacent edits don't overlap.
+        assert!(resolve_edits(&delegate, vec![edit(0..3, ""), edit(3..4, "")]).is_ok());
+        assert!(resolve_edits(&delegate, vec![edit(4..8, "a"), edit(0..5, "b")]).is_err());
+        assert!(resolve_edits(&delegate, vec![edit(0..3, "1"), edit(10..20, "3")]).is_err());
+        assert!(resolve_edits(&delegate, vec![edit(3..0, "1")]).is_err());
+    }
+
+    #[gpui::test]
+    fn test_syntax_ancestors(cx: &mut TestAppContext) {
+        let text = "fn main() { let x = 1; }";
+        let delegate = buffer_delegate(text, Some(rust_lang()), cx);
+
+        let literal = text.find('1').unwrap();
+        let ancestors = delegate.syntax_ancestors(literal..literal + 1);
+        assert_eq!(
+            ancestors
+                .iter()
+                .map(|node| node.kind.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "integer_literal",
+                "let_declaration",
+                "block",
+                "function_item",
+                "source_file"
+            ]
+        );
+        assert_eq!(ancestors[0].range, literal..literal + 1);
+        assert_eq!(ancestors.last().unwrap().range, 0..text.len());
+
+        assert!(delegate.syntax_ancestors(0..text.len() + 1).is_empty());
+        assert!(delegate.syntax_ancestors(5..2).is_empty());
+
+        let plain_text = buffer_delegate(text, None, cx);
+        assert!(plain_text.syntax_ancestors(0..2).is_empty());
+    }
+}
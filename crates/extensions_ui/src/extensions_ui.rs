@@ -1,25 +1,30 @@
 mod components;
+mod editor_commands;
 mod extension_suggest;
 mod extension_version_selector;
 
+use std::str::FromStr;
 use std::sync::OnceLock;
 use std::time::Duration;
 use std::{ops::Range, sync::Arc};
 
+use anyhow::Result;
 use client::{ExtensionMetadata, ExtensionProvides};
 use collections::{BTreeMap, BTreeSet};
 use editor::{Editor, EditorElement, EditorStyle};
-use extension_host::{ExtensionManifest, ExtensionOperation, ExtensionStore};
+use extension::ExtensionCapabilities;
+use extension_host::{ExtensionManifest, ExtensionOperation, ExtensionSettings, ExtensionStore};
 use feature_flags::FeatureFlagAppExt as _;
 use fuzzy::{match_strings, StringMatchCandidate};
 use gpui::{
     actions, uniform_list, Action, App, ClipboardItem, Context, Entity, EventEmitter, Flatten,
-    Focusable, InteractiveElement, KeyContext, ParentElement, Render, Styled, Task, TextStyle,
-    UniformListScrollHandle, WeakEntity, Window,
+    Focusable, InteractiveElement, KeyContext, ParentElement, PromptLevel, Render, Styled, Task,
+    TextStyle, UniformListScrollHandle, WeakEntity, Window,
 };
 use num_format::{Locale, ToFormattedString};
 use project::DirectoryLister;
 use release_channel::ReleaseChannel;
+use semantic_version::SemanticVersion;
 use settings::Settings;
 use theme::ThemeSettings;
 use ui::{prelude::*, CheckboxWithLabel, ContextMenu, PopoverMenu, ToggleButton, Tooltip};
@@ -34,9 +39,16 @@ use crate::extension_version_selector::{
     ExtensionVersionSelector, ExtensionVersionSelectorDelegate,
 };
 
-actions!(zed, [InstallDevExtension]);
+actions!(zed, [InstallDevExtension, InstallExtensionFromArchive]);
 
 pub fn init(cx: &mut App) {
+    editor_commands::init(cx);
+    if let Some(store) = ExtensionStore::try_global(cx) {
+        store.update(cx, |store, _| {
+            store.set_capabilities_prompt(Arc::new(confirm_extension_capabilities))
+        });
+    }
+
     cx.observe_new(move |workspace: &mut Workspace, window, cx| {
         let Some(window) = window else {
             return;
@@ -115,7 +127,62 @@ pub fn init(cx: &mut App) {
                         Some(())
                     })
                     .detach();
-            });
+            })
+            .register_action(
+                move |workspace, _: &InstallExtensionFromArchive, window, cx| {
+                    let store = ExtensionStore::global(cx);
+                    let prompt = workspace.prompt_for_open_path(
+                        gpui::PathPromptOptions {
+                            files: true,
+                            directories: false,
+                            multiple: false,
+                        },
+                        DirectoryLister::Local(workspace.app_state().fs.clone()),
+                        window,
+                        cx,
+                    );
+
+                    let workspace_handle = cx.entity().downgrade();
+                    window
+                        .spawn(cx, |mut cx| async move {
+                            let archive_path =
+                                match Flatten::flatten(prompt.await.map_err(|e| e.into())) {
+                                    Ok(Some(mut paths)) => paths.pop()?,
+                                    Ok(None) => return None,
+                                    Err(err) => {
+                                        workspace_handle
+                                            .update(&mut cx, |workspace, cx| {
+                                                workspace.show_portal_error(err.to_string(), cx);
+                                            })
+                                            .ok();
+                                        return None;
+                                    }
+                                };
+
+                            let install_task = store
+                                .update(&mut cx, |store, cx| {
+                                    store.install_extension_from_archive(archive_path, cx)
+                                })
+                                .ok()?;
+
+                            if let Err(err) = install_task.await {
+                                workspace_handle
+                                    .update(&mut cx, |workspace, cx| {
+                                        workspace.show_error(
+                                            &err.context(
+                                                "failed to install extension from archive",
+                                            ),
+                                            cx,
+                                        );
+                                    })
+                                    .ok();
+                            }
+
+                            Some(())
+                        })
+                        .detach();
+                },
+            );
 
         cx.subscribe_in(workspace.project(), window, |_, _, event, window, cx| {
             if let project::Event::LanguageNotFound(buffer) = event {
@@ -127,6 +194,60 @@ pub fn init(cx: &mut App) {
     .detach();
 }
 
+/// Asks the user to confirm the capabilities that an extension will be granted
+/// before it's installed.
+fn confirm_extension_capabilities(
+    manifest: Arc<ExtensionManifest>,
+    capabilities: ExtensionCapabilities,
+    cx: &mut App,
+) -> Task<Result<bool>> {
+    let is_overridden = ExtensionSettings::get_global(cx)
+        .extension_capabilities
+        .contains_key(&manifest.id);
+    let mut detail = if manifest.capabilities.is_none() && !is_overridden {
+        "This extension does not declare its capabilities, so it may run any command and access any host.".to_string()
+    } else if capabilities.is_empty() {
+        return Task::ready(Ok(true));
+    } else {
+        let mut lines = Vec::new();
+        if !capabilities.commands.is_empty() {
+            lines.push(format!(
+                "Run commands: {}",
+                capabilities.commands.join(", ")
+            ));
+        }
+        if !capabilities.hosts.is_empty() {
+            lines.push(format!("Access hosts: {}", capabilities.hosts.join(", ")));
+        }
+        if !capabilities.paths.is_empty() {
+            lines.push(format!(
+                "Read directories: {}",
+                capabilities.paths.join(", ")
+            ));
+        }
+        lines.join("\n")
+    };
+    detail.push_str(
+        "\n\nYou can change the capabilities of this extension with the `extension_capabilities` setting.",
+    );
+
+    // Extensions are only installed without a window when the user's settings
+    // ask for it, such as with `auto_install_extensions`.
+    let Some(window) = cx.active_window() else {
+        return Task::ready(Ok(true));
+    };
+    let answer = window.update(cx, |_, window, cx| {
+        window.prompt(
+            PromptLevel::Info,
+            &format!("Install {} with the following capabilities?", manifest.name),
+            Some(&detail),
+            &["Install", "Cancel"],
+            cx,
+        )
+    });
+    cx.background_spawn(async move { Ok(answer?.await? == 0) })
+}
+
 #[derive(Clone)]
 pub enum ExtensionStatus {
     NotInstalled,
@@ -564,7 +685,6 @@ impl ExtensionsPage {
         let status = Self::extension_status(&extension.id, cx);
         let has_dev_extension = Self::dev_extension_exists(&extension.id, cx);
 
-        let extension_id = extension.id.clone();
         let (install_or_uninstall_button, upgrade_button) =
             self.buttons_for_entry(extension, &status, has_dev_extension, cx);
         let version = extension.manifest.version.clone();
@@ -574,6 +694,17 @@ impl ExtensionsPage {
             ExtensionStatus::Installed(installed_version) => Some(installed_version),
             _ => None,
         };
+        let update_available = installed_version
+            .as_ref()
+            .map_or(false, |installed_version| {
+                let installed_version = SemanticVersion::from_str(installed_version).ok();
+                let version = SemanticVersion::from_str(&version).ok();
+                version > installed_version
+                    && extension_host::is_version_compatible(ReleaseChannel::global(cx), extension)
+            });
+        let pinned_version = ExtensionSettings::get_global(cx)
+            .pinned_version(&extension.id)
+            .cloned();
 
         ExtensionCard::new()
             .overridden_by_dev_extension(has_dev_extension)
@@ -596,6 +727,20 @@ impl ExtensionsPage {
                                             .size(HeadlineSize::XSmall)
                                     }),
                             )
+                            .when(update_available, |parent| {
+                                parent.child(Self::render_badge(
+                                    "Update Available",
+                                    Color::Accent,
+                                    cx,
+                                ))
+                            })
+                            .when_some(pinned_version, |parent, pinned_version| {
+                                parent.child(Self::render_badge(
+                                    format!("Pinned to v{pinned_version}"),
+                                    Color::Muted,
+                                    cx,
+                                ))
+                            })
                             .map(|parent| {
                                 if extension.manifest.provides.is_empty() {
                                     return parent;
@@ -721,7 +866,7 @@ impl ExtensionsPage {
                                 .menu(move |window, cx| {
                                     Some(Self::render_remote_extension_context_menu(
                                         &this,
-                                        extension_id.clone(),
+                                        extension.clone(),
                                         window,
                                         cx,
                                     ))
@@ -731,12 +876,34 @@ impl ExtensionsPage {
             )
     }
 
+    fn render_badge(label: impl Into<SharedString>, color: Color, cx: &App) -> impl IntoElement {
+        div()
+            .bg(cx.theme().colors().element_background)
+            .px_0p5()
+            .border_1()
+            .border_color(cx.theme().colors().border)
+            .rounded_md()
+            .child(Label::new(label).size(LabelSize::XSmall).color(color))
+    }
+
     fn render_remote_extension_context_menu(
         this: &Entity<Self>,
-        extension_id: Arc<str>,
+        extension: ExtensionMetadata,
         window: &mut Window,
         cx: &mut App,
     ) -> Entity<ContextMenu> {
+        let extension_id = extension.id.clone();
+        let extension_store = ExtensionStore::global(cx).read(cx);
+        let installed_version = extension_store
+            .installed_extensions()
+            .get(&extension_id)
+            .filter(|entry| !entry.dev)
+            .map(|entry| entry.manifest.version.clone());
+        let previous_version = extension_store.previous_version(&extension_id).cloned();
+        let extension_settings = ExtensionSettings::get_global(cx);
+        let pinned_version = extension_settings.pinned_version(&extension_id).cloned();
+        let auto_update = extension_settings.should_auto_update(&extension_id);
+
         let context_menu = ContextMenu::build(window, cx, |context_menu, window, _| {
             context_menu
                 .entry(
@@ -749,6 +916,89 @@ impl ExtensionsPage {
                         }
                     }),
                 )
+                .when_some(installed_version, |context_menu, installed_version| {
+                    let extension_id = extension_id.clone();
+                    match pinned_version {
+                        Some(_) => context_menu.entry(
+                            "Unpin Version",
+                            None,
+                            window.handler_for(this, {
+                                let extension_id = extension_id.clone();
+                                move |this, _, cx| {
+                                    let extension_id = extension_id.clone();
+                                    this.update_extension_settings(cx, move |settings| {
+                                        settings.pinned_extensions.remove(&extension_id);
+                                    });
+                                }
+                            }),
+                        ),
+                        None => context_menu
+                            .entry(
+                                format!("Pin to v{installed_version}"),
+                                None,
+                                window.handler_for(this, {
+                                    let extension_id = extension_id.clone();
+                                    move |this, _, cx| {
+                                        let extension_id = extension_id.clone();
+                                        let installed_version = installed_version.clone();
+                                        this.update_extension_settings(cx, move |settings| {
+                                            settings
+                                                .pinned_extensions
+                                                .insert(extension_id, installed_version);
+                                        });
+                                    }
+                                }),
+                            )
+                            .entry(
+                                if auto_update {
+                                    "Disable Auto-Updates"
+                                } else {
+                                    "Enable Auto-Updates"
+                                },
+                                None,
+                                window.handler_for(this, {
+                                    let extension_id = extension_id.clone();
+                                    move |this, _, cx| {
+                                        let extension_id = extension_id.clone();
+                                        this.update_extension_settings(cx, move |settings| {
+                                            if auto_update {
+                                                settings
+                                                    .auto_update_extensions
+                                                    .insert(extension_id, false);
+                                            } else {
+                                                settings
+                                                    .auto_update_extensions
+                                                    .remove(&extension_id);
+                                            }
+                                        });
+                                    }
+                                }),
+                            ),
+                    }
+                    .when_some(
+                        previous_version,
+                        |context_menu, previous_version| {
+                            context_menu.entry(
+                                format!("Roll Back to v{previous_version}"),
+                                None,
+                                window.handler_for(this, move |this, _, cx| {
+                                    this.rollback_extension(
+                                        extension_id.clone(),
+                                        previous_version.clone(),
+                                        cx,
+                                    )
+                                }),
+                            )
+                        },
+                    )
+                })
+                .entry(
+                    "View Changelog",
+                    None,
+                    window.handler_for(this, move |this, window, cx| {
+                        this.show_extension_changelog(&extension, window, cx)
+                    }),
+                )
                 .entry("Copy Extension ID", None, {
                     let extension_id = extension_id.clone();
                     move |_, cx| {
@@ -760,6 +1010,97 @@ impl ExtensionsPage {
         context_menu
     }
 
+    fn update_extension_settings(
+        &mut self,
+        cx: &mut Context<Self>,
+        callback: impl 'static + Send + FnOnce(&mut ExtensionSettings),
+    ) {
+        if let Some(workspace) = self.workspace.upgrade() {
+            let fs = workspace.read(cx).app_state().fs.clone();
+            settings::update_settings_file::<ExtensionSettings>(fs, cx, move |settings, _| {
+                callback(settings)
+            });
+        }
+    }
+
+    /// Rolls the extension back to its previously installed version, and pins
+    /// it to that version so that it is not upgraded again automatically.
+    fn rollback_extension(
+        &mut self,
+        extension_id: Arc<str>,
+        previous_version: Arc<str>,
+        cx: &mut Context<Self>,
+    ) {
+        telemetry::event!(
+            "Extension Rolled Back",
+            extension_id,
+            version = previous_version
+        );
+        let rollback = ExtensionStore::global(cx).update(cx, |store, cx| {
+            store.rollback_extension(extension_id.clone(), cx)
+        });
+        cx.spawn(|this, mut cx| async move {
+            rollback.await?;
+            this.update(&mut cx, |this, cx| {
+                this.update_extension_settings(cx, move |settings| {
+                    settings
+                        .pinned_extensions
+                        .insert(extension_id, previous_version);
+                });
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Shows the changelog of the given extension version.
+    ///
+    /// The changelog packaged with the extension is opened when that version is
+    /// installed. Otherwise, the releases page of the extension's repository is opened.
+    fn show_extension_changelog(
+        &mut self,
+        extension: &ExtensionMetadata,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+
+        let extension_store = ExtensionStore::global(cx).read(cx);
+        let changelog_path = extension_store
+            .installed_extensions()
+            .get(&extension.id)
+            .filter(|entry| entry.manifest.version == extension.manifest.version)
+            .map(|_| {
+                extension_store
+                    .installed_dir
+                    .join(extension.id.as_ref())
+                    .join("CHANGELOG.md")
+            });
+        let releases_url = format!(
+            "{}/releases",
+            extension.manifest.repository.trim_end_matches('/')
+        );
+        let fs = workspace.read(cx).app_state().fs.clone();
+
+        cx.spawn_in(window, move |_, mut cx| async move {
+            if let Some(changelog_path) = changelog_path {
+                if fs.is_file(&changelog_path).await {
+                    workspace
+                        .update_in(&mut cx, |workspace, window, cx| {
+                            workspace.open_abs_path(changelog_path, false, window, cx)
+                        })?
+                        .await?;
+                    return anyhow::Ok(());
+                }
+            }
+
+            cx.update(|_, cx| cx.open_url(&releases_url))?;
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
     fn show_extension_version_list(
         &mut self,
         extension_id: Arc<str>,
@@ -841,20 +1182,24 @@ impl ExtensionsPage {
                     Button::new(SharedString::from(extension.id.clone()), "Upgrade").disabled(true),
                 ),
             ),
-            ExtensionStatus::Installed(installed_version) => (
-                Button::new(SharedString::from(extension.id.clone()), "Uninstall").on_click({
-                    let extension_id = extension.id.clone();
-                    move |_, _, cx| {
-                        telemetry::event!("Extension Uninstalled", extension_id);
-                        ExtensionStore::global(cx).update(cx, |store, cx| {
-                            store.uninstall_extension(extension_id.clone(), cx)
-                        });
-                    }
-                }),
-                if installed_version == extension.manifest.version {
-                    None
-                } else {
-                    Some(
+            ExtensionStatus::Installed(installed_version) => {
+                let is_pinned = ExtensionSettings::get_global(cx)
+                    .pinned_version(&extension.id)
+                    .is_some();
+                (
+                    Button::new(SharedString::from(extension.id.clone()), "Uninstall").on_click({
+                        let extension_id = extension.id.clone();
+                        move |_, _, cx| {
+                            telemetry::event!("Extension Uninstalled", extension_id);
+                            ExtensionStore::global(cx).update(cx, |store, cx| {
+                                store.uninstall_extension(extension_id.clone(), cx)
+                            });
+                        }
+                    }),
+                    if installed_version == extension.manifest.version {
+                        None
+                    } else {
+                        Some(
                         Button::new(SharedString::from(extension.id.clone()), "Upgrade")
                             .when(!is_compatible, |upgrade_button| {
                                 upgrade_button.disabled(true).tooltip({
@@ -869,7 +1214,12 @@ impl ExtensionsPage {
                                     }
                                 })
                             })
-                            .disabled(!is_compatible)
+                            .when(is_compatible && is_pinned, |upgrade_button| {
+                                upgrade_button.tooltip(Tooltip::text(
+                                    "Unpin this extension's version to upgrade it.",
+                                ))
+                            })
+                            .disabled(!is_compatible || is_pinned)
                             .on_click({
                                 let extension_id = extension.id.clone();
                                 let version = extension.manifest.version.clone();
@@ -887,8 +1237,9 @@ impl ExtensionsPage {
                                 }
                             }),
                     )
-                },
-            ),
+                    },
+                )
+            }
             ExtensionStatus::Removing => (
                 Button::new(SharedString::from(extension.id.clone()), "Uninstall").disabled(true),
                 None,
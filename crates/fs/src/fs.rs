@@ -45,7 +45,7 @@ use std::{
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use tempfile::{NamedTempFile, TempDir};
-use text::LineEnding;
+use text::{Encoding, LineEnding};
 use util::ResultExt;
 
 #[cfg(any(test, feature = "test-support"))]
@@ -114,8 +114,42 @@ pub trait Fs: Send + Sync {
         Ok(String::from_utf8(self.load_bytes(path).await?)?)
     }
     async fn load_bytes(&self, path: &Path) -> Result<Vec<u8>>;
-    async fn atomic_write(&self, path: PathBuf, text: String) -> Result<()>;
-    async fn save(&self, path: &Path, text: &Rope, line_ending: LineEnding) -> Result<()>;
+    /// Loads a text file, detecting its encoding unless one is given.
+    async fn load_with_encoding(
+        &self,
+        path: &Path,
+        encoding: Option<Encoding>,
+    ) -> Result<(String, Encoding)> {
+        let bytes = self.load_bytes(path).await?;
+        let encoding = match encoding {
+            Some(encoding) => encoding,
+            None => Encoding::detect(&bytes).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "stream did not contain valid text",
+                )
+            })?,
+        };
+        Ok(encoding.decode(bytes))
+    }
+    async fn atomic_write(&self, path: PathBuf, text: String) -> Result<()> {
+        self.atomic_write_bytes(path, text.into_bytes()).await
+    }
+    async fn atomic_write_bytes(&self, path: PathBuf, data: Vec<u8>) -> Result<()>;
+    /// Overwrites the contents of a file in place, so that unlike [`Fs::atomic_write_bytes`]
+    /// it keeps the file's permissions and ownership.
+    async fn write_bytes(&self, path: &Path, data: Vec<u8>) -> Result<()>;
+    async fn save(&self, path: &Path, text: &Rope, line_ending: LineEnding) -> Result<()> {
+        self.save_with_encoding(path, text, line_ending, Encoding::default())
+            .await
+    }
+    async fn save_with_encoding(
+        &self,
+        path: &Path,
+        text: &Rope,
+        line_ending: LineEnding,
+        encoding: Encoding,
+    ) -> Result<()>;
     async fn canonicalize(&self, path: &Path) -> Result<PathBuf>;
     async fn is_file(&self, path: &Path) -> bool;
     async fn is_dir(&self, path: &Path) -> bool;
@@ -526,7 +560,7 @@ impl Fs for RealFs {
         Ok(bytes)
     }
 
-    async fn atomic_write(&self, path: PathBuf, data: String) -> Result<()> {
+    async fn atomic_write_bytes(&self, path: PathBuf, data: Vec<u8>) -> Result<()> {
         smol::unblock(move || {
             let mut tmp_file = if cfg!(any(target_os = "linux", target_os = "freebsd")) {
                 // Use the directory of the destination as temp dir to avoid
@@ -546,7 +580,7 @@ impl Fs for RealFs {
             } else {
                 NamedTempFile::new()
             }?;
-            tmp_file.write_all(data.as_bytes())?;
+            tmp_file.write_all(&data)?;
             tmp_file.persist(path)?;
             Ok::<(), anyhow::Error>(())
         })
@@ -555,15 +589,42 @@ impl Fs for RealFs {
         Ok(())
     }
 
-    async fn save(&self, path: &Path, text: &Rope, line_ending: LineEnding) -> Result<()> {
+    async fn write_bytes(&self, path: &Path, data: Vec<u8>) -> Result<()> {
+        let mut file = smol::fs::File::create(path).await?;
+        file.write_all(&data).await?;
+        file.flush().await?;
+        Ok(())
+    }
+
+    async fn save_with_encoding(
+        &self,
+        path: &Path,
+        text: &Rope,
+        line_ending: LineEnding,
+        encoding: Encoding,
+    ) -> Result<()> {
+        // Encode the text up front, so that a file isn't truncated when the text can't be
+        // represented in its encoding. UTF-8 text is streamed as is.
+        let encoded = if encoding.is_utf8() {
+            None
+        } else {
+            let content = chunks(text, line_ending).collect::<String>();
+            Some(encoding.encode(&content)?.into_owned())
+        };
+
         let buffer_size = text.summary().len.min(10 * 1024);
         if let Some(path) = path.parent() {
             self.create_dir(path).await?;
         }
         let file = smol::fs::File::create(path).await?;
         let mut writer = smol::io::BufWriter::with_capacity(buffer_size, file);
-        for chunk in chunks(text, line_ending) {
-            writer.write_all(chunk.as_bytes()).await?;
+        if let Some(encoded) = encoded {
+            writer.write_all(&encoded).await?;
+        } else {
+            writer.write_all(encoding.bom()).await?;
+            for chunk in chunks(text, line_ending) {
+                writer.write_all(chunk.as_bytes()).await?;
+            }
         }
         writer.flush().await?;
         Ok(())
@@ -1896,21 +1957,35 @@ impl Fs for FakeFs {
         self.load_internal(path).await
     }
 
-    async fn atomic_write(&self, path: PathBuf, data: String) -> Result<()> {
+    async fn atomic_write_bytes(&self, path: PathBuf, data: Vec<u8>) -> Result<()> {
         self.simulate_random_delay().await;
         let path = normalize_path(path.as_path());
-        self.write_file_internal(path, data.into_bytes())?;
+        self.write_file_internal(path, data)?;
         Ok(())
     }
 
-    async fn save(&self, path: &Path, text: &Rope, line_ending: LineEnding) -> Result<()> {
+    async fn write_bytes(&self, path: &Path, data: Vec<u8>) -> Result<()> {
+        self.simulate_random_delay().await;
+        let path = normalize_path(path);
+        self.write_file_internal(path, data)?;
+        Ok(())
+    }
+
+    async fn save_with_encoding(
+        &self,
+        path: &Path,
+        text: &Rope,
+        line_ending: LineEnding,
+        encoding: Encoding,
+    ) -> Result<()> {
         self.simulate_random_delay().await;
         let path = normalize_path(path);
         let content = chunks(text, line_ending).collect::<String>();
+        let content = encoding.encode(&content)?.into_owned();
         if let Some(path) = path.parent() {
             self.create_dir(path).await?;
         }
-        self.write_file_internal(path, content.into_bytes())?;
+        self.write_file_internal(path, content)?;
         Ok(())
     }
 
@@ -2649,4 +2724,77 @@ mod tests {
             "B"
         );
     }
+
+    #[gpui::test]
+    async fn test_encoding_round_trip(executor: BackgroundExecutor) {
+        let fs = FakeFs::new(executor.clone());
+        let for_label = |label| Encoding::for_label(label).unwrap();
+        let cases = [
+            (
+                path!("/shift_jis.txt"),
+                "こんにちは、世界\n",
+                for_label("shift_jis"),
+            ),
+            (path!("/utf16.txt"), "café 世界\r\n", for_label("utf-16le")),
+            (
+                path!("/bom.txt"),
+                "café\n",
+                for_label("utf-8").with_bom(true),
+            ),
+            (path!("/latin1.txt"), "café crème\n", for_label("latin1")),
+        ];
+        for (path, text, encoding) in cases {
+            let bytes = encoding.encode(text).unwrap().into_owned();
+            fs.insert_file(path, bytes.clone()).await;
+
+            let (loaded, detected) = fs.load_with_encoding(path.as_ref(), None).await.unwrap();
+            assert_eq!(loaded, text);
+            assert_eq!(detected, encoding);
+
+            let line_ending = LineEnding::detect(&loaded);
+            let mut normalized = loaded;
+            LineEnding::normalize(&mut normalized);
+            fs.save_with_encoding(
+                path.as_ref(),
+                &Rope::from(normalized.as_str()),
+                line_ending,
+                detected,
+            )
+            .await
+            .unwrap();
+            assert_eq!(fs.load_bytes(path.as_ref()).await.unwrap(), bytes);
+        }
+
+        fs.insert_file(
+            path!("/binary"),
+            vec![0x7f, b'E', b'L', b'F', 0, 0xff, 0, 0],
+        )
+        .await;
+        let error = fs
+            .load_with_encoding(path!("/binary").as_ref(), None)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<io::Error>().unwrap().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        // Text that the file's encoding can't represent is rejected without touching the file.
+        let latin1 = for_label("latin1");
+        let result = fs
+            .save_with_encoding(
+                path!("/latin1.txt").as_ref(),
+                &Rope::from("世界"),
+                LineEnding::Unix,
+                latin1,
+            )
+            .await;
+        assert!(result.is_err());
+        assert_eq!(
+            fs.load_with_encoding(path!("/latin1.txt").as_ref(), Some(latin1))
+                .await
+                .unwrap(),
+            ("café crème\n".to_string(), latin1)
+        );
+    }
 }
@@ -0,0 +1,150 @@
+use anyhow::{anyhow, bail, Result};
+
+/// A sequence of bytes to search for, where `None` matches any byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BytePattern(Vec<Option<u8>>);
+
+impl BytePattern {
+    /// Parses a pattern written either as hex digits, such as `7f 45 4c 46`, where `??`
+    /// matches any byte, or as text in double quotes, such as `"ELF"`.
+    pub fn parse(query: &str) -> Result<Self> {
+        let query = query.trim();
+        if let Some(text) = query
+            .strip_prefix('"')
+            .and_then(|query| query.strip_suffix('"'))
+        {
+            if text.is_empty() {
+                bail!("the pattern is empty");
+            }
+            return Ok(Self(text.bytes().map(Some).collect()));
+        }
+
+        let digits = query
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<Vec<_>>();
+        if digits.is_empty() {
+            bail!("the pattern is empty");
+        }
+        if digits.len() % 2 != 0 {
+            bail!("the pattern has an odd number of hex digits");
+        }
+
+        digits
+            .chunks(2)
+            .map(|pair| match pair {
+                ['?', '?'] => Ok(None),
+                [high, low] => {
+                    let high = high.to_digit(16);
+                    let low = low.to_digit(16);
+                    high.zip(low)
+                        .map(|(high, low)| Some((high * 16 + low) as u8))
+                        .ok_or_else(|| anyhow!("{}{} isn't a hex byte", pair[0], pair[1]))
+                }
+                _ => unreachable!(),
+            })
+            .collect::<Result<_>>()
+            .map(Self)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn matches(&self, bytes: &[u8]) -> bool {
+        self.0
+            .iter()
+            .zip(bytes)
+            .all(|(expected, byte)| expected.map_or(true, |expected| expected == *byte))
+    }
+
+    /// Finds the first occurrence of the pattern at or after `start`, wrapping around to the
+    /// start of the bytes if there's none.
+    pub fn find_next(&self, bytes: &[u8], start: usize) -> Option<usize> {
+        let last_start = bytes.len().checked_sub(self.len())?;
+        let start = start.min(last_start + 1);
+        (start..=last_start)
+            .chain(0..start)
+            .find(|&ix| self.matches(&bytes[ix..ix + self.len()]))
+    }
+
+    /// Finds the last occurrence of the pattern before `end`, wrapping around to the end of
+    /// the bytes if there's none.
+    pub fn find_prev(&self, bytes: &[u8], end: usize) -> Option<usize> {
+        let last_start = bytes.len().checked_sub(self.len())?;
+        let end = end.min(last_start + 1);
+        (0..end)
+            .rev()
+            .chain((end..=last_start).rev())
+            .find(|&ix| self.matches(&bytes[ix..ix + self.len()]))
+    }
+}
+
+/// Parses an offset written in decimal, or in hex with a `0x` prefix.
+pub fn parse_offset(offset: &str) -> Result<usize> {
+    let offset = offset.trim();
+    let parsed = match offset
+        .strip_prefix("0x")
+        .or_else(|| offset.strip_prefix("0X"))
+    {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => offset.parse(),
+    };
+    parsed.map_err(|_| anyhow!("{offset:?} isn't an offset"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pattern() {
+        assert_eq!(
+            BytePattern::parse("7f 45 4C46").unwrap(),
+            BytePattern(vec![Some(0x7f), Some(0x45), Some(0x4c), Some(0x46)])
+        );
+        assert_eq!(
+            BytePattern::parse("00 ?? ff").unwrap(),
+            BytePattern(vec![Some(0x00), None, Some(0xff)])
+        );
+        assert_eq!(
+            BytePattern::parse("\"ELF\"").unwrap(),
+            BytePattern(vec![Some(b'E'), Some(b'L'), Some(b'F')])
+        );
+        assert!(BytePattern::parse("").is_err());
+        assert!(BytePattern::parse("abc").is_err());
+        assert!(BytePattern::parse("zz").is_err());
+    }
+
+    #[test]
+    fn test_find() {
+        let bytes = [0x00, 0x01, 0x02, 0x00, 0x01, 0x03];
+        let pattern = BytePattern::parse("00 01").unwrap();
+        assert_eq!(pattern.find_next(&bytes, 0), Some(0));
+        assert_eq!(pattern.find_next(&bytes, 1), Some(3));
+        assert_eq!(pattern.find_next(&bytes, 4), Some(0));
+        assert_eq!(pattern.find_prev(&bytes, 3), Some(0));
+        assert_eq!(pattern.find_prev(&bytes, 0), Some(3));
+
+        let wildcard = BytePattern::parse("01 ??").unwrap();
+        assert_eq!(wildcard.find_next(&bytes, 2), Some(4));
+        assert_eq!(BytePattern::parse("04").unwrap().find_next(&bytes, 0), None);
+        assert_eq!(
+            BytePattern::parse("00 01 02 00 01 03 04")
+                .unwrap()
+                .find_next(&bytes, 0),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_offset() {
+        assert_eq!(parse_offset("0x1f").unwrap(), 31);
+        assert_eq!(parse_offset(" 31 ").unwrap(), 31);
+        assert!(parse_offset("1f").is_err());
+    }
+}
@@ -0,0 +1,1166 @@
+//! # Hex Editor
+//!
+//! Files that can't be opened as buffers because they look binary are opened in a hex
+//! editor instead. It shows the bytes of the file as rows of offsets, hex and ASCII, and lets
+//! them be overwritten, searched for byte patterns and jumped to by offset.
+//!
+//! The whole file is kept in memory, so files larger than the `large_file_threshold` worktree
+//! setting are left to the large-file view. Changes made to the file on disk are reloaded
+//! unless there are unsaved edits, in which case saving asks before overwriting them.
+
+mod byte_pattern;
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context as _, Result};
+use editor::{items::entry_git_aware_label_color, Editor};
+use file_icons::FileIcons;
+use gpui::{
+    actions, uniform_list, AnyElement, App, Context, Entity, EventEmitter, FocusHandle, Focusable,
+    InteractiveElement, IntoElement, KeyDownEvent, MouseButton, ParentElement, Render,
+    ScrollStrategy, Styled, Subscription, Task, UniformListScrollHandle, Window,
+};
+use project::{
+    Fs, MTime, Project, ProjectEntryId, ProjectPath, UpdatedEntriesSet, Worktree, WorktreeSettings,
+};
+use settings::{Settings, SettingsLocation};
+use ui::{prelude::*, Tooltip};
+use util::paths::PathExt;
+use workspace::{
+    item::{Item, ItemEvent, ProjectItem, TabContentParams},
+    ItemSettings, WorkspaceId,
+};
+
+pub use crate::byte_pattern::*;
+
+actions!(
+    hex_editor,
+    [
+        MoveLeft,
+        MoveRight,
+        MoveUp,
+        MoveDown,
+        MovePageUp,
+        MovePageDown,
+        SwitchColumn,
+        GoToOffset,
+        Search,
+        SelectNextMatch,
+        SelectPrevMatch,
+    ]
+);
+
+const BYTES_PER_ROW: usize = 16;
+const ROWS_PER_PAGE: usize = 32;
+
+pub fn init(cx: &mut App) {
+    workspace::register_project_item::<HexEditor>(cx);
+}
+
+/// The contents of a binary file, along with any unsaved changes to them.
+pub struct HexFile {
+    project_path: ProjectPath,
+    entry_id: Option<ProjectEntryId>,
+    abs_path: PathBuf,
+    fs: Arc<dyn Fs>,
+    bytes: Vec<u8>,
+    /// The bytes that have been changed since the file was last loaded or saved, keyed by
+    /// their offsets, along with the values they had then.
+    modified: BTreeMap<usize, u8>,
+    /// The modification time of the file when it was last loaded or saved.
+    saved_mtime: Option<MTime>,
+    /// The latest modification time of the file on disk.
+    disk_mtime: Option<MTime>,
+    deleted: bool,
+}
+
+pub enum HexFileEvent {
+    Edited,
+    Saved,
+    Reloaded,
+    /// The file changed on disk while it had unsaved edits, or was deleted.
+    FileChanged,
+}
+
+impl EventEmitter<HexFileEvent> for HexFile {}
+
+impl HexFile {
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn is_modified(&self, offset: usize) -> bool {
+        self.modified.contains_key(&offset)
+    }
+
+    /// Replaces the byte at the given offset. Bytes can only be overwritten, so the size of the
+    /// file never changes.
+    pub fn overwrite(&mut self, offset: usize, byte: u8, cx: &mut Context<Self>) {
+        let Some(existing) = self.bytes.get_mut(offset) else {
+            return;
+        };
+        if *existing == byte {
+            return;
+        }
+
+        let original = *self.modified.entry(offset).or_insert(*existing);
+        if original == byte {
+            self.modified.remove(&offset);
+        }
+        *existing = byte;
+        cx.emit(HexFileEvent::Edited);
+        cx.notify();
+    }
+
+    /// Whether the file changed on disk since it was loaded or saved, while it has unsaved edits.
+    pub fn has_conflict(&self) -> bool {
+        !self.modified.is_empty() && !self.deleted && self.disk_mtime != self.saved_mtime
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.deleted
+    }
+
+    fn save(&mut self, cx: &mut Context<Self>) -> Task<Result<()>> {
+        let fs = self.fs.clone();
+        let abs_path = self.abs_path.clone();
+        let bytes = self.bytes.clone();
+        // Once a conflict has been reported, saving means the changes on disk are overwritten.
+        let expected_mtime = if self.has_conflict() || self.deleted {
+            None
+        } else {
+            self.saved_mtime
+        };
+        cx.spawn(|this, mut cx| async move {
+            if let Some(expected_mtime) = expected_mtime {
+                let mtime = fs.metadata(&abs_path).await?.map(|metadata| metadata.mtime);
+                if mtime != Some(expected_mtime) {
+                    this.update(&mut cx, |this, cx| {
+                        this.disk_mtime = mtime;
+                        this.deleted = mtime.is_none();
+                        cx.emit(HexFileEvent::FileChanged);
+                        cx.notify();
+                    })?;
+                    return Err(anyhow!(
+                        "{abs_path:?} has changed on disk since it was opened"
+                    ));
+                }
+            }
+
+            // The file is written in place rather than replaced, so that it keeps its
+            // permissions, ownership and identity, which matter for the kinds of files
+            // edited as bytes, such as executables.
+            fs.write_bytes(&abs_path, bytes).await?;
+            let mtime = fs.metadata(&abs_path).await?.map(|metadata| metadata.mtime);
+            this.update(&mut cx, |this, cx| {
+                this.modified.clear();
+                this.saved_mtime = mtime;
+                this.disk_mtime = mtime;
+                this.deleted = false;
+                cx.emit(HexFileEvent::Saved);
+                cx.notify();
+            })
+        })
+    }
+
+    fn reload(&mut self, cx: &mut Context<Self>) -> Task<Result<()>> {
+        let fs = self.fs.clone();
+        let abs_path = self.abs_path.clone();
+        cx.spawn(|this, mut cx| async move {
+            let mtime = fs.metadata(&abs_path).await?.map(|metadata| metadata.mtime);
+            let bytes = fs.load_bytes(&abs_path).await?;
+            this.update(&mut cx, |this, cx| {
+                this.bytes = bytes;
+                this.modified.clear();
+                this.saved_mtime = mtime;
+                this.disk_mtime = mtime;
+                this.deleted = false;
+                cx.emit(HexFileEvent::Reloaded);
+                cx.notify();
+            })
+        })
+    }
+
+    fn worktree_entries_changed(
+        &mut self,
+        worktree: Entity<Worktree>,
+        changes: &UpdatedEntriesSet,
+        cx: &mut Context<Self>,
+    ) {
+        if !changes
+            .iter()
+            .any(|(path, _, _)| *path == self.project_path.path)
+        {
+            return;
+        }
+
+        let Some(entry) = worktree.read(cx).entry_for_path(&self.project_path.path) else {
+            if !self.deleted {
+                self.deleted = true;
+                cx.emit(HexFileEvent::FileChanged);
+                cx.notify();
+            }
+            return;
+        };
+        self.entry_id = Some(entry.id);
+        self.deleted = false;
+        if entry.mtime == self.disk_mtime {
+            return;
+        }
+
+        self.disk_mtime = entry.mtime;
+        if self.modified.is_empty() {
+            self.reload(cx).detach_and_log_err(cx);
+        } else {
+            cx.emit(HexFileEvent::FileChanged);
+            cx.notify();
+        }
+    }
+}
+
+impl project::ProjectItem for HexFile {
+    fn try_open(
+        project: &Entity<Project>,
+        path: &ProjectPath,
+        cx: &mut App,
+    ) -> Option<Task<gpui::Result<Entity<Self>>>> {
+        let project = project.read(cx);
+        if !project.is_binary_file(path, cx) {
+            return None;
+        }
+        let worktree_handle = project.worktree_for_id(path.worktree_id, cx)?;
+        let worktree = worktree_handle.read(cx);
+        if !worktree.is_local() {
+            return None;
+        }
+
+        // The whole file is loaded into memory, so large files are left to the large-file view.
+        let settings = WorktreeSettings::get(
+            Some(SettingsLocation {
+                worktree_id: path.worktree_id,
+                path: &path.path,
+            }),
+            cx,
+        )
+        .clone();
+        let entry = worktree.entry_for_path(&path.path);
+        if entry.is_some_and(|entry| settings.is_large_file(entry.size)) {
+            return None;
+        }
+
+        let abs_path = worktree.absolutize(&path.path).ok()?;
+        let entry_id = entry.map(|entry| entry.id);
+        let project_path = path.clone();
+        let fs = project.fs().clone();
+        Some(cx.spawn(|mut cx| async move {
+            let metadata = fs
+                .metadata(&abs_path)
+                .await?
+                .with_context(|| format!("{abs_path:?} was not found"))?;
+            if settings.is_large_file(metadata.len) {
+                return Err(anyhow!(
+                    "{abs_path:?} is too large to open in the hex editor ({} bytes)",
+                    metadata.len
+                ));
+            }
+            let bytes = fs.load_bytes(&abs_path).await?;
+            cx.new(|cx| {
+                cx.subscribe(&worktree_handle, |this, worktree, event, cx| {
+                    if let worktree::Event::UpdatedEntries(changes) = event {
+                        this.worktree_entries_changed(worktree, changes, cx);
+                    }
+                })
+                .detach();
+                HexFile {
+                    project_path,
+                    entry_id,
+                    abs_path,
+                    fs,
+                    bytes,
+                    modified: BTreeMap::new(),
+                    saved_mtime: Some(metadata.mtime),
+                    disk_mtime: Some(metadata.mtime),
+                    deleted: false,
+                }
+            })
+        }))
+    }
+
+    fn entry_id(&self, _: &App) -> Option<ProjectEntryId> {
+        self.entry_id
+    }
+
+    fn project_path(&self, _: &App) -> Option<ProjectPath> {
+        Some(self.project_path.clone())
+    }
+
+    fn is_dirty(&self) -> bool {
+        !self.modified.is_empty()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Column {
+    Hex,
+    Ascii,
+}
+
+pub struct HexEditor {
+    file: Entity<HexFile>,
+    project: Entity<Project>,
+    focus_handle: FocusHandle,
+    scroll_handle: UniformListScrollHandle,
+    cursor: usize,
+    /// Whether the next hex digit typed replaces the low half of the byte under the cursor.
+    low_nibble: bool,
+    column: Column,
+    offset_editor: Entity<Editor>,
+    search_editor: Entity<Editor>,
+    search_match: Option<Range<usize>>,
+    error: Option<SharedString>,
+    _subscription: Subscription,
+}
+
+impl HexEditor {
+    pub fn new(
+        file: Entity<HexFile>,
+        project: Entity<Project>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let offset_editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text("Go to offset (e.g. 0x1f)…", cx);
+            editor
+        });
+        let search_editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text("Search bytes (e.g. 7f 45 ?? 46 or \"text\")…", cx);
+            editor
+        });
+        let subscription = cx.subscribe(&file, |this, _, event, cx| {
+            match event {
+                HexFileEvent::Edited => cx.emit(ItemEvent::Edit),
+                HexFileEvent::Saved | HexFileEvent::FileChanged => {}
+                HexFileEvent::Reloaded => {
+                    let len = this.file.read(cx).bytes.len();
+                    this.cursor = this.cursor.min(len.saturating_sub(1));
+                    this.search_match = None;
+                }
+            }
+            cx.emit(ItemEvent::UpdateTab);
+            cx.notify();
+        });
+
+        Self {
+            file,
+            project,
+            focus_handle: cx.focus_handle(),
+            scroll_handle: UniformListScrollHandle::new(),
+            cursor: 0,
+            low_nibble: false,
+            column: Column::Hex,
+            offset_editor,
+            search_editor,
+            search_match: None,
+            error: None,
+            _subscription: subscription,
+        }
+    }
+
+    fn len(&self, cx: &App) -> usize {
+        self.file.read(cx).bytes.len()
+    }
+
+    fn move_cursor_to(&mut self, offset: usize, cx: &mut Context<Self>) {
+        let len = self.len(cx);
+        if len == 0 {
+            return;
+        }
+        self.cursor = offset.min(len - 1);
+        self.low_nibble = false;
+        self.scroll_handle
+            .scroll_to_item(self.cursor / BYTES_PER_ROW, ScrollStrategy::Top);
+        cx.notify();
+    }
+
+    fn move_cursor_by(&mut self, delta: isize, cx: &mut Context<Self>) {
+        let offset = self.cursor.saturating_add_signed(delta);
+        if delta < 0 || offset < self.len(cx) {
+            self.move_cursor_to(offset, cx);
+        }
+    }
+
+    fn move_left(&mut self, _: &MoveLeft, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_cursor_by(-1, cx);
+    }
+
+    fn move_right(&mut self, _: &MoveRight, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_cursor_by(1, cx);
+    }
+
+    fn move_up(&mut self, _: &MoveUp, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_cursor_by(-(BYTES_PER_ROW as isize), cx);
+    }
+
+    fn move_down(&mut self, _: &MoveDown, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_cursor_by(BYTES_PER_ROW as isize, cx);
+    }
+
+    fn move_page_up(&mut self, _: &MovePageUp, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_cursor_by(-((BYTES_PER_ROW * ROWS_PER_PAGE) as isize), cx);
+    }
+
+    fn move_page_down(&mut self, _: &MovePageDown, _: &mut Window, cx: &mut Context<Self>) {
+        let last = self.len(cx).saturating_sub(1);
+        self.move_cursor_to((self.cursor + BYTES_PER_ROW * ROWS_PER_PAGE).min(last), cx);
+    }
+
+    fn switch_column(&mut self, _: &SwitchColumn, _: &mut Window, cx: &mut Context<Self>) {
+        self.column = match self.column {
+            Column::Hex => Column::Ascii,
+            Column::Ascii => Column::Hex,
+        };
+        self.low_nibble = false;
+        cx.notify();
+    }
+
+    fn focus_offset_editor(&mut self, _: &GoToOffset, window: &mut Window, cx: &mut Context<Self>) {
+        self.offset_editor.update(cx, |editor, cx| {
+            editor.select_all(&editor::actions::SelectAll, window, cx);
+        });
+        window.focus(&self.offset_editor.focus_handle(cx));
+    }
+
+    fn focus_search_editor(&mut self, _: &Search, window: &mut Window, cx: &mut Context<Self>) {
+        self.search_editor.update(cx, |editor, cx| {
+            editor.select_all(&editor::actions::SelectAll, window, cx);
+        });
+        window.focus(&self.search_editor.focus_handle(cx));
+    }
+
+    fn cancel_input(&mut self, _: &menu::Cancel, window: &mut Window, cx: &mut Context<Self>) {
+        self.error = None;
+        window.focus(&self.focus_handle);
+        cx.notify();
+    }
+
+    fn confirm_offset(&mut self, _: &menu::Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        let query = self.offset_editor.read(cx).text(cx);
+        match parse_offset(&query) {
+            Ok(offset) if offset < self.len(cx) => {
+                self.error = None;
+                self.move_cursor_to(offset, cx);
+                window.focus(&self.focus_handle);
+            }
+            Ok(offset) => {
+                self.error = Some(format!("Offset {offset:#x} is past the end of the file").into())
+            }
+            Err(error) => self.error = Some(error.to_string().into()),
+        }
+        cx.notify();
+    }
+
+    fn confirm_search(&mut self, _: &menu::Confirm, _: &mut Window, cx: &mut Context<Self>) {
+        self.search(true, cx);
+    }
+
+    fn select_next_match(&mut self, _: &SelectNextMatch, _: &mut Window, cx: &mut Context<Self>) {
+        self.search(true, cx);
+    }
+
+    fn select_prev_match(&mut self, _: &SelectPrevMatch, _: &mut Window, cx: &mut Context<Self>) {
+        self.search(false, cx);
+    }
+
+    fn search(&mut self, forward: bool, cx: &mut Context<Self>) {
+        let query = self.search_editor.read(cx).text(cx);
+        let pattern = match BytePattern::parse(&query) {
+            Ok(pattern) => pattern,
+            Err(error) => {
+                self.error = Some(error.to_string().into());
+                cx.notify();
+                return;
+            }
+        };
+
+        let bytes = &self.file.read(cx).bytes;
+        let found = if forward {
+            let start = match &self.search_match {
+                Some(search_match) if search_match.start == self.cursor => self.cursor + 1,
+                _ => self.cursor,
+            };
+            pattern.find_next(bytes, start)
+        } else {
+            pattern.find_prev(bytes, self.cursor)
+        };
+
+        match found {
+            Some(offset) => {
+                self.error = None;
+                self.search_match = Some(offset..offset + pattern.len());
+                self.move_cursor_to(offset, cx);
+            }
+            None => {
+                self.search_match = None;
+                self.error = Some("No matches".into());
+                cx.notify();
+            }
+        }
+    }
+
+    fn handle_key_down(&mut self, event: &KeyDownEvent, _: &mut Window, cx: &mut Context<Self>) {
+        let modifiers = &event.keystroke.modifiers;
+        if modifiers.control || modifiers.alt || modifiers.platform || modifiers.function {
+            return;
+        }
+        let Some(typed) = event.keystroke.key_char.as_deref() else {
+            return;
+        };
+        let mut chars = typed.chars();
+        let (Some(typed), None) = (chars.next(), chars.next()) else {
+            return;
+        };
+        if self.input(typed, cx) {
+            cx.stop_propagation();
+        }
+    }
+
+    /// Overwrites the byte under the cursor with a typed hex digit or ASCII character,
+    /// depending on the active column. Returns whether the character was used.
+    fn input(&mut self, typed: char, cx: &mut Context<Self>) -> bool {
+        let Some(&byte) = self.file.read(cx).bytes.get(self.cursor) else {
+            return false;
+        };
+
+        let cursor = self.cursor;
+        match self.column {
+            Column::Hex => {
+                let Some(digit) = typed.to_digit(16) else {
+                    return false;
+                };
+                let digit = digit as u8;
+                let byte = if self.low_nibble {
+                    (byte & 0xf0) | digit
+                } else {
+                    (byte & 0x0f) | (digit << 4)
+                };
+                self.file
+                    .update(cx, |file, cx| file.overwrite(cursor, byte, cx));
+                if self.low_nibble {
+                    self.move_cursor_by(1, cx);
+                } else {
+                    self.low_nibble = true;
+                }
+            }
+            Column::Ascii => {
+                if !typed.is_ascii() || typed.is_ascii_control() {
+                    return false;
+                }
+                self.file
+                    .update(cx, |file, cx| file.overwrite(cursor, typed as u8, cx));
+                self.move_cursor_by(1, cx);
+            }
+        }
+        cx.notify();
+        true
+    }
+
+    fn render_rows(&mut self, range: Range<usize>, cx: &mut Context<Self>) -> Vec<AnyElement> {
+        let file = self.file.read(cx);
+        let colors = cx.theme().colors();
+        let modified_color = cx.theme().status().modified;
+        let offset_digits = offset_digits(file.bytes.len());
+
+        range
+            .map(|row| {
+                let row_start = row * BYTES_PER_ROW;
+                let row_end = (row_start + BYTES_PER_ROW).min(file.bytes.len());
+                let row_bytes = &file.bytes[row_start..row_end];
+
+                let cell_background = |offset: usize, column: Column| {
+                    if offset == self.cursor {
+                        if column == self.column {
+                            Some(colors.element_selected)
+                        } else {
+                            Some(colors.element_hover)
+                        }
+                    } else if self
+                        .search_match
+                        .as_ref()
+                        .is_some_and(|search_match| search_match.contains(&offset))
+                    {
+                        Some(colors.search_match_background)
+                    } else {
+                        None
+                    }
+                };
+
+                let hex_cells = (0..BYTES_PER_ROW).map(|ix| {
+                    let offset = row_start + ix;
+                    let cell = div()
+                        .id(("hex", offset))
+                        .w(rems(1.5))
+                        .when(ix == BYTES_PER_ROW / 2, |this| this.ml_2())
+                        .flex_none();
+                    let Some(&byte) = row_bytes.get(ix) else {
+                        return cell.into_any_element();
+                    };
+                    cell.when_some(cell_background(offset, Column::Hex), |this, background| {
+                        this.bg(background)
+                    })
+                    .when(file.is_modified(offset), |this| {
+                        this.text_color(modified_color)
+                    })
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(move |this, _, window, cx| {
+                            this.column = Column::Hex;
+                            this.move_cursor_to(offset, cx);
+                            window.focus(&this.focus_handle);
+                        }),
+                    )
+                    .child(format!("{byte:02x}"))
+                    .into_any_element()
+                });
+
+                let ascii_cells = row_bytes.iter().enumerate().map(|(ix, &byte)| {
+                    let offset = row_start + ix;
+                    div()
+                        .id(("ascii", offset))
+                        .when_some(
+                            cell_background(offset, Column::Ascii),
+                            |this, background| this.bg(background),
+                        )
+                        .when(file.is_modified(offset), |this| {
+                            this.text_color(modified_color)
+                        })
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |this, _, window, cx| {
+                                this.column = Column::Ascii;
+                                this.move_cursor_to(offset, cx);
+                                window.focus(&this.focus_handle);
+                            }),
+                        )
+                        .child(display_char(byte).to_string())
+                        .into_any_element()
+                });
+
+                h_flex()
+                    .id(row)
+                    .px_2()
+                    .gap_4()
+                    .child(
+                        div()
+                            .flex_none()
+                            .text_color(colors.editor_line_number)
+                            .child(format!("{row_start:0offset_digits$x}")),
+                    )
+                    .child(h_flex().flex_none().children(hex_cells))
+                    .child(h_flex().flex_none().children(ascii_cells))
+                    .into_any_element()
+            })
+            .collect()
+    }
+
+    fn render_toolbar(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let input = |editor: &Entity<Editor>| {
+            h_flex()
+                .flex_1()
+                .px_2()
+                .py_1()
+                .rounded_md()
+                .border_1()
+                .border_color(cx.theme().colors().border_variant)
+                .child(editor.clone())
+        };
+
+        h_flex()
+            .gap_2()
+            .px_2()
+            .py_1()
+            .border_b_1()
+            .border_color(cx.theme().colors().border)
+            .child(
+                input(&self.offset_editor)
+                    .on_action(cx.listener(Self::confirm_offset))
+                    .on_action(cx.listener(Self::cancel_input)),
+            )
+            .child(
+                input(&self.search_editor)
+                    .on_action(cx.listener(Self::confirm_search))
+                    .on_action(cx.listener(Self::cancel_input)),
+            )
+            .child(
+                IconButton::new("previous-match", IconName::ChevronLeft)
+                    .tooltip(Tooltip::text("Select Previous Match"))
+                    .on_click(cx.listener(|this, _, _, cx| this.search(false, cx))),
+            )
+            .child(
+                IconButton::new("next-match", IconName::ChevronRight)
+                    .tooltip(Tooltip::text("Select Next Match"))
+                    .on_click(cx.listener(|this, _, _, cx| this.search(true, cx))),
+            )
+    }
+
+    fn render_status(&self, cx: &App) -> impl IntoElement {
+        let file = self.file.read(cx);
+        let status = match file.bytes.get(self.cursor) {
+            Some(byte) => format!(
+                "Offset {:#x} ({}) · {byte:#04x} ({byte}) · {} bytes",
+                self.cursor,
+                self.cursor,
+                file.bytes.len()
+            ),
+            None => "Empty file".to_string(),
+        };
+
+        h_flex()
+            .gap_2()
+            .px_2()
+            .py_1()
+            .border_t_1()
+            .border_color(cx.theme().colors().border)
+            .child(Label::new(status).color(Color::Muted))
+            .children(
+                self.error
+                    .clone()
+                    .map(|error| Label::new(error).color(Color::Error)),
+            )
+    }
+}
+
+/// The number of hex digits needed to show every offset in a file of the given length.
+fn offset_digits(len: usize) -> usize {
+    let digits = (usize::BITS - len.leading_zeros()).div_ceil(4) as usize;
+    digits.max(8)
+}
+
+fn display_char(byte: u8) -> char {
+    if byte.is_ascii_graphic() || byte == b' ' {
+        byte as char
+    } else {
+        '.'
+    }
+}
+
+impl EventEmitter<ItemEvent> for HexEditor {}
+
+impl Item for HexEditor {
+    type Event = ItemEvent;
+
+    fn to_item_events(event: &Self::Event, mut f: impl FnMut(ItemEvent)) {
+        f(*event)
+    }
+
+    fn for_each_project_item(
+        &self,
+        cx: &App,
+        f: &mut dyn FnMut(gpui::EntityId, &dyn project::ProjectItem),
+    ) {
+        f(self.file.entity_id(), self.file.read(cx))
+    }
+
+    fn is_singleton(&self, _cx: &App) -> bool {
+        true
+    }
+
+    fn tab_tooltip_text(&self, cx: &App) -> Option<SharedString> {
+        let abs_path = self.file.read(cx).abs_path.compact();
+        Some(abs_path.to_string_lossy().to_string().into())
+    }
+
+    fn tab_content(&self, params: TabContentParams, _: &Window, cx: &App) -> AnyElement {
+        let project_path = self.file.read(cx).project_path.clone();
+
+        let label_color = if ItemSettings::get_global(cx).git_status {
+            let git_status = self
+                .project
+                .read(cx)
+                .project_path_git_status(&project_path, cx)
+                .map(|status| status.summary())
+                .unwrap_or_default();
+
+            self.project
+                .read(cx)
+                .entry_for_path(&project_path, cx)
+                .map(|entry| {
+                    entry_git_aware_label_color(git_status, entry.is_ignored, params.selected)
+                })
+                .unwrap_or_else(|| params.text_color())
+        } else {
+            params.text_color()
+        };
+
+        let title = project_path
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Label::new(title)
+            .single_line()
+            .color(label_color)
+            .when(params.preview, |this| this.italic())
+            .into_any_element()
+    }
+
+    fn tab_icon(&self, _: &Window, cx: &App) -> Option<Icon> {
+        let path = self.file.read(cx).project_path.path.clone();
+        ItemSettings::get_global(cx)
+            .file_icons
+            .then(|| FileIcons::get_icon(&path, cx))
+            .flatten()
+            .map(Icon::from_path)
+    }
+
+    fn is_dirty(&self, cx: &App) -> bool {
+        project::ProjectItem::is_dirty(self.file.read(cx))
+    }
+
+    fn has_conflict(&self, cx: &App) -> bool {
+        self.file.read(cx).has_conflict()
+    }
+
+    fn has_deleted_file(&self, cx: &App) -> bool {
+        self.file.read(cx).is_deleted()
+    }
+
+    fn can_save(&self, _cx: &App) -> bool {
+        true
+    }
+
+    fn save(
+        &mut self,
+        _format: bool,
+        _project: Entity<Project>,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        self.file.update(cx, |file, cx| file.save(cx))
+    }
+
+    fn reload(
+        &mut self,
+        _project: Entity<Project>,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        self.file.update(cx, |file, cx| file.reload(cx))
+    }
+
+    fn clone_on_split(
+        &self,
+        _workspace_id: Option<WorkspaceId>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Option<Entity<Self>>
+    where
+        Self: Sized,
+    {
+        Some(cx.new(|cx| Self::new(self.file.clone(), self.project.clone(), window, cx)))
+    }
+}
+
+impl Focusable for HexEditor {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for HexEditor {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let row_count = self.len(cx).div_ceil(BYTES_PER_ROW);
+
+        v_flex()
+            .size_full()
+            .bg(cx.theme().colors().editor_background)
+            .on_action(cx.listener(Self::focus_offset_editor))
+            .on_action(cx.listener(Self::focus_search_editor))
+            .on_action(cx.listener(Self::select_next_match))
+            .on_action(cx.listener(Self::select_prev_match))
+            .child(self.render_toolbar(cx))
+            .child(
+                div()
+                    .key_context("HexEditor")
+                    .track_focus(&self.focus_handle)
+                    .on_action(cx.listener(Self::move_left))
+                    .on_action(cx.listener(Self::move_right))
+                    .on_action(cx.listener(Self::move_up))
+                    .on_action(cx.listener(Self::move_down))
+                    .on_action(cx.listener(Self::move_page_up))
+                    .on_action(cx.listener(Self::move_page_down))
+                    .on_action(cx.listener(Self::switch_column))
+                    .on_key_down(cx.listener(Self::handle_key_down))
+                    .flex_1()
+                    .font_buffer(cx)
+                    .child(
+                        uniform_list(cx.entity(), "hex-rows", row_count, |this, range, _, cx| {
+                            this.render_rows(range, cx)
+                        })
+                        .size_full()
+                        .track_scroll(self.scroll_handle.clone()),
+                    ),
+            )
+            .child(self.render_status(cx))
+    }
+}
+
+impl ProjectItem for HexEditor {
+    type Item = HexFile;
+
+    fn for_project_item(
+        project: Entity<Project>,
+        item: Entity<Self::Item>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self
+    where
+        Self: Sized,
+    {
+        Self::new(item, project, window, cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::{TestAppContext, VisualTestContext};
+    use project::{FakeFs, RealFs};
+    use serde_json::json;
+    use std::path::Path;
+    use util::{path, test::TempTree};
+    use workspace::{AppState, Workspace};
+
+    const ELF_HEADER: [u8; 6] = [0x7f, b'E', b'L', b'F', 0xff, 0x00];
+
+    #[test]
+    fn test_offset_digits() {
+        assert_eq!(offset_digits(0), 8);
+        assert_eq!(offset_digits(0xffff_ffff), 8);
+        assert_eq!(offset_digits(0x1_0000_0000), 9);
+    }
+
+    #[gpui::test]
+    async fn test_overwrite_nibbles(cx: &mut TestAppContext) {
+        let (_, _, hex_editor, cx) = open_hex_editor(cx).await;
+
+        hex_editor.update(cx, |hex_editor, cx| {
+            // The first digit replaces the high half of the byte, and the second one the low
+            // half, after which the cursor moves on.
+            assert!(hex_editor.input('4', cx));
+            assert_eq!(hex_editor.file.read(cx).bytes()[0], 0x4f);
+            assert_eq!(hex_editor.cursor, 0);
+            assert!(hex_editor.input('1', cx));
+            assert_eq!(hex_editor.file.read(cx).bytes()[0], 0x41);
+            assert_eq!(hex_editor.cursor, 1);
+            assert!(!hex_editor.input('g', cx));
+
+            // Typing the byte that's already there doesn't modify it.
+            assert!(hex_editor.input('4', cx));
+            assert!(hex_editor.input('5', cx));
+            assert_eq!(hex_editor.cursor, 2);
+
+            hex_editor.column = Column::Ascii;
+            assert!(hex_editor.input('x', cx));
+            assert!(!hex_editor.input('\n', cx));
+            assert_eq!(hex_editor.cursor, 3);
+
+            let file = hex_editor.file.read(cx);
+            assert_eq!(file.bytes(), &[0x41, b'E', b'x', b'F', 0xff, 0x00]);
+            assert!(file.is_modified(0));
+            assert!(!file.is_modified(1));
+            assert!(file.is_modified(2));
+        });
+    }
+
+    #[gpui::test]
+    async fn test_dirty_state(cx: &mut TestAppContext) {
+        let (_, project, hex_editor, cx) = open_hex_editor(cx).await;
+
+        hex_editor.update(cx, |hex_editor, cx| {
+            assert!(!hex_editor.is_dirty(cx));
+            hex_editor.column = Column::Ascii;
+            hex_editor.move_cursor_to(1, cx);
+            hex_editor.input('E', cx);
+            assert!(!hex_editor.is_dirty(cx));
+            hex_editor.input('e', cx);
+            assert!(hex_editor.is_dirty(cx));
+
+            // Setting a byte back to its original value undoes its modification.
+            hex_editor.move_cursor_to(1, cx);
+            hex_editor.input('E', cx);
+            assert!(!hex_editor.file.read(cx).is_modified(1));
+            assert!(!hex_editor.is_dirty(cx));
+            hex_editor.input('l', cx);
+            assert!(hex_editor.is_dirty(cx));
+        });
+
+        hex_editor
+            .update_in(cx, |hex_editor, window, cx| {
+                hex_editor.reload(project.clone(), window, cx)
+            })
+            .await
+            .unwrap();
+        hex_editor.update(cx, |hex_editor, cx| {
+            assert!(!hex_editor.is_dirty(cx));
+            assert_eq!(hex_editor.file.read(cx).bytes(), &ELF_HEADER);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_save_round_trip(cx: &mut TestAppContext) {
+        let (fs, project, hex_editor, cx) = open_hex_editor(cx).await;
+
+        hex_editor.update(cx, |hex_editor, cx| {
+            hex_editor.move_cursor_to(4, cx);
+            hex_editor.input('0', cx);
+            hex_editor.input('a', cx);
+        });
+        hex_editor
+            .update_in(cx, |hex_editor, window, cx| {
+                hex_editor.save(false, project.clone(), window, cx)
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            fs.load_bytes(path!("/dir/binary").as_ref()).await.unwrap(),
+            vec![0x7f, b'E', b'L', b'F', 0x0a, 0x00]
+        );
+        cx.run_until_parked();
+        hex_editor.update(cx, |hex_editor, cx| {
+            assert!(!hex_editor.is_dirty(cx));
+            assert!(!hex_editor.has_conflict(cx));
+        });
+
+        // Changes on disk are reloaded while there are no unsaved edits.
+        fs.insert_file(path!("/dir/binary"), ELF_HEADER.to_vec())
+            .await;
+        cx.run_until_parked();
+        hex_editor.update(cx, |hex_editor, cx| {
+            assert_eq!(hex_editor.file.read(cx).bytes(), &ELF_HEADER);
+            hex_editor.move_cursor_to(0, cx);
+            hex_editor.input('0', cx);
+            assert!(!hex_editor.has_conflict(cx));
+        });
+
+        // Otherwise they're reported as a conflict, which saving overwrites.
+        fs.insert_file(path!("/dir/binary"), vec![0; 6]).await;
+        cx.run_until_parked();
+        hex_editor.update(cx, |hex_editor, cx| {
+            assert!(hex_editor.is_dirty(cx));
+            assert!(hex_editor.has_conflict(cx));
+        });
+        hex_editor
+            .update_in(cx, |hex_editor, window, cx| {
+                hex_editor.save(false, project.clone(), window, cx)
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            fs.load_bytes(path!("/dir/binary").as_ref()).await.unwrap(),
+            vec![0x0f, b'E', b'L', b'F', 0xff, 0x00]
+        );
+        hex_editor.update(cx, |hex_editor, cx| {
+            assert!(!hex_editor.is_dirty(cx));
+            assert!(!hex_editor.has_conflict(cx));
+        });
+    }
+
+    #[cfg(unix)]
+    #[gpui::test]
+    async fn test_save_keeps_permissions(cx: &mut TestAppContext) {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        cx.executor().allow_parking();
+        let dir = TempTree::new(json!({}));
+        let path = dir.path().join("binary");
+        std::fs::write(&path, ELF_HEADER).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o751)).unwrap();
+
+        init_test(cx);
+        let (project, hex_editor, cx) =
+            open_hex_editor_in(Arc::new(RealFs::default()), dir.path(), cx).await;
+        hex_editor.update(cx, |hex_editor, cx| {
+            hex_editor.input('0', cx);
+            hex_editor.input('0', cx);
+        });
+        hex_editor
+            .update_in(cx, |hex_editor, window, cx| {
+                hex_editor.save(false, project.clone(), window, cx)
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            std::fs::read(&path).unwrap(),
+            [0x00, b'E', b'L', b'F', 0xff, 0x00]
+        );
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o751);
+    }
+
+    async fn open_hex_editor(
+        cx: &mut TestAppContext,
+    ) -> (
+        Arc<FakeFs>,
+        Entity<Project>,
+        Entity<HexEditor>,
+        &mut VisualTestContext,
+    ) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(path!("/dir"), json!({ "text": "hello" }))
+            .await;
+        fs.insert_file(path!("/dir/binary"), ELF_HEADER.to_vec())
+            .await;
+
+        let (project, hex_editor, cx) =
+            open_hex_editor_in(fs.clone(), path!("/dir").as_ref(), cx).await;
+        (fs, project, hex_editor, cx)
+    }
+
+    /// Opens the file named `binary` in the given directory, which must not be valid text.
+    async fn open_hex_editor_in<'a>(
+        fs: Arc<dyn Fs>,
+        dir: &Path,
+        cx: &'a mut TestAppContext,
+    ) -> (
+        Entity<Project>,
+        Entity<HexEditor>,
+        &'a mut VisualTestContext,
+    ) {
+        let project = Project::test(fs, [dir], cx).await;
+        let (workspace, cx) =
+            cx.add_window_view(|window, cx| Workspace::test_new(project.clone(), window, cx));
+        let worktree_id = project.update(cx, |project, cx| {
+            project.worktrees(cx).next().unwrap().read(cx).id()
+        });
+
+        // The file fails to open as a buffer, and falls back to the hex editor.
+        let hex_editor = workspace
+            .update_in(cx, |workspace, window, cx| {
+                workspace.open_path((worktree_id, "binary"), None, true, window, cx)
+            })
+            .await
+            .unwrap()
+            .downcast::<HexEditor>()
+            .unwrap();
+        (project, hex_editor, cx)
+    }
+
+    fn init_test(cx: &mut TestAppContext) -> Arc<AppState> {
+        cx.update(|cx| {
+            let state = AppState::test(cx);
+            language::init(cx);
+            editor::init(cx);
+            workspace::init_settings(cx);
+            Project::init_settings(cx);
+            crate::init(cx);
+            state
+        })
+    }
+}
@@ -392,6 +392,9 @@ mod persistence {
                     FOREIGN KEY(workspace_id) REFERENCES workspaces(workspace_id)
                     ON DELETE CASCADE
                 ) STRICT;
+            )],
+            &[sql!(
+                DROP TABLE image_viewers;
             )];
     }
 
@@ -59,9 +59,9 @@ use text::operation_queue::OperationQueue;
 use text::*;
 pub use text::{
     Anchor, Bias, Buffer as TextBuffer, BufferId, BufferSnapshot as TextBufferSnapshot, Edit,
-    OffsetRangeExt, OffsetUtf16, Patch, Point, PointUtf16, Rope, Selection, SelectionGoal,
-    Subscription, TextDimension, TextSummary, ToOffset, ToOffsetUtf16, ToPoint, ToPointUtf16,
-    Transaction, TransactionId, Unclipped,
+    Encoding, OffsetRangeExt, OffsetUtf16, Patch, Point, PointUtf16, Rope, Selection,
+    SelectionGoal, Subscription, TextDimension, TextSummary, ToOffset, ToOffsetUtf16, ToPoint,
+    ToPointUtf16, Transaction, TransactionId, Unclipped,
 };
 use theme::{ActiveTheme as _, SyntaxTheme};
 #[cfg(any(test, feature = "test-support"))]
@@ -101,6 +101,9 @@ pub struct Buffer {
     /// The version vector when this buffer was last loaded from
     /// or saved to disk.
     saved_version: clock::Global,
+    /// The encoding of the file when this buffer was last loaded from
+    /// or saved to disk, which is used the next time it is saved.
+    encoding: Encoding,
     preview_version: clock::Global,
     transaction_depth: usize,
     was_dirty_before_starting_transaction: Option<bool>,
@@ -388,6 +391,24 @@ pub trait LocalFile: File {
 
     /// Loads the file's contents from disk.
     fn load_bytes(&self, cx: &App) -> Task<Result<Vec<u8>>>;
+
+    /// Loads the file contents from disk and decodes them with the given encoding,
+    /// or with the detected one if no encoding is given.
+    fn load_with_encoding(
+        &self,
+        encoding: Option<Encoding>,
+        cx: &App,
+    ) -> Task<Result<(String, Encoding)>> {
+        let bytes = self.load_bytes(cx);
+        cx.background_spawn(async move {
+            let bytes = bytes.await?;
+            let encoding = match encoding {
+                Some(encoding) => encoding,
+                None => Encoding::detect(&bytes).context("file did not contain valid text")?,
+            };
+            Ok(encoding.decode(bytes))
+        })
+    }
 }
 
 /// The auto-indent behavior associated with an editing operation.
@@ -936,6 +957,12 @@ impl Buffer {
         self
     }
 
+    /// Assign the encoding of the buffer's file, returning the buffer.
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
     /// Returns the [`Capability`] of this buffer.
     pub fn capability(&self) -> Capability {
         self.capability
@@ -954,6 +981,7 @@ impl Buffer {
         Self {
             saved_mtime,
             saved_version: buffer.version(),
+            encoding: Encoding::default(),
             preview_version: buffer.version(),
             reload_task: None,
             transaction_depth: 0,
@@ -1297,20 +1325,79 @@ impl Buffer {
         cx.notify();
     }
 
+    /// Returns the encoding that the buffer's file is saved with.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Sets the encoding that the buffer's file will be saved with.
+    pub fn set_encoding(&mut self, encoding: Encoding, cx: &mut Context<Self>) {
+        if self.encoding != encoding {
+            self.encoding = encoding;
+            cx.notify();
+        }
+    }
+
     /// Reloads the contents of the buffer from disk.
     pub fn reload(&mut self, cx: &Context<Self>) -> oneshot::Receiver<Option<Transaction>> {
         let (tx, rx) = futures::channel::oneshot::channel();
+        self.reload_from_disk(
+            self.encoding,
+            move |result| {
+                if let Ok(transaction) = result {
+                    tx.send(transaction).ok();
+                }
+            },
+            cx,
+        );
+        rx
+    }
+
+    /// Reloads the contents of the buffer from disk, decoding them with the given encoding.
+    /// Unlike [`Buffer::reload`], this reports why the buffer couldn't be reloaded.
+    pub fn reload_with_encoding(
+        &mut self,
+        encoding: Encoding,
+        cx: &Context<Self>,
+    ) -> oneshot::Receiver<Result<Option<Transaction>>> {
+        let (tx, rx) = futures::channel::oneshot::channel();
+        self.reload_from_disk(
+            encoding,
+            move |result| {
+                tx.send(result).ok();
+            },
+            cx,
+        );
+        rx
+    }
+
+    fn reload_from_disk(
+        &mut self,
+        encoding: Encoding,
+        done: impl FnOnce(Result<Option<Transaction>>) + 'static,
+        cx: &Context<Self>,
+    ) {
         let prev_version = self.text.version();
         self.reload_task = Some(cx.spawn(|this, mut cx| async move {
             let Some((new_mtime, new_text)) = this.update(&mut cx, |this, cx| {
                 let file = this.file.as_ref()?.as_local()?;
-                Some((file.disk_state().mtime(), file.load(cx)))
+                Some((
+                    file.disk_state().mtime(),
+                    file.load_with_encoding(Some(encoding), cx),
+                ))
             })?
             else {
+                done(Err(anyhow!("the buffer's file isn't a local file")));
                 return Ok(());
             };
 
-            let new_text = new_text.await?;
+            let (new_text, new_encoding) = match new_text.await {
+                Ok(loaded) => loaded,
+                Err(error) => {
+                    done(Err(error));
+                    return Ok(());
+                }
+            };
             let diff = this
                 .update(&mut cx, |this, cx| this.diff(new_text.clone(), cx))?
                 .await;
@@ -1318,8 +1405,9 @@ impl Buffer {
                 if this.version() == diff.base_version {
                     this.finalize_last_transaction();
                     this.apply_diff(diff, cx);
-                    tx.send(this.finalize_last_transaction().cloned()).ok();
+                    done(Ok(this.finalize_last_transaction().cloned()));
                     this.has_conflict = false;
+                    this.set_encoding(new_encoding, cx);
                     this.did_reload(this.version(), this.line_ending(), new_mtime, cx);
                 } else {
                     if !diff.edits.is_empty()
@@ -1332,12 +1420,14 @@ impl Buffer {
                     }
 
                     this.did_reload(prev_version, this.line_ending(), this.saved_mtime, cx);
+                    done(Err(anyhow!(
+                        "the buffer was edited while it was being reloaded"
+                    )));
                 }
 
                 this.reload_task.take();
             })
         }));
-        rx
     }
 
     /// This method is called to signal that the buffer has been reloaded.
@@ -2650,6 +2650,165 @@ fn test_undo_after_merge_into_base(cx: &mut TestAppContext) {
     branch.read_with(cx, |branch, _| assert_eq!(branch.text(), "ABCdefgHIjk"));
 }
 
+#[gpui::test]
+async fn test_reload_with_encoding(cx: &mut TestAppContext) {
+    cx.update(|cx| init_settings(cx, |_| {}));
+
+    let shift_jis = Encoding::for_label("shift_jis").unwrap();
+    let text = "// こんにちは\nfn main() {}\n";
+    let file = Arc::new(InMemoryFile {
+        path: Path::new("main.rs").into(),
+        content: Mutex::new(Some(shift_jis.encode(text).unwrap().into_owned())),
+    });
+    let buffer = cx.new(|cx| {
+        let buffer_id = BufferId::from(cx.entity_id().as_non_zero_u64());
+        Buffer::build(
+            TextBuffer::new(0, buffer_id, String::new()),
+            Some(file.clone() as Arc<dyn File>),
+            Capability::ReadWrite,
+        )
+    });
+
+    // Reloading with the wrong encoding garbles the text, while the right one restores it.
+    buffer
+        .update(cx, |buffer, cx| {
+            buffer.reload_with_encoding(Encoding::default(), cx)
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    buffer.read_with(cx, |buffer, _| {
+        assert_ne!(buffer.text(), text);
+        assert_eq!(buffer.encoding(), Encoding::default());
+    });
+    buffer
+        .update(cx, |buffer, cx| buffer.reload_with_encoding(shift_jis, cx))
+        .await
+        .unwrap()
+        .unwrap();
+    buffer.read_with(cx, |buffer, _| {
+        assert_eq!(buffer.text(), text);
+        assert_eq!(buffer.encoding(), shift_jis);
+        assert_eq!(
+            buffer.encoding().encode(&buffer.text()).unwrap(),
+            file.content.lock().as_deref().unwrap()
+        );
+    });
+
+    // A reload keeps the buffer's encoding, and picks up whether the file has a BOM.
+    *file.content.lock() = Some(shift_jis.encode("// 世界\n").unwrap().into_owned());
+    buffer
+        .update(cx, |buffer, cx| buffer.reload(cx))
+        .await
+        .unwrap();
+    buffer.read_with(cx, |buffer, _| {
+        assert_eq!(buffer.text(), "// 世界\n");
+        assert_eq!(buffer.encoding(), shift_jis);
+    });
+
+    let utf8_with_bom = Encoding::default().with_bom(true);
+    *file.content.lock() = Some(utf8_with_bom.encode("// 世界\n").unwrap().into_owned());
+    buffer
+        .update(cx, |buffer, cx| {
+            buffer.reload_with_encoding(Encoding::default(), cx)
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    buffer.read_with(cx, |buffer, _| {
+        assert_eq!(buffer.text(), "// 世界\n");
+        assert_eq!(buffer.encoding(), utf8_with_bom);
+        assert_eq!(
+            buffer.encoding().encode(&buffer.text()).unwrap(),
+            file.content.lock().as_deref().unwrap()
+        );
+    });
+
+    // Failing to load the file is reported, and leaves the buffer as it was.
+    *file.content.lock() = None;
+    let error = buffer
+        .update(cx, |buffer, cx| buffer.reload_with_encoding(shift_jis, cx))
+        .await
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(error.to_string(), "\"main.rs\" was deleted");
+    buffer.read_with(cx, |buffer, _| {
+        assert_eq!(buffer.text(), "// 世界\n");
+        assert_eq!(buffer.encoding(), utf8_with_bom);
+    });
+}
+
+/// A local file whose contents are kept in memory. It has been deleted while its contents
+/// are `None`.
+struct InMemoryFile {
+    path: Arc<Path>,
+    content: Mutex<Option<Vec<u8>>>,
+}
+
+impl File for InMemoryFile {
+    fn as_local(&self) -> Option<&dyn LocalFile> {
+        Some(self)
+    }
+
+    fn disk_state(&self) -> DiskState {
+        DiskState::New
+    }
+
+    fn path(&self) -> &Arc<Path> {
+        &self.path
+    }
+
+    fn full_path(&self, _: &App) -> PathBuf {
+        self.path.to_path_buf()
+    }
+
+    fn file_name<'a>(&'a self, _: &'a App) -> &'a std::ffi::OsStr {
+        self.path.file_name().unwrap()
+    }
+
+    fn worktree_id(&self, _: &App) -> WorktreeId {
+        WorktreeId::from_usize(0)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn to_proto(&self, _: &App) -> rpc::proto::File {
+        rpc::proto::File {
+            worktree_id: 0,
+            entry_id: None,
+            path: self.path.to_string_lossy().into_owned(),
+            mtime: None,
+            is_deleted: self.content.lock().is_none(),
+        }
+    }
+
+    fn is_private(&self) -> bool {
+        false
+    }
+}
+
+impl LocalFile for InMemoryFile {
+    fn abs_path(&self, _: &App) -> PathBuf {
+        self.path.to_path_buf()
+    }
+
+    fn load(&self, cx: &App) -> Task<Result<String>> {
+        let bytes = self.load_bytes(cx);
+        cx.background_spawn(async move { Ok(String::from_utf8(bytes.await?)?) })
+    }
+
+    fn load_bytes(&self, _: &App) -> Task<Result<Vec<u8>>> {
+        Task::ready(
+            self.content
+                .lock()
+                .clone()
+                .ok_or_else(|| anyhow!("{:?} was deleted", self.path)),
+        )
+    }
+}
+
 #[gpui::test]
 async fn test_preview_edits(cx: &mut TestAppContext) {
     cx.update(|cx| {
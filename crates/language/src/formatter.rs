@@ -0,0 +1,50 @@
+//! Provides support for formatters that are not backed by a language server.
+//!
+//! A formatter is registered for a set of languages and is selected by its name in the
+//! `formatter` setting. It either formats the buffer's text itself, or returns a command
+//! that formats the text read from its stdin.
+
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use collections::HashMap;
+use gpui::SharedString;
+
+use crate::{LanguageName, LspAdapterDelegate};
+
+#[async_trait]
+pub trait FormatterProvider: Send + Sync {
+    /// The name the formatter is selected by in the `formatter` setting.
+    fn name(&self) -> SharedString;
+
+    async fn format(
+        &self,
+        request: FormatRequest,
+        delegate: Arc<dyn LspAdapterDelegate>,
+    ) -> Result<FormatResponse>;
+}
+
+/// The buffer to format.
+#[derive(Clone, Debug)]
+pub struct FormatRequest {
+    /// The path of the buffer, relative to the worktree root.
+    pub path: Option<PathBuf>,
+    pub language_name: LanguageName,
+    pub text: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FormatResponse {
+    /// A command that reads the buffer's text from stdin and writes the formatted text to stdout.
+    Command(FormatterCommand),
+    /// The formatted text.
+    Text(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FormatterCommand {
+    pub path: PathBuf,
+    pub arguments: Vec<String>,
+    pub env: HashMap<String, String>,
+}
@@ -8,9 +8,11 @@
 //! Notably we do *not* assign a single language to a single file; in real world a single file can consist of multiple programming languages - HTML is a good example of that - and `language` crate tends to reflect that status quo in its API.
 mod buffer;
 mod diagnostic_set;
+mod formatter;
 mod highlight_map;
 mod language_registry;
 pub mod language_settings;
+mod linter;
 mod outline;
 pub mod proto;
 mod syntax_map;
@@ -72,10 +74,12 @@ use util::serde::default_true;
 pub use buffer::Operation;
 pub use buffer::*;
 pub use diagnostic_set::{DiagnosticEntry, DiagnosticGroup};
+pub use formatter::{FormatRequest, FormatResponse, FormatterCommand, FormatterProvider};
 pub use language_registry::{
     AvailableLanguage, LanguageNotFound, LanguageQueries, LanguageRegistry,
     LanguageServerBinaryStatus, QUERY_FILENAME_PREFIXES,
 };
+pub use linter::{LintDiagnostic, LintRequest, LinterProvider};
 pub use lsp::{LanguageServerId, LanguageServerName};
 pub use outline::*;
 pub use syntax_map::{OwnedSyntaxLayer, SyntaxLayer, ToTreeSitterPoint, TreeSitterOptions};
@@ -3,8 +3,8 @@ use crate::{
         all_language_settings, AllLanguageSettingsContent, LanguageSettingsContent,
     },
     task_context::ContextProvider,
-    with_parser, CachedLspAdapter, File, Language, LanguageConfig, LanguageId, LanguageMatcher,
-    LanguageServerName, LspAdapter, ToolchainLister, PLAIN_TEXT,
+    with_parser, CachedLspAdapter, File, FormatterProvider, Language, LanguageConfig, LanguageId,
+    LanguageMatcher, LanguageServerName, LinterProvider, LspAdapter, ToolchainLister, PLAIN_TEXT,
 };
 use anyhow::{anyhow, Context as _, Result};
 use collections::{hash_map, HashMap, HashSet};
@@ -111,6 +111,8 @@ struct LanguageRegistryState {
     all_lsp_adapters: HashMap<LanguageServerName, Arc<CachedLspAdapter>>,
     available_lsp_adapters:
         HashMap<LanguageServerName, Arc<dyn Fn() -> Arc<CachedLspAdapter> + 'static + Send + Sync>>,
+    formatters: HashMap<LanguageName, Vec<Arc<dyn FormatterProvider>>>,
+    linters: HashMap<LanguageName, Vec<Arc<dyn LinterProvider>>>,
     loading_languages: HashMap<LanguageId, Vec<oneshot::Sender<Result<Arc<Language>>>>>,
     subscription: (watch::Sender<()>, watch::Receiver<()>),
     theme: Option<Arc<Theme>>,
@@ -237,6 +239,8 @@ impl LanguageRegistry {
                 lsp_adapters: Default::default(),
                 all_lsp_adapters: Default::default(),
                 available_lsp_adapters: HashMap::default(),
+                formatters: HashMap::default(),
+                linters: HashMap::default(),
                 subscription: watch::channel(),
                 theme: Default::default(),
                 version: 0,
@@ -395,6 +399,81 @@ impl LanguageRegistry {
         }
     }
 
+    /// Registers a formatter for the given language.
+    pub fn register_formatter(
+        &self,
+        language_name: LanguageName,
+        formatter: Arc<dyn FormatterProvider>,
+    ) {
+        self.state
+            .write()
+            .formatters
+            .entry(language_name)
+            .or_default()
+            .push(formatter);
+    }
+
+    /// Removes the formatter with the given name from the given language.
+    pub fn remove_formatter(&self, language_name: &LanguageName, name: &str) {
+        if let Some(formatters) = self.state.write().formatters.get_mut(language_name) {
+            formatters.retain(|formatter| formatter.name().as_ref() != name);
+        }
+    }
+
+    /// Returns the formatter with the given name registered for the given language.
+    pub fn formatter_for_name(
+        &self,
+        language_name: &LanguageName,
+        name: &str,
+    ) -> Option<Arc<dyn FormatterProvider>> {
+        self.state
+            .read()
+            .formatters
+            .get(language_name)?
+            .iter()
+            .find(|formatter| formatter.name().as_ref() == name)
+            .cloned()
+    }
+
+    /// Registers a linter for the given language.
+    pub fn register_linter(&self, language_name: LanguageName, linter: Arc<dyn LinterProvider>) {
+        self.state
+            .write()
+            .linters
+            .entry(language_name)
+            .or_default()
+            .push(linter);
+    }
+
+    /// Removes the linter with the given name from the given language.
+    pub fn remove_linter(&self, language_name: &LanguageName, name: &str) {
+        let mut state = self.state.write();
+        if let Some(linters) = state.linters.get_mut(language_name) {
+            linters.retain(|linter| linter.name().as_ref() != name);
+        }
+        // Lets the diagnostics the linter reported be cleared.
+        *state.subscription.0.borrow_mut() = ();
+    }
+
+    /// Whether a linter with the given name is registered for any language.
+    pub fn has_linter(&self, name: &str) -> bool {
+        self.state
+            .read()
+            .linters
+            .values()
+            .flatten()
+            .any(|linter| linter.name().as_ref() == name)
+    }
+
+    pub fn linters(&self, language_name: &LanguageName) -> Vec<Arc<dyn LinterProvider>> {
+        self.state
+            .read()
+            .linters
+            .get(language_name)
+            .cloned()
+            .unwrap_or_default()
+    }
+
     /// Register a fake language server and adapter
     /// The returned channel receives a new instance of the language server every time it is started
     #[cfg(any(feature = "test-support", test))]
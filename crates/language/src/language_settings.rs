@@ -138,7 +138,7 @@ pub struct LanguageSettings {
     // Controls how the editor handles the autoclosed characters.
     pub always_treat_brackets_as_autoclosed: bool,
     /// Which code actions to run on save
-    pub code_actions_on_format: HashMap<String, bool>,
+    pub code_actions_on_format: CodeActionsOnFormat,
     /// Whether to perform linked edits
     pub linked_edits: bool,
     /// Task configuration for this language.
@@ -445,11 +445,14 @@ pub struct LanguageSettingsContent {
     ///
     /// Default: true
     pub use_on_type_format: Option<bool>,
-    /// Which code actions to run on save after the formatter.
+    /// Which code actions to run on save before the formatter.
     /// These are not run if formatting is off.
     ///
+    /// Either a map of code action kinds to whether they are enabled, which are
+    /// requested from every language server, or a list of actions that are run in order.
+    ///
     /// Default: {} (or {"source.organizeImports": true} for Go).
-    pub code_actions_on_format: Option<HashMap<String, bool>>,
+    pub code_actions_on_format: Option<CodeActionsOnFormat>,
     /// Whether to perform linked edits of associated ranges, if the language server supports it.
     /// For example, when editing opening <html> tag, the contents of the closing </html> tag will be edited as well.
     ///
@@ -814,15 +817,115 @@ pub enum Formatter {
     LanguageServer { name: Option<String> },
     /// Format code using Zed's Prettier integration.
     Prettier,
-    /// Format code using an external command.
+    /// Format code using an external command, which reads the buffer's text from stdin and writes
+    /// the formatted text to stdout. Consecutive external commands are chained, each reading the
+    /// output of the previous one.
     External {
         /// The external program to run.
         command: Arc<str>,
         /// The arguments to pass to the program.
         arguments: Option<Arc<[String]>>,
+        /// The arguments to pass to the program when formatting a range of the buffer, such as a selection.
+        /// `{range_start_line}` and `{range_end_line}` are replaced with the 1-based lines of the range,
+        /// `{range_start_offset}` and `{range_end_offset}` with its byte offsets.
+        /// When unset, the command is skipped when formatting ranges.
+        #[serde(default)]
+        range_arguments: Option<Arc<[String]>>,
+        /// What to do when the program fails.
+        ///
+        /// Default: abort
+        #[serde(default)]
+        on_failure: FormatterFailureBehavior,
     },
+    /// Format code using the formatter with the given name, provided by an extension.
+    Extension(Arc<str>),
     /// Files should be formatted using code actions executed by language servers.
-    CodeActions(HashMap<String, bool>),
+    CodeActions(CodeActionsOnFormat),
+}
+
+/// What to do when a step of the formatting pipeline fails.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FormatterFailureBehavior {
+    /// Stop formatting and report the error.
+    #[default]
+    Abort,
+    /// Log the error and continue with the next formatter.
+    Skip,
+}
+
+/// The code actions to run when formatting a buffer.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(untagged)]
+pub enum CodeActionsOnFormat {
+    /// A list of code actions, run one after another in the order of declaration.
+    List(Vec<CodeActionOnFormat>),
+    /// A map of code action kinds to whether they are enabled. The enabled kinds are
+    /// requested from every language server of the buffer, ordered by kind.
+    Map(HashMap<String, bool>),
+}
+
+impl Default for CodeActionsOnFormat {
+    fn default() -> Self {
+        Self::Map(HashMap::default())
+    }
+}
+
+impl CodeActionsOnFormat {
+    /// Returns the code actions to run, in the order they should run in.
+    pub fn actions(&self) -> Vec<CodeActionOnFormat> {
+        match self {
+            Self::List(actions) => actions.clone(),
+            Self::Map(kinds) => {
+                let mut kinds = kinds
+                    .iter()
+                    .filter(|(_, enabled)| **enabled)
+                    .map(|(kind, _)| kind.clone())
+                    .collect::<Vec<_>>();
+                kinds.sort();
+                kinds
+                    .into_iter()
+                    .map(|kind| CodeActionOnFormat {
+                        kind,
+                        language_server: None,
+                        timeout_ms: None,
+                        on_timeout: CodeActionTimeoutBehavior::default(),
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// A code action to run when formatting a buffer.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub struct CodeActionOnFormat {
+    /// The kind of the code action, e.g. `source.organizeImports`.
+    pub kind: String,
+    /// The name of the language server to request the code action from.
+    /// When unset, it is requested from every language server of the buffer.
+    #[serde(default)]
+    pub language_server: Option<String>,
+    /// How long to wait for each language server to provide, resolve and apply the code action, in milliseconds.
+    /// When unset, there is no limit.
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// What to do when the code action times out.
+    ///
+    /// Default: skip
+    #[serde(default)]
+    pub on_timeout: CodeActionTimeoutBehavior,
+}
+
+/// What to do when a code action run on format times out.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CodeActionTimeoutBehavior {
+    /// Skip the code action and continue formatting.
+    #[default]
+    Skip,
+    /// Abort formatting with an error.
+    Fail,
 }
 
 /// The settings for indent guides.
@@ -1439,6 +1542,92 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_code_actions_on_format_deserialization() {
+        let raw = r#"{"code_actions_on_format": {"source.organizeImports": true, "source.fixAll": true, "source.removeUnused": false}}"#;
+        let settings: LanguageSettingsContent = serde_json::from_str(raw).unwrap();
+        let kinds = settings
+            .code_actions_on_format
+            .unwrap()
+            .actions()
+            .into_iter()
+            .map(|action| action.kind)
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, ["source.fixAll", "source.organizeImports"]);
+
+        let raw = r#"{"code_actions_on_format": [
+            {"kind": "source.organizeImports"},
+            {"kind": "source.fixAll.eslint", "language_server": "eslint", "timeout_ms": 500, "on_timeout": "fail"}
+        ]}"#;
+        let settings: LanguageSettingsContent = serde_json::from_str(raw).unwrap();
+        assert_eq!(
+            settings.code_actions_on_format.unwrap().actions(),
+            [
+                CodeActionOnFormat {
+                    kind: "source.organizeImports".into(),
+                    language_server: None,
+                    timeout_ms: None,
+                    on_timeout: CodeActionTimeoutBehavior::Skip,
+                },
+                CodeActionOnFormat {
+                    kind: "source.fixAll.eslint".into(),
+                    language_server: Some("eslint".into()),
+                    timeout_ms: Some(500),
+                    on_timeout: CodeActionTimeoutBehavior::Fail,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_external_formatter_deserialization() {
+        let raw = r#"{"formatter": [
+            {"external": {"command": "ruff", "arguments": ["check", "--fix", "-"], "on_failure": "skip"}},
+            {"external": {"command": "black", "arguments": ["-"], "range_arguments": ["--line-ranges={range_start_line}-{range_end_line}", "-"]}}
+        ]}"#;
+        let settings: LanguageSettingsContent = serde_json::from_str(raw).unwrap();
+        assert_eq!(
+            settings.formatter,
+            Some(SelectedFormatter::List(FormatterList(
+                vec![
+                    Formatter::External {
+                        command: "ruff".into(),
+                        arguments: Some(
+                            vec!["check".to_string(), "--fix".to_string(), "-".to_string()].into()
+                        ),
+                        range_arguments: None,
+                        on_failure: FormatterFailureBehavior::Skip,
+                    },
+                    Formatter::External {
+                        command: "black".into(),
+                        arguments: Some(vec!["-".to_string()].into()),
+                        range_arguments: Some(
+                            vec![
+                                "--line-ranges={range_start_line}-{range_end_line}".to_string(),
+                                "-".to_string()
+                            ]
+                            .into()
+                        ),
+                        on_failure: FormatterFailureBehavior::Abort,
+                    },
+                ]
+                .into()
+            )))
+        );
+    }
+
+    #[test]
+    fn test_extension_formatter_deserialization() {
+        let raw = r#"{"formatter": {"extension": "ruff-format"}}"#;
+        let settings: LanguageSettingsContent = serde_json::from_str(raw).unwrap();
+        assert_eq!(
+            settings.formatter,
+            Some(SelectedFormatter::List(FormatterList(
+                Formatter::Extension("ruff-format".into()).into()
+            )))
+        );
+    }
+
     #[test]
     fn test_formatter_deserialization_invalid() {
         let raw_auto = "{\"formatter\": {}}";
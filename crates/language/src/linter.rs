@@ -0,0 +1,43 @@
+//! Provides support for linters that are not backed by a language server.
+//!
+//! A linter is registered for a set of languages and runs on every buffer of those
+//! languages when it is opened or saved. The diagnostics it reports are published
+//! alongside the ones of the buffer's language servers.
+
+use std::{ops::Range, path::PathBuf, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use gpui::SharedString;
+
+use crate::{DiagnosticSeverity, LanguageName, LspAdapterDelegate};
+
+#[async_trait]
+pub trait LinterProvider: Send + Sync {
+    /// The name of the linter, used as the source of its diagnostics.
+    fn name(&self) -> SharedString;
+
+    async fn lint(
+        &self,
+        request: LintRequest,
+        delegate: Arc<dyn LspAdapterDelegate>,
+    ) -> Result<Vec<LintDiagnostic>>;
+}
+
+/// The buffer to lint.
+#[derive(Clone, Debug)]
+pub struct LintRequest {
+    /// The path of the buffer, relative to the worktree root.
+    pub path: Option<PathBuf>,
+    pub language_name: LanguageName,
+    pub text: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LintDiagnostic {
+    /// The byte range of the diagnostic in the linted text.
+    pub range: Range<usize>,
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+    pub code: Option<String>,
+}
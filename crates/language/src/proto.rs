@@ -7,6 +7,7 @@ use lsp::{DiagnosticSeverity, LanguageServerId};
 use rpc::proto;
 use serde_json::Value;
 use std::{ops::Range, str::FromStr, sync::Arc};
+use sum_tree::TreeMap;
 use text::*;
 
 pub use proto::{BufferState, Operation};
@@ -515,6 +516,58 @@ pub fn deserialize_transaction(transaction: proto::Transaction) -> Result<Transa
     })
 }
 
+/// Serializes a buffer's base text, operations and undo history, so that the
+/// buffer can be reconstructed with [`deserialize_history`].
+pub fn serialize_history(buffer: &text::Buffer) -> proto::BufferHistory {
+    proto::BufferHistory {
+        base_text: buffer.base_text().to_string(),
+        operations: buffer
+            .operations()
+            .values()
+            .map(|operation| serialize_operation(&crate::Operation::Buffer(operation.clone())))
+            .collect(),
+        undo_stack: buffer.undo_stack().map(serialize_transaction).collect(),
+        redo_stack: buffer.redo_stack().map(serialize_transaction).collect(),
+    }
+}
+
+/// Reconstructs a buffer from the representation produced by [`serialize_history`],
+/// replaying its operations and restoring its undo and redo stacks.
+pub fn deserialize_history(
+    history: proto::BufferHistory,
+    buffer_id: BufferId,
+    line_ending: LineEnding,
+) -> Result<text::Buffer> {
+    let mut operations = Vec::with_capacity(history.operations.len());
+    for operation in history.operations {
+        if let crate::Operation::Buffer(operation) = deserialize_operation(operation)? {
+            operations.push((operation.timestamp(), operation));
+        }
+    }
+    operations.sort_by_key(|(timestamp, _)| *timestamp);
+
+    let undo_stack = history
+        .undo_stack
+        .into_iter()
+        .map(deserialize_transaction)
+        .collect::<Result<_>>()?;
+    let redo_stack = history
+        .redo_stack
+        .into_iter()
+        .map(deserialize_transaction)
+        .collect::<Result<_>>()?;
+    text::Buffer::from_history(
+        buffer_id,
+        line_ending,
+        text::BufferHistory {
+            base_text: Rope::from(history.base_text),
+            operations: TreeMap::from_ordered_entries(operations),
+            undo_stack,
+            redo_stack,
+        },
+    )
+}
+
 /// Serializes a [`clock::Lamport`] timestamp to be sent over RPC.
 pub fn serialize_timestamp(timestamp: clock::Lamport) -> proto::LamportTimestamp {
     proto::LamportTimestamp {
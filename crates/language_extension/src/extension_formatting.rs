@@ -0,0 +1,147 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use extension::{
+    Extension, ExtensionFormatterProxy, ExtensionLinterProxy, FormatOutput, LintSeverity,
+    SourceFile,
+};
+use gpui::SharedString;
+use language::{
+    DiagnosticSeverity, FormatRequest, FormatResponse, FormatterCommand, FormatterProvider,
+    LanguageName, LintDiagnostic, LintRequest, LinterProvider, LspAdapterDelegate,
+};
+
+use crate::extension_lsp_adapter::WorktreeDelegateAdapter;
+use crate::LanguageServerRegistryProxy;
+
+impl ExtensionFormatterProxy for LanguageServerRegistryProxy {
+    fn register_formatter(
+        &self,
+        extension: Arc<dyn Extension>,
+        formatter_id: Arc<str>,
+        language: LanguageName,
+    ) {
+        self.language_registry.register_formatter(
+            language,
+            Arc::new(ExtensionFormatter {
+                extension,
+                formatter_id,
+            }),
+        );
+    }
+
+    fn remove_formatter(&self, language: &LanguageName, formatter_id: &Arc<str>) {
+        self.language_registry
+            .remove_formatter(language, formatter_id);
+    }
+}
+
+impl ExtensionLinterProxy for LanguageServerRegistryProxy {
+    fn register_linter(
+        &self,
+        extension: Arc<dyn Extension>,
+        linter_id: Arc<str>,
+        language: LanguageName,
+    ) {
+        self.language_registry.register_linter(
+            language,
+            Arc::new(ExtensionLinter {
+                extension,
+                linter_id,
+            }),
+        );
+    }
+
+    fn remove_linter(&self, language: &LanguageName, linter_id: &Arc<str>) {
+        self.language_registry.remove_linter(language, linter_id);
+    }
+}
+
+struct ExtensionFormatter {
+    extension: Arc<dyn Extension>,
+    formatter_id: Arc<str>,
+}
+
+#[async_trait]
+impl FormatterProvider for ExtensionFormatter {
+    fn name(&self) -> SharedString {
+        SharedString::from(self.formatter_id.clone())
+    }
+
+    async fn format(
+        &self,
+        request: FormatRequest,
+        delegate: Arc<dyn LspAdapterDelegate>,
+    ) -> Result<FormatResponse> {
+        let file = SourceFile {
+            path: request.path.map(|path| path.to_string_lossy().into_owned()),
+            language_name: request.language_name.to_string(),
+            text: request.text,
+        };
+        let output = self
+            .extension
+            .format(
+                self.formatter_id.clone(),
+                Arc::new(WorktreeDelegateAdapter(delegate)),
+                file,
+            )
+            .await?;
+
+        Ok(match output {
+            FormatOutput::Command(command) => FormatResponse::Command(FormatterCommand {
+                path: command.command.into(),
+                arguments: command.args,
+                env: command.env.into_iter().collect(),
+            }),
+            FormatOutput::Text(text) => FormatResponse::Text(text),
+        })
+    }
+}
+
+struct ExtensionLinter {
+    extension: Arc<dyn Extension>,
+    linter_id: Arc<str>,
+}
+
+#[async_trait]
+impl LinterProvider for ExtensionLinter {
+    fn name(&self) -> SharedString {
+        SharedString::from(self.linter_id.clone())
+    }
+
+    async fn lint(
+        &self,
+        request: LintRequest,
+        delegate: Arc<dyn LspAdapterDelegate>,
+    ) -> Result<Vec<LintDiagnostic>> {
+        let file = SourceFile {
+            path: request.path.map(|path| path.to_string_lossy().into_owned()),
+            language_name: request.language_name.to_string(),
+            text: request.text,
+        };
+        let diagnostics = self
+            .extension
+            .lint(
+                self.linter_id.clone(),
+                Arc::new(WorktreeDelegateAdapter(delegate)),
+                file,
+            )
+            .await?;
+
+        Ok(diagnostics
+            .into_iter()
+            .map(|diagnostic| LintDiagnostic {
+                range: diagnostic.range,
+                severity: match diagnostic.severity {
+                    LintSeverity::Error => DiagnosticSeverity::ERROR,
+                    LintSeverity::Warning => DiagnosticSeverity::WARNING,
+                    LintSeverity::Information => DiagnosticSeverity::INFORMATION,
+                    LintSeverity::Hint => DiagnosticSeverity::HINT,
+                },
+                message: diagnostic.message,
+                code: diagnostic.code,
+            })
+            .collect())
+    }
+}
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use client::{Client, UserStore};
+use extension::ExtensionHostProxy;
 use fs::Fs;
 use gpui::{App, Context, Entity};
 use language_model::{LanguageModelProviderId, LanguageModelRegistry, ZED_CLOUD_PROVIDER_ID};
@@ -13,6 +14,7 @@ use crate::provider::anthropic::AnthropicLanguageModelProvider;
 use crate::provider::bedrock::BedrockLanguageModelProvider;
 use crate::provider::cloud::CloudLanguageModelProvider;
 use crate::provider::copilot_chat::CopilotChatLanguageModelProvider;
+use crate::provider::extension::ExtensionLanguageModelProviderRegistryProxy;
 use crate::provider::google::GoogleLanguageModelProvider;
 use crate::provider::lmstudio::LmStudioLanguageModelProvider;
 use crate::provider::mistral::MistralLanguageModelProvider;
@@ -26,6 +28,9 @@ pub fn init(user_store: Entity<UserStore>, client: Arc<Client>, fs: Arc<dyn Fs>,
     registry.update(cx, |registry, cx| {
         register_language_model_providers(registry, user_store, client, cx);
     });
+
+    ExtensionHostProxy::default_global(cx)
+        .register_language_model_provider_proxy(ExtensionLanguageModelProviderRegistryProxy);
 }
 
 fn register_language_model_providers(
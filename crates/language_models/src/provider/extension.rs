@@ -0,0 +1,934 @@
+use anyhow::{anyhow, Context as _, Result};
+use credentials_provider::CredentialsProvider;
+use editor::{Editor, EditorElement, EditorStyle};
+use extension::{
+    CompletionEvent, CompletionRequest, Extension, ExtensionLanguageModelProviderProxy,
+    LanguageModelInfo, LanguageModelProviderManifestEntry, MessageRole, RequestMessage,
+};
+use futures::{future::BoxFuture, stream::BoxStream, FutureExt, StreamExt};
+use gpui::{AnyView, App, AsyncApp, Context, Entity, FontStyle, Task, TextStyle, WhiteSpace};
+use language_model::{
+    AuthenticateError, LanguageModel, LanguageModelCompletionEvent, LanguageModelId,
+    LanguageModelName, LanguageModelProvider, LanguageModelProviderId, LanguageModelProviderName,
+    LanguageModelProviderState, LanguageModelRegistry, LanguageModelRequest, RateLimiter, Role,
+    StopReason,
+};
+use settings::Settings;
+use std::sync::Arc;
+use theme::ThemeSettings;
+use ui::{prelude::*, Icon, IconName, Tooltip};
+use util::ResultExt;
+
+/// Registers the language model providers of extensions with the
+/// [`LanguageModelRegistry`].
+pub struct ExtensionLanguageModelProviderRegistryProxy;
+
+impl ExtensionLanguageModelProviderProxy for ExtensionLanguageModelProviderRegistryProxy {
+    fn register_language_model_provider(
+        &self,
+        extension: Arc<dyn Extension>,
+        provider_id: Arc<str>,
+        cx: &mut App,
+    ) {
+        let manifest = extension.manifest();
+        let Some(entry) = manifest.language_model_providers.get(&provider_id).cloned() else {
+            return;
+        };
+
+        let id = extension_provider_id(&manifest.id, &provider_id);
+        let registry = LanguageModelRegistry::global(cx);
+        if registry.read(cx).provider(&id).is_some() {
+            log::error!("language model provider {id} is already registered");
+            return;
+        }
+
+        let provider = ExtensionLanguageModelProvider::new(extension, provider_id, entry, cx);
+        registry.update(cx, |registry, cx| {
+            registry.register_provider(provider, cx);
+        });
+    }
+
+    fn remove_language_model_provider(
+        &self,
+        extension_id: &Arc<str>,
+        provider_id: &Arc<str>,
+        cx: &mut App,
+    ) {
+        LanguageModelRegistry::global(cx).update(cx, |registry, cx| {
+            registry.unregister_provider(extension_provider_id(extension_id, provider_id), cx);
+        });
+    }
+}
+
+/// Returns the ID that a language model provider of an extension is registered
+/// under.
+///
+/// It's namespaced by the ID of the extension, so that extensions can't
+/// replace the built-in providers or those of other extensions.
+pub fn extension_provider_id(extension_id: &str, provider_id: &str) -> LanguageModelProviderId {
+    LanguageModelProviderId(format!("{extension_id}/{provider_id}").into())
+}
+
+/// A [`LanguageModelProvider`] implemented by an extension.
+pub struct ExtensionLanguageModelProvider {
+    id: LanguageModelProviderId,
+    extension: Arc<dyn Extension>,
+    provider_id: Arc<str>,
+    name: SharedString,
+    state: Entity<State>,
+}
+
+pub struct State {
+    extension: Arc<dyn Extension>,
+    provider_id: Arc<str>,
+    entry: LanguageModelProviderManifestEntry,
+    api_key: Option<String>,
+    api_key_from_env: bool,
+    models: Vec<LanguageModelInfo>,
+    fetch_models_task: Option<Task<Result<()>>>,
+}
+
+impl State {
+    fn is_authenticated(&self) -> bool {
+        !self.entry.requires_api_key || self.api_key.is_some()
+    }
+
+    /// The key under which the API key of the provider is stored in the
+    /// system keychain.
+    fn credentials_url(&self) -> String {
+        format!(
+            "zed-extension://{}/{}",
+            self.extension.manifest().id,
+            self.provider_id
+        )
+    }
+
+    fn reset_api_key(&self, cx: &mut Context<Self>) -> Task<Result<()>> {
+        let credentials_provider = <dyn CredentialsProvider>::global(cx);
+        let credentials_url = self.credentials_url();
+        cx.spawn(|this, mut cx| async move {
+            credentials_provider
+                .delete_credentials(&credentials_url, &cx)
+                .await
+                .log_err();
+            this.update(&mut cx, |this, cx| {
+                this.api_key = None;
+                this.api_key_from_env = false;
+                this.restart_fetch_models_task(cx);
+                cx.notify();
+            })
+        })
+    }
+
+    fn set_api_key(&mut self, api_key: String, cx: &mut Context<Self>) -> Task<Result<()>> {
+        let credentials_provider = <dyn CredentialsProvider>::global(cx);
+        let credentials_url = self.credentials_url();
+        cx.spawn(|this, mut cx| async move {
+            credentials_provider
+                .write_credentials(&credentials_url, "Bearer", api_key.as_bytes(), &cx)
+                .await
+                .log_err();
+            this.update(&mut cx, |this, cx| {
+                this.api_key = Some(api_key);
+                this.restart_fetch_models_task(cx);
+                cx.notify();
+            })
+        })
+    }
+
+    fn authenticate(&mut self, cx: &mut Context<Self>) -> Task<Result<(), AuthenticateError>> {
+        if self.api_key.is_some() {
+            return Task::ready(Ok(()));
+        }
+
+        let credentials_provider = <dyn CredentialsProvider>::global(cx);
+        let credentials_url = self.credentials_url();
+        let api_key_env_var = self.entry.api_key_env_var.clone();
+        let requires_api_key = self.entry.requires_api_key;
+        cx.spawn(|this, mut cx| async move {
+            let env_api_key = api_key_env_var.and_then(|var| std::env::var(var).ok());
+            let (api_key, from_env) = if let Some(api_key) = env_api_key {
+                (Some(api_key), true)
+            } else {
+                let credentials = credentials_provider
+                    .read_credentials(&credentials_url, &cx)
+                    .await?;
+                match credentials {
+                    Some((_, api_key)) => (
+                        Some(String::from_utf8(api_key).context("invalid API key")?),
+                        false,
+                    ),
+                    None if requires_api_key => return Err(AuthenticateError::CredentialsNotFound),
+                    None => (None, false),
+                }
+            };
+
+            let fetch_models_task = this.update(&mut cx, |this, cx| {
+                this.api_key = api_key;
+                this.api_key_from_env = from_env;
+                cx.notify();
+                this.fetch_models(cx)
+            })?;
+            fetch_models_task.await?;
+
+            Ok(())
+        })
+    }
+
+    fn fetch_models(&mut self, cx: &mut Context<Self>) -> Task<Result<()>> {
+        let extension = self.extension.clone();
+        let provider_id = self.provider_id.clone();
+        let api_key = self.api_key.clone();
+        cx.spawn(|this, mut cx| async move {
+            let models = extension.language_models(provider_id, api_key).await?;
+            this.update(&mut cx, |this, cx| {
+                this.models = models;
+                cx.notify();
+            })
+        })
+    }
+
+    fn restart_fetch_models_task(&mut self, cx: &mut Context<Self>) {
+        if self.is_authenticated() {
+            let task = self.fetch_models(cx);
+            self.fetch_models_task.replace(task);
+        } else {
+            self.models.clear();
+            self.fetch_models_task = None;
+        }
+    }
+}
+
+impl ExtensionLanguageModelProvider {
+    pub fn new(
+        extension: Arc<dyn Extension>,
+        provider_id: Arc<str>,
+        entry: LanguageModelProviderManifestEntry,
+        cx: &mut App,
+    ) -> Self {
+        let name = SharedString::from(entry.name.clone());
+        let state = cx.new(|_| State {
+            extension: extension.clone(),
+            provider_id: provider_id.clone(),
+            entry,
+            api_key: None,
+            api_key_from_env: false,
+            models: Vec::new(),
+            fetch_models_task: None,
+        });
+
+        state.update(cx, |state, cx| state.restart_fetch_models_task(cx));
+
+        Self {
+            id: extension_provider_id(&extension.manifest().id, &provider_id),
+            extension,
+            provider_id,
+            name,
+            state,
+        }
+    }
+
+    fn create_language_model(&self, model: LanguageModelInfo) -> Arc<dyn LanguageModel> {
+        Arc::new(ExtensionLanguageModel {
+            id: LanguageModelId::from(model.id.clone()),
+            model,
+            extension: self.extension.clone(),
+            provider_id: self.provider_id.clone(),
+            registered_provider_id: self.id.clone(),
+            provider_name: self.name.clone(),
+            state: self.state.clone(),
+            request_limiter: RateLimiter::new(4),
+        })
+    }
+}
+
+impl LanguageModelProviderState for ExtensionLanguageModelProvider {
+    type ObservableEntity = State;
+
+    fn observable_entity(&self) -> Option<Entity<Self::ObservableEntity>> {
+        Some(self.state.clone())
+    }
+}
+
+impl LanguageModelProvider for ExtensionLanguageModelProvider {
+    fn id(&self) -> LanguageModelProviderId {
+        self.id.clone()
+    }
+
+    fn name(&self) -> LanguageModelProviderName {
+        LanguageModelProviderName(self.name.clone())
+    }
+
+    fn default_model(&self, cx: &App) -> Option<Arc<dyn LanguageModel>> {
+        let model = self.state.read(cx).models.first()?.clone();
+        Some(self.create_language_model(model))
+    }
+
+    fn provided_models(&self, cx: &App) -> Vec<Arc<dyn LanguageModel>> {
+        self.state
+            .read(cx)
+            .models
+            .iter()
+            .map(|model| self.create_language_model(model.clone()))
+            .collect()
+    }
+
+    fn is_authenticated(&self, cx: &App) -> bool {
+        self.state.read(cx).is_authenticated()
+    }
+
+    fn authenticate(&self, cx: &mut App) -> Task<Result<(), AuthenticateError>> {
+        self.state.update(cx, |state, cx| state.authenticate(cx))
+    }
+
+    fn configuration_view(&self, window: &mut Window, cx: &mut App) -> AnyView {
+        cx.new(|cx| ConfigurationView::new(self.state.clone(), window, cx))
+            .into()
+    }
+
+    fn reset_credentials(&self, cx: &mut App) -> Task<Result<()>> {
+        self.state.update(cx, |state, cx| state.reset_api_key(cx))
+    }
+}
+
+pub struct ExtensionLanguageModel {
+    id: LanguageModelId,
+    model: LanguageModelInfo,
+    extension: Arc<dyn Extension>,
+    provider_id: Arc<str>,
+    registered_provider_id: LanguageModelProviderId,
+    provider_name: SharedString,
+    state: Entity<State>,
+    request_limiter: RateLimiter,
+}
+
+impl LanguageModel for ExtensionLanguageModel {
+    fn id(&self) -> LanguageModelId {
+        self.id.clone()
+    }
+
+    fn name(&self) -> LanguageModelName {
+        LanguageModelName::from(self.model.name.clone())
+    }
+
+    fn provider_id(&self) -> LanguageModelProviderId {
+        self.registered_provider_id.clone()
+    }
+
+    fn provider_name(&self) -> LanguageModelProviderName {
+        LanguageModelProviderName(self.provider_name.clone())
+    }
+
+    fn telemetry_id(&self) -> String {
+        format!("{}/{}", self.registered_provider_id, self.model.id)
+    }
+
+    fn max_token_count(&self) -> usize {
+        self.model.max_token_count
+    }
+
+    fn max_output_tokens(&self) -> Option<u32> {
+        self.model.max_output_tokens
+    }
+
+    fn count_tokens(
+        &self,
+        request: LanguageModelRequest,
+        _cx: &App,
+    ) -> BoxFuture<'static, Result<usize>> {
+        let extension = self.extension.clone();
+        let provider_id = self.provider_id.clone();
+        let model_id = Arc::from(self.model.id.as_str());
+        async move {
+            extension
+                .count_language_model_tokens(provider_id, model_id, into_extension(request))
+                .await
+        }
+        .boxed()
+    }
+
+    fn stream_completion(
+        &self,
+        request: LanguageModelRequest,
+        cx: &AsyncApp,
+    ) -> BoxFuture<'static, Result<BoxStream<'static, Result<LanguageModelCompletionEvent>>>> {
+        let Ok(api_key) = cx.read_entity(&self.state, |state, _| state.api_key.clone()) else {
+            return futures::future::ready(Err(anyhow!("App state dropped"))).boxed();
+        };
+
+        let extension = self.extension.clone();
+        let provider_id = self.provider_id.clone();
+        let model_id = Arc::from(self.model.id.as_str());
+        let future = self.request_limiter.stream(async move {
+            extension
+                .stream_completion(provider_id, model_id, into_extension(request), api_key)
+                .await
+        });
+
+        async move {
+            Ok(future
+                .await?
+                .map(|event| event.map(into_completion_event))
+                .boxed())
+        }
+        .boxed()
+    }
+
+    fn use_any_tool(
+        &self,
+        _request: LanguageModelRequest,
+        _tool_name: String,
+        _tool_description: String,
+        _schema: serde_json::Value,
+        _cx: &AsyncApp,
+    ) -> BoxFuture<'static, Result<BoxStream<'static, Result<String>>>> {
+        futures::future::ready(Err(anyhow!(
+            "tool use is not supported by extension language models"
+        )))
+        .boxed()
+    }
+}
+
+fn into_extension(request: LanguageModelRequest) -> CompletionRequest {
+    CompletionRequest {
+        messages: request
+            .messages
+            .into_iter()
+            .map(|message| RequestMessage {
+                role: match message.role {
+                    Role::User => MessageRole::User,
+                    Role::Assistant => MessageRole::Assistant,
+                    Role::System => MessageRole::System,
+                },
+                content: message.string_contents(),
+            })
+            .collect(),
+        stop: request.stop,
+        temperature: request.temperature,
+    }
+}
+
+fn into_completion_event(event: CompletionEvent) -> LanguageModelCompletionEvent {
+    match event {
+        CompletionEvent::Text(text) => LanguageModelCompletionEvent::Text(text),
+        CompletionEvent::Stop(reason) => LanguageModelCompletionEvent::Stop(match reason {
+            extension::StopReason::EndTurn => StopReason::EndTurn,
+            extension::StopReason::MaxTokens => StopReason::MaxTokens,
+            extension::StopReason::ToolUse => StopReason::ToolUse,
+        }),
+    }
+}
+
+struct ConfigurationView {
+    api_key_editor: Entity<Editor>,
+    state: Entity<State>,
+    load_credentials_task: Option<Task<()>>,
+}
+
+impl ConfigurationView {
+    fn new(state: Entity<State>, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let api_key_editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text("API key", cx);
+            editor
+        });
+
+        cx.observe(&state, |_, _, cx| {
+            cx.notify();
+        })
+        .detach();
+
+        let load_credentials_task = Some(cx.spawn_in(window, {
+            let state = state.clone();
+            |this, mut cx| async move {
+                if let Some(task) = state
+                    .update(&mut cx, |state, cx| state.authenticate(cx))
+                    .log_err()
+                {
+                    // We don't log an error, because "not signed in" is also an error.
+                    let _ = task.await;
+                }
+
+                this.update(&mut cx, |this, cx| {
+                    this.load_credentials_task = None;
+                    cx.notify();
+                })
+                .log_err();
+            }
+        }));
+
+        Self {
+            api_key_editor,
+            state,
+            load_credentials_task,
+        }
+    }
+
+    fn save_api_key(&mut self, _: &menu::Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        let api_key = self.api_key_editor.read(cx).text(cx);
+        if api_key.is_empty() {
+            return;
+        }
+
+        let state = self.state.clone();
+        cx.spawn_in(window, |_, mut cx| async move {
+            state
+                .update(&mut cx, |state, cx| state.set_api_key(api_key, cx))?
+                .await
+        })
+        .detach_and_log_err(cx);
+
+        cx.notify();
+    }
+
+    fn reset_api_key(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.api_key_editor
+            .update(cx, |editor, cx| editor.set_text("", window, cx));
+
+        let state = self.state.clone();
+        cx.spawn_in(window, |_, mut cx| async move {
+            state
+                .update(&mut cx, |state, cx| state.reset_api_key(cx))?
+                .await
+        })
+        .detach_and_log_err(cx);
+
+        cx.notify();
+    }
+
+    fn render_api_key_editor(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let settings = ThemeSettings::get_global(cx);
+        let text_style = TextStyle {
+            color: cx.theme().colors().text,
+            font_family: settings.ui_font.family.clone(),
+            font_features: settings.ui_font.features.clone(),
+            font_fallbacks: settings.ui_font.fallbacks.clone(),
+            font_size: rems(0.875).into(),
+            font_weight: settings.ui_font.weight,
+            font_style: FontStyle::Normal,
+            line_height: relative(1.3),
+            white_space: WhiteSpace::Normal,
+            ..Default::default()
+        };
+        EditorElement::new(
+            &self.api_key_editor,
+            EditorStyle {
+                background: cx.theme().colors().editor_background,
+                local_player: cx.theme().players().local(),
+                text: text_style,
+                ..Default::default()
+            },
+        )
+    }
+}
+
+impl Render for ConfigurationView {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let state = self.state.read(cx);
+        let provider_name = state.entry.name.clone();
+        let extension_name = state.extension.manifest().name.clone();
+        let api_key_env_var = state.entry.api_key_env_var.clone();
+        let api_key_set = state.api_key.is_some();
+        let env_var_set = state.api_key_from_env;
+
+        if self.load_credentials_task.is_some() {
+            div().child(Label::new("Loading credentials...")).into_any()
+        } else if !api_key_set {
+            v_flex()
+                .size_full()
+                .on_action(cx.listener(Self::save_api_key))
+                .child(Label::new(format!(
+                    "{provider_name} is provided by the {extension_name} extension."
+                )))
+                .child(Label::new(
+                    "Paste your API key below and hit enter to start using the assistant.",
+                ))
+                .child(
+                    h_flex()
+                        .w_full()
+                        .my_2()
+                        .px_2()
+                        .py_1()
+                        .bg(cx.theme().colors().editor_background)
+                        .border_1()
+                        .border_color(cx.theme().colors().border_variant)
+                        .rounded_md()
+                        .child(self.render_api_key_editor(cx)),
+                )
+                .when_some(api_key_env_var, |this, env_var| {
+                    this.child(
+                        Label::new(format!(
+                            "You can also assign the {env_var} environment variable and restart Zed."
+                        ))
+                        .size(LabelSize::Small),
+                    )
+                })
+                .into_any()
+        } else {
+            let env_var_name = api_key_env_var.unwrap_or_default();
+            h_flex()
+                .size_full()
+                .justify_between()
+                .child(
+                    h_flex()
+                        .gap_1()
+                        .child(Icon::new(IconName::Check).color(Color::Success))
+                        .child(Label::new(if env_var_set {
+                            format!("API key set in {env_var_name} environment variable.")
+                        } else {
+                            "API key configured.".to_string()
+                        })),
+                )
+                .child(
+                    Button::new("reset-key", "Reset key")
+                        .icon(Some(IconName::Trash))
+                        .icon_size(IconSize::Small)
+                        .icon_position(IconPosition::Start)
+                        .disabled(env_var_set)
+                        .when(env_var_set, |this| {
+                            this.tooltip(Tooltip::text(format!(
+                                "To reset your API key, unset the {env_var_name} environment variable."
+                            )))
+                        })
+                        .on_click(
+                            cx.listener(|this, _, window, cx| this.reset_api_key(window, cx)),
+                        ),
+                )
+                .into_any()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::bail;
+    use async_trait::async_trait;
+    use extension::{
+        BufferDelegate, CodeLabel, Command, Completion, EditorCommand, EnvVars, ExtensionManifest,
+        FormatOutput, KeyValueStoreDelegate, LintDiagnostic, ProjectDelegate, SlashCommand,
+        SlashCommandArgumentCompletion, SlashCommandOutput, SourceFile, Symbol, TaskTemplate,
+        TextEdit, WorktreeDelegate,
+    };
+    use gpui::TestAppContext;
+    use language::LanguageName;
+    use lsp::LanguageServerName;
+    use serde_json::json;
+    use std::path::Path;
+
+    /// An extension that only provides language models.
+    struct FakeExtension {
+        manifest: Arc<ExtensionManifest>,
+    }
+
+    impl FakeExtension {
+        fn new(extension_id: &str, provider_id: &str, provider_name: &str) -> Arc<Self> {
+            let manifest = serde_json::from_value(json!({
+                "id": extension_id,
+                "name": extension_id,
+                "version": "1.0.0",
+                "schema_version": 1,
+                "language_model_providers": {
+                    provider_id: { "name": provider_name },
+                },
+            }))
+            .unwrap();
+            Arc::new(Self {
+                manifest: Arc::new(manifest),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl Extension for FakeExtension {
+        fn manifest(&self) -> Arc<ExtensionManifest> {
+            self.manifest.clone()
+        }
+
+        fn work_dir(&self) -> Arc<Path> {
+            Path::new("/extensions/work").into()
+        }
+
+        async fn language_server_command(
+            &self,
+            _: LanguageServerName,
+            _: LanguageName,
+            _: Arc<dyn WorktreeDelegate>,
+        ) -> Result<Command> {
+            bail!("not supported")
+        }
+
+        async fn language_server_initialization_options(
+            &self,
+            _: LanguageServerName,
+            _: LanguageName,
+            _: Arc<dyn WorktreeDelegate>,
+        ) -> Result<Option<String>> {
+            bail!("not supported")
+        }
+
+        async fn language_server_workspace_configuration(
+            &self,
+            _: LanguageServerName,
+            _: Arc<dyn WorktreeDelegate>,
+        ) -> Result<Option<String>> {
+            bail!("not supported")
+        }
+
+        async fn labels_for_completions(
+            &self,
+            _: LanguageServerName,
+            _: Vec<Completion>,
+        ) -> Result<Vec<Option<CodeLabel>>> {
+            bail!("not supported")
+        }
+
+        async fn labels_for_symbols(
+            &self,
+            _: LanguageServerName,
+            _: Vec<Symbol>,
+        ) -> Result<Vec<Option<CodeLabel>>> {
+            bail!("not supported")
+        }
+
+        async fn complete_slash_command_argument(
+            &self,
+            _: SlashCommand,
+            _: Vec<String>,
+        ) -> Result<Vec<SlashCommandArgumentCompletion>> {
+            bail!("not supported")
+        }
+
+        async fn run_slash_command(
+            &self,
+            _: SlashCommand,
+            _: Vec<String>,
+            _: Option<Arc<dyn WorktreeDelegate>>,
+        ) -> Result<SlashCommandOutput> {
+            bail!("not supported")
+        }
+
+        async fn context_server_command(
+            &self,
+            _: Arc<str>,
+            _: Arc<dyn ProjectDelegate>,
+        ) -> Result<Command> {
+            bail!("not supported")
+        }
+
+        async fn suggest_docs_packages(&self, _: Arc<str>) -> Result<Vec<String>> {
+            bail!("not supported")
+        }
+
+        async fn index_docs(
+            &self,
+            _: Arc<str>,
+            _: Arc<str>,
+            _: Arc<dyn KeyValueStoreDelegate>,
+        ) -> Result<()> {
+            bail!("not supported")
+        }
+
+        async fn run_editor_command(
+            &self,
+            _: EditorCommand,
+            _: Arc<dyn BufferDelegate>,
+        ) -> Result<Vec<TextEdit>> {
+            bail!("not supported")
+        }
+
+        async fn task_templates(
+            &self,
+            _: Arc<str>,
+            _: Arc<dyn WorktreeDelegate>,
+        ) -> Result<Vec<TaskTemplate>> {
+            bail!("not supported")
+        }
+
+        async fn task_variables(
+            &self,
+            _: Arc<str>,
+            _: Arc<dyn WorktreeDelegate>,
+            _: EnvVars,
+        ) -> Result<EnvVars> {
+            bail!("not supported")
+        }
+
+        async fn format(
+            &self,
+            _: Arc<str>,
+            _: Arc<dyn WorktreeDelegate>,
+            _: SourceFile,
+        ) -> Result<FormatOutput> {
+            bail!("not supported")
+        }
+
+        async fn lint(
+            &self,
+            _: Arc<str>,
+            _: Arc<dyn WorktreeDelegate>,
+            _: SourceFile,
+        ) -> Result<Vec<LintDiagnostic>> {
+            bail!("not supported")
+        }
+
+        async fn language_models(
+            &self,
+            provider_id: Arc<str>,
+            _: Option<String>,
+        ) -> Result<Vec<LanguageModelInfo>> {
+            Ok(vec![LanguageModelInfo {
+                id: format!("{provider_id}-model"),
+                name: format!("{provider_id} model"),
+                max_token_count: 1000,
+                max_output_tokens: None,
+            }])
+        }
+
+        async fn count_language_model_tokens(
+            &self,
+            _: Arc<str>,
+            _: Arc<str>,
+            _: CompletionRequest,
+        ) -> Result<usize> {
+            bail!("not supported")
+        }
+
+        async fn stream_completion(
+            &self,
+            _: Arc<str>,
+            _: Arc<str>,
+            _: CompletionRequest,
+            _: Option<String>,
+        ) -> Result<BoxStream<'static, Result<CompletionEvent>>> {
+            bail!("not supported")
+        }
+    }
+
+    fn register(extension: Arc<FakeExtension>, provider_id: &str, cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            ExtensionLanguageModelProviderRegistryProxy.register_language_model_provider(
+                extension,
+                provider_id.into(),
+                cx,
+            )
+        });
+        cx.run_until_parked();
+    }
+
+    fn provider_names(cx: &mut TestAppContext) -> Vec<(String, String)> {
+        cx.update(|cx| {
+            let mut providers = LanguageModelRegistry::read_global(cx)
+                .providers()
+                .into_iter()
+                .map(|provider| (provider.id().0.to_string(), provider.name().0.to_string()))
+                .collect::<Vec<_>>();
+            providers.sort();
+            providers
+        })
+    }
+
+    #[gpui::test]
+    fn test_register_and_unregister_providers(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            LanguageModelRegistry::test(cx);
+        });
+
+        register(
+            FakeExtension::new("my-extension", "my-provider", "Mine"),
+            "my-provider",
+            cx,
+        );
+        assert_eq!(
+            provider_names(cx),
+            [
+                ("fake".to_string(), "Fake".to_string()),
+                ("my-extension/my-provider".to_string(), "Mine".to_string()),
+            ]
+        );
+
+        let models = cx.update(|cx| {
+            LanguageModelRegistry::read_global(cx)
+                .available_models(cx)
+                .filter(|model| model.provider_id().0 == "my-extension/my-provider")
+                .map(|model| (model.id().0.to_string(), model.telemetry_id()))
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(
+            models,
+            [(
+                "my-provider-model".to_string(),
+                "my-extension/my-provider/my-provider-model".to_string()
+            )]
+        );
+
+        // Providers that aren't in the manifest aren't registered.
+        register(
+            FakeExtension::new("my-extension", "my-provider", "Mine"),
+            "other",
+            cx,
+        );
+        assert_eq!(provider_names(cx).len(), 2);
+
+        cx.update(|cx| {
+            ExtensionLanguageModelProviderRegistryProxy.remove_language_model_provider(
+                &"my-extension".into(),
+                &"my-provider".into(),
+                cx,
+            )
+        });
+        assert_eq!(
+            provider_names(cx),
+            [("fake".to_string(), "Fake".to_string())]
+        );
+    }
+
+    #[gpui::test]
+    fn test_provider_id_collisions(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            LanguageModelRegistry::test(cx);
+        });
+
+        // A provider with the same ID as a built-in one doesn't replace it.
+        register(
+            FakeExtension::new("my-extension", "fake", "Mine"),
+            "fake",
+            cx,
+        );
+        // Nor can another extension replace the provider of an extension.
+        register(
+            FakeExtension::new("other-extension", "fake", "Other"),
+            "fake",
+            cx,
+        );
+        register(
+            FakeExtension::new("my-extension", "fake", "Imposter"),
+            "fake",
+            cx,
+        );
+        assert_eq!(
+            provider_names(cx),
+            [
+                ("fake".to_string(), "Fake".to_string()),
+                ("my-extension/fake".to_string(), "Mine".to_string()),
+                ("other-extension/fake".to_string(), "Other".to_string()),
+            ]
+        );
+
+        // Removing the provider of an extension leaves those of the same name alone.
+        cx.update(|cx| {
+            ExtensionLanguageModelProviderRegistryProxy.remove_language_model_provider(
+                &"my-extension".into(),
+                &"fake".into(),
+                cx,
+            )
+        });
+        assert_eq!(
+            provider_names(cx),
+            [
+                ("fake".to_string(), "Fake".to_string()),
+                ("other-extension/fake".to_string(), "Other".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,797 @@
+//! # Large File Viewer
+//!
+//! Files larger than the `large_file_threshold` worktree setting aren't loaded into a buffer,
+//! as the whole of their text would have to be kept in memory. This crate opens them in a
+//! read-only view instead, which indexes the file's lines once and then reads the lines that
+//! are scrolled into view from disk, keeping a bounded number of them in memory. Searching reads
+//! through the file in chunks, and the lines are indexed again whenever the file changes on disk.
+//! There's no syntax highlighting, and no language servers are started for these files.
+//!
+//! Only files in local worktrees are opened this way. Files in remote worktrees are opened in an
+//! editor as usual, as they're only available through their buffers.
+
+mod paged_file;
+
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use collections::{HashMap, HashSet};
+use editor::{items::entry_git_aware_label_color, Editor};
+use file_icons::FileIcons;
+use gpui::{
+    uniform_list, AnyElement, App, Context, Entity, EventEmitter, FocusHandle, Focusable,
+    InteractiveElement, IntoElement, ParentElement, Render, ScrollStrategy, Styled, Subscription,
+    Task, UniformListScrollHandle, Window,
+};
+use project::{
+    Project, ProjectEntryId, ProjectPath, UpdatedEntriesSet, Worktree, WorktreeSettings,
+};
+use settings::{Settings, SettingsLocation};
+use ui::{prelude::*, Tooltip};
+use util::{paths::PathExt, ResultExt};
+use workspace::{
+    item::{Item, ProjectItem, TabContentParams},
+    ItemSettings, WorkspaceId,
+};
+
+pub use crate::paged_file::*;
+
+/// How many lines are read from disk at a time.
+const LINES_PER_PAGE: u64 = 128;
+/// How many pages of lines are kept in memory.
+const MAX_PAGES: usize = 32;
+/// Searches stop after this many matches, so that their results take a bounded amount of memory.
+const MAX_SEARCH_MATCHES: usize = 1000;
+
+pub fn init(cx: &mut App) {
+    workspace::register_project_item::<LargeFileView>(cx);
+}
+
+/// A file in a local worktree that is too large to be loaded into a buffer.
+pub struct LargeFile {
+    project_path: ProjectPath,
+    entry_id: ProjectEntryId,
+    abs_path: PathBuf,
+    size: u64,
+}
+
+pub enum LargeFileEvent {
+    /// The file changed on disk.
+    Changed,
+}
+
+impl EventEmitter<LargeFileEvent> for LargeFile {}
+
+impl LargeFile {
+    fn worktree_entries_changed(
+        &mut self,
+        worktree: Entity<Worktree>,
+        changes: &UpdatedEntriesSet,
+        cx: &mut Context<Self>,
+    ) {
+        let changed = changes.iter().any(|(path, entry_id, _)| {
+            *entry_id == self.entry_id || *path == self.project_path.path
+        });
+        if !changed {
+            return;
+        }
+
+        if let Some(entry) = worktree.read(cx).entry_for_path(&self.project_path.path) {
+            self.entry_id = entry.id;
+            self.size = entry.size;
+        }
+        cx.emit(LargeFileEvent::Changed);
+    }
+
+    pub fn abs_path(&self) -> &PathBuf {
+        &self.abs_path
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+impl project::ProjectItem for LargeFile {
+    fn try_open(
+        project: &Entity<Project>,
+        path: &ProjectPath,
+        cx: &mut App,
+    ) -> Option<Task<gpui::Result<Entity<Self>>>> {
+        let worktree_handle = project.read(cx).worktree_for_id(path.worktree_id, cx)?;
+        let worktree = worktree_handle.read(cx);
+        // Remote files are read through their buffers, so there's no file to page in from, and
+        // they're left to the editor.
+        if !worktree.is_local() {
+            return None;
+        }
+
+        let entry = worktree.entry_for_path(&path.path)?;
+        let settings = WorktreeSettings::get(
+            Some(SettingsLocation {
+                worktree_id: path.worktree_id,
+                path: &path.path,
+            }),
+            cx,
+        );
+        if !entry.is_file() || !settings.is_large_file(entry.size) {
+            return None;
+        }
+
+        let large_file = worktree.absolutize(&path.path).map(|abs_path| LargeFile {
+            project_path: path.clone(),
+            entry_id: entry.id,
+            abs_path,
+            size: entry.size,
+        });
+        Some(Task::ready(large_file.map(|large_file| {
+            cx.new(|cx| {
+                cx.subscribe(&worktree_handle, |this, worktree, event, cx| {
+                    if let worktree::Event::UpdatedEntries(changes) = event {
+                        this.worktree_entries_changed(worktree, changes, cx);
+                    }
+                })
+                .detach();
+                large_file
+            })
+        })))
+    }
+
+    fn entry_id(&self, _: &App) -> Option<ProjectEntryId> {
+        Some(self.entry_id)
+    }
+
+    fn project_path(&self, _: &App) -> Option<ProjectPath> {
+        Some(self.project_path.clone())
+    }
+
+    fn is_dirty(&self) -> bool {
+        false
+    }
+}
+
+pub struct LargeFileView {
+    file: Entity<LargeFile>,
+    project: Entity<Project>,
+    focus_handle: FocusHandle,
+    index: Option<Arc<LineIndex>>,
+    error: Option<SharedString>,
+    /// The lines that have been read, by page, along with the order in which the pages were
+    /// last used.
+    pages: HashMap<u64, Vec<SharedString>>,
+    page_order: VecDeque<u64>,
+    loading_pages: HashSet<u64>,
+    scroll_handle: UniformListScrollHandle,
+    query_editor: Entity<Editor>,
+    case_sensitive: bool,
+    matches: Vec<SearchMatch>,
+    active_match: Option<usize>,
+    /// The query that `matches` are for.
+    searched_query: Option<String>,
+    search_task: Option<Task<()>>,
+    index_task: Task<()>,
+    _file_subscription: Subscription,
+}
+
+impl LargeFileView {
+    pub fn new(
+        file: Entity<LargeFile>,
+        project: Entity<Project>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let mut this = Self::with_index(file, project, None, window, cx);
+        this.reindex(cx);
+        this
+    }
+
+    fn with_index(
+        file: Entity<LargeFile>,
+        project: Entity<Project>,
+        index: Option<Arc<LineIndex>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let query_editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text("Search…", cx);
+            editor
+        });
+        let file_subscription = cx.subscribe(&file, |this, _, event, cx| match event {
+            LargeFileEvent::Changed => this.reindex(cx),
+        });
+
+        Self {
+            file,
+            project,
+            focus_handle: cx.focus_handle(),
+            index,
+            error: None,
+            pages: HashMap::default(),
+            page_order: VecDeque::new(),
+            loading_pages: HashSet::default(),
+            scroll_handle: UniformListScrollHandle::new(),
+            query_editor,
+            case_sensitive: false,
+            matches: Vec::new(),
+            active_match: None,
+            searched_query: None,
+            search_task: None,
+            index_task: Task::ready(()),
+            _file_subscription: file_subscription,
+        }
+    }
+
+    /// Indexes the file's lines, replacing the current index once that's done. Lines that were
+    /// read with the previous index are dropped, and the last search is run again.
+    fn reindex(&mut self, cx: &mut Context<Self>) {
+        let abs_path = self.file.read(cx).abs_path.clone();
+        self.index_task = cx.spawn(|this, mut cx| async move {
+            let index = cx
+                .background_spawn(async move {
+                    let file = std::fs::File::open(&abs_path)?;
+                    LineIndex::build(file)
+                })
+                .await;
+            this.update(&mut cx, |this, cx| {
+                this.pages.clear();
+                this.page_order.clear();
+                match index {
+                    Ok(index) => {
+                        this.index = Some(Arc::new(index));
+                        this.error = None;
+                    }
+                    Err(error) => {
+                        this.index = None;
+                        this.error = Some(error.to_string().into());
+                    }
+                }
+                if let Some(query) = this.searched_query.clone() {
+                    this.search(query, cx);
+                }
+                cx.notify();
+            })
+            .log_err();
+        });
+    }
+
+    fn line(&mut self, row: u64, cx: &mut Context<Self>) -> Option<SharedString> {
+        let page = row / LINES_PER_PAGE;
+        let Some(lines) = self.pages.get(&page) else {
+            self.load_page(page, cx);
+            return None;
+        };
+        let line = lines.get((row % LINES_PER_PAGE) as usize).cloned();
+
+        if self.page_order.back() != Some(&page) {
+            self.page_order.retain(|loaded_page| *loaded_page != page);
+            self.page_order.push_back(page);
+        }
+        line
+    }
+
+    fn load_page(&mut self, page: u64, cx: &mut Context<Self>) {
+        let Some(index) = self.index.clone() else {
+            return;
+        };
+        if !self.loading_pages.insert(page) {
+            return;
+        }
+
+        let abs_path = self.file.read(cx).abs_path.clone();
+        cx.spawn(|this, mut cx| async move {
+            let lines = cx
+                .background_spawn({
+                    let index = index.clone();
+                    async move {
+                        let file = std::fs::File::open(&abs_path)?;
+                        index.read_lines(file, page * LINES_PER_PAGE..(page + 1) * LINES_PER_PAGE)
+                    }
+                })
+                .await;
+            this.update(&mut cx, |this, cx| {
+                this.loading_pages.remove(&page);
+                // The file was indexed again while the lines were read, so they may be stale.
+                if !this
+                    .index
+                    .as_ref()
+                    .is_some_and(|current| Arc::ptr_eq(current, &index))
+                {
+                    cx.notify();
+                    return;
+                }
+                match lines {
+                    Ok(lines) => {
+                        this.pages
+                            .insert(page, lines.into_iter().map(Into::into).collect());
+                        this.page_order.push_back(page);
+                        while this.page_order.len() > MAX_PAGES {
+                            if let Some(evicted) = this.page_order.pop_front() {
+                                this.pages.remove(&evicted);
+                            }
+                        }
+                    }
+                    Err(error) => this.error = Some(error.to_string().into()),
+                }
+                cx.notify();
+            })
+            .log_err();
+        })
+        .detach();
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        let query = self.query_editor.read(cx).text(cx);
+        if self.searched_query.as_ref() == Some(&query) {
+            self.select_next_match(window, cx);
+        } else {
+            self.search(query, cx);
+        }
+    }
+
+    fn toggle_case_sensitive(&mut self, cx: &mut Context<Self>) {
+        self.case_sensitive = !self.case_sensitive;
+        if let Some(query) = self.searched_query.clone() {
+            self.search(query, cx);
+        }
+    }
+
+    fn search(&mut self, query: String, cx: &mut Context<Self>) {
+        self.matches.clear();
+        self.active_match = None;
+        self.searched_query = Some(query.clone());
+        if query.is_empty() {
+            self.search_task = None;
+            cx.notify();
+            return;
+        }
+
+        let abs_path = self.file.read(cx).abs_path.clone();
+        let case_sensitive = self.case_sensitive;
+        self.search_task = Some(cx.spawn(|this, mut cx| async move {
+            let matches = cx
+                .background_spawn(async move {
+                    let file = std::fs::File::open(&abs_path)?;
+                    paged_file::search(file, &query, case_sensitive, MAX_SEARCH_MATCHES)
+                })
+                .await;
+            this.update(&mut cx, |this, cx| {
+                this.search_task = None;
+                match matches {
+                    Ok(matches) => {
+                        this.matches = matches;
+                        if !this.matches.is_empty() {
+                            this.activate_match(0, cx);
+                        }
+                    }
+                    Err(error) => this.error = Some(error.to_string().into()),
+                }
+                cx.notify();
+            })
+            .log_err();
+        }));
+        cx.notify();
+    }
+
+    fn select_next_match(&mut self, _: &mut Window, cx: &mut Context<Self>) {
+        if !self.matches.is_empty() {
+            let ix = self
+                .active_match
+                .map_or(0, |ix| (ix + 1) % self.matches.len());
+            self.activate_match(ix, cx);
+        }
+    }
+
+    fn select_prev_match(&mut self, _: &mut Window, cx: &mut Context<Self>) {
+        if !self.matches.is_empty() {
+            let ix = self
+                .active_match
+                .map_or(0, |ix| ix.checked_sub(1).unwrap_or(self.matches.len() - 1));
+            self.activate_match(ix, cx);
+        }
+    }
+
+    fn activate_match(&mut self, ix: usize, cx: &mut Context<Self>) {
+        self.active_match = Some(ix);
+        self.scroll_handle
+            .scroll_to_item(self.matches[ix].line as usize, ScrollStrategy::Center);
+        cx.notify();
+    }
+
+    fn render_rows(&mut self, range: Range<usize>, cx: &mut Context<Self>) -> Vec<AnyElement> {
+        let active_line = self
+            .active_match
+            .and_then(|ix| self.matches.get(ix))
+            .map(|search_match| search_match.line);
+        let line_number_width = self
+            .index
+            .as_ref()
+            .map_or(1, |index| index.line_count().to_string().len());
+
+        range
+            .map(|row| {
+                let row = row as u64;
+                let text = self.line(row, cx).unwrap_or_default();
+                let is_match = self
+                    .matches
+                    .binary_search_by_key(&row, |search_match| search_match.line)
+                    .is_ok();
+
+                h_flex()
+                    .id(row as usize)
+                    .w_full()
+                    .gap_3()
+                    .px_2()
+                    .when(is_match, |this| {
+                        this.bg(cx.theme().colors().search_match_background)
+                    })
+                    .when(active_line == Some(row), |this| {
+                        this.bg(cx.theme().colors().editor_active_line_background)
+                    })
+                    .child(
+                        div()
+                            .flex_none()
+                            .text_color(cx.theme().colors().editor_line_number)
+                            .child(format!("{:>line_number_width$}", row + 1)),
+                    )
+                    .child(div().whitespace_nowrap().child(text))
+                    .into_any_element()
+            })
+            .collect()
+    }
+
+    fn render_search_bar(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let match_count = if self.search_task.is_some() {
+            "Searching…".to_string()
+        } else if self.searched_query.is_some() {
+            let limit_reached = self.matches.len() >= MAX_SEARCH_MATCHES;
+            match self.active_match {
+                Some(ix) if limit_reached => {
+                    format!("{}/{}+", ix + 1, self.matches.len())
+                }
+                Some(ix) => format!("{}/{}", ix + 1, self.matches.len()),
+                None => "No results".to_string(),
+            }
+        } else {
+            String::new()
+        };
+
+        h_flex()
+            .gap_2()
+            .px_2()
+            .py_1()
+            .border_b_1()
+            .border_color(cx.theme().colors().border)
+            .child(
+                h_flex()
+                    .flex_1()
+                    .px_2()
+                    .py_1()
+                    .rounded_md()
+                    .border_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .child(self.query_editor.clone()),
+            )
+            .child(
+                IconButton::new("case-sensitive", IconName::CaseSensitive)
+                    .toggle_state(self.case_sensitive)
+                    .tooltip(Tooltip::text("Match Case"))
+                    .on_click(cx.listener(|this, _, _, cx| this.toggle_case_sensitive(cx))),
+            )
+            .child(
+                IconButton::new("previous-match", IconName::ChevronLeft)
+                    .disabled(self.matches.is_empty())
+                    .tooltip(Tooltip::text("Select Previous Match"))
+                    .on_click(
+                        cx.listener(|this, _, window, cx| this.select_prev_match(window, cx)),
+                    ),
+            )
+            .child(
+                IconButton::new("next-match", IconName::ChevronRight)
+                    .disabled(self.matches.is_empty())
+                    .tooltip(Tooltip::text("Select Next Match"))
+                    .on_click(
+                        cx.listener(|this, _, window, cx| this.select_next_match(window, cx)),
+                    ),
+            )
+            .child(Label::new(match_count).color(Color::Muted))
+    }
+}
+
+impl EventEmitter<()> for LargeFileView {}
+
+impl Item for LargeFileView {
+    type Event = ();
+
+    fn for_each_project_item(
+        &self,
+        cx: &App,
+        f: &mut dyn FnMut(gpui::EntityId, &dyn project::ProjectItem),
+    ) {
+        f(self.file.entity_id(), self.file.read(cx))
+    }
+
+    fn is_singleton(&self, _cx: &App) -> bool {
+        true
+    }
+
+    fn tab_tooltip_text(&self, cx: &App) -> Option<SharedString> {
+        let abs_path = self.file.read(cx).abs_path.compact();
+        Some(abs_path.to_string_lossy().to_string().into())
+    }
+
+    fn tab_content(&self, params: TabContentParams, _: &Window, cx: &App) -> AnyElement {
+        let project_path = self.file.read(cx).project_path.clone();
+
+        let label_color = if ItemSettings::get_global(cx).git_status {
+            let git_status = self
+                .project
+                .read(cx)
+                .project_path_git_status(&project_path, cx)
+                .map(|status| status.summary())
+                .unwrap_or_default();
+
+            self.project
+                .read(cx)
+                .entry_for_path(&project_path, cx)
+                .map(|entry| {
+                    entry_git_aware_label_color(git_status, entry.is_ignored, params.selected)
+                })
+                .unwrap_or_else(|| params.text_color())
+        } else {
+            params.text_color()
+        };
+
+        let title = project_path
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Label::new(title)
+            .single_line()
+            .color(label_color)
+            .when(params.preview, |this| this.italic())
+            .into_any_element()
+    }
+
+    fn tab_icon(&self, _: &Window, cx: &App) -> Option<Icon> {
+        let path = self.file.read(cx).project_path.path.clone();
+        ItemSettings::get_global(cx)
+            .file_icons
+            .then(|| FileIcons::get_icon(&path, cx))
+            .flatten()
+            .map(Icon::from_path)
+    }
+
+    fn clone_on_split(
+        &self,
+        _workspace_id: Option<WorkspaceId>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Option<Entity<Self>>
+    where
+        Self: Sized,
+    {
+        let Some(index) = self.index.clone() else {
+            return Some(
+                cx.new(|cx| Self::new(self.file.clone(), self.project.clone(), window, cx)),
+            );
+        };
+        Some(cx.new(|cx| {
+            Self::with_index(
+                self.file.clone(),
+                self.project.clone(),
+                Some(index),
+                window,
+                cx,
+            )
+        }))
+    }
+}
+
+impl Focusable for LargeFileView {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for LargeFileView {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let size = self.file.read(cx).size;
+        let banner = h_flex()
+            .gap_2()
+            .px_2()
+            .py_1()
+            .border_b_1()
+            .border_color(cx.theme().colors().border)
+            .child(Icon::new(IconName::Info).color(Color::Muted))
+            .child(
+                Label::new(format!(
+                    "This file is {}, so it's shown read-only, without syntax highlighting or language servers.",
+                    format_file_size(size)
+                ))
+                .color(Color::Muted),
+            );
+
+        let content = if let Some(error) = self.error.clone() {
+            div()
+                .p_2()
+                .child(Label::new(error).color(Color::Error))
+                .into_any_element()
+        } else if let Some(index) = self.index.clone() {
+            uniform_list(
+                cx.entity(),
+                "large-file-lines",
+                index.line_count() as usize,
+                |this, range, _, cx| this.render_rows(range, cx),
+            )
+            .size_full()
+            .track_scroll(self.scroll_handle.clone())
+            .into_any_element()
+        } else {
+            div()
+                .p_2()
+                .child(Label::new("Indexing lines…").color(Color::Muted))
+                .into_any_element()
+        };
+
+        v_flex()
+            .key_context("LargeFileView")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::confirm))
+            .size_full()
+            .bg(cx.theme().colors().editor_background)
+            .child(banner)
+            .child(self.render_search_bar(cx))
+            .child(div().flex_1().font_buffer(cx).child(content))
+    }
+}
+
+impl ProjectItem for LargeFileView {
+    type Item = LargeFile;
+
+    fn for_project_item(
+        project: Entity<Project>,
+        item: Entity<Self::Item>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self
+    where
+        Self: Sized,
+    {
+        Self::new(item, project, window, cx)
+    }
+}
+
+fn format_file_size(size: u64) -> String {
+    const GIB: f64 = (1u64 << 30) as f64;
+    const MIB: f64 = (1u64 << 20) as f64;
+    let size = size as f64;
+    if size >= GIB {
+        format!("{:.1} GiB", size / GIB)
+    } else {
+        format!("{:.1} MiB", size / MIB)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::{TestAppContext, VisualTestContext};
+    use project::RealFs;
+    use serde_json::json;
+    use settings::SettingsStore;
+    use util::test::TempTree;
+    use workspace::{AppState, Workspace};
+    use worktree::WorktreeModelHandle as _;
+
+    #[gpui::test]
+    async fn test_open_large_files(cx: &mut TestAppContext) {
+        init_test(cx);
+        cx.executor().allow_parking();
+
+        let dir = TempTree::new(json!({
+            "small.txt": "small\n",
+            "large.txt": "one\ntwo\nthree\nfour\nfive\n",
+        }));
+        let project = Project::test(Arc::new(RealFs::default()), [dir.path()], cx).await;
+        let (workspace, cx) =
+            cx.add_window_view(|window, cx| Workspace::test_new(project.clone(), window, cx));
+        let worktree_id = project.update(cx, |project, cx| {
+            project.worktrees(cx).next().unwrap().read(cx).id()
+        });
+
+        let item = open_path(&workspace, (worktree_id, "small.txt"), cx).await;
+        assert!(item.downcast::<Editor>().is_some());
+        assert!(item.downcast::<LargeFileView>().is_none());
+
+        let item = open_path(&workspace, (worktree_id, "large.txt"), cx).await;
+        let view = item.downcast::<LargeFileView>().unwrap();
+        cx.run_until_parked();
+        view.update(cx, |view, cx| {
+            assert_eq!(view.file.read(cx).size(), 24);
+            assert_eq!(view.index.as_ref().unwrap().line_count(), 6);
+            assert_eq!(view.error, None);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_reindex_when_file_changes(cx: &mut TestAppContext) {
+        init_test(cx);
+        cx.executor().allow_parking();
+
+        let dir = TempTree::new(json!({
+            "large.txt": "one\ntwo\nthree\nfour\nfive\n",
+        }));
+        let project = Project::test(Arc::new(RealFs::default()), [dir.path()], cx).await;
+        let (workspace, cx) =
+            cx.add_window_view(|window, cx| Workspace::test_new(project.clone(), window, cx));
+        let worktree = project.update(cx, |project, cx| project.worktrees(cx).next().unwrap());
+        let worktree_id = worktree.update(cx, |worktree, _| worktree.id());
+
+        let view = open_path(&workspace, (worktree_id, "large.txt"), cx)
+            .await
+            .downcast::<LargeFileView>()
+            .unwrap();
+        cx.run_until_parked();
+        view.update_in(cx, |view, window, cx| {
+            view.query_editor.update(cx, |editor, cx| {
+                editor.set_text("five", window, cx);
+            });
+            view.confirm(&menu::Confirm, window, cx);
+        });
+        cx.run_until_parked();
+        view.update(cx, |view, _| {
+            assert_eq!(view.matches, vec![SearchMatch { line: 4, column: 0 }]);
+        });
+
+        worktree.flush_fs_events(cx).await;
+        std::fs::write(
+            dir.path().join("large.txt"),
+            "zero\none\ntwo\nthree\nfour\nfive\nsix\n",
+        )
+        .unwrap();
+        worktree.flush_fs_events(cx).await;
+        cx.run_until_parked();
+
+        // The lines are indexed again, and the last search is repeated against the new contents.
+        view.update(cx, |view, cx| {
+            assert_eq!(view.file.read(cx).size(), 33);
+            assert_eq!(view.index.as_ref().unwrap().line_count(), 8);
+            assert_eq!(view.matches, vec![SearchMatch { line: 5, column: 0 }]);
+            assert_eq!(view.error, None);
+        });
+    }
+
+    async fn open_path(
+        workspace: &Entity<Workspace>,
+        path: (project::WorktreeId, &str),
+        cx: &mut VisualTestContext,
+    ) -> Box<dyn workspace::item::ItemHandle> {
+        workspace
+            .update_in(cx, |workspace, window, cx| {
+                workspace.open_path(path, None, true, window, cx)
+            })
+            .await
+            .unwrap()
+    }
+
+    fn init_test(cx: &mut TestAppContext) -> Arc<AppState> {
+        cx.update(|cx| {
+            let state = AppState::test(cx);
+            language::init(cx);
+            editor::init(cx);
+            workspace::init_settings(cx);
+            Project::init_settings(cx);
+            crate::init(cx);
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.update_user_settings::<WorktreeSettings>(cx, |settings| {
+                    settings.large_file_threshold = Some(16);
+                });
+            });
+            state
+        })
+    }
+}
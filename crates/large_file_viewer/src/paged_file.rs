@@ -0,0 +1,327 @@
+//! Reads lines from, and searches in, files without loading them into memory.
+//!
+//! A [`LineIndex`] records where every [`LINES_PER_CHECKPOINT`]th line starts, so that any line
+//! can be found by seeking to the closest checkpoint before it and scanning forward. The memory
+//! used is proportional to the number of lines divided by that stride, rather than to the size
+//! of the file.
+
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom};
+use std::ops::Range;
+
+/// How many lines apart the line starts recorded in a [`LineIndex`] are.
+const LINES_PER_CHECKPOINT: u64 = 256;
+
+/// Lines longer than this many bytes are truncated when read, so that files without line
+/// breaks can't end up in memory all at once.
+pub const MAX_LINE_LEN: usize = 4096;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// A sparse index of the lines in a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineIndex {
+    /// The offset of every [`LINES_PER_CHECKPOINT`]th line, starting with the first.
+    checkpoints: Vec<u64>,
+    line_count: u64,
+    len: u64,
+}
+
+impl LineIndex {
+    /// Indexes the lines of a file by reading through it once.
+    pub fn build(mut reader: impl Read) -> io::Result<Self> {
+        let mut chunk = vec![0; CHUNK_SIZE];
+        let mut checkpoints = vec![0];
+        let mut line_count = 1;
+        let mut len = 0;
+        loop {
+            let read = match reader.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error),
+            };
+            for (ix, _) in chunk[..read]
+                .iter()
+                .enumerate()
+                .filter(|(_, byte)| **byte == b'\n')
+            {
+                if line_count % LINES_PER_CHECKPOINT == 0 {
+                    checkpoints.push(len + ix as u64 + 1);
+                }
+                line_count += 1;
+            }
+            len += read as u64;
+        }
+
+        Ok(Self {
+            checkpoints,
+            line_count,
+            len,
+        })
+    }
+
+    /// The number of lines in the file, counting the one after a trailing newline.
+    pub fn line_count(&self) -> u64 {
+        self.line_count
+    }
+
+    /// The size of the file in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Reads the given lines, without their line endings. Lines longer than [`MAX_LINE_LEN`]
+    /// are truncated, and invalid UTF-8 is replaced.
+    pub fn read_lines(
+        &self,
+        mut reader: impl Read + Seek,
+        lines: Range<u64>,
+    ) -> io::Result<Vec<String>> {
+        let lines = lines.start.min(self.line_count)..lines.end.min(self.line_count);
+        if lines.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let checkpoint = (lines.start / LINES_PER_CHECKPOINT) as usize;
+        reader.seek(SeekFrom::Start(self.checkpoints[checkpoint]))?;
+        let mut reader = BufReader::with_capacity(CHUNK_SIZE, reader);
+
+        let mut result = Vec::with_capacity((lines.end - lines.start) as usize);
+        let mut line = Vec::new();
+        let mut row = checkpoint as u64 * LINES_PER_CHECKPOINT;
+        while row < lines.end {
+            let keep = row >= lines.start;
+            let at_end = read_line(&mut reader, keep.then_some(&mut line))?;
+            if keep {
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+                result.push(String::from_utf8_lossy(&line).into_owned());
+                line.clear();
+            }
+            if at_end {
+                break;
+            }
+            row += 1;
+        }
+        Ok(result)
+    }
+}
+
+/// Reads up to the next newline, appending at most [`MAX_LINE_LEN`] bytes of the line to
+/// `line`. Returns whether the end of the file was reached.
+fn read_line(reader: &mut impl BufRead, mut line: Option<&mut Vec<u8>>) -> io::Result<bool> {
+    loop {
+        let buffer = match reader.fill_buf() {
+            Ok(buffer) => buffer,
+            Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        };
+        if buffer.is_empty() {
+            return Ok(true);
+        }
+
+        let newline = buffer.iter().position(|byte| *byte == b'\n');
+        let content = &buffer[..newline.unwrap_or(buffer.len())];
+        if let Some(line) = line.as_deref_mut() {
+            let remaining = MAX_LINE_LEN.saturating_sub(line.len());
+            line.extend_from_slice(&content[..content.len().min(remaining)]);
+        }
+
+        match newline {
+            Some(newline) => {
+                reader.consume(newline + 1);
+                return Ok(false);
+            }
+            None => {
+                let len = buffer.len();
+                reader.consume(len);
+            }
+        }
+    }
+}
+
+/// An occurrence of a search query in a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchMatch {
+    pub line: u64,
+    /// The byte offset of the match within its line.
+    pub column: u64,
+}
+
+/// Searches a file for a query by reading through it in chunks, stopping after `max_matches`
+/// matches. Case-insensitive searches only fold the case of ASCII letters.
+pub fn search(
+    mut reader: impl Read,
+    query: &str,
+    case_sensitive: bool,
+    max_matches: usize,
+) -> io::Result<Vec<SearchMatch>> {
+    let query = query.as_bytes();
+    let mut matches = Vec::new();
+    if query.is_empty() {
+        return Ok(matches);
+    }
+
+    let is_match = |candidate: &[u8]| {
+        if case_sensitive {
+            candidate == query
+        } else {
+            candidate.eq_ignore_ascii_case(query)
+        }
+    };
+
+    // The window holds the chunk being searched, after the end of the previous chunk that
+    // could be the start of a match spanning both.
+    let mut window = Vec::with_capacity(CHUNK_SIZE + query.len());
+    let mut chunk = vec![0; CHUNK_SIZE];
+    let mut window_offset = 0;
+    let mut line = 0;
+    let mut line_start = 0;
+    // How far into the window newlines have been counted.
+    let mut counted = 0;
+
+    let mut count_lines = |window: &[u8], window_offset: u64, range: Range<usize>| {
+        for (ix, _) in window[range.clone()]
+            .iter()
+            .enumerate()
+            .filter(|(_, byte)| **byte == b'\n')
+        {
+            line += 1;
+            line_start = window_offset + (range.start + ix) as u64 + 1;
+        }
+        (line, line_start)
+    };
+
+    loop {
+        let read = match reader.read(&mut chunk) {
+            Ok(read) => read,
+            Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        };
+        window.extend_from_slice(&chunk[..read]);
+
+        if window.len() >= query.len() {
+            for start in 0..=window.len() - query.len() {
+                if !is_match(&window[start..start + query.len()]) {
+                    continue;
+                }
+                let (line, line_start) = count_lines(&window, window_offset, counted..start);
+                counted = start;
+                matches.push(SearchMatch {
+                    line,
+                    column: window_offset + start as u64 - line_start,
+                });
+                if matches.len() >= max_matches {
+                    return Ok(matches);
+                }
+            }
+        }
+
+        if read == 0 {
+            return Ok(matches);
+        }
+
+        // Keep the bytes that could start a match continuing into the next chunk.
+        let searched = window.len().saturating_sub(query.len() - 1);
+        count_lines(&window, window_offset, counted..searched);
+        window.drain(..searched);
+        window_offset += searched as u64;
+        counted = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn numbered_lines(count: usize) -> String {
+        (0..count).map(|ix| format!("line {ix}\n")).collect()
+    }
+
+    #[test]
+    fn test_read_lines() {
+        let text = numbered_lines(1000);
+        let index = LineIndex::build(Cursor::new(&text)).unwrap();
+        assert_eq!(index.line_count(), 1001);
+        assert_eq!(index.len(), text.len() as u64);
+
+        assert_eq!(
+            index.read_lines(Cursor::new(&text), 0..2).unwrap(),
+            vec!["line 0", "line 1"]
+        );
+        assert_eq!(
+            index.read_lines(Cursor::new(&text), 255..258).unwrap(),
+            vec!["line 255", "line 256", "line 257"]
+        );
+        assert_eq!(
+            index.read_lines(Cursor::new(&text), 998..2000).unwrap(),
+            vec!["line 998", "line 999", ""]
+        );
+        assert!(index
+            .read_lines(Cursor::new(&text), 5000..5010)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_read_long_lines() {
+        let text = format!("{}\r\nshort\r\n", "x".repeat(MAX_LINE_LEN * 3));
+        let index = LineIndex::build(Cursor::new(&text)).unwrap();
+        let lines = index.read_lines(Cursor::new(&text), 0..3).unwrap();
+        assert_eq!(lines[0].len(), MAX_LINE_LEN);
+        assert_eq!(lines[1], "short");
+        assert_eq!(lines[2], "");
+    }
+
+    #[test]
+    fn test_search() {
+        let text = "Error: one\nok\nerror: two ERROR\n";
+        assert_eq!(
+            search(Cursor::new(text), "error", false, 10).unwrap(),
+            vec![
+                SearchMatch { line: 0, column: 0 },
+                SearchMatch { line: 2, column: 0 },
+                SearchMatch {
+                    line: 2,
+                    column: 11
+                },
+            ]
+        );
+        assert_eq!(
+            search(Cursor::new(text), "error", true, 10).unwrap(),
+            vec![SearchMatch { line: 2, column: 0 }]
+        );
+        assert_eq!(
+            search(Cursor::new(text), "error", false, 1).unwrap().len(),
+            1
+        );
+        assert!(search(Cursor::new(text), "", false, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_search_across_chunks() {
+        // Put a match across the boundary between the first two chunks.
+        let mut text = "a".repeat(CHUNK_SIZE - 3);
+        text.push_str("\nneedle\n");
+        text.push_str(&numbered_lines(10));
+        text.push_str("needle");
+
+        let matches = search(Cursor::new(&text), "needle", true, 10).unwrap();
+        assert_eq!(
+            matches,
+            vec![
+                SearchMatch { line: 1, column: 0 },
+                SearchMatch {
+                    line: 12,
+                    column: 0
+                },
+            ]
+        );
+    }
+}
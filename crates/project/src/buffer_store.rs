@@ -1,5 +1,7 @@
 use crate::{
     lsp_store::OpenLspBufferHandle,
+    persistent_undo,
+    project_settings::ProjectSettings,
     search::SearchQuery,
     worktree_store::{WorktreeStore, WorktreeStoreEvent},
     ProjectItem as _, ProjectPath,
@@ -7,7 +9,6 @@ use crate::{
 use ::git::{parse_git_remote_url, BuildPermalinkParams, GitHostingProviderRegistry};
 use anyhow::{anyhow, bail, Context as _, Result};
 use buffer_diff::{BufferDiff, BufferDiffEvent};
-use client::Client;
 use collections::{hash_map, HashMap, HashSet};
 use fs::Fs;
 use futures::{channel::oneshot, future::Shared, Future, FutureExt as _, StreamExt};
@@ -28,6 +29,7 @@ use rpc::{
     AnyProtoClient, ErrorExt as _, TypedEnvelope,
 };
 use serde::Deserialize;
+use settings::Settings as _;
 use smol::channel::Receiver;
 use std::{
     io,
@@ -35,6 +37,7 @@ use std::{
     path::{Path, PathBuf},
     pin::pin,
     str::FromStr as _,
+    string::FromUtf8Error,
     sync::Arc,
     time::Instant,
 };
@@ -53,6 +56,9 @@ pub struct BufferStore {
     state: BufferStoreState,
     #[allow(clippy::type_complexity)]
     loading_buffers: HashMap<ProjectPath, Shared<Task<Result<Entity<Buffer>, Arc<anyhow::Error>>>>>,
+    /// Paths that failed to open as buffers because their contents look binary, i.e. they
+    /// contain NUL bytes and aren't UTF-16 or UTF-8.
+    binary_files: HashSet<ProjectPath>,
     #[allow(clippy::type_complexity)]
     loading_diffs:
         HashMap<(BufferId, DiffKind), Shared<Task<Result<Entity<BufferDiff>, Arc<anyhow::Error>>>>>,
@@ -696,6 +702,7 @@ impl LocalBufferStore {
 
         let text = buffer.as_rope().clone();
         let line_ending = buffer.line_ending();
+        let encoding = buffer.encoding();
         let version = buffer.version();
         let buffer_id = buffer.remote_id();
         if buffer
@@ -706,7 +713,7 @@ impl LocalBufferStore {
         }
 
         let save = worktree.update(cx, |worktree, cx| {
-            worktree.write_file(path.as_ref(), text, line_ending, cx)
+            worktree.write_file(path.as_ref(), text, line_ending, encoding, cx)
         });
 
         cx.spawn(move |this, mut cx| async move {
@@ -738,6 +745,10 @@ impl LocalBufferStore {
                     buffer.file_updated(new_file, cx);
                 }
                 buffer.did_save(version.clone(), mtime, cx);
+                let settings = ProjectSettings::get_global(cx).persistent_undo;
+                if settings.enabled {
+                    persistent_undo::save_history(buffer, settings, cx).detach_and_log_err(cx);
+                }
             })
         })
     }
@@ -1119,17 +1130,32 @@ impl LocalBufferStore {
         worktree: Entity<Worktree>,
         cx: &mut Context<BufferStore>,
     ) -> Task<Result<Entity<Buffer>>> {
+        let restore_history = ProjectSettings::get_global(cx).persistent_undo.enabled;
         let load_buffer = worktree.update(cx, |worktree, cx| {
             let load_file = worktree.load_file(path.as_ref(), cx);
+            let history_path = worktree.absolutize(&path).ok().filter(|_| restore_history);
             let reservation = cx.reserve_entity();
             let buffer_id = BufferId::from(reservation.entity_id().as_non_zero_u64());
             cx.spawn(move |_, mut cx| async move {
                 let loaded = load_file.await?;
+                let encoding = loaded.encoding;
                 let text_buffer = cx
-                    .background_spawn(async move { text::Buffer::new(0, buffer_id, loaded.text) })
+                    .background_spawn(async move {
+                        let text_buffer = text::Buffer::new(0, buffer_id, loaded.text);
+                        match history_path {
+                            Some(history_path) => {
+                                persistent_undo::restore_history(&history_path, &text_buffer)
+                                    .log_err()
+                                    .flatten()
+                                    .unwrap_or(text_buffer)
+                            }
+                            None => text_buffer,
+                        }
+                    })
                     .await;
                 cx.insert_entity(reservation, |_| {
                     Buffer::build(text_buffer, Some(loaded.file), Capability::ReadWrite)
+                        .with_encoding(encoding)
                 })
             })
         });
@@ -1226,6 +1252,7 @@ impl BufferStore {
         client.add_entity_message_handler(Self::handle_update_buffer_file);
         client.add_entity_request_handler(Self::handle_save_buffer);
         client.add_entity_request_handler(Self::handle_blame_buffer);
+        client.add_entity_request_handler(Self::handle_get_buffer_disk_text);
         client.add_entity_request_handler(Self::handle_reload_buffers);
         client.add_entity_request_handler(Self::handle_get_permalink_to_line);
         client.add_entity_request_handler(Self::handle_open_unstaged_diff);
@@ -1251,6 +1278,7 @@ impl BufferStore {
             opened_buffers: Default::default(),
             shared_buffers: Default::default(),
             loading_buffers: Default::default(),
+            binary_files: Default::default(),
             loading_diffs: Default::default(),
             worktree_store,
         }
@@ -1274,6 +1302,7 @@ impl BufferStore {
             downstream_client: None,
             opened_buffers: Default::default(),
             loading_buffers: Default::default(),
+            binary_files: Default::default(),
             loading_diffs: Default::default(),
             shared_buffers: Default::default(),
             worktree_store,
@@ -1333,6 +1362,15 @@ impl BufferStore {
                             this.update(&mut cx, |this, _cx| {
                                 // Record the fact that the buffer is no longer loading.
                                 this.loading_buffers.remove(&project_path);
+                                match &load_result {
+                                    Err(error) if is_invalid_utf8_error(error) => {
+                                        this.binary_files.insert(project_path.clone());
+                                    }
+                                    Err(_) => {}
+                                    Ok(_) => {
+                                        this.binary_files.remove(&project_path);
+                                    }
+                                }
                             })
                             .ok();
                             load_result.map_err(Arc::new)
@@ -1546,6 +1584,29 @@ impl BufferStore {
         })
     }
 
+    /// Loads the contents of the buffer's file as they currently are on disk,
+    /// regardless of the buffer's unsaved changes.
+    pub fn load_disk_text(&self, buffer: &Entity<Buffer>, cx: &mut App) -> Task<Result<String>> {
+        let buffer = buffer.read(cx);
+        let Some(file) = File::from_dyn(buffer.file()) else {
+            return Task::ready(Err(anyhow!("buffer has no file")));
+        };
+        let buffer_id = buffer.remote_id();
+        let path = file.path.clone();
+        let worktree = file.worktree.clone();
+
+        if let Some(remote_worktree) = worktree.read(cx).as_remote() {
+            let request = remote_worktree.client().request(proto::GetBufferDiskText {
+                project_id: remote_worktree.project_id(),
+                buffer_id: buffer_id.into(),
+            });
+            return cx.background_spawn(async move { Ok(request.await?.text) });
+        }
+
+        let load_file = worktree.update(cx, |worktree, cx| worktree.load_file(&path, cx));
+        cx.background_spawn(async move { Ok(load_file.await?.text) })
+    }
+
     pub fn blame_buffer(
         &self,
         buffer: &Entity<Buffer>,
@@ -1721,8 +1782,15 @@ impl BufferStore {
         };
 
         let handle = cx.entity().downgrade();
+        let is_local = self.as_local().is_some();
         buffer_entity.update(cx, move |_, cx| {
             cx.on_release(move |buffer, cx| {
+                if is_local {
+                    let settings = ProjectSettings::get_global(cx).persistent_undo;
+                    if settings.enabled {
+                        persistent_undo::save_history(buffer, settings, cx).detach_and_log_err(cx);
+                    }
+                }
                 handle
                     .update(cx, |_, cx| {
                         cx.emit(BufferStoreEvent::BufferDropped(buffer.remote_id()))
@@ -1771,6 +1839,12 @@ impl BufferStore {
         })
     }
 
+    /// Whether the file at the given path has failed to open as a buffer because its
+    /// contents look binary.
+    pub fn is_binary_file(&self, path: &ProjectPath) -> bool {
+        self.binary_files.contains(path)
+    }
+
     pub fn get_by_path(&self, path: &ProjectPath, cx: &App) -> Option<Entity<Buffer>> {
         self.buffers().find_map(|buffer| {
             let file = File::from_dyn(buffer.read(cx).file())?;
@@ -1856,6 +1930,21 @@ impl BufferStore {
         }
     }
 
+    /// Unlike [`Self::disconnected_from_host`], keeps buffers editable, so
+    /// that they can be synchronized with the server if the connection to it
+    /// is resumed.
+    pub fn disconnected_from_ssh_remote(&mut self, cx: &mut App) {
+        for open_buffer in self.opened_buffers.values_mut() {
+            if let Some(buffer) = open_buffer.upgrade() {
+                buffer.update(cx, |buffer, _| buffer.give_up_waiting());
+            }
+        }
+
+        if let Some(remote) = self.as_remote_mut() {
+            remote.remote_buffer_listeners.clear()
+        }
+    }
+
     pub fn shared(&mut self, remote_id: u64, downstream_client: AnyProtoClient, _cx: &mut App) {
         self.downstream_client = Some((downstream_client, remote_id));
     }
@@ -2036,14 +2125,20 @@ impl BufferStore {
         &mut self,
         envelope: TypedEnvelope<proto::SynchronizeBuffers>,
         cx: &mut Context<Self>,
-        client: Arc<Client>,
+        client: AnyProtoClient,
     ) -> Result<proto::SynchronizeBuffersResponse> {
         let project_id = envelope.payload.project_id;
         let mut response = proto::SynchronizeBuffersResponse {
             buffers: Default::default(),
         };
-        let Some(guest_id) = envelope.original_sender_id else {
-            anyhow::bail!("missing original_sender_id on SynchronizeBuffers request");
+        // Requests from the ssh client aren't forwarded, so they have no
+        // original sender.
+        let guest_id = if client.is_via_collab() {
+            envelope
+                .original_sender_id
+                .context("missing original_sender_id on SynchronizeBuffers request")?
+        } else {
+            proto::SSH_PEER_ID
         };
 
         self.shared_buffers.entry(guest_id).or_default().clear();
@@ -2336,6 +2431,19 @@ impl BufferStore {
         Ok(serialize_blame_buffer_response(blame))
     }
 
+    pub async fn handle_get_buffer_disk_text(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::GetBufferDiskText>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::GetBufferDiskTextResponse> {
+        let buffer_id = BufferId::new(envelope.payload.buffer_id)?;
+        let buffer = this.read_with(&cx, |this, _| this.get_existing(buffer_id))??;
+        let text = this
+            .update(&mut cx, |this, cx| this.load_disk_text(&buffer, cx))?
+            .await?;
+        Ok(proto::GetBufferDiskTextResponse { text })
+    }
+
     pub async fn handle_get_permalink_to_line(
         this: Entity<Self>,
         envelope: TypedEnvelope<proto::GetPermalinkToLine>,
@@ -2694,6 +2802,14 @@ fn is_not_found_error(error: &anyhow::Error) -> bool {
         .is_some_and(|err| err.kind() == io::ErrorKind::NotFound)
 }
 
+fn is_invalid_utf8_error(error: &anyhow::Error) -> bool {
+    let root_cause = error.root_cause();
+    root_cause
+        .downcast_ref::<io::Error>()
+        .is_some_and(|err| err.kind() == io::ErrorKind::InvalidData)
+        || root_cause.is::<FromUtf8Error>()
+}
+
 fn serialize_blame_buffer_response(blame: Option<git::blame::Blame>) -> proto::BlameBufferResponse {
     let Some(blame) = blame else {
         return proto::BlameBufferResponse {
@@ -2,9 +2,11 @@ mod signature_help;
 
 use crate::{
     lsp_store::{LocalLspStore, LspStore},
-    CodeAction, CoreCompletion, DocumentHighlight, Hover, HoverBlock, HoverBlockKind, InlayHint,
-    InlayHintLabel, InlayHintLabelPart, InlayHintLabelPartTooltip, InlayHintTooltip, Location,
-    LocationLink, MarkupContent, PrepareRenameResponse, ProjectTransaction, ResolveState,
+    BufferRenameEdits, ChangeAnnotation, CodeAction, ColorPresentation, CoreCompletion,
+    DocumentColor, DocumentHighlight, DocumentLink, Hover, HoverBlock, HoverBlockKind, InlayHint,
+    InlayHintLabel, InlayHintLabelPart, InlayHintLabelPartTooltip, InlayHintTooltip, InlineValue,
+    InlineValueKind, Location, LocationLink, MarkupContent, PrepareRenameResponse,
+    ProjectTransaction, RenameEdit, ResolveState,
 };
 use anyhow::{anyhow, Context as _, Result};
 use async_trait::async_trait;
@@ -168,6 +170,12 @@ pub(crate) struct PerformRename {
     pub push_to_history: bool,
 }
 
+#[derive(Debug)]
+pub(crate) struct GetRenameEdits {
+    pub position: PointUtf16,
+    pub new_name: String,
+}
+
 #[derive(Debug)]
 pub struct GetDefinition {
     pub position: PointUtf16,
@@ -238,6 +246,25 @@ pub(crate) struct LinkedEditingRange {
     pub position: Anchor,
 }
 
+#[derive(Debug)]
+pub(crate) struct GetDocumentLinks;
+
+#[derive(Debug)]
+pub(crate) struct GetDocumentColors;
+
+#[derive(Debug)]
+pub(crate) struct GetColorPresentations {
+    pub range: Range<Anchor>,
+    pub color: lsp::Color,
+    /// The language server that reported the color, which has to present it.
+    pub server_id: LanguageServerId,
+}
+
+#[derive(Debug)]
+pub(crate) struct GetInlineValues {
+    pub range: Range<Anchor>,
+}
+
 #[async_trait(?Send)]
 impl LspCommand for PrepareRename {
     type Response = PrepareRenameResponse;
@@ -542,6 +569,182 @@ impl LspCommand for PerformRename {
     }
 }
 
+#[async_trait(?Send)]
+impl LspCommand for GetRenameEdits {
+    type Response = Vec<BufferRenameEdits>;
+    type LspRequest = lsp::request::Rename;
+    type ProtoRequest = proto::GetRenameEdits;
+
+    fn display_name(&self) -> &str {
+        "Rename preview"
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &App,
+    ) -> Result<lsp::RenameParams> {
+        Ok(lsp::RenameParams {
+            text_document_position: make_lsp_text_document_position(path, self.position)?,
+            new_name: self.new_name.clone(),
+            work_done_progress_params: Default::default(),
+        })
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<lsp::WorkspaceEdit>,
+        lsp_store: Entity<LspStore>,
+        buffer: Entity<Buffer>,
+        server_id: LanguageServerId,
+        mut cx: AsyncApp,
+    ) -> Result<Vec<BufferRenameEdits>> {
+        if let Some(edit) = message {
+            let (lsp_adapter, lsp_server) =
+                language_server_for_buffer(&lsp_store, &buffer, server_id, &mut cx)?;
+            LocalLspStore::rename_edits_from_workspace_edit(
+                lsp_store,
+                edit,
+                lsp_adapter,
+                lsp_server,
+                &mut cx,
+            )
+            .await
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetRenameEdits {
+        proto::GetRenameEdits {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            position: Some(language::proto::serialize_anchor(
+                &buffer.anchor_before(self.position),
+            )),
+            new_name: self.new_name.clone(),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetRenameEdits,
+        _: Entity<LspStore>,
+        buffer: Entity<Buffer>,
+        mut cx: AsyncApp,
+    ) -> Result<Self> {
+        let position = message
+            .position
+            .and_then(deserialize_anchor)
+            .ok_or_else(|| anyhow!("invalid position"))?;
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self {
+            position: buffer.update(&mut cx, |buffer, _| position.to_point_utf16(buffer))?,
+            new_name: message.new_name,
+        })
+    }
+
+    fn response_to_proto(
+        response: Vec<BufferRenameEdits>,
+        lsp_store: &mut LspStore,
+        peer_id: PeerId,
+        _: &clock::Global,
+        cx: &mut App,
+    ) -> proto::GetRenameEditsResponse {
+        let buffers = response
+            .into_iter()
+            .map(|buffer_edits| {
+                lsp_store
+                    .buffer_store()
+                    .update(cx, |buffer_store, cx| {
+                        buffer_store.create_buffer_for_peer(&buffer_edits.buffer, peer_id, cx)
+                    })
+                    .detach_and_log_err(cx);
+
+                proto::BufferRenameEdits {
+                    buffer_id: buffer_edits.buffer.read(cx).remote_id().into(),
+                    edits: buffer_edits
+                        .edits
+                        .into_iter()
+                        .map(|edit| proto::RenameEdit {
+                            start: Some(serialize_anchor(&edit.range.start)),
+                            end: Some(serialize_anchor(&edit.range.end)),
+                            new_text: edit.new_text,
+                            annotation: edit.annotation.map(|annotation| proto::ChangeAnnotation {
+                                label: annotation.label,
+                                description: annotation.description,
+                                needs_confirmation: annotation.needs_confirmation,
+                            }),
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+        proto::GetRenameEditsResponse { buffers }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetRenameEditsResponse,
+        lsp_store: Entity<LspStore>,
+        _: Entity<Buffer>,
+        mut cx: AsyncApp,
+    ) -> Result<Vec<BufferRenameEdits>> {
+        let mut buffers = Vec::new();
+        for buffer_edits in message.buffers {
+            let buffer_id = BufferId::new(buffer_edits.buffer_id)?;
+            let buffer = lsp_store
+                .update(&mut cx, |lsp_store, cx| {
+                    lsp_store.wait_for_remote_buffer(buffer_id, cx)
+                })?
+                .await?;
+
+            let mut edits = Vec::new();
+            for edit in buffer_edits.edits {
+                let start = edit
+                    .start
+                    .and_then(deserialize_anchor)
+                    .ok_or_else(|| anyhow!("missing edit start"))?;
+                let end = edit
+                    .end
+                    .and_then(deserialize_anchor)
+                    .ok_or_else(|| anyhow!("missing edit end"))?;
+                edits.push(RenameEdit {
+                    range: start..end,
+                    new_text: edit.new_text,
+                    annotation: edit.annotation.map(|annotation| ChangeAnnotation {
+                        label: annotation.label,
+                        description: annotation.description,
+                        needs_confirmation: annotation.needs_confirmation,
+                    }),
+                });
+            }
+            buffer
+                .update(&mut cx, |buffer, _| {
+                    buffer.wait_for_anchors(
+                        edits
+                            .iter()
+                            .flat_map(|edit| [edit.range.start, edit.range.end]),
+                    )
+                })?
+                .await?;
+
+            buffers.push(BufferRenameEdits { buffer, edits });
+        }
+        Ok(buffers)
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetRenameEdits) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
 #[async_trait(?Send)]
 impl LspCommand for GetDefinition {
     type Response = Vec<LocationLink>;
@@ -3175,3 +3378,719 @@ impl LspCommand for LinkedEditingRange {
         BufferId::new(message.buffer_id)
     }
 }
+
+#[async_trait(?Send)]
+impl LspCommand for GetDocumentLinks {
+    type Response = Vec<DocumentLink>;
+    type LspRequest = lsp::request::DocumentLinkRequest;
+    type ProtoRequest = proto::GetDocumentLinks;
+
+    fn display_name(&self) -> &str {
+        "Get document links"
+    }
+
+    fn check_capabilities(&self, capabilities: AdapterServerCapabilities) -> bool {
+        capabilities
+            .server_capabilities
+            .document_link_provider
+            .is_some()
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &App,
+    ) -> Result<lsp::DocumentLinkParams> {
+        Ok(lsp::DocumentLinkParams {
+            text_document: make_text_document_identifier(path)?,
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<Vec<lsp::DocumentLink>>,
+        _: Entity<LspStore>,
+        buffer: Entity<Buffer>,
+        server_id: LanguageServerId,
+        cx: AsyncApp,
+    ) -> Result<Vec<DocumentLink>> {
+        let mut lsp_links = message.unwrap_or_default();
+        lsp_links.sort_by_key(|link| link.range.start);
+        buffer.read_with(&cx, |buffer, _| {
+            lsp_links
+                .into_iter()
+                .map(|lsp_link| Self::lsp_to_project_link(lsp_link, buffer, server_id))
+                .collect()
+        })
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetDocumentLinks {
+        proto::GetDocumentLinks {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetDocumentLinks,
+        _: Entity<LspStore>,
+        buffer: Entity<Buffer>,
+        mut cx: AsyncApp,
+    ) -> Result<Self> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self)
+    }
+
+    fn response_to_proto(
+        response: Vec<DocumentLink>,
+        _: &mut LspStore,
+        _: PeerId,
+        buffer_version: &clock::Global,
+        _: &mut App,
+    ) -> proto::GetDocumentLinksResponse {
+        proto::GetDocumentLinksResponse {
+            links: response
+                .into_iter()
+                .map(Self::project_to_proto_link)
+                .collect(),
+            version: serialize_version(buffer_version),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetDocumentLinksResponse,
+        _: Entity<LspStore>,
+        buffer: Entity<Buffer>,
+        mut cx: AsyncApp,
+    ) -> Result<Vec<DocumentLink>> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        let mut links = Vec::new();
+        for link in message.links {
+            let link = Self::proto_to_project_link(link)?;
+            buffer
+                .update(&mut cx, |buffer, _| {
+                    buffer.wait_for_anchors([link.range.start, link.range.end])
+                })?
+                .await?;
+            links.push(link);
+        }
+        Ok(links)
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetDocumentLinks) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+impl GetDocumentLinks {
+    pub fn can_resolve_links(capabilities: &lsp::ServerCapabilities) -> bool {
+        capabilities
+            .document_link_provider
+            .as_ref()
+            .and_then(|options| options.resolve_provider)
+            .unwrap_or(false)
+    }
+
+    pub fn lsp_to_project_link(
+        lsp_link: lsp::DocumentLink,
+        buffer: &Buffer,
+        server_id: LanguageServerId,
+    ) -> DocumentLink {
+        let start = buffer.clip_point_utf16(point_from_lsp(lsp_link.range.start), Bias::Left);
+        let end = buffer.clip_point_utf16(point_from_lsp(lsp_link.range.end), Bias::Left);
+        DocumentLink {
+            range: buffer.anchor_after(start)..buffer.anchor_before(end),
+            target: lsp_link.target.map(|target| target.to_string()),
+            tooltip: lsp_link.tooltip,
+            server_id,
+            lsp_data: lsp_link.data,
+        }
+    }
+
+    pub fn project_to_lsp_link(
+        link: DocumentLink,
+        snapshot: &BufferSnapshot,
+    ) -> Result<lsp::DocumentLink> {
+        Ok(lsp::DocumentLink {
+            range: range_to_lsp(link.range.to_point_utf16(snapshot))?,
+            target: link.target.and_then(|target| lsp::Url::parse(&target).ok()),
+            tooltip: link.tooltip,
+            data: link.lsp_data,
+        })
+    }
+
+    pub fn project_to_proto_link(link: DocumentLink) -> proto::DocumentLink {
+        proto::DocumentLink {
+            start: Some(serialize_anchor(&link.range.start)),
+            end: Some(serialize_anchor(&link.range.end)),
+            target: link.target,
+            tooltip: link.tooltip,
+            language_server_id: link.server_id.to_proto(),
+            lsp_data: link.lsp_data.map(|data| data.to_string()),
+        }
+    }
+
+    pub fn proto_to_project_link(link: proto::DocumentLink) -> Result<DocumentLink> {
+        let start = link
+            .start
+            .and_then(deserialize_anchor)
+            .ok_or_else(|| anyhow!("missing link start"))?;
+        let end = link
+            .end
+            .and_then(deserialize_anchor)
+            .ok_or_else(|| anyhow!("missing link end"))?;
+        Ok(DocumentLink {
+            range: start..end,
+            target: link.target,
+            tooltip: link.tooltip,
+            server_id: LanguageServerId::from_proto(link.language_server_id),
+            lsp_data: link
+                .lsp_data
+                .map(|data| serde_json::from_str(&data))
+                .transpose()
+                .context("invalid document link data")?,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetDocumentColors {
+    type Response = Vec<DocumentColor>;
+    type LspRequest = lsp::request::DocumentColor;
+    type ProtoRequest = proto::GetDocumentColors;
+
+    fn display_name(&self) -> &str {
+        "Get document colors"
+    }
+
+    fn check_capabilities(&self, capabilities: AdapterServerCapabilities) -> bool {
+        match &capabilities.server_capabilities.color_provider {
+            Some(lsp::ColorProviderCapability::Simple(enabled)) => *enabled,
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &App,
+    ) -> Result<lsp::DocumentColorParams> {
+        Ok(lsp::DocumentColorParams {
+            text_document: make_text_document_identifier(path)?,
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })
+    }
+
+    async fn response_from_lsp(
+        self,
+        mut message: Vec<lsp::ColorInformation>,
+        _: Entity<LspStore>,
+        buffer: Entity<Buffer>,
+        server_id: LanguageServerId,
+        cx: AsyncApp,
+    ) -> Result<Vec<DocumentColor>> {
+        message.sort_by_key(|information| information.range.start);
+        buffer.read_with(&cx, |buffer, _| {
+            message
+                .into_iter()
+                .map(|information| {
+                    let start = buffer
+                        .clip_point_utf16(point_from_lsp(information.range.start), Bias::Left);
+                    let end =
+                        buffer.clip_point_utf16(point_from_lsp(information.range.end), Bias::Left);
+                    DocumentColor {
+                        range: buffer.anchor_before(start)..buffer.anchor_after(end),
+                        color: information.color,
+                        server_id,
+                    }
+                })
+                .collect()
+        })
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetDocumentColors {
+        proto::GetDocumentColors {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetDocumentColors,
+        _: Entity<LspStore>,
+        buffer: Entity<Buffer>,
+        mut cx: AsyncApp,
+    ) -> Result<Self> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self)
+    }
+
+    fn response_to_proto(
+        response: Vec<DocumentColor>,
+        _: &mut LspStore,
+        _: PeerId,
+        buffer_version: &clock::Global,
+        _: &mut App,
+    ) -> proto::GetDocumentColorsResponse {
+        proto::GetDocumentColorsResponse {
+            colors: response
+                .into_iter()
+                .map(|color| proto::DocumentColor {
+                    start: Some(serialize_anchor(&color.range.start)),
+                    end: Some(serialize_anchor(&color.range.end)),
+                    red: color.color.red,
+                    green: color.color.green,
+                    blue: color.color.blue,
+                    alpha: color.color.alpha,
+                    language_server_id: color.server_id.to_proto(),
+                })
+                .collect(),
+            version: serialize_version(buffer_version),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetDocumentColorsResponse,
+        _: Entity<LspStore>,
+        buffer: Entity<Buffer>,
+        mut cx: AsyncApp,
+    ) -> Result<Vec<DocumentColor>> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        let mut colors = Vec::new();
+        for color in message.colors {
+            let start = color
+                .start
+                .and_then(deserialize_anchor)
+                .ok_or_else(|| anyhow!("missing color start"))?;
+            let end = color
+                .end
+                .and_then(deserialize_anchor)
+                .ok_or_else(|| anyhow!("missing color end"))?;
+            buffer
+                .update(&mut cx, |buffer, _| buffer.wait_for_anchors([start, end]))?
+                .await?;
+            colors.push(DocumentColor {
+                range: start..end,
+                color: lsp::Color {
+                    red: color.red,
+                    green: color.green,
+                    blue: color.blue,
+                    alpha: color.alpha,
+                },
+                server_id: LanguageServerId::from_proto(color.language_server_id),
+            });
+        }
+        Ok(colors)
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetDocumentColors) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetColorPresentations {
+    type Response = Vec<ColorPresentation>;
+    type LspRequest = lsp::request::ColorPresentationRequest;
+    type ProtoRequest = proto::GetColorPresentations;
+
+    fn display_name(&self) -> &str {
+        "Get color presentations"
+    }
+
+    fn check_capabilities(&self, capabilities: AdapterServerCapabilities) -> bool {
+        GetDocumentColors.check_capabilities(capabilities)
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        buffer: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &App,
+    ) -> Result<lsp::ColorPresentationParams> {
+        Ok(lsp::ColorPresentationParams {
+            text_document: make_text_document_identifier(path)?,
+            color: self.color,
+            range: range_to_lsp(self.range.to_point_utf16(buffer))?,
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Vec<lsp::ColorPresentation>,
+        _: Entity<LspStore>,
+        buffer: Entity<Buffer>,
+        _: LanguageServerId,
+        cx: AsyncApp,
+    ) -> Result<Vec<ColorPresentation>> {
+        buffer.read_with(&cx, |buffer, _| {
+            let edit_from_lsp = |edit: lsp::TextEdit| {
+                let start = buffer.clip_point_utf16(point_from_lsp(edit.range.start), Bias::Left);
+                let end = buffer.clip_point_utf16(point_from_lsp(edit.range.end), Bias::Left);
+                (
+                    buffer.anchor_before(start)..buffer.anchor_after(end),
+                    edit.new_text,
+                )
+            };
+            message
+                .into_iter()
+                .map(|presentation| ColorPresentation {
+                    label: presentation.label,
+                    text_edit: presentation.text_edit.map(edit_from_lsp),
+                    additional_text_edits: presentation
+                        .additional_text_edits
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(edit_from_lsp)
+                        .collect(),
+                })
+                .collect()
+        })
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetColorPresentations {
+        proto::GetColorPresentations {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            start: Some(serialize_anchor(&self.range.start)),
+            end: Some(serialize_anchor(&self.range.end)),
+            red: self.color.red,
+            green: self.color.green,
+            blue: self.color.blue,
+            alpha: self.color.alpha,
+            version: serialize_version(&buffer.version()),
+            language_server_id: self.server_id.to_proto(),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetColorPresentations,
+        _: Entity<LspStore>,
+        buffer: Entity<Buffer>,
+        mut cx: AsyncApp,
+    ) -> Result<Self> {
+        let start = message
+            .start
+            .and_then(deserialize_anchor)
+            .context("invalid start")?;
+        let end = message
+            .end
+            .and_then(deserialize_anchor)
+            .context("invalid end")?;
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self {
+            range: start..end,
+            color: lsp::Color {
+                red: message.red,
+                green: message.green,
+                blue: message.blue,
+                alpha: message.alpha,
+            },
+            server_id: LanguageServerId::from_proto(message.language_server_id),
+        })
+    }
+
+    fn response_to_proto(
+        response: Vec<ColorPresentation>,
+        _: &mut LspStore,
+        _: PeerId,
+        buffer_version: &clock::Global,
+        _: &mut App,
+    ) -> proto::GetColorPresentationsResponse {
+        let edit_to_proto =
+            |(range, new_text): (Range<Anchor>, String)| proto::ColorPresentationEdit {
+                start: Some(serialize_anchor(&range.start)),
+                end: Some(serialize_anchor(&range.end)),
+                new_text,
+            };
+        proto::GetColorPresentationsResponse {
+            presentations: response
+                .into_iter()
+                .map(|presentation| proto::ColorPresentation {
+                    label: presentation.label,
+                    text_edit: presentation.text_edit.map(edit_to_proto),
+                    additional_text_edits: presentation
+                        .additional_text_edits
+                        .into_iter()
+                        .map(edit_to_proto)
+                        .collect(),
+                })
+                .collect(),
+            version: serialize_version(buffer_version),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetColorPresentationsResponse,
+        _: Entity<LspStore>,
+        buffer: Entity<Buffer>,
+        mut cx: AsyncApp,
+    ) -> Result<Vec<ColorPresentation>> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        let edit_from_proto = |edit: proto::ColorPresentationEdit| {
+            let start = edit.start.and_then(deserialize_anchor)?;
+            let end = edit.end.and_then(deserialize_anchor)?;
+            Some((start..end, edit.new_text))
+        };
+        Ok(message
+            .presentations
+            .into_iter()
+            .map(|presentation| ColorPresentation {
+                label: presentation.label,
+                text_edit: presentation.text_edit.and_then(edit_from_proto),
+                additional_text_edits: presentation
+                    .additional_text_edits
+                    .into_iter()
+                    .filter_map(edit_from_proto)
+                    .collect(),
+            })
+            .collect())
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetColorPresentations) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetInlineValues {
+    type Response = Vec<InlineValue>;
+    type LspRequest = lsp::request::InlineValueRequest;
+    type ProtoRequest = proto::GetInlineValues;
+
+    fn display_name(&self) -> &str {
+        "Get inline values"
+    }
+
+    fn check_capabilities(&self, capabilities: AdapterServerCapabilities) -> bool {
+        match &capabilities.server_capabilities.inline_value_provider {
+            Some(OneOf::Left(enabled)) => *enabled,
+            Some(OneOf::Right(_)) => true,
+            None => false,
+        }
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        buffer: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &App,
+    ) -> Result<lsp::InlineValueParams> {
+        let range = range_to_lsp(self.range.to_point_utf16(buffer))?;
+        Ok(lsp::InlineValueParams {
+            text_document: make_text_document_identifier(path)?,
+            range,
+            context: lsp::InlineValueContext {
+                // There is no debugger stack frame to report, so the request is scoped to the range.
+                frame_id: 0,
+                stopped_location: lsp::Range::new(range.end, range.end),
+            },
+            work_done_progress_params: Default::default(),
+        })
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<Vec<lsp::InlineValue>>,
+        _: Entity<LspStore>,
+        buffer: Entity<Buffer>,
+        _: LanguageServerId,
+        cx: AsyncApp,
+    ) -> Result<Vec<InlineValue>> {
+        buffer.read_with(&cx, |buffer, _| {
+            message
+                .unwrap_or_default()
+                .into_iter()
+                .map(|value| {
+                    let (range, kind) = match value {
+                        lsp::InlineValue::Text(text) => {
+                            (text.range, InlineValueKind::Text(text.text))
+                        }
+                        lsp::InlineValue::VariableLookup(lookup) => (
+                            lookup.range,
+                            InlineValueKind::VariableLookup {
+                                variable_name: lookup.variable_name,
+                                case_sensitive: lookup.case_sensitive_lookup,
+                            },
+                        ),
+                        lsp::InlineValue::EvaluatableExpression(expression) => (
+                            expression.range,
+                            InlineValueKind::EvaluatableExpression {
+                                expression: expression.expression,
+                            },
+                        ),
+                    };
+                    let start = buffer.clip_point_utf16(point_from_lsp(range.start), Bias::Left);
+                    let end = buffer.clip_point_utf16(point_from_lsp(range.end), Bias::Left);
+                    InlineValue {
+                        range: buffer.anchor_before(start)..buffer.anchor_after(end),
+                        kind,
+                    }
+                })
+                .collect()
+        })
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetInlineValues {
+        proto::GetInlineValues {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            start: Some(serialize_anchor(&self.range.start)),
+            end: Some(serialize_anchor(&self.range.end)),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetInlineValues,
+        _: Entity<LspStore>,
+        buffer: Entity<Buffer>,
+        mut cx: AsyncApp,
+    ) -> Result<Self> {
+        let start = message
+            .start
+            .and_then(deserialize_anchor)
+            .ok_or_else(|| anyhow!("invalid start"))?;
+        let end = message
+            .end
+            .and_then(deserialize_anchor)
+            .ok_or_else(|| anyhow!("invalid end"))?;
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self { range: start..end })
+    }
+
+    fn response_to_proto(
+        response: Vec<InlineValue>,
+        _: &mut LspStore,
+        _: PeerId,
+        buffer_version: &clock::Global,
+        _: &mut App,
+    ) -> proto::GetInlineValuesResponse {
+        proto::GetInlineValuesResponse {
+            values: response
+                .into_iter()
+                .map(|value| proto::InlineValue {
+                    start: Some(serialize_anchor(&value.range.start)),
+                    end: Some(serialize_anchor(&value.range.end)),
+                    kind: Some(match value.kind {
+                        InlineValueKind::Text(text) => proto::inline_value::Kind::Text(text),
+                        InlineValueKind::VariableLookup {
+                            variable_name,
+                            case_sensitive,
+                        } => proto::inline_value::Kind::VariableLookup(
+                            proto::InlineValueVariableLookup {
+                                variable_name,
+                                case_sensitive_lookup: case_sensitive,
+                            },
+                        ),
+                        InlineValueKind::EvaluatableExpression { expression } => {
+                            proto::inline_value::Kind::EvaluatableExpression(
+                                proto::InlineValueEvaluatableExpression { expression },
+                            )
+                        }
+                    }),
+                })
+                .collect(),
+            version: serialize_version(buffer_version),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetInlineValuesResponse,
+        _: Entity<LspStore>,
+        buffer: Entity<Buffer>,
+        mut cx: AsyncApp,
+    ) -> Result<Vec<InlineValue>> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        let mut values = Vec::new();
+        for value in message.values {
+            let start = value
+                .start
+                .and_then(deserialize_anchor)
+                .ok_or_else(|| anyhow!("missing inline value start"))?;
+            let end = value
+                .end
+                .and_then(deserialize_anchor)
+                .ok_or_else(|| anyhow!("missing inline value end"))?;
+            let kind = match value
+                .kind
+                .ok_or_else(|| anyhow!("missing inline value kind"))?
+            {
+                proto::inline_value::Kind::Text(text) => InlineValueKind::Text(text),
+                proto::inline_value::Kind::VariableLookup(lookup) => {
+                    InlineValueKind::VariableLookup {
+                        variable_name: lookup.variable_name,
+                        case_sensitive: lookup.case_sensitive_lookup,
+                    }
+                }
+                proto::inline_value::Kind::EvaluatableExpression(expression) => {
+                    InlineValueKind::EvaluatableExpression {
+                        expression: expression.expression,
+                    }
+                }
+            };
+            buffer
+                .update(&mut cx, |buffer, _| buffer.wait_for_anchors([start, end]))?
+                .await?;
+            values.push(InlineValue {
+                range: start..end,
+                kind,
+            });
+        }
+        Ok(values)
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetInlineValues) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
@@ -1,6 +1,5 @@
 use crate::{
     buffer_store::{BufferStore, BufferStoreEvent},
-    deserialize_code_actions,
     environment::ProjectEnvironment,
     lsp_command::{self, *},
     lsp_ext_command,
@@ -11,8 +10,9 @@ use crate::{
     toolchain_store::{EmptyToolchainStore, ToolchainStoreEvent},
     worktree_store::{WorktreeStore, WorktreeStoreEvent},
     yarn::YarnPathStore,
-    CodeAction, Completion, CoreCompletion, Hover, InlayHint, ProjectItem as _, ProjectPath,
-    ProjectTransaction, ResolveState, Symbol, ToolchainStore,
+    BufferRenameEdits, ChangeAnnotation, CodeAction, ColorPresentation, Completion, CoreCompletion,
+    DocumentColor, DocumentLink, Hover, InlayHint, InlineValue, ProjectItem as _, ProjectPath,
+    ProjectTransaction, RenameEdit, ResolveState, Symbol, ToolchainStore,
 };
 use anyhow::{anyhow, Context as _, Result};
 use async_trait::async_trait;
@@ -33,14 +33,16 @@ use http_client::HttpClient;
 use itertools::Itertools as _;
 use language::{
     language_settings::{
-        language_settings, FormatOnSave, Formatter, LanguageSettings, SelectedFormatter,
+        language_settings, CodeActionOnFormat, CodeActionTimeoutBehavior, FormatOnSave, Formatter,
+        FormatterFailureBehavior, LanguageSettings, SelectedFormatter,
     },
     point_to_lsp,
     proto::{deserialize_anchor, deserialize_version, serialize_anchor, serialize_version},
     range_from_lsp, range_to_lsp, Bias, Buffer, BufferSnapshot, CachedLspAdapter, CodeLabel,
-    Diagnostic, DiagnosticEntry, DiagnosticSet, Diff, File as _, Language, LanguageRegistry,
-    LanguageServerBinaryStatus, LanguageToolchainStore, LocalFile, LspAdapter, LspAdapterDelegate,
-    Patch, PointUtf16, TextBufferSnapshot, ToOffset, ToPointUtf16, Transaction, Unclipped,
+    Diagnostic, DiagnosticEntry, DiagnosticSet, Diff, File as _, FormatRequest, FormatResponse,
+    Language, LanguageRegistry, LanguageServerBinaryStatus, LanguageToolchainStore, LintRequest,
+    LocalFile, LspAdapter, LspAdapterDelegate, Patch, PointUtf16, TextBufferSnapshot, ToOffset,
+    ToPointUtf16, Transaction, Unclipped,
 };
 use lsp::{
     notification::DidRenameFiles, CodeActionKind, CompletionContext, DiagnosticSeverity,
@@ -163,6 +165,9 @@ pub struct LocalLspStore {
         >,
     >,
     buffer_snapshots: HashMap<BufferId, HashMap<LanguageServerId, Vec<LspBufferSnapshot>>>, // buffer_id -> server_id -> vec of snapshots
+    /// The IDs the diagnostics of each linter are published under, keyed by linter name.
+    linter_ids: HashMap<SharedString, LanguageServerId>,
+    lint_tasks: HashMap<BufferId, Task<()>>,
     _subscription: gpui::Subscription,
     lsp_tree: Entity<LanguageServerTree>,
     registered_buffers: HashMap<BufferId, usize>,
@@ -1089,11 +1094,14 @@ impl LocalLspStore {
         self.language_servers_for_buffer(buffer, cx).next()
     }
 
+    /// Formats the buffers with the configured formatters, or with only the external
+    /// commands among them when `external_only` is set.
     async fn format_locally(
         lsp_store: WeakEntity<LspStore>,
         mut buffers: Vec<FormattableBuffer>,
         push_to_history: bool,
         trigger: FormatTrigger,
+        external_only: bool,
         mut cx: AsyncApp,
     ) -> anyhow::Result<ProjectTransaction> {
         // Do not allow multiple concurrent formatting requests for the
@@ -1140,8 +1148,9 @@ impl LocalLspStore {
                     .into_owned()
             })?;
 
-            let remove_trailing_whitespace = settings.remove_trailing_whitespace_on_save;
-            let ensure_final_newline = settings.ensure_final_newline_on_save;
+            let remove_trailing_whitespace =
+                settings.remove_trailing_whitespace_on_save && !external_only;
+            let ensure_final_newline = settings.ensure_final_newline_on_save && !external_only;
 
             // First, format buffer's whitespace according to the settings.
             let trailing_whitespace_diff = if remove_trailing_whitespace {
@@ -1169,15 +1178,16 @@ impl LocalLspStore {
             let initial_transaction_id = whitespace_transaction_id;
 
             // Apply the `code_actions_on_format` before we run the formatter.
-            let code_actions = deserialize_code_actions(&settings.code_actions_on_format);
+            let code_actions = settings.code_actions_on_format.actions();
             #[allow(clippy::nonminimal_bool)]
             if !code_actions.is_empty()
+                && !external_only
                 && !(trigger == FormatTrigger::Save && settings.format_on_save == FormatOnSave::Off)
             {
                 Self::execute_code_actions_on_servers(
                     &lsp_store,
                     &adapters_and_servers,
-                    code_actions,
+                    &code_actions,
                     &buffer.handle,
                     push_to_history,
                     &mut project_transaction,
@@ -1208,9 +1218,18 @@ impl LocalLspStore {
                     }
                 }
             };
+            let formatters = if external_only {
+                formatters
+                    .iter()
+                    .filter(|formatter| matches!(formatter, Formatter::External { .. }))
+                    .cloned()
+                    .collect::<Vec<_>>()
+            } else {
+                formatters.to_vec()
+            };
             Self::execute_formatters(
                 lsp_store.clone(),
-                formatters,
+                &formatters,
                 buffer,
                 &settings,
                 &adapters_and_servers,
@@ -1239,7 +1258,8 @@ impl LocalLspStore {
     ) -> anyhow::Result<()> {
         let mut prev_transaction_id = initial_transaction_id;
 
-        for formatter in formatters {
+        let mut formatters = formatters.iter().peekable();
+        while let Some(formatter) = formatters.next() {
             let operation = match formatter {
                 Formatter::LanguageServer { name } => {
                     let Some(language_server) = lsp_store.update(cx, |lsp_store, cx| {
@@ -1309,22 +1329,34 @@ impl LocalLspStore {
                         .await
                         .transpose()?
                 }
-                Formatter::External { command, arguments } => {
-                    Self::format_via_external_command(buffer, command, arguments.as_deref(), cx)
-                        .await
-                        .context(format!(
-                            "failed to format via external command {:?}",
-                            command
-                        ))?
+                Formatter::External { .. } => {
+                    // Consecutive external commands are piped into one another, so that the
+                    // buffer is only edited once with the output of the last one. Ranges are
+                    // resolved against the buffer, so each command formatting ranges runs alone.
+                    let mut commands = vec![formatter];
+                    if buffer.ranges.is_none() {
+                        while let Some(next) = formatters
+                            .next_if(|formatter| matches!(formatter, Formatter::External { .. }))
+                        {
+                            commands.push(next);
+                        }
+                    }
+                    Self::format_via_external_commands(buffer, &commands, cx)
+                        .await?
+                        .map(FormatOperation::External)
+                }
+                Formatter::Extension(name) => {
+                    Self::format_via_formatter_provider(&lsp_store, buffer, name, cx)
+                        .await?
                         .map(FormatOperation::External)
                 }
                 Formatter::CodeActions(code_actions) => {
-                    let code_actions = deserialize_code_actions(code_actions);
+                    let code_actions = code_actions.actions();
                     if !code_actions.is_empty() {
                         Self::execute_code_actions_on_servers(
                             &lsp_store,
                             adapters_and_servers,
-                            code_actions,
+                            &code_actions,
                             &buffer.handle,
                             push_to_history,
                             project_transaction,
@@ -1531,12 +1563,168 @@ impl LocalLspStore {
         }
     }
 
-    async fn format_via_external_command(
+    /// Pipes the buffer's text through the given external commands, returning the diff
+    /// between the buffer and the output of the last command.
+    async fn format_via_external_commands(
         buffer: &FormattableBuffer,
-        command: &str,
-        arguments: Option<&[String]>,
+        commands: &[&Formatter],
+        cx: &mut AsyncApp,
+    ) -> Result<Option<Diff>> {
+        let (mut text, range) = buffer.handle.update(cx, |buffer_handle, _| {
+            let snapshot = buffer_handle.snapshot();
+            // Commands that format ranges are given a single range spanning all of them.
+            let range = buffer.ranges.as_ref().and_then(|ranges| {
+                let start = ranges
+                    .iter()
+                    .map(|range| range.start.to_offset(&snapshot))
+                    .min()?;
+                let end = ranges
+                    .iter()
+                    .map(|range| range.end.to_offset(&snapshot))
+                    .max()?;
+                let rows = snapshot.offset_to_point(start).row..snapshot.offset_to_point(end).row;
+                Some((rows, start..end))
+            });
+            (snapshot.text(), range)
+        })?;
+
+        let mut formatted = false;
+        for formatter in commands {
+            let Formatter::External {
+                command,
+                arguments,
+                range_arguments,
+                on_failure,
+            } = formatter
+            else {
+                continue;
+            };
+            let arguments = match &range {
+                Some((rows, offsets)) => {
+                    let Some(range_arguments) = range_arguments else {
+                        log::info!(
+                            "skipping external formatter {command:?}, which doesn't support formatting ranges"
+                        );
+                        continue;
+                    };
+                    range_arguments
+                        .iter()
+                        .map(|argument| {
+                            argument
+                                .replace("{range_start_line}", &(rows.start + 1).to_string())
+                                .replace("{range_end_line}", &(rows.end + 1).to_string())
+                                .replace("{range_start_offset}", &offsets.start.to_string())
+                                .replace("{range_end_offset}", &offsets.end.to_string())
+                        })
+                        .collect::<Vec<_>>()
+                }
+                None => arguments.as_deref().unwrap_or_default().to_vec(),
+            };
+
+            match Self::run_external_formatter(
+                buffer,
+                command,
+                &arguments,
+                &HashMap::default(),
+                &text,
+                cx,
+            )
+            .await
+            .with_context(|| format!("failed to format via external command {:?}", command))
+            {
+                Ok(output) => {
+                    text = output;
+                    formatted = true;
+                }
+                Err(error) => match on_failure {
+                    FormatterFailureBehavior::Abort => return Err(error),
+                    FormatterFailureBehavior::Skip => log::error!("{error:?}"),
+                },
+            }
+        }
+
+        if !formatted {
+            return Ok(None);
+        }
+        Ok(Some(
+            buffer
+                .handle
+                .update(cx, |buffer, cx| buffer.diff(text, cx))?
+                .await,
+        ))
+    }
+
+    async fn format_via_formatter_provider(
+        lsp_store: &WeakEntity<LspStore>,
+        buffer: &FormattableBuffer,
+        name: &str,
         cx: &mut AsyncApp,
     ) -> Result<Option<Diff>> {
+        if buffer.ranges.is_some() {
+            log::info!("skipping formatter {name:?}, which doesn't support formatting ranges");
+            return Ok(None);
+        }
+
+        let Some((formatter, request, delegate)) = lsp_store.update(cx, |lsp_store, cx| {
+            let local = lsp_store.as_local()?;
+            let buffer = buffer.handle.read(cx);
+            let language_name = buffer.language()?.name();
+            let file = File::from_dyn(buffer.file())?;
+            let request = FormatRequest {
+                path: Some(file.path().to_path_buf()),
+                language_name: language_name.clone(),
+                text: buffer.text(),
+            };
+            let worktree = file.worktree.clone();
+            let formatter = local.languages.formatter_for_name(&language_name, name);
+            let delegate = LocalLspAdapterDelegate::from_local_lsp(local, &worktree, cx);
+            Some((formatter, request, delegate))
+        })?
+        else {
+            return Ok(None);
+        };
+        let formatter = formatter.ok_or_else(|| {
+            anyhow!(
+                "no formatter named {name:?} is available for {}",
+                request.language_name
+            )
+        })?;
+
+        let input = request.text.clone();
+        let response = formatter
+            .format(request, delegate)
+            .await
+            .with_context(|| format!("failed to format via formatter {name:?}"))?;
+        let text = match response {
+            FormatResponse::Text(text) => text,
+            FormatResponse::Command(command) => Self::run_external_formatter(
+                buffer,
+                &command.path.to_string_lossy(),
+                &command.arguments,
+                &command.env,
+                &input,
+                cx,
+            )
+            .await
+            .with_context(|| format!("failed to format via formatter {name:?}"))?,
+        };
+
+        Ok(Some(
+            buffer
+                .handle
+                .update(cx, |buffer, cx| buffer.diff(text, cx))?
+                .await,
+        ))
+    }
+
+    async fn run_external_formatter(
+        buffer: &FormattableBuffer,
+        command: &str,
+        arguments: &[String],
+        env: &HashMap<String, String>,
+        input: &str,
+        cx: &mut AsyncApp,
+    ) -> Result<String> {
         let working_dir_path = buffer.handle.update(cx, |buffer, cx| {
             let file = File::from_dyn(buffer.file())?;
             let worktree = file.worktree.read(cx);
@@ -1552,20 +1740,19 @@ impl LocalLspStore {
         if let Some(buffer_env) = buffer.env.as_ref() {
             child.envs(buffer_env);
         }
+        child.envs(env);
 
         if let Some(working_dir_path) = working_dir_path {
             child.current_dir(working_dir_path);
         }
 
-        if let Some(arguments) = arguments {
-            child.args(arguments.iter().map(|arg| {
-                if let Some(buffer_abs_path) = buffer.abs_path.as_ref() {
-                    arg.replace("{buffer_path}", &buffer_abs_path.to_string_lossy())
-                } else {
-                    arg.replace("{buffer_path}", "Untitled")
-                }
-            }));
-        }
+        child.args(arguments.iter().map(|arg| {
+            if let Some(buffer_abs_path) = buffer.abs_path.as_ref() {
+                arg.replace("{buffer_path}", &buffer_abs_path.to_string_lossy())
+            } else {
+                arg.replace("{buffer_path}", "Untitled")
+            }
+        }));
 
         let mut child = child
             .stdin(smol::process::Stdio::piped())
@@ -1577,12 +1764,7 @@ impl LocalLspStore {
             .stdin
             .as_mut()
             .ok_or_else(|| anyhow!("failed to acquire stdin"))?;
-        let text = buffer
-            .handle
-            .update(cx, |buffer, _| buffer.as_rope().clone())?;
-        for chunk in text.chunks() {
-            stdin.write_all(chunk.as_bytes()).await?;
-        }
+        stdin.write_all(input.as_bytes()).await?;
         stdin.flush().await?;
 
         let output = child.output().await?;
@@ -1595,13 +1777,7 @@ impl LocalLspStore {
             ));
         }
 
-        let stdout = String::from_utf8(output.stdout)?;
-        Ok(Some(
-            buffer
-                .handle
-                .update(cx, |buffer, cx| buffer.diff(stdout, cx))?
-                .await,
-        ))
+        Ok(String::from_utf8(output.stdout)?)
     }
 
     async fn try_resolve_code_action(
@@ -2043,78 +2219,183 @@ impl LocalLspStore {
         }
     }
 
+    /// Runs the given code actions one after another, requesting each of them from the
+    /// language servers it targets in the order the servers are registered for the buffer.
     async fn execute_code_actions_on_servers(
         this: &WeakEntity<LspStore>,
         adapters_and_servers: &[(Arc<CachedLspAdapter>, Arc<LanguageServer>)],
-        code_actions: Vec<lsp::CodeActionKind>,
+        code_actions: &[CodeActionOnFormat],
         buffer: &Entity<Buffer>,
         push_to_history: bool,
         project_transaction: &mut ProjectTransaction,
         cx: &mut AsyncApp,
     ) -> Result<(), anyhow::Error> {
-        for (lsp_adapter, language_server) in adapters_and_servers.iter() {
-            let code_actions = code_actions.clone();
-
-            let actions = this
-                .update(cx, move |this, cx| {
-                    let request = GetCodeActions {
-                        range: text::Anchor::MIN..text::Anchor::MAX,
-                        kinds: Some(code_actions),
-                    };
-                    let server = LanguageServerToQuery::Other(language_server.server_id());
-                    this.request_lsp(buffer.clone(), server, request, cx)
-                })?
-                .await?;
+        for code_action in code_actions {
+            for (lsp_adapter, language_server) in adapters_and_servers.iter() {
+                if code_action
+                    .language_server
+                    .as_ref()
+                    .is_some_and(|name| lsp_adapter.name.0.as_ref() != name.as_str())
+                {
+                    continue;
+                }
 
-            for mut action in actions {
-                Self::try_resolve_code_action(language_server, &mut action)
-                    .await
-                    .context("resolving a formatting code action")?;
+                Self::execute_code_action_on_format(
+                    this,
+                    code_action,
+                    lsp_adapter,
+                    language_server,
+                    buffer,
+                    push_to_history,
+                    project_transaction,
+                    cx,
+                )
+                .await?;
+            }
+        }
 
-                if let Some(edit) = action.lsp_action.edit {
-                    if edit.changes.is_none() && edit.document_changes.is_none() {
-                        continue;
-                    }
+        Ok(())
+    }
 
-                    let new = Self::deserialize_workspace_edit(
-                        this.upgrade().ok_or_else(|| anyhow!("project dropped"))?,
-                        edit,
-                        push_to_history,
-                        lsp_adapter.clone(),
-                        language_server.clone(),
-                        cx,
-                    )
+    /// Requests, resolves and applies the code actions of the given kind from a language
+    /// server, within the code action's timeout.
+    ///
+    /// When the language server times out and the code action is skipped, the edits that
+    /// were already applied are kept in the project transaction.
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_code_action_on_format(
+        this: &WeakEntity<LspStore>,
+        code_action: &CodeActionOnFormat,
+        lsp_adapter: &Arc<CachedLspAdapter>,
+        language_server: &Arc<LanguageServer>,
+        buffer: &Entity<Buffer>,
+        push_to_history: bool,
+        project_transaction: &mut ProjectTransaction,
+        cx: &mut AsyncApp,
+    ) -> Result<()> {
+        let timeout = code_action.timeout_ms.map(|timeout_ms| {
+            let timer = cx
+                .background_executor()
+                .timer(Duration::from_millis(timeout_ms));
+            (timeout_ms, timer)
+        });
+        let execute = async {
+            let actions =
+                Self::fetch_code_actions_on_format(this, code_action, language_server, buffer, cx)
                     .await?;
-                    project_transaction.0.extend(new.0);
-                }
+            Self::apply_code_actions_on_format(
+                this,
+                actions,
+                lsp_adapter,
+                language_server,
+                push_to_history,
+                project_transaction,
+                cx,
+            )
+            .await
+        };
 
-                if let Some(command) = action.lsp_action.command {
-                    this.update(cx, |this, _| {
-                        if let LspStoreMode::Local(mode) = &mut this.mode {
-                            mode.last_workspace_edits_by_language_server
-                                .remove(&language_server.server_id());
-                        }
-                    })?;
+        let Some((timeout_ms, timer)) = timeout else {
+            return execute.await;
+        };
+        select! {
+            result = execute.fuse() => result,
+            _ = timer.fuse() => match code_action.on_timeout {
+                CodeActionTimeoutBehavior::Skip => {
+                    log::warn!(
+                        "skipping code action {} on format: language server {} timed out after {timeout_ms}ms",
+                        code_action.kind,
+                        language_server.name(),
+                    );
+                    Ok(())
+                }
+                CodeActionTimeoutBehavior::Fail => Err(anyhow!(
+                    "code action {} on format timed out after {timeout_ms}ms waiting for language server {}",
+                    code_action.kind,
+                    language_server.name(),
+                )),
+            },
+        }
+    }
 
-                    language_server
-                        .request::<lsp::request::ExecuteCommand>(lsp::ExecuteCommandParams {
-                            command: command.command,
-                            arguments: command.arguments.unwrap_or_default(),
-                            ..Default::default()
-                        })
-                        .await?;
+    /// Requests the code actions of the given kind from a language server and resolves them.
+    async fn fetch_code_actions_on_format(
+        this: &WeakEntity<LspStore>,
+        code_action: &CodeActionOnFormat,
+        language_server: &Arc<LanguageServer>,
+        buffer: &Entity<Buffer>,
+        cx: &mut AsyncApp,
+    ) -> Result<Vec<CodeAction>> {
+        let request = this.update(cx, |this, cx| {
+            let request = GetCodeActions {
+                range: text::Anchor::MIN..text::Anchor::MAX,
+                kinds: Some(vec![code_action.kind.clone().into()]),
+            };
+            let server = LanguageServerToQuery::Other(language_server.server_id());
+            this.request_lsp(buffer.clone(), server, request, cx)
+        })?;
+        let mut actions = request.await?;
+        for action in &mut actions {
+            Self::try_resolve_code_action(language_server, action)
+                .await
+                .context("resolving a formatting code action")?;
+        }
+        Ok(actions)
+    }
 
-                    this.update(cx, |this, _| {
-                        if let LspStoreMode::Local(mode) = &mut this.mode {
-                            project_transaction.0.extend(
-                                mode.last_workspace_edits_by_language_server
-                                    .remove(&language_server.server_id())
-                                    .unwrap_or_default()
-                                    .0,
-                            )
-                        }
-                    })?;
+    async fn apply_code_actions_on_format(
+        this: &WeakEntity<LspStore>,
+        actions: Vec<CodeAction>,
+        lsp_adapter: &Arc<CachedLspAdapter>,
+        language_server: &Arc<LanguageServer>,
+        push_to_history: bool,
+        project_transaction: &mut ProjectTransaction,
+        cx: &mut AsyncApp,
+    ) -> Result<()> {
+        for action in actions {
+            if let Some(edit) = action.lsp_action.edit {
+                if edit.changes.is_none() && edit.document_changes.is_none() {
+                    continue;
                 }
+
+                let new = Self::deserialize_workspace_edit(
+                    this.upgrade().ok_or_else(|| anyhow!("project dropped"))?,
+                    edit,
+                    push_to_history,
+                    lsp_adapter.clone(),
+                    language_server.clone(),
+                    cx,
+                )
+                .await?;
+                project_transaction.0.extend(new.0);
+            }
+
+            if let Some(command) = action.lsp_action.command {
+                this.update(cx, |this, _| {
+                    if let LspStoreMode::Local(mode) = &mut this.mode {
+                        mode.last_workspace_edits_by_language_server
+                            .remove(&language_server.server_id());
+                    }
+                })?;
+
+                language_server
+                    .request::<lsp::request::ExecuteCommand>(lsp::ExecuteCommandParams {
+                        command: command.command,
+                        arguments: command.arguments.unwrap_or_default(),
+                        ..Default::default()
+                    })
+                    .await?;
+
+                this.update(cx, |this, _| {
+                    if let LspStoreMode::Local(mode) = &mut this.mode {
+                        project_transaction.0.extend(
+                            mode.last_workspace_edits_by_language_server
+                                .remove(&language_server.server_id())
+                                .unwrap_or_default()
+                                .0,
+                        )
+                    }
+                })?;
             }
         }
 
@@ -2469,6 +2750,136 @@ impl LocalLspStore {
         Ok(project_transaction)
     }
 
+    /// Resolves the text edits of a `WorkspaceEdit` against the buffers they
+    /// target, without applying them.
+    pub(crate) async fn rename_edits_from_workspace_edit(
+        this: Entity<LspStore>,
+        edit: lsp::WorkspaceEdit,
+        lsp_adapter: Arc<CachedLspAdapter>,
+        language_server: Arc<LanguageServer>,
+        cx: &mut AsyncApp,
+    ) -> Result<Vec<BufferRenameEdits>> {
+        let mut document_edits = Vec::new();
+        if let Some(document_changes) = edit.document_changes {
+            match document_changes {
+                lsp::DocumentChanges::Edits(edits) => document_edits = edits,
+                lsp::DocumentChanges::Operations(operations) => {
+                    for operation in operations {
+                        match operation {
+                            lsp::DocumentChangeOperation::Edit(edit) => document_edits.push(edit),
+                            lsp::DocumentChangeOperation::Op(_) => {
+                                return Err(anyhow!(
+                                    "renames that create, move or delete files cannot be previewed"
+                                ))
+                            }
+                        }
+                    }
+                }
+            }
+        } else if let Some(changes) = edit.changes {
+            document_edits.extend(
+                changes
+                    .into_iter()
+                    .map(|(uri, edits)| lsp::TextDocumentEdit {
+                        text_document: lsp::OptionalVersionedTextDocumentIdentifier {
+                            uri,
+                            version: None,
+                        },
+                        edits: edits.into_iter().map(Edit::Plain).collect(),
+                    }),
+            );
+        }
+
+        let change_annotations = edit.change_annotations.unwrap_or_default();
+        let mut rename_edits = Vec::<BufferRenameEdits>::new();
+        for document_edit in document_edits {
+            let buffer = this
+                .update(cx, |this, cx| {
+                    this.open_local_buffer_via_lsp(
+                        document_edit.text_document.uri.clone(),
+                        language_server.server_id(),
+                        lsp_adapter.name.clone(),
+                        cx,
+                    )
+                })?
+                .await?;
+
+            // Edits sharing an annotation are resolved together, so that the
+            // annotation can be attached to every edit derived from them.
+            let mut edits_by_annotation =
+                HashMap::<Option<lsp::ChangeAnnotationIdentifier>, Vec<TextEdit>>::default();
+            for edit in document_edit.edits {
+                let (annotation_id, edit) = match edit {
+                    Edit::Plain(edit) => (None, edit),
+                    Edit::Annotated(edit) => (Some(edit.annotation_id), edit.text_edit),
+                    Edit::Snippet(edit) => {
+                        let Ok(snippet) = Snippet::parse(&edit.snippet.value) else {
+                            continue;
+                        };
+                        (
+                            edit.annotation_id,
+                            TextEdit {
+                                range: edit.range,
+                                new_text: snippet.text,
+                            },
+                        )
+                    }
+                };
+                let edits = edits_by_annotation.entry(annotation_id).or_default();
+                if !edits.contains(&edit) {
+                    edits.push(edit);
+                }
+            }
+
+            let mut edits = Vec::new();
+            for (annotation_id, lsp_edits) in edits_by_annotation {
+                let annotation = annotation_id
+                    .and_then(|id| change_annotations.get(&id))
+                    .map(|annotation| ChangeAnnotation {
+                        label: annotation.label.clone(),
+                        description: annotation.description.clone(),
+                        needs_confirmation: annotation.needs_confirmation.unwrap_or(false),
+                    });
+                let resolved_edits = this
+                    .update(cx, |this, cx| {
+                        this.as_local_mut().unwrap().edits_from_lsp(
+                            &buffer,
+                            lsp_edits,
+                            language_server.server_id(),
+                            document_edit.text_document.version,
+                            cx,
+                        )
+                    })?
+                    .await?;
+                edits.extend(
+                    resolved_edits
+                        .into_iter()
+                        .map(|(range, new_text)| RenameEdit {
+                            range,
+                            new_text: new_text.to_string(),
+                            annotation: annotation.clone(),
+                        }),
+                );
+            }
+
+            let snapshot = buffer.read_with(cx, |buffer, _| buffer.snapshot())?;
+            edits.sort_by(|a, b| a.range.start.cmp(&b.range.start, &snapshot));
+            if let Some(existing) = rename_edits
+                .iter_mut()
+                .find(|existing| existing.buffer == buffer)
+            {
+                existing.edits.extend(edits);
+                existing
+                    .edits
+                    .sort_by(|a, b| a.range.start.cmp(&b.range.start, &snapshot));
+            } else {
+                rename_edits.push(BufferRenameEdits { buffer, edits });
+            }
+        }
+
+        Ok(rename_edits)
+    }
+
     async fn on_lsp_workspace_edit(
         this: WeakEntity<LspStore>,
         params: lsp::ApplyWorkspaceEditParams,
@@ -2905,6 +3316,7 @@ impl LspStore {
         client.add_entity_request_handler(Self::handle_inlay_hints);
         client.add_entity_request_handler(Self::handle_get_project_symbols);
         client.add_entity_request_handler(Self::handle_resolve_inlay_hint);
+        client.add_entity_request_handler(Self::handle_resolve_document_link);
         client.add_entity_request_handler(Self::handle_open_buffer_for_symbol);
         client.add_entity_request_handler(Self::handle_refresh_inlay_hints);
         client.add_entity_request_handler(Self::handle_on_type_formatting);
@@ -2921,8 +3333,13 @@ impl LspStore {
         client.add_entity_request_handler(Self::handle_lsp_command::<GetReferences>);
         client.add_entity_request_handler(Self::handle_lsp_command::<PrepareRename>);
         client.add_entity_request_handler(Self::handle_lsp_command::<PerformRename>);
+        client.add_entity_request_handler(Self::handle_lsp_command::<GetRenameEdits>);
         client.add_entity_request_handler(Self::handle_lsp_command::<lsp_ext_command::ExpandMacro>);
         client.add_entity_request_handler(Self::handle_lsp_command::<LinkedEditingRange>);
+        client.add_entity_request_handler(Self::handle_lsp_command::<GetDocumentLinks>);
+        client.add_entity_request_handler(Self::handle_lsp_command::<GetDocumentColors>);
+        client.add_entity_request_handler(Self::handle_get_color_presentations);
+        client.add_entity_request_handler(Self::handle_lsp_command::<GetInlineValues>);
     }
 
     pub fn as_remote(&self) -> Option<&RemoteLspStore> {
@@ -3009,6 +3426,8 @@ impl LspStore {
                 language_server_watcher_registrations: Default::default(),
                 buffers_being_formatted: Default::default(),
                 buffer_snapshots: Default::default(),
+                linter_ids: Default::default(),
+                lint_tasks: Default::default(),
                 prettier_store,
                 environment,
                 http_client,
@@ -3267,6 +3686,7 @@ impl LspStore {
 
             if !ignore_refcounts || *refcount == 1 {
                 local.register_buffer_with_language_servers(buffer, cx);
+                self.lint_buffer(buffer, cx);
             }
             if !ignore_refcounts {
                 cx.observe_release(&handle, move |this, buffer, cx| {
@@ -3279,6 +3699,7 @@ impl LspStore {
                     *refcount -= 1;
                     if *refcount == 0 {
                         local.registered_buffers.remove(&buffer_id);
+                        local.lint_tasks.remove(&buffer_id);
                         if let Some(file) = File::from_dyn(buffer.read(cx).file()).cloned() {
                             local.unregister_old_buffer_from_language_servers(&buffer, &file, cx);
                         }
@@ -3349,6 +3770,8 @@ impl LspStore {
                     }
 
                     this.update(&mut cx, |this, cx| {
+                        this.clear_removed_linter_diagnostics(cx);
+
                         let mut plain_text_buffers = Vec::new();
                         let mut buffers_with_unknown_injections = Vec::new();
                         for handle in this.buffer_store.read(cx).buffers() {
@@ -4004,6 +4427,125 @@ impl LspStore {
         )
     }
 
+    pub fn document_links(
+        &mut self,
+        buffer: &Entity<Buffer>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<Vec<DocumentLink>>> {
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::FirstCapable,
+            GetDocumentLinks,
+            cx,
+        )
+    }
+
+    /// Asks the language server that reported `link` for its target, if the link
+    /// was reported without one.
+    pub fn resolve_document_link(
+        &self,
+        link: DocumentLink,
+        buffer_handle: Entity<Buffer>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<DocumentLink>> {
+        if link.target.is_some() {
+            return Task::ready(Ok(link));
+        }
+
+        if let Some((upstream_client, project_id)) = self.upstream_client() {
+            let request = proto::ResolveDocumentLink {
+                project_id,
+                buffer_id: buffer_handle.read(cx).remote_id().into(),
+                link: Some(GetDocumentLinks::project_to_proto_link(link.clone())),
+            };
+            cx.spawn(move |_, _| async move {
+                let response = upstream_client
+                    .request(request)
+                    .await
+                    .context("document link proto request")?;
+                match response.link {
+                    Some(resolved_link) => GetDocumentLinks::proto_to_project_link(resolved_link),
+                    None => Ok(link),
+                }
+            })
+        } else {
+            let server_id = link.server_id;
+            let Some(lang_server) = buffer_handle.update(cx, |buffer, cx| {
+                self.language_server_for_local_buffer(buffer, server_id, cx)
+                    .map(|(_, server)| server.clone())
+            }) else {
+                return Task::ready(Ok(link));
+            };
+            if !GetDocumentLinks::can_resolve_links(&lang_server.capabilities()) {
+                return Task::ready(Ok(link));
+            }
+            let snapshot = buffer_handle.read(cx).snapshot();
+            let lsp_link = match GetDocumentLinks::project_to_lsp_link(link, &snapshot) {
+                Ok(lsp_link) => lsp_link,
+                Err(error) => return Task::ready(Err(error)),
+            };
+            cx.spawn(move |_, cx| async move {
+                let resolved_link = lang_server
+                    .request::<lsp::request::DocumentLinkResolve>(lsp_link)
+                    .await
+                    .context("document link resolve LSP request")?;
+                buffer_handle.read_with(&cx, |buffer, _| {
+                    GetDocumentLinks::lsp_to_project_link(resolved_link, buffer, server_id)
+                })
+            })
+        }
+    }
+
+    pub fn document_colors(
+        &mut self,
+        buffer: &Entity<Buffer>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<Vec<DocumentColor>>> {
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::FirstCapable,
+            GetDocumentColors,
+            cx,
+        )
+    }
+
+    /// Asks the language server that reported `document_color` how `new_color`
+    /// should be written in place of it.
+    pub fn color_presentations(
+        &mut self,
+        buffer: &Entity<Buffer>,
+        document_color: &DocumentColor,
+        new_color: lsp::Color,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<Vec<ColorPresentation>>> {
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Other(document_color.server_id),
+            GetColorPresentations {
+                range: document_color.range.clone(),
+                color: new_color,
+                server_id: document_color.server_id,
+            },
+            cx,
+        )
+    }
+
+    /// Queries the language server for the values that can be shown inline for `range`,
+    /// treating the end of the range as the location execution stopped at.
+    pub fn inline_values(
+        &mut self,
+        buffer: &Entity<Buffer>,
+        range: Range<Anchor>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<Vec<InlineValue>>> {
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::FirstCapable,
+            GetInlineValues { range },
+            cx,
+        )
+    }
+
     fn apply_on_type_formatting(
         &mut self,
         buffer: Entity<Buffer>,
@@ -5213,10 +5755,179 @@ impl LspStore {
         for language_server_id in language_servers {
             self.simulate_disk_based_diagnostics_events_if_needed(language_server_id, cx);
         }
+        self.lint_buffer(&buffer, cx);
 
         None
     }
 
+    /// Runs the linters registered for the buffer's language, replacing the
+    /// diagnostics they previously reported for it.
+    fn lint_buffer(&mut self, buffer: &Entity<Buffer>, cx: &mut Context<Self>) {
+        let Some(local) = self.as_local_mut() else {
+            return;
+        };
+        let (snapshot, request, abs_path, worktree) = {
+            let buffer = buffer.read(cx);
+            let Some(language) = buffer.language() else {
+                return;
+            };
+            let Some(file) = File::from_dyn(buffer.file()).filter(|file| file.is_local()) else {
+                return;
+            };
+            let request = LintRequest {
+                path: Some(file.path().to_path_buf()),
+                language_name: language.name(),
+                text: buffer.text(),
+            };
+            (
+                buffer.text_snapshot(),
+                request,
+                file.abs_path(cx),
+                file.worktree.clone(),
+            )
+        };
+        let linters = local.languages.linters(&request.language_name);
+        // Linters no longer registered for the buffer's language won't replace
+        // the diagnostics they reported, so they're cleared here.
+        let stale_linter_ids = local
+            .linter_ids
+            .iter()
+            .filter(|(name, _)| !linters.iter().any(|linter| linter.name() == **name))
+            .map(|(_, linter_id)| *linter_id)
+            .collect::<Vec<_>>();
+        for linter_id in stale_linter_ids {
+            self.update_diagnostic_entries(linter_id, abs_path.clone(), None, Vec::new(), cx)
+                .log_err();
+        }
+        if linters.is_empty() {
+            return;
+        }
+        let Some(local) = self.as_local_mut() else {
+            return;
+        };
+        let linters = linters
+            .into_iter()
+            .map(|linter| {
+                let linter_id = *local
+                    .linter_ids
+                    .entry(linter.name())
+                    .or_insert_with(|| local.languages.next_language_server_id());
+                (linter_id, linter)
+            })
+            .collect::<Vec<_>>();
+        let delegate: Arc<dyn LspAdapterDelegate> =
+            LocalLspAdapterDelegate::from_local_lsp(local, &worktree, cx);
+
+        let buffer = buffer.clone();
+        let task = cx.spawn(move |this, mut cx| async move {
+            let results = join_all(linters.into_iter().map(|(linter_id, linter)| {
+                let request = request.clone();
+                let delegate = delegate.clone();
+                async move {
+                    let name = linter.name();
+                    let diagnostics = linter
+                        .lint(request, delegate)
+                        .await
+                        .with_context(|| format!("failed to lint via linter {name:?}"));
+                    (linter_id, name, diagnostics)
+                }
+            }))
+            .await;
+
+            this.update(&mut cx, |this, cx| {
+                // Resolve the diagnostics against the linted text, so that they
+                // follow any edits made while the linters were running.
+                let current_snapshot = buffer.read(cx).text_snapshot();
+                let to_point = |offset: usize, bias: Bias| {
+                    let offset = snapshot.clip_offset(offset.min(snapshot.len()), bias);
+                    Unclipped(
+                        snapshot
+                            .anchor_at(offset, bias)
+                            .to_point_utf16(&current_snapshot),
+                    )
+                };
+                for (linter_id, name, diagnostics) in results {
+                    let Some(diagnostics) = diagnostics.log_err() else {
+                        continue;
+                    };
+                    let Some(local) = this.as_local_mut() else {
+                        return;
+                    };
+                    let diagnostics = diagnostics
+                        .into_iter()
+                        .map(|diagnostic| DiagnosticEntry {
+                            range: to_point(diagnostic.range.start, Bias::Left)
+                                ..to_point(diagnostic.range.end, Bias::Right),
+                            diagnostic: Diagnostic {
+                                source: Some(name.to_string()),
+                                code: diagnostic.code.map(lsp::NumberOrString::String),
+                                severity: diagnostic.severity,
+                                message: diagnostic.message,
+                                group_id: post_inc(&mut local.next_diagnostic_group_id),
+                                is_primary: true,
+                                ..Diagnostic::default()
+                            },
+                        })
+                        .collect();
+                    this.update_diagnostic_entries(
+                        linter_id,
+                        abs_path.clone(),
+                        None,
+                        diagnostics,
+                        cx,
+                    )
+                    .log_err();
+                }
+            })
+            .ok();
+        });
+        if let Some(local) = self.as_local_mut() {
+            local.lint_tasks.insert(snapshot.remote_id(), task);
+        }
+    }
+
+    /// Clears the diagnostics of the linters that were removed from the language registry.
+    fn clear_removed_linter_diagnostics(&mut self, cx: &mut Context<Self>) {
+        let Some(local) = self.as_local_mut() else {
+            return;
+        };
+        let languages = local.languages.clone();
+        let mut removed_linter_ids = Vec::new();
+        local.linter_ids.retain(|name, linter_id| {
+            let registered = languages.has_linter(name);
+            if !registered {
+                removed_linter_ids.push(*linter_id);
+            }
+            registered
+        });
+        if removed_linter_ids.is_empty() {
+            return;
+        }
+
+        let mut paths_to_clear = Vec::new();
+        for (worktree_id, diagnostics) in &local.diagnostics {
+            for (path, diagnostics_by_server_id) in diagnostics {
+                for (server_id, _) in diagnostics_by_server_id {
+                    if removed_linter_ids.contains(server_id) {
+                        paths_to_clear.push((*worktree_id, path.clone(), *server_id));
+                    }
+                }
+            }
+        }
+        for (worktree_id, path, linter_id) in paths_to_clear {
+            let Some(worktree) = self
+                .worktree_store
+                .read(cx)
+                .worktree_for_id(worktree_id, cx)
+            else {
+                continue;
+            };
+            let abs_path = worktree.read(cx).abs_path().join(&path);
+            self.update_diagnostic_entries(linter_id, abs_path, None, Vec::new(), cx)
+                .log_err();
+        }
+    }
+
     pub(crate) async fn refresh_workspace_configurations(
         this: &WeakEntity<Self>,
         fs: Arc<dyn Fs>,
@@ -6875,6 +7586,66 @@ impl LspStore {
         })
     }
 
+    async fn handle_get_color_presentations(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::GetColorPresentations>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::GetColorPresentationsResponse> {
+        let sender_id = envelope.original_sender_id().unwrap_or_default();
+        let buffer_id = GetColorPresentations::buffer_id_from_proto(&envelope.payload)?;
+        let buffer_handle = this.update(&mut cx, |this, cx| {
+            this.buffer_store.read(cx).get_existing(buffer_id)
+        })??;
+        let request = GetColorPresentations::from_proto(
+            envelope.payload,
+            this.clone(),
+            buffer_handle.clone(),
+            cx.clone(),
+        )
+        .await?;
+        // Only the server that reported the color knows how to present it.
+        let server = LanguageServerToQuery::Other(request.server_id);
+        let response = this
+            .update(&mut cx, |this, cx| {
+                this.request_lsp(buffer_handle.clone(), server, request, cx)
+            })?
+            .await?;
+        this.update(&mut cx, |this, cx| {
+            GetColorPresentations::response_to_proto(
+                response,
+                this,
+                sender_id,
+                &buffer_handle.read(cx).version(),
+                cx,
+            )
+        })
+    }
+
+    async fn handle_resolve_document_link(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::ResolveDocumentLink>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::ResolveDocumentLinkResponse> {
+        let link = envelope
+            .payload
+            .link
+            .ok_or_else(|| anyhow!("missing document link"))?;
+        let link = GetDocumentLinks::proto_to_project_link(link)?;
+        let buffer = this.update(&mut cx, |this, cx| {
+            let buffer_id = BufferId::new(envelope.payload.buffer_id)?;
+            this.buffer_store.read(cx).get_existing(buffer_id)
+        })??;
+        let resolved_link = this
+            .update(&mut cx, |this, cx| {
+                this.resolve_document_link(link, buffer, cx)
+            })?
+            .await
+            .context("resolving document link")?;
+        Ok(proto::ResolveDocumentLinkResponse {
+            link: Some(GetDocumentLinks::project_to_proto_link(resolved_link)),
+        })
+    }
+
     async fn handle_open_buffer_for_symbol(
         this: Entity<Self>,
         envelope: TypedEnvelope<proto::OpenBufferForSymbol>,
@@ -7098,6 +7869,36 @@ impl LspStore {
         push_to_history: bool,
         trigger: FormatTrigger,
         cx: &mut Context<Self>,
+    ) -> Task<anyhow::Result<ProjectTransaction>> {
+        self.format_internal(buffers, target, push_to_history, trigger, false, cx)
+    }
+
+    /// Formats the buffers with only the external commands among the configured formatters.
+    pub fn format_with_external_formatters(
+        &mut self,
+        buffers: HashSet<Entity<Buffer>>,
+        target: LspFormatTarget,
+        push_to_history: bool,
+        cx: &mut Context<Self>,
+    ) -> Task<anyhow::Result<ProjectTransaction>> {
+        self.format_internal(
+            buffers,
+            target,
+            push_to_history,
+            FormatTrigger::Manual,
+            true,
+            cx,
+        )
+    }
+
+    fn format_internal(
+        &mut self,
+        buffers: HashSet<Entity<Buffer>>,
+        target: LspFormatTarget,
+        push_to_history: bool,
+        trigger: FormatTrigger,
+        external_only: bool,
+        cx: &mut Context<Self>,
     ) -> Task<anyhow::Result<ProjectTransaction>> {
         if let Some(_) = self.as_local() {
             let buffers = buffers
@@ -7144,6 +7945,7 @@ impl LspStore {
                     formattable_buffers,
                     push_to_history,
                     trigger,
+                    external_only,
                     cx.clone(),
                 )
                 .await;
@@ -7154,6 +7956,11 @@ impl LspStore {
                 result
             })
         } else if let Some((client, project_id)) = self.upstream_client() {
+            if external_only {
+                return Task::ready(Err(anyhow!(
+                    "formatting with external formatters is not supported in remote projects"
+                )));
+            }
             // Don't support formatting ranges via remote
             match target {
                 LspFormatTarget::Buffers => {}
@@ -0,0 +1,276 @@
+//! Persists the undo history of local buffers across restarts, similar to Vim's `undofile`.
+//!
+//! A buffer's history is stored under the file's absolute path along with a hash of the
+//! file's contents, and is only restored if the file still has those contents when it's
+//! reopened.
+
+use crate::project_settings::PersistentUndoSettings;
+use anyhow::{anyhow, Context as _, Result};
+use db::{define_connection, query, sqlez_macros::sql};
+use gpui::{App, AppContext as _, Task};
+use language::{
+    proto::{deserialize_history, serialize_history},
+    Buffer, File as _, LocalFile as _,
+};
+use rpc::proto::{self, Message as _};
+use sha2::{Digest, Sha256};
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use text::Rope;
+use worktree::File;
+
+define_connection!(
+    pub static ref DB: PersistentUndoDb<()> = &[sql!(
+        CREATE TABLE buffer_histories(
+            path BLOB NOT NULL PRIMARY KEY,
+            content_hash BLOB NOT NULL,
+            history BLOB NOT NULL,
+            updated_at INTEGER NOT NULL
+        ) STRICT;
+        CREATE INDEX buffer_histories_updated_at ON buffer_histories(updated_at);
+    )],
+    &[sql!(
+        DROP TABLE buffer_histories;
+    )];
+);
+
+impl PersistentUndoDb {
+    query! {
+        pub fn get_history(path: &Path, content_hash: &[u8]) -> Result<Option<Vec<u8>>> {
+            SELECT history FROM buffer_histories
+            WHERE path = ? AND content_hash = ?
+        }
+    }
+
+    query! {
+        pub async fn save_history(path: PathBuf, content_hash: Vec<u8>, history: Vec<u8>, updated_at: i64) -> Result<()> {
+            INSERT INTO buffer_histories
+                (path, content_hash, history, updated_at)
+            VALUES
+                (?1, ?2, ?3, ?4)
+            ON CONFLICT DO UPDATE SET
+                content_hash = ?2,
+                history = ?3,
+                updated_at = ?4
+        }
+    }
+
+    query! {
+        pub async fn delete_history(path: PathBuf) -> Result<()> {
+            DELETE FROM buffer_histories WHERE path = ?
+        }
+    }
+
+    // Deletes the histories saved before `min_updated_at`, followed by the least recently
+    // saved histories that don't fit within `max_total_size` bytes.
+    query! {
+        pub async fn prune(min_updated_at: i64, max_total_size: i64) -> Result<()> {
+            DELETE FROM buffer_histories WHERE updated_at < ?1;
+            DELETE FROM buffer_histories WHERE path IN (
+                SELECT path FROM (
+                    SELECT
+                        path,
+                        SUM(length(history)) OVER (ORDER BY updated_at DESC, path) AS total_size
+                    FROM buffer_histories
+                )
+                WHERE total_size > ?2
+            )
+        }
+    }
+}
+
+/// Rebuilds `buffer`, which was just loaded from `abs_path`, from its saved history, if
+/// the history was saved when the file had the same contents.
+pub(crate) fn restore_history(
+    abs_path: &Path,
+    buffer: &text::Buffer,
+) -> Result<Option<text::Buffer>> {
+    let hash = content_hash(buffer.as_rope());
+    let Some(history) = DB.get_history(abs_path, &hash)? else {
+        return Ok(None);
+    };
+
+    let history = proto::BufferHistory::decode(history.as_slice())?;
+    let restored = deserialize_history(history, buffer.remote_id(), buffer.line_ending())?;
+    if content_hash(restored.as_rope()) != hash {
+        return Err(anyhow!(
+            "saved history for {abs_path:?} doesn't produce its contents"
+        ));
+    }
+    Ok(Some(restored))
+}
+
+/// Saves the history of `buffer`, provided that it's a local buffer whose contents match
+/// the file on disk and that isn't private.
+///
+/// The history is compacted so that it starts from the text before the oldest undoable
+/// transaction, rather than from wherever it started in the first session it was saved in.
+/// When it doesn't fit within the total size of saved histories, its oldest transactions are
+/// dropped until it does.
+pub(crate) fn save_history(
+    buffer: &Buffer,
+    settings: PersistentUndoSettings,
+    cx: &App,
+) -> Task<Result<()>> {
+    let Some(file) = File::from_dyn(buffer.file()) else {
+        return Task::ready(Ok(()));
+    };
+    if buffer.replica_id() != 0
+        || !file.is_local()
+        || file.is_private()
+        || file.disk_state().mtime().is_none()
+        || buffer.is_dirty()
+        || buffer.has_conflict()
+        || (buffer.peek_undo_stack().is_none() && buffer.peek_redo_stack().is_none())
+    {
+        return Task::ready(Ok(()));
+    }
+
+    let abs_path = file.abs_path(cx);
+    let content = buffer.as_rope().clone();
+    let history = buffer.history();
+    let buffer_id = buffer.remote_id();
+    let line_ending = buffer.line_ending();
+    let max_total_size = settings.max_size_mb.saturating_mul(1024 * 1024);
+    let max_age = Duration::from_secs(settings.max_age_days.saturating_mul(24 * 60 * 60));
+    cx.background_spawn(async move {
+        let hash = content_hash(&content);
+        let text_buffer = text::Buffer::from_history(buffer_id, line_ending, history)?;
+        let Some(history) = encode_history(text_buffer, max_total_size)
+            .with_context(|| format!("compacting the history for {abs_path:?}"))?
+        else {
+            // Even the redo stack on its own is too large to keep.
+            return DB.delete_history(abs_path).await;
+        };
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+        DB.save_history(abs_path, hash, history, now.as_secs() as i64)
+            .await?;
+        DB.prune(
+            now.saturating_sub(max_age).as_secs() as i64,
+            i64::try_from(max_total_size).unwrap_or(i64::MAX),
+        )
+        .await
+    })
+}
+
+/// Compacts the history of `buffer` and encodes it, halving the number of undoable
+/// transactions that are kept until it fits within `max_size` bytes. Returns `None` if it
+/// doesn't fit even without any of them.
+fn encode_history(mut buffer: text::Buffer, max_size: u64) -> Result<Option<Vec<u8>>> {
+    let hash = content_hash(buffer.as_rope());
+    let mut max_undo_entries = buffer.undo_stack().count();
+    loop {
+        buffer = buffer.compact_history(max_undo_entries);
+        if content_hash(buffer.as_rope()) != hash {
+            return Err(anyhow!(
+                "compacted history doesn't produce the buffer's contents"
+            ));
+        }
+
+        let history = serialize_history(&buffer).encode_to_vec();
+        if history.len() as u64 <= max_size {
+            return Ok(Some(history));
+        }
+        if max_undo_entries == 0 {
+            return Ok(None);
+        }
+        max_undo_entries /= 2;
+    }
+}
+
+fn content_hash(content: &Rope) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    for chunk in content.chunks() {
+        hasher.update(chunk.as_bytes());
+    }
+    hasher.finalize().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use db::open_test_db;
+    use text::BufferId;
+
+    #[gpui::test]
+    async fn test_prune_old_histories() {
+        let db = PersistentUndoDb(open_test_db("test_prune_old_histories").await);
+        let day = 24 * 60 * 60;
+        db.save_history("/old".into(), vec![1], vec![0; 10], 10 * day)
+            .await
+            .unwrap();
+        db.save_history("/new".into(), vec![2], vec![0; 10], 20 * day)
+            .await
+            .unwrap();
+
+        db.prune(15 * day, i64::MAX).await.unwrap();
+        assert_eq!(db.get_history(Path::new("/old"), &[1]).unwrap(), None);
+        assert_eq!(
+            db.get_history(Path::new("/new"), &[2]).unwrap(),
+            Some(vec![0; 10])
+        );
+    }
+
+    #[gpui::test]
+    async fn test_prune_least_recently_saved_histories() {
+        let db = PersistentUndoDb(open_test_db("test_prune_least_recently_saved_histories").await);
+        for (ix, path) in ["/a", "/b", "/c"].into_iter().enumerate() {
+            db.save_history(path.into(), vec![1], vec![0; 10], ix as i64)
+                .await
+                .unwrap();
+        }
+
+        // The oldest histories are deleted until the rest fit.
+        db.prune(0, 25).await.unwrap();
+        assert_eq!(db.get_history(Path::new("/a"), &[1]).unwrap(), None);
+        assert!(db.get_history(Path::new("/b"), &[1]).unwrap().is_some());
+        assert!(db.get_history(Path::new("/c"), &[1]).unwrap().is_some());
+
+        db.prune(0, 9).await.unwrap();
+        assert_eq!(db.get_history(Path::new("/b"), &[1]).unwrap(), None);
+        assert_eq!(db.get_history(Path::new("/c"), &[1]).unwrap(), None);
+    }
+
+    #[test]
+    fn test_encode_oversized_history() {
+        let buffer_id = BufferId::new(1).unwrap();
+        let mut buffer = text::Buffer::new(0, buffer_id, String::new());
+        for ix in 0..8 {
+            let len = buffer.len();
+            buffer.edit([(len..len, ix.to_string().repeat(100))]);
+            buffer.finalize_last_transaction();
+        }
+        let text = buffer.text();
+        let rebuild = || {
+            text::Buffer::from_history(buffer_id, buffer.line_ending(), buffer.history()).unwrap()
+        };
+        let decode = |history: Vec<u8>| {
+            let history = proto::BufferHistory::decode(history.as_slice()).unwrap();
+            deserialize_history(history, buffer_id, buffer.line_ending()).unwrap()
+        };
+
+        let full_size = encode_history(rebuild(), u64::MAX).unwrap().unwrap().len() as u64;
+        let restored = decode(encode_history(rebuild(), full_size).unwrap().unwrap());
+        assert_eq!(restored.undo_stack().count(), 8);
+        assert_eq!(restored.text(), text);
+
+        // Histories that are too large keep fewer of their oldest transactions, but still
+        // produce the same text.
+        let trimmed = encode_history(rebuild(), full_size - 1).unwrap().unwrap();
+        assert!(trimmed.len() as u64 <= full_size - 1);
+        let mut restored = decode(trimmed);
+        assert_eq!(restored.text(), text);
+        assert_eq!(restored.undo_stack().count(), 4);
+        for _ in 0..4 {
+            restored.undo();
+        }
+        assert_eq!(restored.text(), text[..400]);
+        assert!(restored.undo().is_none());
+
+        // If not even the text fits, there's no history to save.
+        assert_eq!(encode_history(rebuild(), 10).unwrap(), None);
+    }
+}
@@ -7,11 +7,13 @@ pub mod image_store;
 pub mod lsp_command;
 pub mod lsp_ext_command;
 pub mod lsp_store;
+mod persistent_undo;
 pub mod prettier_store;
 pub mod project_settings;
 mod project_tree;
 pub mod search;
 mod task_inventory;
+mod task_provider_registry;
 pub mod task_store;
 pub mod terminals;
 pub mod toolchain_store;
@@ -84,6 +86,7 @@ use snippet::Snippet;
 use snippet_provider::SnippetProvider;
 use std::{
     borrow::Cow,
+    mem,
     ops::Range,
     path::{Component, Path, PathBuf},
     pin::pin,
@@ -93,7 +96,7 @@ use std::{
 };
 use task_store::TaskStore;
 use terminals::Terminals;
-use text::{Anchor, BufferId};
+use text::{Anchor, BufferId, LineEnding};
 use toolchain_store::EmptyToolchainStore;
 use util::{
     paths::{compare_paths, SanitizedPath},
@@ -109,6 +112,7 @@ pub use prettier::FORMAT_SUFFIX as TEST_PRETTIER_FORMAT_SUFFIX;
 pub use task_inventory::{
     BasicContextProvider, ContextProviderWithTasks, Inventory, TaskContexts, TaskSourceKind,
 };
+pub use task_provider_registry::{TaskProvider, TaskProviderRegistry, TaskProviderWorktree};
 pub use worktree::{
     Entry, EntryKind, File, LocalWorktree, PathChange, ProjectEntryId, UpdatedEntriesSet,
     UpdatedGitRepositoriesSet, Worktree, WorktreeId, WorktreeSettings, FS_WATCH_LATENCY,
@@ -181,6 +185,10 @@ pub struct Project {
     environment: Entity<ProjectEnvironment>,
     settings_observer: Entity<SettingsObserver>,
     toolchain_store: Option<Entity<ToolchainStore>>,
+    remote_listening_ports: Vec<RemoteListeningPort>,
+    /// Buffers edited while disconnected from the ssh host, along with the
+    /// version they were last saved or loaded at when they were first edited.
+    buffers_edited_offline: HashMap<BufferId, clock::Global>,
 }
 
 #[derive(Default)]
@@ -273,6 +281,8 @@ pub enum Event {
     RemoteIdChanged(Option<u64>),
     DisconnectedFromHost,
     DisconnectedFromSshRemote,
+    RemoteListeningPortsChanged,
+    OfflineEditConflicts(Vec<OfflineEditConflict>),
     Closed,
     DeletedEntry(WorktreeId, ProjectEntryId),
     CollaboratorUpdated {
@@ -290,6 +300,24 @@ pub enum Event {
     ExpandedAllForEntry(WorktreeId, ProjectEntryId),
 }
 
+/// A TCP port listened on by a process on the ssh host.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RemoteListeningPort {
+    pub address: String,
+    pub port: u16,
+    pub pid: u32,
+    pub process_name: String,
+}
+
+/// A buffer edited while disconnected from the ssh host, whose file changed
+/// on the host in the meantime.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OfflineEditConflict {
+    pub buffer: Entity<Buffer>,
+    /// The contents of the file on the host after reconnecting.
+    pub disk_text: String,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
 pub struct ProjectPath {
     pub worktree_id: WorktreeId,
@@ -477,6 +505,74 @@ pub struct DocumentHighlight {
     pub kind: DocumentHighlightKind,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentLink {
+    pub range: Range<language::Anchor>,
+    pub target: Option<String>,
+    pub tooltip: Option<String>,
+    /// The language server that reported the link.
+    pub server_id: LanguageServerId,
+    /// The data the language server attached to the link, for resolving its target.
+    pub lsp_data: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentColor {
+    pub range: Range<language::Anchor>,
+    pub color: lsp::Color,
+    pub server_id: LanguageServerId,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorPresentation {
+    pub label: String,
+    pub text_edit: Option<(Range<language::Anchor>, String)>,
+    pub additional_text_edits: Vec<(Range<language::Anchor>, String)>,
+}
+
+/// A value that can be rendered next to a range of a buffer, as reported by `textDocument/inlineValue`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlineValue {
+    pub range: Range<language::Anchor>,
+    pub kind: InlineValueKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InlineValueKind {
+    /// The text to show, as computed by the language server.
+    Text(String),
+    /// A variable whose value has to be looked up, named by the range when `variable_name` is `None`.
+    VariableLookup {
+        variable_name: Option<String>,
+        case_sensitive: bool,
+    },
+    /// An expression that has to be evaluated, given by the range when `expression` is `None`.
+    EvaluatableExpression { expression: Option<String> },
+}
+
+/// The edits a rename would make to a single buffer, computed without applying them.
+#[derive(Debug, Clone)]
+pub struct BufferRenameEdits {
+    pub buffer: Entity<Buffer>,
+    pub edits: Vec<RenameEdit>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenameEdit {
+    pub range: Range<language::Anchor>,
+    pub new_text: String,
+    pub annotation: Option<ChangeAnnotation>,
+}
+
+/// Additional information the language server attached to an edit of a `WorkspaceEdit`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeAnnotation {
+    pub label: String,
+    pub description: Option<String>,
+    /// Whether the user has to confirm this edit before it gets applied.
+    pub needs_confirmation: bool,
+}
+
 #[derive(Clone, Debug)]
 pub struct Symbol {
     pub language_server_name: LanguageServerName,
@@ -739,6 +835,8 @@ impl Project {
 
                 search_included_history: Self::new_search_history(),
                 search_excluded_history: Self::new_search_history(),
+                remote_listening_ports: Vec::new(),
+                buffers_edited_offline: HashMap::default(),
 
                 toolchain_store: Some(toolchain_store),
             }
@@ -883,6 +981,8 @@ impl Project {
 
                 search_included_history: Self::new_search_history(),
                 search_excluded_history: Self::new_search_history(),
+                remote_listening_ports: Vec::new(),
+                buffers_edited_offline: HashMap::default(),
 
                 toolchain_store: Some(toolchain_store),
             };
@@ -902,6 +1002,7 @@ impl Project {
             ssh_proto.add_entity_message_handler(Self::handle_toast);
             ssh_proto.add_entity_request_handler(Self::handle_language_server_prompt_request);
             ssh_proto.add_entity_message_handler(Self::handle_hide_toast);
+            ssh_proto.add_entity_message_handler(Self::handle_update_listening_ports);
             ssh_proto.add_entity_request_handler(Self::handle_update_buffer_from_ssh);
             BufferStore::init(&ssh_proto);
             LspStore::init(&ssh_proto);
@@ -1100,6 +1201,8 @@ impl Project {
                 search_history: Self::new_search_history(),
                 search_included_history: Self::new_search_history(),
                 search_excluded_history: Self::new_search_history(),
+                remote_listening_ports: Vec::new(),
+                buffers_edited_offline: HashMap::default(),
                 environment: ProjectEnvironment::new(&worktree_store, None, cx),
                 remotely_created_models: Arc::new(Mutex::new(RemotelyCreatedModels::default())),
                 toolchain_store: None,
@@ -1287,6 +1390,31 @@ impl Project {
         self.client.clone()
     }
 
+    /// The ports listened on by processes on the ssh host, such as servers
+    /// started from terminals or tasks, while they are being watched.
+    pub fn remote_listening_ports(&self) -> &[RemoteListeningPort] {
+        &self.remote_listening_ports
+    }
+
+    /// Starts or stops detecting the ports listened on by processes on the ssh host.
+    pub fn watch_remote_listening_ports(&mut self, watch: bool, cx: &mut Context<Self>) {
+        let Some(ssh_client) = &self.ssh_client else {
+            return;
+        };
+        ssh_client
+            .read(cx)
+            .proto_client()
+            .send(proto::WatchListeningPorts {
+                project_id: SSH_PROJECT_ID,
+                watch,
+            })
+            .log_err();
+        if !watch && !self.remote_listening_ports.is_empty() {
+            self.remote_listening_ports.clear();
+            cx.emit(Event::RemoteListeningPortsChanged);
+        }
+    }
+
     pub fn ssh_client(&self) -> Option<Entity<SshRemoteClient>> {
         self.ssh_client.clone()
     }
@@ -1981,6 +2109,13 @@ impl Project {
         })
     }
 
+    /// Whether the file at the given path has failed to open as a buffer because its contents
+    /// look binary, i.e. they contain NUL bytes and aren't UTF-16 or UTF-8. Other contents
+    /// that aren't valid UTF-8 are decoded in a detected encoding instead.
+    pub fn is_binary_file(&self, path: &ProjectPath, cx: &App) -> bool {
+        self.buffer_store.read(cx).is_binary_file(path)
+    }
+
     #[cfg(any(test, feature = "test-support"))]
     pub fn open_buffer_with_lsp(
         &mut self,
@@ -2382,19 +2517,33 @@ impl Project {
     ) {
         match event {
             remote::SshRemoteEvent::Disconnected => {
-                // if self.is_via_ssh() {
-                // self.collaborators.clear();
-                self.worktree_store.update(cx, |store, cx| {
-                    store.disconnected_from_host(cx);
-                });
-                self.buffer_store.update(cx, |buffer_store, cx| {
-                    buffer_store.disconnected_from_host(cx)
-                });
-                self.lsp_store.update(cx, |lsp_store, _cx| {
-                    lsp_store.disconnected_from_ssh_remote()
-                });
+                let can_resume = self
+                    .ssh_client
+                    .as_ref()
+                    .is_some_and(|ssh| ssh.read(cx).can_resume());
+                if can_resume {
+                    // The server may still be running, so keep buffers
+                    // editable and reconcile them once the connection is
+                    // resumed.
+                    self.buffer_store.update(cx, |buffer_store, cx| {
+                        buffer_store.disconnected_from_ssh_remote(cx)
+                    });
+                } else {
+                    self.worktree_store.update(cx, |store, cx| {
+                        store.disconnected_from_host(cx);
+                    });
+                    self.buffer_store.update(cx, |buffer_store, cx| {
+                        buffer_store.disconnected_from_host(cx)
+                    });
+                    self.lsp_store.update(cx, |lsp_store, _cx| {
+                        lsp_store.disconnected_from_ssh_remote()
+                    });
+                }
                 cx.emit(Event::DisconnectedFromSshRemote);
             }
+            remote::SshRemoteEvent::Resumed => {
+                self.reconcile_offline_edits(cx).detach_and_log_err(cx);
+            }
         }
     }
 
@@ -2505,14 +2654,23 @@ impl Project {
                 let operation = language::proto::serialize_operation(operation);
 
                 if let Some(ssh) = &self.ssh_client {
-                    ssh.read(cx)
-                        .proto_client()
-                        .send(proto::UpdateBuffer {
-                            project_id: 0,
-                            buffer_id: buffer_id.to_proto(),
-                            operations: vec![operation.clone()],
-                        })
-                        .ok();
+                    let ssh = ssh.read(cx);
+                    if ssh.is_disconnected() {
+                        // The operation is sent when synchronizing buffers
+                        // after the connection is resumed.
+                        let saved_version = buffer.read(cx).saved_version().clone();
+                        self.buffers_edited_offline
+                            .entry(buffer_id)
+                            .or_insert(saved_version);
+                    } else {
+                        ssh.proto_client()
+                            .send(proto::UpdateBuffer {
+                                project_id: 0,
+                                buffer_id: buffer_id.to_proto(),
+                                operations: vec![operation.clone()],
+                            })
+                            .ok();
+                    }
                 }
 
                 self.enqueue_buffer_ordered_message(BufferOrderedMessage::Operation {
@@ -2768,6 +2926,18 @@ impl Project {
         })
     }
 
+    pub fn format_with_external_formatters(
+        &mut self,
+        buffers: HashSet<Entity<Buffer>>,
+        target: LspFormatTarget,
+        push_to_history: bool,
+        cx: &mut Context<Project>,
+    ) -> Task<anyhow::Result<ProjectTransaction>> {
+        self.lsp_store.update(cx, |lsp_store, cx| {
+            lsp_store.format_with_external_formatters(buffers, target, push_to_history, cx)
+        })
+    }
+
     #[inline(never)]
     fn definition_impl(
         &mut self,
@@ -2990,6 +3160,60 @@ impl Project {
         })
     }
 
+    pub fn document_links(
+        &self,
+        buffer: &Entity<Buffer>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<Vec<DocumentLink>>> {
+        self.lsp_store
+            .update(cx, |lsp_store, cx| lsp_store.document_links(buffer, cx))
+    }
+
+    pub fn resolve_document_link(
+        &self,
+        link: DocumentLink,
+        buffer: Entity<Buffer>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<DocumentLink>> {
+        self.lsp_store.update(cx, |lsp_store, cx| {
+            lsp_store.resolve_document_link(link, buffer, cx)
+        })
+    }
+
+    pub fn document_colors(
+        &self,
+        buffer: &Entity<Buffer>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<Vec<DocumentColor>>> {
+        self.lsp_store
+            .update(cx, |lsp_store, cx| lsp_store.document_colors(buffer, cx))
+    }
+
+    pub fn color_presentations(
+        &self,
+        buffer: &Entity<Buffer>,
+        document_color: &DocumentColor,
+        new_color: lsp::Color,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<Vec<ColorPresentation>>> {
+        self.lsp_store.update(cx, |lsp_store, cx| {
+            lsp_store.color_presentations(buffer, document_color, new_color, cx)
+        })
+    }
+
+    pub fn inline_values<T: ToOffset>(
+        &mut self,
+        buffer_handle: &Entity<Buffer>,
+        range: Range<T>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<Vec<InlineValue>>> {
+        let buffer = buffer_handle.read(cx);
+        let range = buffer.anchor_before(range.start)..buffer.anchor_after(range.end);
+        self.lsp_store.update(cx, |lsp_store, cx| {
+            lsp_store.inline_values(buffer_handle, range, cx)
+        })
+    }
+
     pub fn completions<T: ToOffset + ToPointUtf16>(
         &self,
         buffer: &Entity<Buffer>,
@@ -3073,6 +3297,24 @@ impl Project {
         )
     }
 
+    /// Computes the edits that renaming the symbol at `position` would produce,
+    /// without applying them to any buffer.
+    pub fn rename_edits<T: ToPointUtf16>(
+        &mut self,
+        buffer: Entity<Buffer>,
+        position: T,
+        new_name: String,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<Vec<BufferRenameEdits>>> {
+        let position = position.to_point_utf16(buffer.read(cx));
+        self.request_lsp(
+            buffer,
+            LanguageServerToQuery::FirstCapable,
+            GetRenameEdits { position, new_name },
+            cx,
+        )
+    }
+
     pub fn on_type_format<T: ToPointUtf16>(
         &mut self,
         buffer: Entity<Buffer>,
@@ -3879,6 +4121,29 @@ impl Project {
         })?
     }
 
+    async fn handle_update_listening_ports(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::UpdateListeningPorts>,
+        mut cx: AsyncApp,
+    ) -> Result<()> {
+        this.update(&mut cx, |this, cx| {
+            this.remote_listening_ports = envelope
+                .payload
+                .ports
+                .into_iter()
+                .filter_map(|port| {
+                    Some(RemoteListeningPort {
+                        address: port.address,
+                        port: u16::try_from(port.port).ok()?,
+                        pid: port.pid,
+                        process_name: port.process_name,
+                    })
+                })
+                .collect();
+            cx.emit(Event::RemoteListeningPortsChanged);
+        })
+    }
+
     // Collab sends UpdateWorktree protos as messages
     async fn handle_update_worktree(
         this: Entity<Self>,
@@ -3972,7 +4237,7 @@ impl Project {
         mut cx: AsyncApp,
     ) -> Result<proto::SynchronizeBuffersResponse> {
         let response = this.update(&mut cx, |this, cx| {
-            let client = this.client.clone();
+            let client = this.client.clone().into();
             this.buffer_store.update(cx, |this, cx| {
                 this.handle_synchronize_buffers(envelope, cx, client)
             })
@@ -4116,7 +4381,17 @@ impl Project {
             }
         };
 
-        let client = self.client.clone();
+        self.synchronize_buffers(self.client.clone().into(), project_id, cx)
+    }
+
+    /// Sends the operations the host is missing for every open buffer, based on
+    /// the versions it reports for them.
+    fn synchronize_buffers(
+        &mut self,
+        client: AnyProtoClient,
+        project_id: u64,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
         cx.spawn(move |this, mut cx| async move {
             let (buffers, incomplete_buffer_ids) = this.update(&mut cx, |this, cx| {
                 this.buffer_store.read(cx).buffer_version_info(cx)
@@ -4166,12 +4441,14 @@ impl Project {
 
             // Any incomplete buffers have open requests waiting. Request that the host sends
             // creates these buffers for us again to unblock any waiting futures.
-            for id in incomplete_buffer_ids {
-                cx.background_spawn(client.request(proto::OpenBufferById {
-                    project_id,
-                    id: id.into(),
-                }))
-                .detach();
+            if client.is_via_collab() {
+                for id in incomplete_buffer_ids {
+                    cx.background_spawn(client.request(proto::OpenBufferById {
+                        project_id,
+                        id: id.into(),
+                    }))
+                    .detach();
+                }
             }
 
             futures::future::join_all(send_updates_for_buffers)
@@ -4181,6 +4458,63 @@ impl Project {
         })
     }
 
+    /// Sends the edits made while disconnected from the ssh host once the
+    /// connection to it is resumed, and reports the edited buffers whose files
+    /// changed on the host in the meantime.
+    fn reconcile_offline_edits(&mut self, cx: &mut Context<Self>) -> Task<Result<()>> {
+        let Some(ssh) = self.ssh_client.as_ref() else {
+            return Task::ready(Ok(()));
+        };
+        let client = ssh.read(cx).proto_client();
+        let synchronize = self.synchronize_buffers(client, SSH_PROJECT_ID, cx);
+        cx.spawn(move |this, mut cx| async move {
+            synchronize.await?;
+
+            // The host's file updates may still be in flight, so the file's contents are
+            // compared against what they were when the buffer was last saved or loaded.
+            let (buffers, disk_texts) = this.update(&mut cx, |this, cx| {
+                let buffers = mem::take(&mut this.buffers_edited_offline)
+                    .into_iter()
+                    .filter_map(|(buffer_id, saved_version)| {
+                        let buffer = this.buffer_for_id(buffer_id, cx)?;
+                        if !buffer.read(cx).is_dirty() {
+                            return None;
+                        }
+                        let saved_text = buffer.read(cx).rope_for_version(&saved_version);
+                        Some((buffer, saved_text))
+                    })
+                    .collect::<Vec<_>>();
+                let disk_texts = buffers
+                    .iter()
+                    .map(|(buffer, _)| {
+                        this.buffer_store.update(cx, |buffer_store, cx| {
+                            buffer_store.load_disk_text(buffer, cx)
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                (buffers, disk_texts)
+            })?;
+
+            let disk_texts = futures::future::join_all(disk_texts).await;
+            let conflicts = buffers
+                .into_iter()
+                .zip(disk_texts)
+                .filter_map(|((buffer, saved_text), disk_text)| {
+                    let mut disk_text = disk_text.log_err()?;
+                    LineEnding::normalize(&mut disk_text);
+                    (saved_text.to_string() != disk_text)
+                        .then_some(OfflineEditConflict { buffer, disk_text })
+                })
+                .collect::<Vec<_>>();
+            if !conflicts.is_empty() {
+                this.update(&mut cx, |_, cx| {
+                    cx.emit(Event::OfflineEditConflicts(conflicts))
+                })?;
+            }
+            Ok(())
+        })
+    }
+
     pub fn worktree_metadata_protos(&self, cx: &App) -> Vec<proto::WorktreeMetadata> {
         self.worktree_store.read(cx).worktree_metadata_protos(cx)
     }
@@ -4311,19 +4645,6 @@ impl Project {
     }
 }
 
-fn deserialize_code_actions(code_actions: &HashMap<String, bool>) -> Vec<lsp::CodeActionKind> {
-    code_actions
-        .iter()
-        .flat_map(|(kind, enabled)| {
-            if *enabled {
-                Some(kind.clone().into())
-            } else {
-                None
-            }
-        })
-        .collect()
-}
-
 pub struct PathMatchCandidateSet {
     pub snapshot: Snapshot,
     pub include_ignored: bool,
@@ -59,6 +59,10 @@ pub struct ProjectSettings {
     /// Configuration for session-related features
     #[serde(default)]
     pub session: SessionSettings,
+
+    /// Configuration for persisting undo history across restarts
+    #[serde(default)]
+    pub persistent_undo: PersistentUndoSettings,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
@@ -273,6 +277,35 @@ impl Default for SessionSettings {
     }
 }
 
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct PersistentUndoSettings {
+    /// Whether to save the undo history of local files when they're saved or
+    /// closed, and restore it when an unchanged file is reopened.
+    ///
+    /// Default: false
+    pub enabled: bool,
+    /// The number of days after which a file's saved undo history is deleted.
+    ///
+    /// Default: 30
+    pub max_age_days: u64,
+    /// The total size in megabytes of saved undo histories, beyond which the
+    /// least recently saved histories are deleted.
+    ///
+    /// Default: 100
+    pub max_size_mb: u64,
+}
+
+impl Default for PersistentUndoSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_age_days: 30,
+            max_size_mb: 100,
+        }
+    }
+}
+
 impl Settings for ProjectSettings {
     const KEY: Option<&'static str> = None;
 
@@ -5,7 +5,11 @@ use futures::{future, StreamExt};
 use gpui::{App, SemanticVersion, UpdateGlobal};
 use http_client::Url;
 use language::{
-    language_settings::{language_settings, AllLanguageSettings, LanguageSettingsContent},
+    language_settings::{
+        language_settings, AllLanguageSettings, CodeActionOnFormat, CodeActionTimeoutBehavior,
+        CodeActionsOnFormat, Formatter, FormatterFailureBehavior, FormatterList,
+        LanguageSettingsContent, SelectedFormatter,
+    },
     tree_sitter_rust, tree_sitter_typescript, Diagnostic, DiagnosticEntry, DiagnosticSet,
     DiskState, FakeLspAdapter, LanguageConfig, LanguageMatcher, LanguageName, LineEnding,
     OffsetRangeExt, Point, ToPoint,
@@ -196,6 +200,53 @@ async fn test_editorconfig_support(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_task_provider_worktree_reads(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        path!("/dir"),
+        json!({
+            "project": {
+                "Makefile": "all:\n\techo all\n",
+            },
+            "secret.txt": "secret",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), [path!("/dir/project").as_ref()], cx).await;
+    cx.executor().run_until_parked();
+    let worktree = project.read_with(cx, |project, cx| project.worktrees(cx).next().unwrap());
+    let task_worktree = worktree.read_with(cx, |worktree, _| {
+        TaskProviderWorktree::new(
+            worktree.snapshot(),
+            worktree.abs_path(),
+            HashMap::default(),
+            fs.clone(),
+        )
+    });
+
+    assert_eq!(
+        task_worktree
+            .read_text_file(Path::new("Makefile"))
+            .await
+            .unwrap(),
+        "all:\n\techo all\n"
+    );
+    for path in [
+        Path::new("missing"),
+        Path::new("../secret.txt"),
+        Path::new(path!("/dir/secret.txt")),
+    ] {
+        assert!(
+            task_worktree.read_text_file(path).await.is_err(),
+            "{path:?} should not be readable"
+        );
+    }
+}
+
 #[gpui::test]
 async fn test_managing_project_specific_settings(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -2277,6 +2328,554 @@ async fn test_diagnostics_from_multiple_language_servers(cx: &mut gpui::TestAppC
     });
 }
 
+#[gpui::test]
+async fn test_diagnostics_from_linters(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    struct TodoLinter;
+
+    #[async_trait::async_trait]
+    impl language::LinterProvider for TodoLinter {
+        fn name(&self) -> SharedString {
+            "todo-linter".into()
+        }
+
+        async fn lint(
+            &self,
+            request: language::LintRequest,
+            _: Arc<dyn language::LspAdapterDelegate>,
+        ) -> Result<Vec<language::LintDiagnostic>> {
+            Ok(request
+                .text
+                .match_indices("TODO")
+                .map(|(offset, todo)| language::LintDiagnostic {
+                    range: offset..offset + todo.len(),
+                    severity: DiagnosticSeverity::WARNING,
+                    message: "unresolved TODO".to_string(),
+                    code: Some("todo".to_string()),
+                })
+                .collect())
+        }
+    }
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(path!("/dir"), json!({ "a.rs": "// TODO: one\n" }))
+        .await;
+
+    let project = Project::test(fs, [path!("/dir").as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    language_registry.register_linter("Rust".into(), Arc::new(TodoLinter));
+
+    let (buffer, _handle) = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer_with_lsp(path!("/dir/a.rs"), cx)
+        })
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+
+    let diagnostics = |cx: &mut gpui::TestAppContext| {
+        buffer.update(cx, |buffer, _| {
+            buffer
+                .snapshot()
+                .diagnostics_in_range::<_, Point>(0..buffer.len(), false)
+                .map(|entry| (entry.range, entry.diagnostic.message))
+                .collect::<Vec<_>>()
+        })
+    };
+    assert_eq!(
+        diagnostics(cx),
+        [(
+            Point::new(0, 3)..Point::new(0, 7),
+            "unresolved TODO".to_string()
+        )]
+    );
+
+    // Linting again on save replaces the previous diagnostics.
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit([(0..0, "// TODO: two\n")], None, cx)
+    });
+    project
+        .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    assert_eq!(
+        diagnostics(cx),
+        [
+            (
+                Point::new(0, 3)..Point::new(0, 7),
+                "unresolved TODO".to_string()
+            ),
+            (
+                Point::new(1, 3)..Point::new(1, 7),
+                "unresolved TODO".to_string()
+            )
+        ]
+    );
+
+    // Removing the linter clears the diagnostics it reported.
+    language_registry.remove_linter(&"Rust".into(), "todo-linter");
+    cx.executor().run_until_parked();
+    assert!(diagnostics(cx).is_empty());
+    project.update(cx, |project, cx| {
+        assert_eq!(
+            project.diagnostic_summary(false, cx),
+            DiagnosticSummary::default()
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_formatting_via_formatter_provider(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    struct UppercaseFormatter;
+
+    #[async_trait::async_trait]
+    impl language::FormatterProvider for UppercaseFormatter {
+        fn name(&self) -> SharedString {
+            "uppercase".into()
+        }
+
+        async fn format(
+            &self,
+            request: language::FormatRequest,
+            _: Arc<dyn language::LspAdapterDelegate>,
+        ) -> Result<language::FormatResponse> {
+            assert_eq!(request.path, Some(PathBuf::from("a.rs")));
+            Ok(language::FormatResponse::Text(request.text.to_uppercase()))
+        }
+    }
+
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |settings, cx| {
+            settings.update_user_settings::<AllLanguageSettings>(cx, |settings| {
+                settings.defaults.formatter = Some(SelectedFormatter::List(FormatterList(
+                    Formatter::Extension("uppercase".into()).into(),
+                )));
+            });
+        })
+    });
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(path!("/dir"), json!({ "a.rs": "fn one() {}\n" }))
+        .await;
+
+    let project = Project::test(fs, [path!("/dir").as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    language_registry.register_formatter("Rust".into(), Arc::new(UppercaseFormatter));
+
+    let (buffer, _handle) = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer_with_lsp(path!("/dir/a.rs"), cx)
+        })
+        .await
+        .unwrap();
+    project
+        .update(cx, |project, cx| {
+            project.format(
+                HashSet::from_iter([buffer.clone()]),
+                LspFormatTarget::Buffers,
+                true,
+                lsp_store::FormatTrigger::Manual,
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        buffer.read_with(cx, |buffer, _| buffer.text()),
+        "FN ONE() {}\n"
+    );
+}
+
+#[cfg(not(windows))]
+#[gpui::test]
+async fn test_formatting_via_chained_external_commands(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    // Each command reads the output of the previous one.
+    let (result, text) = format_via_external_commands(
+        "let one = 1;\n",
+        vec![
+            external_formatter(
+                "awk",
+                &["{sub(/one/,\"two\")}1"],
+                FormatterFailureBehavior::Abort,
+            ),
+            external_formatter(
+                "awk",
+                &["{sub(/two/,\"three\")}1"],
+                FormatterFailureBehavior::Abort,
+            ),
+        ],
+        None,
+        cx,
+    )
+    .await;
+    result.unwrap();
+    assert_eq!(text, "let three = 1;\n");
+}
+
+#[cfg(not(windows))]
+#[gpui::test]
+async fn test_external_formatter_failures(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    // A failing command that's skipped leaves the text for the next command.
+    let (result, text) = format_via_external_commands(
+        "let one = 1;\n",
+        vec![
+            external_formatter("false", &[], FormatterFailureBehavior::Skip),
+            external_formatter(
+                "awk",
+                &["{sub(/one/,\"two\")}1"],
+                FormatterFailureBehavior::Abort,
+            ),
+        ],
+        None,
+        cx,
+    )
+    .await;
+    result.unwrap();
+    assert_eq!(text, "let two = 1;\n");
+
+    // A failing command that aborts formatting discards the output of the previous commands.
+    let (result, text) = format_via_external_commands(
+        "let one = 1;\n",
+        vec![
+            external_formatter(
+                "awk",
+                &["{sub(/one/,\"two\")}1"],
+                FormatterFailureBehavior::Abort,
+            ),
+            external_formatter("false", &[], FormatterFailureBehavior::Abort),
+        ],
+        None,
+        cx,
+    )
+    .await;
+    let error = format!("{:?}", result.unwrap_err());
+    assert!(
+        error.contains("failed to format via external command \"false\""),
+        "unexpected error: {error}"
+    );
+    assert_eq!(text, "let one = 1;\n");
+}
+
+#[cfg(not(windows))]
+#[gpui::test]
+async fn test_external_formatter_range_arguments(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let text = "fn one() {}\nfn two() {}\nfn three() {}\n";
+    let range_formatter = Formatter::External {
+        command: "echo".into(),
+        arguments: None,
+        range_arguments: Some(
+            vec![
+                "{range_start_line}-{range_end_line}".to_string(),
+                "{range_start_offset}-{range_end_offset}".to_string(),
+            ]
+            .into(),
+        ),
+        on_failure: FormatterFailureBehavior::Abort,
+    };
+    let (result, formatted) = format_via_external_commands(
+        text,
+        vec![
+            range_formatter.clone(),
+            // Commands without range arguments are skipped when formatting ranges.
+            external_formatter("false", &[], FormatterFailureBehavior::Abort),
+        ],
+        Some(Point::new(1, 0)..Point::new(2, 5)),
+        cx,
+    )
+    .await;
+    result.unwrap();
+    assert_eq!(formatted, "2-3 12-29\n");
+
+    // The regular arguments are used when formatting whole buffers.
+    let (result, formatted) =
+        format_via_external_commands(text, vec![range_formatter], None, cx).await;
+    result.unwrap();
+    assert_eq!(formatted, "\n");
+}
+
+fn external_formatter(
+    command: &str,
+    arguments: &[&str],
+    on_failure: FormatterFailureBehavior,
+) -> Formatter {
+    Formatter::External {
+        command: command.into(),
+        arguments: Some(
+            arguments
+                .iter()
+                .map(|argument| argument.to_string())
+                .collect(),
+        ),
+        range_arguments: None,
+        on_failure,
+    }
+}
+
+/// Formats a buffer with the given text using the given external commands, returning the
+/// result of formatting and the buffer's text afterwards.
+async fn format_via_external_commands(
+    text: &str,
+    formatters: Vec<Formatter>,
+    range: Option<Range<Point>>,
+    cx: &mut gpui::TestAppContext,
+) -> (Result<()>, String) {
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |settings, cx| {
+            settings.update_user_settings::<AllLanguageSettings>(cx, |settings| {
+                settings.defaults.formatter =
+                    Some(SelectedFormatter::List(FormatterList(formatters.into())));
+            });
+        })
+    });
+
+    // The commands are run in the worktree's directory, so it has to exist on disk.
+    let dir = TempTree::new(json!({}));
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(dir.path(), json!({ "a.rs": text })).await;
+
+    let project = Project::test(fs, [dir.path()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let (buffer, _handle) = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer_with_lsp(dir.path().join("a.rs"), cx)
+        })
+        .await
+        .unwrap();
+
+    let target = match range {
+        Some(range) => buffer.read_with(cx, |buffer, _| {
+            let range = buffer.anchor_before(range.start)..buffer.anchor_after(range.end);
+            LspFormatTarget::Ranges(collections::BTreeMap::from_iter([(
+                buffer.remote_id(),
+                vec![range],
+            )]))
+        }),
+        None => LspFormatTarget::Buffers,
+    };
+    cx.executor().allow_parking();
+    let result = project
+        .update(cx, |project, cx| {
+            project.format(
+                HashSet::from_iter([buffer.clone()]),
+                target,
+                true,
+                lsp_store::FormatTrigger::Manual,
+                cx,
+            )
+        })
+        .await;
+    cx.executor().forbid_parking();
+    let text = buffer.read_with(cx, |buffer, _| buffer.text());
+    (result.map(|_| ()), text)
+}
+
+#[gpui::test]
+async fn test_code_actions_on_format_run_in_order(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |settings, cx| {
+            settings.update_user_settings::<AllLanguageSettings>(cx, |settings| {
+                settings.defaults.code_actions_on_format = Some(CodeActionsOnFormat::List(
+                    ["source.second", "source.first"]
+                        .into_iter()
+                        .map(|kind| CodeActionOnFormat {
+                            kind: kind.to_string(),
+                            language_server: None,
+                            timeout_ms: None,
+                            on_timeout: CodeActionTimeoutBehavior::Skip,
+                        })
+                        .collect(),
+                ));
+            });
+        })
+    });
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(path!("/dir"), json!({ "a.rs": "fn one() {}\n" }))
+        .await;
+
+    let project = Project::test(fs, [path!("/dir").as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                code_action_provider: Some(lsp::CodeActionProviderCapability::Simple(true)),
+                ..lsp::ServerCapabilities::default()
+            },
+            ..FakeLspAdapter::default()
+        },
+    );
+
+    let (buffer, _handle) = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer_with_lsp(path!("/dir/a.rs"), cx)
+        })
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+
+    // Each code action inserts a comment naming its kind at the start of the buffer.
+    let requested_kinds = Arc::new(Mutex::new(Vec::new()));
+    fake_server.handle_request::<lsp::request::CodeActionRequest, _, _>({
+        let requested_kinds = requested_kinds.clone();
+        move |params, _| {
+            let kind = params.context.only.unwrap()[0].as_str().to_string();
+            requested_kinds.lock().push(kind.clone());
+            async move {
+                Ok(Some(vec![lsp::CodeActionOrCommand::CodeAction(
+                    lsp::CodeAction {
+                        title: kind.clone(),
+                        kind: Some(kind.clone().into()),
+                        edit: Some(lsp::WorkspaceEdit {
+                            changes: Some(
+                                [(
+                                    params.text_document.uri,
+                                    vec![lsp::TextEdit {
+                                        range: lsp::Range::new(
+                                            lsp::Position::new(0, 0),
+                                            lsp::Position::new(0, 0),
+                                        ),
+                                        new_text: format!("// {kind}\n"),
+                                    }],
+                                )]
+                                .into_iter()
+                                .collect(),
+                            ),
+                            ..lsp::WorkspaceEdit::default()
+                        }),
+                        ..lsp::CodeAction::default()
+                    },
+                )]))
+            }
+        }
+    });
+
+    project
+        .update(cx, |project, cx| {
+            project.format(
+                HashSet::from_iter([buffer.clone()]),
+                LspFormatTarget::Buffers,
+                true,
+                lsp_store::FormatTrigger::Manual,
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        *requested_kinds.lock(),
+        ["source.second".to_string(), "source.first".to_string()]
+    );
+    assert_eq!(
+        buffer.read_with(cx, |buffer, _| buffer.text()),
+        "// source.first\n// source.second\nfn one() {}\n"
+    );
+}
+
+#[gpui::test]
+async fn test_code_actions_on_format_timeout(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(path!("/dir"), json!({ "a.rs": "fn one() {}\n" }))
+        .await;
+
+    let project = Project::test(fs, [path!("/dir").as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                code_action_provider: Some(lsp::CodeActionProviderCapability::Simple(true)),
+                ..lsp::ServerCapabilities::default()
+            },
+            ..FakeLspAdapter::default()
+        },
+    );
+
+    let (buffer, _handle) = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer_with_lsp(path!("/dir/a.rs"), cx)
+        })
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+
+    // The code action is provided right away, but applying it runs a command that never
+    // finishes, so the timeout has to cover applying the code action too.
+    fake_server.handle_request::<lsp::request::CodeActionRequest, _, _>(|_, _| async move {
+        Ok(Some(vec![lsp::CodeActionOrCommand::CodeAction(
+            lsp::CodeAction {
+                title: "Fix all".into(),
+                command: Some(lsp::Command {
+                    title: "Fix all".into(),
+                    command: "_the/fix-all".into(),
+                    arguments: None,
+                }),
+                ..lsp::CodeAction::default()
+            },
+        )]))
+    });
+    fake_server.handle_request::<lsp::request::ExecuteCommand, _, _>(|_, _| {
+        future::pending::<Result<Option<serde_json::Value>>>()
+    });
+
+    for (on_timeout, should_fail) in [
+        (CodeActionTimeoutBehavior::Skip, false),
+        (CodeActionTimeoutBehavior::Fail, true),
+    ] {
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |settings, cx| {
+                settings.update_user_settings::<AllLanguageSettings>(cx, |settings| {
+                    settings.defaults.code_actions_on_format =
+                        Some(CodeActionsOnFormat::List(vec![CodeActionOnFormat {
+                            kind: "source.fixAll".into(),
+                            language_server: None,
+                            timeout_ms: Some(500),
+                            on_timeout,
+                        }]));
+                });
+            })
+        });
+
+        let format = project.update(cx, |project, cx| {
+            project.format(
+                HashSet::from_iter([buffer.clone()]),
+                LspFormatTarget::Buffers,
+                true,
+                lsp_store::FormatTrigger::Manual,
+                cx,
+            )
+        });
+        cx.executor().run_until_parked();
+        cx.executor().advance_clock(Duration::from_millis(500));
+        let result = format.await;
+        if should_fail {
+            let error = result.unwrap_err().to_string();
+            assert!(error.contains("timed out"), "unexpected error: {error}");
+        } else {
+            result.unwrap();
+        }
+    }
+}
+
 #[gpui::test]
 async fn test_edits_from_lsp2_with_past_version(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -3048,51 +3647,221 @@ async fn test_apply_code_actions_with_commands(cx: &mut gpui::TestAppContext) {
         .next()
         .await;
 
-    // Applying the code action returns a project transaction containing the edits
-    // sent by the language server in its `workspaceEdit` request.
-    let transaction = apply.await.unwrap();
-    assert!(transaction.0.contains_key(&buffer));
+    // Applying the code action returns a project transaction containing the edits
+    // sent by the language server in its `workspaceEdit` request.
+    let transaction = apply.await.unwrap();
+    assert!(transaction.0.contains_key(&buffer));
+    buffer.update(cx, |buffer, cx| {
+        assert_eq!(buffer.text(), "Xa");
+        buffer.undo(cx);
+        assert_eq!(buffer.text(), "a");
+    });
+}
+
+#[gpui::test(iterations = 10)]
+async fn test_save_file(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        path!("/dir"),
+        json!({
+            "file1": "the old contents",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), [path!("/dir").as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer(path!("/dir/file1"), cx))
+        .await
+        .unwrap();
+    buffer.update(cx, |buffer, cx| {
+        assert_eq!(buffer.text(), "the old contents");
+        buffer.edit([(0..0, "a line of text.\n".repeat(10 * 1024))], None, cx);
+    });
+
+    project
+        .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+        .await
+        .unwrap();
+
+    let new_text = fs
+        .load(Path::new(path!("/dir/file1")))
+        .await
+        .unwrap()
+        .replace("\r\n", "\n");
+    assert_eq!(new_text, buffer.update(cx, |buffer, _| buffer.text()));
+}
+
+#[gpui::test]
+async fn test_persistent_undo(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |settings, cx| {
+            settings.update_user_settings::<project_settings::ProjectSettings>(cx, |settings| {
+                settings.persistent_undo.enabled = true;
+            });
+        })
+    });
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(path!("/persistent-undo"), json!({ "file": "one" }))
+        .await;
+
+    let project = Project::test(fs.clone(), [path!("/persistent-undo").as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |p, cx| {
+            p.open_local_buffer(path!("/persistent-undo/file"), cx)
+        })
+        .await
+        .unwrap();
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit([(3..3, " two")], None, cx);
+        buffer.edit([(7..7, " three")], None, cx);
+        buffer.edit([(13..13, " four")], None, cx);
+        buffer.undo(cx);
+    });
+    project
+        .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+        .await
+        .unwrap();
+    drop(buffer);
+    drop(project);
+    cx.run_until_parked();
+
+    // Reopening the unchanged file restores its undo and redo history.
+    let project = Project::test(fs.clone(), [path!("/persistent-undo").as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |p, cx| {
+            p.open_local_buffer(path!("/persistent-undo/file"), cx)
+        })
+        .await
+        .unwrap();
+    buffer.update(cx, |buffer, cx| {
+        assert_eq!(buffer.text(), "one two three");
+        assert!(!buffer.is_dirty());
+        buffer.redo(cx);
+        assert_eq!(buffer.text(), "one two three four");
+        buffer.undo(cx);
+        buffer.undo(cx);
+        assert_eq!(buffer.text(), "one two");
+        buffer.undo(cx);
+        assert_eq!(buffer.text(), "one");
+        assert!(buffer.undo(cx).is_none());
+    });
+    drop(buffer);
+    drop(project);
+    cx.run_until_parked();
+
+    // Once the file has changed on disk, its saved history is no longer used.
+    fs.insert_file(path!("/persistent-undo/file"), b"one two three!".to_vec())
+        .await;
+    let project = Project::test(fs.clone(), [path!("/persistent-undo").as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |p, cx| {
+            p.open_local_buffer(path!("/persistent-undo/file"), cx)
+        })
+        .await
+        .unwrap();
+    buffer.update(cx, |buffer, cx| {
+        assert_eq!(buffer.text(), "one two three!");
+        assert!(buffer.undo(cx).is_none());
+        assert!(buffer.redo(cx).is_none());
+    });
+    drop(buffer);
+    drop(project);
+    cx.run_until_parked();
+
+    // The history of private files isn't saved.
+    fs.insert_file(path!("/persistent-undo/.env"), b"KEY=".to_vec())
+        .await;
+    let project = Project::test(fs.clone(), [path!("/persistent-undo").as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |p, cx| {
+            p.open_local_buffer(path!("/persistent-undo/.env"), cx)
+        })
+        .await
+        .unwrap();
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit([(4..4, "secret")], None, cx);
+    });
+    project
+        .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+        .await
+        .unwrap();
+    drop(buffer);
+    drop(project);
+    cx.run_until_parked();
+
+    let project = Project::test(fs.clone(), [path!("/persistent-undo").as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |p, cx| {
+            p.open_local_buffer(path!("/persistent-undo/.env"), cx)
+        })
+        .await
+        .unwrap();
     buffer.update(cx, |buffer, cx| {
-        assert_eq!(buffer.text(), "Xa");
-        buffer.undo(cx);
-        assert_eq!(buffer.text(), "a");
+        assert_eq!(buffer.text(), "KEY=secret");
+        assert!(buffer.undo(cx).is_none());
     });
 }
 
-#[gpui::test(iterations = 10)]
-async fn test_save_file(cx: &mut gpui::TestAppContext) {
+#[gpui::test]
+async fn test_open_binary_file(cx: &mut gpui::TestAppContext) {
     init_test(cx);
 
     let fs = FakeFs::new(cx.executor());
-    fs.insert_tree(
-        path!("/dir"),
-        json!({
-            "file1": "the old contents",
-        }),
+    fs.insert_tree(path!("/dir"), json!({ "text": "hello" }))
+        .await;
+    fs.insert_file(
+        path!("/dir/binary"),
+        vec![0x7f, b'E', b'L', b'F', 0xff, 0x00],
     )
     .await;
+    // Contents that aren't valid UTF-8 but have no NUL bytes are decoded as text.
+    fs.insert_file(path!("/dir/latin1"), b"caf\xe9 cr\xe8me\n".to_vec())
+        .await;
 
     let project = Project::test(fs.clone(), [path!("/dir").as_ref()], cx).await;
-    let buffer = project
-        .update(cx, |p, cx| p.open_local_buffer(path!("/dir/file1"), cx))
-        .await
-        .unwrap();
-    buffer.update(cx, |buffer, cx| {
-        assert_eq!(buffer.text(), "the old contents");
-        buffer.edit([(0..0, "a line of text.\n".repeat(10 * 1024))], None, cx);
+    let worktree_id = project.update(cx, |project, cx| {
+        project.worktrees(cx).next().unwrap().read(cx).id()
     });
+    let binary_path = ProjectPath {
+        worktree_id,
+        path: Path::new("binary").into(),
+    };
+    let text_path = ProjectPath {
+        worktree_id,
+        path: Path::new("text").into(),
+    };
+    let latin1_path = ProjectPath {
+        worktree_id,
+        path: Path::new("latin1").into(),
+    };
 
     project
-        .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+        .update(cx, |project, cx| {
+            project.open_buffer(binary_path.clone(), cx)
+        })
+        .await
+        .unwrap_err();
+    project
+        .update(cx, |project, cx| project.open_buffer(text_path.clone(), cx))
         .await
         .unwrap();
-
-    let new_text = fs
-        .load(Path::new(path!("/dir/file1")))
+    let latin1_buffer = project
+        .update(cx, |project, cx| {
+            project.open_buffer(latin1_path.clone(), cx)
+        })
         .await
-        .unwrap()
-        .replace("\r\n", "\n");
-    assert_eq!(new_text, buffer.update(cx, |buffer, _| buffer.text()));
+        .unwrap();
+    latin1_buffer.read_with(cx, |buffer, _| assert_eq!(buffer.text(), "café crème\n"));
+    project.read_with(cx, |project, cx| {
+        assert!(project.is_binary_file(&binary_path, cx));
+        assert!(!project.is_binary_file(&text_path, cx));
+        assert!(!project.is_binary_file(&latin1_path, cx));
+    });
 }
 
 #[gpui::test(iterations = 30)]
@@ -4373,6 +5142,145 @@ async fn test_rename(cx: &mut gpui::TestAppContext) {
     );
 }
 
+#[gpui::test]
+async fn test_rename_edits(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        path!("/dir"),
+        json!({
+            "one.rs": "const ONE: usize = 1;",
+            "two.rs": "const TWO: usize = one::ONE + one::ONE;"
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), [path!("/dir").as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                rename_provider: Some(lsp::OneOf::Left(true)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let (buffer, _handle) = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer_with_lsp(path!("/dir/one.rs"), cx)
+        })
+        .await
+        .unwrap();
+
+    let fake_server = fake_servers.next().await.unwrap();
+
+    let response = project.update(cx, |project, cx| {
+        project.rename_edits(buffer.clone(), 7, "THREE".to_string(), cx)
+    });
+    fake_server
+        .handle_request::<lsp::request::Rename, _, _>(|params, _| async move {
+            assert_eq!(params.new_name, "THREE");
+            Ok(Some(lsp::WorkspaceEdit {
+                document_changes: Some(lsp::DocumentChanges::Edits(vec![
+                    lsp::TextDocumentEdit {
+                        text_document: lsp::OptionalVersionedTextDocumentIdentifier {
+                            uri: lsp::Url::from_file_path(path!("/dir/one.rs")).unwrap(),
+                            version: None,
+                        },
+                        edits: vec![lsp::Edit::Plain(lsp::TextEdit::new(
+                            lsp::Range::new(lsp::Position::new(0, 6), lsp::Position::new(0, 9)),
+                            "THREE".to_string(),
+                        ))],
+                    },
+                    lsp::TextDocumentEdit {
+                        text_document: lsp::OptionalVersionedTextDocumentIdentifier {
+                            uri: lsp::Url::from_file_path(path!("/dir/two.rs")).unwrap(),
+                            version: None,
+                        },
+                        edits: vec![
+                            lsp::Edit::Plain(lsp::TextEdit::new(
+                                lsp::Range::new(
+                                    lsp::Position::new(0, 24),
+                                    lsp::Position::new(0, 27),
+                                ),
+                                "THREE".to_string(),
+                            )),
+                            lsp::Edit::Annotated(lsp::AnnotatedTextEdit {
+                                text_edit: lsp::TextEdit::new(
+                                    lsp::Range::new(
+                                        lsp::Position::new(0, 35),
+                                        lsp::Position::new(0, 38),
+                                    ),
+                                    "THREE".to_string(),
+                                ),
+                                annotation_id: "in-expression".to_string(),
+                            }),
+                        ],
+                    },
+                ])),
+                change_annotations: Some(
+                    [(
+                        "in-expression".to_string(),
+                        lsp::ChangeAnnotation {
+                            label: "Rename inside expression".to_string(),
+                            needs_confirmation: Some(true),
+                            description: None,
+                        },
+                    )]
+                    .into_iter()
+                    .collect(),
+                ),
+                ..Default::default()
+            }))
+        })
+        .next()
+        .await
+        .unwrap();
+    let rename_edits = response.await.unwrap();
+    assert_eq!(rename_edits.len(), 2);
+
+    // Nothing is applied until the edits are confirmed.
+    for buffer_edits in &rename_edits {
+        assert!(!buffer_edits
+            .buffer
+            .read_with(cx, |buffer, _| buffer.is_dirty()));
+    }
+
+    let (one_edits, two_edits) = (&rename_edits[0], &rename_edits[1]);
+    assert_eq!(one_edits.buffer, buffer);
+    one_edits.buffer.read_with(cx, |buffer, _| {
+        assert_eq!(
+            one_edits
+                .edits
+                .iter()
+                .map(|edit| (edit.range.to_offset(buffer), edit.new_text.as_str()))
+                .collect::<Vec<_>>(),
+            [(6..9, "THREE")]
+        );
+    });
+    two_edits.buffer.read_with(cx, |buffer, _| {
+        assert_eq!(
+            two_edits
+                .edits
+                .iter()
+                .map(|edit| (
+                    edit.range.to_offset(buffer),
+                    edit.annotation
+                        .as_ref()
+                        .map(|annotation| annotation.needs_confirmation)
+                ))
+                .collect::<Vec<_>>(),
+            [(24..27, None), (35..38, Some(true))]
+        );
+    });
+}
+
 #[gpui::test]
 async fn test_search(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -5295,6 +6203,197 @@ async fn test_hovers_with_empty_parts(cx: &mut gpui::TestAppContext) {
     );
 }
 
+#[gpui::test]
+async fn test_document_colors_and_presentations(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        path!("/dir"),
+        json!({
+            "a.ts": "const red = \"#ff0000\";",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, [path!("/dir").as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(typescript_lang());
+    let mut fake_language_servers = language_registry.register_fake_lsp(
+        "TypeScript",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                color_provider: Some(lsp::ColorProviderCapability::Simple(true)),
+                ..lsp::ServerCapabilities::default()
+            },
+            ..FakeLspAdapter::default()
+        },
+    );
+
+    let (buffer, _handle) = project
+        .update(cx, |p, cx| {
+            p.open_local_buffer_with_lsp(path!("/dir/a.ts"), cx)
+        })
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+
+    let fake_server = fake_language_servers
+        .next()
+        .await
+        .expect("failed to get the language server");
+
+    let color_range = lsp::Range::new(lsp::Position::new(0, 13), lsp::Position::new(0, 20));
+    fake_server.handle_request::<lsp::request::DocumentColor, _, _>(move |_, _| async move {
+        Ok(vec![lsp::ColorInformation {
+            range: color_range,
+            color: lsp::Color {
+                red: 1.0,
+                green: 0.0,
+                blue: 0.0,
+                alpha: 1.0,
+            },
+        }])
+    });
+    fake_server.handle_request::<lsp::request::ColorPresentationRequest, _, _>(
+        move |params, _| async move {
+            assert_eq!(params.range, color_range);
+            assert_eq!(params.color.blue, 1.0);
+            Ok(vec![lsp::ColorPresentation {
+                label: "#0000ff".to_string(),
+                text_edit: Some(lsp::TextEdit::new(color_range, "#0000ff".to_string())),
+                additional_text_edits: None,
+            }])
+        },
+    );
+
+    let colors = project
+        .update(cx, |project, cx| project.document_colors(&buffer, cx))
+        .await
+        .unwrap();
+    assert_eq!(colors.len(), 1);
+    buffer.read_with(cx, |buffer, _| {
+        assert_eq!(
+            colors[0].range.to_offset(buffer),
+            13..20,
+            "color range should be converted from the LSP response"
+        );
+    });
+
+    let presentations = project
+        .update(cx, |project, cx| {
+            project.color_presentations(
+                &buffer,
+                &colors[0],
+                lsp::Color {
+                    red: 0.0,
+                    green: 0.0,
+                    blue: 1.0,
+                    alpha: 1.0,
+                },
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+    assert_eq!(presentations.len(), 1);
+    assert_eq!(presentations[0].label, "#0000ff");
+    buffer.read_with(cx, |buffer, _| {
+        let (range, new_text) = presentations[0].text_edit.clone().unwrap();
+        assert_eq!(range.to_offset(buffer), 13..20);
+        assert_eq!(new_text, "#0000ff");
+    });
+}
+
+#[gpui::test]
+async fn test_inline_values(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        path!("/dir"),
+        json!({
+            "a.ts": "let total = a + b;\nconsole.log(total);",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, [path!("/dir").as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(typescript_lang());
+    let mut fake_language_servers = language_registry.register_fake_lsp(
+        "TypeScript",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                inline_value_provider: Some(lsp::OneOf::Left(true)),
+                ..lsp::ServerCapabilities::default()
+            },
+            ..FakeLspAdapter::default()
+        },
+    );
+
+    let (buffer, _handle) = project
+        .update(cx, |p, cx| {
+            p.open_local_buffer_with_lsp(path!("/dir/a.ts"), cx)
+        })
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+
+    let fake_server = fake_language_servers
+        .next()
+        .await
+        .expect("failed to get the language server");
+
+    fake_server.handle_request::<lsp::request::InlineValueRequest, _, _>(
+        move |params, _| async move {
+            assert_eq!(
+                params.range,
+                lsp::Range::new(lsp::Position::new(0, 0), lsp::Position::new(1, 19))
+            );
+            Ok(Some(vec![
+                lsp::InlineValue::VariableLookup(lsp::InlineValueVariableLookup {
+                    range: lsp::Range::new(lsp::Position::new(0, 4), lsp::Position::new(0, 9)),
+                    variable_name: None,
+                    case_sensitive_lookup: true,
+                }),
+                lsp::InlineValue::Text(lsp::InlineValueText {
+                    range: lsp::Range::new(lsp::Position::new(1, 12), lsp::Position::new(1, 17)),
+                    text: "3".to_string(),
+                }),
+            ]))
+        },
+    );
+
+    let inline_values = project
+        .update(cx, |project, cx| {
+            let end = buffer.read(cx).len();
+            project.inline_values(&buffer, 0..end, cx)
+        })
+        .await
+        .unwrap();
+    buffer.read_with(cx, |buffer, _| {
+        assert_eq!(
+            inline_values
+                .iter()
+                .map(|value| (value.range.to_offset(buffer), value.kind.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    4..9,
+                    InlineValueKind::VariableLookup {
+                        variable_name: None,
+                        case_sensitive: true,
+                    }
+                ),
+                (31..36, InlineValueKind::Text("3".to_string())),
+            ]
+        );
+    });
+}
+
 #[gpui::test]
 async fn test_code_actions_only_kinds(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -28,6 +28,7 @@ use crate::worktree_store::WorktreeStore;
 pub struct Inventory {
     last_scheduled_tasks: VecDeque<(TaskSourceKind, ResolvedTask)>,
     templates_from_settings: ParsedTemplates,
+    templates_from_providers: HashMap<WorktreeId, Vec<(SharedString, TaskTemplate)>>,
 }
 
 #[derive(Debug, Default)]
@@ -54,6 +55,11 @@ pub enum TaskSourceKind {
     },
     /// Languages-specific tasks coming from extensions.
     Language { name: SharedString },
+    /// Tasks computed for a worktree by a [`crate::TaskProvider`], such as an extension.
+    Provider {
+        worktree: WorktreeId,
+        name: SharedString,
+    },
 }
 
 /// A collection of task contexts, derived from the current state of the workspace.
@@ -115,6 +121,7 @@ impl TaskSourceKind {
                 format!("{id_base}_{id}_{}", directory_in_worktree.display())
             }
             TaskSourceKind::Language { name } => format!("language_{name}"),
+            TaskSourceKind::Provider { worktree, name } => format!("provider_{name}_{worktree}"),
         }
     }
 }
@@ -146,6 +153,7 @@ impl Inventory {
             .chain(global_tasks);
 
         self.worktree_templates_from_settings(worktree)
+            .chain(self.worktree_templates_from_providers(worktree))
             .chain(language_tasks)
             .collect()
     }
@@ -217,13 +225,16 @@ impl Inventory {
             .flat_map(|task| Some((task_source_kind.clone()?, task)));
         let worktree_tasks = self
             .worktree_templates_from_settings(worktree)
+            .chain(self.worktree_templates_from_providers(worktree))
             .chain(language_tasks)
             .chain(global_tasks);
 
         let new_resolved_tasks = worktree_tasks
             .flat_map(|(kind, task)| {
                 let id_base = kind.to_id_base();
-                if let TaskSourceKind::Worktree { id, .. } = &kind {
+                if let TaskSourceKind::Worktree { id, .. }
+                | TaskSourceKind::Provider { worktree: id, .. } = &kind
+                {
                     None.or_else(|| {
                         let (_, _, item_context) = task_contexts
                             .active_item_context
@@ -239,16 +250,12 @@ impl Inventory {
                         task.resolve_task(&id_base, worktree_context)
                     })
                     .or_else(|| {
-                        if let TaskSourceKind::Worktree { id, .. } = &kind {
-                            let worktree_context = task_contexts
-                                .other_worktree_contexts
-                                .iter()
-                                .find(|(worktree_id, _)| worktree_id == id)
-                                .map(|(_, context)| context)?;
-                            task.resolve_task(&id_base, worktree_context)
-                        } else {
-                            None
-                        }
+                        let worktree_context = task_contexts
+                            .other_worktree_contexts
+                            .iter()
+                            .find(|(worktree_id, _)| worktree_id == id)
+                            .map(|(_, context)| context)?;
+                        task.resolve_task(&id_base, worktree_context)
                     })
                 } else {
                     None.or_else(|| {
@@ -364,6 +371,40 @@ impl Inventory {
         })
     }
 
+    fn worktree_templates_from_providers(
+        &self,
+        worktree: Option<WorktreeId>,
+    ) -> impl '_ + Iterator<Item = (TaskSourceKind, TaskTemplate)> {
+        worktree.into_iter().flat_map(|worktree| {
+            self.templates_from_providers
+                .get(&worktree)
+                .into_iter()
+                .flatten()
+                .map(move |(name, template)| {
+                    (
+                        TaskSourceKind::Provider {
+                            worktree,
+                            name: name.clone(),
+                        },
+                        template.clone(),
+                    )
+                })
+        })
+    }
+
+    /// Replaces the task templates computed by [`crate::TaskProvider`]s for the given worktree.
+    pub(crate) fn update_provider_tasks(
+        &mut self,
+        worktree: WorktreeId,
+        templates: Vec<(SharedString, TaskTemplate)>,
+    ) {
+        if templates.is_empty() {
+            self.templates_from_providers.remove(&worktree);
+        } else {
+            self.templates_from_providers.insert(worktree, templates);
+        }
+    }
+
     /// Updates in-memory task metadata from the JSON string given.
     /// Will fail if the JSON is not a valid array of objects, but will continue if any object will not parse into a [`TaskTemplate`].
     ///
@@ -432,7 +473,8 @@ fn task_source_kind_preference(kind: &TaskSourceKind) -> u32 {
         TaskSourceKind::Language { .. } => 1,
         TaskSourceKind::UserInput => 2,
         TaskSourceKind::Worktree { .. } => 3,
-        TaskSourceKind::AbsPath { .. } => 4,
+        TaskSourceKind::Provider { .. } => 4,
+        TaskSourceKind::AbsPath { .. } => 5,
     }
 }
 
@@ -927,6 +969,69 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_inventory_provider_tasks(cx: &mut TestAppContext) {
+        init_test(cx);
+        let inventory = cx.update(Inventory::new);
+        let worktree_1 = WorktreeId::from_usize(1);
+        let worktree_2 = WorktreeId::from_usize(2);
+        let provider_name = SharedString::from("make");
+        let provider_task = |label: &str| TaskTemplate {
+            label: label.to_string(),
+            command: "make".to_string(),
+            args: vec![label.to_string()],
+            ..TaskTemplate::default()
+        };
+
+        inventory.update(cx, |inventory, _| {
+            inventory.update_provider_tasks(
+                worktree_1,
+                vec![
+                    (provider_name.clone(), provider_task("build")),
+                    (provider_name.clone(), provider_task("test")),
+                ],
+            );
+        });
+
+        let worktree_1_tasks = vec![
+            (
+                TaskSourceKind::Provider {
+                    worktree: worktree_1,
+                    name: provider_name.clone(),
+                },
+                "build".to_string(),
+            ),
+            (
+                TaskSourceKind::Provider {
+                    worktree: worktree_1,
+                    name: provider_name.clone(),
+                },
+                "test".to_string(),
+            ),
+        ];
+        assert_eq!(
+            list_tasks(&inventory, Some(worktree_1), cx).await,
+            worktree_1_tasks
+        );
+        assert_eq!(
+            list_tasks_sorted_by_last_used(&inventory, Some(worktree_1), cx).await,
+            worktree_1_tasks,
+        );
+        assert!(
+            list_tasks(&inventory, Some(worktree_2), cx)
+                .await
+                .is_empty(),
+            "Provider tasks should only be listed for the worktree they were computed for"
+        );
+
+        inventory.update(cx, |inventory, _| {
+            inventory.update_provider_tasks(worktree_1, Vec::new());
+        });
+        assert!(list_tasks(&inventory, Some(worktree_1), cx)
+            .await
+            .is_empty());
+    }
+
     fn init_test(_cx: &mut TestAppContext) {
         if std::env::var("RUST_LOG").is_ok() {
             env_logger::try_init().ok();
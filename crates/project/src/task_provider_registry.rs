@@ -0,0 +1,131 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context as _, Result};
+use async_trait::async_trait;
+use collections::HashMap;
+use fs::Fs;
+use gpui::{App, AppContext as _, Context, Entity, Global, SharedString};
+use task::{TaskTemplates, TaskVariables};
+use worktree::{Snapshot, WorktreeId};
+
+/// A source of task templates and task variables that are computed on demand
+/// for a worktree, such as an extension that discovers the targets of a `Makefile`.
+#[async_trait]
+pub trait TaskProvider: Send + Sync + 'static {
+    /// The name of the provider, shown next to its tasks.
+    fn name(&self) -> SharedString;
+
+    /// Returns the task templates available in the given worktree.
+    async fn task_templates(&self, worktree: TaskProviderWorktree) -> Result<TaskTemplates>;
+
+    /// Returns the values of the custom task variables defined by the provider,
+    /// given the variables resolved so far.
+    ///
+    /// Only [`task::VariableName::Custom`] variables are taken from the result.
+    async fn task_variables(
+        &self,
+        worktree: TaskProviderWorktree,
+        variables: TaskVariables,
+    ) -> Result<TaskVariables>;
+}
+
+/// The worktree a [`TaskProvider`] is queried for.
+#[derive(Clone)]
+pub struct TaskProviderWorktree {
+    pub id: WorktreeId,
+    pub abs_path: Arc<Path>,
+    pub shell_env: HashMap<String, String>,
+    snapshot: Snapshot,
+    fs: Arc<dyn Fs>,
+}
+
+impl TaskProviderWorktree {
+    pub(crate) fn new(
+        snapshot: Snapshot,
+        abs_path: Arc<Path>,
+        shell_env: HashMap<String, String>,
+        fs: Arc<dyn Fs>,
+    ) -> Self {
+        Self {
+            id: snapshot.id(),
+            abs_path,
+            shell_env,
+            snapshot,
+            fs,
+        }
+    }
+
+    /// Reads the file at the given path, relative to the worktree root.
+    ///
+    /// Only files within the worktree can be read.
+    pub async fn read_text_file(&self, path: &Path) -> Result<String> {
+        let entry = self
+            .snapshot
+            .entry_for_path(path)
+            .with_context(|| format!("no worktree entry for path {path:?}"))?;
+        let abs_path = self
+            .snapshot
+            .absolutize(&entry.path)
+            .with_context(|| format!("cannot absolutize path {path:?}"))?;
+
+        self.fs.load(&abs_path).await
+    }
+
+    /// Looks up the given binary on the worktree's `PATH`.
+    pub fn which(&self, binary_name: &str) -> Option<PathBuf> {
+        let shell_path = self.shell_env.get("PATH");
+        which::which_in(binary_name, shell_path, &self.abs_path).ok()
+    }
+}
+
+struct GlobalTaskProviderRegistry(Entity<TaskProviderRegistry>);
+
+impl Global for GlobalTaskProviderRegistry {}
+
+/// The [`TaskProvider`]s registered in the application, keyed by their IDs.
+#[derive(Default)]
+pub struct TaskProviderRegistry {
+    providers: HashMap<Arc<str>, Arc<dyn TaskProvider>>,
+}
+
+impl TaskProviderRegistry {
+    /// Returns the global [`TaskProviderRegistry`], if one exists.
+    pub fn try_global(cx: &App) -> Option<Entity<Self>> {
+        cx.try_global::<GlobalTaskProviderRegistry>()
+            .map(|registry| registry.0.clone())
+    }
+
+    /// Returns the global [`TaskProviderRegistry`].
+    ///
+    /// Inserts a default [`TaskProviderRegistry`] if one does not yet exist.
+    pub fn default_global(cx: &mut App) -> Entity<Self> {
+        if !cx.has_global::<GlobalTaskProviderRegistry>() {
+            let registry = cx.new(|_| Self::default());
+            cx.set_global(GlobalTaskProviderRegistry(registry));
+        }
+        cx.global::<GlobalTaskProviderRegistry>().0.clone()
+    }
+
+    pub fn providers(&self) -> Vec<Arc<dyn TaskProvider>> {
+        self.providers.values().cloned().collect()
+    }
+
+    /// Registers the provided [`TaskProvider`].
+    pub fn register_provider(
+        &mut self,
+        id: Arc<str>,
+        provider: Arc<dyn TaskProvider>,
+        cx: &mut Context<Self>,
+    ) {
+        self.providers.insert(id, provider);
+        cx.notify();
+    }
+
+    /// Unregisters the [`TaskProvider`] with the given ID.
+    pub fn unregister_provider(&mut self, id: &str, cx: &mut Context<Self>) {
+        if self.providers.remove(id).is_some() {
+            cx.notify();
+        }
+    }
+}
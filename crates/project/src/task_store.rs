@@ -1,10 +1,10 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
 use anyhow::Context as _;
 use collections::HashMap;
 use fs::Fs;
 use futures::StreamExt as _;
-use gpui::{App, AsyncApp, Context, Entity, EventEmitter, Task, WeakEntity};
+use gpui::{App, AsyncApp, Context, Entity, EventEmitter, Subscription, Task, WeakEntity};
 use language::{
     proto::{deserialize_anchor, serialize_anchor},
     ContextProvider as _, LanguageToolchainStore, Location,
@@ -14,12 +14,18 @@ use settings::{watch_config_file, SettingsLocation};
 use task::{TaskContext, TaskVariables, VariableName};
 use text::{BufferId, OffsetRangeExt};
 use util::ResultExt;
+use worktree::{Worktree, WorktreeId};
 
 use crate::{
-    buffer_store::BufferStore, worktree_store::WorktreeStore, BasicContextProvider, Inventory,
-    ProjectEnvironment,
+    buffer_store::BufferStore,
+    worktree_store::{WorktreeStore, WorktreeStoreEvent},
+    BasicContextProvider, Inventory, ProjectEnvironment, TaskProvider, TaskProviderRegistry,
+    TaskProviderWorktree,
 };
 
+/// How long to wait after a worktree changes before asking the [`TaskProvider`]s for its tasks again.
+const PROVIDER_TASKS_DEBOUNCE: Duration = Duration::from_millis(500);
+
 #[allow(clippy::large_enum_variant)] // platform-dependent warning
 pub enum TaskStore {
     Functional(StoreState),
@@ -32,13 +38,16 @@ pub struct StoreState {
     buffer_store: WeakEntity<BufferStore>,
     worktree_store: Entity<WorktreeStore>,
     toolchain_store: Arc<dyn LanguageToolchainStore>,
+    provider_task_refreshes: HashMap<WorktreeId, Task<()>>,
     _global_task_config_watcher: Task<()>,
+    _subscriptions: Vec<Subscription>,
 }
 
 enum StoreMode {
     Local {
         downstream_client: Option<(AnyProtoClient, u64)>,
         environment: Entity<ProjectEnvironment>,
+        fs: Arc<dyn Fs>,
     },
     Remote {
         upstream_client: AnyProtoClient,
@@ -159,16 +168,26 @@ impl TaskStore {
         environment: Entity<ProjectEnvironment>,
         cx: &mut Context<'_, Self>,
     ) -> Self {
+        let task_provider_registry = TaskProviderRegistry::default_global(cx);
+        let subscriptions = vec![
+            cx.subscribe(&worktree_store, Self::on_worktree_store_event),
+            cx.observe(&task_provider_registry, |task_store, _, cx| {
+                task_store.refresh_all_provider_tasks(cx)
+            }),
+        ];
         Self::Functional(StoreState {
             mode: StoreMode::Local {
                 downstream_client: None,
                 environment,
+                fs: fs.clone(),
             },
             task_inventory: Inventory::new(cx),
             buffer_store,
             toolchain_store,
             worktree_store,
+            provider_task_refreshes: HashMap::default(),
             _global_task_config_watcher: Self::subscribe_to_global_task_file_changes(fs, cx),
+            _subscriptions: subscriptions,
         })
     }
 
@@ -190,7 +209,9 @@ impl TaskStore {
             buffer_store,
             toolchain_store,
             worktree_store,
+            provider_task_refreshes: HashMap::default(),
             _global_task_config_watcher: Self::subscribe_to_global_task_file_changes(fs, cx),
+            _subscriptions: Vec::new(),
         })
     }
 
@@ -202,10 +223,13 @@ impl TaskStore {
     ) -> Task<Option<TaskContext>> {
         match self {
             TaskStore::Functional(state) => match &state.mode {
-                StoreMode::Local { environment, .. } => local_task_context_for_location(
+                StoreMode::Local {
+                    environment, fs, ..
+                } => local_task_context_for_location(
                     state.worktree_store.clone(),
                     state.toolchain_store.clone(),
                     environment.clone(),
+                    fs.clone(),
                     captured_variables,
                     location,
                     cx,
@@ -277,6 +301,125 @@ impl TaskStore {
         })
     }
 
+    fn on_worktree_store_event(
+        &mut self,
+        worktree_store: Entity<WorktreeStore>,
+        event: &WorktreeStoreEvent,
+        cx: &mut Context<Self>,
+    ) {
+        match event {
+            WorktreeStoreEvent::WorktreeAdded(worktree) => {
+                self.refresh_provider_tasks(worktree.clone(), cx);
+            }
+            WorktreeStoreEvent::WorktreeUpdatedEntries(worktree_id, _) => {
+                if let Some(worktree) = worktree_store.read(cx).worktree_for_id(*worktree_id, cx) {
+                    self.refresh_provider_tasks(worktree, cx);
+                }
+            }
+            WorktreeStoreEvent::WorktreeRemoved(_, worktree_id) => {
+                if let TaskStore::Functional(state) = self {
+                    state.provider_task_refreshes.remove(worktree_id);
+                    state.task_inventory.update(cx, |inventory, _| {
+                        inventory.update_provider_tasks(*worktree_id, Vec::new())
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn refresh_all_provider_tasks(&mut self, cx: &mut Context<Self>) {
+        let TaskStore::Functional(state) = self else {
+            return;
+        };
+        let worktrees = state
+            .worktree_store
+            .read(cx)
+            .visible_worktrees(cx)
+            .collect::<Vec<_>>();
+        for worktree in worktrees {
+            self.refresh_provider_tasks(worktree, cx);
+        }
+    }
+
+    /// Asks the registered [`TaskProvider`]s for the tasks of the given worktree, once it stops changing.
+    fn refresh_provider_tasks(&mut self, worktree: Entity<Worktree>, cx: &mut Context<Self>) {
+        let TaskStore::Functional(state) = self else {
+            return;
+        };
+        let StoreMode::Local {
+            environment, fs, ..
+        } = &state.mode
+        else {
+            return;
+        };
+        let (worktree_id, worktree_abs_path) = {
+            let worktree = worktree.read(cx);
+            if !worktree.is_visible() {
+                return;
+            }
+            (worktree.id(), worktree.root_dir())
+        };
+        let Some(worktree_abs_path) = worktree_abs_path else {
+            return;
+        };
+
+        let providers = TaskProviderRegistry::default_global(cx)
+            .read(cx)
+            .providers();
+        let task_inventory = state.task_inventory.clone();
+        if providers.is_empty() {
+            state.provider_task_refreshes.remove(&worktree_id);
+            task_inventory.update(cx, |inventory, _| {
+                inventory.update_provider_tasks(worktree_id, Vec::new())
+            });
+            return;
+        }
+
+        let shell_env = environment.update(cx, |environment, cx| {
+            environment.get_environment(Some(worktree_id), Some(worktree_abs_path.clone()), cx)
+        });
+        let fs = fs.clone();
+        let worktree = worktree.downgrade();
+        let refresh = cx.spawn(|_, mut cx| async move {
+            cx.background_executor()
+                .timer(PROVIDER_TASKS_DEBOUNCE)
+                .await;
+            let Ok(worktree_snapshot) = worktree.read_with(&cx, |worktree, _| worktree.snapshot())
+            else {
+                return;
+            };
+            let worktree = TaskProviderWorktree::new(
+                worktree_snapshot,
+                worktree_abs_path,
+                shell_env.await.unwrap_or_default(),
+                fs,
+            );
+
+            let mut templates = Vec::new();
+            for provider in providers {
+                match provider.task_templates(worktree.clone()).await {
+                    Ok(provider_templates) => templates.extend(
+                        provider_templates
+                            .0
+                            .into_iter()
+                            .map(|template| (provider.name(), template)),
+                    ),
+                    Err(error) => {
+                        log::error!("failed to load tasks from {}: {error:#}", provider.name())
+                    }
+                }
+            }
+
+            task_inventory
+                .update(&mut cx, |inventory, _| {
+                    inventory.update_provider_tasks(worktree_id, templates)
+                })
+                .ok();
+        });
+        state.provider_task_refreshes.insert(worktree_id, refresh);
+    }
+
     fn subscribe_to_global_task_file_changes(
         fs: Arc<dyn Fs>,
         cx: &mut Context<'_, Self>,
@@ -317,14 +460,18 @@ fn local_task_context_for_location(
     worktree_store: Entity<WorktreeStore>,
     toolchain_store: Arc<dyn LanguageToolchainStore>,
     environment: Entity<ProjectEnvironment>,
+    fs: Arc<dyn Fs>,
     captured_variables: TaskVariables,
     location: Location,
     cx: &App,
 ) -> Task<Option<TaskContext>> {
     let worktree_id = location.buffer.read(cx).file().map(|f| f.worktree_id(cx));
-    let worktree_abs_path = worktree_id
-        .and_then(|worktree_id| worktree_store.read(cx).worktree_for_id(worktree_id, cx))
+    let worktree = worktree_id
+        .and_then(|worktree_id| worktree_store.read(cx).worktree_for_id(worktree_id, cx));
+    let worktree_abs_path = worktree
+        .as_ref()
         .and_then(|worktree| worktree.read(cx).root_dir());
+    let worktree_snapshot = worktree.map(|worktree| worktree.read(cx).snapshot());
 
     cx.spawn(|mut cx| async move {
         let worktree_abs_path = worktree_abs_path.clone();
@@ -349,6 +496,26 @@ fn local_task_context_for_location(
             .ok()?
             .await
             .log_err()?;
+        if let Some((worktree_snapshot, worktree_abs_path)) =
+            worktree_snapshot.zip(worktree_abs_path.clone())
+        {
+            let providers = cx
+                .update(|cx| {
+                    TaskProviderRegistry::default_global(cx)
+                        .read(cx)
+                        .providers()
+                })
+                .ok()?;
+            let worktree = TaskProviderWorktree::new(
+                worktree_snapshot,
+                worktree_abs_path,
+                project_env.clone().unwrap_or_default(),
+                fs,
+            );
+            let provider_variables =
+                provider_task_variables(providers, worktree, &task_variables).await;
+            task_variables.extend(provider_variables);
+        }
         // Remove all custom entries starting with _, as they're not intended for use by the end user.
         task_variables.sweep();
 
@@ -423,6 +590,33 @@ fn remote_task_context_for_location(
     })
 }
 
+/// Collects the custom task variables that the given providers define for a worktree.
+async fn provider_task_variables(
+    providers: Vec<Arc<dyn TaskProvider>>,
+    worktree: TaskProviderWorktree,
+    variables: &TaskVariables,
+) -> TaskVariables {
+    let mut provider_variables = TaskVariables::default();
+    for provider in providers {
+        match provider
+            .task_variables(worktree.clone(), variables.clone())
+            .await
+        {
+            Ok(variables) => provider_variables.extend(
+                variables
+                    .into_iter()
+                    .filter(|(name, _)| matches!(name, VariableName::Custom(_)))
+                    .collect(),
+            ),
+            Err(error) => log::error!(
+                "failed to load task variables from {}: {error:#}",
+                provider.name()
+            ),
+        }
+    }
+    provider_variables
+}
+
 fn combine_task_variables(
     mut captured_variables: TaskVariables,
     location: Location,
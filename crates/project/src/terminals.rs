@@ -4,6 +4,7 @@ use collections::HashMap;
 use gpui::{AnyWindowHandle, App, AppContext as _, Context, Entity, Task, WeakEntity};
 use itertools::Itertools;
 use language::LanguageName;
+use remote::RemoteTransport;
 use settings::{Settings, SettingsLocation};
 use smol::channel::bounded;
 use std::{
@@ -39,8 +40,12 @@ pub enum TerminalKind {
 
 /// SshCommand describes how to connect to a remote server
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct SshCommand {
-    arguments: Vec<String>,
+pub enum SshCommand {
+    /// Connect with `ssh`, using these arguments.
+    Ssh { arguments: Vec<String> },
+    /// Run a command that executes its trailing arguments on the remote host.
+    /// Empty when the remote server runs on this machine.
+    Exec { command: Vec<String> },
 }
 
 impl Project {
@@ -67,12 +72,25 @@ impl Project {
     pub fn ssh_details(&self, cx: &App) -> Option<(String, SshCommand)> {
         if let Some(ssh_client) = &self.ssh_client {
             let ssh_client = ssh_client.read(cx);
-            if let Some(args) = ssh_client.ssh_args() {
-                return Some((
-                    ssh_client.connection_options().host.clone(),
-                    SshCommand { arguments: args },
-                ));
-            }
+            let connection_options = ssh_client.connection_options();
+            let ssh_command = match connection_options.transport {
+                RemoteTransport::Ssh => SshCommand::Ssh {
+                    arguments: ssh_client.ssh_args()?,
+                },
+                RemoteTransport::Command {
+                    program,
+                    args,
+                    terminal_args,
+                } => SshCommand::Exec {
+                    command: iter::once(program)
+                        .chain(terminal_args.unwrap_or(args))
+                        .collect(),
+                },
+                RemoteTransport::Local => SshCommand::Exec {
+                    command: Vec::new(),
+                },
+            };
+            return Some((connection_options.host, ssh_command));
         }
 
         return None;
@@ -559,14 +577,26 @@ fn wrap_for_ssh(
     } else {
         format!("cd; {env_changes} {to_run}")
     };
-    let shell_invocation = format!("sh -c {}", shlex::try_quote(&commands).unwrap());
+    match ssh_command {
+        SshCommand::Ssh { arguments } => {
+            let shell_invocation = format!("sh -c {}", shlex::try_quote(&commands).unwrap());
 
-    let program = "ssh".to_string();
-    let mut args = ssh_command.arguments.clone();
+            let program = "ssh".to_string();
+            let mut args = arguments.clone();
 
-    args.push("-t".to_string());
-    args.push(shell_invocation);
-    (program, args)
+            args.push("-t".to_string());
+            args.push(shell_invocation);
+            (program, args)
+        }
+        SshCommand::Exec { command } => {
+            // Unlike ssh, exec-style commands pass their arguments through
+            // without re-parsing them with a shell.
+            let mut args = command.clone();
+            args.extend(["sh".to_string(), "-c".to_string(), commands]);
+            let program = args.remove(0);
+            (program, args)
+        }
+    }
 }
 
 fn add_environment_path(env: &mut HashMap<String, String>, new_path: &Path) -> Result<()> {
@@ -354,6 +354,8 @@ messages!(
     (SubscribeToChannels, Foreground),
     (SynchronizeBuffers, Foreground),
     (SynchronizeBuffersResponse, Foreground),
+    (GetBufferDiskText, Background),
+    (GetBufferDiskTextResponse, Background),
     (TaskContextForLocation, Background),
     (TaskContext, Background),
     (Test, Foreground),
@@ -416,6 +418,8 @@ messages!(
     (LanguageServerLog, Foreground),
     (Toast, Background),
     (HideToast, Background),
+    (UpdateListeningPorts, Background),
+    (WatchListeningPorts, Background),
     (OpenServerSettings, Foreground),
     (GetPermalinkToLine, Foreground),
     (GetPermalinkToLineResponse, Foreground),
@@ -449,6 +453,18 @@ messages!(
     (GetRemotes, Background),
     (GetRemotesResponse, Background),
     (Pull, Background),
+    (GetDocumentLinks, Background),
+    (GetDocumentLinksResponse, Background),
+    (GetDocumentColors, Background),
+    (GetDocumentColorsResponse, Background),
+    (GetColorPresentations, Background),
+    (GetColorPresentationsResponse, Background),
+    (ResolveDocumentLink, Background),
+    (ResolveDocumentLinkResponse, Background),
+    (GetInlineValues, Background),
+    (GetInlineValuesResponse, Background),
+    (GetRenameEdits, Background),
+    (GetRenameEditsResponse, Background),
 );
 
 request_messages!(
@@ -546,6 +562,7 @@ request_messages!(
     (SetChannelVisibility, Ack),
     (ShareProject, ShareProjectResponse),
     (SynchronizeBuffers, SynchronizeBuffersResponse),
+    (GetBufferDiskText, GetBufferDiskTextResponse),
     (TaskContextForLocation, TaskContext),
     (Test, Test),
     (Unstage, Ack),
@@ -590,6 +607,12 @@ request_messages!(
     (Fetch, Ack),
     (GetRemotes, GetRemotesResponse),
     (Pull, Ack),
+    (GetDocumentLinks, GetDocumentLinksResponse),
+    (GetDocumentColors, GetDocumentColorsResponse),
+    (GetColorPresentations, GetColorPresentationsResponse),
+    (ResolveDocumentLink, ResolveDocumentLinkResponse),
+    (GetInlineValues, GetInlineValuesResponse),
+    (GetRenameEdits, GetRenameEditsResponse),
 );
 
 entity_messages!(
@@ -648,6 +671,7 @@ entity_messages!(
     Stage,
     StartLanguageServer,
     SynchronizeBuffers,
+    GetBufferDiskText,
     TaskContextForLocation,
     UnshareProject,
     Unstage,
@@ -671,6 +695,8 @@ entity_messages!(
     LanguageServerLog,
     Toast,
     HideToast,
+    UpdateListeningPorts,
+    WatchListeningPorts,
     OpenServerSettings,
     GetPermalinkToLine,
     LanguageServerPromptRequest,
@@ -690,6 +716,12 @@ entity_messages!(
     Fetch,
     GetRemotes,
     Pull,
+    GetDocumentLinks,
+    GetDocumentColors,
+    GetColorPresentations,
+    GetInlineValues,
+    GetRenameEdits,
+    ResolveDocumentLink,
 );
 
 entity_messages!(
@@ -11,7 +11,7 @@ use ui::{
 };
 use workspace::{notifications::DetachAndPromptErr, ModalView, OpenOptions, Workspace};
 
-use crate::open_ssh_project;
+use crate::{open_ssh_project, ssh_connections::resume_ssh_project};
 
 enum Host {
     RemoteProject,
@@ -21,6 +21,9 @@ enum Host {
 pub struct DisconnectedOverlay {
     workspace: WeakEntity<Workspace>,
     host: Host,
+    /// Whether the server may still be running, so that the project can keep
+    /// being edited and be resumed later.
+    can_resume: bool,
     focus_handle: FocusHandle,
     finished: bool,
 }
@@ -67,6 +70,10 @@ impl DisconnectedOverlay {
                 let handle = cx.entity().downgrade();
 
                 let ssh_connection_options = project.read(cx).ssh_connection_options(cx);
+                let can_resume = project
+                    .read(cx)
+                    .ssh_client()
+                    .is_some_and(|ssh_client| ssh_client.read(cx).can_resume());
                 let host = if let Some(ssh_connection_options) = ssh_connection_options {
                     Host::SshRemoteProject(ssh_connection_options)
                 } else {
@@ -77,6 +84,7 @@ impl DisconnectedOverlay {
                     finished: false,
                     workspace: handle,
                     host,
+                    can_resume,
                     focus_handle: cx.focus_handle(),
                 });
             },
@@ -118,7 +126,24 @@ impl DisconnectedOverlay {
 
         let paths = ssh_project.paths.iter().map(PathBuf::from).collect();
 
+        // Resuming keeps the window's buffers, including the edits made to
+        // them while disconnected, so it's preferred over reopening the
+        // project.
+        let resumable_client = workspace
+            .read(cx)
+            .project()
+            .read(cx)
+            .ssh_client()
+            .filter(|ssh_client| ssh_client.read(cx).can_resume());
+
         cx.spawn_in(window, move |_, mut cx| async move {
+            if let Some(ssh_client) = resumable_client {
+                match resume_ssh_project(ssh_client, window_handle, &mut cx).await {
+                    Ok(()) => return Ok(()),
+                    Err(error) => log::error!("failed to resume ssh connection: {error:?}"),
+                }
+            }
+
             open_ssh_project(
                 connection_options,
                 paths,
@@ -150,7 +175,9 @@ impl Render for DisconnectedOverlay {
                 "Your connection to the remote project has been lost.".to_string()
             }
             Host::SshRemoteProject(options) => {
-                let autosave = if ProjectSettings::get_global(cx)
+                let autosave = if self.can_resume {
+                    "\nYou can keep editing, and your changes will be synchronized once reconnected."
+                } else if ProjectSettings::get_global(cx)
                     .session
                     .restore_unsaved_buffers
                 {
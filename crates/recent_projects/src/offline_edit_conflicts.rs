@@ -0,0 +1,246 @@
+use std::any::TypeId;
+
+use buffer_diff::BufferDiff;
+use collections::HashSet;
+use editor::{Editor, EditorEvent, ExcerptRange, MultiBuffer};
+use gpui::{App, Entity, EventEmitter, FocusHandle, Focusable, Render, Subscription};
+use language::{Buffer, BufferEvent, Capability};
+use project::{OfflineEditConflict, Project};
+use ui::prelude::*;
+use workspace::{item::ItemEvent, searchable::SearchableItemHandle, Item, Workspace};
+
+/// Shows the buffers that were edited while disconnected from an ssh host and
+/// whose files changed on the host in the meantime, diffed against the
+/// current contents of those files.
+///
+/// Saving keeps the offline edits, while discarding them reloads the buffers
+/// from disk.
+pub struct OfflineEditConflicts {
+    editor: Entity<Editor>,
+    project: Entity<Project>,
+    conflicts: Vec<ConflictedBuffer>,
+    _subscriptions: Vec<Subscription>,
+}
+
+struct ConflictedBuffer {
+    buffer: Entity<Buffer>,
+    disk_buffer: Entity<Buffer>,
+    diff: Entity<BufferDiff>,
+}
+
+impl OfflineEditConflicts {
+    pub fn register(
+        workspace: &mut Workspace,
+        window: Option<&mut Window>,
+        cx: &mut Context<Workspace>,
+    ) {
+        let Some(window) = window else {
+            return;
+        };
+        cx.subscribe_in(
+            workspace.project(),
+            window,
+            |workspace, project, event, window, cx| {
+                let project::Event::OfflineEditConflicts(conflicts) = event else {
+                    return;
+                };
+                let project = project.clone();
+                let conflicts = conflicts.clone();
+                let view = cx.new(|cx| Self::new(conflicts, project, window, cx));
+                workspace.add_item_to_active_pane(Box::new(view), None, true, window, cx);
+            },
+        )
+        .detach();
+    }
+
+    fn new(
+        conflicts: Vec<OfflineEditConflict>,
+        project: Entity<Project>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let multibuffer = cx.new(|_| MultiBuffer::new(Capability::ReadWrite));
+        let mut subscriptions = Vec::new();
+        let conflicts = conflicts
+            .into_iter()
+            .map(|conflict| {
+                let buffer = conflict.buffer;
+                let language = buffer.read(cx).language().cloned();
+                let disk_buffer = cx.new(|cx| {
+                    let mut disk_buffer = Buffer::local(conflict.disk_text, cx);
+                    disk_buffer.set_language(language, cx);
+                    disk_buffer
+                });
+                let snapshot = buffer.read(cx).text_snapshot();
+                let diff = cx.new(|_| BufferDiff::new(&snapshot));
+                subscriptions.push(cx.subscribe(&buffer, |this, buffer, event, cx| {
+                    if let BufferEvent::Edited = event {
+                        if let Some(conflict) = this
+                            .conflicts
+                            .iter()
+                            .find(|conflict| conflict.buffer == buffer)
+                        {
+                            conflict.update_diff(cx);
+                        }
+                    }
+                }));
+
+                multibuffer.update(cx, |multibuffer, cx| {
+                    let len = buffer.read(cx).len();
+                    multibuffer.push_excerpts(
+                        buffer.clone(),
+                        [ExcerptRange {
+                            context: 0..len,
+                            primary: None,
+                        }],
+                        cx,
+                    );
+                });
+
+                let conflict = ConflictedBuffer {
+                    buffer,
+                    disk_buffer,
+                    diff,
+                };
+                conflict.update_diff(cx);
+                conflict
+            })
+            .collect::<Vec<_>>();
+
+        let editor = cx.new(|cx| {
+            let mut editor =
+                Editor::for_multibuffer(multibuffer, Some(project.clone()), true, window, cx);
+            editor.set_expand_all_diff_hunks(cx);
+            editor.buffer().update(cx, |multibuffer, cx| {
+                for conflict in &conflicts {
+                    multibuffer.add_diff(conflict.diff.clone(), cx);
+                }
+            });
+            editor
+        });
+
+        Self {
+            editor,
+            project,
+            conflicts,
+            _subscriptions: subscriptions,
+        }
+    }
+
+    fn save(&mut self, cx: &mut Context<Self>) {
+        let buffers = self.buffers();
+        self.project
+            .update(cx, |project, cx| project.save_buffers(buffers, cx))
+            .detach_and_log_err(cx);
+        cx.emit(EditorEvent::Closed);
+    }
+
+    fn discard(&mut self, cx: &mut Context<Self>) {
+        let buffers = self.buffers();
+        self.project
+            .update(cx, |project, cx| project.reload_buffers(buffers, true, cx))
+            .detach_and_log_err(cx);
+        cx.emit(EditorEvent::Closed);
+    }
+
+    fn buffers(&self) -> HashSet<Entity<Buffer>> {
+        self.conflicts
+            .iter()
+            .map(|conflict| conflict.buffer.clone())
+            .collect()
+    }
+}
+
+impl ConflictedBuffer {
+    fn update_diff(&self, cx: &mut App) {
+        let snapshot = self.buffer.read(cx).text_snapshot();
+        let disk_buffer = self.disk_buffer.clone();
+        let _ = self
+            .diff
+            .update(cx, |diff, cx| diff.set_base_text(disk_buffer, snapshot, cx));
+    }
+}
+
+impl Render for OfflineEditConflicts {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .size_full()
+            .key_context("OfflineEditConflicts")
+            .child(
+                h_flex()
+                    .p_2()
+                    .gap_2()
+                    .justify_between()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border)
+                    .child(Label::new(format!(
+                        "{} files edited while disconnected changed on the remote host",
+                        self.conflicts.len()
+                    )))
+                    .child(
+                        h_flex()
+                            .gap_1()
+                            .child(
+                                Button::new("discard-offline-edits", "Discard My Changes")
+                                    .on_click(cx.listener(|this, _, _, cx| this.discard(cx))),
+                            )
+                            .child(
+                                Button::new("save-offline-edits", "Keep My Changes")
+                                    .style(ButtonStyle::Filled)
+                                    .on_click(cx.listener(|this, _, _, cx| this.save(cx))),
+                            ),
+                    ),
+            )
+            .child(div().flex_1().min_h_0().child(self.editor.clone()))
+    }
+}
+
+impl Focusable for OfflineEditConflicts {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.editor.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<EditorEvent> for OfflineEditConflicts {}
+
+impl Item for OfflineEditConflicts {
+    type Event = EditorEvent;
+
+    fn tab_icon(&self, _window: &Window, _cx: &App) -> Option<Icon> {
+        Some(Icon::new(IconName::Diff))
+    }
+
+    fn tab_content_text(&self, _window: &Window, _cx: &App) -> Option<SharedString> {
+        Some("Offline Edit Conflicts".into())
+    }
+
+    fn to_item_events(event: &EditorEvent, mut f: impl FnMut(ItemEvent)) {
+        if let EditorEvent::Closed = event {
+            f(ItemEvent::CloseItem);
+        }
+    }
+
+    fn as_searchable(&self, _: &Entity<Self>) -> Option<Box<dyn SearchableItemHandle>> {
+        Some(Box::new(self.editor.clone()))
+    }
+
+    fn act_as_type<'a>(
+        &'a self,
+        type_id: TypeId,
+        self_handle: &'a Entity<Self>,
+        _: &'a App,
+    ) -> Option<gpui::AnyView> {
+        if type_id == TypeId::of::<Self>() {
+            Some(self_handle.to_any())
+        } else if type_id == TypeId::of::<Editor>() {
+            Some(self.editor.to_any())
+        } else {
+            None
+        }
+    }
+
+    fn deactivated(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.editor
+            .update(cx, |editor, cx| editor.deactivated(window, cx));
+    }
+}
@@ -0,0 +1,478 @@
+use editor::Editor;
+use gpui::{
+    actions, AnyElement, App, Context, Entity, EventEmitter, FocusHandle, Focusable, Pixels,
+    Subscription, Window,
+};
+use project::{Fs, Project, RemoteListeningPort};
+use remote::{SshPortForwardOption, SshRemoteClient};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use settings::{Settings, SettingsSources};
+use std::sync::Arc;
+use ui::{prelude::*, List, ListHeader, ListItem, ListItemSpacing, Tab, Tooltip};
+use workspace::{
+    dock::{DockPosition, Panel, PanelEvent},
+    notifications::DetachAndPromptErr,
+    Workspace,
+};
+
+actions!(ports_panel, [ToggleFocus]);
+
+pub fn init(cx: &mut App) {
+    PortsPanelSettings::register(cx);
+    cx.observe_new(PortsPanel::register).detach();
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema, Debug)]
+pub struct PortsPanelSettingsContent {
+    /// Whether to show the panel button in the status bar of remote projects.
+    ///
+    /// Default: true
+    pub button: Option<bool>,
+    /// Where to dock the panel.
+    ///
+    /// Default: right
+    pub dock: Option<DockPosition>,
+    /// Default width of the panel in pixels.
+    ///
+    /// Default: 300
+    pub default_width: Option<f32>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct PortsPanelSettings {
+    pub button: bool,
+    pub dock: DockPosition,
+    pub default_width: Pixels,
+}
+
+impl Settings for PortsPanelSettings {
+    const KEY: Option<&'static str> = Some("ports_panel");
+
+    type FileContent = PortsPanelSettingsContent;
+
+    fn load(sources: SettingsSources<Self::FileContent>, _: &mut App) -> anyhow::Result<Self> {
+        sources.json_merge()
+    }
+}
+
+/// Lists the ports forwarded to the ssh host of a remote project, along with
+/// the ports that processes on the host are listening on, so that they can be
+/// forwarded and opened in a browser.
+pub struct PortsPanel {
+    project: Entity<Project>,
+    ssh_client: Entity<SshRemoteClient>,
+    fs: Arc<dyn Fs>,
+    focus_handle: FocusHandle,
+    port_editor: Entity<Editor>,
+    port_error: Option<SharedString>,
+    width: Option<Pixels>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl PortsPanel {
+    fn register(
+        workspace: &mut Workspace,
+        window: Option<&mut Window>,
+        cx: &mut Context<Workspace>,
+    ) {
+        workspace.register_action(|workspace, _: &ToggleFocus, window, cx| {
+            workspace.toggle_panel_focus::<PortsPanel>(window, cx);
+        });
+
+        let Some(window) = window else {
+            return;
+        };
+        let project = workspace.project().clone();
+        let Some(ssh_client) = project.read(cx).ssh_client() else {
+            return;
+        };
+        let fs = workspace.app_state().fs.clone();
+        let panel = cx.new(|cx| Self::new(project, ssh_client, fs, window, cx));
+        workspace.add_panel(panel, window, cx);
+    }
+
+    fn new(
+        project: Entity<Project>,
+        ssh_client: Entity<SshRemoteClient>,
+        fs: Arc<dyn Fs>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let port_editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text("Forward a port, e.g. 8080 or 8080:localhost:3000", cx);
+            editor
+        });
+        let subscriptions = vec![
+            cx.subscribe(&project, |_, _, event, cx| {
+                if let project::Event::RemoteListeningPortsChanged = event {
+                    cx.notify();
+                }
+            }),
+            cx.observe(&ssh_client, |_, _, cx| cx.notify()),
+        ];
+        Self {
+            project,
+            ssh_client,
+            fs,
+            focus_handle: cx.focus_handle(),
+            port_editor,
+            port_error: None,
+            width: None,
+            _subscriptions: subscriptions,
+        }
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        let input = self.port_editor.read(cx).text(cx);
+        let forward = match parse_forward(input.trim()) {
+            Some(forward) => forward,
+            None => {
+                self.port_error = Some(format!("Invalid port forward: {input:?}").into());
+                cx.notify();
+                return;
+            }
+        };
+        self.port_error = None;
+        self.port_editor
+            .update(cx, |editor, cx| editor.set_text("", window, cx));
+        self.forward(forward, window, cx);
+    }
+
+    fn forward(
+        &mut self,
+        forward: SshPortForwardOption,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.ssh_client
+            .update(cx, |client, cx| client.add_port_forward(forward, cx))
+            .detach_and_prompt_err("Failed to forward port", window, cx, |_, _, _| None);
+    }
+
+    fn stop_forwarding(
+        &mut self,
+        forward: SshPortForwardOption,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.ssh_client
+            .update(cx, |client, cx| client.remove_port_forward(forward, cx))
+            .detach_and_prompt_err("Failed to stop forwarding port", window, cx, |_, _, _| None);
+    }
+
+    fn render_forward(
+        &self,
+        ix: usize,
+        forward: &SshPortForwardOption,
+        listener: Option<&RemoteListeningPort>,
+        cx: &mut Context<Self>,
+    ) -> AnyElement {
+        let local_port = forward.local_port;
+        let description = match listener {
+            Some(listener) => format!("{} ({})", listener.process_name, listener.pid),
+            None => format!(
+                "{}:{}",
+                forward.remote_host.as_deref().unwrap_or("localhost"),
+                forward.remote_port
+            ),
+        };
+        ListItem::new(("forwarded-port", ix))
+            .spacing(ListItemSpacing::Sparse)
+            .start_slot(Icon::new(IconName::Link).color(Color::Accent))
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(Label::new(local_port.to_string()))
+                    .child(
+                        Label::new(description)
+                            .color(Color::Muted)
+                            .size(LabelSize::Small)
+                            .text_ellipsis(),
+                    ),
+            )
+            .end_slot(
+                h_flex()
+                    .gap_1()
+                    .child(
+                        IconButton::new(("open-port", ix), IconName::ArrowUpRight)
+                            .icon_size(IconSize::Small)
+                            .tooltip(Tooltip::text("Open in Browser"))
+                            .on_click(move |_, _, cx| {
+                                cx.open_url(&format!("http://localhost:{local_port}"))
+                            }),
+                    )
+                    .child(
+                        IconButton::new(("stop-forwarding-port", ix), IconName::Close)
+                            .icon_size(IconSize::Small)
+                            .tooltip(Tooltip::text("Stop Forwarding"))
+                            .on_click(cx.listener({
+                                let forward = forward.clone();
+                                move |this, _, window, cx| {
+                                    this.stop_forwarding(forward.clone(), window, cx)
+                                }
+                            })),
+                    ),
+            )
+            .into_any_element()
+    }
+
+    fn render_listener(
+        &self,
+        ix: usize,
+        listener: &RemoteListeningPort,
+        cx: &mut Context<Self>,
+    ) -> AnyElement {
+        ListItem::new(("detected-port", ix))
+            .spacing(ListItemSpacing::Sparse)
+            .start_slot(Icon::new(IconName::Server).color(Color::Muted))
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(Label::new(listener.port.to_string()))
+                    .child(
+                        Label::new(format!("{} ({})", listener.process_name, listener.pid))
+                            .color(Color::Muted)
+                            .size(LabelSize::Small)
+                            .text_ellipsis(),
+                    ),
+            )
+            .end_slot(
+                Button::new(("forward-port", ix), "Forward")
+                    .label_size(LabelSize::Small)
+                    .on_click(cx.listener({
+                        let forward = forward_for_listener(listener);
+                        move |this, _, window, cx| this.forward(forward.clone(), window, cx)
+                    })),
+            )
+            .into_any_element()
+    }
+}
+
+/// Parses either a single port, forwarded to the same port on the remote
+/// host, or a `local_port:remote_host:remote_port` spec like `ssh -L` takes.
+fn parse_forward(input: &str) -> Option<SshPortForwardOption> {
+    if let Ok(port) = input.parse() {
+        return Some(SshPortForwardOption {
+            local_host: None,
+            local_port: port,
+            remote_host: None,
+            remote_port: port,
+        });
+    }
+    let mut parts = input.split(':');
+    let local_port = parts.next()?.parse().ok()?;
+    let remote_host = parts.next()?.to_string();
+    let remote_port = parts.next()?.parse().ok()?;
+    if parts.next().is_some() || remote_host.is_empty() {
+        return None;
+    }
+    Some(SshPortForwardOption {
+        local_host: None,
+        local_port,
+        remote_host: Some(remote_host),
+        remote_port,
+    })
+}
+
+fn forward_for_listener(listener: &RemoteListeningPort) -> SshPortForwardOption {
+    // Servers listening on all interfaces are reachable through localhost,
+    // and ssh expects IPv6 addresses to be bracketed.
+    let remote_host = match listener.address.as_str() {
+        "0.0.0.0" | "::" | "127.0.0.1" => None,
+        address if address.contains(':') => Some(format!("[{address}]")),
+        address => Some(address.to_string()),
+    };
+    SshPortForwardOption {
+        local_host: None,
+        local_port: listener.port,
+        remote_host,
+        remote_port: listener.port,
+    }
+}
+
+fn forwards_listener(forward: &SshPortForwardOption, listener: &RemoteListeningPort) -> bool {
+    forward.remote_port == listener.port
+        && forward.remote_host == forward_for_listener(listener).remote_host
+}
+
+impl Render for PortsPanel {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let listeners = self.project.read(cx).remote_listening_ports().to_vec();
+        let forwards = self.ssh_client.read(cx).port_forwards().to_vec();
+
+        let forwarded_items = forwards
+            .iter()
+            .enumerate()
+            .map(|(ix, forward)| {
+                let listener = listeners
+                    .iter()
+                    .find(|listener| forwards_listener(forward, listener));
+                self.render_forward(ix, forward, listener, cx)
+            })
+            .collect::<Vec<_>>();
+        let detected_items = listeners
+            .iter()
+            .filter(|listener| {
+                !forwards
+                    .iter()
+                    .any(|forward| forwards_listener(forward, listener))
+            })
+            .enumerate()
+            .map(|(ix, listener)| self.render_listener(ix, listener, cx))
+            .collect::<Vec<_>>();
+
+        v_flex()
+            .key_context("PortsPanel")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::confirm))
+            .size_full()
+            .child(
+                h_flex()
+                    .justify_between()
+                    .px_2()
+                    .py_1()
+                    // Match the height of the tab bar so they line up.
+                    .h(Tab::container_height(cx))
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border)
+                    .child(Label::new("Ports"))
+                    .child(Icon::new(IconName::Globe)),
+            )
+            .child(
+                v_flex()
+                    .p_2()
+                    .gap_1()
+                    .child(
+                        div()
+                            .px_2()
+                            .py_1()
+                            .border_1()
+                            .rounded_md()
+                            .border_color(cx.theme().colors().border_variant)
+                            .child(self.port_editor.clone()),
+                    )
+                    .children(
+                        self.port_error.clone().map(|error| {
+                            Label::new(error).size(LabelSize::Small).color(Color::Error)
+                        }),
+                    ),
+            )
+            .child(
+                v_flex()
+                    .id("ports")
+                    .flex_1()
+                    .overflow_y_scroll()
+                    .child(
+                        List::new()
+                            .header(ListHeader::new("Forwarded"))
+                            .empty_message("No forwarded ports")
+                            .children(forwarded_items),
+                    )
+                    .child(
+                        List::new()
+                            .header(ListHeader::new("Detected"))
+                            .empty_message("No other ports are being listened on")
+                            .children(detected_items),
+                    ),
+            )
+    }
+}
+
+impl Focusable for PortsPanel {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl EventEmitter<PanelEvent> for PortsPanel {}
+
+impl Panel for PortsPanel {
+    fn persistent_name() -> &'static str {
+        "PortsPanel"
+    }
+
+    fn position(&self, _: &Window, cx: &App) -> DockPosition {
+        PortsPanelSettings::get_global(cx).dock
+    }
+
+    fn position_is_valid(&self, position: DockPosition) -> bool {
+        matches!(position, DockPosition::Left | DockPosition::Right)
+    }
+
+    fn set_position(&mut self, position: DockPosition, _: &mut Window, cx: &mut Context<Self>) {
+        settings::update_settings_file::<PortsPanelSettings>(
+            self.fs.clone(),
+            cx,
+            move |settings, _| settings.dock = Some(position),
+        );
+    }
+
+    fn size(&self, _: &Window, cx: &App) -> Pixels {
+        self.width
+            .unwrap_or_else(|| PortsPanelSettings::get_global(cx).default_width)
+    }
+
+    fn set_size(&mut self, size: Option<Pixels>, _: &mut Window, cx: &mut Context<Self>) {
+        self.width = size;
+        cx.notify();
+    }
+
+    fn icon(&self, _: &Window, cx: &App) -> Option<IconName> {
+        Some(IconName::Globe).filter(|_| PortsPanelSettings::get_global(cx).button)
+    }
+
+    fn icon_tooltip(&self, _: &Window, _: &App) -> Option<&'static str> {
+        Some("Ports Panel")
+    }
+
+    fn set_active(&mut self, active: bool, _: &mut Window, cx: &mut Context<Self>) {
+        // Ports are only detected while someone is looking at them.
+        self.project.update(cx, |project, cx| {
+            project.watch_remote_listening_ports(active, cx)
+        });
+    }
+
+    fn icon_label(&self, _: &Window, cx: &App) -> Option<String> {
+        let count = self.project.read(cx).remote_listening_ports().len();
+        (count > 0).then(|| count.to_string())
+    }
+
+    fn toggle_action(&self) -> Box<dyn gpui::Action> {
+        Box::new(ToggleFocus)
+    }
+
+    fn activation_priority(&self) -> u32 {
+        9
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_forward() {
+        assert_eq!(
+            parse_forward("8080"),
+            Some(SshPortForwardOption {
+                local_host: None,
+                local_port: 8080,
+                remote_host: None,
+                remote_port: 8080,
+            })
+        );
+        assert_eq!(
+            parse_forward("8080:db.internal:5432"),
+            Some(SshPortForwardOption {
+                local_host: None,
+                local_port: 8080,
+                remote_host: Some("db.internal".into()),
+                remote_port: 5432,
+            })
+        );
+        assert_eq!(parse_forward("8080:"), None);
+        assert_eq!(parse_forward("http"), None);
+    }
+}
@@ -1,4 +1,6 @@
 pub mod disconnected_overlay;
+mod offline_edit_conflicts;
+pub mod ports_panel;
 mod remote_servers;
 mod ssh_connections;
 pub use ssh_connections::{is_connecting_over_ssh, open_ssh_project};
@@ -9,6 +11,7 @@ use gpui::{
     Action, AnyElement, App, Context, DismissEvent, Entity, EventEmitter, FocusHandle, Focusable,
     Subscription, Task, WeakEntity, Window,
 };
+use offline_edit_conflicts::OfflineEditConflicts;
 use ordered_float::OrderedFloat;
 use picker::{
     highlighted_match_with_paths::{HighlightedMatch, HighlightedMatchWithPaths},
@@ -31,9 +34,11 @@ use zed_actions::{OpenRecent, OpenRemote};
 
 pub fn init(cx: &mut App) {
     SshSettings::register(cx);
+    ports_panel::init(cx);
     cx.observe_new(RecentProjects::register).detach();
     cx.observe_new(RemoteServerProjects::register).detach();
     cx.observe_new(DisconnectedOverlay::register).detach();
+    cx.observe_new(OfflineEditConflicts::register).detach();
 }
 
 pub struct RecentProjects {
@@ -5,18 +5,19 @@ use anyhow::{anyhow, Result};
 use auto_update::AutoUpdater;
 use editor::Editor;
 use extension_host::ExtensionStore;
-use futures::channel::oneshot;
+use futures::{channel::oneshot, FutureExt as _};
 use gpui::{
     percentage, Animation, AnimationExt, AnyWindowHandle, App, AsyncApp, DismissEvent, Entity,
     EventEmitter, Focusable, FontFeatures, ParentElement as _, PromptLevel, Render,
     SemanticVersion, SharedString, Task, TextStyleRefinement, Transformation, WeakEntity,
+    WindowHandle,
 };
 
 use language::CursorShape;
 use markdown::{Markdown, MarkdownStyle};
 use release_channel::ReleaseChannel;
 use remote::ssh_session::{ConnectionIdentifier, SshPortForwardOption};
-use remote::{SshConnectionOptions, SshPlatform, SshRemoteClient};
+use remote::{RemoteTransport, SshConnectionOptions, SshPlatform, SshRemoteClient};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use settings::{Settings, SettingsSources};
@@ -54,6 +55,7 @@ impl SshSettings {
                     username,
                     port_forwards: conn.port_forwards,
                     password: None,
+                    transport: conn.transport.unwrap_or_default(),
                 };
             }
         }
@@ -90,6 +92,12 @@ pub struct SshConnection {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub port_forwards: Option<Vec<SshPortForwardOption>>,
+
+    /// How to reach the host, when it isn't over SSH. For example, a container
+    /// can be reached with:
+    /// `{ "kind": "command", "program": "docker", "args": ["exec", "-i", "my-container"] }`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transport: Option<RemoteTransport>,
 }
 
 impl From<SshConnection> for SshConnectionOptions {
@@ -103,6 +111,7 @@ impl From<SshConnection> for SshConnectionOptions {
             nickname: val.nickname,
             upload_binary_over_ssh: val.upload_binary_over_ssh.unwrap_or_default(),
             port_forwards: val.port_forwards,
+            transport: val.transport.unwrap_or_default(),
         }
     }
 }
@@ -654,3 +663,59 @@ pub async fn open_ssh_project(
     // Already showed the error to the user
     Ok(())
 }
+
+/// Reconnects the project in the given window to the server it was connected
+/// to before the connection was lost, keeping its open buffers and worktrees.
+pub async fn resume_ssh_project(
+    ssh_client: Entity<SshRemoteClient>,
+    window: WindowHandle<Workspace>,
+    cx: &mut AsyncApp,
+) -> Result<()> {
+    let (cancel_tx, cancel_rx) = oneshot::channel();
+    let delegate = window.update(cx, |workspace, window, cx| {
+        let connection_options = ssh_client.read(cx).connection_options();
+        let paths = workspace
+            .serialized_ssh_project()
+            .map(|project| project.paths.iter().map(PathBuf::from).collect())
+            .unwrap_or_default();
+        window.activate_window();
+        workspace.toggle_modal(window, cx, |window, cx| {
+            SshConnectionModal::new(&connection_options, paths, window, cx)
+        });
+
+        let ui = workspace
+            .active_modal::<SshConnectionModal>(cx)?
+            .read(cx)
+            .prompt
+            .clone();
+
+        ui.update(cx, |ui, _cx| {
+            ui.set_cancellation_tx(cancel_tx);
+        });
+
+        Some(Arc::new(SshClientDelegate {
+            window: window.window_handle(),
+            ui: ui.downgrade(),
+            known_password: connection_options.password.clone(),
+        }))
+    })?;
+    let Some(delegate) = delegate else {
+        return Err(anyhow!("failed to show the connection modal"));
+    };
+
+    let resume = ssh_client.update(cx, |ssh_client, cx| ssh_client.resume(delegate, cx))?;
+    let result = futures::select! {
+        _ = cancel_rx.fuse() => Err(anyhow!("reconnection was canceled")),
+        result = resume.fuse() => result,
+    };
+
+    window
+        .update(cx, |workspace, _, cx| {
+            if let Some(ui) = workspace.active_modal::<SshConnectionModal>(cx) {
+                ui.update(cx, |modal, cx| modal.finished(cx))
+            }
+        })
+        .ok();
+
+    result
+}
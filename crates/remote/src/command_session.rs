@@ -0,0 +1,423 @@
+use crate::{
+    shell_script,
+    ssh_session::{
+        multiplex, proxy_command, RemoteConnection, RemoteShell, RemoteTransport,
+        SshClientDelegate, SshConnectionOptions,
+    },
+};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures::{
+    channel::{
+        mpsc::{Sender, UnboundedReceiver, UnboundedSender},
+        oneshot,
+    },
+    AsyncWriteExt as _, FutureExt as _,
+};
+use gpui::{App, AppContext as _, AsyncApp, Task};
+use parking_lot::Mutex;
+use release_channel::{AppCommitSha, AppVersion, ReleaseChannel};
+use rpc::proto::Envelope;
+use smol::{
+    fs,
+    process::{self, Stdio},
+};
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering::SeqCst},
+        Arc,
+    },
+};
+
+/// Runs commands on the remote host by appending them to a user-provided
+/// command, or directly on this machine when there is none.
+struct CommandShell {
+    prefix: Option<(String, Vec<String>)>,
+}
+
+/// A connection to a remote host reached through an arbitrary command, as
+/// described by [`RemoteTransport::Command`], or to this machine, as
+/// described by [`RemoteTransport::Local`].
+///
+/// Unlike SSH, these commands don't keep a master connection open: each
+/// command spawns its own process, so killing the connection kills the
+/// processes of the proxies it started and prevents new ones from starting.
+pub(crate) struct CommandRemoteConnection {
+    shell: CommandShell,
+    connection_options: SshConnectionOptions,
+    remote_binary_path: PathBuf,
+    killed: AtomicBool,
+    /// Stop the running proxies when signalled or dropped, which kills their processes.
+    kill_proxy_txs: Mutex<Vec<oneshot::Sender<()>>>,
+}
+
+impl CommandShell {
+    fn new(transport: &RemoteTransport) -> Result<Self> {
+        let prefix = match transport {
+            RemoteTransport::Command { program, args, .. } => Some((program.clone(), args.clone())),
+            RemoteTransport::Local => None,
+            RemoteTransport::Ssh => {
+                return Err(anyhow!("ssh connections must use SshRemoteConnection"))
+            }
+        };
+        Ok(Self { prefix })
+    }
+}
+
+#[async_trait(?Send)]
+impl RemoteShell for CommandShell {
+    fn command(&self, program: &str, args: &[&str]) -> process::Command {
+        // Exec-style commands don't re-parse their arguments through a shell,
+        // so they are passed as-is, after moving to the home directory like
+        // SSH sessions do.
+        let home_script = "cd && exec \"$0\" \"$@\"";
+        let mut command = match &self.prefix {
+            Some((prefix_program, prefix_args)) => {
+                let mut command = util::command::new_smol_command(prefix_program);
+                command.args(prefix_args).arg("sh");
+                command
+            }
+            None => util::command::new_smol_command("sh"),
+        };
+        log::debug!("{:?} {program} {args:?}", self.prefix);
+        command
+            .args(["-c", home_script, program])
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        command
+    }
+
+    async fn upload_file(&self, src_path: &Path, dest_path: &Path) -> Result<()> {
+        log::debug!("uploading file {:?} to {:?}", src_path, dest_path);
+        let contents = fs::read(src_path).await?;
+        let script = shell_script!(
+            "cat > {dest_path}",
+            dest_path = &dest_path.to_string_lossy()
+        );
+        let mut child = self.command("sh", &["-c", &script]).spawn()?;
+        let mut stdin = child.stdin.take().unwrap();
+        stdin.write_all(&contents).await?;
+        stdin.close().await?;
+        drop(stdin);
+
+        let output = child.output().await?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "failed to upload file {} -> {}: {}",
+                src_path.display(),
+                dest_path.display(),
+                String::from_utf8_lossy(&output.stderr)
+            ))
+        }
+    }
+}
+
+impl CommandRemoteConnection {
+    pub(crate) async fn new(
+        connection_options: SshConnectionOptions,
+        delegate: Arc<dyn SshClientDelegate>,
+        cx: &mut AsyncApp,
+    ) -> Result<Self> {
+        delegate.set_status(Some("Connecting"), cx);
+        let shell = CommandShell::new(&connection_options.transport)?;
+        shell
+            .run_command("true", &[])
+            .await
+            .map_err(|error| error.context("Failed to connect to host"))?;
+
+        let (release_channel, version, commit) = cx.update(|cx| {
+            (
+                ReleaseChannel::global(cx),
+                AppVersion::global(cx),
+                AppCommitSha::try_global(cx),
+            )
+        })?;
+        let remote_binary_path = shell
+            .ensure_server_binary(
+                connection_options.upload_binary_over_ssh,
+                &delegate,
+                release_channel,
+                version,
+                commit,
+                cx,
+            )
+            .await?;
+
+        Ok(Self {
+            shell,
+            connection_options,
+            remote_binary_path,
+            killed: AtomicBool::new(false),
+            kill_proxy_txs: Mutex::default(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl RemoteConnection for CommandRemoteConnection {
+    fn start_proxy(
+        &self,
+        unique_identifier: String,
+        reconnect: bool,
+        incoming_tx: UnboundedSender<Envelope>,
+        outgoing_rx: UnboundedReceiver<Envelope>,
+        connection_activity_tx: Sender<()>,
+        delegate: Arc<dyn SshClientDelegate>,
+        cx: &mut AsyncApp,
+    ) -> Task<Result<i32>> {
+        if self.has_been_killed() {
+            return Task::ready(Err(anyhow!("connection has been killed")));
+        }
+        delegate.set_status(Some("Starting proxy"), cx);
+
+        let start_proxy_command =
+            proxy_command(&self.remote_binary_path, &unique_identifier, reconnect);
+        let proxy_process = match self
+            .shell
+            .command("sh", &["-c", &start_proxy_command])
+            // IMPORTANT: we kill this process when we drop the task that uses it.
+            .kill_on_drop(true)
+            .spawn()
+        {
+            Ok(process) => process,
+            Err(error) => {
+                return Task::ready(Err(anyhow!("failed to spawn remote server: {}", error)))
+            }
+        };
+
+        let proxy = multiplex(
+            proxy_process,
+            incoming_tx,
+            outgoing_rx,
+            connection_activity_tx,
+            &cx,
+        );
+        let (kill_tx, kill_rx) = oneshot::channel();
+        let mut kill_proxy_txs = self.kill_proxy_txs.lock();
+        kill_proxy_txs.retain(|kill_tx| !kill_tx.is_canceled());
+        kill_proxy_txs.push(kill_tx);
+        drop(kill_proxy_txs);
+        cx.spawn(|_| async move {
+            futures::select! {
+                result = proxy.fuse() => result,
+                _ = kill_rx.fuse() => Err(anyhow!("connection has been killed")),
+            }
+        })
+    }
+
+    fn upload_directory(
+        &self,
+        src_path: PathBuf,
+        dest_path: PathBuf,
+        cx: &App,
+    ) -> Task<Result<()>> {
+        let script = shell_script!(
+            "mkdir -p {dest_path} && tar -xzf - -C {dest_path}",
+            dest_path = &dest_path.to_string_lossy()
+        );
+        let mut extract = self.shell.command("sh", &["-c", &script]);
+        cx.background_spawn(async move {
+            let archive = util::command::new_smol_command("tar")
+                .arg("-czf")
+                .arg("-")
+                .arg("-C")
+                .arg(&src_path)
+                .arg(".")
+                .output()
+                .await?;
+            if !archive.status.success() {
+                return Err(anyhow!(
+                    "failed to archive directory {}: {}",
+                    src_path.display(),
+                    String::from_utf8_lossy(&archive.stderr)
+                ));
+            }
+
+            let mut child = extract.spawn()?;
+            let mut stdin = child.stdin.take().unwrap();
+            stdin.write_all(&archive.stdout).await?;
+            stdin.close().await?;
+            drop(stdin);
+
+            let output = child.output().await?;
+            if !output.status.success() {
+                return Err(anyhow!(
+                    "failed to upload directory {} -> {}: {}",
+                    src_path.display(),
+                    dest_path.display(),
+                    String::from_utf8_lossy(&output.stderr)
+                ));
+            }
+            Ok(())
+        })
+    }
+
+    async fn kill(&self) -> Result<()> {
+        self.killed.store(true, SeqCst);
+        for kill_tx in self.kill_proxy_txs.lock().drain(..) {
+            kill_tx.send(()).ok();
+        }
+        Ok(())
+    }
+
+    fn has_been_killed(&self) -> bool {
+        self.killed.load(SeqCst)
+    }
+
+    fn ssh_args(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn connection_options(&self) -> SshConnectionOptions {
+        self.connection_options.clone()
+    }
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use super::*;
+    use crate::ssh_session::fake::Delegate;
+    use futures::{channel::mpsc, AsyncBufReadExt as _, AsyncWriteExt as _, StreamExt as _};
+    use gpui::TestAppContext;
+    use smol::io::BufReader;
+    use std::{fs::Permissions, os::unix::fs::PermissionsExt as _, time::Duration};
+
+    #[gpui::test]
+    async fn test_local_command_output(cx: &mut TestAppContext) {
+        cx.executor().allow_parking();
+        let shell = CommandShell::new(&RemoteTransport::Local).unwrap();
+
+        // Output is streamed while the command is running.
+        let mut child = shell.command("cat", &[]).spawn().unwrap();
+        let mut stdin = child.stdin.take().unwrap();
+        let mut stdout = BufReader::new(child.stdout.take().unwrap()).lines();
+        stdin.write_all(b"one\n").await.unwrap();
+        stdin.flush().await.unwrap();
+        assert_eq!(stdout.next().await.unwrap().unwrap(), "one");
+        stdin.write_all(b"two\n").await.unwrap();
+        stdin.flush().await.unwrap();
+        assert_eq!(stdout.next().await.unwrap().unwrap(), "two");
+        drop(stdin);
+        assert!(stdout.next().await.is_none());
+        assert!(child.status().await.unwrap().success());
+
+        // Commands run from the home directory, with stdout and stderr kept apart.
+        let output = shell
+            .command("sh", &["-c", "pwd; echo error >&2; exit 3"])
+            .output()
+            .await
+            .unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout).trim(),
+            util::paths::home_dir().to_string_lossy()
+        );
+        assert_eq!(String::from_utf8_lossy(&output.stderr), "error\n");
+        assert_eq!(output.status.code(), Some(3));
+
+        let error = shell
+            .run_command("sh", &["-c", "echo error >&2; exit 1"])
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "failed to run command: error\n");
+    }
+
+    #[gpui::test]
+    async fn test_proxy_exit_status(cx: &mut TestAppContext) {
+        cx.executor().allow_parking();
+        let dir = tempfile::tempdir().unwrap();
+        let connection = local_connection(dir.path(), "exit 7");
+
+        let status = start_proxy(&connection, cx).await;
+        assert_eq!(status.unwrap(), 7);
+    }
+
+    #[gpui::test]
+    async fn test_kill_terminates_proxy(cx: &mut TestAppContext) {
+        cx.executor().allow_parking();
+        let dir = tempfile::tempdir().unwrap();
+        let pid_path = dir.path().join("pid");
+        let connection = local_connection(
+            dir.path(),
+            &format!("echo $$ > {}\nexec sleep 1000", pid_path.display()),
+        );
+
+        let proxy = start_proxy(&connection, cx);
+        let pid = wait_until(|| {
+            std::fs::read_to_string(&pid_path)
+                .ok()
+                .filter(|pid| pid.ends_with('\n'))
+        });
+
+        connection.kill().await.unwrap();
+        assert!(connection.has_been_killed());
+        assert_eq!(
+            proxy.await.unwrap_err().to_string(),
+            "connection has been killed"
+        );
+        wait_until(|| {
+            let running = std::process::Command::new("kill")
+                .args(["-0", pid.trim()])
+                .stderr(Stdio::null())
+                .status()
+                .unwrap()
+                .success();
+            (!running).then_some(())
+        });
+
+        // No new proxies are started once the connection has been killed.
+        assert_eq!(
+            start_proxy(&connection, cx).await.unwrap_err().to_string(),
+            "connection has been killed"
+        );
+    }
+
+    /// A connection to this machine whose server binary runs the given script.
+    fn local_connection(dir: &Path, script: &str) -> CommandRemoteConnection {
+        let remote_binary_path = dir.join("server");
+        std::fs::write(&remote_binary_path, format!("#!/bin/sh\n{script}\n")).unwrap();
+        std::fs::set_permissions(&remote_binary_path, Permissions::from_mode(0o755)).unwrap();
+        CommandRemoteConnection {
+            shell: CommandShell::new(&RemoteTransport::Local).unwrap(),
+            connection_options: SshConnectionOptions {
+                transport: RemoteTransport::Local,
+                ..SshConnectionOptions::default()
+            },
+            remote_binary_path,
+            killed: AtomicBool::new(false),
+            kill_proxy_txs: Mutex::default(),
+        }
+    }
+
+    fn start_proxy(
+        connection: &CommandRemoteConnection,
+        cx: &mut TestAppContext,
+    ) -> Task<Result<i32>> {
+        let (incoming_tx, _incoming_rx) = mpsc::unbounded();
+        let (_outgoing_tx, outgoing_rx) = mpsc::unbounded();
+        let (connection_activity_tx, _connection_activity_rx) = mpsc::channel(1);
+        connection.start_proxy(
+            "test".into(),
+            false,
+            incoming_tx,
+            outgoing_rx,
+            connection_activity_tx,
+            Arc::new(Delegate),
+            &mut cx.to_async(),
+        )
+    }
+
+    fn wait_until<T>(mut condition: impl FnMut() -> Option<T>) -> T {
+        for _ in 0..500 {
+            if let Some(result) = condition() {
+                return result;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        panic!("timed out waiting for condition");
+    }
+}
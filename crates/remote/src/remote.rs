@@ -1,9 +1,10 @@
+mod command_session;
 pub mod json_log;
 pub mod protocol;
 pub mod proxy;
 pub mod ssh_session;
 
 pub use ssh_session::{
-    ConnectionState, SshClientDelegate, SshConnectionOptions, SshPlatform, SshRemoteClient,
-    SshRemoteEvent,
+    ConnectionState, RemoteConnection, RemoteTransport, SshClientDelegate, SshConnectionOptions,
+    SshPlatform, SshPortForwardOption, SshRemoteClient, SshRemoteEvent,
 };
@@ -1,4 +1,5 @@
 use crate::{
+    command_session::CommandRemoteConnection,
     json_log::LogRecord,
     protocol::{
         message_len_from_buffer, read_message_with_len, write_message, MessageId, MESSAGE_LEN_SIZE,
@@ -71,6 +72,43 @@ pub struct SshPortForwardOption {
     pub remote_port: u16,
 }
 
+impl SshPortForwardOption {
+    /// The `local_host:local_port:remote_host:remote_port` form of this
+    /// forward, as passed to `ssh -L`.
+    pub fn ssh_spec(&self) -> String {
+        format!(
+            "{}:{}:{}:{}",
+            self.local_host.as_deref().unwrap_or("localhost"),
+            self.local_port,
+            self.remote_host.as_deref().unwrap_or("localhost"),
+            self.remote_port
+        )
+    }
+}
+
+/// How Zed reaches the host running the remote server.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Deserialize, Serialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RemoteTransport {
+    /// Connect with `ssh`, using the host, port and username of the connection.
+    #[default]
+    Ssh,
+    /// Run a command that executes its trailing arguments on the remote host
+    /// with their stdio attached, such as `docker exec -i <container>`,
+    /// `kubectl exec -i <pod> --` or `distrobox enter <name> --`.
+    Command {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+        /// Arguments to use instead of `args` when opening terminals, e.g. to
+        /// allocate a TTY with `docker exec -it <container>`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        terminal_args: Option<Vec<String>>,
+    },
+    /// Run the remote server as a subprocess of this machine.
+    Local,
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
 pub struct SshConnectionOptions {
     pub host: String,
@@ -82,6 +120,7 @@ pub struct SshConnectionOptions {
 
     pub nickname: Option<String>,
     pub upload_binary_over_ssh: bool,
+    pub transport: RemoteTransport,
 }
 
 #[macro_export]
@@ -232,6 +271,7 @@ impl SshConnectionOptions {
             password: None,
             nickname: None,
             upload_binary_over_ssh: false,
+            transport: RemoteTransport::Ssh,
         })
     }
 
@@ -253,21 +293,7 @@ impl SshConnectionOptions {
         let mut args = self.args.iter().flatten().cloned().collect::<Vec<String>>();
 
         if let Some(forwards) = &self.port_forwards {
-            args.extend(forwards.iter().map(|pf| {
-                let local_host = match &pf.local_host {
-                    Some(host) => host,
-                    None => "localhost",
-                };
-                let remote_host = match &pf.remote_host {
-                    Some(host) => host,
-                    None => "localhost",
-                };
-
-                format!(
-                    "-L{}:{}:{}:{}",
-                    local_host, pf.local_port, remote_host, pf.remote_port
-                )
-            }));
+            args.extend(forwards.iter().map(|pf| format!("-L{}", pf.ssh_spec())));
         }
 
         args
@@ -363,18 +389,6 @@ impl SshSocket {
         command
     }
 
-    async fn run_command(&self, program: &str, args: &[&str]) -> Result<String> {
-        let output = self.ssh_command(program, args).output().await?;
-        if output.status.success() {
-            Ok(String::from_utf8_lossy(&output.stdout).to_string())
-        } else {
-            Err(anyhow!(
-                "failed to run command: {}",
-                String::from_utf8_lossy(&output.stderr)
-            ))
-        }
-    }
-
     fn ssh_options<'a>(&self, command: &'a mut process::Command) -> &'a mut process::Command {
         command
             .stdin(Stdio::piped())
@@ -384,6 +398,21 @@ impl SshSocket {
             .arg(format!("ControlPath={}", self.socket_path.display()))
     }
 
+    /// Adds or cancels a port forward on the master connection, where
+    /// `operation` is either `forward` or `cancel`.
+    fn port_forward_command(
+        &self,
+        operation: &str,
+        forward: &SshPortForwardOption,
+    ) -> process::Command {
+        let mut command = util::command::new_smol_command("ssh");
+        self.ssh_options(&mut command)
+            .args(["-O", operation])
+            .arg(format!("-L{}", forward.ssh_spec()))
+            .arg(self.connection_options.ssh_url());
+        command
+    }
+
     fn ssh_args(&self) -> Vec<String> {
         vec![
             "-o".to_string(),
@@ -560,12 +589,16 @@ pub struct SshRemoteClient {
     client: Arc<ChannelClient>,
     unique_identifier: String,
     connection_options: SshConnectionOptions,
+    port_forwards: Vec<SshPortForwardOption>,
     state: Arc<Mutex<Option<State>>>,
 }
 
 #[derive(Debug)]
 pub enum SshRemoteEvent {
     Disconnected,
+    /// The connection was re-established with [`SshRemoteClient::resume`], to
+    /// the same server process as before the disconnection.
+    Resumed,
 }
 
 impl EventEmitter<SshRemoteEvent> for SshRemoteClient {}
@@ -624,6 +657,7 @@ impl SshRemoteClient {
                     client: client.clone(),
                     unique_identifier: unique_identifier.clone(),
                     connection_options: connection_options.clone(),
+                    port_forwards: connection_options.port_forwards.clone().unwrap_or_default(),
                     state: Arc::new(Mutex::new(Some(State::Connecting))),
                 })?;
 
@@ -781,6 +815,7 @@ impl SshRemoteClient {
 
         let unique_identifier = self.unique_identifier.clone();
         let client = self.client.clone();
+        let port_forwards = self.port_forwards.clone();
         let reconnect_task = cx.spawn(|this, mut cx| async move {
             macro_rules! failed {
                 ($error:expr, $attempts:expr, $ssh_connection:expr, $delegate:expr) => {
@@ -841,6 +876,8 @@ impl SshRemoteClient {
                 failed!(error, attempts, ssh_connection, delegate);
             };
 
+            Self::restore_port_forwards(&ssh_connection, &port_forwards, &mut cx).await;
+
             State::Connected {
                 ssh_connection,
                 delegate,
@@ -896,6 +933,128 @@ impl SshRemoteClient {
         Ok(())
     }
 
+    /// Whether the connection was lost after exhausting its automatic
+    /// reconnection attempts, while the server may still be running.
+    pub fn can_resume(&self) -> bool {
+        self.state_is(State::is_reconnect_exhausted)
+    }
+
+    /// Reconnects to the server process that was running before automatic
+    /// reconnection attempts were exhausted, so that the state it holds on
+    /// behalf of this client, such as open buffers, is kept.
+    pub fn resume(
+        &mut self,
+        delegate: Arc<dyn SshClientDelegate>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        if !self.can_resume() {
+            return Task::ready(Err(anyhow!(
+                "cannot resume connection in state {}",
+                self.state
+                    .lock()
+                    .as_ref()
+                    .map_or("none".to_string(), |state| state.to_string())
+            )));
+        }
+        self.set_state(State::Reconnecting, cx);
+
+        let unique_identifier = self.unique_identifier.clone();
+        let client = self.client.clone();
+        let connection_options = self.connection_options.clone();
+        let port_forwards = self.port_forwards.clone();
+        cx.spawn(|this, mut cx| async move {
+            let (outgoing_tx, outgoing_rx) = mpsc::unbounded::<Envelope>();
+            let (incoming_tx, incoming_rx) = mpsc::unbounded::<Envelope>();
+            let (connection_activity_tx, connection_activity_rx) = mpsc::channel::<()>(1);
+
+            let result = async {
+                let ssh_connection = cx
+                    .update_global(|pool: &mut ConnectionPool, cx| {
+                        pool.connect(connection_options, &delegate, cx)
+                    })?
+                    .await
+                    .map_err(|error| error.cloned())?;
+
+                let io_task = ssh_connection.start_proxy(
+                    unique_identifier,
+                    true,
+                    incoming_tx,
+                    outgoing_rx,
+                    connection_activity_tx,
+                    delegate.clone(),
+                    &mut cx,
+                );
+                let multiplex_task = Self::monitor(this.clone(), io_task, &cx);
+                client.reconnect(incoming_rx, outgoing_tx, &cx);
+                client.resync(HEARTBEAT_TIMEOUT).await?;
+
+                Self::restore_port_forwards(&ssh_connection, &port_forwards, &mut cx).await;
+                anyhow::Ok((ssh_connection, multiplex_task))
+            }
+            .await;
+
+            match result {
+                Ok((ssh_connection, multiplex_task)) => {
+                    let heartbeat_task =
+                        Self::heartbeat(this.clone(), connection_activity_rx, &mut cx);
+                    this.update(&mut cx, |this, cx| {
+                        this.set_state(
+                            State::Connected {
+                                ssh_connection,
+                                delegate,
+                                multiplex_task,
+                                heartbeat_task,
+                            },
+                            cx,
+                        );
+                        cx.emit(SshRemoteEvent::Resumed);
+                    })
+                }
+                Err(error) => {
+                    log::error!("failed to resume ssh connection: {error:?}");
+                    this.update(&mut cx, |this, cx| {
+                        // The proxy reports when the server is gone, in which
+                        // case there's nothing left to resume.
+                        if !this.state_is(State::is_server_not_running) {
+                            this.set_state(State::ReconnectExhausted, cx);
+                        }
+                    })?;
+                    Err(error)
+                }
+            }
+        })
+    }
+
+    /// Makes the forwards of a new connection match the ones of the connection
+    /// it replaces, since forwards added or removed while connected aren't
+    /// part of the options the new connection was started with.
+    async fn restore_port_forwards(
+        ssh_connection: &Arc<dyn RemoteConnection>,
+        port_forwards: &[SshPortForwardOption],
+        cx: &mut AsyncApp,
+    ) {
+        let initial_forwards = ssh_connection
+            .connection_options()
+            .port_forwards
+            .unwrap_or_default();
+        for forward in &initial_forwards {
+            if !port_forwards.contains(forward) {
+                if let Ok(task) =
+                    cx.update(|cx| ssh_connection.cancel_port_forward(forward.clone(), cx))
+                {
+                    task.await.log_err();
+                }
+            }
+        }
+        for forward in port_forwards {
+            if !initial_forwards.contains(forward) {
+                if let Ok(task) = cx.update(|cx| ssh_connection.forward_port(forward.clone(), cx)) {
+                    task.await.log_err();
+                }
+            }
+        }
+    }
+
     fn heartbeat(
         this: WeakEntity<Self>,
         mut connection_activity_rx: mpsc::Receiver<()>,
@@ -1088,6 +1247,65 @@ impl SshRemoteClient {
         connection.upload_directory(src_path, dest_path, cx)
     }
 
+    /// The ports currently forwarded from this machine to the remote host.
+    pub fn port_forwards(&self) -> &[SshPortForwardOption] {
+        &self.port_forwards
+    }
+
+    /// Starts forwarding a local port to the remote host, without restarting
+    /// the connection.
+    pub fn add_port_forward(
+        &mut self,
+        forward: SshPortForwardOption,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        if self.port_forwards.contains(&forward) {
+            return Task::ready(Ok(()));
+        }
+        let task = {
+            let state = self.state.lock();
+            let Some(connection) = state.as_ref().and_then(|state| state.ssh_connection()) else {
+                return Task::ready(Err(anyhow!("no ssh connection")));
+            };
+            connection.forward_port(forward.clone(), cx)
+        };
+        cx.spawn(|this, mut cx| async move {
+            task.await?;
+            this.update(&mut cx, |this, cx| {
+                if !this.port_forwards.contains(&forward) {
+                    this.port_forwards.push(forward);
+                    cx.notify();
+                }
+            })
+        })
+    }
+
+    /// Stops forwarding a local port to the remote host, without restarting
+    /// the connection.
+    pub fn remove_port_forward(
+        &mut self,
+        forward: SshPortForwardOption,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        if !self.port_forwards.contains(&forward) {
+            return Task::ready(Ok(()));
+        }
+        let task = {
+            let state = self.state.lock();
+            let Some(connection) = state.as_ref().and_then(|state| state.ssh_connection()) else {
+                return Task::ready(Err(anyhow!("no ssh connection")));
+            };
+            connection.cancel_port_forward(forward.clone(), cx)
+        };
+        cx.spawn(|this, mut cx| async move {
+            task.await?;
+            this.update(&mut cx, |this, cx| {
+                this.port_forwards.retain(|existing| existing != &forward);
+                cx.notify();
+            })
+        })
+    }
+
     pub fn proto_client(&self) -> AnyProtoClient {
         self.client.clone().into()
     }
@@ -1132,6 +1350,18 @@ impl SshRemoteClient {
         })
     }
 
+    /// Drops the connection as if every automatic reconnection attempt had failed,
+    /// leaving it to be resumed with [`Self::simulate_resume`].
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn simulate_reconnect_exhausted(&mut self, cx: &mut Context<Self>) {
+        self.set_state(State::ReconnectExhausted, cx);
+    }
+
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn simulate_resume(&mut self, cx: &mut Context<Self>) -> Task<Result<()>> {
+        self.resume(Arc::new(fake::Delegate), cx)
+    }
+
     #[cfg(any(test, feature = "test-support"))]
     pub fn fake_server(
         client_cx: &mut gpui::TestAppContext,
@@ -1239,9 +1469,18 @@ impl ConnectionPool {
                 let opts = opts.clone();
                 let delegate = delegate.clone();
                 |mut cx| async move {
-                    let connection = SshRemoteConnection::new(opts.clone(), delegate, &mut cx)
-                        .await
-                        .map(|connection| Arc::new(connection) as Arc<dyn RemoteConnection>);
+                    let connection = match &opts.transport {
+                        RemoteTransport::Ssh => {
+                            SshRemoteConnection::new(opts.clone(), delegate, &mut cx)
+                                .await
+                                .map(|connection| Arc::new(connection) as Arc<dyn RemoteConnection>)
+                        }
+                        RemoteTransport::Command { .. } | RemoteTransport::Local => {
+                            CommandRemoteConnection::new(opts.clone(), delegate, &mut cx)
+                                .await
+                                .map(|connection| Arc::new(connection) as Arc<dyn RemoteConnection>)
+                        }
+                    };
 
                     cx.update_global(|pool: &mut Self, _| {
                         debug_assert!(matches!(
@@ -1278,8 +1517,10 @@ impl From<SshRemoteClient> for AnyProtoClient {
     }
 }
 
+/// A live connection to the host of a remote server, able to start the
+/// server's proxy and exchange messages with it over the proxy's stdio.
 #[async_trait(?Send)]
-trait RemoteConnection: Send + Sync {
+pub trait RemoteConnection: Send + Sync {
     #[allow(clippy::too_many_arguments)]
     fn start_proxy(
         &self,
@@ -1298,6 +1539,24 @@ trait RemoteConnection: Send + Sync {
     fn ssh_args(&self) -> Vec<String>;
     fn connection_options(&self) -> SshConnectionOptions;
 
+    /// Forwards a local port to the remote host for as long as this
+    /// connection is alive.
+    fn forward_port(&self, forward: SshPortForwardOption, _: &App) -> Task<Result<()>> {
+        Task::ready(Err(anyhow!(
+            "cannot forward port {}: port forwarding is only supported over ssh",
+            forward.local_port
+        )))
+    }
+
+    /// Stops a forward started with [`RemoteConnection::forward_port`] or
+    /// passed in the options of this connection.
+    fn cancel_port_forward(&self, forward: SshPortForwardOption, _: &App) -> Task<Result<()>> {
+        Task::ready(Err(anyhow!(
+            "cannot cancel forward of port {}: port forwarding is only supported over ssh",
+            forward.local_port
+        )))
+    }
+
     #[cfg(any(test, feature = "test-support"))]
     fn simulate_disconnect(&self, _: &AsyncApp) {}
 }
@@ -1332,6 +1591,14 @@ impl RemoteConnection for SshRemoteConnection {
         self.socket.connection_options.clone()
     }
 
+    fn forward_port(&self, forward: SshPortForwardOption, cx: &App) -> Task<Result<()>> {
+        self.run_port_forward_command("forward", forward, cx)
+    }
+
+    fn cancel_port_forward(&self, forward: SshPortForwardOption, cx: &App) -> Task<Result<()>> {
+        self.run_port_forward_command("cancel", forward, cx)
+    }
+
     fn upload_directory(
         &self,
         src_path: PathBuf,
@@ -1391,29 +1658,7 @@ impl RemoteConnection for SshRemoteConnection {
             return Task::ready(Err(anyhow!("Remote binary path not set")));
         };
 
-        let mut start_proxy_command = shell_script!(
-            "exec {binary_path} proxy --identifier {identifier}",
-            binary_path = &remote_binary_path.to_string_lossy(),
-            identifier = &unique_identifier,
-        );
-
-        if let Some(rust_log) = std::env::var("RUST_LOG").ok() {
-            start_proxy_command = format!(
-                "RUST_LOG={} {}",
-                shlex::try_quote(&rust_log).unwrap(),
-                start_proxy_command
-            )
-        }
-        if let Some(rust_backtrace) = std::env::var("RUST_BACKTRACE").ok() {
-            start_proxy_command = format!(
-                "RUST_BACKTRACE={} {}",
-                shlex::try_quote(&rust_backtrace).unwrap(),
-                start_proxy_command
-            )
-        }
-        if reconnect {
-            start_proxy_command.push_str(" --reconnect");
-        }
+        let start_proxy_command = proxy_command(&remote_binary_path, &unique_identifier, reconnect);
 
         let ssh_proxy_process = match self
             .socket
@@ -1428,7 +1673,7 @@ impl RemoteConnection for SshRemoteConnection {
             }
         };
 
-        Self::multiplex(
+        multiplex(
             ssh_proxy_process,
             incoming_tx,
             outgoing_rx,
@@ -1626,15 +1871,242 @@ impl SshRemoteConnection {
             )
         })?;
         this.remote_binary_path = Some(
-            this.ensure_server_binary(&delegate, release_channel, version, commit, cx)
+            this.socket
+                .ensure_server_binary(
+                    this.socket.connection_options.upload_binary_over_ssh,
+                    &delegate,
+                    release_channel,
+                    version,
+                    commit,
+                    cx,
+                )
                 .await?,
         );
 
         Ok(this)
     }
 
+    fn run_port_forward_command(
+        &self,
+        operation: &'static str,
+        forward: SshPortForwardOption,
+        cx: &App,
+    ) -> Task<Result<()>> {
+        let output = self
+            .socket
+            .port_forward_command(operation, &forward)
+            .output();
+        cx.background_spawn(async move {
+            let output = output.await?;
+            if !output.status.success() {
+                return Err(anyhow!(
+                    "failed to {operation} port {}: {}",
+                    forward.ssh_spec(),
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+            }
+            Ok(())
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl RemoteShell for SshSocket {
+    fn command(&self, program: &str, args: &[&str]) -> process::Command {
+        self.ssh_command(program, args)
+    }
+
+    async fn upload_file(&self, src_path: &Path, dest_path: &Path) -> Result<()> {
+        log::debug!("uploading file {:?} to {:?}", src_path, dest_path);
+        let mut command = util::command::new_smol_command("scp");
+        let output = self
+            .ssh_options(&mut command)
+            .args(
+                self.connection_options
+                    .port
+                    .map(|port| vec!["-P".to_string(), port.to_string()])
+                    .unwrap_or_default(),
+            )
+            .arg(src_path)
+            .arg(format!(
+                "{}:{}",
+                self.connection_options.scp_url(),
+                dest_path.display()
+            ))
+            .output()
+            .await?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "failed to upload file {} -> {}: {}",
+                src_path.display(),
+                dest_path.display(),
+                String::from_utf8_lossy(&output.stderr)
+            ))
+        }
+    }
+}
+
+/// The shell script that starts the remote server's proxy, to be run through `sh -c`.
+pub(crate) fn proxy_command(
+    remote_binary_path: &Path,
+    unique_identifier: &str,
+    reconnect: bool,
+) -> String {
+    let mut start_proxy_command = shell_script!(
+        "exec {binary_path} proxy --identifier {identifier}",
+        binary_path = &remote_binary_path.to_string_lossy(),
+        identifier = unique_identifier,
+    );
+
+    if let Some(rust_log) = std::env::var("RUST_LOG").ok() {
+        start_proxy_command = format!(
+            "RUST_LOG={} {}",
+            shlex::try_quote(&rust_log).unwrap(),
+            start_proxy_command
+        )
+    }
+    if let Some(rust_backtrace) = std::env::var("RUST_BACKTRACE").ok() {
+        start_proxy_command = format!(
+            "RUST_BACKTRACE={} {}",
+            shlex::try_quote(&rust_backtrace).unwrap(),
+            start_proxy_command
+        )
+    }
+    if reconnect {
+        start_proxy_command.push_str(" --reconnect");
+    }
+    start_proxy_command
+}
+
+pub(crate) fn multiplex(
+    mut proxy_process: Child,
+    incoming_tx: UnboundedSender<Envelope>,
+    mut outgoing_rx: UnboundedReceiver<Envelope>,
+    mut connection_activity_tx: Sender<()>,
+    cx: &AsyncApp,
+) -> Task<Result<i32>> {
+    let mut child_stderr = proxy_process.stderr.take().unwrap();
+    let mut child_stdout = proxy_process.stdout.take().unwrap();
+    let mut child_stdin = proxy_process.stdin.take().unwrap();
+
+    let mut stdin_buffer = Vec::new();
+    let mut stdout_buffer = Vec::new();
+    let mut stderr_buffer = Vec::new();
+    let mut stderr_offset = 0;
+
+    let stdin_task = cx.background_spawn(async move {
+        while let Some(outgoing) = outgoing_rx.next().await {
+            write_message(&mut child_stdin, &mut stdin_buffer, outgoing).await?;
+        }
+        anyhow::Ok(())
+    });
+
+    let stdout_task = cx.background_spawn({
+        let mut connection_activity_tx = connection_activity_tx.clone();
+        async move {
+            loop {
+                stdout_buffer.resize(MESSAGE_LEN_SIZE, 0);
+                let len = child_stdout.read(&mut stdout_buffer).await?;
+
+                if len == 0 {
+                    return anyhow::Ok(());
+                }
+
+                if len < MESSAGE_LEN_SIZE {
+                    child_stdout.read_exact(&mut stdout_buffer[len..]).await?;
+                }
+
+                let message_len = message_len_from_buffer(&stdout_buffer);
+                let envelope =
+                    read_message_with_len(&mut child_stdout, &mut stdout_buffer, message_len)
+                        .await?;
+                connection_activity_tx.try_send(()).ok();
+                incoming_tx.unbounded_send(envelope).ok();
+            }
+        }
+    });
+
+    let stderr_task: Task<anyhow::Result<()>> = cx.background_spawn(async move {
+        loop {
+            stderr_buffer.resize(stderr_offset + 1024, 0);
+
+            let len = child_stderr
+                .read(&mut stderr_buffer[stderr_offset..])
+                .await?;
+            if len == 0 {
+                return anyhow::Ok(());
+            }
+
+            stderr_offset += len;
+            let mut start_ix = 0;
+            while let Some(ix) = stderr_buffer[start_ix..stderr_offset]
+                .iter()
+                .position(|b| b == &b'\n')
+            {
+                let line_ix = start_ix + ix;
+                let content = &stderr_buffer[start_ix..line_ix];
+                start_ix = line_ix + 1;
+                if let Ok(record) = serde_json::from_slice::<LogRecord>(content) {
+                    record.log(log::logger())
+                } else {
+                    eprintln!("(remote) {}", String::from_utf8_lossy(content));
+                }
+            }
+            stderr_buffer.drain(0..start_ix);
+            stderr_offset -= start_ix;
+
+            connection_activity_tx.try_send(()).ok();
+        }
+    });
+
+    cx.spawn(|_| async move {
+        let result = futures::select! {
+            result = stdin_task.fuse() => {
+                result.context("stdin")
+            }
+            result = stdout_task.fuse() => {
+                result.context("stdout")
+            }
+            result = stderr_task.fuse() => {
+                result.context("stderr")
+            }
+        };
+
+        let status = proxy_process.status().await?.code().unwrap_or(1);
+        match result {
+            Ok(_) => Ok(status),
+            Err(error) => Err(error),
+        }
+    })
+}
+
+/// Runs commands on the host of a remote connection, which is all that is needed
+/// to install the remote server there.
+#[async_trait(?Send)]
+pub(crate) trait RemoteShell: Send + Sync {
+    /// Builds a command that runs `program` on the remote host, from its home
+    /// directory, with its stdio piped to this process.
+    fn command(&self, program: &str, args: &[&str]) -> process::Command;
+
+    async fn upload_file(&self, src_path: &Path, dest_path: &Path) -> Result<()>;
+
+    async fn run_command(&self, program: &str, args: &[&str]) -> Result<String> {
+        let output = self.command(program, args).output().await?;
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        } else {
+            Err(anyhow!(
+                "failed to run command: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ))
+        }
+    }
+
     async fn platform(&self) -> Result<SshPlatform> {
-        let uname = self.socket.run_command("uname", &["-sm"]).await?;
+        let uname = self.run_command("uname", &["-sm"]).await?;
         let Some((os, arch)) = uname.split_once(" ") else {
             Err(anyhow!("unknown uname: {uname:?}"))?
         };
@@ -1664,111 +2136,9 @@ impl SshRemoteConnection {
         Ok(SshPlatform { os, arch })
     }
 
-    fn multiplex(
-        mut ssh_proxy_process: Child,
-        incoming_tx: UnboundedSender<Envelope>,
-        mut outgoing_rx: UnboundedReceiver<Envelope>,
-        mut connection_activity_tx: Sender<()>,
-        cx: &AsyncApp,
-    ) -> Task<Result<i32>> {
-        let mut child_stderr = ssh_proxy_process.stderr.take().unwrap();
-        let mut child_stdout = ssh_proxy_process.stdout.take().unwrap();
-        let mut child_stdin = ssh_proxy_process.stdin.take().unwrap();
-
-        let mut stdin_buffer = Vec::new();
-        let mut stdout_buffer = Vec::new();
-        let mut stderr_buffer = Vec::new();
-        let mut stderr_offset = 0;
-
-        let stdin_task = cx.background_spawn(async move {
-            while let Some(outgoing) = outgoing_rx.next().await {
-                write_message(&mut child_stdin, &mut stdin_buffer, outgoing).await?;
-            }
-            anyhow::Ok(())
-        });
-
-        let stdout_task = cx.background_spawn({
-            let mut connection_activity_tx = connection_activity_tx.clone();
-            async move {
-                loop {
-                    stdout_buffer.resize(MESSAGE_LEN_SIZE, 0);
-                    let len = child_stdout.read(&mut stdout_buffer).await?;
-
-                    if len == 0 {
-                        return anyhow::Ok(());
-                    }
-
-                    if len < MESSAGE_LEN_SIZE {
-                        child_stdout.read_exact(&mut stdout_buffer[len..]).await?;
-                    }
-
-                    let message_len = message_len_from_buffer(&stdout_buffer);
-                    let envelope =
-                        read_message_with_len(&mut child_stdout, &mut stdout_buffer, message_len)
-                            .await?;
-                    connection_activity_tx.try_send(()).ok();
-                    incoming_tx.unbounded_send(envelope).ok();
-                }
-            }
-        });
-
-        let stderr_task: Task<anyhow::Result<()>> = cx.background_spawn(async move {
-            loop {
-                stderr_buffer.resize(stderr_offset + 1024, 0);
-
-                let len = child_stderr
-                    .read(&mut stderr_buffer[stderr_offset..])
-                    .await?;
-                if len == 0 {
-                    return anyhow::Ok(());
-                }
-
-                stderr_offset += len;
-                let mut start_ix = 0;
-                while let Some(ix) = stderr_buffer[start_ix..stderr_offset]
-                    .iter()
-                    .position(|b| b == &b'\n')
-                {
-                    let line_ix = start_ix + ix;
-                    let content = &stderr_buffer[start_ix..line_ix];
-                    start_ix = line_ix + 1;
-                    if let Ok(record) = serde_json::from_slice::<LogRecord>(content) {
-                        record.log(log::logger())
-                    } else {
-                        eprintln!("(remote) {}", String::from_utf8_lossy(content));
-                    }
-                }
-                stderr_buffer.drain(0..start_ix);
-                stderr_offset -= start_ix;
-
-                connection_activity_tx.try_send(()).ok();
-            }
-        });
-
-        cx.spawn(|_| async move {
-            let result = futures::select! {
-                result = stdin_task.fuse() => {
-                    result.context("stdin")
-                }
-                result = stdout_task.fuse() => {
-                    result.context("stdout")
-                }
-                result = stderr_task.fuse() => {
-                    result.context("stderr")
-                }
-            };
-
-            let status = ssh_proxy_process.status().await?.code().unwrap_or(1);
-            match result {
-                Ok(_) => Ok(status),
-                Err(error) => Err(error),
-            }
-        })
-    }
-
-    #[allow(unused)]
     async fn ensure_server_binary(
         &self,
+        upload_binary_locally: bool,
         delegate: &Arc<dyn SshClientDelegate>,
         release_channel: ReleaseChannel,
         version: SemanticVersion,
@@ -1809,7 +2179,6 @@ impl SshRemoteConnection {
         }
 
         if self
-            .socket
             .run_command(&dst_path.to_string_lossy(), &["version"])
             .await
             .is_ok()
@@ -1830,7 +2199,7 @@ impl SshRemoteConnection {
 
         let platform = self.platform().await?;
 
-        if !self.socket.connection_options.upload_binary_over_ssh {
+        if !upload_binary_locally {
             if let Some((url, body)) = delegate
                 .get_download_params(platform, release_channel, wanted_version, cx)
                 .await?
@@ -1873,15 +2242,13 @@ impl SshRemoteConnection {
         cx: &mut AsyncApp,
     ) -> Result<()> {
         if let Some(parent) = tmp_path_gz.parent() {
-            self.socket
-                .run_command("mkdir", &["-p", &parent.to_string_lossy()])
+            self.run_command("mkdir", &["-p", &parent.to_string_lossy()])
                 .await?;
         }
 
         delegate.set_status(Some("Downloading remote development server on host"), cx);
 
         match self
-            .socket
             .run_command(
                 "curl",
                 &[
@@ -1902,12 +2269,11 @@ impl SshRemoteConnection {
         {
             Ok(_) => {}
             Err(e) => {
-                if self.socket.run_command("which", &["curl"]).await.is_ok() {
+                if self.run_command("which", &["curl"]).await.is_ok() {
                     return Err(e);
                 }
 
                 match self
-                    .socket
                     .run_command(
                         "wget",
                         &[
@@ -1924,7 +2290,7 @@ impl SshRemoteConnection {
                 {
                     Ok(_) => {}
                     Err(e) => {
-                        if self.socket.run_command("which", &["wget"]).await.is_ok() {
+                        if self.run_command("which", &["wget"]).await.is_ok() {
                             return Err(e);
                         } else {
                             anyhow::bail!("Neither curl nor wget is available");
@@ -1945,8 +2311,7 @@ impl SshRemoteConnection {
         cx: &mut AsyncApp,
     ) -> Result<()> {
         if let Some(parent) = tmp_path_gz.parent() {
-            self.socket
-                .run_command("mkdir", &["-p", &parent.to_string_lossy()])
+            self.run_command("mkdir", &["-p", &parent.to_string_lossy()])
                 .await?;
         }
 
@@ -1984,44 +2349,10 @@ impl SshRemoteConnection {
             server_mode = &format!("{:o}", server_mode),
             dst_path = &dst_path.to_string_lossy()
         );
-        self.socket.run_command("sh", &["-c", &script]).await?;
+        self.run_command("sh", &["-c", &script]).await?;
         Ok(())
     }
 
-    async fn upload_file(&self, src_path: &Path, dest_path: &Path) -> Result<()> {
-        log::debug!("uploading file {:?} to {:?}", src_path, dest_path);
-        let mut command = util::command::new_smol_command("scp");
-        let output = self
-            .socket
-            .ssh_options(&mut command)
-            .args(
-                self.socket
-                    .connection_options
-                    .port
-                    .map(|port| vec!["-P".to_string(), port.to_string()])
-                    .unwrap_or_default(),
-            )
-            .arg(src_path)
-            .arg(format!(
-                "{}:{}",
-                self.socket.connection_options.scp_url(),
-                dest_path.display()
-            ))
-            .output()
-            .await?;
-
-        if output.status.success() {
-            Ok(())
-        } else {
-            Err(anyhow!(
-                "failed to upload file {} -> {}: {}",
-                src_path.display(),
-                dest_path.display(),
-                String::from_utf8_lossy(&output.stderr)
-            ))
-        }
-    }
-
     #[cfg(debug_assertions)]
     async fn build_local(
         &self,
@@ -2437,7 +2768,7 @@ impl ProtoClient for ChannelClient {
 }
 
 #[cfg(any(test, feature = "test-support"))]
-mod fake {
+pub(crate) mod fake {
     use std::{path::PathBuf, sync::Arc};
 
     use anyhow::Result;
@@ -2555,7 +2886,7 @@ mod fake {
         }
     }
 
-    pub(super) struct Delegate;
+    pub(crate) struct Delegate;
 
     impl SshClientDelegate for Delegate {
         fn ask_password(&self, _: String, _: &mut AsyncApp) -> oneshot::Receiver<Result<String>> {
@@ -3,7 +3,7 @@ use anyhow::{anyhow, Result};
 use extension::ExtensionHostProxy;
 use extension_host::headless_host::HeadlessExtensionStore;
 use fs::Fs;
-use gpui::{App, AppContext as _, AsyncApp, Context, Entity, PromptLevel};
+use gpui::{App, AppContext as _, AsyncApp, Context, Entity, PromptLevel, Task};
 use http_client::HttpClient;
 use language::{proto::serialize_operation, Buffer, BufferEvent, LanguageRegistry};
 use node_runtime::NodeRuntime;
@@ -22,11 +22,13 @@ use rpc::{
     AnyProtoClient, TypedEnvelope,
 };
 
+use crate::listening_ports::listening_ports;
 use settings::initial_server_settings_content;
 use smol::stream::StreamExt;
 use std::{
     path::{Path, PathBuf},
     sync::{atomic::AtomicUsize, Arc},
+    time::Duration,
 };
 use util::ResultExt;
 use worktree::Worktree;
@@ -43,8 +45,12 @@ pub struct HeadlessProject {
     pub languages: Arc<LanguageRegistry>,
     pub extensions: Entity<HeadlessExtensionStore>,
     pub git_store: Entity<GitStore>,
+    /// Reports the ports listened on by processes on this host while the client watches them.
+    listening_ports_task: Option<Task<()>>,
 }
 
+const LISTENING_PORTS_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
 pub struct HeadlessAppState {
     pub session: Arc<ChannelClient>,
     pub fs: Arc<dyn Fs>,
@@ -193,9 +199,11 @@ impl HeadlessProject {
         client.add_entity_request_handler(Self::handle_open_new_buffer);
         client.add_entity_request_handler(Self::handle_find_search_candidates);
         client.add_entity_request_handler(Self::handle_open_server_settings);
+        client.add_entity_message_handler(Self::handle_watch_listening_ports);
 
         client.add_entity_request_handler(BufferStore::handle_update_buffer);
         client.add_entity_message_handler(BufferStore::handle_close_buffer);
+        client.add_entity_request_handler(Self::handle_synchronize_buffers);
 
         client.add_request_handler(
             extensions.clone().downgrade(),
@@ -226,9 +234,38 @@ impl HeadlessProject {
             languages,
             extensions,
             git_store,
+            listening_ports_task: None,
         }
     }
 
+    /// Periodically reports the ports listened on by the user's processes on this host,
+    /// other than this server and its descendants, so that they can be forwarded. These
+    /// are typically servers started from terminals and tasks.
+    fn detect_listening_ports(session: AnyProtoClient, cx: &mut Context<Self>) -> Task<()> {
+        let root_pid = std::process::id();
+        cx.spawn(|_, cx| async move {
+            let mut last_ports = Vec::new();
+            loop {
+                let ports = cx
+                    .background_spawn(async move { listening_ports(root_pid) })
+                    .await;
+                if ports != last_ports {
+                    session
+                        .send(proto::UpdateListeningPorts {
+                            project_id: SSH_PROJECT_ID,
+                            ports: ports.clone(),
+                        })
+                        .log_err();
+                    last_ports = ports;
+                }
+
+                cx.background_executor()
+                    .timer(LISTENING_PORTS_POLL_INTERVAL)
+                    .await;
+            }
+        })
+    }
+
     fn on_buffer_event(
         &mut self,
         buffer: Entity<Buffer>,
@@ -458,6 +495,21 @@ impl HeadlessProject {
         })
     }
 
+    pub async fn handle_watch_listening_ports(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::WatchListeningPorts>,
+        mut cx: AsyncApp,
+    ) -> Result<()> {
+        this.update(&mut cx, |this, cx| {
+            if !envelope.payload.watch {
+                this.listening_ports_task = None;
+            } else if this.listening_ports_task.is_none() {
+                this.listening_ports_task =
+                    Some(Self::detect_listening_ports(this.session.clone(), cx));
+            }
+        })
+    }
+
     pub async fn handle_open_server_settings(
         this: Entity<Self>,
         _: TypedEnvelope<proto::OpenServerSettings>,
@@ -601,6 +653,19 @@ impl HeadlessProject {
         Ok(proto::Ack {})
     }
 
+    pub async fn handle_synchronize_buffers(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::SynchronizeBuffers>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::SynchronizeBuffersResponse> {
+        this.update(&mut cx, |this, cx| {
+            let client = this.session.clone();
+            this.buffer_store.update(cx, |buffer_store, cx| {
+                buffer_store.handle_synchronize_buffers(envelope, cx, client)
+            })
+        })?
+    }
+
     pub async fn handle_ping(
         _this: Entity<Self>,
         _envelope: TypedEnvelope<proto::Ping>,
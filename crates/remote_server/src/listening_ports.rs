@@ -0,0 +1,210 @@
+#![cfg_attr(not(target_os = "linux"), allow(dead_code))]
+
+use rpc::proto;
+
+/// Returns the TCP ports listened on by processes of the user running the
+/// server, such as servers started from terminals and tasks.
+///
+/// Ports listened on by the server itself or by its descendants, such as
+/// language servers, are excluded.
+#[cfg(target_os = "linux")]
+pub fn listening_ports(server_pid: u32) -> Vec<proto::ListeningPort> {
+    use std::collections::{HashMap, HashSet};
+    use std::fs;
+    use std::os::unix::fs::MetadataExt as _;
+
+    let Ok(uid) = fs::metadata("/proc/self").map(|metadata| metadata.uid()) else {
+        return Vec::new();
+    };
+    let mut sockets = HashMap::new();
+    for (table, is_ipv6) in [("/proc/net/tcp", false), ("/proc/net/tcp6", true)] {
+        let Ok(contents) = fs::read_to_string(table) else {
+            continue;
+        };
+        for socket in contents
+            .lines()
+            .skip(1)
+            .filter_map(|line| parse_listening_socket(line, is_ipv6))
+            .filter(|socket| socket.uid == uid)
+        {
+            sockets.insert(socket.inode, socket);
+        }
+    }
+    if sockets.is_empty() {
+        return Vec::new();
+    }
+
+    let mut children = HashMap::<u32, Vec<u32>>::new();
+    let mut names = HashMap::new();
+    for entry in fs::read_dir("/proc").into_iter().flatten().flatten() {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse().ok())
+        else {
+            continue;
+        };
+        let Ok(stat) = fs::read_to_string(entry.path().join("stat")) else {
+            continue;
+        };
+        if let Some((name, parent_pid)) = parse_stat(&stat) {
+            children.entry(parent_pid).or_default().push(pid);
+            names.insert(pid, name);
+        }
+    }
+
+    let mut server_pids = HashSet::new();
+    let mut pending_pids = vec![server_pid];
+    while let Some(pid) = pending_pids.pop() {
+        if server_pids.insert(pid) {
+            pending_pids.extend(children.get(&pid).into_iter().flatten());
+        }
+    }
+
+    let mut ports = Vec::new();
+    let mut seen_ports = HashSet::new();
+    for (&pid, name) in &names {
+        if server_pids.contains(&pid) {
+            continue;
+        }
+        // Descriptors of other users' processes can't be read.
+        let Ok(descriptors) = fs::read_dir(format!("/proc/{pid}/fd")) else {
+            continue;
+        };
+        for descriptor in descriptors.flatten() {
+            let Ok(target) = fs::read_link(descriptor.path()) else {
+                continue;
+            };
+            let Some(inode) = target
+                .to_str()
+                .and_then(|target| target.strip_prefix("socket:["))
+                .and_then(|target| target.strip_suffix(']'))
+                .and_then(|inode| inode.parse::<u64>().ok())
+            else {
+                continue;
+            };
+            if let Some(socket) = sockets.get(&inode) {
+                // A server listening on both IPv4 and IPv6 is reported once.
+                if seen_ports.insert(socket.port) {
+                    ports.push(proto::ListeningPort {
+                        address: socket.address.clone(),
+                        port: socket.port as u32,
+                        pid,
+                        process_name: name.clone(),
+                    });
+                }
+            }
+        }
+    }
+    ports.sort_by_key(|port| port.port);
+    ports
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn listening_ports(_root_pid: u32) -> Vec<proto::ListeningPort> {
+    Vec::new()
+}
+
+#[derive(Debug, PartialEq)]
+struct ListeningSocket {
+    address: String,
+    port: u16,
+    uid: u32,
+    inode: u64,
+}
+
+const TCP_LISTEN: &str = "0A";
+
+/// Parses a line of `/proc/net/tcp` or `/proc/net/tcp6`, returning the socket
+/// it describes if that socket is listening.
+fn parse_listening_socket(line: &str, is_ipv6: bool) -> Option<ListeningSocket> {
+    let fields = line.split_whitespace().collect::<Vec<_>>();
+    if fields.get(3) != Some(&TCP_LISTEN) {
+        return None;
+    }
+    let (address, port) = fields.get(1)?.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let uid = fields.get(7)?.parse().ok()?;
+    let inode = fields.get(9)?.parse().ok()?;
+
+    // Addresses are printed as 32-bit words in host byte order.
+    let mut bytes = Vec::with_capacity(16);
+    for word in address.as_bytes().chunks(8) {
+        let word = u32::from_str_radix(std::str::from_utf8(word).ok()?, 16).ok()?;
+        bytes.extend(word.to_ne_bytes());
+    }
+    let address = if is_ipv6 {
+        std::net::Ipv6Addr::from(<[u8; 16]>::try_from(bytes).ok()?).to_string()
+    } else {
+        std::net::Ipv4Addr::from(<[u8; 4]>::try_from(bytes).ok()?).to_string()
+    };
+
+    Some(ListeningSocket {
+        address,
+        port,
+        uid,
+        inode,
+    })
+}
+
+/// Parses the contents of `/proc/<pid>/stat`, returning the process's name
+/// and parent pid.
+fn parse_stat(stat: &str) -> Option<(String, u32)> {
+    // The name is parenthesized and may itself contain spaces and parentheses.
+    let name_start = stat.find('(')?;
+    let name_end = stat.rfind(')')?;
+    let name = stat.get(name_start + 1..name_end)?.to_string();
+    let mut fields = stat.get(name_end + 1..)?.split_whitespace();
+    let _state = fields.next()?;
+    let parent_pid = fields.next()?.parse().ok()?;
+    Some((name, parent_pid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_listening_socket() {
+        assert_eq!(
+            parse_listening_socket(
+                "   0: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 123456 1 0000000000000000 100 0 0 10 0",
+                false,
+            ),
+            Some(ListeningSocket {
+                address: "127.0.0.1".into(),
+                port: 8080,
+                uid: 1000,
+                inode: 123456,
+            })
+        );
+        assert_eq!(
+            parse_listening_socket(
+                "   1: 00000000000000000000000000000000:0BB8 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 654321 1 0000000000000000 100 0 0 10 0",
+                true,
+            ),
+            Some(ListeningSocket {
+                address: "::".into(),
+                port: 3000,
+                uid: 1000,
+                inode: 654321,
+            })
+        );
+        // Established connections are not listening.
+        assert_eq!(
+            parse_listening_socket(
+                "   2: 0100007F:1F90 0100007F:C350 01 00000000:00000000 00:00000000 00000000  1000        0 111111 1 0000000000000000 20 4 30 10 -1",
+                false,
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_stat() {
+        assert_eq!(
+            parse_stat("4242 (node (dev)) S 4200 4242 4200 0 -1 4194560"),
+            Some(("node (dev)".to_string(), 4200))
+        );
+    }
+}
@@ -23,8 +23,10 @@ use serde_json::json;
 use settings::{initial_server_settings_content, Settings, SettingsLocation, SettingsStore};
 use smol::stream::StreamExt;
 use std::{
+    cell::RefCell,
     collections::HashSet,
     path::{Path, PathBuf},
+    rc::Rc,
     sync::Arc,
 };
 use unindent::Unindent as _;
@@ -1102,6 +1104,111 @@ async fn test_reconnect(cx: &mut TestAppContext, server_cx: &mut TestAppContext)
     );
 }
 
+#[gpui::test]
+async fn test_resume_after_offline_edits(cx: &mut TestAppContext, server_cx: &mut TestAppContext) {
+    let fs = FakeFs::new(server_cx.executor());
+    fs.insert_tree(
+        path!("/code"),
+        json!({
+            "project1": {
+                "changed.rs": "fn one() {}\n",
+                "unchanged.rs": "fn two() {}\n",
+            },
+        }),
+    )
+    .await;
+
+    let (project, headless) = init_test(&fs, cx, server_cx).await;
+    let (worktree, _) = project
+        .update(cx, |project, cx| {
+            project.find_or_create_worktree(path!("/code/project1"), true, cx)
+        })
+        .await
+        .unwrap();
+    let worktree_id = worktree.read_with(cx, |worktree, _| worktree.id());
+    let changed_buffer = project
+        .update(cx, |project, cx| {
+            project.open_buffer((worktree_id, Path::new("changed.rs")), cx)
+        })
+        .await
+        .unwrap();
+    let unchanged_buffer = project
+        .update(cx, |project, cx| {
+            project.open_buffer((worktree_id, Path::new("unchanged.rs")), cx)
+        })
+        .await
+        .unwrap();
+
+    let conflicts = Rc::new(RefCell::new(Vec::new()));
+    cx.update(|cx| {
+        let conflicts = conflicts.clone();
+        cx.subscribe(&project, move |_, event, _| {
+            if let project::Event::OfflineEditConflicts(new_conflicts) = event {
+                conflicts.borrow_mut().extend(
+                    new_conflicts
+                        .iter()
+                        .map(|conflict| (conflict.buffer.clone(), conflict.disk_text.clone())),
+                );
+            }
+        })
+        .detach();
+    });
+
+    let client = cx.read(|cx| project.read(cx).ssh_client().unwrap());
+    client.update(cx, |client, cx| client.simulate_reconnect_exhausted(cx));
+    cx.run_until_parked();
+    assert!(client.read_with(cx, |client, _| client.can_resume()));
+
+    // Both buffers stay editable while disconnected, and one of their files
+    // changes on the host in the meantime.
+    for buffer in [&changed_buffer, &unchanged_buffer] {
+        buffer.update(cx, |buffer, cx| {
+            buffer.edit([(0..0, "// offline\n")], None, cx)
+        });
+    }
+    fs.save(
+        path!("/code/project1/changed.rs").as_ref(),
+        &"fn one() -> usize { 1 }\n".into(),
+        LineEnding::Unix,
+    )
+    .await
+    .unwrap();
+    server_cx.run_until_parked();
+
+    client
+        .update(cx, |client, cx| client.simulate_resume(cx))
+        .await
+        .unwrap();
+    cx.run_until_parked();
+    server_cx.run_until_parked();
+    cx.run_until_parked();
+
+    // The offline edits are sent to the host.
+    let unchanged_buffer_id = unchanged_buffer.read_with(cx, |buffer, _| buffer.remote_id());
+    headless.update(server_cx, |headless, cx| {
+        let buffer = headless
+            .buffer_store
+            .read(cx)
+            .get(unchanged_buffer_id)
+            .unwrap();
+        assert_eq!(buffer.read(cx).text(), "// offline\nfn two() {}\n");
+    });
+    assert_eq!(
+        unchanged_buffer.read_with(cx, |buffer, _| buffer.text()),
+        "// offline\nfn two() {}\n"
+    );
+
+    // Only the buffer whose file changed on the host is reported as conflicting.
+    assert_eq!(
+        conflicts.borrow().as_slice(),
+        &[(
+            changed_buffer.clone(),
+            "fn one() -> usize { 1 }\n".to_string()
+        )]
+    );
+    assert!(changed_buffer.read_with(cx, |buffer, _| buffer.is_dirty()));
+}
+
 #[gpui::test]
 async fn test_remote_root_rename(cx: &mut TestAppContext, server_cx: &mut TestAppContext) {
     let fs = FakeFs::new(server_cx.executor());
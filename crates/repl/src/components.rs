@@ -1,5 +1,7 @@
+mod jupyter_server_modal;
 mod kernel_list_item;
 mod kernel_options;
 
+pub use jupyter_server_modal::*;
 pub use kernel_list_item::*;
 pub use kernel_options::*;
@@ -0,0 +1,191 @@
+use editor::Editor;
+use gpui::{DismissEvent, Entity, EventEmitter, FocusHandle, Focusable};
+use settings::update_settings_file;
+use ui::prelude::*;
+use workspace::ModalView;
+
+use crate::repl_store::ReplStore;
+use crate::{JupyterServerSettings, JupyterSettings};
+
+/// Prompts for the URL and token of a Jupyter server, and adds it to the `jupyter.servers` setting.
+///
+/// The token is kept in the system keychain rather than in the settings file.
+pub struct JupyterServerModal {
+    url_editor: Entity<Editor>,
+    token_editor: Entity<Editor>,
+    name_editor: Entity<Editor>,
+    error: Option<SharedString>,
+}
+
+impl JupyterServerModal {
+    pub fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let url_editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text("http://localhost:8888", cx);
+            editor
+        });
+        let token_editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text("Token (optional)", cx);
+            editor.set_masked(true, cx);
+            editor
+        });
+        let name_editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text("Name (defaults to the server's host)", cx);
+            editor
+        });
+
+        Self {
+            url_editor,
+            token_editor,
+            name_editor,
+            error: None,
+        }
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, _window: &mut Window, cx: &mut Context<Self>) {
+        let url = self.url_editor.read(cx).text(cx).trim().to_string();
+        let token = self.token_editor.read(cx).text(cx).trim().to_string();
+        let name = self.name_editor.read(cx).text(cx).trim().to_string();
+
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            self.error = Some("The server URL must start with http:// or https://".into());
+            cx.notify();
+            return;
+        }
+
+        let name = if name.is_empty() {
+            server_name_from_url(&url)
+        } else {
+            name
+        };
+        let store_token = if token.is_empty() {
+            cx.delete_credentials(&url)
+        } else {
+            cx.write_credentials(&url, "token", token.as_bytes())
+        };
+        let server = JupyterServerSettings { url, token: None };
+        let fs = ReplStore::global(cx).read(cx).fs().clone();
+
+        cx.spawn(|this, mut cx| async move {
+            if let Err(error) = store_token.await {
+                return this.update(&mut cx, |this, cx| {
+                    this.error = Some(format!("Failed to store the token: {error:#}").into());
+                    cx.notify();
+                });
+            }
+
+            this.update(&mut cx, |_, cx| {
+                update_settings_file::<JupyterSettings>(fs, cx, move |settings, _| {
+                    settings
+                        .servers
+                        .get_or_insert_with(Default::default)
+                        .insert(name, server);
+                });
+                // Changing the token of a server that's already configured
+                // leaves the settings as they were, so refresh explicitly.
+                ReplStore::global(cx).update(cx, |store, cx| {
+                    store.refresh_kernelspecs(cx).detach_and_log_err(cx);
+                });
+                cx.emit(DismissEvent);
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, _window: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn render_field(&self, label: &'static str, editor: &Entity<Editor>, cx: &App) -> Div {
+        v_flex()
+            .gap_1()
+            .child(Label::new(label).size(LabelSize::Small).color(Color::Muted))
+            .child(
+                div()
+                    .p_2()
+                    .border_1()
+                    .rounded_md()
+                    .border_color(cx.theme().colors().border)
+                    .bg(cx.theme().colors().editor_background)
+                    .child(editor.clone()),
+            )
+    }
+}
+
+/// The host (and port) of a server URL, used to name servers added without a name.
+fn server_name_from_url(url: &str) -> String {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    without_scheme
+        .split('/')
+        .next()
+        .unwrap_or(without_scheme)
+        .to_string()
+}
+
+impl ModalView for JupyterServerModal {}
+
+impl EventEmitter<DismissEvent> for JupyterServerModal {}
+
+impl Focusable for JupyterServerModal {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.url_editor.focus_handle(cx)
+    }
+}
+
+impl Render for JupyterServerModal {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .key_context("JupyterServerModal")
+            .on_action(cx.listener(Self::confirm))
+            .on_action(cx.listener(Self::cancel))
+            .elevation_3(cx)
+            .w(rems(34.))
+            .p_4()
+            .gap_3()
+            .child(Headline::new("Connect to a Jupyter Server").size(HeadlineSize::Small))
+            .child(self.render_field("Server URL", &self.url_editor, cx))
+            .child(self.render_field("Token", &self.token_editor, cx))
+            .child(self.render_field("Name", &self.name_editor, cx))
+            .when_some(self.error.clone(), |this, error| {
+                this.child(Label::new(error).color(Color::Error))
+            })
+            .child(
+                h_flex()
+                    .justify_end()
+                    .gap_1()
+                    .child(
+                        Button::new("cancel", "Cancel")
+                            .style(ButtonStyle::Subtle)
+                            .on_click(cx.listener(|this, _, window, cx| {
+                                this.cancel(&menu::Cancel, window, cx)
+                            })),
+                    )
+                    .child(
+                        Button::new("connect", "Connect")
+                            .style(ButtonStyle::Filled)
+                            .on_click(cx.listener(|this, _, window, cx| {
+                                this.confirm(&menu::Confirm, window, cx)
+                            })),
+                    ),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_name_from_url() {
+        assert_eq!(
+            server_name_from_url("http://localhost:8888"),
+            "localhost:8888"
+        );
+        assert_eq!(
+            server_name_from_url("https://jupyter.example.com/user/me/"),
+            "jupyter.example.com"
+        );
+    }
+}
@@ -0,0 +1,198 @@
+//! Expressions evaluated in a kernel on Zed's behalf, such as inline values and the
+//! variable explorer's listings.
+//!
+//! Each expression is executed as its own request that isn't stored in the kernel's
+//! history, and its value is read from the `execute_result` the kernel publishes for it.
+//! An evaluation is done once the kernel reports being idle after its request, whether or
+//! not it produced a value.
+
+use collections::HashMap;
+use runtimelib::{ExecuteRequest, ExecutionState, JupyterMessage, JupyterMessageContent, MimeType};
+
+pub struct Evaluations<T> {
+    /// The evaluations in flight, keyed by the id of their request.
+    pending: HashMap<String, PendingEvaluation<T>>,
+}
+
+struct PendingEvaluation<T> {
+    purpose: T,
+    value: Option<String>,
+}
+
+impl<T> Default for Evaluations<T> {
+    fn default() -> Self {
+        Self {
+            pending: HashMap::default(),
+        }
+    }
+}
+
+impl<T> Evaluations<T> {
+    /// Returns the request that evaluates `expression`, and tracks it until the kernel is
+    /// done with it.
+    pub fn request(&mut self, expression: String, purpose: T) -> JupyterMessage {
+        let message: JupyterMessage = ExecuteRequest {
+            code: expression,
+            // Kernels don't publish the results of silent executions.
+            silent: false,
+            store_history: false,
+            user_expressions: None,
+            allow_stdin: false,
+            // A failed evaluation mustn't abort the executions queued after it.
+            stop_on_error: false,
+        }
+        .into();
+        self.pending.insert(
+            message.header.msg_id.clone(),
+            PendingEvaluation {
+                purpose,
+                value: None,
+            },
+        );
+        message
+    }
+
+    /// Handles a message from the kernel, returning the evaluation that it completes along
+    /// with the plain text representation of its value, if it had one.
+    pub fn handle(&mut self, message: &JupyterMessage) -> Option<(T, Option<String>)> {
+        let parent_id = &message.parent_header.as_ref()?.msg_id;
+        match &message.content {
+            JupyterMessageContent::ExecuteResult(result) => {
+                let pending = self.pending.get_mut(parent_id)?;
+                pending.value = result.data.content.iter().find_map(|data| match data {
+                    MimeType::Plain(text) => Some(text.clone()),
+                    _ => None,
+                });
+                None
+            }
+            JupyterMessageContent::Status(status)
+                if status.execution_state == ExecutionState::Idle =>
+            {
+                let pending = self.pending.remove(parent_id)?;
+                Some((pending.purpose, pending.value))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the message with the given id is one of the evaluation requests.
+    pub fn contains(&self, message_id: &str) -> bool {
+        self.pending.contains_key(message_id)
+    }
+
+    pub fn any(&self, mut predicate: impl FnMut(&T) -> bool) -> bool {
+        self.pending
+            .values()
+            .any(|pending| predicate(&pending.purpose))
+    }
+
+    pub fn retain(&mut self, mut predicate: impl FnMut(&T) -> bool) {
+        self.pending
+            .retain(|_, pending| predicate(&pending.purpose));
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn kernel_message(
+        request: &JupyterMessage,
+        msg_type: &str,
+        content: serde_json::Value,
+    ) -> JupyterMessage {
+        JupyterMessage::from_value(json!({
+            "header": {
+                "msg_id": "b2a3c8f6-1c1e4e1a0f9e5b2d7a6c4e3f_16_2",
+                "session": "b2a3c8f6-1c1e4e1a0f9e5b2d7a6c4e3f",
+                "username": "username",
+                "date": "2026-10-19T10:32:14.123456Z",
+                "msg_type": msg_type,
+                "version": "5.3",
+            },
+            "parent_header": serde_json::to_value(&request.header).unwrap(),
+            "metadata": {},
+            "content": content,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_evaluation_results() {
+        let mut evaluations = Evaluations::default();
+        let request = evaluations.request("x + 1".into(), "x");
+        let JupyterMessageContent::ExecuteRequest(execute_request) = &request.content else {
+            panic!("expected an execute request");
+        };
+        assert_eq!(execute_request.code, "x + 1");
+        assert!(!execute_request.store_history);
+        assert!(!execute_request.stop_on_error);
+
+        let busy = kernel_message(&request, "status", json!({ "execution_state": "busy" }));
+        assert!(evaluations.handle(&busy).is_none());
+
+        let result = kernel_message(
+            &request,
+            "execute_result",
+            json!({
+                "execution_count": 3,
+                "data": {
+                    "text/plain": "42",
+                    "text/html": "<b>42</b>",
+                },
+                "metadata": {},
+            }),
+        );
+        assert!(evaluations.handle(&result).is_none());
+        assert!(evaluations.contains(&request.header.msg_id));
+
+        let idle = kernel_message(&request, "status", json!({ "execution_state": "idle" }));
+        assert_eq!(evaluations.handle(&idle), Some(("x", Some("42".into()))));
+        assert!(!evaluations.contains(&request.header.msg_id));
+        assert!(evaluations.handle(&idle).is_none());
+    }
+
+    #[test]
+    fn test_failed_evaluations_are_completed() {
+        let mut evaluations = Evaluations::default();
+        let request = evaluations.request("undefined_name".into(), "undefined_name");
+
+        let error = kernel_message(
+            &request,
+            "error",
+            json!({
+                "ename": "NameError",
+                "evalue": "name 'undefined_name' is not defined",
+                "traceback": [],
+            }),
+        );
+        assert!(evaluations.handle(&error).is_none());
+
+        let idle = kernel_message(&request, "status", json!({ "execution_state": "idle" }));
+        assert_eq!(evaluations.handle(&idle), Some(("undefined_name", None)));
+        assert!(!evaluations.any(|_| true));
+    }
+
+    #[test]
+    fn test_unrelated_messages_are_ignored() {
+        let mut evaluations = Evaluations::default();
+        evaluations.request("x".into(), "x");
+        let other_request: JupyterMessage = ExecuteRequest::new("print(1)".into()).into();
+
+        let idle = kernel_message(
+            &other_request,
+            "status",
+            json!({ "execution_state": "idle" }),
+        );
+        assert!(evaluations.handle(&idle).is_none());
+        assert!(evaluations.any(|purpose| *purpose == "x"));
+
+        evaluations.retain(|purpose| *purpose != "x");
+        assert!(!evaluations.any(|_| true));
+    }
+}
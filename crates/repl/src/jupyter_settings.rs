@@ -2,6 +2,7 @@ use std::collections::HashMap;
 
 use editor::EditorSettings;
 use gpui::App;
+use jupyter_websocket_client::RemoteServer;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use settings::{Settings, SettingsSources};
@@ -9,6 +10,9 @@ use settings::{Settings, SettingsSources};
 #[derive(Debug, Default)]
 pub struct JupyterSettings {
     pub kernel_selections: HashMap<String, String>,
+    pub servers: HashMap<String, JupyterServerSettings>,
+    pub mime_type_priority: Vec<String>,
+    pub evaluate_inline_values: bool,
 }
 
 impl JupyterSettings {
@@ -26,12 +30,63 @@ pub struct JupyterSettingsContent {
     ///
     /// Default: `{}`
     pub kernel_selections: Option<HashMap<String, String>>,
+    /// Remote Jupyter servers whose kernels can be used, keyed by a name for each server.
+    ///
+    /// Default: `{}`
+    pub servers: Option<HashMap<String, JupyterServerSettings>>,
+    /// The MIME types that outputs are preferably shown as, most preferred first.
+    ///
+    /// Outputs available in none of these types are shown in the first type Zed
+    /// supports, in the order `application/vnd.dataresource+json`, `image/png`,
+    /// `image/jpeg`, `image/svg+xml`, `text/html`, `text/markdown`, `text/latex`,
+    /// `application/json` and `text/plain`.
+    ///
+    /// Default: `[]`
+    pub mime_type_priority: Option<Vec<String>>,
+    /// Whether to show the values of the expressions the language server reports after
+    /// running code, by evaluating them in the kernel.
+    ///
+    /// Evaluating an expression runs it like any other code, so it may have side effects,
+    /// and it is shown to every other client attached to the kernel.
+    ///
+    /// Default: `false`
+    pub evaluate_inline_values: Option<bool>,
 }
 
 impl Default for JupyterSettingsContent {
     fn default() -> Self {
         JupyterSettingsContent {
             kernel_selections: Some(HashMap::new()),
+            servers: Some(HashMap::new()),
+            mime_type_priority: Some(Vec::new()),
+            evaluate_inline_values: Some(false),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Debug, PartialEq, Eq)]
+pub struct JupyterServerSettings {
+    /// The URL of the Jupyter server, e.g. `http://localhost:8888`.
+    pub url: String,
+    /// The token used to authenticate with the server.
+    ///
+    /// When omitted, the token stored in the system keychain for the server's
+    /// URL is used, falling back to the `JUPYTER_TOKEN` environment variable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+impl JupyterServerSettings {
+    /// The server to connect to, given the token stored in the keychain for its URL.
+    pub fn remote_server(&self, stored_token: Option<String>) -> RemoteServer {
+        RemoteServer {
+            base_url: self.url.trim_end_matches('/').to_string(),
+            token: self
+                .token
+                .clone()
+                .or(stored_token)
+                .or_else(|| std::env::var("JUPYTER_TOKEN").ok())
+                .unwrap_or_default(),
         }
     }
 }
@@ -56,6 +111,20 @@ impl Settings for JupyterSettings {
                     settings.kernel_selections.insert(k.clone(), v.clone());
                 }
             }
+
+            if let Some(source) = &value.servers {
+                for (name, server) in source {
+                    settings.servers.insert(name.clone(), server.clone());
+                }
+            }
+
+            if let Some(mime_type_priority) = &value.mime_type_priority {
+                settings.mime_type_priority = mime_type_priority.clone();
+            }
+
+            if let Some(evaluate_inline_values) = value.evaluate_inline_values {
+                settings.evaluate_inline_values = evaluate_inline_values;
+            }
         }
 
         Ok(settings)
@@ -6,7 +6,7 @@ use futures::{
     future::Shared,
     stream,
 };
-use gpui::{App, Entity, Task, Window};
+use gpui::{App, Context, Entity, Task, Window};
 use language::LanguageName;
 pub use native_kernel::*;
 
@@ -33,7 +33,7 @@ impl KernelSpecification {
         match self {
             Self::Jupyter(spec) => spec.name.clone().into(),
             Self::PythonEnv(spec) => spec.name.clone().into(),
-            Self::Remote(spec) => spec.name.clone().into(),
+            Self::Remote(spec) => spec.display_name().into(),
         }
     }
 
@@ -141,6 +141,12 @@ pub fn python_env_kernel_specifications(
     }
 }
 
+/// Receives the messages and errors of a running kernel.
+pub trait KernelSession: Sized {
+    fn route(&mut self, message: &JupyterMessage, window: &mut Window, cx: &mut Context<Self>);
+    fn kernel_errored(&mut self, error_message: String, cx: &mut Context<Self>);
+}
+
 pub trait RunningKernel: Send + Debug {
     fn request_tx(&self) -> mpsc::Sender<JupyterMessage>;
     fn working_directory(&self) -> &PathBuf;
@@ -149,6 +155,11 @@ pub trait RunningKernel: Send + Debug {
     fn kernel_info(&self) -> Option<&KernelInfoReply>;
     fn set_kernel_info(&mut self, info: KernelInfoReply);
     fn force_shutdown(&mut self, window: &mut Window, cx: &mut App) -> Task<anyhow::Result<()>>;
+    /// Whether this is a kernel on a Jupyter server that was attached to rather
+    /// than launched, which shutting down only detaches from.
+    fn is_attached(&self) -> bool {
+        false
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -1,4 +1,4 @@
-use futures::{channel::mpsc, SinkExt as _};
+use futures::{channel::mpsc, FutureExt as _, SinkExt as _};
 use gpui::{App, AppContext as _, Entity, Task, Window};
 use http_client::{AsyncBody, HttpClient, Request};
 use jupyter_protocol::{ExecutionState, JupyterKernelspec, JupyterMessage, KernelInfoReply};
@@ -11,15 +11,15 @@ use async_tungstenite::{
 use futures::StreamExt;
 use smol::io::AsyncReadExt as _;
 
-use crate::Session;
-
-use super::RunningKernel;
-use anyhow::Result;
+use super::{KernelSession, RunningKernel};
+use anyhow::{anyhow, Context as _, Result};
 use jupyter_websocket_client::{
     JupyterWebSocket, JupyterWebSocketReader, JupyterWebSocketWriter, KernelLaunchRequest,
     KernelSpecsResponse, RemoteServer,
 };
-use std::{fmt::Debug, sync::Arc};
+use serde::Deserialize;
+use std::{fmt::Debug, sync::Arc, time::Duration};
+use util::ResultExt as _;
 
 #[derive(Debug, Clone)]
 pub struct RemoteKernelSpecification {
@@ -27,6 +27,74 @@ pub struct RemoteKernelSpecification {
     pub url: String,
     pub token: String,
     pub kernelspec: JupyterKernelspec,
+    /// The ID of a kernel already running on the server, which is attached to
+    /// instead of launching a new kernel.
+    pub kernel_id: Option<String>,
+}
+
+impl RemoteKernelSpecification {
+    pub fn remote_server(&self) -> RemoteServer {
+        RemoteServer {
+            base_url: self.url.clone(),
+            token: self.token.clone(),
+        }
+    }
+
+    /// The name shown for this kernel, which identifies the running kernel when attaching.
+    pub fn display_name(&self) -> String {
+        match &self.kernel_id {
+            Some(kernel_id) => {
+                let short_id = kernel_id.get(..8).unwrap_or(kernel_id);
+                format!("{} ({})", self.name, short_id)
+            }
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// A kernel running on a Jupyter server, as returned by `GET /api/kernels`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RemoteKernel {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub execution_state: Option<String>,
+}
+
+async fn send_remote_request(
+    remote_server: &RemoteServer,
+    http_client: &Arc<dyn HttpClient>,
+    method: &str,
+    path: &str,
+    body: AsyncBody,
+) -> Result<Vec<u8>> {
+    let mut request = Request::builder()
+        .method(method)
+        .uri(&remote_server.api_url(path));
+    if !remote_server.token.is_empty() {
+        request = request.header("Authorization", format!("token {}", remote_server.token));
+    }
+    let response = http_client.send(request.body(body)?).await?;
+
+    let status = response.status();
+    let mut body = Vec::new();
+    response.into_body().read_to_end(&mut body).await?;
+
+    if status.as_u16() == 401 || status.as_u16() == 403 {
+        Err(anyhow!(
+            "Authentication with the Jupyter server at {} failed. Check its token.",
+            remote_server.base_url
+        ))
+    } else if !status.is_success() {
+        Err(anyhow!(
+            "Request to {} failed ({}): {}",
+            path,
+            status,
+            String::from_utf8_lossy(&body)
+        ))
+    } else {
+        Ok(body)
+    }
 }
 
 pub async fn launch_remote_kernel(
@@ -35,7 +103,6 @@ pub async fn launch_remote_kernel(
     kernel_name: &str,
     _path: &str,
 ) -> Result<String> {
-    //
     let kernel_launch_request = KernelLaunchRequest {
         name: kernel_name.to_string(),
         // Note: since the path we have locally may not be the same as the one on the remote server,
@@ -45,24 +112,17 @@ pub async fn launch_remote_kernel(
 
     let kernel_launch_request = serde_json::to_string(&kernel_launch_request)?;
 
-    let request = Request::builder()
-        .method("POST")
-        .uri(&remote_server.api_url("/kernels"))
-        .header("Authorization", format!("token {}", remote_server.token))
-        .body(AsyncBody::from(kernel_launch_request))?;
-
-    let response = http_client.send(request).await?;
-
-    if !response.status().is_success() {
-        let mut body = String::new();
-        response.into_body().read_to_string(&mut body).await?;
-        return Err(anyhow::anyhow!("Failed to launch kernel: {}", body));
-    }
-
-    let mut body = String::new();
-    response.into_body().read_to_string(&mut body).await?;
+    let body = send_remote_request(
+        remote_server,
+        &http_client,
+        "POST",
+        "/kernels",
+        AsyncBody::from(kernel_launch_request),
+    )
+    .await
+    .context("Failed to launch kernel")?;
 
-    let response: jupyter_websocket_client::Kernel = serde_json::from_str(&body)?;
+    let response: jupyter_websocket_client::Kernel = serde_json::from_slice(&body)?;
 
     Ok(response.id)
 }
@@ -71,158 +131,268 @@ pub async fn list_remote_kernelspecs(
     remote_server: RemoteServer,
     http_client: Arc<dyn HttpClient>,
 ) -> Result<Vec<RemoteKernelSpecification>> {
-    let url = remote_server.api_url("/kernelspecs");
-
-    let request = Request::builder()
-        .method("GET")
-        .uri(&url)
-        .header("Authorization", format!("token {}", remote_server.token))
-        .body(AsyncBody::default())?;
+    let body = send_remote_request(
+        &remote_server,
+        &http_client,
+        "GET",
+        "/kernelspecs",
+        AsyncBody::default(),
+    )
+    .await
+    .context("Failed to fetch kernel specs")?;
+
+    let kernel_specs: KernelSpecsResponse = serde_json::from_slice(&body)?;
+
+    let mut remote_kernelspecs = kernel_specs
+        .kernelspecs
+        .into_iter()
+        .map(|(name, spec)| RemoteKernelSpecification {
+            name,
+            url: remote_server.base_url.clone(),
+            token: remote_server.token.clone(),
+            kernelspec: spec.spec,
+            kernel_id: None,
+        })
+        .collect::<Vec<RemoteKernelSpecification>>();
+    remote_kernelspecs.sort_by(|a, b| a.name.cmp(&b.name));
 
-    let response = http_client.send(request).await?;
+    if remote_kernelspecs.is_empty() {
+        Err(anyhow!("No kernel specs found"))
+    } else {
+        Ok(remote_kernelspecs)
+    }
+}
 
-    if response.status().is_success() {
-        let mut body = response.into_body();
+pub async fn list_remote_kernels(
+    remote_server: &RemoteServer,
+    http_client: Arc<dyn HttpClient>,
+) -> Result<Vec<RemoteKernel>> {
+    let body = send_remote_request(
+        remote_server,
+        &http_client,
+        "GET",
+        "/kernels",
+        AsyncBody::default(),
+    )
+    .await
+    .context("Failed to list running kernels")?;
+
+    Ok(serde_json::from_slice(&body)?)
+}
 
-        let mut body_bytes = Vec::new();
-        body.read_to_end(&mut body_bytes).await?;
+/// Lists the kernelspecs of a Jupyter server, followed by the kernels already
+/// running on it that can be attached to.
+pub async fn list_remote_kernel_specifications(
+    remote_server: RemoteServer,
+    http_client: Arc<dyn HttpClient>,
+) -> Result<Vec<RemoteKernelSpecification>> {
+    let mut specifications = list_remote_kernelspecs(
+        RemoteServer {
+            base_url: remote_server.base_url.clone(),
+            token: remote_server.token.clone(),
+        },
+        http_client.clone(),
+    )
+    .await?;
+
+    let running_kernels = list_remote_kernels(&remote_server, http_client)
+        .await
+        .log_err()
+        .unwrap_or_default();
+
+    let attachable = running_kernels
+        .into_iter()
+        .filter_map(|kernel| {
+            let spec = specifications
+                .iter()
+                .find(|spec| spec.name == kernel.name)?;
+            Some(RemoteKernelSpecification {
+                kernel_id: Some(kernel.id),
+                ..spec.clone()
+            })
+        })
+        .collect::<Vec<_>>();
+    specifications.extend(attachable);
 
-        let kernel_specs: KernelSpecsResponse = serde_json::from_slice(&body_bytes)?;
+    Ok(specifications)
+}
 
-        let remote_kernelspecs = kernel_specs
-            .kernelspecs
-            .into_iter()
-            .map(|(name, spec)| RemoteKernelSpecification {
-                name: name.clone(),
-                url: remote_server.base_url.clone(),
-                token: remote_server.token.clone(),
-                kernelspec: spec.spec,
-            })
-            .collect::<Vec<RemoteKernelSpecification>>();
+/// The websocket URL for a kernel's channels.
+pub fn kernel_channels_url(remote_server: &RemoteServer, kernel_id: &str) -> String {
+    let base_url = remote_server.base_url.trim_end_matches('/');
+    let ws_base_url = if let Some(rest) = base_url.strip_prefix("https://") {
+        format!("wss://{rest}")
+    } else if let Some(rest) = base_url.strip_prefix("http://") {
+        format!("ws://{rest}")
+    } else {
+        base_url.to_string()
+    };
 
-        if remote_kernelspecs.is_empty() {
-            Err(anyhow::anyhow!("No kernel specs found"))
-        } else {
-            Ok(remote_kernelspecs.clone())
-        }
+    if remote_server.token.is_empty() {
+        format!("{ws_base_url}/api/kernels/{kernel_id}/channels")
     } else {
-        Err(anyhow::anyhow!(
-            "Failed to fetch kernel specs: {}",
-            response.status()
-        ))
+        let token: String =
+            url::form_urlencoded::byte_serialize(remote_server.token.as_bytes()).collect();
+        format!("{ws_base_url}/api/kernels/{kernel_id}/channels?token={token}")
     }
 }
 
+async fn connect_to_kernel(
+    remote_server: &RemoteServer,
+    kernel_id: &str,
+) -> Result<(JupyterWebSocketWriter, JupyterWebSocketReader)> {
+    let mut req: Request<()> =
+        kernel_channels_url(remote_server, kernel_id).into_client_request()?;
+    let headers = req.headers_mut();
+
+    headers.insert(
+        "User-Agent",
+        HeaderValue::from_str(&format!(
+            "Zed/{} ({}; {})",
+            "repl",
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        ))?,
+    );
+
+    let (ws_stream, _response) = connect_async(req).await?;
+    let kernel_socket = JupyterWebSocket { inner: ws_stream };
+    Ok(kernel_socket.split())
+}
+
 impl PartialEq for RemoteKernelSpecification {
     fn eq(&self, other: &Self) -> bool {
-        self.name == other.name && self.url == other.url
+        self.name == other.name && self.url == other.url && self.kernel_id == other.kernel_id
     }
 }
 
 impl Eq for RemoteKernelSpecification {}
 
+/// How many times in a row reconnecting to a kernel's websocket is attempted
+/// before the kernel is considered lost.
+const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+const RECONNECT_BASE_DELAY: Duration = Duration::from_millis(500);
+
 pub struct RemoteRunningKernel {
     remote_server: RemoteServer,
-    _receiving_task: Task<Result<()>>,
-    _routing_task: Task<Result<()>>,
+    _connection_task: Task<Result<()>>,
     http_client: Arc<dyn HttpClient>,
     pub working_directory: std::path::PathBuf,
     pub request_tx: mpsc::Sender<JupyterMessage>,
     pub execution_state: ExecutionState,
     pub kernel_info: Option<KernelInfoReply>,
     pub kernel_id: String,
+    attached: bool,
 }
 
 impl RemoteRunningKernel {
-    pub fn new(
+    pub fn new<S: KernelSession + 'static>(
         kernelspec: RemoteKernelSpecification,
         working_directory: std::path::PathBuf,
-        session: Entity<Session>,
+        session: Entity<S>,
         window: &mut Window,
         cx: &mut App,
     ) -> Task<Result<Box<dyn RunningKernel>>> {
-        let remote_server = RemoteServer {
-            base_url: kernelspec.url,
-            token: kernelspec.token,
-        };
-
+        let remote_server = kernelspec.remote_server();
         let http_client = cx.http_client();
+        let attached = kernelspec.kernel_id.is_some();
 
         window.spawn(cx, |cx| async move {
-            let kernel_id = launch_remote_kernel(
-                &remote_server,
-                http_client.clone(),
-                &kernelspec.name,
-                working_directory.to_str().unwrap_or_default(),
-            )
-            .await?;
-
-            let ws_url = format!(
-                "{}/api/kernels/{}/channels?token={}",
-                remote_server.base_url.replace("http", "ws"),
-                kernel_id,
-                remote_server.token
-            );
-
-            let mut req: Request<()> = ws_url.into_client_request()?;
-            let headers = req.headers_mut();
-
-            headers.insert(
-                "User-Agent",
-                HeaderValue::from_str(&format!(
-                    "Zed/{} ({}; {})",
-                    "repl",
-                    std::env::consts::OS,
-                    std::env::consts::ARCH
-                ))?,
-            );
-
-            let response = connect_async(req).await;
+            let kernel_id = match kernelspec.kernel_id {
+                Some(kernel_id) => kernel_id,
+                None => {
+                    launch_remote_kernel(
+                        &remote_server,
+                        http_client.clone(),
+                        &kernelspec.name,
+                        working_directory.to_str().unwrap_or_default(),
+                    )
+                    .await?
+                }
+            };
 
-            let (ws_stream, _response) = response?;
+            let connection = connect_to_kernel(&remote_server, &kernel_id).await?;
 
-            let kernel_socket = JupyterWebSocket { inner: ws_stream };
+            let (request_tx, request_rx) = futures::channel::mpsc::channel::<JupyterMessage>(100);
 
-            let (mut w, mut r): (JupyterWebSocketWriter, JupyterWebSocketReader) =
-                kernel_socket.split();
+            let connection_task = cx.spawn({
+                let remote_server = RemoteServer {
+                    base_url: remote_server.base_url.clone(),
+                    token: remote_server.token.clone(),
+                };
+                let kernel_id = kernel_id.clone();
+                let executor = cx.background_executor().clone();
 
-            let (request_tx, mut request_rx) =
-                futures::channel::mpsc::channel::<JupyterMessage>(100);
+                |mut cx| async move {
+                    let mut request_rx = request_rx;
+                    let mut connection = Some(connection);
+                    let mut attempts = 0;
+
+                    loop {
+                        if let Some((mut w, mut r)) = connection.take() {
+                            attempts = 0;
+                            loop {
+                                futures::select_biased! {
+                                    request = request_rx.next() => match request {
+                                        Some(request) => {
+                                            if let Err(error) = w.send(request).await {
+                                                log::error!("Error sending message: {:?}", error);
+                                                break;
+                                            }
+                                        }
+                                        // The kernel has been dropped.
+                                        None => return Ok(()),
+                                    },
+                                    message = r.next().fuse() => match message {
+                                        Some(Ok(message)) => {
+                                            session
+                                                .update_in(&mut cx, |session, window, cx| {
+                                                    session.route(&message, window, cx);
+                                                })
+                                                .ok();
+                                        }
+                                        Some(Err(error)) => {
+                                            log::error!("Error receiving message: {:?}", error);
+                                        }
+                                        None => break,
+                                    },
+                                }
+                            }
+                            log::warn!(
+                                "Lost connection to remote kernel {kernel_id}, reconnecting"
+                            );
+                        }
 
-            let routing_task = cx.background_spawn({
-                async move {
-                    while let Some(message) = request_rx.next().await {
-                        w.send(message).await.ok();
-                    }
-                    Ok(())
-                }
-            });
+                        if attempts == MAX_RECONNECT_ATTEMPTS {
+                            let error_message = format!(
+                                "Lost connection to the kernel at {}",
+                                remote_server.base_url
+                            );
+                            session
+                                .update(&mut cx, |session, cx| {
+                                    session.kernel_errored(error_message.clone(), cx);
+                                })
+                                .ok();
+                            return Err(anyhow!(error_message));
+                        }
 
-            let receiving_task = cx.spawn({
-                let session = session.clone();
+                        executor
+                            .timer(RECONNECT_BASE_DELAY * 2u32.pow(attempts))
+                            .await;
+                        attempts += 1;
 
-                |mut cx| async move {
-                    while let Some(message) = r.next().await {
-                        match message {
-                            Ok(message) => {
-                                session
-                                    .update_in(&mut cx, |session, window, cx| {
-                                        session.route(&message, window, cx);
-                                    })
-                                    .ok();
-                            }
-                            Err(e) => {
-                                log::error!("Error receiving message: {:?}", e);
+                        match connect_to_kernel(&remote_server, &kernel_id).await {
+                            Ok(reconnected) => connection = Some(reconnected),
+                            Err(error) => {
+                                log::error!("Failed to reconnect to remote kernel: {:?}", error);
                             }
                         }
                     }
-                    Ok(())
                 }
             });
 
             anyhow::Ok(Box::new(Self {
-                _routing_task: routing_task,
-                _receiving_task: receiving_task,
+                _connection_task: connection_task,
                 remote_server,
                 working_directory,
                 request_tx,
@@ -230,6 +400,7 @@ impl RemoteRunningKernel {
                 execution_state: ExecutionState::Idle,
                 kernel_info: None,
                 kernel_id,
+                attached,
                 http_client: http_client.clone(),
             }) as Box<dyn RunningKernel>)
         })
@@ -275,29 +446,393 @@ impl RunningKernel for RemoteRunningKernel {
     }
 
     fn force_shutdown(&mut self, window: &mut Window, cx: &mut App) -> Task<anyhow::Result<()>> {
-        let url = self
-            .remote_server
-            .api_url(&format!("/kernels/{}", self.kernel_id));
-        let token = self.remote_server.token.clone();
+        // Kernels we attached to belong to whoever launched them, so we only
+        // drop our connection instead of deleting them from the server.
+        if self.attached {
+            return Task::ready(Ok(()));
+        }
+
+        let remote_server = RemoteServer {
+            base_url: self.remote_server.base_url.clone(),
+            token: self.remote_server.token.clone(),
+        };
+        let path = format!("/kernels/{}", self.kernel_id);
         let http_client = self.http_client.clone();
 
         window.spawn(cx, |_| async move {
-            let request = Request::builder()
-                .method("DELETE")
-                .uri(&url)
-                .header("Authorization", format!("token {}", token))
-                .body(AsyncBody::default())?;
-
-            let response = http_client.send(request).await?;
-
-            if response.status().is_success() {
-                Ok(())
-            } else {
-                Err(anyhow::anyhow!(
-                    "Failed to shutdown kernel: {}",
-                    response.status()
-                ))
+            send_remote_request(
+                &remote_server,
+                &http_client,
+                "DELETE",
+                &path,
+                AsyncBody::default(),
+            )
+            .await
+            .context("Failed to shutdown kernel")?;
+            Ok(())
+        })
+    }
+
+    fn is_attached(&self) -> bool {
+        self.attached
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_tungstenite::{
+        tungstenite::{
+            handshake::server::{
+                ErrorResponse, Request as HandshakeRequest, Response as HandshakeResponse,
+            },
+            Message,
+        },
+        WebSocketStream,
+    };
+    use futures::{channel::oneshot, FutureExt as _, SinkExt as _};
+    use gpui::{BackgroundExecutor, Context, TestAppContext};
+    use http_client::{FakeHttpClient, Response};
+    use jupyter_protocol::ShutdownRequest;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    const TOKEN: &str = "secret-token";
+
+    fn remote_server(token: &str) -> RemoteServer {
+        RemoteServer {
+            base_url: "http://localhost:8888".to_string(),
+            token: token.to_string(),
+        }
+    }
+
+    /// A fake Jupyter server exposing a single `python3` kernelspec and one running kernel.
+    fn fake_jupyter_server() -> Arc<dyn HttpClient> {
+        FakeHttpClient::create(|request| async move {
+            let authorized = request
+                .headers()
+                .get("Authorization")
+                .and_then(|value| value.to_str().ok())
+                == Some(format!("token {TOKEN}").as_str());
+            if !authorized {
+                return Ok(Response::builder().status(403).body(AsyncBody::default())?);
             }
+
+            let body = match (request.method().as_str(), request.uri().path()) {
+                ("GET", "/api/kernelspecs") => json!({
+                    "default": "python3",
+                    "kernelspecs": {
+                        "python3": {
+                            "name": "python3",
+                            "spec": {
+                                "argv": ["python", "-m", "ipykernel_launcher", "-f", "{connection_file}"],
+                                "display_name": "Python 3 (ipykernel)",
+                                "language": "python",
+                                "interrupt_mode": "signal",
+                                "env": {},
+                                "metadata": {}
+                            },
+                            "resources": {
+                                "logo-32x32": "/kernelspecs/python3/logo-32x32.png",
+                                "logo-64x64": "/kernelspecs/python3/logo-64x64.png"
+                            }
+                        }
+                    }
+                }),
+                ("GET", "/api/kernels") => json!([
+                    {
+                        "id": "4e2f6a1c-9a4b-4f8e-8a53-0f3c9d2b7e10",
+                        "name": "python3",
+                        "last_activity": "2024-01-01T00:00:00.000000Z",
+                        "execution_state": "idle",
+                        "connections": 1
+                    },
+                    {
+                        "id": "0b7d2c55-1d9e-4c3a-9f61-2a8e4b6c0d33",
+                        "name": "ir",
+                        "last_activity": "2024-01-01T00:00:00.000000Z",
+                        "execution_state": "busy",
+                        "connections": 0
+                    }
+                ]),
+                ("POST", "/api/kernels") => json!({
+                    "id": "9c1a3e77-5b2d-4e6f-8a90-1b2c3d4e5f60",
+                    "name": "python3",
+                    "last_activity": "2024-01-01T00:00:00.000000Z",
+                    "execution_state": "starting",
+                    "connections": 0
+                }),
+                _ => return Ok(Response::builder().status(404).body(AsyncBody::default())?),
+            };
+
+            Ok(Response::builder()
+                .status(200)
+                .body(AsyncBody::from(body.to_string()))?)
         })
     }
+
+    #[gpui::test]
+    async fn test_list_remote_kernel_specifications() {
+        let specs = list_remote_kernel_specifications(remote_server(TOKEN), fake_jupyter_server())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            specs
+                .iter()
+                .map(|spec| (spec.name.as_str(), spec.kernel_id.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("python3", None),
+                ("python3", Some("4e2f6a1c-9a4b-4f8e-8a53-0f3c9d2b7e10")),
+            ],
+            "running kernels without a matching kernelspec can't be attached to"
+        );
+        assert_eq!(specs[0].kernelspec.language, "python");
+        assert_eq!(specs[1].display_name(), "python3 (4e2f6a1c)");
+        assert_ne!(specs[0], specs[1]);
+    }
+
+    #[gpui::test]
+    async fn test_launch_remote_kernel() {
+        let kernel_id =
+            launch_remote_kernel(&remote_server(TOKEN), fake_jupyter_server(), "python3", "/")
+                .await
+                .unwrap();
+        assert_eq!(kernel_id, "9c1a3e77-5b2d-4e6f-8a90-1b2c3d4e5f60");
+    }
+
+    #[gpui::test]
+    async fn test_remote_authentication_failure() {
+        let error =
+            list_remote_kernel_specifications(remote_server("wrong-token"), fake_jupyter_server())
+                .await
+                .unwrap_err();
+
+        assert!(
+            format!("{error:#}").contains("Authentication with the Jupyter server"),
+            "unexpected error: {error:#}"
+        );
+    }
+
+    #[test]
+    fn test_kernel_channels_url() {
+        let kernel_id = "4e2f6a1c";
+        assert_eq!(
+            kernel_channels_url(&remote_server(TOKEN), kernel_id),
+            "ws://localhost:8888/api/kernels/4e2f6a1c/channels?token=secret-token"
+        );
+        assert_eq!(
+            kernel_channels_url(&remote_server("a b&c/=d"), kernel_id),
+            "ws://localhost:8888/api/kernels/4e2f6a1c/channels?token=a+b%26c%2F%3Dd"
+        );
+        assert_eq!(
+            kernel_channels_url(
+                &RemoteServer {
+                    base_url: "https://jupyter.example.com/user/me/".to_string(),
+                    token: String::new(),
+                },
+                kernel_id
+            ),
+            "wss://jupyter.example.com/user/me/api/kernels/4e2f6a1c/channels"
+        );
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum TestSessionEvent {
+        Routed(String),
+        Errored(String),
+    }
+
+    struct TestSession {
+        events: mpsc::UnboundedSender<TestSessionEvent>,
+    }
+
+    impl KernelSession for TestSession {
+        fn route(&mut self, message: &JupyterMessage, _: &mut Window, _: &mut Context<Self>) {
+            self.events
+                .unbounded_send(TestSessionEvent::Routed(message.header.msg_id.clone()))
+                .ok();
+        }
+
+        fn kernel_errored(&mut self, error_message: String, _: &mut Context<Self>) {
+            self.events
+                .unbounded_send(TestSessionEvent::Errored(error_message))
+                .ok();
+        }
+    }
+
+    type ServerConnection = (String, WebSocketStream<smol::net::TcpStream>);
+
+    /// A fake Jupyter server accepting websocket connections to kernel channels,
+    /// each of which is handed to the test along with the URI it requested. The
+    /// server stops listening when the returned task is dropped.
+    async fn fake_websocket_server(
+        executor: &BackgroundExecutor,
+    ) -> (String, mpsc::UnboundedReceiver<ServerConnection>, Task<()>) {
+        let listener = smol::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let (connections_tx, connections_rx) = mpsc::unbounded();
+
+        let server = executor.spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let (uri_tx, uri_rx) = oneshot::channel();
+                let callback = move |request: &HandshakeRequest, response: HandshakeResponse| {
+                    uri_tx.send(request.uri().to_string()).ok();
+                    Ok::<_, ErrorResponse>(response)
+                };
+                let Ok(stream) = async_tungstenite::accept_hdr_async(stream, callback).await else {
+                    continue;
+                };
+                let uri = uri_rx.await.unwrap();
+                connections_tx.unbounded_send((uri, stream)).ok();
+            }
+        });
+
+        (base_url, connections_rx, server)
+    }
+
+    fn attached_kernelspec(base_url: &str, token: &str) -> RemoteKernelSpecification {
+        RemoteKernelSpecification {
+            name: "python3".to_string(),
+            url: base_url.to_string(),
+            token: token.to_string(),
+            kernelspec: serde_json::from_value(json!({
+                "argv": ["python", "-m", "ipykernel_launcher", "-f", "{connection_file}"],
+                "display_name": "Python 3 (ipykernel)",
+                "language": "python",
+                "interrupt_mode": "signal",
+                "env": {},
+                "metadata": {}
+            }))
+            .unwrap(),
+            kernel_id: Some("kernel-1".to_string()),
+        }
+    }
+
+    /// A status message on the iopub channel, sent in reply to `parent`.
+    fn status_message(msg_id: &str, parent: &serde_json::Value) -> Message {
+        Message::Text(
+            json!({
+                "header": {
+                    "msg_id": msg_id,
+                    "username": "kernel",
+                    "session": "session-1",
+                    "date": "2024-01-01T00:00:00.000000Z",
+                    "msg_type": "status",
+                    "version": "5.3"
+                },
+                "parent_header": parent["header"],
+                "metadata": {},
+                "content": { "execution_state": "idle" },
+                "buffers": [],
+                "channel": "iopub"
+            })
+            .to_string(),
+        )
+    }
+
+    async fn next_request(connection: &mut ServerConnection) -> serde_json::Value {
+        loop {
+            match connection.1.next().await.unwrap().unwrap() {
+                Message::Text(text) => return serde_json::from_str(&text).unwrap(),
+                _ => continue,
+            }
+        }
+    }
+
+    /// Waits for `stream` to yield, advancing the clock past the kernel's
+    /// reconnect delays in the meantime.
+    async fn next_advancing_clock<T>(
+        stream: &mut mpsc::UnboundedReceiver<T>,
+        executor: &BackgroundExecutor,
+    ) -> T {
+        loop {
+            executor.advance_clock(RECONNECT_BASE_DELAY * 2u32.pow(MAX_RECONNECT_ATTEMPTS));
+            futures::select_biased! {
+                item = stream.next() => return item.unwrap(),
+                _ = smol::Timer::after(Duration::from_millis(10)).fuse() => {}
+            }
+        }
+    }
+
+    #[gpui::test]
+    async fn test_remote_kernel_websocket(cx: &mut TestAppContext) {
+        cx.executor().allow_parking();
+        let executor = cx.executor();
+        let (base_url, mut connections, server) = fake_websocket_server(&executor).await;
+        let (events_tx, mut events) = mpsc::unbounded();
+        let session = cx.new(|_| TestSession { events: events_tx });
+        let cx = cx.add_empty_window();
+
+        let mut kernel = cx
+            .update(|window, cx| {
+                RemoteRunningKernel::new(
+                    attached_kernelspec(&base_url, "a b&c"),
+                    PathBuf::from("/"),
+                    session.clone(),
+                    window,
+                    cx,
+                )
+            })
+            .await
+            .unwrap();
+
+        let mut connection = connections.next().await.unwrap();
+        assert_eq!(
+            connection.0, "/api/kernels/kernel-1/channels?token=a+b%26c",
+            "the token is encoded in the query"
+        );
+
+        // Requests are sent to the server, and its messages are routed to the session.
+        let message: JupyterMessage = ShutdownRequest { restart: false }.into();
+        kernel.request_tx().try_send(message).unwrap();
+        let request = next_request(&mut connection).await;
+        assert_eq!(request["header"]["msg_type"], "shutdown_request");
+
+        connection
+            .1
+            .send(status_message("first-connection", &request))
+            .await
+            .unwrap();
+        assert_eq!(
+            events.next().await,
+            Some(TestSessionEvent::Routed("first-connection".to_string()))
+        );
+
+        // When the server drops the connection, the kernel reconnects.
+        connection.1.close(None).await.unwrap();
+        drop(connection);
+        let mut connection = next_advancing_clock(&mut connections, &executor).await;
+        assert_eq!(connection.0, "/api/kernels/kernel-1/channels?token=a+b%26c");
+
+        let message: JupyterMessage = ShutdownRequest { restart: true }.into();
+        kernel.request_tx().try_send(message).unwrap();
+        let request = next_request(&mut connection).await;
+        connection
+            .1
+            .send(status_message("second-connection", &request))
+            .await
+            .unwrap();
+        assert_eq!(
+            events.next().await,
+            Some(TestSessionEvent::Routed("second-connection".to_string()))
+        );
+
+        // Shutting down an attached kernel only detaches from it, without
+        // asking the server to delete it.
+        assert!(kernel.is_attached());
+        cx.update(|window, cx| kernel.force_shutdown(window, cx))
+            .await
+            .unwrap();
+
+        // Once the server is gone, the kernel gives up after its reconnect attempts.
+        drop(server);
+        connection.1.close(None).await.unwrap();
+        drop(connection);
+        assert_eq!(
+            next_advancing_clock(&mut events, &executor).await,
+            TestSessionEvent::Errored(format!("Lost connection to the kernel at {base_url}"))
+        );
+    }
 }
@@ -1,4 +1,6 @@
 mod cell;
+mod notebook_kernel;
 mod notebook_ui;
 pub use cell::*;
+pub use notebook_kernel::*;
 pub use notebook_ui::*;
@@ -1,20 +1,25 @@
 #![allow(unused, dead_code)]
 use std::sync::Arc;
 
-use editor::{Editor, EditorMode, MultiBuffer};
+use editor::{Editor, EditorEvent, EditorMode, MultiBuffer};
 use futures::future::Shared;
-use gpui::{prelude::*, App, Entity, Hsla, Task, TextStyleRefinement};
+use gpui::{
+    prelude::*, App, ClickEvent, Entity, EventEmitter, Focusable, Hsla, Subscription, Task,
+    TextStyleRefinement,
+};
 use language::{Buffer, Language, LanguageRegistry};
 use markdown_preview::{markdown_parser::parse_markdown, markdown_renderer::render_markdown_block};
 use nbformat::v4::{CellId, CellMetadata, CellType};
+use runtimelib::{ExecutionState, JupyterMessageContent, MimeBundle};
 use settings::Settings as _;
 use theme::ThemeSettings;
 use ui::{prelude::*, IconButtonShape};
 use util::ResultExt;
+use uuid::Uuid;
 
 use crate::{
     notebook::{CODE_BLOCK_INSET, GUTTER_WIDTH},
-    outputs::{plain::TerminalOutput, user_error::ErrorView, Output},
+    outputs::{plain::TerminalOutput, user_error::ErrorView, ExecutionStatus, Output},
 };
 
 #[derive(Copy, Clone, PartialEq, PartialOrd)]
@@ -110,6 +115,162 @@ fn convert_outputs(
         .collect()
 }
 
+/// Converts an output message from the kernel to an nbformat output, so it can be saved.
+pub(crate) fn nbformat_output(message: &JupyterMessageContent) -> Option<nbformat::v4::Output> {
+    let (output_type, content) = match message {
+        JupyterMessageContent::ExecuteResult(result) => {
+            ("execute_result", serde_json::to_value(result))
+        }
+        JupyterMessageContent::DisplayData(data) => ("display_data", serde_json::to_value(data)),
+        JupyterMessageContent::StreamContent(stream) => ("stream", serde_json::to_value(stream)),
+        JupyterMessageContent::ErrorOutput(error) => ("error", serde_json::to_value(error)),
+        _ => return None,
+    };
+
+    let mut content = content.log_err()?;
+    let object = content.as_object_mut()?;
+    // Transient data, such as display IDs, is not stored in notebooks.
+    object.remove("transient");
+    object.insert("output_type".into(), output_type.into());
+    serde_json::from_value(content).log_err()
+}
+
+/// Appends the text of a stream output to the last output if it belongs to the same stream.
+///
+/// Returns false if the output needs to be added as a new output instead.
+pub(crate) fn merge_stream_output(
+    outputs: &mut [nbformat::v4::Output],
+    output: &nbformat::v4::Output,
+) -> bool {
+    match (outputs.last_mut(), output) {
+        (
+            Some(nbformat::v4::Output::Stream {
+                name: last_name,
+                text: last_text,
+            }),
+            nbformat::v4::Output::Stream { name, text },
+        ) if last_name == name => {
+            last_text.0.push_str(&text.0);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Replaces the data of a display data or execute result output.
+fn with_display_data(
+    output: &nbformat::v4::Output,
+    data: &MimeBundle,
+) -> Option<nbformat::v4::Output> {
+    let mut output = serde_json::to_value(output).log_err()?;
+    output
+        .as_object_mut()?
+        .insert("data".into(), serde_json::to_value(data).log_err()?);
+    serde_json::from_value(output).log_err()
+}
+
+/// Splits cell source into lines, as they are stored in nbformat.
+pub fn source_lines(source: &str) -> Vec<String> {
+    source
+        .split_inclusive('\n')
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// Returns an empty nbformat cell of the given type with a new, unique ID.
+pub fn new_nbformat_cell(cell_type: CellType, source: &str) -> nbformat::v4::Cell {
+    nbformat_cell(
+        cell_type,
+        CellId::from(Uuid::new_v4()),
+        empty_cell_metadata(),
+        source,
+    )
+}
+
+/// Returns an nbformat cell of the given type, without outputs.
+pub fn nbformat_cell(
+    cell_type: CellType,
+    id: CellId,
+    metadata: CellMetadata,
+    source: &str,
+) -> nbformat::v4::Cell {
+    let source = source_lines(source);
+    match cell_type {
+        CellType::Code => nbformat::v4::Cell::Code {
+            id,
+            metadata,
+            execution_count: None,
+            source,
+            outputs: Vec::new(),
+        },
+        CellType::Markdown => nbformat::v4::Cell::Markdown {
+            id,
+            metadata,
+            source,
+            attachments: None,
+        },
+        CellType::Raw => nbformat::v4::Cell::Raw {
+            id,
+            metadata,
+            source,
+        },
+    }
+}
+
+fn empty_cell_metadata() -> CellMetadata {
+    serde_json::from_value(serde_json::json!({})).expect("empty cell metadata is valid")
+}
+
+fn cell_editor(
+    text: &str,
+    language: Option<Shared<Task<Option<Arc<Language>>>>>,
+    window: &mut Window,
+    cx: &mut App,
+) -> (Entity<Editor>, Task<()>) {
+    let buffer = cx.new(|cx| Buffer::local(text.to_string(), cx));
+    let multi_buffer = cx.new(|cx| MultiBuffer::singleton(buffer.clone(), cx));
+
+    let editor = cx.new(|cx| {
+        let mut editor = Editor::new(
+            EditorMode::AutoHeight { max_lines: 1024 },
+            multi_buffer,
+            None,
+            false,
+            window,
+            cx,
+        );
+
+        let theme = ThemeSettings::get_global(cx);
+
+        let refinement = TextStyleRefinement {
+            font_family: Some(theme.buffer_font.family.clone()),
+            font_size: Some(theme.buffer_font_size(cx).into()),
+            color: Some(cx.theme().colors().editor_foreground),
+            background_color: Some(gpui::transparent_black()),
+            ..Default::default()
+        };
+
+        editor.set_show_gutter(false, cx);
+        editor.set_text_style_refinement(refinement);
+        editor
+    });
+
+    let language_task = match language {
+        Some(language) => window.spawn(cx, |mut cx| async move {
+            let language = language.await;
+
+            buffer
+                .update(&mut cx, |buffer, cx| {
+                    buffer.set_language(language, cx);
+                })
+                .ok();
+        }),
+        None => Task::ready(()),
+    };
+
+    (editor, language_task)
+}
+
 impl Cell {
     pub fn load(
         cell: &nbformat::v4::Cell,
@@ -123,39 +284,47 @@ impl Cell {
                 id,
                 metadata,
                 source,
-                ..
+                attachments,
             } => {
                 let source = source.join("");
+                let markdown_language = {
+                    let languages = languages.clone();
+                    cx.background_spawn(async move {
+                        languages.language_for_name("Markdown").await.ok()
+                    })
+                    .shared()
+                };
+                let (editor, language_task) =
+                    cell_editor(&source, Some(markdown_language), window, cx);
 
                 let entity = cx.new(|cx| {
-                    let markdown_parsing_task = {
-                        let languages = languages.clone();
-                        let source = source.clone();
-
-                        cx.spawn_in(window, |this, mut cx| async move {
-                            let parsed_markdown = cx
-                                .background_spawn(async move {
-                                    parse_markdown(&source, None, Some(languages)).await
-                                })
-                                .await;
-
-                            this.update(&mut cx, |cell: &mut MarkdownCell, _| {
-                                cell.parsed_markdown = Some(parsed_markdown);
-                            })
-                            .log_err();
-                        })
-                    };
+                    let editor_subscription = cx.subscribe_in(
+                        &editor,
+                        window,
+                        |this: &mut MarkdownCell, _, event: &EditorEvent, window, cx| {
+                            if let EditorEvent::Blurred = event {
+                                this.finish_editing(window, cx);
+                            }
+                        },
+                    );
 
-                    MarkdownCell {
-                        markdown_parsing_task,
+                    let mut cell = MarkdownCell {
+                        markdown_parsing_task: Task::ready(()),
                         languages: languages.clone(),
                         id: id.clone(),
                         metadata: metadata.clone(),
+                        attachments: attachments.clone(),
                         source: source.clone(),
+                        editor,
+                        editing: false,
                         parsed_markdown: None,
                         selected: false,
                         cell_position: None,
-                    }
+                        language_task,
+                        _editor_subscription: editor_subscription,
+                    };
+                    cell.parse_markdown(window, cx);
+                    cell
                 });
 
                 Cell::Markdown(entity)
@@ -168,54 +337,19 @@ impl Cell {
                 outputs,
             } => Cell::Code(cx.new(|cx| {
                 let text = source.join("");
-
-                let buffer = cx.new(|cx| Buffer::local(text.clone(), cx));
-                let multi_buffer = cx.new(|cx| MultiBuffer::singleton(buffer.clone(), cx));
-
-                let editor_view = cx.new(|cx| {
-                    let mut editor = Editor::new(
-                        EditorMode::AutoHeight { max_lines: 1024 },
-                        multi_buffer,
-                        None,
-                        false,
-                        window,
-                        cx,
-                    );
-
-                    let theme = ThemeSettings::get_global(cx);
-
-                    let refinement = TextStyleRefinement {
-                        font_family: Some(theme.buffer_font.family.clone()),
-                        font_size: Some(theme.buffer_font_size(cx).into()),
-                        color: Some(cx.theme().colors().editor_foreground),
-                        background_color: Some(gpui::transparent_black()),
-                        ..Default::default()
-                    };
-
-                    editor.set_text(text, window, cx);
-                    editor.set_show_gutter(false, cx);
-                    editor.set_text_style_refinement(refinement);
-
-                    // editor.set_read_only(true);
-                    editor
-                });
-
-                let buffer = buffer.clone();
-                let language_task = cx.spawn_in(window, |this, mut cx| async move {
-                    let language = notebook_language.await;
-
-                    buffer.update(&mut cx, |buffer, cx| {
-                        buffer.set_language(language.clone(), cx);
-                    });
-                });
+                let (editor, language_task) =
+                    cell_editor(&text, Some(notebook_language), window, cx);
 
                 CodeCell {
                     id: id.clone(),
                     metadata: metadata.clone(),
                     execution_count: *execution_count,
-                    source: source.join(""),
-                    editor: editor_view,
+                    source: text,
+                    editor,
                     outputs: convert_outputs(outputs, window, cx),
+                    nbformat_outputs: outputs.clone(),
+                    execution_status: ExecutionStatus::Unknown,
+                    clear_on_next_output: false,
                     selected: false,
                     language_task,
                     cell_position: None,
@@ -225,13 +359,90 @@ impl Cell {
                 id,
                 metadata,
                 source,
-            } => Cell::Raw(cx.new(|_| RawCell {
-                id: id.clone(),
-                metadata: metadata.clone(),
-                source: source.join(""),
-                selected: false,
-                cell_position: None,
-            })),
+            } => {
+                let source = source.join("");
+                let (editor, _) = cell_editor(&source, None, window, cx);
+                Cell::Raw(cx.new(|_| RawCell {
+                    id: id.clone(),
+                    metadata: metadata.clone(),
+                    source,
+                    editor,
+                    selected: false,
+                    cell_position: None,
+                }))
+            }
+        }
+    }
+
+    pub fn id(&self, cx: &App) -> CellId {
+        match self {
+            Cell::Code(cell) => cell.read(cx).id.clone(),
+            Cell::Markdown(cell) => cell.read(cx).id.clone(),
+            Cell::Raw(cell) => cell.read(cx).id.clone(),
+        }
+    }
+
+    pub fn cell_type(&self) -> CellType {
+        match self {
+            Cell::Code(_) => CellType::Code,
+            Cell::Markdown(_) => CellType::Markdown,
+            Cell::Raw(_) => CellType::Raw,
+        }
+    }
+
+    /// Returns the editor containing the cell's source.
+    pub fn editor(&self, cx: &App) -> Entity<Editor> {
+        match self {
+            Cell::Code(cell) => cell.read(cx).editor.clone(),
+            Cell::Markdown(cell) => cell.read(cx).editor.clone(),
+            Cell::Raw(cell) => cell.read(cx).editor.clone(),
+        }
+    }
+
+    pub fn metadata(&self, cx: &App) -> CellMetadata {
+        match self {
+            Cell::Code(cell) => cell.read(cx).metadata.clone(),
+            Cell::Markdown(cell) => cell.read(cx).metadata.clone(),
+            Cell::Raw(cell) => cell.read(cx).metadata.clone(),
+        }
+    }
+
+    /// Returns the current source of the cell, including unsaved edits.
+    pub fn current_source(&self, cx: &App) -> String {
+        self.editor(cx).read(cx).text(cx)
+    }
+
+    /// Converts the cell back to nbformat, preserving its outputs.
+    pub fn to_nbformat_cell(&self, cx: &App) -> nbformat::v4::Cell {
+        let source = source_lines(&self.current_source(cx));
+        match self {
+            Cell::Code(cell) => {
+                let cell = cell.read(cx);
+                nbformat::v4::Cell::Code {
+                    id: cell.id.clone(),
+                    metadata: cell.metadata.clone(),
+                    execution_count: cell.execution_count,
+                    source,
+                    outputs: cell.nbformat_outputs.clone(),
+                }
+            }
+            Cell::Markdown(cell) => {
+                let cell = cell.read(cx);
+                nbformat::v4::Cell::Markdown {
+                    id: cell.id.clone(),
+                    metadata: cell.metadata.clone(),
+                    source,
+                    attachments: cell.attachments.clone(),
+                }
+            }
+            Cell::Raw(cell) => {
+                let cell = cell.read(cx);
+                nbformat::v4::Cell::Raw {
+                    id: cell.id.clone(),
+                    metadata: cell.metadata.clone(),
+                    source,
+                }
+            }
         }
     }
 }
@@ -330,12 +541,65 @@ pub trait RunnableCell: RenderableCell {
 pub struct MarkdownCell {
     id: CellId,
     metadata: CellMetadata,
+    attachments: Option<serde_json::Value>,
     source: String,
+    editor: Entity<Editor>,
+    editing: bool,
     parsed_markdown: Option<markdown_preview::markdown_elements::ParsedMarkdown>,
     markdown_parsing_task: Task<()>,
     selected: bool,
     cell_position: Option<CellPosition>,
     languages: Arc<LanguageRegistry>,
+    language_task: Task<()>,
+    _editor_subscription: Subscription,
+}
+
+impl MarkdownCell {
+    pub fn is_editing(&self) -> bool {
+        self.editing
+    }
+
+    /// Shows the markdown source in an editor in place of the rendered markdown.
+    pub fn start_editing(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.editing = true;
+        window.focus(&self.editor.focus_handle(cx));
+        cx.notify();
+    }
+
+    pub fn finish_editing(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.editing {
+            return;
+        }
+
+        self.editing = false;
+        self.refresh_markdown(window, cx);
+    }
+
+    /// Re-renders the markdown after the source in the cell's editor has changed.
+    pub fn refresh_markdown(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.source = self.editor.read(cx).text(cx);
+        self.parse_markdown(window, cx);
+        cx.notify();
+    }
+
+    fn parse_markdown(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let languages = self.languages.clone();
+        let source = self.source.clone();
+
+        self.markdown_parsing_task = cx.spawn_in(window, |this, mut cx| async move {
+            let parsed_markdown = cx
+                .background_spawn(
+                    async move { parse_markdown(&source, None, Some(languages)).await },
+                )
+                .await;
+
+            this.update(&mut cx, |cell: &mut MarkdownCell, cx| {
+                cell.parsed_markdown = Some(parsed_markdown);
+                cx.notify();
+            })
+            .log_err();
+        });
+    }
 }
 
 impl RenderableCell for MarkdownCell {
@@ -382,8 +646,41 @@ impl RenderableCell for MarkdownCell {
 
 impl Render for MarkdownCell {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        if self.editing {
+            return v_flex()
+                .size_full()
+                .children(self.cell_position_spacer(true, window, cx))
+                .child(
+                    h_flex()
+                        .w_full()
+                        .pr_6()
+                        .rounded_sm()
+                        .items_start()
+                        .gap(DynamicSpacing::Base08.rems(cx))
+                        .bg(self.selected_bg_color(window, cx))
+                        .child(self.gutter(window, cx))
+                        .child(
+                            div().py_1p5().w_full().child(
+                                div()
+                                    .flex()
+                                    .size_full()
+                                    .flex_1()
+                                    .py_3()
+                                    .px_5()
+                                    .rounded_lg()
+                                    .border_1()
+                                    .border_color(cx.theme().colors().border)
+                                    .bg(cx.theme().colors().editor_background)
+                                    .child(div().w_full().child(self.editor.clone())),
+                            ),
+                        ),
+                )
+                .children(self.cell_position_spacer(false, window, cx))
+                .into_any_element();
+        }
+
         let Some(parsed) = self.parsed_markdown.as_ref() else {
-            return div();
+            return div().into_any_element();
         };
 
         let mut markdown_render_context =
@@ -404,12 +701,22 @@ impl Render for MarkdownCell {
                     .child(self.gutter(window, cx))
                     .child(
                         v_flex()
+                            .id("markdown-cell")
                             .size_full()
                             .flex_1()
                             .p_3()
                             .font_ui(cx)
                             .text_size(TextSize::Default.rems(cx))
-                            //
+                            .on_click(cx.listener(|this, event: &ClickEvent, window, cx| {
+                                if event.up.click_count == 2 {
+                                    this.start_editing(window, cx);
+                                }
+                            }))
+                            .when(parsed.children.is_empty(), |this| {
+                                this.child(
+                                    Label::new("Double-click to edit markdown").color(Color::Muted),
+                                )
+                            })
                             .children(parsed.children.iter().map(|child| {
                                 div().relative().child(div().relative().child(
                                     render_markdown_block(child, &mut markdown_render_context),
@@ -419,6 +726,7 @@ impl Render for MarkdownCell {
             )
             // TODO: Move base cell render into trait impl so we don't have to repeat this
             .children(self.cell_position_spacer(false, window, cx))
+            .into_any_element()
     }
 }
 
@@ -429,6 +737,12 @@ pub struct CodeCell {
     source: String,
     editor: Entity<editor::Editor>,
     outputs: Vec<Output>,
+    /// The outputs as they are stored in the notebook, so they can be saved back to it.
+    /// These correspond one-to-one with `outputs`.
+    nbformat_outputs: Vec<nbformat::v4::Output>,
+    execution_status: ExecutionStatus,
+    /// Whether a `clear_output` message asked to clear the outputs once the next output arrives.
+    clear_on_next_output: bool,
     selected: bool,
     cell_position: Option<CellPosition>,
     language_task: Task<()>,
@@ -444,6 +758,175 @@ impl CodeCell {
 
     pub fn clear_outputs(&mut self) {
         self.outputs.clear();
+        self.nbformat_outputs.clear();
+        self.execution_count = None;
+        self.clear_on_next_output = false;
+    }
+
+    pub fn current_source(&self, cx: &App) -> String {
+        self.editor.read(cx).text(cx)
+    }
+
+    pub fn execution_status(&self) -> &ExecutionStatus {
+        &self.execution_status
+    }
+
+    pub fn set_execution_status(&mut self, status: ExecutionStatus, cx: &mut Context<Self>) {
+        self.execution_status = status;
+        cx.notify();
+    }
+
+    /// Clears the outputs of a previous run, and marks the cell as waiting for the kernel.
+    pub fn start_execution(&mut self, cx: &mut Context<Self>) {
+        self.clear_outputs();
+        self.execution_status = ExecutionStatus::Queued;
+        cx.notify();
+    }
+
+    /// Applies a message from the kernel that belongs to this cell's execution.
+    pub fn handle_message(
+        &mut self,
+        message: &JupyterMessageContent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match message {
+            JupyterMessageContent::Status(status) => {
+                self.execution_status = match status.execution_state {
+                    ExecutionState::Busy => ExecutionStatus::Executing,
+                    ExecutionState::Idle => ExecutionStatus::Finished,
+                };
+            }
+            JupyterMessageContent::ExecuteInput(input) => {
+                self.execution_count = Some(input.execution_count.0 as i32);
+            }
+            JupyterMessageContent::ExecuteReply(reply) => {
+                self.execution_count = Some(reply.execution_count.0 as i32);
+            }
+            JupyterMessageContent::ClearOutput(options) => {
+                if options.wait {
+                    self.clear_on_next_output = true;
+                } else {
+                    self.outputs.clear();
+                    self.nbformat_outputs.clear();
+                }
+            }
+            JupyterMessageContent::StreamContent(stream) => {
+                let Some(nbformat_output) = nbformat_output(message) else {
+                    return;
+                };
+                self.clear_if_requested();
+
+                if merge_stream_output(&mut self.nbformat_outputs, &nbformat_output) {
+                    if let Some(Output::Stream { content }) = self.outputs.last() {
+                        content.update(cx, |content, cx| content.append_text(&stream.text, cx));
+                    }
+                } else {
+                    self.outputs.push(Output::Stream {
+                        content: cx.new(|cx| TerminalOutput::from(&stream.text, window, cx)),
+                    });
+                    self.nbformat_outputs.push(nbformat_output);
+                }
+            }
+            JupyterMessageContent::ExecuteResult(_)
+            | JupyterMessageContent::DisplayData(_)
+            | JupyterMessageContent::ErrorOutput(_) => {
+                let Some(nbformat_output) = nbformat_output(message) else {
+                    return;
+                };
+                let output = match message {
+                    JupyterMessageContent::ExecuteResult(result) => Output::new(
+                        &result.data,
+                        result.transient.as_ref().and_then(|t| t.display_id.clone()),
+                        window,
+                        cx,
+                    ),
+                    JupyterMessageContent::DisplayData(data) => Output::new(
+                        &data.data,
+                        data.transient.as_ref().and_then(|t| t.display_id.clone()),
+                        window,
+                        cx,
+                    ),
+                    JupyterMessageContent::ErrorOutput(error) => Output::ErrorOutput(ErrorView {
+                        ename: error.ename.clone(),
+                        evalue: error.evalue.clone(),
+                        traceback: cx.new(|cx| {
+                            TerminalOutput::from(&error.traceback.join("\n"), window, cx)
+                        }),
+                    }),
+                    _ => return,
+                };
+                self.clear_if_requested();
+                self.outputs.push(output);
+                self.nbformat_outputs.push(nbformat_output);
+            }
+            _ => return,
+        }
+
+        cx.notify();
+    }
+
+    /// Replaces the outputs shown for the given display ID.
+    pub fn update_display_data(
+        &mut self,
+        data: &MimeBundle,
+        display_id: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        let mut updated = false;
+        for (ix, output) in self.outputs.iter_mut().enumerate() {
+            if output.display_id().as_deref() != Some(display_id) {
+                continue;
+            }
+
+            *output = Output::new(data, Some(display_id.to_string()), window, cx);
+            if let Some(nbformat_output) = self.nbformat_outputs.get_mut(ix) {
+                if let Some(updated_output) = with_display_data(nbformat_output, data) {
+                    *nbformat_output = updated_output;
+                }
+            }
+            updated = true;
+        }
+
+        if updated {
+            cx.notify();
+        }
+        updated
+    }
+
+    fn clear_if_requested(&mut self) {
+        if self.clear_on_next_output {
+            self.clear_on_next_output = false;
+            self.outputs.clear();
+            self.nbformat_outputs.clear();
+        }
+    }
+
+    fn render_execution_status(&self) -> Option<AnyElement> {
+        match &self.execution_status {
+            ExecutionStatus::Queued => Some(
+                Label::new("Queued...")
+                    .color(Color::Muted)
+                    .into_any_element(),
+            ),
+            ExecutionStatus::ConnectingToKernel => Some(
+                Label::new("Connecting to kernel...")
+                    .color(Color::Muted)
+                    .into_any_element(),
+            ),
+            ExecutionStatus::Executing => Some(
+                Label::new("Executing...")
+                    .color(Color::Muted)
+                    .into_any_element(),
+            ),
+            ExecutionStatus::KernelErrored(error) => Some(
+                Label::new(format!("Kernel error: {}", error))
+                    .color(Color::Error)
+                    .into_any_element(),
+            ),
+            _ => None,
+        }
     }
 
     fn output_control(&self) -> Option<CellControlType> {
@@ -520,8 +1003,8 @@ impl RenderableCell for CodeCell {
             CellControl::new("rerun-cell", CellControlType::RerunCell)
         } else {
             CellControl::new("run-cell", CellControlType::RunCell)
-                .on_click(cx.listener(move |this, _, window, cx| this.run(window, cx)))
-        };
+        }
+        .on_click(cx.listener(move |this, _, window, cx| this.run(window, cx)));
 
         Some(cell_control)
     }
@@ -546,8 +1029,8 @@ impl RenderableCell for CodeCell {
 }
 
 impl RunnableCell for CodeCell {
-    fn run(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        println!("Running code cell: {}", self.id);
+    fn run(&mut self, _window: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(CodeCellEvent::Run);
     }
 
     fn execution_count(&self) -> Option<i32> {
@@ -561,6 +1044,13 @@ impl RunnableCell for CodeCell {
     }
 }
 
+pub enum CodeCellEvent {
+    /// The cell asked to be executed by the notebook's kernel.
+    Run,
+}
+
+impl EventEmitter<CodeCellEvent> for CodeCell {}
+
 impl Render for CodeCell {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         v_flex()
@@ -615,45 +1105,63 @@ impl Render for CodeCell {
                                 .border_1()
                                 // .border_color(cx.theme().colors().border)
                                 // .bg(cx.theme().colors().editor_background)
-                                .child(div().w_full().children(self.outputs.iter().map(
-                                    |output| {
-                                        let content = match output {
-                                            Output::Plain { content, .. } => {
-                                                Some(content.clone().into_any_element())
-                                            }
-                                            Output::Markdown { content, .. } => {
-                                                Some(content.clone().into_any_element())
-                                            }
-                                            Output::Stream { content, .. } => {
-                                                Some(content.clone().into_any_element())
-                                            }
-                                            Output::Image { content, .. } => {
-                                                Some(content.clone().into_any_element())
-                                            }
-                                            Output::Message(message) => Some(
-                                                div().child(message.clone()).into_any_element(),
-                                            ),
-                                            Output::Table { content, .. } => {
-                                                Some(content.clone().into_any_element())
-                                            }
-                                            Output::ErrorOutput(error_view) => {
-                                                error_view.render(window, cx)
-                                            }
-                                            Output::ClearOutputWaitMarker => None,
-                                        };
-
-                                        div()
-                                            // .w_full()
-                                            // .mt_3()
-                                            // .p_3()
-                                            // .rounded_md()
-                                            // .bg(cx.theme().colors().editor_background)
-                                            // .border(px(1.))
-                                            // .border_color(cx.theme().colors().border)
-                                            // .shadow_sm()
-                                            .children(content)
-                                    },
-                                ))),
+                                .when_some(self.execution_count(), |this, count| {
+                                    this.child(
+                                        div().flex_none().pr_2().child(
+                                            Label::new(format!("[{}]", count))
+                                                .size(LabelSize::Small)
+                                                .color(Color::Muted),
+                                        ),
+                                    )
+                                })
+                                .child(
+                                    div()
+                                        .w_full()
+                                        .children(self.outputs.iter().map(|output| {
+                                            let content = match output {
+                                                Output::Plain { content, .. } => {
+                                                    Some(content.clone().into_any_element())
+                                                }
+                                                Output::Markdown { content, .. } => {
+                                                    Some(content.clone().into_any_element())
+                                                }
+                                                Output::Stream { content, .. } => {
+                                                    Some(content.clone().into_any_element())
+                                                }
+                                                Output::Image { content, .. } => {
+                                                    Some(content.clone().into_any_element())
+                                                }
+                                                Output::Message(message) => Some(
+                                                    div().child(message.clone()).into_any_element(),
+                                                ),
+                                                Output::Table { content, .. } => {
+                                                    Some(content.clone().into_any_element())
+                                                }
+                                                Output::Latex { content, .. } => {
+                                                    Some(content.clone().into_any_element())
+                                                }
+                                                Output::Json { content, .. } => {
+                                                    Some(content.clone().into_any_element())
+                                                }
+                                                Output::ErrorOutput(error_view) => {
+                                                    error_view.render(window, cx)
+                                                }
+                                                Output::ClearOutputWaitMarker => None,
+                                            };
+
+                                            div()
+                                                // .w_full()
+                                                // .mt_3()
+                                                // .p_3()
+                                                // .rounded_md()
+                                                // .bg(cx.theme().colors().editor_background)
+                                                // .border(px(1.))
+                                                // .border_color(cx.theme().colors().border)
+                                                // .shadow_sm()
+                                                .children(content)
+                                        }))
+                                        .children(self.render_execution_status()),
+                                ),
                         ),
                     ),
             )
@@ -666,6 +1174,7 @@ pub struct RawCell {
     id: CellId,
     metadata: CellMetadata,
     source: String,
+    editor: Entity<Editor>,
     selected: bool,
     cell_position: Option<CellPosition>,
 }
@@ -729,12 +1238,50 @@ impl Render for RawCell {
                             .size_full()
                             .flex_1()
                             .p_3()
-                            .font_ui(cx)
-                            .text_size(TextSize::Default.rems(cx))
-                            .child(self.source.clone()),
+                            .child(div().w_full().child(self.editor.clone())),
                     ),
             )
             // TODO: Move base cell render into trait impl so we don't have to repeat this
             .children(self.cell_position_spacer(false, window, cx))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runtimelib::{Stdio, StreamContent};
+
+    fn stream(name: Stdio, text: &str) -> nbformat::v4::Output {
+        nbformat_output(&JupyterMessageContent::StreamContent(StreamContent {
+            name,
+            text: text.to_string(),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_stream_outputs_are_merged_per_stream() {
+        let mut outputs = vec![stream(Stdio::Stdout, "1\n")];
+
+        assert!(merge_stream_output(
+            &mut outputs,
+            &stream(Stdio::Stdout, "2\n")
+        ));
+        assert!(!merge_stream_output(
+            &mut outputs,
+            &stream(Stdio::Stderr, "oops\n")
+        ));
+        outputs.push(stream(Stdio::Stderr, "oops\n"));
+
+        match &outputs[0] {
+            nbformat::v4::Output::Stream { text, .. } => assert_eq!(text.0, "1\n2\n"),
+            _ => panic!("expected a stream output"),
+        }
+
+        let outputs = serde_json::to_value(&outputs).unwrap();
+        assert_eq!(outputs[0]["output_type"], "stream");
+        assert_eq!(outputs[0]["name"], "stdout");
+        assert_eq!(outputs[1]["name"], "stderr");
+        assert_eq!(outputs.as_array().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,315 @@
+use collections::HashMap;
+use nbformat::v4::CellId;
+use runtimelib::{ExecuteRequest, InterruptRequest, JupyterMessage};
+
+use crate::kernels::Kernel;
+use crate::KernelSpecification;
+
+/// The kernel that a notebook's cells are executed against, along with the
+/// executions that haven't finished yet.
+#[derive(Debug)]
+pub struct NotebookKernel {
+    pub kernel: Kernel,
+    pub kernel_specification: Option<KernelSpecification>,
+    /// Requests made while the kernel is starting, which are sent once it is running.
+    queued_messages: Vec<JupyterMessage>,
+    /// The cell executed by each pending execute request, keyed by the request's message ID.
+    executions: HashMap<String, CellId>,
+}
+
+impl Default for NotebookKernel {
+    fn default() -> Self {
+        Self {
+            kernel: Kernel::Shutdown,
+            kernel_specification: None,
+            queued_messages: Vec::new(),
+            executions: HashMap::default(),
+        }
+    }
+}
+
+impl NotebookKernel {
+    /// Whether the kernel is running, or will be running once it has started.
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self.kernel,
+            Kernel::RunningKernel(_) | Kernel::StartingKernel(_) | Kernel::Restarting
+        )
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self.kernel, Kernel::RunningKernel(_))
+    }
+
+    pub fn has_pending_executions(&self) -> bool {
+        !self.executions.is_empty()
+    }
+
+    /// Replaces the kernel, returning the cells whose executions were abandoned.
+    ///
+    /// Messages queued while the kernel was starting are sent once it is running.
+    pub fn set_kernel(&mut self, kernel: Kernel) -> Vec<CellId> {
+        self.kernel = kernel;
+        match &mut self.kernel {
+            Kernel::RunningKernel(kernel) => {
+                let mut request_tx = kernel.request_tx();
+                for message in self.queued_messages.drain(..) {
+                    request_tx.try_send(message).ok();
+                }
+                Vec::new()
+            }
+            Kernel::StartingKernel(_) | Kernel::Restarting => Vec::new(),
+            Kernel::ErroredLaunch(_) | Kernel::ShuttingDown | Kernel::Shutdown => {
+                self.abandon_executions()
+            }
+        }
+    }
+
+    /// Forgets about all pending executions, returning the cells they belonged to.
+    pub fn abandon_executions(&mut self) -> Vec<CellId> {
+        self.queued_messages.clear();
+        self.executions
+            .drain()
+            .map(|(_, cell_id)| cell_id)
+            .collect()
+    }
+
+    /// Requests the execution of the given code for a cell, returning the ID of the request.
+    ///
+    /// Returns `None` if the kernel is neither running nor starting.
+    pub fn execute(&mut self, cell_id: CellId, code: String) -> Option<String> {
+        if !self.is_active() {
+            return None;
+        }
+
+        let message: JupyterMessage = ExecuteRequest {
+            code,
+            ..ExecuteRequest::default()
+        }
+        .into();
+        let message_id = message.header.msg_id.clone();
+
+        // Re-running a cell replaces its previous execution.
+        self.executions.retain(|_, id| *id != cell_id);
+        self.executions.insert(message_id.clone(), cell_id);
+        self.send(message);
+        Some(message_id)
+    }
+
+    /// Interrupts the kernel, returning the cells whose executions were dropped
+    /// because the kernel hadn't started yet.
+    pub fn interrupt(&mut self) -> Vec<CellId> {
+        match &mut self.kernel {
+            Kernel::RunningKernel(kernel) => {
+                kernel
+                    .request_tx()
+                    .try_send(InterruptRequest {}.into())
+                    .ok();
+                Vec::new()
+            }
+            _ => self.abandon_executions(),
+        }
+    }
+
+    /// Returns the cell whose execution the message belongs to.
+    pub fn cell_for_message(&self, message: &JupyterMessage) -> Option<&CellId> {
+        let parent_header = message.parent_header.as_ref()?;
+        self.executions.get(&parent_header.msg_id)
+    }
+
+    /// Marks the execution that the message belongs to as finished, returning its cell.
+    pub fn finish_execution(&mut self, message: &JupyterMessage) -> Option<CellId> {
+        let parent_header = message.parent_header.as_ref()?;
+        self.executions.remove(&parent_header.msg_id)
+    }
+
+    fn send(&mut self, message: JupyterMessage) {
+        match &mut self.kernel {
+            Kernel::RunningKernel(kernel) => {
+                kernel.request_tx().try_send(message).ok();
+            }
+            _ => self.queued_messages.push(message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::RunningKernel;
+    use futures::{channel::mpsc, FutureExt as _};
+    use gpui::{App, Task, Window};
+    use runtimelib::{
+        ExecutionState, JupyterMessageContent, KernelInfoReply, Stdio, StreamContent,
+    };
+    use std::path::PathBuf;
+    use uuid::Uuid;
+
+    #[derive(Debug)]
+    struct FakeKernel {
+        request_tx: mpsc::Sender<JupyterMessage>,
+        working_directory: PathBuf,
+        execution_state: ExecutionState,
+        kernel_info: Option<KernelInfoReply>,
+    }
+
+    impl FakeKernel {
+        fn new() -> (Self, mpsc::Receiver<JupyterMessage>) {
+            let (request_tx, request_rx) = mpsc::channel(100);
+            let kernel = Self {
+                request_tx,
+                working_directory: PathBuf::from("/"),
+                execution_state: ExecutionState::Idle,
+                kernel_info: None,
+            };
+            (kernel, request_rx)
+        }
+    }
+
+    impl RunningKernel for FakeKernel {
+        fn request_tx(&self) -> mpsc::Sender<JupyterMessage> {
+            self.request_tx.clone()
+        }
+
+        fn working_directory(&self) -> &PathBuf {
+            &self.working_directory
+        }
+
+        fn execution_state(&self) -> &ExecutionState {
+            &self.execution_state
+        }
+
+        fn set_execution_state(&mut self, state: ExecutionState) {
+            self.execution_state = state;
+        }
+
+        fn kernel_info(&self) -> Option<&KernelInfoReply> {
+            self.kernel_info.as_ref()
+        }
+
+        fn set_kernel_info(&mut self, info: KernelInfoReply) {
+            self.kernel_info = Some(info);
+        }
+
+        fn force_shutdown(&mut self, _: &mut Window, _: &mut App) -> Task<anyhow::Result<()>> {
+            Task::ready(Ok(()))
+        }
+    }
+
+    fn new_cell_id() -> CellId {
+        CellId::from(Uuid::new_v4())
+    }
+
+    fn sent_messages(request_rx: &mut mpsc::Receiver<JupyterMessage>) -> Vec<JupyterMessage> {
+        std::iter::from_fn(|| request_rx.try_next().ok().flatten()).collect()
+    }
+
+    fn executed_code(message: &JupyterMessage) -> Option<&str> {
+        match &message.content {
+            JupyterMessageContent::ExecuteRequest(request) => Some(&request.code),
+            _ => None,
+        }
+    }
+
+    fn reply_to(request: &JupyterMessage, text: &str) -> JupyterMessage {
+        let mut message: JupyterMessage = StreamContent {
+            name: Stdio::Stdout,
+            text: text.to_string(),
+        }
+        .into();
+        message.parent_header = Some(request.header.clone());
+        message
+    }
+
+    #[test]
+    fn test_executions_are_queued_until_the_kernel_is_running() {
+        let mut notebook_kernel = NotebookKernel::default();
+        assert_eq!(notebook_kernel.execute(new_cell_id(), "1".into()), None);
+
+        notebook_kernel.set_kernel(Kernel::StartingKernel(Task::ready(()).shared()));
+        notebook_kernel.execute(new_cell_id(), "x = 1".into());
+        notebook_kernel.execute(new_cell_id(), "print(x)".into());
+
+        let (kernel, mut request_rx) = FakeKernel::new();
+        let abandoned = notebook_kernel.set_kernel(Kernel::RunningKernel(Box::new(kernel)));
+        assert!(abandoned.is_empty());
+
+        let sent = sent_messages(&mut request_rx);
+        assert_eq!(
+            sent.iter().filter_map(executed_code).collect::<Vec<_>>(),
+            vec!["x = 1", "print(x)"]
+        );
+
+        notebook_kernel.execute(new_cell_id(), "x + 1".into());
+        let sent = sent_messages(&mut request_rx);
+        assert_eq!(
+            sent.iter().filter_map(executed_code).collect::<Vec<_>>(),
+            vec!["x + 1"]
+        );
+    }
+
+    #[test]
+    fn test_messages_are_routed_to_the_executed_cell() {
+        let mut notebook_kernel = NotebookKernel::default();
+        let (kernel, mut request_rx) = FakeKernel::new();
+        notebook_kernel.set_kernel(Kernel::RunningKernel(Box::new(kernel)));
+
+        let first_cell = new_cell_id();
+        let second_cell = new_cell_id();
+        notebook_kernel.execute(first_cell.clone(), "1".into());
+        notebook_kernel.execute(second_cell.clone(), "2".into());
+
+        let sent = sent_messages(&mut request_rx);
+        let first_output = reply_to(&sent[0], "1\n");
+        let second_output = reply_to(&sent[1], "2\n");
+        assert_eq!(
+            notebook_kernel.cell_for_message(&first_output),
+            Some(&first_cell)
+        );
+        assert_eq!(
+            notebook_kernel.cell_for_message(&second_output),
+            Some(&second_cell)
+        );
+
+        assert_eq!(
+            notebook_kernel.finish_execution(&first_output),
+            Some(first_cell)
+        );
+        assert_eq!(notebook_kernel.cell_for_message(&first_output), None);
+        assert!(notebook_kernel.has_pending_executions());
+
+        // Re-running a cell replaces its pending execution.
+        notebook_kernel.execute(second_cell.clone(), "3".into());
+        assert_eq!(notebook_kernel.cell_for_message(&second_output), None);
+
+        let rerun = sent_messages(&mut request_rx);
+        let rerun_output = reply_to(&rerun[0], "3\n");
+        assert_eq!(
+            notebook_kernel.cell_for_message(&rerun_output),
+            Some(&second_cell)
+        );
+    }
+
+    #[test]
+    fn test_interrupting_and_failing_kernels_abandon_executions() {
+        let mut notebook_kernel = NotebookKernel::default();
+        let (kernel, mut request_rx) = FakeKernel::new();
+        notebook_kernel.set_kernel(Kernel::RunningKernel(Box::new(kernel)));
+
+        let cell = new_cell_id();
+        notebook_kernel.execute(cell.clone(), "while True: pass".into());
+        assert!(notebook_kernel.interrupt().is_empty());
+
+        let sent = sent_messages(&mut request_rx);
+        assert!(matches!(
+            sent.last().map(|message| &message.content),
+            Some(JupyterMessageContent::InterruptRequest(_))
+        ));
+        assert!(notebook_kernel.has_pending_executions());
+
+        let abandoned = notebook_kernel.set_kernel(Kernel::ErroredLaunch("crashed".into()));
+        assert_eq!(abandoned, vec![cell]);
+        assert!(!notebook_kernel.has_pending_executions());
+        assert_eq!(notebook_kernel.execute(new_cell_id(), "1".into()), None);
+    }
+}
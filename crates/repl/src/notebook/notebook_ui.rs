@@ -1,40 +1,63 @@
 #![allow(unused, dead_code)]
+use std::env::temp_dir;
 use std::future::Future;
+use std::time::Duration;
 use std::{path::PathBuf, sync::Arc};
 
 use anyhow::{Context as _, Result};
 use client::proto::ViewId;
 use collections::HashMap;
+use editor::EditorEvent;
 use feature_flags::{FeatureFlagAppExt as _, NotebookFeatureFlag};
 use futures::future::Shared;
-use futures::FutureExt;
+use futures::{FutureExt, StreamExt as _};
 use gpui::{
     actions, list, prelude::*, AnyElement, App, Entity, EventEmitter, FocusHandle, Focusable,
-    ListScrollEvent, ListState, Point, Task,
+    ListScrollEvent, ListState, Point, Subscription, Task,
 };
 use language::{Language, LanguageRegistry};
-use project::{Project, ProjectEntryId, ProjectPath};
+use project::{Fs, MTime, Project, ProjectEntryId, ProjectPath};
+use runtimelib::{ExecutionState, JupyterMessage, JupyterMessageContent, ShutdownRequest};
+use serde::Serialize as _;
 use ui::{prelude::*, Tooltip};
+use util::ResultExt as _;
 use workspace::item::{ItemEvent, TabContentParams};
 use workspace::searchable::SearchableItemHandle;
 use workspace::{Item, ItemHandle, ProjectItem, ToolbarItemLocation};
 use workspace::{ToolbarItemEvent, ToolbarItemView};
 
-use super::{Cell, CellPosition, RenderableCell};
+use super::{
+    nbformat_cell, new_nbformat_cell, Cell, CellPosition, CodeCell, CodeCellEvent, NotebookKernel,
+    RenderableCell,
+};
+use crate::kernels::{Kernel, KernelSession, NativeRunningKernel, RemoteRunningKernel};
+use crate::outputs::ExecutionStatus;
+use crate::repl_store::ReplStore;
+use crate::{KernelSpecification, KernelStatus};
 
-use nbformat::v4::CellId;
 use nbformat::v4::Metadata as NotebookMetadata;
+use nbformat::v4::{CellId, CellType};
 
 actions!(
     notebook,
     [
         OpenNotebook,
+        RunCell,
         RunAll,
+        RunCellsAbove,
+        InterruptKernel,
+        RestartKernel,
         ClearOutputs,
         MoveCellUp,
         MoveCellDown,
         AddMarkdownBlock,
         AddCodeBlock,
+        DeleteCell,
+        SplitCell,
+        MergeCellBelow,
+        ConvertToCodeCell,
+        ConvertToMarkdownCell,
+        ConvertToRawCell,
     ]
 );
 
@@ -46,6 +69,8 @@ pub(crate) const GUTTER_WIDTH: f32 = 19.0;
 pub(crate) const CODE_BLOCK_INSET: f32 = MEDIUM_SPACING_SIZE;
 pub(crate) const CONTROL_SIZE: f32 = 20.0;
 
+const FILE_WATCH_LATENCY: Duration = Duration::from_millis(100);
+
 pub fn init(cx: &mut App) {
     if cx.has_flag::<NotebookFeatureFlag>() || std::env::var("LOCAL_NOTEBOOK_DEV").is_ok() {
         workspace::register_project_item::<NotebookEditor>(cx);
@@ -70,6 +95,7 @@ pub struct NotebookEditor {
 
     focus_handle: FocusHandle,
     notebook_item: Entity<NotebookItem>,
+    notebook_language: Shared<Task<Option<Arc<Language>>>>,
 
     remote_id: Option<ViewId>,
     cell_list: ListState,
@@ -77,6 +103,16 @@ pub struct NotebookEditor {
     selected_cell_index: usize,
     cell_order: Vec<CellId>,
     cell_map: HashMap<CellId, Cell>,
+    dirty: bool,
+    kernel: NotebookKernel,
+    cell_subscriptions: HashMap<CellId, Vec<Subscription>>,
+    _notebook_item_subscription: Subscription,
+}
+
+pub enum NotebookEditorEvent {
+    Edited,
+    Saved,
+    TitleChanged,
 }
 
 impl NotebookEditor {
@@ -89,36 +125,14 @@ impl NotebookEditor {
         let focus_handle = cx.focus_handle();
 
         let languages = project.read(cx).languages().clone();
-        let language_name = notebook_item.read(cx).language_name();
 
         let notebook_language = notebook_item.read(cx).notebook_language();
         let notebook_language = cx.spawn_in(window, |_, _| notebook_language).shared();
 
-        let mut cell_order = vec![]; // Vec<CellId>
-        let mut cell_map = HashMap::default(); // HashMap<CellId, Cell>
-
-        for (index, cell) in notebook_item
-            .read(cx)
-            .notebook
-            .clone()
-            .cells
-            .iter()
-            .enumerate()
-        {
-            let cell_id = cell.id();
-            cell_order.push(cell_id.clone());
-            cell_map.insert(
-                cell_id.clone(),
-                Cell::load(cell, &languages, notebook_language.clone(), window, cx),
-            );
-        }
-
         let notebook_handle = cx.entity().downgrade();
-        let cell_count = cell_order.len();
 
-        let this = cx.entity();
         let cell_list = ListState::new(
-            cell_count,
+            0,
             gpui::ListAlignment::Top,
             px(1000.),
             move |ix, window, cx| {
@@ -141,17 +155,190 @@ impl NotebookEditor {
             },
         );
 
-        Self {
+        let notebook_item_subscription =
+            cx.subscribe_in(&notebook_item, window, Self::on_notebook_item_event);
+
+        let mut this = Self {
             project,
             languages: languages.clone(),
             focus_handle,
             notebook_item,
+            notebook_language,
             remote_id: None,
             cell_list,
             selected_cell_index: 0,
-            cell_order: cell_order.clone(),
-            cell_map: cell_map.clone(),
+            cell_order: Vec::new(),
+            cell_map: HashMap::default(),
+            dirty: false,
+            kernel: NotebookKernel::default(),
+            cell_subscriptions: HashMap::default(),
+            _notebook_item_subscription: notebook_item_subscription,
+        };
+        this.load_cells(window, cx);
+        this
+    }
+
+    /// Replaces the cells in the editor with the cells of the notebook on disk.
+    fn load_cells(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let cells = self.notebook_item.read(cx).notebook.cells.clone();
+
+        self.cell_order.clear();
+        self.cell_map.clear();
+        self.cell_subscriptions.clear();
+        for cell in &cells {
+            let cell = Cell::load(
+                cell,
+                &self.languages,
+                self.notebook_language.clone(),
+                window,
+                cx,
+            );
+            let cell_id = self.register_cell(cell, window, cx);
+            self.cell_order.push(cell_id);
+        }
+
+        self.cell_list.reset(self.cell_order.len());
+        self.selected_cell_index = self
+            .selected_cell_index
+            .min(self.cell_order.len().saturating_sub(1));
+        self.dirty = false;
+        cx.notify();
+    }
+
+    fn register_cell(&mut self, cell: Cell, window: &mut Window, cx: &mut Context<Self>) -> CellId {
+        let cell_id = cell.id(cx);
+        let mut subscriptions =
+            vec![
+                cx.subscribe(&cell.editor(cx), |this, _, event: &EditorEvent, cx| {
+                    if let EditorEvent::BufferEdited = event {
+                        this.mark_dirty(cx);
+                    }
+                }),
+            ];
+        if let Cell::Code(code_cell) = &cell {
+            subscriptions.push(cx.subscribe_in(
+                code_cell,
+                window,
+                |this, code_cell, event: &CodeCellEvent, window, cx| match event {
+                    CodeCellEvent::Run => this.run_code_cell(code_cell.clone(), window, cx),
+                },
+            ));
         }
+        self.cell_subscriptions
+            .insert(cell_id.clone(), subscriptions);
+        self.cell_map.insert(cell_id.clone(), cell);
+        cell_id
+    }
+
+    fn on_notebook_item_event(
+        &mut self,
+        notebook_item: &Entity<NotebookItem>,
+        event: &NotebookItemEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match event {
+            NotebookItemEvent::ChangedOnDisk => {
+                // Unmodified notebooks follow the file on disk, while modified
+                // ones are left alone and reported as conflicted instead.
+                if !self.dirty && !notebook_item.read(cx).has_deleted_file() {
+                    notebook_item
+                        .update(cx, |notebook_item, cx| notebook_item.reload(cx))
+                        .detach_and_log_err(cx);
+                }
+                cx.emit(NotebookEditorEvent::TitleChanged);
+            }
+            NotebookItemEvent::Reloaded => {
+                self.load_cells(window, cx);
+                cx.emit(NotebookEditorEvent::TitleChanged);
+            }
+            NotebookItemEvent::Saved => {
+                cx.emit(NotebookEditorEvent::TitleChanged);
+            }
+        }
+    }
+
+    fn mark_dirty(&mut self, cx: &mut Context<Self>) {
+        self.dirty = true;
+        cx.emit(NotebookEditorEvent::Edited);
+        cx.notify();
+    }
+
+    /// Returns the notebook with the current contents of every cell.
+    pub fn to_notebook(&self, cx: &App) -> nbformat::v4::Notebook {
+        let mut notebook = self.notebook_item.read(cx).notebook.clone();
+        notebook.cells = self
+            .cell_order
+            .iter()
+            .filter_map(|cell_id| self.cell_map.get(cell_id))
+            .map(|cell| cell.to_nbformat_cell(cx))
+            .collect();
+        notebook
+    }
+
+    fn selected_cell(&self) -> Option<&Cell> {
+        self.cell_order
+            .get(self.selected_cell_index)
+            .and_then(|cell_id| self.cell_map.get(cell_id))
+    }
+
+    fn insert_cell(
+        &mut self,
+        index: usize,
+        cell: nbformat::v4::Cell,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Cell {
+        let cell = Cell::load(
+            &cell,
+            &self.languages,
+            self.notebook_language.clone(),
+            window,
+            cx,
+        );
+        let cell_id = self.register_cell(cell.clone(), window, cx);
+        self.cell_order.insert(index, cell_id);
+        self.cell_list.splice(index..index, 1);
+        self.set_selected_index(index, true, window, cx);
+        self.mark_dirty(cx);
+        cell
+    }
+
+    fn remove_cell(&mut self, index: usize, cx: &mut Context<Self>) -> Option<Cell> {
+        if index >= self.cell_order.len() {
+            return None;
+        }
+
+        let cell_id = self.cell_order.remove(index);
+        self.cell_list.splice(index..index + 1, 0);
+        self.cell_subscriptions.remove(&cell_id);
+        self.mark_dirty(cx);
+        self.cell_map.remove(&cell_id)
+    }
+
+    fn add_cell(&mut self, cell_type: CellType, window: &mut Window, cx: &mut Context<Self>) {
+        let index = if self.cell_order.is_empty() {
+            0
+        } else {
+            self.selected_cell_index + 1
+        };
+
+        match self.insert_cell(index, new_nbformat_cell(cell_type, ""), window, cx) {
+            Cell::Markdown(cell) => cell.update(cx, |cell, cx| cell.start_editing(window, cx)),
+            cell => window.focus(&cell.editor(cx).focus_handle(cx)),
+        }
+    }
+
+    fn move_cell(&mut self, from: usize, to: usize, window: &mut Window, cx: &mut Context<Self>) {
+        if from >= self.cell_order.len() || to >= self.cell_order.len() || from == to {
+            return;
+        }
+
+        self.cell_order.swap(from, to);
+        let start = from.min(to);
+        self.cell_list.splice(start..start + 2, 2);
+        self.set_selected_index(to, true, window, cx);
+        self.mark_dirty(cx);
     }
 
     fn has_outputs(&self, window: &mut Window, cx: &mut Context<Self>) -> bool {
@@ -165,17 +352,229 @@ impl NotebookEditor {
     }
 
     fn clear_outputs(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.has_outputs(window, cx) {
+            self.mark_dirty(cx);
+        }
+
         for cell in self.cell_map.values() {
             if let Cell::Code(code_cell) = cell {
-                code_cell.update(cx, |cell, _cx| {
+                code_cell.update(cx, |cell, cx| {
                     cell.clear_outputs();
+                    cx.notify();
                 });
             }
         }
     }
 
+    fn code_cells(&self, range: std::ops::Range<usize>) -> Vec<Entity<CodeCell>> {
+        self.cell_order[range]
+            .iter()
+            .filter_map(|cell_id| match self.cell_map.get(cell_id) {
+                Some(Cell::Code(code_cell)) => Some(code_cell.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
     fn run_cells(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        println!("Cells would all run here, if that was implemented!");
+        for code_cell in self.code_cells(0..self.cell_order.len()) {
+            self.run_code_cell(code_cell, window, cx);
+        }
+    }
+
+    fn run_selected_cell(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(Cell::Code(code_cell)) = self.selected_cell().cloned() {
+            self.run_code_cell(code_cell, window, cx);
+        }
+    }
+
+    fn run_cells_above(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let end = self.selected_cell_index.min(self.cell_order.len());
+        for code_cell in self.code_cells(0..end) {
+            self.run_code_cell(code_cell, window, cx);
+        }
+    }
+
+    /// Sends the source of the cell to the notebook's kernel, starting the kernel if needed.
+    fn run_code_cell(
+        &mut self,
+        code_cell: Entity<CodeCell>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let code = code_cell.read(cx).current_source(cx);
+        if code.trim().is_empty() {
+            return;
+        }
+
+        if !self.kernel.is_active() {
+            self.start_kernel(window, cx);
+        }
+
+        let cell_id = code_cell.read(cx).id().clone();
+        code_cell.update(cx, |cell, cx| cell.start_execution(cx));
+        if self.kernel.execute(cell_id, code).is_none() {
+            let status = match &self.kernel.kernel {
+                Kernel::ErroredLaunch(error) => ExecutionStatus::KernelErrored(error.clone()),
+                Kernel::ShuttingDown => ExecutionStatus::ShuttingDown,
+                _ => ExecutionStatus::Shutdown,
+            };
+            code_cell.update(cx, |cell, cx| cell.set_execution_status(status, cx));
+        }
+        self.mark_dirty(cx);
+    }
+
+    /// Returns the kernel to run the notebook with: the kernel named in the
+    /// notebook's metadata if it's available, or else one for its language.
+    fn kernel_specification(&self, cx: &App) -> Option<KernelSpecification> {
+        let notebook_item = self.notebook_item.read(cx);
+        let worktree_id = notebook_item.project_path.worktree_id;
+        let kernel_name = notebook_item
+            .notebook
+            .metadata
+            .kernelspec
+            .as_ref()
+            .map(|kernelspec| kernelspec.name.clone());
+        let language_name = notebook_item.language_name()?.to_lowercase();
+
+        let store = ReplStore::global(cx);
+        let store = store.read(cx);
+        let kernel_specifications = store
+            .kernel_specifications_for_worktree(worktree_id)
+            .collect::<Vec<_>>();
+
+        kernel_name
+            .and_then(|kernel_name| {
+                kernel_specifications
+                    .iter()
+                    .find(|spec| spec.name().as_ref() == kernel_name)
+            })
+            .or_else(|| {
+                kernel_specifications
+                    .iter()
+                    .find(|spec| spec.language().to_lowercase() == language_name)
+            })
+            .map(|spec| (*spec).clone())
+    }
+
+    fn start_kernel(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(kernel_specification) = self.kernel_specification(cx) else {
+            let language = self
+                .notebook_item
+                .read(cx)
+                .language_name()
+                .unwrap_or_else(|| "this notebook".to_string());
+            self.kernel_errored(format!("No kernel found for {language}"), cx);
+            return;
+        };
+
+        let working_directory = self
+            .notebook_item
+            .read(cx)
+            .path
+            .parent()
+            .map(|path| path.to_path_buf())
+            .unwrap_or_else(temp_dir);
+        let fs = self.project.read(cx).fs().clone();
+        let notebook_editor = cx.entity();
+
+        let kernel = match kernel_specification.clone() {
+            KernelSpecification::Jupyter(kernel_specification)
+            | KernelSpecification::PythonEnv(kernel_specification) => NativeRunningKernel::new(
+                kernel_specification,
+                cx.entity_id(),
+                working_directory,
+                fs,
+                notebook_editor,
+                window,
+                cx,
+            ),
+            KernelSpecification::Remote(remote_kernel_specification) => RemoteRunningKernel::new(
+                remote_kernel_specification,
+                working_directory,
+                notebook_editor,
+                window,
+                cx,
+            ),
+        };
+
+        let pending_kernel = cx
+            .spawn(|this, mut cx| async move {
+                let kernel = kernel.await;
+                this.update(&mut cx, |this, cx| match kernel {
+                    Ok(kernel) => this.set_kernel(Kernel::RunningKernel(kernel), cx),
+                    Err(error) => this.kernel_errored(error.to_string(), cx),
+                })
+                .ok();
+            })
+            .shared();
+
+        self.kernel.kernel_specification = Some(kernel_specification);
+        self.set_kernel(Kernel::StartingKernel(pending_kernel), cx);
+    }
+
+    fn set_kernel(&mut self, kernel: Kernel, cx: &mut Context<Self>) {
+        let status = match &kernel {
+            Kernel::ErroredLaunch(error) => ExecutionStatus::KernelErrored(error.clone()),
+            _ => ExecutionStatus::Unknown,
+        };
+        let abandoned_cells = self.kernel.set_kernel(kernel);
+        self.set_cells_execution_status(abandoned_cells, status, cx);
+        cx.notify();
+    }
+
+    fn set_cells_execution_status(
+        &mut self,
+        cell_ids: Vec<CellId>,
+        status: ExecutionStatus,
+        cx: &mut Context<Self>,
+    ) {
+        for cell_id in cell_ids {
+            if let Some(Cell::Code(code_cell)) = self.cell_map.get(&cell_id) {
+                code_cell.update(cx, |cell, cx| cell.set_execution_status(status.clone(), cx));
+            }
+        }
+    }
+
+    fn interrupt_kernel(&mut self, cx: &mut Context<Self>) {
+        let dropped_cells = self.kernel.interrupt();
+        self.set_cells_execution_status(dropped_cells, ExecutionStatus::Unknown, cx);
+        cx.notify();
+    }
+
+    fn restart_kernel(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let abandoned_cells = self.kernel.abandon_executions();
+        self.set_cells_execution_status(abandoned_cells, ExecutionStatus::Restarting, cx);
+
+        let kernel = std::mem::replace(&mut self.kernel.kernel, Kernel::Restarting);
+        match kernel {
+            Kernel::Restarting => {
+                // Do nothing if already restarting
+            }
+            Kernel::RunningKernel(mut kernel) => {
+                let mut request_tx = kernel.request_tx();
+                let forced = kernel.force_shutdown(window, cx);
+
+                cx.spawn_in(window, |this, mut cx| async move {
+                    let message: JupyterMessage = ShutdownRequest { restart: true }.into();
+                    request_tx.try_send(message).ok();
+
+                    // Wait for kernel to shutdown
+                    cx.background_executor().timer(Duration::from_secs(1)).await;
+
+                    // Force kill the kernel if it hasn't shut down
+                    forced.await.log_err();
+
+                    this.update_in(&mut cx, |this, window, cx| {
+                        this.start_kernel(window, cx);
+                    })
+                    .ok();
+                })
+                .detach();
+            }
+            _ => self.start_kernel(window, cx),
+        }
+        cx.notify();
     }
 
     fn open_notebook(&mut self, _: &OpenNotebook, _window: &mut Window, _cx: &mut Context<Self>) {
@@ -183,19 +582,105 @@ impl NotebookEditor {
     }
 
     fn move_cell_up(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        println!("Move cell up triggered");
+        let index = self.selected_cell_index;
+        if index > 0 {
+            self.move_cell(index, index - 1, window, cx);
+        }
     }
 
     fn move_cell_down(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        println!("Move cell down triggered");
+        let index = self.selected_cell_index;
+        self.move_cell(index, index + 1, window, cx);
     }
 
     fn add_markdown_block(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        println!("Add markdown block triggered");
+        self.add_cell(CellType::Markdown, window, cx);
     }
 
     fn add_code_block(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        println!("Add code block triggered");
+        self.add_cell(CellType::Code, window, cx);
+    }
+
+    fn delete_cell(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.remove_cell(self.selected_cell_index, cx).is_some() {
+            let index = self
+                .selected_cell_index
+                .min(self.cell_order.len().saturating_sub(1));
+            self.set_selected_index(index, true, window, cx);
+        }
+    }
+
+    /// Splits the selected cell in two at the cursor position of its editor.
+    fn split_cell(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(cell) = self.selected_cell().cloned() else {
+            return;
+        };
+
+        let editor = cell.editor(cx);
+        let (text, offset) = editor.update(cx, |editor, cx| {
+            let offset = editor.selections.newest::<usize>(cx).head();
+            (editor.text(cx), offset)
+        });
+        let (before, after) = text.split_at(offset.min(text.len()));
+        let before = before.trim_end_matches('\n').to_string();
+        let after = after.trim_start_matches('\n').to_string();
+
+        editor.update(cx, |editor, cx| editor.set_text(before, window, cx));
+        if let Cell::Markdown(cell) = &cell {
+            cell.update(cx, |cell, cx| cell.refresh_markdown(window, cx));
+        }
+
+        let new_cell = new_nbformat_cell(cell.cell_type(), &after);
+        self.insert_cell(self.selected_cell_index + 1, new_cell, window, cx);
+    }
+
+    /// Appends the source of the cell below the selected cell to it, removing the cell below.
+    fn merge_cell_below(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let index = self.selected_cell_index;
+        let Some(cell) = self.selected_cell().cloned() else {
+            return;
+        };
+        let Some(next_cell) = self.remove_cell(index + 1, cx) else {
+            return;
+        };
+
+        let text = format!(
+            "{}\n{}",
+            cell.current_source(cx),
+            next_cell.current_source(cx)
+        );
+        cell.editor(cx)
+            .update(cx, |editor, cx| editor.set_text(text, window, cx));
+        if let Cell::Markdown(cell) = &cell {
+            cell.update(cx, |cell, cx| cell.refresh_markdown(window, cx));
+        }
+    }
+
+    fn convert_cell(&mut self, cell_type: CellType, window: &mut Window, cx: &mut Context<Self>) {
+        let index = self.selected_cell_index;
+        let Some(cell) = self.selected_cell() else {
+            return;
+        };
+        if std::mem::discriminant(&cell.cell_type()) == std::mem::discriminant(&cell_type) {
+            return;
+        }
+
+        let converted = nbformat_cell(
+            cell_type,
+            cell.id(cx),
+            cell.metadata(cx),
+            &cell.current_source(cx),
+        );
+        let cell = Cell::load(
+            &converted,
+            &self.languages,
+            self.notebook_language.clone(),
+            window,
+            cx,
+        );
+        self.register_cell(cell, window, cx);
+        self.cell_list.splice(index..index + 1, 1);
+        self.mark_dirty(cx);
     }
 
     fn cell_count(&self) -> usize {
@@ -446,11 +931,79 @@ impl NotebookEditor {
                     ))
                     .child(
                         Self::button_group(window, cx)
-                            .child(IconButton::new("repl", IconName::ReplNeutral)),
+                            .child(
+                                Self::render_notebook_control(
+                                    "interrupt-kernel",
+                                    IconName::Stop,
+                                    window,
+                                    cx,
+                                )
+                                .disabled(!self.kernel.is_running())
+                                .tooltip(move |window, cx| {
+                                    Tooltip::for_action(
+                                        "Interrupt kernel",
+                                        &InterruptKernel,
+                                        window,
+                                        cx,
+                                    )
+                                })
+                                .on_click(|_, window, cx| {
+                                    window.dispatch_action(Box::new(InterruptKernel), cx);
+                                }),
+                            )
+                            .child(
+                                Self::render_notebook_control(
+                                    "restart-kernel",
+                                    IconName::RotateCw,
+                                    window,
+                                    cx,
+                                )
+                                .tooltip(move |window, cx| {
+                                    Tooltip::for_action(
+                                        "Restart kernel",
+                                        &RestartKernel,
+                                        window,
+                                        cx,
+                                    )
+                                })
+                                .on_click(|_, window, cx| {
+                                    window.dispatch_action(Box::new(RestartKernel), cx);
+                                }),
+                            )
+                            .child(
+                                IconButton::new("repl", IconName::ReplNeutral)
+                                    .icon_color(self.kernel_status_color())
+                                    .tooltip(Tooltip::text(self.kernel_status_text())),
+                            ),
                     ),
             )
     }
 
+    fn kernel_status_text(&self) -> String {
+        let status = KernelStatus::from(&self.kernel.kernel).to_string();
+        match (&self.kernel.kernel_specification, &self.kernel.kernel) {
+            (_, Kernel::ErroredLaunch(error)) => format!("Kernel error: {error}"),
+            (Some(kernel_specification), _) => {
+                format!("{} ({status})", kernel_specification.name())
+            }
+            (None, _) => "No kernel started".to_string(),
+        }
+    }
+
+    fn kernel_status_color(&self) -> Color {
+        match &self.kernel.kernel {
+            Kernel::RunningKernel(kernel) => match kernel.execution_state() {
+                ExecutionState::Idle => Color::Success,
+                ExecutionState::Busy => Color::Modified,
+            },
+            Kernel::StartingKernel(_) | Kernel::Restarting | Kernel::ShuttingDown => {
+                Color::Modified
+            }
+            Kernel::ErroredLaunch(_) => Color::Error,
+            Kernel::Shutdown => Color::Muted,
+        }
+    }
+
     fn cell_position(&self, index: usize) -> CellPosition {
         match index {
             0 => CellPosition::First,
@@ -508,6 +1061,14 @@ impl Render for NotebookEditor {
                 cx.listener(|this, &ClearOutputs, window, cx| this.clear_outputs(window, cx)),
             )
             .on_action(cx.listener(|this, &RunAll, window, cx| this.run_cells(window, cx)))
+            .on_action(cx.listener(|this, &RunCell, window, cx| this.run_selected_cell(window, cx)))
+            .on_action(
+                cx.listener(|this, &RunCellsAbove, window, cx| this.run_cells_above(window, cx)),
+            )
+            .on_action(cx.listener(|this, &InterruptKernel, _, cx| this.interrupt_kernel(cx)))
+            .on_action(
+                cx.listener(|this, &RestartKernel, window, cx| this.restart_kernel(window, cx)),
+            )
             .on_action(cx.listener(|this, &MoveCellUp, window, cx| this.move_cell_up(window, cx)))
             .on_action(
                 cx.listener(|this, &MoveCellDown, window, cx| this.move_cell_down(window, cx)),
@@ -518,6 +1079,20 @@ impl Render for NotebookEditor {
             .on_action(
                 cx.listener(|this, &AddCodeBlock, window, cx| this.add_code_block(window, cx)),
             )
+            .on_action(cx.listener(|this, &DeleteCell, window, cx| this.delete_cell(window, cx)))
+            .on_action(cx.listener(|this, &SplitCell, window, cx| this.split_cell(window, cx)))
+            .on_action(
+                cx.listener(|this, &MergeCellBelow, window, cx| this.merge_cell_below(window, cx)),
+            )
+            .on_action(cx.listener(|this, &ConvertToCodeCell, window, cx| {
+                this.convert_cell(CellType::Code, window, cx)
+            }))
+            .on_action(cx.listener(|this, &ConvertToMarkdownCell, window, cx| {
+                this.convert_cell(CellType::Markdown, window, cx)
+            }))
+            .on_action(cx.listener(|this, &ConvertToRawCell, window, cx| {
+                this.convert_cell(CellType::Raw, window, cx)
+            }))
             .on_action(cx.listener(Self::select_next))
             .on_action(cx.listener(Self::select_previous))
             .on_action(cx.listener(Self::select_first))
@@ -552,12 +1127,27 @@ pub struct NotebookItem {
     path: PathBuf,
     project_path: ProjectPath,
     languages: Arc<LanguageRegistry>,
-    // Raw notebook data
+    fs: Arc<dyn Fs>,
+    // Raw notebook data, as last loaded from or saved to disk
     notebook: nbformat::v4::Notebook,
     // Store our version of the notebook in memory (cell_order, cell_map)
-    id: ProjectEntryId,
+    id: Option<ProjectEntryId>,
+    mtime: Option<MTime>,
+    changed_on_disk: bool,
+    deleted: bool,
+    saving: bool,
+    _watch_task: Task<()>,
+}
+
+pub enum NotebookItemEvent {
+    /// The notebook file was modified or deleted by another program.
+    ChangedOnDisk,
+    Reloaded,
+    Saved,
 }
 
+impl EventEmitter<NotebookItemEvent> for NotebookItem {}
+
 impl project::ProjectItem for NotebookItem {
     fn try_open(
         project: &Entity<Project>,
@@ -575,36 +1165,31 @@ impl project::ProjectItem for NotebookItem {
                     .read_with(&cx, |project, cx| project.absolute_path(&path, cx))?
                     .ok_or_else(|| anyhow::anyhow!("Failed to find the absolute path"))?;
 
-                // todo: watch for changes to the file
+                let mtime = fs.metadata(&abs_path).await?.map(|metadata| metadata.mtime);
                 let file_content = fs.load(&abs_path.as_path()).await?;
-                let notebook = nbformat::parse_notebook(&file_content);
-
-                let notebook = match notebook {
-                    Ok(nbformat::Notebook::V4(notebook)) => notebook,
-                    // 4.1 - 4.4 are converted to 4.5
-                    Ok(nbformat::Notebook::Legacy(legacy_notebook)) => {
-                        // TODO: Decide if we want to mutate the notebook by including Cell IDs
-                        // and any other conversions
-                        let notebook = nbformat::upgrade_legacy_notebook(legacy_notebook)?;
-                        notebook
-                    }
-                    // Bad notebooks and notebooks v4.0 and below are not supported
-                    Err(e) => {
-                        anyhow::bail!("Failed to parse notebook: {:?}", e);
-                    }
-                };
+                let notebook = parse_notebook(&file_content)?;
 
                 let id = project
                     .update(&mut cx, |project, cx| project.entry_for_path(&path, cx))?
                     .context("Entry not found")?
                     .id;
 
-                cx.new(|_| NotebookItem {
-                    path: abs_path,
-                    project_path: path,
-                    languages,
-                    notebook,
-                    id,
+                cx.new(|cx| {
+                    let mut notebook_item = NotebookItem {
+                        path: abs_path,
+                        project_path: path,
+                        languages,
+                        fs,
+                        notebook,
+                        id: Some(id),
+                        mtime,
+                        changed_on_disk: false,
+                        deleted: false,
+                        saving: false,
+                        _watch_task: Task::ready(()),
+                    };
+                    notebook_item.watch_for_changes(cx);
+                    notebook_item
                 })
             }))
         } else {
@@ -613,7 +1198,7 @@ impl project::ProjectItem for NotebookItem {
     }
 
     fn entry_id(&self, _: &App) -> Option<ProjectEntryId> {
-        Some(self.id)
+        self.id
     }
 
     fn project_path(&self, _: &App) -> Option<ProjectPath> {
@@ -652,9 +1237,251 @@ impl NotebookItem {
             }
         }
     }
+
+    /// Whether the file was modified by another program since it was last loaded or saved.
+    pub fn has_changed_on_disk(&self) -> bool {
+        self.changed_on_disk
+    }
+
+    pub fn has_deleted_file(&self) -> bool {
+        self.deleted
+    }
+
+    fn watch_for_changes(&mut self, cx: &mut Context<Self>) {
+        let fs = self.fs.clone();
+        let path = self.path.clone();
+        self._watch_task = cx.spawn(|this, mut cx| async move {
+            let (mut events, _watcher) = fs.watch(&path, FILE_WATCH_LATENCY).await;
+            while events.next().await.is_some() {
+                let mtime = fs
+                    .metadata(&path)
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(|metadata| metadata.mtime);
+                if this
+                    .update(&mut cx, |this, cx| this.file_changed(mtime, cx))
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+    }
+
+    fn file_changed(&mut self, mtime: Option<MTime>, cx: &mut Context<Self>) {
+        // Our own writes are accounted for once the save completes.
+        if self.saving || mtime == self.mtime {
+            return;
+        }
+
+        self.deleted = mtime.is_none();
+        self.changed_on_disk = true;
+        self.mtime = mtime;
+        cx.emit(NotebookItemEvent::ChangedOnDisk);
+    }
+
+    /// Replaces the notebook with the contents of the file on disk.
+    pub fn reload(&mut self, cx: &mut Context<Self>) -> Task<Result<()>> {
+        let fs = self.fs.clone();
+        let path = self.path.clone();
+        cx.spawn(|this, mut cx| async move {
+            let mtime = fs.metadata(&path).await?.map(|metadata| metadata.mtime);
+            let file_content = fs.load(&path).await?;
+            let notebook = parse_notebook(&file_content)?;
+
+            this.update(&mut cx, |this, cx| {
+                this.notebook = notebook;
+                this.mtime = mtime;
+                this.changed_on_disk = false;
+                this.deleted = false;
+                cx.emit(NotebookItemEvent::Reloaded);
+            })
+        })
+    }
+
+    pub fn save(
+        &mut self,
+        notebook: nbformat::v4::Notebook,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        self.write(notebook, self.path.clone(), cx)
+    }
+
+    pub fn save_as(
+        &mut self,
+        notebook: nbformat::v4::Notebook,
+        project: Entity<Project>,
+        project_path: ProjectPath,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        let Some(abs_path) = project.read(cx).absolute_path(&project_path, cx) else {
+            return Task::ready(Err(anyhow::anyhow!(
+                "Failed to find the absolute path for {:?}",
+                project_path.path
+            )));
+        };
+
+        let write = self.write(notebook, abs_path.clone(), cx);
+        cx.spawn(|this, mut cx| async move {
+            write.await?;
+            let id = project
+                .update(&mut cx, |project, cx| {
+                    project.entry_for_path(&project_path, cx)
+                })?
+                .map(|entry| entry.id);
+
+            this.update(&mut cx, |this, cx| {
+                this.path = abs_path;
+                this.project_path = project_path;
+                this.id = id;
+                this.watch_for_changes(cx);
+            })
+        })
+    }
+
+    fn write(
+        &mut self,
+        notebook: nbformat::v4::Notebook,
+        abs_path: PathBuf,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        let fs = self.fs.clone();
+        self.saving = true;
+        cx.spawn(|this, mut cx| async move {
+            let result = async {
+                let content = serialize_notebook(&notebook)?;
+                fs.atomic_write(abs_path.clone(), content).await?;
+                anyhow::Ok(fs.metadata(&abs_path).await?.map(|metadata| metadata.mtime))
+            }
+            .await;
+
+            this.update(&mut cx, |this, cx| {
+                this.saving = false;
+                let mtime = result?;
+                this.notebook = notebook;
+                this.mtime = mtime;
+                this.changed_on_disk = false;
+                this.deleted = false;
+                cx.emit(NotebookItemEvent::Saved);
+                Ok(())
+            })?
+        })
+    }
+}
+
+/// Parses a notebook, upgrading older v4 notebooks to the latest minor version.
+pub fn parse_notebook(content: &str) -> Result<nbformat::v4::Notebook> {
+    match nbformat::parse_notebook(content) {
+        Ok(nbformat::Notebook::V4(notebook)) => Ok(notebook),
+        // 4.1 - 4.4 are converted to 4.5
+        Ok(nbformat::Notebook::Legacy(legacy_notebook)) => {
+            // TODO: Decide if we want to mutate the notebook by including Cell IDs
+            // and any other conversions
+            let notebook = nbformat::upgrade_legacy_notebook(legacy_notebook)?;
+            Ok(notebook)
+        }
+        // Bad notebooks and notebooks v4.0 and below are not supported
+        Err(e) => {
+            anyhow::bail!("Failed to parse notebook: {:?}", e);
+        }
+    }
+}
+
+/// Serializes a notebook the same way Jupyter does: with sorted keys, a
+/// one-space indent and a trailing newline, so that saving a notebook
+/// produces minimal diffs.
+pub fn serialize_notebook(notebook: &nbformat::v4::Notebook) -> Result<String> {
+    let value = sort_keys(serde_json::to_value(notebook)?);
+
+    let mut bytes = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b" ");
+    let mut serializer = serde_json::Serializer::with_formatter(&mut bytes, formatter);
+    value.serialize(&mut serializer)?;
+    bytes.push(b'\n');
+
+    Ok(String::from_utf8(bytes)?)
 }
 
-impl EventEmitter<()> for NotebookEditor {}
+fn sort_keys(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(object) => {
+            let mut entries = object.into_iter().collect::<Vec<_>>();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            serde_json::Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_keys(value)))
+                    .collect(),
+            )
+        }
+        serde_json::Value::Array(values) => {
+            serde_json::Value::Array(values.into_iter().map(sort_keys).collect())
+        }
+        value => value,
+    }
+}
+
+impl EventEmitter<NotebookEditorEvent> for NotebookEditor {}
+
+impl KernelSession for NotebookEditor {
+    fn route(&mut self, message: &JupyterMessage, window: &mut Window, cx: &mut Context<Self>) {
+        match &message.content {
+            JupyterMessageContent::Status(status) => {
+                self.kernel
+                    .kernel
+                    .set_execution_state(&status.execution_state);
+            }
+            JupyterMessageContent::KernelInfoReply(reply) => {
+                self.kernel.kernel.set_kernel_info(reply);
+            }
+            JupyterMessageContent::UpdateDisplayData(update) => {
+                let Some(display_id) = update.transient.display_id.as_ref() else {
+                    return;
+                };
+
+                let mut updated = false;
+                for cell in self.cell_map.values() {
+                    if let Cell::Code(code_cell) = cell {
+                        updated |= code_cell.update(cx, |cell, cx| {
+                            cell.update_display_data(&update.data, display_id, window, cx)
+                        });
+                    }
+                }
+                if updated {
+                    self.mark_dirty(cx);
+                }
+                return;
+            }
+            _ => {}
+        }
+
+        if let Some(Cell::Code(code_cell)) = self
+            .kernel
+            .cell_for_message(message)
+            .and_then(|cell_id| self.cell_map.get(cell_id))
+        {
+            code_cell.update(cx, |cell, cx| {
+                cell.handle_message(&message.content, window, cx)
+            });
+        }
+
+        // The kernel reports that it is idle once all outputs of an execution have been sent.
+        if let JupyterMessageContent::Status(status) = &message.content {
+            if matches!(status.execution_state, ExecutionState::Idle)
+                && self.kernel.finish_execution(message).is_some()
+            {
+                self.mark_dirty(cx);
+            }
+        }
+
+        cx.notify();
+    }
+
+    fn kernel_errored(&mut self, error_message: String, cx: &mut Context<Self>) {
+        self.set_kernel(Kernel::ErroredLaunch(error_message), cx);
+    }
+}
 
 // pub struct NotebookControls {
 //     pane_focused: bool,
@@ -702,7 +1529,20 @@ impl EventEmitter<()> for NotebookEditor {}
 // }
 
 impl Item for NotebookEditor {
-    type Event = ();
+    type Event = NotebookEditorEvent;
+
+    fn to_item_events(event: &Self::Event, mut f: impl FnMut(ItemEvent)) {
+        match event {
+            NotebookEditorEvent::Edited => {
+                f(ItemEvent::Edit);
+                f(ItemEvent::UpdateTab);
+            }
+            NotebookEditorEvent::Saved | NotebookEditorEvent::TitleChanged => {
+                f(ItemEvent::UpdateTab);
+                f(ItemEvent::UpdateBreadcrumbs);
+            }
+        }
+    }
 
     fn clone_on_split(
         &self,
@@ -769,48 +1609,79 @@ impl Item for NotebookEditor {
         // TODO
     }
 
-    // TODO
     fn can_save(&self, _cx: &App) -> bool {
-        false
+        true
     }
-    // TODO
+
+    fn can_save_as(&self, _cx: &App) -> bool {
+        true
+    }
+
     fn save(
         &mut self,
         _format: bool,
         _project: Entity<Project>,
-        _window: &mut Window,
-        _cx: &mut Context<Self>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
     ) -> Task<Result<()>> {
-        unimplemented!("save() must be implemented if can_save() returns true")
+        let notebook = self.to_notebook(cx);
+        let save = self
+            .notebook_item
+            .update(cx, |notebook_item, cx| notebook_item.save(notebook, cx));
+        self.finish_save(save, window, cx)
     }
 
-    // TODO
     fn save_as(
         &mut self,
-        _project: Entity<Project>,
-        _path: ProjectPath,
-        _window: &mut Window,
-        _cx: &mut Context<Self>,
+        project: Entity<Project>,
+        path: ProjectPath,
+        window: &mut Window,
+        cx: &mut Context<Self>,
     ) -> Task<Result<()>> {
-        unimplemented!("save_as() must be implemented if can_save() returns true")
+        let notebook = self.to_notebook(cx);
+        let save = self.notebook_item.update(cx, |notebook_item, cx| {
+            notebook_item.save_as(notebook, project, path, cx)
+        });
+        self.finish_save(save, window, cx)
     }
-    // TODO
+
     fn reload(
         &mut self,
         _project: Entity<Project>,
         _window: &mut Window,
-        _cx: &mut Context<Self>,
+        cx: &mut Context<Self>,
     ) -> Task<Result<()>> {
-        unimplemented!("reload() must be implemented if can_save() returns true")
+        self.notebook_item
+            .update(cx, |notebook_item, cx| notebook_item.reload(cx))
     }
 
-    fn is_dirty(&self, cx: &App) -> bool {
-        self.cell_map.values().any(|cell| {
-            if let Cell::Code(code_cell) = cell {
-                code_cell.read(cx).is_dirty(cx)
-            } else {
-                false
-            }
+    fn is_dirty(&self, _cx: &App) -> bool {
+        self.dirty
+    }
+
+    fn has_conflict(&self, cx: &App) -> bool {
+        self.dirty && self.notebook_item.read(cx).has_changed_on_disk()
+    }
+
+    fn has_deleted_file(&self, cx: &App) -> bool {
+        self.notebook_item.read(cx).has_deleted_file()
+    }
+}
+
+impl NotebookEditor {
+    fn finish_save(
+        &mut self,
+        save: Task<Result<()>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        cx.spawn_in(window, |this, mut cx| async move {
+            save.await?;
+            this.update(&mut cx, |this, cx| {
+                this.dirty = false;
+                cx.emit(NotebookEditorEvent::Saved);
+                cx.notify();
+            })
         })
     }
 }
@@ -833,3 +1704,363 @@ impl ProjectItem for NotebookEditor {
         Self::new(project, item, window, cx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::{TestAppContext, VisualTestContext};
+    use indoc::indoc;
+    use project::FakeFs;
+    use serde_json::json;
+    use settings::{Settings as _, SettingsStore};
+    use std::path::Path;
+
+    const NOTEBOOK: &str = indoc! {r##"
+        {
+         "nbformat": 4,
+         "nbformat_minor": 5,
+         "metadata": {
+          "language_info": {"name": "python"},
+          "kernelspec": {"name": "python3", "display_name": "Python 3", "language": "python"}
+         },
+         "cells": [
+          {
+           "cell_type": "markdown",
+           "id": "intro",
+           "metadata": {},
+           "source": ["# Title\n", "Some text"]
+          },
+          {
+           "cell_type": "code",
+           "id": "compute",
+           "metadata": {"tags": ["keep"]},
+           "execution_count": 3,
+           "source": ["print(1 + 1)"],
+           "outputs": [
+            {"output_type": "stream", "name": "stdout", "text": ["2\n"]}
+           ]
+          }
+         ]
+        }
+    "##};
+
+    #[test]
+    fn test_serialize_notebook_is_stable() {
+        let notebook = parse_notebook(NOTEBOOK).unwrap();
+        let serialized = serialize_notebook(&notebook).unwrap();
+
+        assert!(serialized.ends_with("}\n"));
+        assert!(serialized.starts_with("{\n \"cells\": [\n"));
+        assert!(
+            serialized.find("\"metadata\"").unwrap() < serialized.find("\"nbformat\"").unwrap()
+        );
+
+        let reserialized = serialize_notebook(&parse_notebook(&serialized).unwrap()).unwrap();
+        assert_eq!(serialized, reserialized);
+    }
+
+    #[test]
+    fn test_serialize_notebook_preserves_outputs() {
+        let notebook = parse_notebook(NOTEBOOK).unwrap();
+        let serialized = serialize_notebook(&notebook).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&serialized).unwrap();
+
+        let code_cell = &value["cells"][1];
+        assert_eq!(code_cell["execution_count"], 3);
+        assert_eq!(code_cell["metadata"]["tags"][0], "keep");
+        assert_eq!(code_cell["outputs"][0]["output_type"], "stream");
+        assert_eq!(code_cell["outputs"][0]["name"], "stdout");
+        assert_eq!(value["cells"][0]["source"][0], "# Title\n");
+    }
+
+    const CELLS_NOTEBOOK: &str = indoc! {r##"
+        {
+         "nbformat": 4,
+         "nbformat_minor": 5,
+         "metadata": {},
+         "cells": [
+          {
+           "cell_type": "code",
+           "id": "first",
+           "metadata": {},
+           "execution_count": null,
+           "source": ["a = 1\n", "b = 2"],
+           "outputs": []
+          },
+          {
+           "cell_type": "markdown",
+           "id": "second",
+           "metadata": {},
+           "source": ["# Heading"]
+          },
+          {
+           "cell_type": "raw",
+           "id": "third",
+           "metadata": {},
+           "source": ["raw text"]
+          }
+         ]
+        }
+    "##};
+
+    #[gpui::test]
+    async fn test_split_and_merge_cells(cx: &mut TestAppContext) {
+        let (editor, cx) = build_notebook_editor(CELLS_NOTEBOOK, cx).await;
+
+        editor.update_in(cx, |editor, window, cx| {
+            editor
+                .selected_cell()
+                .unwrap()
+                .editor(cx)
+                .update(cx, |editor, cx| {
+                    editor.change_selections(None, window, cx, |selections| {
+                        selections.select_ranges([6..6])
+                    })
+                });
+            editor.split_cell(window, cx);
+        });
+        editor.update(cx, |editor, cx| {
+            assert_eq!(
+                cells(editor, cx),
+                [
+                    ("code", "a = 1".to_string()),
+                    ("code", "b = 2".to_string()),
+                    ("markdown", "# Heading".to_string()),
+                    ("raw", "raw text".to_string()),
+                ]
+            );
+            assert_eq!(editor.selected_index(), 1);
+            assert!(editor.is_dirty(cx));
+        });
+
+        editor.update_in(cx, |editor, window, cx| {
+            editor.set_selected_index(0, false, window, cx);
+            editor.merge_cell_below(window, cx);
+        });
+        editor.update(cx, |editor, cx| {
+            assert_eq!(
+                cells(editor, cx),
+                [
+                    ("code", "a = 1\nb = 2".to_string()),
+                    ("markdown", "# Heading".to_string()),
+                    ("raw", "raw text".to_string()),
+                ]
+            );
+            assert_eq!(editor.to_notebook(cx).cells.len(), 3);
+        });
+
+        // The last cell has nothing to merge with.
+        editor.update_in(cx, |editor, window, cx| {
+            editor.set_selected_index(2, false, window, cx);
+            editor.merge_cell_below(window, cx);
+            assert_eq!(editor.cell_order.len(), 3);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_move_convert_and_delete_cells(cx: &mut TestAppContext) {
+        let (editor, cx) = build_notebook_editor(CELLS_NOTEBOOK, cx).await;
+
+        let first_cell_id = editor.update(cx, |editor, _| editor.cell_order[0].clone());
+        editor.update_in(cx, |editor, window, cx| {
+            editor.move_cell(0, 1, window, cx);
+            assert_eq!(editor.selected_index(), 1);
+            editor.move_cell_down(window, cx);
+            assert_eq!(editor.selected_index(), 2);
+            // Moving past the last cell does nothing.
+            editor.move_cell_down(window, cx);
+            assert_eq!(editor.selected_index(), 2);
+        });
+        editor.update(cx, |editor, cx| {
+            assert_eq!(
+                cells(editor, cx),
+                [
+                    ("markdown", "# Heading".to_string()),
+                    ("raw", "raw text".to_string()),
+                    ("code", "a = 1\nb = 2".to_string()),
+                ]
+            );
+        });
+
+        // Converting a cell keeps its id and source.
+        editor.update_in(cx, |editor, window, cx| {
+            editor.convert_cell(CellType::Markdown, window, cx);
+        });
+        editor.update(cx, |editor, cx| {
+            assert_eq!(
+                cells(editor, cx)[2],
+                ("markdown", "a = 1\nb = 2".to_string())
+            );
+            assert!(editor.selected_cell().unwrap().id(cx) == first_cell_id);
+        });
+
+        editor.update_in(cx, |editor, window, cx| {
+            editor.delete_cell(window, cx);
+            assert_eq!(editor.selected_index(), 1);
+            editor.set_selected_index(0, false, window, cx);
+            editor.delete_cell(window, cx);
+            assert_eq!(editor.selected_index(), 0);
+        });
+        editor.update(cx, |editor, cx| {
+            assert_eq!(cells(editor, cx), [("raw", "raw text".to_string())]);
+            let notebook = editor.to_notebook(cx);
+            assert_eq!(notebook.cells.len(), 1);
+            assert!(editor.is_dirty(cx));
+        });
+    }
+
+    #[gpui::test]
+    async fn test_dirty_tracking(cx: &mut TestAppContext) {
+        let (editor, cx) = build_notebook_editor(CELLS_NOTEBOOK, cx).await;
+        let fs = editor.update(cx, |editor, cx| editor.project.read(cx).fs().clone());
+        let project = editor.update(cx, |editor, _| editor.project.clone());
+        editor.update(cx, |editor, cx| assert!(!editor.is_dirty(cx)));
+
+        editor.update_in(cx, |editor, window, cx| {
+            editor
+                .selected_cell()
+                .unwrap()
+                .editor(cx)
+                .update(cx, |editor, cx| editor.set_text("a = 3", window, cx));
+        });
+        editor.update(cx, |editor, cx| {
+            assert!(editor.is_dirty(cx));
+            assert!(!editor.has_conflict(cx));
+        });
+
+        editor
+            .update_in(cx, |editor, window, cx| {
+                editor.save(false, project, window, cx)
+            })
+            .await
+            .unwrap();
+        cx.run_until_parked();
+        editor.update(cx, |editor, cx| {
+            assert!(!editor.is_dirty(cx));
+            assert!(!editor.has_conflict(cx));
+        });
+
+        // Saving doesn't count as an external change.
+        let saved =
+            parse_notebook(&fs.load(Path::new("/dir/notebook.ipynb")).await.unwrap()).unwrap();
+        assert_eq!(saved.cells.len(), 3);
+        let nbformat::v4::Cell::Code { source, .. } = &saved.cells[0] else {
+            panic!("expected a code cell");
+        };
+        assert_eq!(source.join(""), "a = 3");
+    }
+
+    #[gpui::test]
+    async fn test_reload_after_change_on_disk(cx: &mut TestAppContext) {
+        let (editor, cx) = build_notebook_editor(CELLS_NOTEBOOK, cx).await;
+        let fs = editor.update(cx, |editor, cx| editor.project.read(cx).fs().clone());
+        let project = editor.update(cx, |editor, _| editor.project.clone());
+        let path = Path::new("/dir/notebook.ipynb");
+
+        // Unmodified notebooks follow the file on disk.
+        fs.save(
+            path,
+            &CELLS_NOTEBOOK.replace("raw text", "changed on disk").into(),
+            Default::default(),
+        )
+        .await
+        .unwrap();
+        cx.run_until_parked();
+        editor.update(cx, |editor, cx| {
+            assert_eq!(cells(editor, cx)[2], ("raw", "changed on disk".to_string()));
+            assert!(!editor.is_dirty(cx));
+            assert!(!editor.has_conflict(cx));
+        });
+
+        // Modified notebooks are left alone and reported as conflicted.
+        editor.update_in(cx, |editor, window, cx| {
+            editor
+                .selected_cell()
+                .unwrap()
+                .editor(cx)
+                .update(cx, |editor, cx| editor.set_text("a = 3", window, cx));
+        });
+        fs.save(
+            path,
+            &CELLS_NOTEBOOK.replace("raw text", "changed again").into(),
+            Default::default(),
+        )
+        .await
+        .unwrap();
+        cx.run_until_parked();
+        editor.update(cx, |editor, cx| {
+            assert_eq!(cells(editor, cx)[0], ("code", "a = 3".to_string()));
+            assert_eq!(cells(editor, cx)[2], ("raw", "changed on disk".to_string()));
+            assert!(editor.is_dirty(cx));
+            assert!(editor.has_conflict(cx));
+        });
+
+        // Reloading discards the edits.
+        editor
+            .update_in(cx, |editor, window, cx| editor.reload(project, window, cx))
+            .await
+            .unwrap();
+        cx.run_until_parked();
+        editor.update(cx, |editor, cx| {
+            assert_eq!(cells(editor, cx)[0], ("code", "a = 1\nb = 2".to_string()));
+            assert_eq!(cells(editor, cx)[2], ("raw", "changed again".to_string()));
+            assert!(!editor.is_dirty(cx));
+            assert!(!editor.has_conflict(cx));
+        });
+    }
+
+    async fn build_notebook_editor<'a>(
+        content: &str,
+        cx: &'a mut TestAppContext,
+    ) -> (Entity<NotebookEditor>, &'a mut VisualTestContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            theme::init(theme::LoadThemes::JustBase, cx);
+            language::init(cx);
+            Project::init_settings(cx);
+            workspace::init_settings(cx);
+            editor::init_settings(cx);
+            crate::JupyterSettings::register(cx);
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/dir", json!({ "notebook.ipynb": content }))
+            .await;
+        let project = Project::test(fs, [Path::new("/dir")], cx).await;
+        cx.run_until_parked();
+
+        let project_path = project.read_with(cx, |project, cx| ProjectPath {
+            worktree_id: project.worktrees(cx).next().unwrap().read(cx).id(),
+            path: Path::new("notebook.ipynb").into(),
+        });
+        let notebook_item = cx
+            .update(|cx| {
+                <NotebookItem as project::ProjectItem>::try_open(&project, &project_path, cx)
+            })
+            .unwrap()
+            .await
+            .unwrap();
+
+        let (editor, cx) = cx
+            .add_window_view(|window, cx| NotebookEditor::new(project, notebook_item, window, cx));
+        cx.run_until_parked();
+        (editor, cx)
+    }
+
+    fn cells(editor: &NotebookEditor, cx: &App) -> Vec<(&'static str, String)> {
+        editor
+            .cell_order
+            .iter()
+            .map(|cell_id| {
+                let cell = &editor.cell_map[cell_id];
+                let kind = match cell {
+                    Cell::Code(_) => "code",
+                    Cell::Markdown(_) => "markdown",
+                    Cell::Raw(_) => "raw",
+                };
+                (kind, cell.current_source(cx))
+            })
+            .collect()
+    }
+}
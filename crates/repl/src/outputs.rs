@@ -14,14 +14,20 @@
 //! The module supports several output types, including:
 //! - Plain text
 //! - Markdown
-//! - Images (PNG and JPEG)
+//! - HTML, reduced to Markdown
+//! - Images (PNG, JPEG and SVG)
+//! - LaTeX, converted to Unicode text
+//! - JSON, as a collapsible tree
 //! - Tables
 //! - Error messages
 //!
+//! Which type an output is shown as is chosen by the `jupyter.mime_type_priority` setting.
+//!
 //! ## Clipboard Support
 //!
 //! Most output types implement the `SupportsClipboard` trait, allowing
-//! users to easily copy output content to the system clipboard.
+//! users to easily copy output content to the system clipboard. Outputs
+//! available in several MIME types can also be copied as any one of them.
 //!
 //! ## Rendering
 //!
@@ -35,23 +41,38 @@
 
 use std::time::Duration;
 
+use anyhow::{bail, Context as _, Result};
 use editor::{Editor, MultiBuffer};
 use gpui::{
-    percentage, Animation, AnimationExt, AnyElement, ClipboardItem, Entity, Render, Transformation,
-    WeakEntity,
+    percentage, Animation, AnimationExt, AnyElement, ClipboardItem, Corner, Entity, Render,
+    Transformation, WeakEntity,
 };
 use language::Buffer;
 use runtimelib::{ExecutionState, JupyterMessageContent, MimeBundle, MimeType};
-use ui::{div, prelude::*, v_flex, Context, IntoElement, Styled, Tooltip, Window};
+use serde_json::Value;
+use settings::Settings as _;
+use ui::{
+    div, prelude::*, v_flex, Context, ContextMenu, IntoElement, PopoverMenu, Styled, Tooltip,
+    Window,
+};
+use util::ResultExt as _;
+
+use crate::JupyterSettings;
 
 mod image;
 use image::ImageView;
 
+mod json;
+use json::JsonView;
+
+mod latex;
+use latex::LatexView;
+
 mod markdown;
-use markdown::MarkdownView;
+use markdown::{html_to_markdown, MarkdownView};
 
 mod table;
-use table::TableView;
+pub(crate) use table::TableView;
 
 pub mod plain;
 use plain::TerminalOutput;
@@ -60,16 +81,103 @@ pub(crate) mod user_error;
 use user_error::ErrorView;
 use workspace::Workspace;
 
-/// When deciding what to render from a collection of mediatypes, we need to rank them in order of importance
-fn rank_mime_type(mimetype: &MimeType) -> usize {
+/// The MIME types Zed can render, from most to least preferred.
+const DEFAULT_MIME_TYPE_PRIORITY: &[&str] = &[
+    "application/vnd.dataresource+json",
+    "image/png",
+    "image/jpeg",
+    "image/svg+xml",
+    "text/html",
+    "text/markdown",
+    "text/latex",
+    "application/json",
+    "text/plain",
+];
+
+fn mime_type_name(mimetype: &MimeType) -> Option<&'static str> {
     match mimetype {
-        MimeType::DataTable(_) => 6,
-        MimeType::Png(_) => 4,
-        MimeType::Jpeg(_) => 3,
-        MimeType::Markdown(_) => 2,
-        MimeType::Plain(_) => 1,
+        MimeType::DataTable(_) => Some("application/vnd.dataresource+json"),
+        MimeType::Png(_) => Some("image/png"),
+        MimeType::Jpeg(_) => Some("image/jpeg"),
+        MimeType::Svg(_) => Some("image/svg+xml"),
+        MimeType::Html(_) => Some("text/html"),
+        MimeType::Markdown(_) => Some("text/markdown"),
+        MimeType::Latex(_) => Some("text/latex"),
+        MimeType::Json(_) => Some("application/json"),
+        MimeType::Plain(_) => Some("text/plain"),
         // All other media types are not supported in Zed at this time
-        _ => 0,
+        _ => None,
+    }
+}
+
+/// The MIME types to render outputs as, from most to least preferred: those in the
+/// `jupyter.mime_type_priority` setting, followed by the remaining supported types.
+fn mime_type_priority(cx: &App) -> Vec<String> {
+    let mut priority = JupyterSettings::get_global(cx).mime_type_priority.clone();
+    for mime_type in DEFAULT_MIME_TYPE_PRIORITY {
+        if !priority.iter().any(|preferred| preferred == mime_type) {
+            priority.push(mime_type.to_string());
+        }
+    }
+    priority
+}
+
+/// When deciding what to render from a collection of mediatypes, we need to rank them in order of importance
+fn ranked_mime_types<'a>(data: &'a MimeBundle, priority: &[String]) -> Vec<&'a MimeType> {
+    let mut mimetypes = data
+        .content
+        .iter()
+        .filter_map(|mimetype| {
+            let name = mime_type_name(mimetype)?;
+            let position = priority.iter().position(|preferred| preferred == name)?;
+            Some((position, mimetype))
+        })
+        .collect::<Vec<_>>();
+    mimetypes.sort_by_key(|(position, _)| *position);
+    mimetypes
+        .into_iter()
+        .map(|(_, mimetype)| mimetype)
+        .collect()
+}
+
+/// The contents of an output in each of the MIME types the kernel sent it as,
+/// so that it can be copied as any one of them.
+#[derive(Clone, Default)]
+pub struct MimeData(Vec<(SharedString, String)>);
+
+impl MimeData {
+    pub fn new(data: &MimeBundle) -> Self {
+        let Some(Value::Object(bundle)) = serde_json::to_value(data).log_err() else {
+            return Self::default();
+        };
+
+        Self(
+            bundle
+                .into_iter()
+                .map(|(mime_type, value)| {
+                    let text = match value {
+                        Value::String(text) => text,
+                        // Notebooks may store text split into lines.
+                        Value::Array(lines) if lines.iter().all(Value::is_string) => {
+                            lines.iter().filter_map(Value::as_str).collect::<String>()
+                        }
+                        value => serde_json::to_string_pretty(&value).unwrap_or_default(),
+                    };
+                    (mime_type.into(), text)
+                })
+                .collect(),
+        )
+    }
+
+    pub fn mime_types(&self) -> impl Iterator<Item = &SharedString> {
+        self.0.iter().map(|(mime_type, _)| mime_type)
+    }
+
+    pub fn get(&self, mime_type: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(candidate, _)| candidate.as_ref() == mime_type)
+            .map(|(_, text)| text.as_str())
     }
 }
 
@@ -108,6 +216,7 @@ pub enum Output {
     Plain {
         content: Entity<TerminalOutput>,
         display_id: Option<String>,
+        mime_data: MimeData,
     },
     Stream {
         content: Entity<TerminalOutput>,
@@ -115,16 +224,29 @@ pub enum Output {
     Image {
         content: Entity<ImageView>,
         display_id: Option<String>,
+        mime_data: MimeData,
     },
     ErrorOutput(ErrorView),
     Message(String),
     Table {
         content: Entity<TableView>,
         display_id: Option<String>,
+        mime_data: MimeData,
     },
     Markdown {
         content: Entity<MarkdownView>,
         display_id: Option<String>,
+        mime_data: MimeData,
+    },
+    Latex {
+        content: Entity<LatexView>,
+        display_id: Option<String>,
+        mime_data: MimeData,
+    },
+    Json {
+        content: Entity<JsonView>,
+        display_id: Option<String>,
+        mime_data: MimeData,
     },
     ClearOutputWaitMarker,
 }
@@ -132,11 +254,18 @@ pub enum Output {
 impl Output {
     fn render_output_controls<V: OutputContent + 'static>(
         v: Entity<V>,
+        mime_data: Option<&MimeData>,
         workspace: WeakEntity<Workspace>,
         window: &mut Window,
         cx: &mut Context<ExecutionView>,
     ) -> Option<AnyElement> {
-        if !v.has_clipboard_content(window, cx) && !v.has_buffer_content(window, cx) {
+        // Copying as a specific MIME type is only useful when there is more than one.
+        let mime_data = mime_data.filter(|mime_data| mime_data.mime_types().nth(1).is_some());
+
+        if !v.has_clipboard_content(window, cx)
+            && !v.has_buffer_content(window, cx)
+            && mime_data.is_none()
+        {
             return None;
         }
 
@@ -158,6 +287,9 @@ impl Output {
                             })),
                     )
                 })
+                .when_some(mime_data.cloned(), |el, mime_data| {
+                    el.child(Self::render_copy_as_menu(v.entity_id(), mime_data))
+                })
                 .when(v.has_buffer_content(window, cx), |el| {
                     let v = v.clone();
                     el.child(
@@ -209,6 +341,34 @@ impl Output {
         )
     }
 
+    fn render_copy_as_menu(entity_id: gpui::EntityId, mime_data: MimeData) -> impl IntoElement {
+        PopoverMenu::new(ElementId::Name(
+            format!("copy-output-as-{entity_id}").into(),
+        ))
+        .trigger_with_tooltip(
+            IconButton::new(
+                ElementId::Name("copy-output-as".into()),
+                IconName::ChevronDown,
+            )
+            .style(ButtonStyle::Transparent)
+            .icon_size(IconSize::Small),
+            Tooltip::text("Copy Output As…"),
+        )
+        .anchor(Corner::TopRight)
+        .menu(move |window, cx| {
+            let mime_data = mime_data.clone();
+            Some(ContextMenu::build(window, cx, move |mut menu, _, _| {
+                for mime_type in mime_data.mime_types() {
+                    let text = mime_data.get(mime_type).unwrap_or_default().to_string();
+                    menu = menu.entry(format!("Copy as {mime_type}"), None, move |_, cx| {
+                        cx.write_to_clipboard(ClipboardItem::new_string(text.clone()));
+                    });
+                }
+                menu
+            }))
+        })
+    }
+
     pub fn render(
         &self,
 
@@ -223,6 +383,8 @@ impl Output {
             Self::Image { content, .. } => Some(content.clone().into_any_element()),
             Self::Message(message) => Some(div().child(message.clone()).into_any_element()),
             Self::Table { content, .. } => Some(content.clone().into_any_element()),
+            Self::Latex { content, .. } => Some(content.clone().into_any_element()),
+            Self::Json { content, .. } => Some(content.clone().into_any_element()),
             Self::ErrorOutput(error_view) => error_view.render(window, cx),
             Self::ClearOutputWaitMarker => None,
         };
@@ -232,32 +394,83 @@ impl Output {
             .items_start()
             .child(div().flex_1().children(content))
             .children(match self {
-                Self::Plain { content, .. } => {
-                    Self::render_output_controls(content.clone(), workspace.clone(), window, cx)
-                }
-                Self::Markdown { content, .. } => {
-                    Self::render_output_controls(content.clone(), workspace.clone(), window, cx)
-                }
-                Self::Stream { content, .. } => {
-                    Self::render_output_controls(content.clone(), workspace.clone(), window, cx)
-                }
-                Self::Image { content, .. } => {
-                    Self::render_output_controls(content.clone(), workspace.clone(), window, cx)
-                }
+                Self::Plain { content, .. } => Self::render_output_controls(
+                    content.clone(),
+                    self.mime_data(),
+                    workspace.clone(),
+                    window,
+                    cx,
+                ),
+                Self::Markdown { content, .. } => Self::render_output_controls(
+                    content.clone(),
+                    self.mime_data(),
+                    workspace.clone(),
+                    window,
+                    cx,
+                ),
+                Self::Stream { content, .. } => Self::render_output_controls(
+                    content.clone(),
+                    None,
+                    workspace.clone(),
+                    window,
+                    cx,
+                ),
+                Self::Image { content, .. } => Self::render_output_controls(
+                    content.clone(),
+                    self.mime_data(),
+                    workspace.clone(),
+                    window,
+                    cx,
+                ),
                 Self::ErrorOutput(err) => Self::render_output_controls(
                     err.traceback.clone(),
+                    None,
                     workspace.clone(),
                     window,
                     cx,
                 ),
                 Self::Message(_) => None,
-                Self::Table { content, .. } => {
-                    Self::render_output_controls(content.clone(), workspace.clone(), window, cx)
-                }
+                Self::Table { content, .. } => Self::render_output_controls(
+                    content.clone(),
+                    self.mime_data(),
+                    workspace.clone(),
+                    window,
+                    cx,
+                ),
+                Self::Latex { content, .. } => Self::render_output_controls(
+                    content.clone(),
+                    self.mime_data(),
+                    workspace.clone(),
+                    window,
+                    cx,
+                ),
+                Self::Json { content, .. } => Self::render_output_controls(
+                    content.clone(),
+                    self.mime_data(),
+                    workspace.clone(),
+                    window,
+                    cx,
+                ),
                 Self::ClearOutputWaitMarker => None,
             })
     }
 
+    /// The output's contents in each of the MIME types it was sent as.
+    pub fn mime_data(&self) -> Option<&MimeData> {
+        match self {
+            Output::Plain { mime_data, .. }
+            | Output::Image { mime_data, .. }
+            | Output::Table { mime_data, .. }
+            | Output::Markdown { mime_data, .. }
+            | Output::Latex { mime_data, .. }
+            | Output::Json { mime_data, .. } => Some(mime_data),
+            Output::Stream { .. }
+            | Output::ErrorOutput(_)
+            | Output::Message(_)
+            | Output::ClearOutputWaitMarker => None,
+        }
+    }
+
     pub fn display_id(&self) -> Option<String> {
         match self {
             Output::Plain { display_id, .. } => display_id.clone(),
@@ -267,6 +480,8 @@ impl Output {
             Output::Message(_) => None,
             Output::Table { display_id, .. } => display_id.clone(),
             Output::Markdown { display_id, .. } => display_id.clone(),
+            Output::Latex { display_id, .. } => display_id.clone(),
+            Output::Json { display_id, .. } => display_id.clone(),
             Output::ClearOutputWaitMarker => None,
         }
     }
@@ -277,32 +492,90 @@ impl Output {
         window: &mut Window,
         cx: &mut App,
     ) -> Self {
-        match data.richest(rank_mime_type) {
-            Some(MimeType::Plain(text)) => Output::Plain {
+        let mime_data = MimeData::new(data);
+        let priority = mime_type_priority(cx);
+
+        // If an output can't be rendered as its richest MIME type, fall back to the next one.
+        let mut error = None;
+        for mimetype in ranked_mime_types(data, &priority) {
+            match Self::from_mime_type(mimetype, display_id.clone(), mime_data.clone(), window, cx)
+            {
+                Ok(output) => return output,
+                Err(render_error) => error = Some(render_error),
+            }
+        }
+        match error {
+            Some(error) => Output::Message(format!("{error:#}")),
+            // Any other media types are not supported
+            None => Output::Message("Unsupported media type".to_string()),
+        }
+    }
+
+    fn from_mime_type(
+        mimetype: &MimeType,
+        display_id: Option<String>,
+        mime_data: MimeData,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Result<Self> {
+        Ok(match mimetype {
+            MimeType::Plain(text) => Output::Plain {
                 content: cx.new(|cx| TerminalOutput::from(text, window, cx)),
                 display_id,
+                mime_data,
             },
-            Some(MimeType::Markdown(text)) => {
+            MimeType::Markdown(text) => {
                 let content = cx.new(|cx| MarkdownView::from(text.clone(), cx));
                 Output::Markdown {
                     content,
                     display_id,
+                    mime_data,
+                }
+            }
+            MimeType::Html(html) => {
+                let markdown = html_to_markdown(html).context("Failed to render HTML")?;
+                Output::Markdown {
+                    content: cx.new(|cx| MarkdownView::from(markdown, cx)),
+                    display_id,
+                    mime_data,
+                }
+            }
+            MimeType::Png(data) | MimeType::Jpeg(data) => {
+                let view = ImageView::from(data).context("Failed to load image")?;
+                Output::Image {
+                    content: cx.new(|_| view),
+                    display_id,
+                    mime_data,
                 }
             }
-            Some(MimeType::Png(data)) | Some(MimeType::Jpeg(data)) => match ImageView::from(data) {
-                Ok(view) => Output::Image {
+            MimeType::Svg(svg) => {
+                let view = ImageView::from_svg(svg, cx).context("Failed to render SVG")?;
+                Output::Image {
                     content: cx.new(|_| view),
                     display_id,
-                },
-                Err(error) => Output::Message(format!("Failed to load image: {}", error)),
+                    mime_data,
+                }
+            }
+            MimeType::Latex(latex) => Output::Latex {
+                content: cx.new(|_| LatexView::new(latex.clone())),
+                display_id,
+                mime_data,
             },
-            Some(MimeType::DataTable(data)) => Output::Table {
+            MimeType::Json(json) => {
+                let value = serde_json::to_value(json).unwrap_or_default();
+                Output::Json {
+                    content: cx.new(|_| JsonView::new(value)),
+                    display_id,
+                    mime_data,
+                }
+            }
+            MimeType::DataTable(data) => Output::Table {
                 content: cx.new(|cx| TableView::new(data, window, cx)),
                 display_id,
+                mime_data,
             },
-            // Any other media types are not supported
-            _ => Output::Message("Unsupported media type".to_string()),
-        }
+            _ => bail!("Unsupported media type"),
+        })
     }
 }
 
@@ -544,3 +817,53 @@ impl Render for ExecutionView {
             .into_any_element()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn mime_bundle(value: Value) -> MimeBundle {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_ranked_mime_types_follow_priority() {
+        let data = mime_bundle(json!({
+            "text/plain": "   a\n0  1",
+            "text/html": "<table><tr><td>1</td></tr></table>",
+            "application/json": {"a": [1]},
+        }));
+
+        let default_priority = DEFAULT_MIME_TYPE_PRIORITY
+            .iter()
+            .map(|mime_type| mime_type.to_string())
+            .collect::<Vec<_>>();
+        assert!(matches!(
+            ranked_mime_types(&data, &default_priority)[..],
+            [MimeType::Html(_), MimeType::Json(_), MimeType::Plain(_)]
+        ));
+
+        let priority = ["application/json".to_string(), "text/plain".to_string()];
+        assert!(matches!(
+            ranked_mime_types(&data, &priority)[..],
+            [MimeType::Json(_), MimeType::Plain(_)]
+        ));
+
+        let unsupported = mime_bundle(json!({"application/vnd.custom": "x"}));
+        assert!(ranked_mime_types(&unsupported, &default_priority).is_empty());
+    }
+
+    #[test]
+    fn test_mime_data() {
+        let data = mime_bundle(json!({
+            "text/plain": "x",
+            "application/json": {"a": 1},
+        }));
+        let mime_data = MimeData::new(&data);
+
+        assert_eq!(mime_data.get("text/plain"), Some("x"));
+        assert_eq!(mime_data.get("application/json"), Some("{\n  \"a\": 1\n}"));
+        assert_eq!(mime_data.get("text/html"), None);
+    }
+}
@@ -73,6 +73,30 @@ impl ImageView {
     }
 }
 
+impl ImageView {
+    /// Rasterizes an SVG image.
+    pub fn from_svg(svg: &str, cx: &App) -> Result<Self> {
+        let bytes = svg.as_bytes().to_vec();
+        let image = Image {
+            format: ImageFormat::Svg,
+            bytes,
+            id: 0,
+        };
+        let render_image = image.to_image_data(cx.svg_renderer())?;
+        let size = render_image.size(0);
+
+        Ok(ImageView {
+            clipboard_image: Arc::new(Image {
+                id: render_image.id.0 as u64,
+                ..image
+            }),
+            height: size.height.0 as u32,
+            width: size.width.0 as u32,
+            image: render_image,
+        })
+    }
+}
+
 impl Render for ImageView {
     fn render(&mut self, window: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
         let line_height = window.line_height();
@@ -0,0 +1,190 @@
+//! # JSON Output for REPL
+//!
+//! Renders `application/json` outputs as a tree whose objects and arrays can be
+//! expanded and collapsed.
+
+use collections::HashSet;
+use gpui::{AnyElement, App, ClipboardItem, Entity, Window};
+use language::Buffer;
+use serde_json::Value;
+use ui::{prelude::*, Disclosure};
+
+use crate::outputs::OutputContent;
+
+/// The most children of an object or array that are shown when it is expanded.
+const MAX_CHILDREN: usize = 100;
+
+pub struct JsonView {
+    value: Value,
+    /// The JSON pointers of the objects and arrays that are expanded.
+    expanded: HashSet<String>,
+}
+
+impl JsonView {
+    pub fn new(value: Value) -> Self {
+        let mut expanded = HashSet::default();
+        // The root is expanded to start with, so that small outputs are visible at a glance.
+        expanded.insert(String::new());
+        Self { value, expanded }
+    }
+
+    fn toggle(&mut self, pointer: &str, cx: &mut Context<Self>) {
+        if !self.expanded.remove(pointer) {
+            self.expanded.insert(pointer.to_string());
+        }
+        cx.notify();
+    }
+
+    fn render_node(
+        &self,
+        key: Option<SharedString>,
+        value: &Value,
+        pointer: String,
+        depth: usize,
+        rows: &mut Vec<AnyElement>,
+        cx: &mut Context<Self>,
+    ) {
+        let indent = rems(depth as f32);
+        let key_label = key.map(|key| Label::new(format!("{key}:")).color(Color::Accent));
+
+        let children: Vec<(SharedString, &Value)> = match value {
+            Value::Object(object) => object
+                .iter()
+                .map(|(key, value)| (SharedString::from(key.clone()), value))
+                .collect(),
+            Value::Array(array) => array
+                .iter()
+                .enumerate()
+                .map(|(ix, value)| (SharedString::from(ix.to_string()), value))
+                .collect(),
+            scalar => {
+                rows.push(
+                    h_flex()
+                        .pl(indent)
+                        .gap_1()
+                        .children(key_label)
+                        .child(render_scalar(scalar))
+                        .into_any_element(),
+                );
+                return;
+            }
+        };
+
+        let is_expanded = self.expanded.contains(&pointer);
+        let summary = match value {
+            Value::Object(_) if is_expanded => "{".to_string(),
+            Value::Array(_) if is_expanded => "[".to_string(),
+            Value::Object(_) => format!("{{…}} {} keys", children.len()),
+            _ => format!("[…] {} items", children.len()),
+        };
+
+        rows.push(
+            h_flex()
+                .pl(indent)
+                .gap_1()
+                .child(
+                    Disclosure::new(
+                        ElementId::Name(format!("json-{pointer}").into()),
+                        is_expanded,
+                    )
+                    .on_click(cx.listener({
+                        let pointer = pointer.clone();
+                        move |this, _, _, cx| this.toggle(&pointer, cx)
+                    })),
+                )
+                .children(key_label)
+                .child(Label::new(summary).color(Color::Muted))
+                .into_any_element(),
+        );
+
+        if !is_expanded {
+            return;
+        }
+
+        let child_count = children.len();
+        for (key, child) in children.into_iter().take(MAX_CHILDREN) {
+            let child_pointer = format!("{pointer}/{}", escape_pointer_segment(&key));
+            self.render_node(Some(key), child, child_pointer, depth + 1, rows, cx);
+        }
+        if child_count > MAX_CHILDREN {
+            rows.push(
+                div()
+                    .pl(rems((depth + 1) as f32))
+                    .child(
+                        Label::new(format!("… {} more", child_count - MAX_CHILDREN))
+                            .color(Color::Muted),
+                    )
+                    .into_any_element(),
+            );
+        }
+
+        let closing = if value.is_object() { "}" } else { "]" };
+        rows.push(
+            div()
+                .pl(indent)
+                .child(Label::new(closing).color(Color::Muted))
+                .into_any_element(),
+        );
+    }
+}
+
+fn render_scalar(value: &Value) -> Label {
+    match value {
+        Value::String(string) => Label::new(format!("{string:?}")).color(Color::Created),
+        Value::Number(number) => Label::new(number.to_string()).color(Color::Info),
+        Value::Bool(bool) => Label::new(bool.to_string()).color(Color::Warning),
+        _ => Label::new("null").color(Color::Muted),
+    }
+}
+
+/// Escapes a key for use in a JSON pointer, as described in RFC 6901.
+fn escape_pointer_segment(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+impl OutputContent for JsonView {
+    fn clipboard_content(&self, _window: &Window, _cx: &App) -> Option<ClipboardItem> {
+        let json = serde_json::to_string_pretty(&self.value).ok()?;
+        Some(ClipboardItem::new_string(json))
+    }
+
+    fn has_clipboard_content(&self, _window: &Window, _cx: &App) -> bool {
+        true
+    }
+
+    fn has_buffer_content(&self, _window: &Window, _cx: &App) -> bool {
+        true
+    }
+
+    fn buffer_content(&mut self, _: &mut Window, cx: &mut App) -> Option<Entity<Buffer>> {
+        let json = serde_json::to_string_pretty(&self.value).ok()?;
+        let buffer = cx.new(|cx| {
+            let mut buffer =
+                Buffer::local(json, cx).with_language(language::PLAIN_TEXT.clone(), cx);
+            buffer.set_capability(language::Capability::ReadOnly, cx);
+            buffer
+        });
+        Some(buffer)
+    }
+}
+
+impl Render for JsonView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let mut rows = Vec::new();
+        let value = self.value.clone();
+        self.render_node(None, &value, String::new(), 0, &mut rows, cx);
+
+        v_flex().font_buffer(cx).py_1().children(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_pointer_segment() {
+        assert_eq!(escape_pointer_segment("a/b~c"), "a~1b~0c");
+        assert_eq!(escape_pointer_segment("plain"), "plain");
+    }
+}
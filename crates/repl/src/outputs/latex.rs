@@ -0,0 +1,358 @@
+//! # LaTeX Output for REPL
+//!
+//! Kernels such as SymPy emit `text/latex` for mathematical expressions. Zed has no TeX
+//! renderer, so the common subset of math mode is converted to Unicode text: Greek letters,
+//! operators, fractions, roots, superscripts and subscripts.
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+use gpui::{App, ClipboardItem, Window};
+use ui::prelude::*;
+
+use crate::outputs::OutputContent;
+
+pub struct LatexView {
+    raw_text: String,
+    text: SharedString,
+}
+
+impl LatexView {
+    pub fn new(latex: String) -> Self {
+        Self {
+            text: latex_to_unicode(&latex).into(),
+            raw_text: latex,
+        }
+    }
+}
+
+impl OutputContent for LatexView {
+    fn clipboard_content(&self, _window: &Window, _cx: &App) -> Option<ClipboardItem> {
+        Some(ClipboardItem::new_string(self.raw_text.clone()))
+    }
+
+    fn has_clipboard_content(&self, _window: &Window, _cx: &App) -> bool {
+        true
+    }
+}
+
+impl Render for LatexView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        div().py_1().font_buffer(cx).child(self.text.clone())
+    }
+}
+
+/// Converts LaTeX math to its closest plain Unicode representation.
+pub fn latex_to_unicode(latex: &str) -> String {
+    let source = strip_math_delimiters(latex.trim());
+    let mut chars = source.chars().peekable();
+    let mut output = String::new();
+    while chars.peek().is_some() {
+        convert_token(&mut chars, &mut output);
+    }
+    // Line breaks only come from `\\`, so whitespace is normalized within each line.
+    output
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn strip_math_delimiters(latex: &str) -> &str {
+    for (start, end) in [("$$", "$$"), ("\\[", "\\]"), ("\\(", "\\)"), ("$", "$")] {
+        if let Some(inner) = latex
+            .strip_prefix(start)
+            .and_then(|rest| rest.strip_suffix(end))
+        {
+            return inner.trim();
+        }
+    }
+    latex
+}
+
+fn convert_token(chars: &mut Peekable<Chars>, output: &mut String) {
+    let Some(c) = chars.next() else {
+        return;
+    };
+
+    match c {
+        '\\' => convert_command(chars, output),
+        '{' => convert_group(chars, output),
+        '}' | '$' => {}
+        '^' => {
+            let argument = read_argument(chars);
+            output.push_str(&script(&argument, '^', superscript));
+        }
+        '_' => {
+            let argument = read_argument(chars);
+            output.push_str(&script(&argument, '_', subscript));
+        }
+        '&' | '~' => output.push(' '),
+        c if c.is_whitespace() => output.push(' '),
+        c => output.push(c),
+    }
+}
+
+/// Converts the contents of a group, whose opening brace has already been consumed.
+fn convert_group(chars: &mut Peekable<Chars>, output: &mut String) {
+    while let Some(&c) = chars.peek() {
+        if c == '}' {
+            chars.next();
+            return;
+        }
+        convert_token(chars, output);
+    }
+}
+
+fn read_argument(chars: &mut Peekable<Chars>) -> String {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+    let mut argument = String::new();
+    if chars.next_if_eq(&'{').is_some() {
+        convert_group(chars, &mut argument);
+    } else {
+        convert_token(chars, &mut argument);
+    }
+    argument
+}
+
+fn convert_command(chars: &mut Peekable<Chars>, output: &mut String) {
+    let mut name = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_ascii_alphabetic()) {
+        name.push(c);
+    }
+
+    if name.is_empty() {
+        match chars.next() {
+            Some('\\') => output.push('\n'),
+            Some(',' | ';' | ':' | '!' | ' ') => output.push(' '),
+            Some(c) => output.push(c),
+            None => {}
+        }
+        return;
+    }
+
+    match name.as_str() {
+        "frac" | "dfrac" | "tfrac" => {
+            let numerator = read_argument(chars);
+            let denominator = read_argument(chars);
+            output.push_str(&parenthesize(&numerator));
+            output.push('/');
+            output.push_str(&parenthesize(&denominator));
+        }
+        "sqrt" => {
+            let radicand = read_argument(chars);
+            output.push('√');
+            output.push_str(&parenthesize(&radicand));
+        }
+        "text" | "mathrm" | "mathbf" | "mathit" | "mathsf" | "mathtt" | "operatorname"
+        | "textrm" | "textbf" | "textit" => {
+            output.push_str(&read_argument(chars));
+        }
+        "displaystyle" | "textstyle" => {}
+        "begin" | "end" => {
+            read_argument(chars);
+        }
+        // Sizing commands only affect how the next delimiter is drawn.
+        "left" | "right" | "big" | "Big" | "bigg" | "Bigg" => {
+            chars.next_if_eq(&'.');
+        }
+        "quad" | "qquad" => output.push(' '),
+        name => match symbol(name) {
+            Some(symbol) => output.push_str(symbol),
+            // Functions such as `\sin` and `\log` are written as their names.
+            None => output.push_str(name),
+        },
+    }
+}
+
+fn parenthesize(text: &str) -> String {
+    if text.chars().count() > 1 {
+        format!("({text})")
+    } else {
+        text.to_string()
+    }
+}
+
+/// Writes a superscript or subscript with Unicode characters, falling back to its
+/// LaTeX notation when some of its characters have no Unicode equivalent.
+fn script(text: &str, marker: char, map: fn(char) -> Option<char>) -> String {
+    match text.chars().map(map).collect::<Option<String>>() {
+        Some(mapped) if !mapped.is_empty() => mapped,
+        _ => format!("{marker}{}", parenthesize(text)),
+    }
+}
+
+fn superscript(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '⁰',
+        '1' => '¹',
+        '2' => '²',
+        '3' => '³',
+        '4' => '⁴',
+        '5' => '⁵',
+        '6' => '⁶',
+        '7' => '⁷',
+        '8' => '⁸',
+        '9' => '⁹',
+        '+' => '⁺',
+        '-' | '−' => '⁻',
+        '=' => '⁼',
+        '(' => '⁽',
+        ')' => '⁾',
+        'i' => 'ⁱ',
+        'n' => 'ⁿ',
+        'T' => 'ᵀ',
+        _ => return None,
+    })
+}
+
+fn subscript(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '₀',
+        '1' => '₁',
+        '2' => '₂',
+        '3' => '₃',
+        '4' => '₄',
+        '5' => '₅',
+        '6' => '₆',
+        '7' => '₇',
+        '8' => '₈',
+        '9' => '₉',
+        '+' => '₊',
+        '-' | '−' => '₋',
+        '=' => '₌',
+        '(' => '₍',
+        ')' => '₎',
+        'a' => 'ₐ',
+        'e' => 'ₑ',
+        'i' => 'ᵢ',
+        'j' => 'ⱼ',
+        'k' => 'ₖ',
+        'n' => 'ₙ',
+        'x' => 'ₓ',
+        _ => return None,
+    })
+}
+
+fn symbol(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "alpha" => "α",
+        "beta" => "β",
+        "gamma" => "γ",
+        "delta" => "δ",
+        "epsilon" | "varepsilon" => "ε",
+        "zeta" => "ζ",
+        "eta" => "η",
+        "theta" | "vartheta" => "θ",
+        "iota" => "ι",
+        "kappa" => "κ",
+        "lambda" => "λ",
+        "mu" => "μ",
+        "nu" => "ν",
+        "xi" => "ξ",
+        "pi" => "π",
+        "rho" => "ρ",
+        "sigma" => "σ",
+        "tau" => "τ",
+        "upsilon" => "υ",
+        "phi" | "varphi" => "φ",
+        "chi" => "χ",
+        "psi" => "ψ",
+        "omega" => "ω",
+        "Gamma" => "Γ",
+        "Delta" => "Δ",
+        "Theta" => "Θ",
+        "Lambda" => "Λ",
+        "Xi" => "Ξ",
+        "Pi" => "Π",
+        "Sigma" => "Σ",
+        "Phi" => "Φ",
+        "Psi" => "Ψ",
+        "Omega" => "Ω",
+        "times" => "×",
+        "cdot" => "·",
+        "div" => "÷",
+        "pm" => "±",
+        "mp" => "∓",
+        "leq" | "le" => "≤",
+        "geq" | "ge" => "≥",
+        "neq" | "ne" => "≠",
+        "approx" => "≈",
+        "equiv" => "≡",
+        "sim" => "∼",
+        "propto" => "∝",
+        "infty" => "∞",
+        "partial" => "∂",
+        "nabla" => "∇",
+        "sum" => "∑",
+        "prod" => "∏",
+        "int" => "∫",
+        "oint" => "∮",
+        "in" => "∈",
+        "notin" => "∉",
+        "subset" => "⊂",
+        "subseteq" => "⊆",
+        "cup" => "∪",
+        "cap" => "∩",
+        "emptyset" => "∅",
+        "forall" => "∀",
+        "exists" => "∃",
+        "neg" => "¬",
+        "wedge" | "land" => "∧",
+        "vee" | "lor" => "∨",
+        "to" | "rightarrow" => "→",
+        "leftarrow" => "←",
+        "Rightarrow" => "⇒",
+        "Leftarrow" => "⇐",
+        "leftrightarrow" => "↔",
+        "Leftrightarrow" | "iff" => "⇔",
+        "mapsto" => "↦",
+        "ldots" | "dots" => "…",
+        "cdots" => "⋯",
+        "langle" => "⟨",
+        "rangle" => "⟩",
+        "lfloor" => "⌊",
+        "rfloor" => "⌋",
+        "lceil" => "⌈",
+        "rceil" => "⌉",
+        "hbar" => "ℏ",
+        "ell" => "ℓ",
+        "circ" => "∘",
+        "degree" => "°",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latex_to_unicode() {
+        assert_eq!(latex_to_unicode(r"$\alpha \leq \beta$"), "α ≤ β");
+        assert_eq!(latex_to_unicode(r"$$x^2 + y_{10}$$"), "x² + y₁₀");
+        assert_eq!(latex_to_unicode(r"\frac{a + 1}{b}"), "(a + 1)/b");
+        assert_eq!(latex_to_unicode(r"\sqrt{x}"), "√x");
+        assert_eq!(latex_to_unicode(r"e^{i \pi}"), "e^(i π)");
+        assert_eq!(
+            latex_to_unicode(r"\left( \sin{\left(x \right)} \right)"),
+            "( sin(x ) )"
+        );
+        assert_eq!(
+            latex_to_unicode(r"$\displaystyle \sum_{n=0}^{\infty} \frac{1}{n!}$"),
+            "∑ₙ₌₀^∞ 1/(n!)"
+        );
+    }
+
+    #[test]
+    fn test_latex_line_breaks() {
+        assert_eq!(latex_to_unicode(r"a = 1 \\ b = 2"), "a = 1\nb = 2");
+        assert_eq!(latex_to_unicode("$x +\n  y \\\\$"), "x + y");
+        assert_eq!(
+            latex_to_unicode(r"\begin{aligned} x &= 1 \\ y &= \alpha \end{aligned}"),
+            "x = 1\ny = α"
+        );
+    }
+}
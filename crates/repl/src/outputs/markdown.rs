@@ -1,5 +1,9 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
 use anyhow::Result;
 use gpui::{div, prelude::*, App, ClipboardItem, Context, Entity, Task, Window};
+use html_to_markdown::{convert_html_to_markdown, markdown, TagHandler};
 use language::Buffer;
 use markdown_preview::{
     markdown_elements::ParsedMarkdown, markdown_parser::parse_markdown,
@@ -41,6 +45,19 @@ impl MarkdownView {
     }
 }
 
+/// Reduces `text/html` output, such as a pandas DataFrame, to Markdown that can be rendered.
+pub fn html_to_markdown(html: &str) -> Result<String> {
+    let mut handlers: Vec<TagHandler> = vec![
+        Rc::new(RefCell::new(markdown::ParagraphHandler)),
+        Rc::new(RefCell::new(markdown::HeadingHandler)),
+        Rc::new(RefCell::new(markdown::ListHandler)),
+        Rc::new(RefCell::new(markdown::TableHandler::new())),
+        Rc::new(RefCell::new(markdown::StyledTextHandler)),
+        Rc::new(RefCell::new(markdown::CodeHandler)),
+    ];
+    convert_html_to_markdown(html.as_bytes(), &mut handlers)
+}
+
 impl OutputContent for MarkdownView {
     fn clipboard_content(&self, _window: &Window, _cx: &App) -> Option<ClipboardItem> {
         Some(ClipboardItem::new_string(self.raw_text.clone()))
@@ -88,3 +105,32 @@ impl Render for MarkdownView {
             .into_any_element()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn test_html_table_to_markdown() {
+        let html = indoc! {r#"
+            <div>
+            <table border="1" class="dataframe">
+              <thead>
+                <tr style="text-align: right;"><th></th><th>name</th><th>age</th></tr>
+              </thead>
+              <tbody>
+                <tr><th>0</th><td>Alice</td><td>30</td></tr>
+                <tr><th>1</th><td>Bob</td><td>28</td></tr>
+              </tbody>
+            </table>
+            </div>
+        "#};
+
+        let markdown = html_to_markdown(html).unwrap();
+        assert!(markdown.contains("| name | age |"), "{markdown}");
+        assert!(markdown.contains("| --- | --- | --- |"), "{markdown}");
+        assert!(markdown.contains("Alice | 30 |"), "{markdown}");
+        assert!(markdown.contains("Bob | 28 |"), "{markdown}");
+    }
+}
@@ -1,4 +1,5 @@
 pub mod components;
+mod evaluations;
 mod jupyter_settings;
 pub mod kernels;
 pub mod notebook;
@@ -7,6 +8,7 @@ mod repl_editor;
 mod repl_sessions_ui;
 mod repl_store;
 mod session;
+mod variable_explorer;
 
 use std::{sync::Arc, time::Duration};
 
@@ -16,7 +18,7 @@ use project::Fs;
 pub use runtimelib::ExecutionState;
 use settings::Settings as _;
 
-pub use crate::jupyter_settings::JupyterSettings;
+pub use crate::jupyter_settings::{JupyterServerSettings, JupyterSettings};
 pub use crate::kernels::{Kernel, KernelSpecification, KernelStatus};
 pub use crate::repl_editor::*;
 pub use crate::repl_sessions_ui::{
@@ -33,6 +35,7 @@ pub fn init(fs: Arc<dyn Fs>, cx: &mut App) {
     ::editor::init_settings(cx);
     repl_sessions_ui::init(cx);
     ReplStore::init(fs, cx);
+    variable_explorer::init(cx);
 }
 
 fn zed_dispatcher(cx: &mut App) -> impl Dispatcher {
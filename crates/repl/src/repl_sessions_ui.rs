@@ -4,14 +4,15 @@ use gpui::{
     Subscription,
 };
 use project::ProjectItem as _;
-use ui::{prelude::*, ButtonLike, ElevationIndex, KeyBinding};
+use ui::{prelude::*, ButtonLike, ElevationIndex, KeyBinding, Tooltip};
 use util::ResultExt as _;
 use workspace::item::ItemEvent;
 use workspace::WorkspaceId;
 use workspace::{item::Item, Workspace};
 
+use crate::components::JupyterServerModal;
 use crate::jupyter_settings::JupyterSettings;
-use crate::repl_store::ReplStore;
+use crate::repl_store::{RemoteServerState, RemoteServerStatus, ReplStore};
 
 actions!(
     repl,
@@ -23,7 +24,8 @@ actions!(
         Interrupt,
         Shutdown,
         Restart,
-        RefreshKernelspecs
+        RefreshKernelspecs,
+        ConnectToJupyterServer
     ]
 );
 
@@ -57,6 +59,10 @@ pub fn init(cx: &mut App) {
                     store.refresh_kernelspecs(cx).detach();
                 });
             });
+
+            workspace.register_action(|workspace, _: &ConnectToJupyterServer, window, cx| {
+                workspace.toggle_modal(window, cx, JupyterServerModal::new);
+            });
         },
     )
     .detach();
@@ -208,15 +214,17 @@ impl Render for ReplSessionsPage {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let store = ReplStore::global(cx);
 
-        let (kernel_specifications, sessions) = store.update(cx, |store, _cx| {
+        let (kernel_specifications, sessions, remote_servers) = store.update(cx, |store, _cx| {
             (
                 store
                     .pure_jupyter_kernel_specifications()
                     .cloned()
                     .collect::<Vec<_>>(),
                 store.sessions().cloned().collect::<Vec<_>>(),
+                store.remote_servers().to_vec(),
             )
         });
+        let remote_servers = render_remote_servers(remote_servers);
 
         // When there are no kernel specifications, show a link to the Zed docs explaining how to
         // install kernels. It can be assumed they don't have a running kernel if we have no
@@ -239,24 +247,85 @@ impl Render for ReplSessionsPage {
                                 )
                             }),
                     ),
-                );
+                )
+                .child(remote_servers);
         }
 
         // When there are no sessions, show the command to run code in an editor
         if sessions.is_empty() {
             let instructions = "To run code in a Jupyter kernel, select some code and use the 'repl::Run' command.";
 
-            return ReplSessionsContainer::new("No Jupyter Kernel Sessions").child(
-                v_flex()
-                    .child(Label::new(instructions))
-                    .children(KeyBinding::for_action(&Run, window, cx)),
-            );
+            return ReplSessionsContainer::new("No Jupyter Kernel Sessions")
+                .child(
+                    v_flex()
+                        .child(Label::new(instructions))
+                        .children(KeyBinding::for_action(&Run, window, cx)),
+                )
+                .child(remote_servers);
         }
 
-        ReplSessionsContainer::new("Jupyter Kernel Sessions").children(sessions)
+        ReplSessionsContainer::new("Jupyter Kernel Sessions")
+            .children(sessions)
+            .child(remote_servers)
     }
 }
 
+fn render_remote_servers(remote_servers: Vec<RemoteServerState>) -> impl IntoElement {
+    let header = h_flex()
+        .pt_4()
+        .justify_between()
+        .child(Label::new("Jupyter Servers").size(LabelSize::Large))
+        .child(
+            h_flex()
+                .gap_1()
+                .child(
+                    Button::new("connect-jupyter-server", "Connect to Server")
+                        .icon(IconName::Plus)
+                        .icon_position(IconPosition::Start)
+                        .icon_size(IconSize::Small)
+                        .on_click(|_, window, cx| {
+                            window.dispatch_action(Box::new(ConnectToJupyterServer), cx)
+                        }),
+                )
+                .child(
+                    IconButton::new("refresh-jupyter-servers", IconName::RotateCw)
+                        .icon_size(IconSize::Small)
+                        .tooltip(Tooltip::text("Refresh Kernels"))
+                        .on_click(|_, window, cx| {
+                            window.dispatch_action(Box::new(RefreshKernelspecs), cx)
+                        }),
+                ),
+        );
+
+    let servers = remote_servers.into_iter().map(|server| {
+        let (status, color) = match server.status {
+            RemoteServerStatus::Connecting => ("Connecting…".into(), Color::Muted),
+            RemoteServerStatus::Connected {
+                kernelspec_count,
+                running_kernel_count,
+            } => (
+                SharedString::from(format!(
+                    "{kernelspec_count} kernels available, {running_kernel_count} running"
+                )),
+                Color::Success,
+            ),
+            RemoteServerStatus::Error(error) => (error, Color::Error),
+        };
+
+        v_flex()
+            .gap_0p5()
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(Label::new(server.name))
+                    .child(Label::new(server.url).color(Color::Muted)),
+            )
+            .child(Label::new(status).size(LabelSize::Small).color(color))
+    });
+
+    v_flex().gap_2().child(header).children(servers)
+}
+
 #[derive(IntoElement)]
 struct ReplSessionsContainer {
     title: SharedString,
@@ -3,19 +3,41 @@ use std::sync::Arc;
 use anyhow::Result;
 use collections::HashMap;
 use command_palette_hooks::CommandPaletteFilter;
-use gpui::{prelude::*, App, Context, Entity, EntityId, Global, Subscription, Task};
-use jupyter_websocket_client::RemoteServer;
+use gpui::{prelude::*, App, Context, Entity, EntityId, Global, SharedString, Subscription, Task};
 use language::Language;
 use project::{Fs, Project, WorktreeId};
 use settings::{Settings, SettingsStore};
+use util::ResultExt as _;
 
 use crate::kernels::{
-    list_remote_kernelspecs, local_kernel_specifications, python_env_kernel_specifications,
+    list_remote_kernel_specifications, local_kernel_specifications,
+    python_env_kernel_specifications,
 };
-use crate::{JupyterSettings, KernelSpecification, Session};
+use crate::{JupyterServerSettings, JupyterSettings, KernelSpecification, Session};
 
 struct GlobalReplStore(Entity<ReplStore>);
 
+/// The name given to the server configured through the `JUPYTER_SERVER` environment variable.
+const ENV_SERVER_NAME: &str = "JUPYTER_SERVER";
+
+#[derive(Debug, Clone)]
+pub enum RemoteServerStatus {
+    Connecting,
+    Connected {
+        kernelspec_count: usize,
+        running_kernel_count: usize,
+    },
+    Error(SharedString),
+}
+
+/// A configured Jupyter server and whether its kernels could be listed.
+#[derive(Debug, Clone)]
+pub struct RemoteServerState {
+    pub name: SharedString,
+    pub url: SharedString,
+    pub status: RemoteServerStatus,
+}
+
 impl Global for GlobalReplStore {}
 
 pub struct ReplStore {
@@ -25,6 +47,8 @@ pub struct ReplStore {
     kernel_specifications: Vec<KernelSpecification>,
     selected_kernel_for_worktree: HashMap<WorktreeId, KernelSpecification>,
     kernel_specifications_for_worktree: HashMap<WorktreeId, Vec<KernelSpecification>>,
+    server_settings: Vec<(String, JupyterServerSettings)>,
+    remote_servers: Vec<RemoteServerState>,
     _subscriptions: Vec<Subscription>,
 }
 
@@ -48,6 +72,10 @@ impl ReplStore {
     pub fn new(fs: Arc<dyn Fs>, cx: &mut Context<Self>) -> Self {
         let subscriptions = vec![cx.observe_global::<SettingsStore>(move |this, cx| {
             this.set_enabled(JupyterSettings::enabled(cx), cx);
+
+            if this.server_settings != Self::configured_servers(cx) {
+                this.refresh_kernelspecs(cx).detach_and_log_err(cx);
+            }
         })];
 
         let this = Self {
@@ -58,6 +86,8 @@ impl ReplStore {
             _subscriptions: subscriptions,
             kernel_specifications_for_worktree: HashMap::default(),
             selected_kernel_for_worktree: HashMap::default(),
+            server_settings: Vec::new(),
+            remote_servers: Vec::new(),
         };
         this.on_enabled_changed(cx);
         this
@@ -90,6 +120,10 @@ impl ReplStore {
         self.sessions.values()
     }
 
+    pub fn remote_servers(&self) -> &[RemoteServerState] {
+        &self.remote_servers
+    }
+
     fn set_enabled(&mut self, enabled: bool, cx: &mut Context<Self>) {
         if self.enabled == enabled {
             return;
@@ -135,63 +169,109 @@ impl ReplStore {
         })
     }
 
-    fn get_remote_kernel_specifications(
-        &self,
-        cx: &mut Context<Self>,
-    ) -> Option<Task<Result<Vec<KernelSpecification>>>> {
-        match (
-            std::env::var("JUPYTER_SERVER"),
-            std::env::var("JUPYTER_TOKEN"),
-        ) {
-            (Ok(server), Ok(token)) => {
-                let remote_server = RemoteServer {
-                    base_url: server,
-                    token,
-                };
-                let http_client = cx.http_client();
-                Some(cx.spawn(|_, _| async move {
-                    list_remote_kernelspecs(remote_server, http_client)
-                        .await
-                        .map(|specs| specs.into_iter().map(KernelSpecification::Remote).collect())
-                }))
-            }
-            _ => None,
+    /// The Jupyter servers from the settings, along with the one named by the
+    /// `JUPYTER_SERVER` environment variable, sorted by name.
+    fn configured_servers(cx: &App) -> Vec<(String, JupyterServerSettings)> {
+        let mut servers = JupyterSettings::get_global(cx)
+            .servers
+            .iter()
+            .map(|(name, server)| (name.clone(), server.clone()))
+            .collect::<Vec<_>>();
+        servers.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        if let Ok(url) = std::env::var("JUPYTER_SERVER") {
+            servers.push((
+                ENV_SERVER_NAME.to_string(),
+                JupyterServerSettings { url, token: None },
+            ));
         }
+
+        servers
     }
 
     pub fn refresh_kernelspecs(&mut self, cx: &mut Context<Self>) -> Task<Result<()>> {
         let local_kernel_specifications = local_kernel_specifications(self.fs.clone());
 
-        let remote_kernel_specifications = self.get_remote_kernel_specifications(cx);
-
-        let all_specs = cx.background_spawn(async move {
-            let mut all_specs = local_kernel_specifications
-                .await?
-                .into_iter()
-                .map(KernelSpecification::Jupyter)
-                .collect::<Vec<_>>();
-
-            if let Some(remote_task) = remote_kernel_specifications {
-                if let Ok(remote_specs) = remote_task.await {
-                    all_specs.extend(remote_specs);
-                }
-            }
+        let servers = Self::configured_servers(cx);
+        self.remote_servers = servers
+            .iter()
+            .map(|(name, server)| RemoteServerState {
+                name: name.clone().into(),
+                url: server.url.clone().into(),
+                status: RemoteServerStatus::Connecting,
+            })
+            .collect();
+        self.server_settings = servers.clone();
+        cx.notify();
 
-            anyhow::Ok(all_specs)
+        let http_client = cx.http_client();
+        let stored_tokens = servers
+            .iter()
+            .map(|(_, server)| cx.read_credentials(&server.url))
+            .collect::<Vec<_>>();
+        let remote_kernel_specifications = cx.background_spawn(async move {
+            futures::future::join_all(servers.into_iter().zip(stored_tokens).map(
+                |((name, server), stored_token)| {
+                    let http_client = http_client.clone();
+                    async move {
+                        let stored_token = stored_token
+                            .await
+                            .log_err()
+                            .flatten()
+                            .and_then(|(_, token)| String::from_utf8(token).log_err());
+                        let specs = list_remote_kernel_specifications(
+                            server.remote_server(stored_token),
+                            http_client,
+                        )
+                        .await;
+                        (name, specs)
+                    }
+                },
+            ))
+            .await
         });
 
         cx.spawn(|this, mut cx| async move {
-            let all_specs = all_specs.await;
-
-            if let Ok(specs) = all_specs {
-                this.update(&mut cx, |this, cx| {
-                    this.kernel_specifications = specs;
-                    cx.notify();
-                })
-                .ok();
-            }
+            let local_specs = local_kernel_specifications.await.log_err();
+            let remote_results = remote_kernel_specifications.await;
+
+            this.update(&mut cx, |this, cx| {
+                let mut all_specs = local_specs
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(KernelSpecification::Jupyter)
+                    .collect::<Vec<_>>();
+
+                for (name, result) in remote_results {
+                    let status = match result {
+                        Ok(specs) => {
+                            let running_kernel_count =
+                                specs.iter().filter(|spec| spec.kernel_id.is_some()).count();
+                            let status = RemoteServerStatus::Connected {
+                                kernelspec_count: specs.len() - running_kernel_count,
+                                running_kernel_count,
+                            };
+                            all_specs.extend(specs.into_iter().map(KernelSpecification::Remote));
+                            status
+                        }
+                        Err(error) => {
+                            log::error!("Failed to connect to Jupyter server {name}: {error:#}");
+                            RemoteServerStatus::Error(format!("{error:#}").into())
+                        }
+                    };
+
+                    if let Some(server) = this
+                        .remote_servers
+                        .iter_mut()
+                        .find(|server| server.name.as_ref() == name)
+                    {
+                        server.status = status;
+                    }
+                }
 
-            anyhow::Ok(())
+                this.kernel_specifications = all_specs;
+                cx.notify();
+            })
         })
     }
 
@@ -272,11 +352,18 @@ impl ReplStore {
         self.sessions.get(&entity_id)
     }
 
-    pub fn insert_session(&mut self, entity_id: EntityId, session: Entity<Session>) {
+    pub fn insert_session(
+        &mut self,
+        entity_id: EntityId,
+        session: Entity<Session>,
+        cx: &mut Context<Self>,
+    ) {
         self.sessions.insert(entity_id, session);
+        cx.notify();
     }
 
-    pub fn remove_session(&mut self, entity_id: EntityId) {
+    pub fn remove_session(&mut self, entity_id: EntityId, cx: &mut Context<Self>) {
         self.sessions.remove(&entity_id);
+        cx.notify();
     }
 }
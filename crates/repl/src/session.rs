@@ -2,9 +2,13 @@ use crate::components::KernelListItem;
 use crate::kernels::RemoteRunningKernel;
 use crate::setup_editor_session_actions;
 use crate::{
-    kernels::{Kernel, KernelSpecification, NativeRunningKernel},
-    outputs::{ExecutionStatus, ExecutionView},
-    KernelStatus,
+    evaluations::Evaluations,
+    kernels::{Kernel, KernelSession, KernelSpecification, NativeRunningKernel},
+    outputs::{ExecutionStatus, ExecutionView, TableView},
+    variable_explorer::{
+        parse_variables, table_preview_expression, variables_expression, Variable, VariablePreview,
+    },
+    JupyterSettings, KernelStatus,
 };
 use collections::{HashMap, HashSet};
 use editor::{
@@ -13,18 +17,19 @@ use editor::{
         RenderBlock,
     },
     scroll::Autoscroll,
-    Anchor, AnchorRangeExt as _, Editor, MultiBuffer, ToPoint,
+    Anchor, AnchorRangeExt as _, Editor, InlayId, MultiBuffer, ToPoint,
 };
 use futures::FutureExt as _;
 use gpui::{
     div, prelude::*, Context, Entity, EventEmitter, Render, Subscription, Task, WeakEntity, Window,
 };
 use language::Point;
-use project::Fs;
+use project::{Fs, InlineValue, InlineValueKind};
 use runtimelib::{
-    ExecuteRequest, ExecutionState, InterruptRequest, JupyterMessage, JupyterMessageContent,
-    ShutdownRequest,
+    media::datatable::TabularDataResource, ExecuteRequest, ExecutionState, InterruptRequest,
+    JupyterMessage, JupyterMessageContent, ReplyStatus, ShutdownRequest,
 };
+use settings::Settings as _;
 use std::{env::temp_dir, ops::Range, sync::Arc, time::Duration};
 use theme::ActiveTheme;
 use ui::{prelude::*, IconButtonShape, Tooltip};
@@ -35,6 +40,16 @@ pub struct Session {
     editor: WeakEntity<Editor>,
     pub kernel: Kernel,
     blocks: HashMap<String, EditorBlock>,
+    /// Expressions the kernel is evaluating for inline values and the variable explorer.
+    evaluations: Evaluations<Evaluation>,
+    /// The variables in the kernel's namespace, as of the last time they were listed.
+    variables: Vec<Variable>,
+    variables_error: Option<SharedString>,
+    /// Previews of table variables, keyed by the variable's name.
+    variable_previews: HashMap<String, VariablePreview>,
+    /// Whether the variable explorer is showing this session's variables. They're only
+    /// refreshed after executions while it is.
+    variables_shown: bool,
     pub kernel_specification: KernelSpecification,
     _buffer_subscription: Subscription,
 }
@@ -44,6 +59,20 @@ struct EditorBlock {
     invalidation_anchor: Anchor,
     block_id: CustomBlockId,
     execution_view: Entity<ExecutionView>,
+    inline_value_ids: Vec<InlayId>,
+}
+
+#[derive(PartialEq)]
+enum Evaluation {
+    /// A value shown inline after the code of an execution.
+    InlineValue {
+        execution_id: String,
+        position: Anchor,
+    },
+    /// The listing of the kernel's variables.
+    Variables,
+    /// The preview of the table variable with the given name.
+    VariablePreview(String),
 }
 
 type CloseBlockFn =
@@ -104,6 +133,7 @@ impl EditorBlock {
             invalidation_anchor,
             block_id,
             execution_view,
+            inline_value_ids: Vec::new(),
         })
     }
 
@@ -221,6 +251,11 @@ impl Session {
             editor,
             kernel: Kernel::StartingKernel(Task::ready(()).shared()),
             blocks: HashMap::default(),
+            evaluations: Evaluations::default(),
+            variables: Vec::new(),
+            variables_error: None,
+            variable_previews: HashMap::default(),
+            variables_shown: false,
             kernel_specification,
             _buffer_subscription: subscription,
         };
@@ -292,26 +327,6 @@ impl Session {
         cx.notify();
     }
 
-    pub fn kernel_errored(&mut self, error_message: String, cx: &mut Context<Self>) {
-        self.kernel(Kernel::ErroredLaunch(error_message.clone()), cx);
-
-        self.blocks.values().for_each(|block| {
-            block.execution_view.update(cx, |execution_view, cx| {
-                match execution_view.status {
-                    ExecutionStatus::Finished => {
-                        // Do nothing when the output was good
-                    }
-                    _ => {
-                        // All other cases, set the status to errored
-                        execution_view.status =
-                            ExecutionStatus::KernelErrored(error_message.clone())
-                    }
-                }
-                cx.notify();
-            });
-        });
-    }
-
     fn on_buffer_event(
         &mut self,
         buffer: Entity<MultiBuffer>,
@@ -322,12 +337,14 @@ impl Session {
             let snapshot = buffer.read(cx).snapshot(cx);
 
             let mut blocks_to_remove: HashSet<CustomBlockId> = HashSet::default();
+            let mut inline_values_to_remove = Vec::new();
 
             self.blocks.retain(|_id, block| {
                 if block.invalidation_anchor.is_valid(&snapshot) {
                     true
                 } else {
                     blocks_to_remove.insert(block.block_id);
+                    inline_values_to_remove.extend(block.inline_value_ids.drain(..));
                     false
                 }
             });
@@ -336,6 +353,7 @@ impl Session {
                 self.editor
                     .update(cx, |editor, cx| {
                         editor.remove_blocks(blocks_to_remove, None, cx);
+                        editor.remove_inline_values(&inline_values_to_remove, cx);
                     })
                     .ok();
                 cx.notify();
@@ -354,14 +372,22 @@ impl Session {
     pub fn clear_outputs(&mut self, cx: &mut Context<Self>) {
         let blocks_to_remove: HashSet<CustomBlockId> =
             self.blocks.values().map(|block| block.block_id).collect();
+        let inline_values_to_remove = self
+            .blocks
+            .values()
+            .flat_map(|block| block.inline_value_ids.iter().copied())
+            .collect::<Vec<_>>();
 
         self.editor
             .update(cx, |editor, cx| {
                 editor.remove_blocks(blocks_to_remove, None, cx);
+                editor.remove_inline_values(&inline_values_to_remove, cx);
             })
             .ok();
 
         self.blocks.clear();
+        self.evaluations
+            .retain(|evaluation| !matches!(evaluation, Evaluation::InlineValue { .. }));
     }
 
     pub fn execute(
@@ -389,12 +415,14 @@ impl Session {
         let message: JupyterMessage = execute_request.into();
 
         let mut blocks_to_remove: HashSet<CustomBlockId> = HashSet::default();
+        let mut inline_values_to_remove = Vec::new();
 
         let buffer = editor.read(cx).buffer().read(cx).snapshot(cx);
 
         self.blocks.retain(|_key, block| {
             if anchor_range.overlaps(&block.code_range, &buffer) {
                 blocks_to_remove.insert(block.block_id);
+                inline_values_to_remove.extend(block.inline_value_ids.drain(..));
                 false
             } else {
                 true
@@ -404,6 +432,7 @@ impl Session {
         self.editor
             .update(cx, |editor, cx| {
                 editor.remove_blocks(blocks_to_remove, None, cx);
+                editor.remove_inline_values(&inline_values_to_remove, cx);
             })
             .ok();
 
@@ -422,18 +451,23 @@ impl Session {
 
         let on_close: CloseBlockFn = Arc::new(
             move |block_id: CustomBlockId, _: &mut Window, cx: &mut App| {
-                if let Some(session) = session_view.upgrade() {
-                    session.update(cx, |session, cx| {
-                        session.blocks.remove(&parent_message_id);
-                        cx.notify();
-                    });
-                }
+                let inline_value_ids = session_view
+                    .upgrade()
+                    .and_then(|session| {
+                        session.update(cx, |session, cx| {
+                            cx.notify();
+                            session.blocks.remove(&parent_message_id)
+                        })
+                    })
+                    .map(|block| block.inline_value_ids)
+                    .unwrap_or_default();
 
                 if let Some(editor) = weak_editor.upgrade() {
                     editor.update(cx, |editor, cx| {
                         let mut block_ids = HashSet::default();
                         block_ids.insert(block_id);
                         editor.remove_blocks(block_ids, None, cx);
+                        editor.remove_inline_values(&inline_value_ids, cx);
                     });
                 }
             },
@@ -489,49 +523,262 @@ impl Session {
         }
     }
 
-    pub fn route(&mut self, message: &JupyterMessage, window: &mut Window, cx: &mut Context<Self>) {
-        let parent_message_id = match message.parent_header.as_ref() {
-            Some(header) => &header.msg_id,
-            None => return,
+    /// Asks the language server which values in the executed code can be shown inline, so
+    /// that they can be evaluated against the kernel's state after the execution.
+    fn request_inline_values(&mut self, execution_id: String, cx: &mut Context<Self>) {
+        let Some(editor) = self.editor.upgrade() else {
+            return;
+        };
+        let Some(block) = self.blocks.get(&execution_id) else {
+            return;
+        };
+        let code_range = block.code_range.start.text_anchor..block.code_range.end.text_anchor;
+        let (provider, buffer) = {
+            let editor = editor.read(cx);
+            (
+                editor.semantics_provider(),
+                editor.buffer().read(cx).as_singleton(),
+            )
+        };
+        let (Some(provider), Some(buffer)) = (provider, buffer) else {
+            return;
+        };
+        let Some(inline_values) = provider.inline_values(&buffer, code_range, cx) else {
+            return;
         };
 
-        match &message.content {
-            JupyterMessageContent::Status(status) => {
-                self.kernel.set_execution_state(&status.execution_state);
+        cx.spawn(|this, mut cx| async move {
+            let inline_values = inline_values.await?;
+            this.update(&mut cx, |session, cx| {
+                session.evaluate_inline_values(execution_id, inline_values, cx);
+            })
+        })
+        .detach_and_log_err(cx);
+    }
 
-                telemetry::event!(
-                    "Kernel Status Changed",
-                    kernel_language = self.kernel_specification.language(),
-                    kernel_status = KernelStatus::from(&self.kernel).to_string(),
-                    repl_session_id = cx.entity_id().to_string(),
-                );
+    fn evaluate_inline_values(
+        &mut self,
+        execution_id: String,
+        inline_values: Vec<InlineValue>,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(editor) = self.editor.upgrade() else {
+            return;
+        };
+        if inline_values.is_empty() || !self.blocks.contains_key(&execution_id) {
+            return;
+        }
 
-                cx.notify();
-            }
-            JupyterMessageContent::KernelInfoReply(reply) => {
-                self.kernel.set_kernel_info(reply);
-                cx.notify();
+        let snapshot = editor.read(cx).buffer().read(cx).snapshot(cx);
+        let Some((&excerpt_id, _, buffer_snapshot)) = snapshot.as_singleton() else {
+            return;
+        };
+
+        let mut texts = Vec::new();
+        let mut expressions = Vec::new();
+        for inline_value in inline_values {
+            let Some(position) = snapshot.anchor_in_excerpt(excerpt_id, inline_value.range.end)
+            else {
+                continue;
+            };
+            let expression = match inline_value.kind {
+                InlineValueKind::Text(text) => {
+                    texts.push((position, text));
+                    continue;
+                }
+                InlineValueKind::VariableLookup {
+                    variable_name: expression,
+                    ..
+                }
+                | InlineValueKind::EvaluatableExpression { expression } => expression
+                    .unwrap_or_else(|| {
+                        buffer_snapshot
+                            .text_for_range(inline_value.range.clone())
+                            .collect()
+                    }),
+            };
+            expressions.push((position, expression));
+        }
+
+        if !texts.is_empty() {
+            let ids = editor.update(cx, |editor, cx| editor.insert_inline_values(texts, cx));
+            if let Some(block) = self.blocks.get_mut(&execution_id) {
+                block.inline_value_ids.extend(ids);
             }
-            JupyterMessageContent::UpdateDisplayData(update) => {
-                let display_id = if let Some(display_id) = update.transient.display_id.clone() {
-                    display_id
-                } else {
-                    return;
-                };
+        }
 
-                self.blocks.iter_mut().for_each(|(_, block)| {
-                    block.execution_view.update(cx, |execution_view, cx| {
-                        execution_view.update_display_data(&update.data, &display_id, window, cx);
-                    });
+        if expressions.is_empty()
+            || !matches!(self.kernel, Kernel::RunningKernel(_))
+            || !JupyterSettings::get_global(cx).evaluate_inline_values
+        {
+            return;
+        }
+        for (position, expression) in expressions {
+            let message = self.evaluations.request(
+                expression,
+                Evaluation::InlineValue {
+                    execution_id: execution_id.clone(),
+                    position,
+                },
+            );
+            self.send(message, cx).ok();
+        }
+    }
+
+    fn show_evaluated_inline_value(
+        &mut self,
+        execution_id: String,
+        position: Anchor,
+        value: Option<String>,
+        cx: &mut Context<Self>,
+    ) {
+        let (Some(block), Some(value)) = (self.blocks.get_mut(&execution_id), value) else {
+            return;
+        };
+        let value = value.lines().next().unwrap_or_default();
+        let text = format!("= {}", util::truncate_and_trailoff(value, 60));
+        let ids = self
+            .editor
+            .update(cx, |editor, cx| {
+                editor.insert_inline_values(vec![(position, text)], cx)
+            })
+            .unwrap_or_default();
+        block.inline_value_ids.extend(ids);
+    }
+
+    pub fn supports_variables(&self) -> bool {
+        variables_expression(&self.kernel_specification.language()).is_some()
+    }
+
+    pub fn variables(&self) -> &[Variable] {
+        &self.variables
+    }
+
+    pub fn variables_error(&self) -> Option<&SharedString> {
+        self.variables_error.as_ref()
+    }
+
+    pub fn is_loading_variables(&self) -> bool {
+        self.evaluations
+            .any(|evaluation| *evaluation == Evaluation::Variables)
+    }
+
+    pub fn variable_preview(&self, name: &str) -> Option<&VariablePreview> {
+        self.variable_previews.get(name)
+    }
+
+    fn clear_variables(&mut self) {
+        self.variables.clear();
+        self.variables_error = None;
+        self.variable_previews.clear();
+        self.evaluations
+            .retain(|evaluation| matches!(evaluation, Evaluation::InlineValue { .. }));
+    }
+
+    /// Sets whether the variable explorer is showing this session's variables, refreshing
+    /// them when it starts to, since executions since then haven't updated them.
+    pub fn set_variables_shown(&mut self, shown: bool, cx: &mut Context<Self>) {
+        if shown && !self.variables_shown {
+            self.refresh_variables(cx);
+        }
+        self.variables_shown = shown;
+    }
+
+    /// Asks the kernel for the variables in its namespace, for the variable explorer.
+    pub fn refresh_variables(&mut self, cx: &mut Context<Self>) {
+        if !matches!(self.kernel, Kernel::RunningKernel(_)) {
+            return;
+        }
+        let Some(expression) = variables_expression(&self.kernel_specification.language()) else {
+            return;
+        };
+
+        let message = self.evaluations.request(expression, Evaluation::Variables);
+        self.send(message, cx).ok();
+
+        // Open previews are refreshed along with the variables, since the execution that
+        // triggered the refresh may have changed them.
+        let names = self.variable_previews.keys().cloned().collect::<Vec<_>>();
+        for name in names {
+            self.request_variable_preview(name, cx);
+        }
+        cx.notify();
+    }
+
+    pub fn toggle_variable_preview(&mut self, name: &str, cx: &mut Context<Self>) {
+        if self.variable_previews.remove(name).is_none() {
+            self.request_variable_preview(name.to_string(), cx);
+        }
+        cx.notify();
+    }
+
+    fn request_variable_preview(&mut self, name: String, cx: &mut Context<Self>) {
+        let language = self.kernel_specification.language();
+        let Some(expression) = table_preview_expression(&language, &name) else {
+            self.variable_previews.insert(
+                name,
+                VariablePreview::Error("This variable can't be previewed".into()),
+            );
+            return;
+        };
+
+        let message = self
+            .evaluations
+            .request(expression, Evaluation::VariablePreview(name.clone()));
+        self.variable_previews
+            .entry(name)
+            .or_insert(VariablePreview::Loading);
+        self.send(message, cx).ok();
+    }
+
+    fn show_variables(&mut self, result: Option<String>, cx: &mut Context<Self>) {
+        match result.as_deref().map(parse_variables) {
+            Some(Ok(variables)) => {
+                self.variable_previews.retain(|name, _| {
+                    variables
+                        .iter()
+                        .any(|variable| variable.name == *name && variable.is_table)
                 });
-                return;
+                self.variables = variables;
+                self.variables_error = None;
+            }
+            Some(Err(error)) => {
+                log::error!("failed to parse the kernel's variables: {error}");
+                self.variables_error = Some("The kernel's variables couldn't be read".into());
+            }
+            None => {
+                self.variables_error = Some("The kernel couldn't list its variables".into());
             }
-            _ => {}
         }
+        cx.notify();
+    }
 
-        if let Some(block) = self.blocks.get_mut(parent_message_id) {
-            block.handle_message(message, window, cx);
+    fn show_variable_preview(
+        &mut self,
+        name: String,
+        result: Option<String>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if !self.variable_previews.contains_key(&name) {
+            // The preview was closed while it was loading.
+            return;
         }
+        let preview = match result
+            .as_deref()
+            .map(serde_json::from_str::<TabularDataResource>)
+        {
+            Some(Ok(table)) => {
+                VariablePreview::Table(cx.new(|cx| TableView::new(&table, window, cx)))
+            }
+            Some(Err(error)) => {
+                log::error!("failed to parse the preview of {name}: {error}");
+                VariablePreview::Error("The preview couldn't be read".into())
+            }
+            None => VariablePreview::Error("The kernel couldn't preview this variable".into()),
+        };
+        self.variable_previews.insert(name, preview);
+        cx.notify();
     }
 
     pub fn interrupt(&mut self, cx: &mut Context<Self>) {
@@ -562,12 +809,21 @@ impl Session {
         );
 
         self.kernel = kernel;
+        // A new kernel starts with an empty namespace.
+        self.clear_variables();
     }
 
     pub fn shutdown(&mut self, window: &mut Window, cx: &mut Context<Self>) {
         let kernel = std::mem::replace(&mut self.kernel, Kernel::ShuttingDown);
 
         match kernel {
+            Kernel::RunningKernel(kernel) if kernel.is_attached() => {
+                // Dropping the kernel closes our connection to it and leaves it
+                // running on the server for whoever launched it.
+                drop(kernel);
+                self.clear_outputs(cx);
+                self.kernel(Kernel::Shutdown, cx);
+            }
             Kernel::RunningKernel(mut kernel) => {
                 let mut request_tx = kernel.request_tx().clone();
 
@@ -641,6 +897,106 @@ impl Session {
     }
 }
 
+impl KernelSession for Session {
+    fn route(&mut self, message: &JupyterMessage, window: &mut Window, cx: &mut Context<Self>) {
+        let parent_message_id = match message.parent_header.as_ref() {
+            Some(header) => &header.msg_id,
+            None => return,
+        };
+
+        if self.evaluations.contains(parent_message_id) {
+            if let JupyterMessageContent::Status(status) = &message.content {
+                self.kernel.set_execution_state(&status.execution_state);
+                cx.notify();
+            }
+            match self.evaluations.handle(message) {
+                Some((
+                    Evaluation::InlineValue {
+                        execution_id,
+                        position,
+                    },
+                    value,
+                )) => self.show_evaluated_inline_value(execution_id, position, value, cx),
+                Some((Evaluation::Variables, value)) => self.show_variables(value, cx),
+                Some((Evaluation::VariablePreview(name), value)) => {
+                    self.show_variable_preview(name, value, window, cx)
+                }
+                None => {}
+            }
+            return;
+        }
+
+        match &message.content {
+            JupyterMessageContent::Status(status) => {
+                self.kernel.set_execution_state(&status.execution_state);
+
+                telemetry::event!(
+                    "Kernel Status Changed",
+                    kernel_language = self.kernel_specification.language(),
+                    kernel_status = KernelStatus::from(&self.kernel).to_string(),
+                    repl_session_id = cx.entity_id().to_string(),
+                );
+
+                cx.notify();
+            }
+            JupyterMessageContent::KernelInfoReply(reply) => {
+                self.kernel.set_kernel_info(reply);
+                cx.notify();
+            }
+            JupyterMessageContent::UpdateDisplayData(update) => {
+                let display_id = if let Some(display_id) = update.transient.display_id.clone() {
+                    display_id
+                } else {
+                    return;
+                };
+
+                self.blocks.iter_mut().for_each(|(_, block)| {
+                    block.execution_view.update(cx, |execution_view, cx| {
+                        execution_view.update_display_data(&update.data, &display_id, window, cx);
+                    });
+                });
+                return;
+            }
+            JupyterMessageContent::ExecuteReply(reply) => {
+                if self.blocks.contains_key(parent_message_id) {
+                    if reply.status == ReplyStatus::Ok {
+                        self.request_inline_values(parent_message_id.clone(), cx);
+                    }
+                    // Even failed executions may have assigned variables before failing.
+                    if self.variables_shown {
+                        self.refresh_variables(cx);
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(block) = self.blocks.get_mut(parent_message_id) {
+            block.handle_message(message, window, cx);
+        }
+    }
+
+    fn kernel_errored(&mut self, error_message: String, cx: &mut Context<Self>) {
+        self.kernel(Kernel::ErroredLaunch(error_message.clone()), cx);
+
+        self.blocks.values().for_each(|block| {
+            block.execution_view.update(cx, |execution_view, cx| {
+                match execution_view.status {
+                    ExecutionStatus::Finished => {
+                        // Do nothing when the output was good
+                    }
+                    _ => {
+                        // All other cases, set the status to errored
+                        execution_view.status =
+                            ExecutionStatus::KernelErrored(error_message.clone())
+                    }
+                }
+                cx.notify();
+            });
+        });
+    }
+}
+
 pub enum SessionEvent {
     Shutdown(WeakEntity<Editor>),
 }
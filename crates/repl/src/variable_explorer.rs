@@ -0,0 +1,521 @@
+//! # Variable Explorer
+//!
+//! A panel listing the variables in the namespace of the kernel attached to the active editor,
+//! with their types, shapes and a short preview. The list is gathered by evaluating a small
+//! introspection expression in the kernel after each execution, and tables such as pandas
+//! DataFrames can be previewed with the same renderer as table outputs.
+
+use anyhow::Result;
+use editor::Editor;
+use gpui::{
+    actions, AnyElement, App, Context, Entity, EventEmitter, FocusHandle, Focusable, Pixels,
+    Subscription, WeakEntity, Window,
+};
+use project::Fs;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use settings::{Settings, SettingsSources};
+use std::sync::Arc;
+use ui::{prelude::*, List, ListItem, ListItemSpacing, Tab, Tooltip};
+use workspace::{
+    dock::{DockPosition, Panel, PanelEvent},
+    Workspace,
+};
+
+use crate::outputs::TableView;
+use crate::repl_store::ReplStore;
+use crate::{JupyterSettings, Session};
+
+actions!(variable_explorer, [ToggleFocus]);
+
+/// The most rows of a table that are previewed.
+const MAX_PREVIEW_ROWS: usize = 50;
+
+pub fn init(cx: &mut App) {
+    VariableExplorerSettings::register(cx);
+    cx.observe_new(VariableExplorer::register).detach();
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema, Debug)]
+pub struct VariableExplorerSettingsContent {
+    /// Whether to show the panel button in the status bar.
+    ///
+    /// Default: true
+    pub button: Option<bool>,
+    /// Where to dock the panel.
+    ///
+    /// Default: right
+    pub dock: Option<DockPosition>,
+    /// Default width of the panel in pixels.
+    ///
+    /// Default: 300
+    pub default_width: Option<f32>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct VariableExplorerSettings {
+    pub button: bool,
+    pub dock: DockPosition,
+    pub default_width: Pixels,
+}
+
+impl Settings for VariableExplorerSettings {
+    const KEY: Option<&'static str> = Some("variable_explorer");
+
+    type FileContent = VariableExplorerSettingsContent;
+
+    fn load(sources: SettingsSources<Self::FileContent>, _: &mut App) -> anyhow::Result<Self> {
+        sources.json_merge()
+    }
+}
+
+/// A variable in a kernel's namespace.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Variable {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_name: String,
+    /// The dimensions of arrays and tables, or the length of collections.
+    #[serde(default)]
+    pub shape: Option<String>,
+    pub preview: String,
+    /// Whether the variable can be previewed as a table.
+    #[serde(default, rename = "table")]
+    pub is_table: bool,
+}
+
+/// The preview of a table variable.
+pub enum VariablePreview {
+    Loading,
+    Table(Entity<TableView>),
+    Error(SharedString),
+}
+
+/// Builds an expression that evaluates to an object whose `repr` is `json_expression`'s result.
+///
+/// Kernels return the `text/plain` representation of evaluated expressions, which would
+/// otherwise wrap strings in quotes and escape them.
+fn raw_python_repr(json_expression: &str) -> String {
+    format!(
+        "(lambda s: type(\"ZedRepr\", (), {{\"__repr__\": lambda self: s}})())({json_expression})"
+    )
+}
+
+/// The expression that lists the variables in a kernel's namespace as JSON, for the kernel
+/// languages that are supported.
+pub fn variables_expression(language: &str) -> Option<String> {
+    if !language.eq_ignore_ascii_case("python") {
+        return None;
+    }
+
+    // Private names, modules, functions, classes and IPython's own helpers are left out.
+    // Each entry is built by a helper that catches errors, so that an object whose
+    // `len`, `repr` or `shape` fails doesn't keep the other variables from being listed.
+    // The helper is defined with `exec`, since the kernel evaluates a single expression.
+    let entries = format!(
+        r#"(lambda ns: (exec({VARIABLE_ENTRY_PYTHON:?}, ns), [ns["entry"](n, v) for n, v in list(globals().items()) if not n.startswith("_") and n not in ("In", "Out", "exit", "quit", "get_ipython") and not callable(v) and type(v).__name__ != "module"])[1])({{}})"#
+    );
+    Some(raw_python_repr(&format!(
+        "__import__(\"json\").dumps({entries})"
+    )))
+}
+
+/// Defines `entry(name, value)`, which describes a variable for [`variables_expression`].
+const VARIABLE_ENTRY_PYTHON: &str = r#"
+def entry(n, v):
+    try:
+        shape = str(tuple(v.shape)) if hasattr(v, "shape") else ("%d items" % len(v) if hasattr(v, "__len__") else None)
+    except Exception:
+        shape = None
+    try:
+        preview = __import__("reprlib").repr(v)
+    except Exception as error:
+        preview = "<%s while getting the repr>" % type(error).__name__
+    try:
+        table = hasattr(v, "head") and hasattr(v, "to_json")
+    except Exception:
+        table = False
+    return {"name": n, "type": type(v).__name__, "shape": shape, "preview": preview, "table": table}
+"#;
+
+/// The expression that evaluates to the first rows of a table variable, as a
+/// [Tabular Data Resource](https://specs.frictionlessdata.io/tabular-data-resource/).
+pub fn table_preview_expression(language: &str, name: &str) -> Option<String> {
+    if !language.eq_ignore_ascii_case("python") || !is_identifier(name) {
+        return None;
+    }
+    Some(raw_python_repr(&format!(
+        "{name}.head({MAX_PREVIEW_ROWS}).to_json(orient=\"table\")"
+    )))
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .map_or(false, |c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Parses the result of [`variables_expression`], sorted by name.
+pub fn parse_variables(json: &str) -> Result<Vec<Variable>> {
+    let mut variables: Vec<Variable> = serde_json::from_str(json)?;
+    variables.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(variables)
+}
+
+/// Lists the variables of the kernel session attached to the active editor.
+pub struct VariableExplorer {
+    fs: Arc<dyn Fs>,
+    focus_handle: FocusHandle,
+    width: Option<Pixels>,
+    active: bool,
+    active_editor: Option<WeakEntity<Editor>>,
+    session: Option<Entity<Session>>,
+    _session_subscription: Option<Subscription>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl VariableExplorer {
+    fn register(
+        workspace: &mut Workspace,
+        window: Option<&mut Window>,
+        cx: &mut Context<Workspace>,
+    ) {
+        workspace.register_action(|workspace, _: &ToggleFocus, window, cx| {
+            workspace.toggle_panel_focus::<VariableExplorer>(window, cx);
+        });
+
+        let Some(window) = window else {
+            return;
+        };
+        let fs = workspace.app_state().fs.clone();
+        let workspace_entity = cx.entity();
+        let panel = cx.new(|cx| Self::new(workspace_entity, fs, window, cx));
+        workspace.add_panel(panel, window, cx);
+    }
+
+    fn new(
+        workspace: Entity<Workspace>,
+        fs: Arc<dyn Fs>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let subscriptions = vec![
+            cx.subscribe_in(&workspace, window, |this, workspace, event, _, cx| {
+                if let workspace::Event::ActiveItemChanged = event {
+                    this.active_editor = workspace
+                        .read(cx)
+                        .active_item_as::<Editor>(cx)
+                        .map(|editor| editor.downgrade());
+                    this.update_session(cx);
+                }
+            }),
+            cx.observe(&ReplStore::global(cx), |this, _, cx| {
+                this.update_session(cx)
+            }),
+        ];
+
+        Self {
+            fs,
+            focus_handle: cx.focus_handle(),
+            width: None,
+            active: false,
+            active_editor: None,
+            session: None,
+            _session_subscription: None,
+            _subscriptions: subscriptions,
+        }
+    }
+
+    fn update_session(&mut self, cx: &mut Context<Self>) {
+        let session = self.active_editor.as_ref().and_then(|editor| {
+            ReplStore::global(cx)
+                .read(cx)
+                .get_session(editor.entity_id())
+                .cloned()
+        });
+        if session == self.session {
+            return;
+        }
+
+        self.set_variables_shown(false, cx);
+        self._session_subscription = session
+            .as_ref()
+            .map(|session| cx.observe(session, |_, _, cx| cx.notify()));
+        self.session = session;
+        self.set_variables_shown(self.active, cx);
+        cx.notify();
+    }
+
+    fn set_variables_shown(&self, shown: bool, cx: &mut Context<Self>) {
+        if let Some(session) = &self.session {
+            session.update(cx, |session, cx| session.set_variables_shown(shown, cx));
+        }
+    }
+
+    fn render_variable(
+        &self,
+        ix: usize,
+        variable: &Variable,
+        session: &Entity<Session>,
+        cx: &App,
+    ) -> AnyElement {
+        let preview = session.read(cx).variable_preview(&variable.name);
+        let details = match &variable.shape {
+            Some(shape) => format!("{} {shape}", variable.type_name),
+            None => variable.type_name.clone(),
+        };
+
+        let item = ListItem::new(ix)
+            .spacing(ListItemSpacing::Sparse)
+            .toggle(variable.is_table.then_some(preview.is_some()))
+            .on_toggle({
+                let session = session.clone();
+                let name = variable.name.clone();
+                move |_, _, cx| {
+                    session.update(cx, |session, cx| session.toggle_variable_preview(&name, cx));
+                }
+            })
+            .child(
+                v_flex()
+                    .overflow_hidden()
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .child(Label::new(variable.name.clone()))
+                            .child(
+                                Label::new(details)
+                                    .size(LabelSize::Small)
+                                    .color(Color::Muted),
+                            ),
+                    )
+                    .child(
+                        Label::new(variable.preview.clone())
+                            .size(LabelSize::Small)
+                            .color(Color::Muted)
+                            .single_line()
+                            .text_ellipsis(),
+                    ),
+            )
+            .tooltip(Tooltip::text(variable.preview.clone()));
+
+        let preview = match preview {
+            None => None,
+            Some(VariablePreview::Loading) => Some(
+                Label::new("Loading preview…")
+                    .size(LabelSize::Small)
+                    .color(Color::Muted)
+                    .into_any_element(),
+            ),
+            Some(VariablePreview::Error(error)) => Some(
+                Label::new(error.clone())
+                    .size(LabelSize::Small)
+                    .color(Color::Error)
+                    .into_any_element(),
+            ),
+            Some(VariablePreview::Table(table)) => Some(
+                div()
+                    .id(("variable-preview", ix))
+                    .overflow_x_scroll()
+                    .child(table.clone())
+                    .into_any_element(),
+            ),
+        };
+
+        v_flex()
+            .child(item)
+            .children(preview.map(|preview| div().pl_6().pr_2().pb_2().child(preview)))
+            .into_any_element()
+    }
+
+    fn render_message(&self, message: impl Into<SharedString>) -> AnyElement {
+        div()
+            .p_2()
+            .child(Label::new(message.into()).color(Color::Muted))
+            .into_any_element()
+    }
+}
+
+impl Render for VariableExplorer {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let session = self.session.clone();
+
+        let content = match &session {
+            None => self.render_message(
+                "Run code in an editor to start a kernel session, and its variables are listed here.",
+            ),
+            Some(session) if !session.read(cx).supports_variables() => {
+                let language = session.read(cx).kernel_specification.language();
+                self.render_message(format!(
+                    "Listing variables isn't supported for {language} kernels."
+                ))
+            }
+            Some(session) => {
+                let (variables, error) = {
+                    let session = session.read(cx);
+                    (
+                        session.variables().to_vec(),
+                        session.variables_error().cloned(),
+                    )
+                };
+                let items = variables
+                    .iter()
+                    .enumerate()
+                    .map(|(ix, variable)| self.render_variable(ix, variable, session, cx))
+                    .collect::<Vec<_>>();
+
+                v_flex()
+                    .id("variables")
+                    .flex_1()
+                    .overflow_y_scroll()
+                    .children(error.map(|error| {
+                        div()
+                            .p_2()
+                            .child(Label::new(error).size(LabelSize::Small).color(Color::Error))
+                    }))
+                    .child(
+                        List::new()
+                            .empty_message("No variables have been defined")
+                            .children(items),
+                    )
+                    .into_any_element()
+            }
+        };
+
+        let refresh_button = session.as_ref().map(|session| {
+            IconButton::new("refresh-variables", IconName::RotateCw)
+                .icon_size(IconSize::Small)
+                .disabled(session.read(cx).is_loading_variables())
+                .tooltip(Tooltip::text("Refresh Variables"))
+                .on_click({
+                    let session = session.clone();
+                    move |_, _, cx| session.update(cx, |session, cx| session.refresh_variables(cx))
+                })
+        });
+
+        v_flex()
+            .key_context("VariableExplorer")
+            .track_focus(&self.focus_handle)
+            .size_full()
+            .child(
+                h_flex()
+                    .justify_between()
+                    .px_2()
+                    .py_1()
+                    // Match the height of the tab bar so they line up.
+                    .h(Tab::container_height(cx))
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border)
+                    .child(Label::new("Variables"))
+                    .children(refresh_button),
+            )
+            .child(content)
+    }
+}
+
+impl Focusable for VariableExplorer {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl EventEmitter<PanelEvent> for VariableExplorer {}
+
+impl Panel for VariableExplorer {
+    fn persistent_name() -> &'static str {
+        "VariableExplorer"
+    }
+
+    fn position(&self, _: &Window, cx: &App) -> DockPosition {
+        VariableExplorerSettings::get_global(cx).dock
+    }
+
+    fn position_is_valid(&self, position: DockPosition) -> bool {
+        matches!(position, DockPosition::Left | DockPosition::Right)
+    }
+
+    fn set_position(&mut self, position: DockPosition, _: &mut Window, cx: &mut Context<Self>) {
+        settings::update_settings_file::<VariableExplorerSettings>(
+            self.fs.clone(),
+            cx,
+            move |settings, _| settings.dock = Some(position),
+        );
+    }
+
+    fn size(&self, _: &Window, cx: &App) -> Pixels {
+        self.width
+            .unwrap_or_else(|| VariableExplorerSettings::get_global(cx).default_width)
+    }
+
+    fn set_size(&mut self, size: Option<Pixels>, _: &mut Window, cx: &mut Context<Self>) {
+        self.width = size;
+        cx.notify();
+    }
+
+    fn set_active(&mut self, active: bool, _: &mut Window, cx: &mut Context<Self>) {
+        self.active = active;
+        self.set_variables_shown(active, cx);
+    }
+
+    fn icon(&self, _: &Window, cx: &App) -> Option<IconName> {
+        Some(IconName::ListTree).filter(|_| {
+            JupyterSettings::enabled(cx) && VariableExplorerSettings::get_global(cx).button
+        })
+    }
+
+    fn icon_tooltip(&self, _: &Window, _: &App) -> Option<&'static str> {
+        Some("Variable Explorer")
+    }
+
+    fn toggle_action(&self) -> Box<dyn gpui::Action> {
+        Box::new(ToggleFocus)
+    }
+
+    fn activation_priority(&self) -> u32 {
+        10
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_variables() {
+        let variables = parse_variables(
+            r#"[
+                {"name": "x", "type": "int", "shape": null, "preview": "1", "table": false},
+                {"name": "df", "type": "DataFrame", "shape": "(3, 2)", "preview": "   a  b", "table": true},
+                {"name": "names", "type": "list", "shape": "2 items", "preview": "['a', 'b']"}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            variables
+                .iter()
+                .map(|variable| variable.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["df", "names", "x"]
+        );
+        assert_eq!(variables[0].shape.as_deref(), Some("(3, 2)"));
+        assert!(variables[0].is_table);
+        assert!(!variables[1].is_table);
+        assert_eq!(variables[2].type_name, "int");
+
+        assert!(parse_variables("not json").is_err());
+    }
+
+    #[test]
+    fn test_table_preview_expression() {
+        assert!(variables_expression("Python").is_some());
+        assert!(variables_expression("julia").is_none());
+
+        let expression = table_preview_expression("python", "df").unwrap();
+        assert!(expression.contains("df.head(50).to_json(orient=\"table\")"));
+        assert_eq!(table_preview_expression("python", "df; import os"), None);
+        assert_eq!(table_preview_expression("python", "1df"), None);
+        assert_eq!(table_preview_expression("r", "df"), None);
+    }
+}
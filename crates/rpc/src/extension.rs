@@ -59,3 +59,34 @@ pub struct ExtensionMetadata {
 pub struct GetExtensionsResponse {
     pub data: Vec<ExtensionMetadata>,
 }
+
+/// The index of a file-system extension registry.
+///
+/// A file-system registry is a directory (or a static mirror of one) containing
+/// an `index.json` file and the extension archives it refers to.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct ExtensionRegistryIndex {
+    pub extensions: Vec<ExtensionRegistryEntry>,
+}
+
+/// A single extension version in an [`ExtensionRegistryIndex`].
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ExtensionRegistryEntry {
+    #[serde(flatten)]
+    pub metadata: ExtensionMetadata,
+    /// The path to the extension's `.tar.gz` archive, relative to the registry root.
+    pub archive: String,
+    /// The hex-encoded SHA-256 checksum of the extension's archive.
+    pub sha256: String,
+}
+
+impl ExtensionRegistryIndex {
+    pub const FILE_NAME: &'static str = "index.json";
+}
+
+/// Returns the hex-encoded SHA-256 checksum of the given extension archive.
+pub fn extension_archive_checksum(archive: &[u8]) -> String {
+    use sha2::Digest as _;
+
+    format!("{:x}", sha2::Sha256::digest(archive))
+}
@@ -1,14 +1,52 @@
 use std::{
     cell::RefCell,
     ffi::{CStr, CString},
+    fmt,
     marker::PhantomData,
     path::Path,
     ptr,
 };
 
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use libsqlite3_sys::*;
 
+/// An error returned by SQLite, with its result code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqliteError {
+    pub code: i32,
+    pub message: Option<String>,
+}
+
+impl SqliteError {
+    /// Whether the database was locked by another connection, so that the call may
+    /// succeed when retried.
+    pub fn is_busy(&self) -> bool {
+        // Extended result codes keep the primary code in their lowest byte.
+        matches!(self.code & 0xff, SQLITE_BUSY | SQLITE_LOCKED)
+    }
+
+    /// Whether `error`, or any error it was caused by, is one where the database was locked.
+    pub fn is_busy_error(error: &anyhow::Error) -> bool {
+        error.chain().any(|error| {
+            error
+                .downcast_ref::<SqliteError>()
+                .map_or(false, SqliteError::is_busy)
+        })
+    }
+}
+
+impl fmt::Display for SqliteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Sqlite call failed with code {} and message: {:?}",
+            self.code as isize, self.message
+        )
+    }
+}
+
+impl std::error::Error for SqliteError {}
+
 pub struct Connection {
     pub(crate) sqlite3: *mut sqlite3,
     persistent: bool,
@@ -199,14 +237,19 @@ impl Connection {
                 )
             };
 
-            Err(anyhow!(
-                "Sqlite call failed with code {} and message: {:?}",
-                code as isize,
-                message
-            ))
+            Err(SqliteError { code, message }.into())
         }
     }
 
+    /// Runs SQLite's integrity check, returning the problems it found in the database.
+    pub fn integrity_check(&self) -> Result<Vec<String>> {
+        let results = self.select::<String>("PRAGMA integrity_check")?()?;
+        Ok(results
+            .into_iter()
+            .filter(|result| result != "ok")
+            .collect())
+    }
+
     pub(crate) fn with_write<T>(&self, callback: impl FnOnce(&Connection) -> T) -> T {
         *self.write.borrow_mut() = true;
         let result = callback(self);
@@ -1,23 +1,47 @@
-use crate::connection::Connection;
+use crate::{connection::Connection, migrations::SchemaDifference};
 
 pub trait Domain: 'static {
     fn name() -> &'static str;
     fn migrations() -> &'static [&'static str];
+
+    /// The migrations that undo each of `migrations`, at the same index. Steps without one
+    /// can't be rolled back when a database's migrations diverge from these.
+    fn down_migrations() -> &'static [&'static str] {
+        &[]
+    }
 }
 
 pub trait Migrator: 'static {
     fn migrate(connection: &Connection) -> anyhow::Result<()>;
+
+    fn has_pending_migrations(connection: &Connection) -> anyhow::Result<bool>;
+
+    /// Compares the connection's schema to the one produced by running these migrations on
+    /// an empty database.
+    fn verify_schema(connection: &Connection) -> anyhow::Result<Vec<SchemaDifference>> {
+        let expected = Connection::open_memory(None);
+        Self::migrate(&expected)?;
+        connection.schema_differences(&expected)
+    }
 }
 
 impl Migrator for () {
     fn migrate(_connection: &Connection) -> anyhow::Result<()> {
         Ok(()) // Do nothing
     }
+
+    fn has_pending_migrations(_connection: &Connection) -> anyhow::Result<bool> {
+        Ok(false)
+    }
 }
 
 impl<D: Domain> Migrator for D {
     fn migrate(connection: &Connection) -> anyhow::Result<()> {
-        connection.migrate(Self::name(), Self::migrations())
+        connection.migrate_with_rollbacks(Self::name(), Self::migrations(), Self::down_migrations())
+    }
+
+    fn has_pending_migrations(connection: &Connection) -> anyhow::Result<bool> {
+        connection.has_pending_migrations(Self::name(), Self::migrations())
     }
 }
 
@@ -26,6 +50,10 @@ impl<D1: Domain, D2: Domain> Migrator for (D1, D2) {
         D1::migrate(connection)?;
         D2::migrate(connection)
     }
+
+    fn has_pending_migrations(connection: &Connection) -> anyhow::Result<bool> {
+        Ok(D1::has_pending_migrations(connection)? || D2::has_pending_migrations(connection)?)
+    }
 }
 
 impl<D1: Domain, D2: Domain, D3: Domain> Migrator for (D1, D2, D3) {
@@ -34,6 +62,12 @@ impl<D1: Domain, D2: Domain, D3: Domain> Migrator for (D1, D2, D3) {
         D2::migrate(connection)?;
         D3::migrate(connection)
     }
+
+    fn has_pending_migrations(connection: &Connection) -> anyhow::Result<bool> {
+        Ok(D1::has_pending_migrations(connection)?
+            || D2::has_pending_migrations(connection)?
+            || D3::has_pending_migrations(connection)?)
+    }
 }
 
 impl<D1: Domain, D2: Domain, D3: Domain, D4: Domain> Migrator for (D1, D2, D3, D4) {
@@ -43,6 +77,13 @@ impl<D1: Domain, D2: Domain, D3: Domain, D4: Domain> Migrator for (D1, D2, D3, D
         D3::migrate(connection)?;
         D4::migrate(connection)
     }
+
+    fn has_pending_migrations(connection: &Connection) -> anyhow::Result<bool> {
+        Ok(D1::has_pending_migrations(connection)?
+            || D2::has_pending_migrations(connection)?
+            || D3::has_pending_migrations(connection)?
+            || D4::has_pending_migrations(connection)?)
+    }
 }
 
 impl<D1: Domain, D2: Domain, D3: Domain, D4: Domain, D5: Domain> Migrator for (D1, D2, D3, D4, D5) {
@@ -53,4 +94,12 @@ impl<D1: Domain, D2: Domain, D3: Domain, D4: Domain, D5: Domain> Migrator for (D
         D4::migrate(connection)?;
         D5::migrate(connection)
     }
+
+    fn has_pending_migrations(connection: &Connection) -> anyhow::Result<bool> {
+        Ok(D1::has_pending_migrations(connection)?
+            || D2::has_pending_migrations(connection)?
+            || D3::has_pending_migrations(connection)?
+            || D4::has_pending_migrations(connection)?
+            || D5::has_pending_migrations(connection)?)
+    }
 }
@@ -1,17 +1,75 @@
 // Migrations are constructed by domain, and stored in a table in the connection db with domain name,
 // effected tables, actual query text, and order.
-// If a migration is run and any of the query texts don't match, the app panics on startup (maybe fallback
-// to creating a new db?)
+// If a migration is run and any of the query texts don't match, the migrations from that step on are
+// rolled back with their stored down migrations, or an error is returned if some have none.
 // Otherwise any missing migrations are run on the connection
 
 use std::ffi::CString;
+use std::fmt;
 
-use anyhow::{anyhow, Context as _, Result};
+use anyhow::{Context as _, Result};
 use indoc::{formatdoc, indoc};
 use libsqlite3_sys::sqlite3_exec;
 
 use crate::connection::Connection;
 
+/// A table, index, view or trigger whose definition in a database differs from the one
+/// produced by running the database's migrations on an empty database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaDifference {
+    Missing {
+        kind: String,
+        name: String,
+    },
+    Changed {
+        kind: String,
+        name: String,
+        expected: String,
+        actual: String,
+    },
+}
+
+impl fmt::Display for SchemaDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaDifference::Missing { kind, name } => write!(f, "missing {kind} {name}"),
+            SchemaDifference::Changed {
+                kind,
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "changed {kind} {name}\n  expected: {expected}\n  actual: {actual}"
+            ),
+        }
+    }
+}
+
+/// The error returned when a database's migrations diverge from the proposed ones at a step
+/// that has no down migration, so the database can't be migrated without losing data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IrreversibleMigration {
+    pub domain: String,
+    pub step: usize,
+}
+
+impl fmt::Display for IrreversibleMigration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Migration {} for {} can't be rolled back, as it has no down migration",
+            self.step, self.domain
+        )
+    }
+}
+
+impl std::error::Error for IrreversibleMigration {}
+
+fn format_sql(sql: &str) -> String {
+    sqlformat::format(sql, &sqlformat::QueryParams::None, Default::default())
+}
+
 impl Connection {
     fn eager_exec(&self, sql: &str) -> anyhow::Result<()> {
         let sql_str = CString::new(sql).context("Error creating cstr")?;
@@ -30,69 +88,204 @@ impl Connection {
         Ok(())
     }
 
+    fn create_migrations_table(&self) -> Result<()> {
+        self.exec(indoc! {"
+            CREATE TABLE IF NOT EXISTS migrations (
+                domain TEXT,
+                step INTEGER,
+                migration TEXT,
+                down_migration TEXT
+            )"})?()?;
+
+        // Databases created before down migrations were stored lack their column.
+        let columns = self.select::<String>("SELECT name FROM pragma_table_info('migrations')")?()?;
+        if !columns.iter().any(|column| column == "down_migration") {
+            self.exec("ALTER TABLE migrations ADD COLUMN down_migration TEXT")?()?;
+        }
+        Ok(())
+    }
+
     /// Migrate the database, for the given domain.
     /// Note: Unlike everything else in SQLez, migrations are run eagerly, without first
     /// preparing the SQL statements. This makes it possible to do multi-statement schema
     /// updates in a single string without running into prepare errors.
     pub fn migrate(&self, domain: &'static str, migrations: &[&'static str]) -> Result<()> {
+        self.migrate_with_rollbacks(domain, migrations, &[])
+    }
+
+    /// Migrate the database, for the given domain, storing the down migration that undoes
+    /// each step alongside it. `down_migrations` are matched to `migrations` by index, and
+    /// may be shorter than it.
+    ///
+    /// When a completed step differs from the proposed one, such as when a database was
+    /// migrated by a build with diverging migrations, the completed steps from that one on
+    /// are rolled back with their stored down migrations before the proposed ones are run.
+    pub fn migrate_with_rollbacks(
+        &self,
+        domain: &'static str,
+        migrations: &[&'static str],
+        down_migrations: &[&'static str],
+    ) -> Result<()> {
         self.with_savepoint("migrating", || {
             // Setup the migrations table unconditionally
-            self.exec(indoc! {"
-                CREATE TABLE IF NOT EXISTS migrations (
-                    domain TEXT,
-                    step INTEGER,
-                    migration TEXT
-                )"})?()?;
+            self.create_migrations_table()?;
 
-            let completed_migrations =
-                self.select_bound::<&str, (String, usize, String)>(indoc! {"
-                    SELECT domain, step, migration FROM migrations
-                    WHERE domain = ?
-                    ORDER BY step
-                    "})?(domain)?;
+            let mut completed_migrations = self.completed_migrations(domain)?;
 
-            let mut store_completed_migration = self
-                .exec_bound("INSERT INTO migrations (domain, step, migration) VALUES (?, ?, ?)")?;
+            let mut store_completed_migration = self.exec_bound(
+                "INSERT INTO migrations (domain, step, migration, down_migration) VALUES (?, ?, ?, ?)",
+            )?;
+            // Steps completed before their down migration existed get it once it does.
+            let mut store_missing_down_migration = self.exec_bound::<(String, &str, usize)>(
+                "UPDATE migrations SET down_migration = ? WHERE domain = ? AND step = ? AND down_migration IS NULL",
+            )?;
 
             for (index, migration) in migrations.iter().enumerate() {
-                let migration =
-                    sqlformat::format(migration, &sqlformat::QueryParams::None, Default::default());
-                if let Some((_, _, completed_migration)) = completed_migrations.get(index) {
+                let migration = format_sql(migration);
+                let down_migration = down_migrations.get(index).map(|sql| format_sql(sql));
+                if let Some(completed_migration) = completed_migrations.get(index) {
                     // Reformat completed migrations with the current `sqlformat` version, so that past migrations stored
                     // conform to the new formatting rules.
-                    let completed_migration = sqlformat::format(
-                        completed_migration,
-                        &sqlformat::QueryParams::None,
-                        Default::default(),
-                    );
+                    let completed_migration = format_sql(completed_migration);
                     if completed_migration == migration {
                         // Migration already run. Continue
+                        if let Some(down_migration) = down_migration {
+                            store_missing_down_migration((down_migration, domain, index))?;
+                        }
                         continue;
-                    } else {
-                        return Err(anyhow!(formatdoc! {"
+                    }
+
+                    self.rollback_migrations(domain, index).with_context(|| {
+                        formatdoc! {"
                             Migration changed for {} at step {}
 
                             Stored migration:
                             {}
 
                             Proposed migration:
-                            {}", domain, index, completed_migration, migration}));
-                    }
+                            {}", domain, index, completed_migration, migration}
+                    })?;
+                    completed_migrations.truncate(index);
                 }
 
                 self.eager_exec(&migration)?;
-                store_completed_migration((domain, index, migration))?;
+                store_completed_migration((domain, index, migration, down_migration))?;
             }
 
             Ok(())
         })
     }
+
+    fn completed_migrations(&self, domain: &str) -> Result<Vec<String>> {
+        self.select_bound::<&str, String>(indoc! {"
+            SELECT migration FROM migrations
+            WHERE domain = ?
+            ORDER BY step
+            "})?(domain)
+    }
+
+    /// Undoes the completed migrations of a domain from the given step on, newest first,
+    /// using their stored down migrations.
+    ///
+    /// Nothing is rolled back if any of those steps has no down migration.
+    pub fn rollback_migrations(&self, domain: &str, from_step: usize) -> Result<()> {
+        self.with_savepoint("rolling_back_migrations", || {
+            self.create_migrations_table()?;
+
+            let steps = self.select_bound::<(&str, usize), (usize, Option<String>)>(indoc! {"
+                SELECT step, down_migration FROM migrations
+                WHERE domain = ? AND step >= ?
+                ORDER BY step DESC
+                "})?((domain, from_step))?;
+
+            if let Some((step, _)) = steps.iter().find(|(_, down)| down.is_none()) {
+                return Err(IrreversibleMigration {
+                    domain: domain.to_string(),
+                    step: *step,
+                }
+                .into());
+            }
+
+            for (_, down_migration) in steps.into_iter() {
+                if let Some(down_migration) = down_migration {
+                    self.eager_exec(&down_migration)?;
+                }
+            }
+            self.exec_bound::<(&str, usize)>(
+                "DELETE FROM migrations WHERE domain = ? AND step >= ?",
+            )?((domain, from_step))?;
+
+            Ok(())
+        })
+    }
+
+    /// Whether running the given migrations would change the database.
+    pub fn has_pending_migrations(&self, domain: &str, migrations: &[&str]) -> Result<bool> {
+        let has_migrations_table = self.select_row::<bool>(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'migrations'",
+        )?()?
+        .unwrap_or(false);
+        if !has_migrations_table {
+            return Ok(!migrations.is_empty());
+        }
+
+        let completed_migrations = self.completed_migrations(domain)?;
+        Ok(migrations.len() > completed_migrations.len()
+            || migrations
+                .iter()
+                .zip(completed_migrations.iter())
+                .any(|(migration, completed)| format_sql(migration) != format_sql(completed)))
+    }
+
+    /// The type, name and SQL of the tables, indices, views and triggers in the database,
+    /// apart from SQLite's internal ones and the migrations table.
+    fn schema(&self) -> Result<Vec<(String, String, String)>> {
+        self.select::<(String, String, String)>(indoc! {"
+            SELECT type, name, sql FROM sqlite_master
+            WHERE sql IS NOT NULL AND name NOT LIKE 'sqlite_%' AND name != 'migrations'
+            ORDER BY type, name
+            "})?()
+    }
+
+    /// Compares this database's schema to that of `expected`, returning the objects of
+    /// `expected` which are missing from this database or defined differently in it.
+    ///
+    /// Objects that only exist in this database are ignored, as databases are shared by the
+    /// migrations of several domains.
+    pub fn schema_differences(&self, expected: &Connection) -> Result<Vec<SchemaDifference>> {
+        let actual_schema = self.schema()?;
+        let mut differences = Vec::new();
+        for (kind, name, expected_sql) in expected.schema()? {
+            let actual_sql = actual_schema
+                .iter()
+                .find(|(actual_kind, actual_name, _)| *actual_kind == kind && *actual_name == name)
+                .map(|(_, _, sql)| sql);
+            match actual_sql {
+                None => differences.push(SchemaDifference::Missing { kind, name }),
+                Some(actual_sql) => {
+                    // Stored SQL keeps the formatting of the statements that created it.
+                    let expected_sql = format_sql(&expected_sql);
+                    let actual_sql = format_sql(actual_sql);
+                    if expected_sql != actual_sql {
+                        differences.push(SchemaDifference::Changed {
+                            kind,
+                            name,
+                            expected: expected_sql,
+                            actual: actual_sql,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(differences)
+    }
 }
 
 #[cfg(test)]
 mod test {
     use indoc::indoc;
 
+    use super::{IrreversibleMigration, SchemaDifference};
     use crate::connection::Connection;
 
     #[test]
@@ -312,4 +505,173 @@ mod test {
 
         assert_eq!(res, "test text");
     }
+
+    #[test]
+    fn diverged_migrations_are_rolled_back() {
+        let connection = Connection::open_memory(Some("diverged_migrations_are_rolled_back"));
+
+        connection
+            .migrate_with_rollbacks(
+                "test",
+                &[
+                    "CREATE TABLE test (col INTEGER)",
+                    "CREATE TABLE nightly (col INTEGER)",
+                ],
+                &["DROP TABLE test", "DROP TABLE nightly"],
+            )
+            .unwrap();
+
+        // A build whose second step differs rolls it back, and keeps the first one
+        connection
+            .exec("INSERT INTO test (col) VALUES (1)")
+            .unwrap()()
+        .unwrap();
+        connection
+            .migrate(
+                "test",
+                &[
+                    "CREATE TABLE test (col INTEGER)",
+                    "CREATE TABLE stable (col INTEGER)",
+                ],
+            )
+            .unwrap();
+
+        assert_eq!(
+            connection
+                .select_row::<usize>("SELECT col FROM test")
+                .unwrap()()
+            .unwrap(),
+            Some(1)
+        );
+        assert!(connection
+            .select::<usize>("SELECT col FROM nightly")
+            .is_err());
+        assert_eq!(
+            &connection
+                .select::<String>("SELECT migration FROM migrations ORDER BY step")
+                .unwrap()()
+            .unwrap()[..],
+            &[
+                "CREATE TABLE test (col INTEGER)",
+                "CREATE TABLE stable (col INTEGER)",
+            ],
+        );
+
+        // The stable step has no down migration, so diverging from it again fails
+        assert!(connection
+            .migrate(
+                "test",
+                &[
+                    "CREATE TABLE test (col INTEGER)",
+                    "CREATE TABLE other (col INTEGER)",
+                ],
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn down_migrations_are_stored_for_completed_steps() {
+        let connection = Connection::open_memory(Some("down_migrations_are_stored"));
+        let migrations = [
+            "CREATE TABLE test (col INTEGER)",
+            "CREATE TABLE nightly (col INTEGER)",
+        ];
+
+        // Steps run before their down migrations existed can't be rolled back...
+        connection.migrate("test", &migrations).unwrap();
+        let error = connection
+            .migrate("test", &["CREATE TABLE test (col INTEGER)"])
+            .and_then(|_| connection.rollback_migrations("test", 1))
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<IrreversibleMigration>(),
+            Some(&IrreversibleMigration {
+                domain: "test".into(),
+                step: 1,
+            })
+        );
+
+        // ...until a build that knows them runs the migrations again
+        connection
+            .migrate_with_rollbacks(
+                "test",
+                &migrations,
+                &["DROP TABLE test", "DROP TABLE nightly"],
+            )
+            .unwrap();
+        connection
+            .migrate(
+                "test",
+                &[
+                    "CREATE TABLE test (col INTEGER)",
+                    "CREATE TABLE stable (col INTEGER)",
+                ],
+            )
+            .unwrap();
+        assert!(connection
+            .select::<usize>("SELECT col FROM nightly")
+            .is_err());
+    }
+
+    #[test]
+    fn test_pending_migrations() {
+        let connection = Connection::open_memory(Some("test_pending_migrations"));
+        let migrations = ["CREATE TABLE test (col INTEGER)"];
+
+        assert!(connection
+            .has_pending_migrations("test", &migrations)
+            .unwrap());
+        assert!(!connection.has_pending_migrations("test", &[]).unwrap());
+
+        connection.migrate("test", &migrations).unwrap();
+        assert!(!connection
+            .has_pending_migrations("test", &migrations)
+            .unwrap());
+        assert!(connection
+            .has_pending_migrations(
+                "test",
+                &["CREATE TABLE test (col INTEGER)", "DROP TABLE test"]
+            )
+            .unwrap());
+    }
+
+    #[test]
+    fn test_schema_differences() {
+        let expected = Connection::open_memory(Some("test_schema_differences_expected"));
+        expected
+            .migrate(
+                "test",
+                &[indoc! {"
+                    CREATE TABLE test (a TEXT, b TEXT);
+                    CREATE INDEX test_a ON test (a);
+                "}],
+            )
+            .unwrap();
+
+        let actual = Connection::open_memory(Some("test_schema_differences_actual"));
+        actual
+            .exec("CREATE TABLE test (a   TEXT,\n b TEXT)")
+            .unwrap()()
+        .unwrap();
+        actual.exec("CREATE TABLE other (c TEXT)").unwrap()().unwrap();
+
+        // Formatting and tables of other domains don't count as differences
+        assert_eq!(
+            actual.schema_differences(&expected).unwrap(),
+            vec![SchemaDifference::Missing {
+                kind: "index".into(),
+                name: "test_a".into(),
+            }]
+        );
+
+        actual.exec("DROP TABLE test").unwrap()().unwrap();
+        actual.exec("CREATE TABLE test (a TEXT)").unwrap()().unwrap();
+        assert!(matches!(
+            &actual.schema_differences(&expected).unwrap()[..],
+            [
+                SchemaDifference::Missing { .. },
+                SchemaDifference::Changed { name, .. },
+            ] if name == "test"
+        ));
+    }
 }
@@ -5,10 +5,12 @@ use parking_lot::{Mutex, RwLock};
 use std::{
     marker::PhantomData,
     ops::Deref,
-    sync::{Arc, LazyLock},
+    path::PathBuf,
+    sync::{Arc, LazyLock, Weak},
     thread,
 };
 use thread_local::ThreadLocal;
+use util::ResultExt as _;
 
 use crate::{connection::Connection, domain::Migrator, util::UnboundedSyncSender};
 
@@ -24,6 +26,11 @@ type WriteQueueConstructor = Box<dyn 'static + Send + FnMut() -> WriteQueue>;
 /// thread.
 static QUEUES: LazyLock<RwLock<HashMap<Arc<str>, WriteQueue>>> = LazyLock::new(Default::default);
 
+/// The connections built in this process by database uri, which are open for as long as one of
+/// their clones is alive.
+static OPEN_CONNECTIONS: LazyLock<Mutex<HashMap<Arc<str>, Vec<Weak<ThreadLocal<Connection>>>>>> =
+    LazyLock::new(Default::default);
+
 /// Thread safe connection to a given database file or in memory db. This can be cloned, shared, static,
 /// whatever. It derefs to a synchronous connection by thread that is read only. A write capable connection
 /// may be accessed by passing a callback to the `write` function which will queue the callback
@@ -41,6 +48,8 @@ unsafe impl<M: Migrator> Sync for ThreadSafeConnection<M> {}
 pub struct ThreadSafeConnectionBuilder<M: Migrator + 'static = ()> {
     db_initialize_query: Option<&'static str>,
     write_queue_constructor: Option<WriteQueueConstructor>,
+    backup_path: Option<PathBuf>,
+    verify_schema: bool,
     connection: ThreadSafeConnection<M>,
 }
 
@@ -72,11 +81,28 @@ impl<M: Migrator> ThreadSafeConnectionBuilder<M> {
         self
     }
 
+    /// Copies a persistent database to the given path before migrating it, whenever it has
+    /// migrations to run, so that it can be restored if a migration goes wrong.
+    pub fn with_backup_before_migration(mut self, backup_path: impl Into<PathBuf>) -> Self {
+        self.backup_path = Some(backup_path.into());
+        self
+    }
+
+    /// Logs the differences between the database's schema after migrating and the one its
+    /// migrations produce on an empty database. These are only reported, as a database
+    /// migrated by a newer build may have changed its tables in ways this build doesn't know.
+    pub fn with_schema_verification(mut self) -> Self {
+        self.verify_schema = true;
+        self
+    }
+
     pub async fn build(self) -> anyhow::Result<ThreadSafeConnection<M>> {
         self.connection
             .initialize_queues(self.write_queue_constructor);
 
         let db_initialize_query = self.db_initialize_query;
+        let backup_path = self.backup_path;
+        let verify_schema = self.verify_schema;
 
         self.connection
             .write(move |connection| {
@@ -89,6 +115,12 @@ impl<M: Migrator> ThreadSafeConnectionBuilder<M> {
                     })?()?;
                 }
 
+                if let Some(backup_path) = backup_path.as_ref() {
+                    if connection.persistent() && M::has_pending_migrations(connection)? {
+                        connection.backup_main_to(backup_path).log_err();
+                    }
+                }
+
                 // Retry failed migrations in case they were run in parallel from different
                 // processes. This gives a best attempt at migrating before bailing
                 let mut migration_result =
@@ -103,10 +135,33 @@ impl<M: Migrator> ThreadSafeConnectionBuilder<M> {
                     }
                 }
 
-                migration_result
+                migration_result?;
+
+                if verify_schema {
+                    match M::verify_schema(connection) {
+                        Ok(differences) if !differences.is_empty() => log::warn!(
+                            "Database schema doesn't match its migrations:\n{}",
+                            differences
+                                .iter()
+                                .map(|difference| difference.to_string())
+                                .collect::<Vec<_>>()
+                                .join("\n")
+                        ),
+                        Ok(_) => {}
+                        Err(error) => log::warn!("Failed to verify database schema: {error:?}"),
+                    }
+                }
+
+                anyhow::Ok(())
             })
             .await?;
 
+        OPEN_CONNECTIONS
+            .lock()
+            .entry(self.connection.uri.clone())
+            .or_default()
+            .push(Arc::downgrade(&self.connection.connections));
+
         Ok(self.connection)
     }
 }
@@ -129,6 +184,8 @@ impl<M: Migrator> ThreadSafeConnection<M> {
         ThreadSafeConnectionBuilder::<M> {
             db_initialize_query: None,
             write_queue_constructor: None,
+            backup_path: None,
+            verify_schema: false,
             connection: Self {
                 uri: Arc::from(uri),
                 persistent,
@@ -220,6 +277,21 @@ impl ThreadSafeConnection<()> {
         connection.initialize_queues(write_queue_constructor);
         connection
     }
+
+    /// Whether a connection to the given database that was built in this process is still open.
+    pub fn is_open(uri: &str) -> bool {
+        let mut open_connections = OPEN_CONNECTIONS.lock();
+        let Some(connections) = open_connections.get_mut(uri) else {
+            return false;
+        };
+        connections.retain(|connection| connection.strong_count() > 0);
+        if connections.is_empty() {
+            open_connections.remove(uri);
+            false
+        } else {
+            true
+        }
+    }
 }
 
 impl<M: Migrator> Clone for ThreadSafeConnection<M> {
@@ -0,0 +1,135 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use extension::{Extension, ExtensionHostProxy, ExtensionTaskProviderProxy, WorktreeDelegate};
+use gpui::{App, Entity, SharedString};
+use project::{TaskProvider, TaskProviderRegistry, TaskProviderWorktree};
+use task::{TaskTemplate, TaskTemplates, TaskVariables, VariableName};
+
+pub fn init(cx: &mut App) {
+    let proxy = ExtensionHostProxy::default_global(cx);
+    proxy.register_task_provider_proxy(TaskProviderRegistryProxy {
+        task_provider_registry: TaskProviderRegistry::default_global(cx),
+    });
+}
+
+struct TaskProviderRegistryProxy {
+    task_provider_registry: Entity<TaskProviderRegistry>,
+}
+
+impl ExtensionTaskProviderProxy for TaskProviderRegistryProxy {
+    fn register_task_provider(
+        &self,
+        extension: Arc<dyn Extension>,
+        task_provider_id: Arc<str>,
+        cx: &mut App,
+    ) {
+        self.task_provider_registry.update(cx, |registry, cx| {
+            registry.register_provider(
+                task_provider_id.clone(),
+                Arc::new(ExtensionTaskProvider {
+                    extension,
+                    task_provider_id,
+                }),
+                cx,
+            )
+        });
+    }
+
+    fn remove_task_provider(&self, task_provider_id: &Arc<str>, cx: &mut App) {
+        self.task_provider_registry.update(cx, |registry, cx| {
+            registry.unregister_provider(task_provider_id, cx)
+        });
+    }
+}
+
+/// An adapter that allows a [`TaskProviderWorktree`] to be used as a [`WorktreeDelegate`].
+struct WorktreeDelegateAdapter(TaskProviderWorktree);
+
+#[async_trait]
+impl WorktreeDelegate for WorktreeDelegateAdapter {
+    fn id(&self) -> u64 {
+        self.0.id.to_proto()
+    }
+
+    fn root_path(&self) -> String {
+        self.0.abs_path.to_string_lossy().to_string()
+    }
+
+    async fn read_text_file(&self, path: PathBuf) -> Result<String> {
+        self.0.read_text_file(&path).await
+    }
+
+    async fn which(&self, binary_name: String) -> Option<String> {
+        self.0
+            .which(&binary_name)
+            .map(|path| path.to_string_lossy().to_string())
+    }
+
+    async fn shell_env(&self) -> Vec<(String, String)> {
+        self.0.shell_env.clone().into_iter().collect()
+    }
+}
+
+struct ExtensionTaskProvider {
+    extension: Arc<dyn Extension>,
+    task_provider_id: Arc<str>,
+}
+
+#[async_trait]
+impl TaskProvider for ExtensionTaskProvider {
+    fn name(&self) -> SharedString {
+        SharedString::from(self.task_provider_id.clone())
+    }
+
+    async fn task_templates(&self, worktree: TaskProviderWorktree) -> Result<TaskTemplates> {
+        let templates = self
+            .extension
+            .task_templates(
+                self.task_provider_id.clone(),
+                Arc::new(WorktreeDelegateAdapter(worktree)),
+            )
+            .await?;
+
+        Ok(TaskTemplates(
+            templates
+                .into_iter()
+                .map(|template| TaskTemplate {
+                    label: template.label,
+                    command: template.command,
+                    args: template.args,
+                    env: template.env.into_iter().collect(),
+                    cwd: template.cwd,
+                    tags: template.tags,
+                    ..TaskTemplate::default()
+                })
+                .collect(),
+        ))
+    }
+
+    async fn task_variables(
+        &self,
+        worktree: TaskProviderWorktree,
+        variables: TaskVariables,
+    ) -> Result<TaskVariables> {
+        let variables = variables
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect();
+        let variables = self
+            .extension
+            .task_variables(
+                self.task_provider_id.clone(),
+                Arc::new(WorktreeDelegateAdapter(worktree)),
+                variables,
+            )
+            .await?;
+
+        Ok(variables
+            .into_iter()
+            .map(|(name, value)| (VariableName::Custom(name.into()), value))
+            .collect())
+    }
+}
@@ -10,6 +10,7 @@ use task::{RevealTarget, TaskContext, TaskId, TaskVariables, VariableName};
 use workspace::tasks::schedule_task;
 use workspace::{tasks::schedule_resolved_task, Workspace};
 
+mod extension_task_provider;
 mod modal;
 mod settings;
 
@@ -17,6 +18,7 @@ pub use modal::{Rerun, Spawn};
 
 pub fn init(cx: &mut App) {
     settings::TaskSettings::register(cx);
+    extension_task_provider::init(cx);
     cx.observe_new(
         |workspace: &mut Workspace, _window: Option<&mut Window>, _: &mut Context<Workspace>| {
             workspace
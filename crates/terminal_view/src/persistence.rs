@@ -402,6 +402,28 @@ define_connection! {
 
             DROP TABLE terminals;
 
+            ALTER TABLE terminals2 RENAME TO terminals;
+        )],
+        &[sql!(
+            DROP TABLE terminals;
+        ),
+        // Restore the unique constraint on the item_id column, keeping the
+        // first of the terminals that share an item_id.
+        sql!(
+            CREATE TABLE terminals2 (
+                workspace_id INTEGER,
+                item_id INTEGER UNIQUE,
+                working_directory BLOB,
+                PRIMARY KEY(workspace_id, item_id),
+                FOREIGN KEY(workspace_id) REFERENCES workspaces(workspace_id)
+                ON DELETE CASCADE
+            ) STRICT;
+
+            INSERT OR IGNORE INTO terminals2 (workspace_id, item_id, working_directory)
+            SELECT workspace_id, item_id, working_directory FROM terminals;
+
+            DROP TABLE terminals;
+
             ALTER TABLE terminals2 RENAME TO terminals;
         )];
 }
@@ -0,0 +1,369 @@
+use anyhow::{anyhow, Result};
+use std::{borrow::Cow, fmt, str};
+
+/// The character encoding of a file on disk, along with whether the file
+/// starts with a byte order mark.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Encoding {
+    encoding: &'static encoding_rs::Encoding,
+    has_bom: bool,
+}
+
+impl Default for Encoding {
+    fn default() -> Self {
+        Self::new(encoding_rs::UTF_8)
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_utf8() && self.has_bom {
+            write!(f, "UTF-8 with BOM")
+        } else if self.is_utf16() && !self.has_bom {
+            write!(f, "{} without BOM", self.name())
+        } else {
+            write!(f, "{}", self.name())
+        }
+    }
+}
+
+impl Encoding {
+    /// Returns the given encoding, with a byte order mark only if it's a
+    /// UTF-16 encoding, where the BOM is needed to tell the byte order apart.
+    pub fn new(encoding: &'static encoding_rs::Encoding) -> Self {
+        Self {
+            encoding,
+            has_bom: encoding == encoding_rs::UTF_16LE || encoding == encoding_rs::UTF_16BE,
+        }
+    }
+
+    /// Looks up an encoding by one of its WHATWG labels, such as `latin1` or `shift-jis`.
+    pub fn for_label(label: &str) -> Option<Self> {
+        encoding_rs::Encoding::for_label(label.trim().as_bytes()).map(Self::new)
+    }
+
+    pub fn with_bom(mut self, has_bom: bool) -> Self {
+        self.has_bom = has_bom && !Self::bom_for(self.encoding).is_empty();
+        self
+    }
+
+    /// The encodings offered when reopening or saving a file with a specific encoding.
+    pub fn all() -> Vec<Self> {
+        [
+            encoding_rs::UTF_8,
+            encoding_rs::UTF_16LE,
+            encoding_rs::UTF_16BE,
+            encoding_rs::WINDOWS_1252,
+            encoding_rs::ISO_8859_2,
+            encoding_rs::ISO_8859_15,
+            encoding_rs::WINDOWS_1250,
+            encoding_rs::WINDOWS_1251,
+            encoding_rs::KOI8_R,
+            encoding_rs::SHIFT_JIS,
+            encoding_rs::EUC_JP,
+            encoding_rs::GBK,
+            encoding_rs::GB18030,
+            encoding_rs::BIG5,
+            encoding_rs::EUC_KR,
+        ]
+        .into_iter()
+        .flat_map(|encoding| {
+            let encoding = Self::new(encoding);
+            if encoding.is_utf8() {
+                vec![encoding, encoding.with_bom(true)]
+            } else {
+                vec![encoding]
+            }
+        })
+        .collect()
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.encoding.name()
+    }
+
+    pub fn has_bom(&self) -> bool {
+        self.has_bom
+    }
+
+    pub fn is_utf8(&self) -> bool {
+        self.encoding == encoding_rs::UTF_8
+    }
+
+    fn is_utf16(&self) -> bool {
+        self.encoding == encoding_rs::UTF_16LE || self.encoding == encoding_rs::UTF_16BE
+    }
+
+    fn bom_for(encoding: &'static encoding_rs::Encoding) -> &'static [u8] {
+        if encoding == encoding_rs::UTF_8 {
+            b"\xEF\xBB\xBF"
+        } else if encoding == encoding_rs::UTF_16LE {
+            b"\xFF\xFE"
+        } else if encoding == encoding_rs::UTF_16BE {
+            b"\xFE\xFF"
+        } else {
+            b""
+        }
+    }
+
+    /// The byte order mark written at the start of files in this encoding.
+    pub fn bom(&self) -> &'static [u8] {
+        if self.has_bom {
+            Self::bom_for(self.encoding)
+        } else {
+            b""
+        }
+    }
+
+    /// Guesses the encoding of the given file contents, returning `None` if they look
+    /// binary. Since any bytes can be decoded as windows-1252, that's only the case when
+    /// they contain a NUL byte and aren't UTF-16 or UTF-8.
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        if let Some((encoding, _)) = encoding_rs::Encoding::for_bom(bytes) {
+            return Some(Self {
+                encoding,
+                has_bom: true,
+            });
+        }
+        // ASCII text in UTF-16 is also valid UTF-8, so this has to be checked first.
+        if let Some(encoding) = detect_utf16(bytes) {
+            return Some(Self {
+                encoding,
+                has_bom: false,
+            });
+        }
+        if str::from_utf8(bytes).is_ok() {
+            return Some(Self::default());
+        }
+        if bytes.contains(&0) {
+            return None;
+        }
+
+        let candidates: [(&'static encoding_rs::Encoding, fn(&str) -> bool); 5] = [
+            (encoding_rs::SHIFT_JIS, looks_japanese),
+            (encoding_rs::EUC_JP, looks_japanese),
+            (encoding_rs::EUC_KR, |text| looks_like(text, is_korean)),
+            (encoding_rs::GBK, |text| looks_like(text, is_chinese)),
+            (encoding_rs::BIG5, |text| looks_like(text, is_chinese)),
+        ];
+        for (encoding, looks_right) in candidates {
+            if encoding
+                .decode_without_bom_handling_and_without_replacement(bytes)
+                .is_some_and(|text| looks_right(&text))
+            {
+                return Some(Self::new(encoding));
+            }
+        }
+
+        // Every byte is valid in windows-1252, which is also how ISO-8859-1 (Latin-1)
+        // files are decoded.
+        Some(Self::new(encoding_rs::WINDOWS_1252))
+    }
+
+    /// Decodes the given file contents, stripping the byte order mark if there is one.
+    /// Bytes that aren't valid in this encoding are replaced with U+FFFD.
+    pub fn decode(self, bytes: Vec<u8>) -> (String, Self) {
+        let bom = Self::bom_for(self.encoding);
+        let has_bom = !bom.is_empty() && bytes.starts_with(bom);
+        let encoding = Self { has_bom, ..self };
+
+        if self.is_utf8() && !has_bom {
+            let text = match String::from_utf8(bytes) {
+                Ok(text) => text,
+                Err(error) => String::from_utf8_lossy(error.as_bytes()).into_owned(),
+            };
+            return (text, encoding);
+        }
+        let content = if has_bom {
+            &bytes[bom.len()..]
+        } else {
+            &bytes[..]
+        };
+        let (text, _) = self.encoding.decode_without_bom_handling(content);
+        (text.into_owned(), encoding)
+    }
+
+    /// Encodes the given text, failing if it contains characters that can't be
+    /// represented in this encoding.
+    pub fn encode<'a>(&self, text: &'a str) -> Result<Cow<'a, [u8]>> {
+        let bytes: Cow<[u8]> = if self.is_utf16() {
+            let little_endian = self.encoding == encoding_rs::UTF_16LE;
+            Cow::Owned(
+                text.encode_utf16()
+                    .flat_map(|unit| {
+                        if little_endian {
+                            unit.to_le_bytes()
+                        } else {
+                            unit.to_be_bytes()
+                        }
+                    })
+                    .collect(),
+            )
+        } else {
+            let (bytes, _, had_errors) = self.encoding.encode(text);
+            if had_errors {
+                let mut buf = [0; 4];
+                let unmappable = text
+                    .chars()
+                    .find(|c| self.encoding.encode(c.encode_utf8(&mut buf)).2);
+                return Err(match unmappable {
+                    Some(c) => anyhow!("{c:?} can't be represented in {}", self.name()),
+                    None => anyhow!("the text can't be represented in {}", self.name()),
+                });
+            }
+            bytes
+        };
+
+        let bom = self.bom();
+        if bom.is_empty() {
+            Ok(bytes)
+        } else {
+            let mut with_bom = Vec::with_capacity(bom.len() + bytes.len());
+            with_bom.extend_from_slice(bom);
+            with_bom.extend_from_slice(&bytes);
+            Ok(Cow::Owned(with_bom))
+        }
+    }
+}
+
+/// Detects UTF-16 text without a byte order mark from the zero high bytes of
+/// its ASCII characters.
+fn detect_utf16(bytes: &[u8]) -> Option<&'static encoding_rs::Encoding> {
+    if bytes.len() < 2 || bytes.len() % 2 != 0 {
+        return None;
+    }
+
+    let units = bytes.len() / 2;
+    let zeros_at = |parity: usize| {
+        bytes
+            .iter()
+            .skip(parity)
+            .step_by(2)
+            .filter(|byte| **byte == 0)
+            .count()
+    };
+    let (even_zeros, odd_zeros) = (zeros_at(0), zeros_at(1));
+    let encoding = if odd_zeros * 2 >= units && even_zeros * 10 < units {
+        encoding_rs::UTF_16LE
+    } else if even_zeros * 2 >= units && odd_zeros * 10 < units {
+        encoding_rs::UTF_16BE
+    } else {
+        return None;
+    };
+
+    let text = encoding.decode_without_bom_handling_and_without_replacement(bytes)?;
+    text.chars().all(|c| c != '\0').then_some(encoding)
+}
+
+/// Whether nearly all of the non-ASCII characters in the text are expected ones,
+/// with at least two of them in a row. Text in single-byte encodings decoded as a
+/// multi-byte encoding tends to produce isolated characters from unrelated scripts.
+fn looks_like(text: &str, is_expected_char: fn(char) -> bool) -> bool {
+    let mut non_ascii = 0;
+    let mut expected = 0;
+    let mut run = 0;
+    let mut longest_run = 0;
+    for c in text.chars() {
+        if c.is_ascii() {
+            run = 0;
+            continue;
+        }
+        non_ascii += 1;
+        if is_expected_char(c) {
+            expected += 1;
+            run += 1;
+            longest_run = longest_run.max(run);
+        } else {
+            run = 0;
+        }
+    }
+    longest_run >= 2 && expected * 10 >= non_ascii * 9
+}
+
+/// Japanese text is told apart from Chinese text by its kana.
+fn looks_japanese(text: &str) -> bool {
+    looks_like(text, is_japanese) && text.chars().any(is_kana)
+}
+
+fn is_cjk_common(c: char) -> bool {
+    matches!(c,
+        '\u{3000}'..='\u{303F}' // CJK symbols and punctuation
+        | '\u{4E00}'..='\u{9FFF}' // CJK unified ideographs
+        | '\u{FF01}'..='\u{FF5E}' // Fullwidth ASCII variants
+    )
+}
+
+fn is_kana(c: char) -> bool {
+    matches!(c, '\u{3040}'..='\u{30FF}')
+}
+
+fn is_japanese(c: char) -> bool {
+    is_kana(c) || is_cjk_common(c)
+}
+
+fn is_korean(c: char) -> bool {
+    matches!(c, '\u{AC00}'..='\u{D7AF}' | '\u{3000}'..='\u{303F}' | '\u{FF01}'..='\u{FF5E}')
+}
+
+fn is_chinese(c: char) -> bool {
+    is_cjk_common(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        let detect = |bytes: &[u8]| Encoding::detect(bytes).map(|encoding| encoding.to_string());
+
+        assert_eq!(detect(b"fn main() {}\n").as_deref(), Some("UTF-8"));
+        assert_eq!(
+            detect(b"\xEF\xBB\xBFabc").as_deref(),
+            Some("UTF-8 with BOM")
+        );
+        assert_eq!(detect(b"\xFF\xFEa\0b\0").as_deref(), Some("UTF-16LE"));
+        assert_eq!(
+            detect(b"\0a\0b\0c\0\n").as_deref(),
+            Some("UTF-16BE without BOM")
+        );
+        assert_eq!(detect(b"\x7FELF\x02\x01\x01\0\xFF\0\0\0"), None);
+
+        let (shift_jis, _, _) = encoding_rs::SHIFT_JIS.encode("// こんにちは、世界\n");
+        assert_eq!(detect(&shift_jis).as_deref(), Some("Shift_JIS"));
+        let (euc_jp, _, _) = encoding_rs::EUC_JP.encode("// こんにちは、世界\n");
+        assert_eq!(detect(&euc_jp).as_deref(), Some("EUC-JP"));
+        let (gbk, _, _) = encoding_rs::GBK.encode("// 你好，世界\n");
+        assert_eq!(detect(&gbk).as_deref(), Some("GBK"));
+        let (euc_kr, _, _) = encoding_rs::EUC_KR.encode("// 안녕하세요 세계\n");
+        assert_eq!(detect(&euc_kr).as_deref(), Some("EUC-KR"));
+        assert_eq!(
+            detect(b"caf\xE9 \xE9lan r\xE9sum\xE9\n").as_deref(),
+            Some("windows-1252")
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let text = "let s = \"größe\";\n";
+        for encoding in [
+            Encoding::default(),
+            Encoding::default().with_bom(true),
+            Encoding::new(encoding_rs::UTF_16LE),
+            Encoding::new(encoding_rs::UTF_16BE).with_bom(false),
+            Encoding::new(encoding_rs::WINDOWS_1252),
+        ] {
+            let bytes = encoding.encode(text).unwrap().into_owned();
+            assert_eq!(Encoding::detect(&bytes), Some(encoding));
+            assert_eq!(encoding.decode(bytes), (text.to_string(), encoding));
+        }
+
+        assert!(Encoding::new(encoding_rs::WINDOWS_1252)
+            .encode("世界")
+            .is_err());
+        assert_eq!(
+            Encoding::for_label("latin1"),
+            Some(Encoding::new(encoding_rs::WINDOWS_1252))
+        );
+    }
+}
@@ -659,6 +659,97 @@ fn test_finalize_last_transaction() {
     assert_eq!(buffer.text(), "ab2cde6");
 }
 
+#[test]
+fn test_restore_undo_history() {
+    let mut buffer = Buffer::new(0, BufferId::new(1).unwrap(), "123456".into());
+    buffer.edit([(2..4, "cd")]);
+    buffer.edit([(0..1, "a")]);
+    buffer.edit([(6..6, "7")]);
+    buffer.undo();
+    assert_eq!(buffer.text(), "a2cd56");
+
+    let mut restored = Buffer::new(0, BufferId::new(2).unwrap(), buffer.base_text().to_string());
+    restored.apply_ops(buffer.operations().values().cloned());
+    assert_eq!(restored.text(), buffer.text());
+    restored
+        .restore_undo_history(
+            buffer.undo_stack().cloned().collect(),
+            buffer.redo_stack().cloned().collect(),
+        )
+        .unwrap();
+
+    restored.redo();
+    assert_eq!(restored.text(), "a2cd567");
+    restored.undo();
+    restored.undo();
+    assert_eq!(restored.text(), "12cd56");
+    restored.undo();
+    assert_eq!(restored.text(), "123456");
+    assert!(restored.undo().is_none());
+
+    // New edits must not reuse the timestamps of the restored transactions.
+    restored.edit([(0..0, "0")]);
+    assert_eq!(restored.text(), "0123456");
+    restored.undo();
+    assert_eq!(restored.text(), "123456");
+
+    let mut unrelated = Buffer::new(0, BufferId::new(3).unwrap(), "123456".into());
+    assert!(unrelated
+        .restore_undo_history(buffer.undo_stack().cloned().collect(), Vec::new())
+        .is_err());
+}
+
+#[test]
+fn test_compact_history() {
+    let mut buffer = Buffer::new(0, BufferId::new(1).unwrap(), "123456".into());
+    buffer.edit([(2..4, "cd")]);
+    buffer.edit([(0..1, "a")]);
+    buffer.edit([(6..6, "7")]);
+    buffer.undo();
+    assert_eq!(buffer.text(), "a2cd56");
+
+    let rebuild = |buffer: &Buffer| {
+        Buffer::from_history(
+            BufferId::new(2).unwrap(),
+            LineEnding::Unix,
+            buffer.history(),
+        )
+        .unwrap()
+    };
+    assert_eq!(rebuild(&buffer).text(), "a2cd56");
+
+    // Keeping every transaction keeps the base text.
+    let mut compacted = rebuild(&buffer).compact_history(usize::MAX);
+    assert_eq!(compacted.text(), "a2cd56");
+    assert_eq!(compacted.base_text().to_string(), "123456");
+    compacted.redo();
+    assert_eq!(compacted.text(), "a2cd567");
+    compacted.undo();
+    compacted.undo();
+    compacted.undo();
+    assert_eq!(compacted.text(), "123456");
+    assert!(compacted.undo().is_none());
+
+    // Dropping the oldest transactions starts the history from the text before the oldest
+    // one that's kept, and keeps the redo stack.
+    let mut compacted = rebuild(&buffer).compact_history(1);
+    assert_eq!(compacted.text(), "a2cd56");
+    assert_eq!(compacted.base_text().to_string(), "12cd56");
+    compacted.redo();
+    assert_eq!(compacted.text(), "a2cd567");
+    compacted.undo();
+    compacted.undo();
+    assert_eq!(compacted.text(), "12cd56");
+    assert!(compacted.undo().is_none());
+
+    let mut compacted = rebuild(&buffer).compact_history(0);
+    assert_eq!(compacted.base_text().to_string(), "a2cd56");
+    assert!(compacted.undo().is_none());
+    compacted.redo();
+    assert_eq!(compacted.text(), "a2cd567");
+    assert!(compacted.redo().is_none());
+}
+
 #[test]
 fn test_edited_ranges_for_transaction() {
     let now = Instant::now();
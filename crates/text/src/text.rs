@@ -1,4 +1,5 @@
 mod anchor;
+mod encoding;
 pub mod locator;
 #[cfg(any(test, feature = "test-support"))]
 pub mod network;
@@ -15,6 +16,7 @@ use anyhow::{anyhow, Context as _, Result};
 pub use clock::ReplicaId;
 use clock::LOCAL_BRANCH_REPLICA_ID;
 use collections::{HashMap, HashSet};
+pub use encoding::*;
 use locator::Locator;
 use operation_queue::OperationQueue;
 pub use patch::Patch;
@@ -121,6 +123,17 @@ pub struct HistoryEntry {
     suppress_grouping: bool,
 }
 
+/// A buffer's base text, operations and undo and redo stacks, from which it can be rebuilt
+/// with [`Buffer::from_history`]. It's cheap to clone, so it can be taken from a buffer and
+/// then serialized on a background thread.
+#[derive(Clone)]
+pub struct BufferHistory {
+    pub base_text: Rope,
+    pub operations: TreeMap<clock::Lamport, Operation>,
+    pub undo_stack: Vec<Transaction>,
+    pub redo_stack: Vec<Transaction>,
+}
+
 #[derive(Clone, Debug)]
 pub struct Transaction {
     pub id: TransactionId,
@@ -1317,6 +1330,131 @@ impl Buffer {
         self.history.redo_stack.last()
     }
 
+    pub fn undo_stack(&self) -> impl Iterator<Item = &Transaction> {
+        self.history
+            .undo_stack
+            .iter()
+            .map(|entry| &entry.transaction)
+    }
+
+    pub fn redo_stack(&self) -> impl Iterator<Item = &Transaction> {
+        self.history
+            .redo_stack
+            .iter()
+            .map(|entry| &entry.transaction)
+    }
+
+    /// Replaces the undo and redo stacks with the given transactions, e.g. to
+    /// restore the history of a buffer whose operations were replayed from disk.
+    ///
+    /// Every edit referenced by the transactions must already have been applied
+    /// to this buffer.
+    pub fn restore_undo_history(
+        &mut self,
+        undo_stack: Vec<Transaction>,
+        redo_stack: Vec<Transaction>,
+    ) -> Result<()> {
+        assert_eq!(self.history.transaction_depth, 0);
+        for transaction in undo_stack.iter().chain(&redo_stack) {
+            for edit_id in &transaction.edit_ids {
+                if !matches!(
+                    self.history.operations.get(edit_id),
+                    Some(Operation::Edit(_))
+                ) {
+                    return Err(anyhow!(
+                        "transaction {:?} references unknown edit {:?}",
+                        transaction.id,
+                        edit_id
+                    ));
+                }
+            }
+        }
+
+        let now = Instant::now();
+        let to_entry = |transaction: Transaction| HistoryEntry {
+            transaction,
+            first_edit_at: now,
+            last_edit_at: now,
+            suppress_grouping: true,
+        };
+        for transaction in undo_stack.iter().chain(&redo_stack) {
+            self.lamport_clock.observe(transaction.id);
+        }
+        self.history.undo_stack = undo_stack.into_iter().map(to_entry).collect();
+        self.history.redo_stack = redo_stack.into_iter().map(to_entry).collect();
+        Ok(())
+    }
+
+    pub fn history(&self) -> BufferHistory {
+        BufferHistory {
+            base_text: self.history.base_text.clone(),
+            operations: self.history.operations.clone(),
+            undo_stack: self.undo_stack().cloned().collect(),
+            redo_stack: self.redo_stack().cloned().collect(),
+        }
+    }
+
+    /// Rebuilds a buffer by replaying the operations of the given history on top of its base
+    /// text, and restoring its undo and redo stacks.
+    pub fn from_history(
+        remote_id: BufferId,
+        line_ending: LineEnding,
+        history: BufferHistory,
+    ) -> Result<Buffer> {
+        let mut buffer = Buffer::new_normalized(0, remote_id, line_ending, history.base_text);
+        buffer.apply_ops(history.operations.values().cloned());
+        if buffer.has_deferred_ops() {
+            return Err(anyhow!("buffer history has missing operations"));
+        }
+        buffer.restore_undo_history(history.undo_stack, history.redo_stack)?;
+        Ok(buffer)
+    }
+
+    /// Rebuilds this buffer with only the last `max_undo_entries` transactions of its undo
+    /// stack and all of its redo stack, replayed on top of the text from before the oldest of
+    /// them. Any older operations, and the text they deleted, are dropped from its history.
+    pub fn compact_history(mut self, max_undo_entries: usize) -> Buffer {
+        let undo_count = self.history.undo_stack.len().min(max_undo_entries);
+        let redo_count = self.history.redo_stack.len();
+        for _ in 0..undo_count {
+            self.undo();
+        }
+
+        let mut compacted = Buffer::new_normalized(
+            0,
+            self.remote_id,
+            self.line_ending(),
+            self.visible_text.clone(),
+        );
+        let mut compacted_redo_count = 0;
+        for ix in 0..undo_count + redo_count {
+            let version = self.version.clone();
+            if self.redo().is_none() {
+                break;
+            }
+            let edits = self
+                .edits_since::<usize>(&version)
+                .map(|edit| (edit.old, self.text_for_range(edit.new).collect::<String>()))
+                .collect::<Vec<_>>();
+            // Transactions that end up not changing the text have nothing to undo.
+            if edits.is_empty() {
+                continue;
+            }
+
+            compacted.start_transaction();
+            compacted.edit(edits);
+            compacted.end_transaction();
+            compacted.finalize_last_transaction();
+            if ix >= undo_count {
+                compacted_redo_count += 1;
+            }
+        }
+        for _ in 0..compacted_redo_count {
+            compacted.undo();
+        }
+        compacted
+    }
+
     pub fn start_transaction(&mut self) -> Option<TransactionId> {
         self.start_transaction_at(Instant::now())
     }
@@ -383,6 +383,116 @@ define_connection! {
     sql!(
         ALTER TABLE toolchains ADD COLUMN raw_json TEXT DEFAULT "{}";
     ),
+    ],
+    &[
+        sql!(
+            DROP TABLE items;
+            DROP TABLE center_panes;
+            DROP TABLE panes;
+            DROP TABLE pane_groups;
+            DROP TABLE workspaces;
+        ),
+        sql!(
+            ALTER TABLE workspaces DROP COLUMN display;
+            ALTER TABLE workspaces DROP COLUMN window_height;
+            ALTER TABLE workspaces DROP COLUMN window_width;
+            ALTER TABLE workspaces DROP COLUMN window_y;
+            ALTER TABLE workspaces DROP COLUMN window_x;
+            ALTER TABLE workspaces DROP COLUMN window_state;
+        ),
+        // Restore the foreign key constraint from workspaces.dock_pane to panes table.
+        sql!(
+            CREATE TABLE workspaces_2(
+                workspace_id INTEGER PRIMARY KEY,
+                workspace_location BLOB UNIQUE,
+                dock_visible INTEGER,
+                dock_anchor TEXT,
+                dock_pane INTEGER,
+                left_sidebar_open INTEGER,
+                timestamp TEXT DEFAULT CURRENT_TIMESTAMP NOT NULL,
+                window_state TEXT,
+                window_x REAL,
+                window_y REAL,
+                window_width REAL,
+                window_height REAL,
+                display BLOB,
+                FOREIGN KEY(dock_pane) REFERENCES panes(pane_id)
+            ) STRICT;
+            // The dock pane is no longer stored, and may not exist anymore.
+            INSERT INTO workspaces_2
+            SELECT
+                workspace_id, workspace_location, dock_visible, dock_anchor, NULL,
+                left_sidebar_open, timestamp, window_state, window_x, window_y,
+                window_width, window_height, display
+            FROM workspaces;
+            DROP TABLE workspaces;
+            ALTER TABLE workspaces_2 RENAME TO workspaces;
+        ),
+        sql!(
+            ALTER TABLE workspaces DROP COLUMN bottom_dock_active_panel;
+            ALTER TABLE workspaces DROP COLUMN bottom_dock_visible;
+            ALTER TABLE workspaces DROP COLUMN right_dock_active_panel;
+            ALTER TABLE workspaces DROP COLUMN right_dock_visible;
+            ALTER TABLE workspaces DROP COLUMN left_dock_active_panel;
+            ALTER TABLE workspaces DROP COLUMN left_dock_visible;
+        ),
+        sql!(
+            ALTER TABLE workspaces DROP COLUMN bottom_dock_zoom;
+            ALTER TABLE workspaces DROP COLUMN right_dock_zoom;
+            ALTER TABLE workspaces DROP COLUMN left_dock_zoom;
+        ),
+        sql!(
+            ALTER TABLE pane_groups DROP COLUMN flexes;
+        ),
+        sql!(
+            ALTER TABLE workspaces DROP COLUMN fullscreen;
+        ),
+        sql!(
+            ALTER TABLE items DROP COLUMN preview;
+        ),
+        sql!(
+            ALTER TABLE workspaces DROP COLUMN centered_layout;
+        ),
+        sql!(
+            ALTER TABLE workspaces RENAME COLUMN local_paths TO workspace_location;
+            ALTER TABLE workspaces DROP COLUMN remote_project_id;
+            DROP TABLE remote_projects;
+        ),
+        sql!(
+            ALTER TABLE workspaces DROP COLUMN dev_server_project_id;
+            ALTER TABLE workspaces ADD COLUMN remote_project_id INTEGER;
+            DROP TABLE dev_server_projects;
+            CREATE TABLE remote_projects (
+                remote_project_id INTEGER NOT NULL UNIQUE,
+                path TEXT,
+                dev_server_name TEXT
+            );
+        ),
+        sql!(
+            ALTER TABLE workspaces DROP COLUMN local_paths_order;
+        ),
+        sql!(
+            ALTER TABLE workspaces DROP COLUMN session_id;
+        ),
+        sql!(
+            ALTER TABLE workspaces DROP COLUMN window_id;
+        ),
+        sql!(
+            ALTER TABLE panes DROP COLUMN pinned_count;
+        ),
+        sql!(
+            ALTER TABLE workspaces DROP COLUMN ssh_project_id;
+            DROP TABLE ssh_projects;
+        ),
+        sql!(
+            ALTER TABLE ssh_projects RENAME COLUMN paths TO path;
+        ),
+        sql!(
+            DROP TABLE toolchains;
+        ),
+        sql!(
+            ALTER TABLE toolchains DROP COLUMN raw_json;
+        ),
     ];
 }
 
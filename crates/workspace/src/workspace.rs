@@ -2912,14 +2912,43 @@ impl Workspace {
     ) -> Task<Result<(Option<ProjectEntryId>, WorkspaceItemBuilder)>> {
         let project = self.project().clone();
         let project_item_builders = cx.default_global::<ProjectItemOpeners>().clone();
-        let Some(open_project_item) = project_item_builders
+        let Some((opener_ix, open_project_item)) = project_item_builders
             .iter()
+            .enumerate()
             .rev()
-            .find_map(|open_project_item| open_project_item(&project, &path, window, cx))
+            .find_map(|(ix, open_project_item)| {
+                Some((ix, open_project_item(&project, &path, window, cx)?))
+            })
         else {
             return Task::ready(Err(anyhow!("cannot open file {:?}", path.path)));
         };
-        open_project_item
+
+        window.spawn(cx, |mut cx| async move {
+            let error = match open_project_item.await {
+                Ok(opened) => return Ok(opened),
+                Err(error) => error,
+            };
+
+            // A file that fails to load as a buffer because its contents look binary is given to
+            // the other openers, such as the hex editor. Any other error is reported as it is.
+            let fallback = cx.update(|window, cx| {
+                if !project.read(cx).is_binary_file(&path, cx) {
+                    return None;
+                }
+                project_item_builders
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .filter(|(ix, _)| *ix != opener_ix)
+                    .find_map(|(_, open_project_item)| {
+                        open_project_item(&project, &path, window, cx)
+                    })
+            })?;
+            match fallback {
+                Some(open_project_item) => open_project_item.await,
+                None => Err(error),
+            }
+        })
     }
 
     pub fn find_project_item<T>(
@@ -5231,6 +5260,32 @@ fn notify_if_database_failed(workspace: WindowHandle<Workspace>, cx: &mut AsyncA
                         })
                     },
                 );
+            } else if (*db::DB_QUARANTINED).load(std::sync::atomic::Ordering::Acquire) {
+                struct DatabaseQuarantinedNotification;
+
+                let message = if (*db::DB_RESTORED_FROM_BACKUP)
+                    .load(std::sync::atomic::Ordering::Acquire)
+                {
+                    "The database file was damaged, so it was moved aside and restored from a backup."
+                } else {
+                    "The database file was damaged, so it was moved aside and a new one was created."
+                };
+                workspace.show_notification(
+                    NotificationId::unique::<DatabaseQuarantinedNotification>(),
+                    cx,
+                    |cx| {
+                        cx.new(|_| {
+                            MessageNotification::new(message)
+                                .primary_message("Show Moved Database")
+                                .primary_icon(IconName::Folder)
+                                .primary_on_click(|_window, cx| {
+                                    cx.reveal_path(
+                                        &db::database_dir().join(db::QUARANTINE_DIR_NAME),
+                                    )
+                                })
+                        })
+                    },
+                );
             }
         })
         .log_err();
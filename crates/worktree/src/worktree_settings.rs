@@ -7,14 +7,23 @@ use serde::{Deserialize, Serialize};
 use settings::{Settings, SettingsSources};
 use util::paths::PathMatcher;
 
+const DEFAULT_LARGE_FILE_THRESHOLD: u64 = 50_000_000;
+
 #[derive(Clone, PartialEq, Eq)]
 pub struct WorktreeSettings {
     pub file_scan_inclusions: PathMatcher,
     pub file_scan_exclusions: PathMatcher,
     pub private_files: PathMatcher,
+    pub large_file_threshold: u64,
 }
 
 impl WorktreeSettings {
+    /// Whether a file of the given size is opened in large-file mode, rather than loaded
+    /// into a buffer.
+    pub fn is_large_file(&self, size: u64) -> bool {
+        size > self.large_file_threshold
+    }
+
     pub fn is_path_private(&self, path: &Path) -> bool {
         path.ancestors()
             .any(|ancestor| self.private_files.is_match(ancestor))
@@ -62,6 +71,12 @@ pub struct WorktreeSettingsContent {
     /// Treat the files matching these globs as `.env` files.
     /// Default: [ "**/.env*" ]
     pub private_files: Option<Vec<String>>,
+
+    /// The size in bytes above which files are opened in large-file mode: read-only and
+    /// paged in from disk, without syntax highlighting or language servers.
+    ///
+    /// Default: 50000000
+    pub large_file_threshold: Option<u64>,
 }
 
 impl Settings for WorktreeSettings {
@@ -94,6 +109,9 @@ impl Settings for WorktreeSettings {
                 &parsed_file_scan_inclusions,
                 "file_scan_inclusions",
             )?,
+            large_file_threshold: result
+                .large_file_threshold
+                .unwrap_or(DEFAULT_LARGE_FILE_THRESHOLD),
         })
     }
 }
@@ -563,6 +563,53 @@ async fn test_open_gitignored_files(cx: &mut TestAppContext) {
     );
 }
 
+#[gpui::test]
+async fn test_load_large_files(cx: &mut TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings::<WorktreeSettings>(cx, |project_settings| {
+                project_settings.large_file_threshold = Some(8);
+            });
+        });
+    });
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "small.txt": "small",
+            "large.txt": "one\ntwo\nthree\n",
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+
+    // Whether a file is opened in large-file mode is up to the opener, so the worktree still
+    // loads files over the threshold, e.g. for remote clients.
+    tree.read_with(cx, |tree, cx| {
+        let settings = WorktreeSettings::get_global(cx);
+        assert!(!settings.is_large_file(tree.entry_for_path("small.txt").unwrap().size));
+        assert!(settings.is_large_file(tree.entry_for_path("large.txt").unwrap().size));
+    });
+    let loaded = tree
+        .update(cx, |tree, cx| tree.load_file("large.txt".as_ref(), cx))
+        .await
+        .unwrap();
+    assert_eq!(loaded.text, "one\ntwo\nthree\n");
+    assert_eq!(loaded.file.path.as_ref(), Path::new("large.txt"));
+}
+
 #[gpui::test]
 async fn test_dirs_no_longer_ignored(cx: &mut TestAppContext) {
     init_test(cx);
@@ -876,6 +923,7 @@ async fn test_write_file(cx: &mut TestAppContext) {
             Path::new("tracked-dir/file.txt"),
             "hello".into(),
             Default::default(),
+            Default::default(),
             cx,
         )
     })
@@ -886,6 +934,7 @@ async fn test_write_file(cx: &mut TestAppContext) {
             Path::new("ignored-dir/file.txt"),
             "world".into(),
             Default::default(),
+            Default::default(),
             cx,
         )
     })
@@ -1975,8 +2024,13 @@ fn randomly_mutate_worktree(
                 })
             } else {
                 log::info!("overwriting file {:?} ({})", entry.path, entry.id.0);
-                let task =
-                    worktree.write_file(entry.path.clone(), "".into(), Default::default(), cx);
+                let task = worktree.write_file(
+                    entry.path.clone(),
+                    "".into(),
+                    Default::default(),
+                    Default::default(),
+                    cx,
+                );
                 cx.background_spawn(async move {
                     task.await?;
                     Ok(())
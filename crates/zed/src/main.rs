@@ -494,6 +494,8 @@ fn main() {
 
         app_state.languages.set_theme(cx.theme().clone());
         editor::init(cx);
+        hex_editor::init(cx);
+        large_file_viewer::init(cx);
         image_viewer::init(cx);
         repl::notebook::init(cx);
         diagnostics::init(cx);
@@ -518,6 +520,7 @@ fn main() {
         terminal_view::init(cx);
         journal::init(app_state.clone(), cx);
         language_selector::init(cx);
+        encoding_selector::init(cx);
         toolchain_selector::init(cx);
         theme_selector::init(cx);
         language_tools::init(cx);
@@ -221,6 +221,7 @@ pub fn initialize_workspace(
             window,
             cx,
         );
+        let active_buffer_encoding = cx.new(|_| encoding_selector::ActiveBufferEncoding::default());
         let active_buffer_language =
             cx.new(|_| language_selector::ActiveBufferLanguage::new(workspace));
         let active_toolchain_language =
@@ -233,6 +234,7 @@ pub fn initialize_workspace(
             status_bar.add_left_item(diagnostic_summary, window, cx);
             status_bar.add_left_item(activity_indicator, window, cx);
             status_bar.add_right_item(inline_completion_button, window, cx);
+            status_bar.add_right_item(active_buffer_encoding, window, cx);
             status_bar.add_right_item(active_buffer_language, window, cx);
             status_bar.add_right_item(active_toolchain_language, window, cx);
             status_bar.add_right_item(vim_mode_indicator, window, cx);
@@ -4237,6 +4239,8 @@ mod tests {
                 app_state.client.http_client().clone(),
                 cx,
             );
+            hex_editor::init(cx);
+            large_file_viewer::init(cx);
             image_viewer::init(cx);
             language_model::init(app_state.client.clone(), cx);
             language_models::init(